@@ -111,9 +111,10 @@ impl PriceFeedApi for RemotePriceFeedContract {
     fn price(&self, ctx: QueryCtx) -> Result<PriceResponse, Self::Error> {
         Ok(self
             .price_keeper
-            .price(ctx.deps, &ctx.env)
-            .map(|rate| PriceResponse {
-                native_per_foreign: rate,
+            .price_info(ctx.deps, &ctx.env)
+            .map(|info| PriceResponse {
+                native_per_foreign: info.native_per_foreign,
+                time: Some(info.time),
             })?)
     }
 
@@ -1,31 +1,48 @@
 use cosmwasm_std::{
-    ensure_eq, to_json_binary, Addr, BankMsg, Coin, CosmosMsg, Decimal, Deps, DepsMut, Event,
-    Fraction, IbcMsg, MessageInfo, Reply, Response, StdError, SubMsg, SubMsgResponse, Uint128,
-    Validator, WasmMsg,
+    ensure, ensure_eq, to_json_binary, Addr, BankMsg, Coin, CosmosMsg, Decimal, Deps, DepsMut, Env,
+    Event, Fraction, IbcMsg, MessageInfo, Order, QuerierWrapper, Reply, Response, StdError,
+    StdResult, Storage, SubMsg, SubMsgResponse, Timestamp, Uint128, Validator, WasmMsg,
 };
 use cw2::set_contract_version;
-use cw_storage_plus::Item;
+use cw_storage_plus::{Item, Map};
 use cw_utils::{must_pay, nonpayable, parse_instantiate_response_data};
-use mesh_apis::ibc::ConsumerPacket;
+use mesh_apis::ibc::{CollateralSummaryAck, ConsumerPacket, ConversionTerms, StakeAck, UnstakeAck};
+use std::cmp::min;
+use std::collections::BTreeSet;
 use sylvia::types::{ExecCtx, InstantiateCtx, QueryCtx, ReplyCtx};
 use sylvia::{contract, schemars};
 
 use mesh_apis::converter_api::{self, ConverterApi, RewardInfo, ValidatorSlashInfo};
-use mesh_apis::price_feed_api;
+use mesh_apis::price_feed_api::{self, PriceResponse};
 use mesh_apis::virtual_staking_api;
 
 use crate::error::ContractError;
 use crate::ibc::{
-    make_ibc_packet, packet_timeout_internal_unstake, valset_update_msg, IBC_CHANNEL,
+    make_ibc_packet, packet_timeout_internal_unstake, valset_resync_msgs, valset_update_msg,
+    IBC_CHANNEL, PROVIDER_CHAIN_ID,
+};
+use crate::msg::{
+    ChannelContributions, CollateralBoundResponse, ConfigResponse, KeeperResponse, KeeperTask,
+    MetricsResponse, PriceFeedExecMsg, ProviderContributionsResponse, ProviderEndpointInfo,
+    ProviderEndpointsResponse, ProviderIdentityResponse, SubsidyResponse,
+};
+use crate::state::{
+    CollateralBound, Config, KeeperConfig, Metrics, ProviderContributions, ProviderEndpoint,
+    ProviderIdentity, SubsidyConfig,
 };
-use crate::msg::ConfigResponse;
-use crate::state::Config;
 
 pub const CONTRACT_NAME: &str = env!("CARGO_PKG_NAME");
 pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 const REPLY_ID_INSTANTIATE: u64 = 1;
 
+/// Default number of validators per valset resync packet
+pub const DEFAULT_RESYNC_CHUNK_SIZE: u32 = 50;
+
+/// Default tolerance over the collateral reported by the provider, covering the stakes sent
+/// since its last summary
+pub const DEFAULT_COLLATERAL_TOLERANCE: Decimal = Decimal::percent(10);
+
 #[cfg(not(feature = "fake-custom"))]
 pub mod custom {
     pub type ConverterMsg = cosmwasm_std::Empty;
@@ -39,9 +56,47 @@ pub mod custom {
     pub type Response = cosmwasm_std::Response<ConverterMsg>;
 }
 
+/// Ratio of the collateral backing the converted stake to the stake, if any is converted
+fn collateralization_ratio(collateral: Uint128, converted: Uint128) -> Option<Decimal> {
+    Decimal::checked_from_ratio(collateral, converted).ok()
+}
+
 pub struct ConverterContract<'a> {
     pub config: Item<'a, Config>,
     pub virtual_stake: Item<'a, Addr>,
+    pub metrics: Item<'a, Metrics>,
+    /// Active validators, as reported to the provider.
+    /// Kept up to date with every valset update, and rebuilt from scratch on resync.
+    pub active_validators: Map<'a, &'a str, ()>,
+    /// Keeper rewards configuration
+    pub keeper_config: Item<'a, KeeperConfig>,
+    /// Funds set aside for keeper rewards, in local denom
+    pub keeper_pot: Item<'a, Uint128>,
+    /// Last run of each keeper task
+    pub keeper_last_run: Map<'a, &'a str, Timestamp>,
+    /// Cumulative provider contributions, by channel id
+    pub contributions: Map<'a, &'a str, ProviderContributions>,
+    /// Subsidy paid to the provider on top of delegation rewards
+    pub subsidy_config: Item<'a, SubsidyConfig>,
+    /// Funds set aside for subsidies, in local denom
+    pub subsidy_budget: Item<'a, Uint128>,
+    /// Provider endpoints authorized by the admin, by `(connection_id, port_id)`.
+    /// They take precedence over the provider set at instantiation
+    pub provider_endpoints: Map<'a, (&'a str, &'a str), ProviderEndpoint>,
+    /// Collateral reported in the last provider summary
+    pub collateral_bound: Item<'a, CollateralBound>,
+    /// Tolerance over the reported collateral. `DEFAULT_COLLATERAL_TOLERANCE` if not set
+    pub collateral_tolerance: Item<'a, Decimal>,
+    /// Stake converted for the provider, in remote denom, net of unstakes and burns
+    pub provider_stake: Item<'a, Uint128>,
+    /// Stake converted for every provider delegator, in remote denom, net of unstakes
+    pub delegator_stakes: Map<'a, &'a str, Uint128>,
+}
+
+impl Default for ConverterContract<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 #[cfg_attr(not(feature = "library"), sylvia::entry_points)]
@@ -55,6 +110,19 @@ impl ConverterContract<'_> {
         Self {
             config: Item::new("config"),
             virtual_stake: Item::new("virtual_stake"),
+            metrics: Item::new("metrics"),
+            active_validators: Map::new("active_validators"),
+            keeper_config: Item::new("keeper_config"),
+            keeper_pot: Item::new("keeper_pot"),
+            keeper_last_run: Map::new("keeper_last_run"),
+            contributions: Map::new("contributions"),
+            subsidy_config: Item::new("subsidy_config"),
+            subsidy_budget: Item::new("subsidy_budget"),
+            provider_endpoints: Map::new("provider_endpoints"),
+            collateral_bound: Item::new("collateral_bound"),
+            collateral_tolerance: Item::new("collateral_tolerance"),
+            provider_stake: Item::new("provider_stake"),
+            delegator_stakes: Map::new("delegator_stakes"),
         }
     }
 
@@ -74,8 +142,11 @@ impl ConverterContract<'_> {
         remote_denom: String,
         virtual_staking_code_id: u64,
         tombstoned_unbond_enable: bool,
+        #[serde(default)] jailed_unbond_enable: bool,
         admin: Option<String>,
         max_retrieve: u32,
+        // provider the channel must be opened with, any if not set
+        provider: Option<ProviderIdentity>,
     ) -> Result<custom::Response, ContractError> {
         nonpayable(&ctx.info)?;
         // validate args
@@ -85,23 +156,28 @@ impl ConverterContract<'_> {
         if remote_denom.is_empty() {
             return Err(ContractError::InvalidDenom(remote_denom));
         }
+        if let Some(provider) = &provider {
+            provider.validate()?;
+        }
         let config = Config {
             price_feed: ctx.deps.api.addr_validate(&price_feed)?,
             price_adjustment: Decimal::one() - discount,
             local_denom: ctx.deps.querier.query_bonded_denom()?,
             remote_denom,
+            admin: admin
+                .as_ref()
+                .map(|admin| ctx.deps.api.addr_validate(admin))
+                .transpose()?,
+            provider,
         };
         self.config.save(ctx.deps.storage, &config)?;
 
         set_contract_version(ctx.deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
-        if let Some(admin) = &admin {
-            ctx.deps.api.addr_validate(admin)?;
-        }
-
         let msg = to_json_binary(&mesh_virtual_staking::contract::sv::InstantiateMsg {
             max_retrieve,
             tombstoned_unbond_enable,
+            jailed_unbond_enable,
         })?;
         // Instantiate virtual staking contract
         let init_msg = WasmMsg::Instantiate {
@@ -152,8 +228,12 @@ impl ConverterContract<'_> {
     ) -> Result<custom::Response, ContractError> {
         #[cfg(any(test, feature = "mt"))]
         {
-            // This can only ever be called in tests
-            self.stake(ctx.deps, delegator, validator, stake)
+            // This can only ever be called in tests. The ack is returned as data
+            let (resp, conversion) = self.stake(ctx.deps, &ctx.env, delegator, validator, stake)?;
+            let ack = StakeAck {
+                conversion: Some(conversion),
+            };
+            Ok(resp.set_data(to_json_binary(&ack)?))
         }
         #[cfg(not(any(test, feature = "mt")))]
         {
@@ -174,8 +254,13 @@ impl ConverterContract<'_> {
     ) -> Result<custom::Response, ContractError> {
         #[cfg(any(test, feature = "mt"))]
         {
-            // This can only ever be called in tests
-            self.unstake(ctx.deps, delegator, validator, unstake)
+            // This can only ever be called in tests. The ack is returned as data
+            let (resp, conversion) =
+                self.unstake(ctx.deps, &ctx.env, delegator, validator, unstake)?;
+            let ack = UnstakeAck {
+                conversion: Some(conversion),
+            };
+            Ok(resp.set_data(to_json_binary(&ack)?))
         }
         #[cfg(not(any(test, feature = "mt")))]
         {
@@ -205,6 +290,27 @@ impl ConverterContract<'_> {
         }
     }
 
+    /// This is only used for tests.
+    /// Ideally we want conditional compilation of these whole methods and the enum variants
+    #[sv::msg(exec)]
+    fn test_collateral_summary(
+        &self,
+        ctx: ExecCtx<custom::ConverterQuery>,
+        total_collateral: Coin,
+    ) -> Result<custom::Response, ContractError> {
+        #[cfg(any(test, feature = "mt"))]
+        {
+            // This can only ever be called in tests. The ack is returned as data
+            let resp = self.collateral_summary(ctx.deps, &ctx.env, total_collateral)?;
+            Ok(resp.set_data(to_json_binary(&CollateralSummaryAck {})?))
+        }
+        #[cfg(not(any(test, feature = "mt")))]
+        {
+            let _ = (ctx, total_collateral);
+            Err(ContractError::Unauthorized)
+        }
+    }
+
     #[sv::msg(query)]
     fn config(
         &self,
@@ -216,19 +322,727 @@ impl ConverterContract<'_> {
             price_feed: config.price_feed.into_string(),
             adjustment: config.price_adjustment,
             virtual_staking,
+            admin: config.admin.map(Addr::into_string),
+        })
+    }
+
+    /// Identity of the provider: the expected one, if configured, and the one the channel was
+    /// opened with, once connected.
+    #[sv::msg(query)]
+    fn provider_identity(
+        &self,
+        ctx: QueryCtx<custom::ConverterQuery>,
+    ) -> Result<ProviderIdentityResponse, ContractError> {
+        let config = self.config.load(ctx.deps.storage)?;
+        let channel = IBC_CHANNEL.may_load(ctx.deps.storage)?;
+        let chain_id = PROVIDER_CHAIN_ID.may_load(ctx.deps.storage)?;
+        Ok(ProviderIdentityResponse {
+            expected: config.provider,
+            chain_id,
+            connection_id: channel.as_ref().map(|c| c.connection_id.clone()),
+            port_id: channel.map(|c| c.counterparty_endpoint.port_id),
+        })
+    }
+
+    /// Authorizes a provider endpoint besides the one set at instantiation, e.g. the new provider
+    /// contract during a migration. Disabled endpoints can't open a channel, and packets from
+    /// them are rejected, so the old endpoint can be disabled once the new one is connected.
+    /// Can only be called by the admin.
+    #[sv::msg(exec)]
+    fn set_provider_endpoint(
+        &self,
+        ctx: ExecCtx<custom::ConverterQuery>,
+        provider: ProviderIdentity,
+        enabled: bool,
+    ) -> Result<custom::Response, ContractError> {
+        nonpayable(&ctx.info)?;
+        let config = self.config.load(ctx.deps.storage)?;
+        ensure!(
+            config.admin.as_ref() == Some(&ctx.info.sender),
+            ContractError::Unauthorized
+        );
+        provider.validate()?;
+
+        let endpoint = ProviderEndpoint {
+            chain_id: provider.chain_id.clone(),
+            enabled,
+        };
+        self.provider_endpoints.save(
+            ctx.deps.storage,
+            (&provider.connection_id, &provider.port_id),
+            &endpoint,
+        )?;
+
+        let resp = Response::new()
+            .add_attribute("action", "set_provider_endpoint")
+            .add_attribute("chain_id", provider.chain_id)
+            .add_attribute("connection_id", provider.connection_id)
+            .add_attribute("port_id", provider.port_id)
+            .add_attribute("enabled", enabled.to_string());
+        Ok(resp)
+    }
+
+    /// Removes a provider endpoint authorized by the admin.
+    /// Can only be called by the admin.
+    #[sv::msg(exec)]
+    fn remove_provider_endpoint(
+        &self,
+        ctx: ExecCtx<custom::ConverterQuery>,
+        connection_id: String,
+        port_id: String,
+    ) -> Result<custom::Response, ContractError> {
+        nonpayable(&ctx.info)?;
+        let config = self.config.load(ctx.deps.storage)?;
+        ensure!(
+            config.admin.as_ref() == Some(&ctx.info.sender),
+            ContractError::Unauthorized
+        );
+        self.provider_endpoints
+            .remove(ctx.deps.storage, (&connection_id, &port_id));
+
+        let resp = Response::new()
+            .add_attribute("action", "remove_provider_endpoint")
+            .add_attribute("connection_id", connection_id)
+            .add_attribute("port_id", port_id);
+        Ok(resp)
+    }
+
+    /// Returns the provider endpoints authorized by the admin
+    #[sv::msg(query)]
+    fn provider_endpoints(
+        &self,
+        ctx: QueryCtx<custom::ConverterQuery>,
+    ) -> Result<ProviderEndpointsResponse, ContractError> {
+        let endpoints = self
+            .provider_endpoints
+            .range(ctx.deps.storage, None, None, Order::Ascending)
+            .map(|item| {
+                let ((connection_id, port_id), endpoint) = item?;
+                Ok(ProviderEndpointInfo {
+                    provider: ProviderIdentity {
+                        chain_id: endpoint.chain_id,
+                        connection_id,
+                        port_id,
+                    },
+                    enabled: endpoint.enabled,
+                })
+            })
+            .collect::<StdResult<_>>()?;
+        Ok(ProviderEndpointsResponse { endpoints })
+    }
+
+    /// Checks the provider endpoint on `connection_id` and `port_id` is authorized, returning the
+    /// chain id the provider must announce, if any.
+    /// Endpoints set by the admin take precedence over the one set at instantiation. If none is
+    /// set, any provider is authorized.
+    pub(crate) fn authorize_provider_endpoint(
+        &self,
+        storage: &dyn Storage,
+        connection_id: &str,
+        port_id: &str,
+    ) -> Result<Option<String>, ContractError> {
+        if let Some(endpoint) = self
+            .provider_endpoints
+            .may_load(storage, (connection_id, port_id))?
+        {
+            ensure!(
+                endpoint.enabled,
+                ContractError::ProviderEndpointDisabled {
+                    connection_id: connection_id.to_owned(),
+                    port_id: port_id.to_owned(),
+                }
+            );
+            return Ok(Some(endpoint.chain_id));
+        }
+
+        match self.config.load(storage)?.provider {
+            Some(provider) => {
+                if provider.connection_id != connection_id {
+                    return Err(ContractError::UnexpectedProviderEndpoint(
+                        connection_id.to_owned(),
+                    ));
+                }
+                if provider.port_id != port_id {
+                    return Err(ContractError::UnexpectedProviderEndpoint(
+                        port_id.to_owned(),
+                    ));
+                }
+                Ok(Some(provider.chain_id))
+            }
+            None => {
+                // Once endpoints are set, other ones are not authorized anymore
+                let endpoints_set = self
+                    .provider_endpoints
+                    .keys_raw(storage, None, None, Order::Ascending)
+                    .next()
+                    .is_some();
+                ensure!(
+                    !endpoints_set,
+                    ContractError::UnexpectedProviderEndpoint(port_id.to_owned())
+                );
+                Ok(None)
+            }
+        }
+    }
+
+    /// Sends an authoritative snapshot of the current active validator set to the provider,
+    /// in chunks of at most `chunk_size` validators. The provider reconciles its validator set
+    /// with it, recovering from any divergence accumulated during channel downtime.
+    /// Can only be called by the admin.
+    #[sv::msg(exec)]
+    fn resync_valset(
+        &self,
+        ctx: ExecCtx<custom::ConverterQuery>,
+        chunk_size: Option<u32>,
+    ) -> Result<custom::Response, ContractError> {
+        nonpayable(&ctx.info)?;
+        let config = self.config.load(ctx.deps.storage)?;
+        ensure!(
+            config.admin.as_ref() == Some(&ctx.info.sender),
+            ContractError::Unauthorized
+        );
+
+        self.do_resync_valset(ctx.deps, &ctx.env, chunk_size)
+    }
+
+    fn do_resync_valset(
+        &self,
+        deps: DepsMut<custom::ConverterQuery>,
+        env: &Env,
+        chunk_size: Option<u32>,
+    ) -> Result<custom::Response, ContractError> {
+        let channel = IBC_CHANNEL.load(deps.storage)?;
+        let validators = deps.querier.query_all_validators()?;
+        let chunk_size = chunk_size.unwrap_or(DEFAULT_RESYNC_CHUNK_SIZE) as usize;
+        let msgs = valset_resync_msgs(env, &channel, &validators, chunk_size)?;
+        let packets = msgs.len() as u64;
+        self.update_metrics(deps.storage, |m| m.packets_sent.valset_resync += packets)?;
+        let (added, removed) = self.reset_active_validators(deps.storage, &validators)?;
+
+        let resp = Response::new()
+            .add_messages(msgs)
+            .add_attribute("action", "resync_valset")
+            .add_attribute("validators", validators.len().to_string())
+            .add_attribute("packets", packets.to_string())
+            .add_attribute("added", added.len().to_string())
+            .add_attribute("removed", removed.len().to_string());
+        Ok(resp)
+    }
+
+    /// Sends the delegation policy of this chain to the provider, which enforces it on new stakes.
+    /// `max_validators` limits the number of validators a single user can stake to, and
+    /// `max_validator_share` the share of the provider total stake on a single validator.
+    /// Can only be called by the admin.
+    #[sv::msg(exec)]
+    fn set_delegation_policy(
+        &self,
+        mut ctx: ExecCtx<custom::ConverterQuery>,
+        max_validators: Option<u32>,
+        max_validator_share: Option<Decimal>,
+    ) -> Result<custom::Response, ContractError> {
+        nonpayable(&ctx.info)?;
+        let config = self.config.load(ctx.deps.storage)?;
+        ensure!(
+            config.admin.as_ref() == Some(&ctx.info.sender),
+            ContractError::Unauthorized
+        );
+        if let Some(share) = max_validator_share {
+            ensure!(
+                share <= Decimal::one(),
+                ContractError::InvalidValidatorShare
+            );
+        }
+
+        let packet = ConsumerPacket::DelegationPolicy {
+            max_validators,
+            max_validator_share,
+        };
+        let msg = make_ibc_packet(&mut ctx, packet)?;
+        self.update_metrics(ctx.deps.storage, |m| m.packets_sent.delegation_policy += 1)?;
+
+        let resp = Response::new()
+            .add_message(msg)
+            .add_attribute("action", "set_delegation_policy")
+            .add_attribute(
+                "max_validators",
+                max_validators.map_or("none".to_owned(), |max| max.to_string()),
+            )
+            .add_attribute(
+                "max_validator_share",
+                max_validator_share.map_or("none".to_owned(), |share| share.to_string()),
+            );
+        Ok(resp)
+    }
+
+    /// Adds the sent funds (in local denom) to the keeper pot. Can be called by anyone.
+    #[sv::msg(exec)]
+    fn fund_keeper_pot(
+        &self,
+        ctx: ExecCtx<custom::ConverterQuery>,
+    ) -> Result<custom::Response, ContractError> {
+        let config = self.config.load(ctx.deps.storage)?;
+        let amount = must_pay(&ctx.info, &config.local_denom)?;
+        let pot = self
+            .keeper_pot
+            .may_load(ctx.deps.storage)?
+            .unwrap_or_default()
+            + amount;
+        self.keeper_pot.save(ctx.deps.storage, &pot)?;
+
+        let resp = Response::new()
+            .add_attribute("action", "fund_keeper_pot")
+            .add_attribute("sender", ctx.info.sender)
+            .add_attribute("amount", amount.to_string())
+            .add_attribute("pot", pot.to_string());
+        Ok(resp)
+    }
+
+    /// Sets the rewards paid to keepers. `None` disables keeper tasks; the pot is kept.
+    /// Can only be called by the admin.
+    #[sv::msg(exec)]
+    fn set_keeper_config(
+        &self,
+        ctx: ExecCtx<custom::ConverterQuery>,
+        keeper_config: Option<KeeperConfig>,
+    ) -> Result<custom::Response, ContractError> {
+        nonpayable(&ctx.info)?;
+        let config = self.config.load(ctx.deps.storage)?;
+        ensure!(
+            config.admin.as_ref() == Some(&ctx.info.sender),
+            ContractError::Unauthorized
+        );
+
+        let resp = Response::new().add_attribute("action", "set_keeper_config");
+        match keeper_config {
+            Some(keeper_config) => {
+                ensure!(
+                    keeper_config.min_interval > 0,
+                    ContractError::InvalidKeeperConfig
+                );
+                self.keeper_config.save(ctx.deps.storage, &keeper_config)?;
+                Ok(resp
+                    .add_attribute("reward", keeper_config.reward.to_string())
+                    .add_attribute("min_interval", keeper_config.min_interval.to_string()))
+            }
+            None => {
+                self.keeper_config.remove(ctx.deps.storage);
+                Ok(resp)
+            }
+        }
+    }
+
+    /// Runs a maintenance task, paying the caller the keeper reward out of the keeper pot.
+    /// Each task can run at most once per `min_interval`, so that liveness doesn't depend on a
+    /// single operator bot without opening the door to spam. Can be called by anyone.
+    #[sv::msg(exec)]
+    fn run_keeper_task(
+        &self,
+        mut ctx: ExecCtx<custom::ConverterQuery>,
+        task: KeeperTask,
+    ) -> Result<custom::Response, ContractError> {
+        nonpayable(&ctx.info)?;
+        let keeper_config = self
+            .keeper_config
+            .may_load(ctx.deps.storage)?
+            .ok_or(ContractError::KeeperDisabled)?;
+
+        if let Some(last_run) = self
+            .keeper_last_run
+            .may_load(ctx.deps.storage, task.as_str())?
+        {
+            let next_run = last_run.plus_seconds(keeper_config.min_interval);
+            ensure!(
+                ctx.env.block.time >= next_run,
+                ContractError::KeeperTaskTooEarly {
+                    task: task.as_str().to_owned(),
+                    next_run,
+                }
+            );
+        }
+        self.keeper_last_run
+            .save(ctx.deps.storage, task.as_str(), &ctx.env.block.time)?;
+
+        let config = self.config.load(ctx.deps.storage)?;
+        let mut resp = match task {
+            KeeperTask::ResyncValset => self.do_resync_valset(ctx.deps.branch(), &ctx.env, None)?,
+            KeeperTask::RefreshPrice => {
+                let msg = WasmMsg::Execute {
+                    contract_addr: config.price_feed.to_string(),
+                    msg: to_json_binary(&PriceFeedExecMsg::Request {})?,
+                    funds: vec![],
+                };
+                Response::new().add_message(msg)
+            }
+        };
+
+        // Rewards are paid as long as there are funds left in the pot
+        let pot = self
+            .keeper_pot
+            .may_load(ctx.deps.storage)?
+            .unwrap_or_default();
+        let reward = min(keeper_config.reward, pot);
+        if !reward.is_zero() {
+            self.keeper_pot.save(ctx.deps.storage, &(pot - reward))?;
+            resp = resp.add_message(BankMsg::Send {
+                to_address: ctx.info.sender.to_string(),
+                amount: vec![Coin::new(reward.u128(), &config.local_denom)],
+            });
+        }
+
+        let event = Event::new("keeper_task")
+            .add_attribute("task", task.as_str())
+            .add_attribute("keeper", ctx.info.sender)
+            .add_attribute("reward", reward.to_string());
+        Ok(resp.add_event(event))
+    }
+
+    #[sv::msg(query)]
+    fn keeper(
+        &self,
+        ctx: QueryCtx<custom::ConverterQuery>,
+    ) -> Result<KeeperResponse, ContractError> {
+        Ok(KeeperResponse {
+            config: self.keeper_config.may_load(ctx.deps.storage)?,
+            pot: self
+                .keeper_pot
+                .may_load(ctx.deps.storage)?
+                .unwrap_or_default(),
+        })
+    }
+
+    /// Sets the subsidy paid to the provider every epoch, on top of the delegation rewards.
+    /// `None` disables subsidies; the budget is kept. Can only be called by the admin.
+    #[sv::msg(exec)]
+    fn set_subsidy_config(
+        &self,
+        ctx: ExecCtx<custom::ConverterQuery>,
+        subsidy_config: Option<SubsidyConfig>,
+    ) -> Result<custom::Response, ContractError> {
+        nonpayable(&ctx.info)?;
+        let config = self.config.load(ctx.deps.storage)?;
+        ensure!(
+            config.admin.as_ref() == Some(&ctx.info.sender),
+            ContractError::Unauthorized
+        );
+
+        let resp = Response::new().add_attribute("action", "set_subsidy_config");
+        match subsidy_config {
+            Some(subsidy_config) => {
+                ctx.deps.api.addr_validate(&subsidy_config.funder)?;
+                self.subsidy_config
+                    .save(ctx.deps.storage, &subsidy_config)?;
+                Ok(resp
+                    .add_attribute("funder", subsidy_config.funder)
+                    .add_attribute("per_epoch", subsidy_config.per_epoch.to_string()))
+            }
+            None => {
+                self.subsidy_config.remove(ctx.deps.storage);
+                Ok(resp)
+            }
+        }
+    }
+
+    /// Adds the sent funds (in local denom) to the subsidy budget, accounted for separately from
+    /// the delegation rewards. Can only be called by the subsidy funder (e.g. the mint or
+    /// treasury module), or the admin.
+    #[sv::msg(exec)]
+    fn top_up_subsidy(
+        &self,
+        ctx: ExecCtx<custom::ConverterQuery>,
+    ) -> Result<custom::Response, ContractError> {
+        let config = self.config.load(ctx.deps.storage)?;
+        let funder = self
+            .subsidy_config
+            .may_load(ctx.deps.storage)?
+            .map(|subsidy_config| subsidy_config.funder);
+        ensure!(
+            config.admin.as_ref() == Some(&ctx.info.sender)
+                || funder.as_deref() == Some(ctx.info.sender.as_str()),
+            ContractError::Unauthorized
+        );
+
+        let amount = must_pay(&ctx.info, &config.local_denom)?;
+        let budget = self
+            .subsidy_budget
+            .may_load(ctx.deps.storage)?
+            .unwrap_or_default()
+            + amount;
+        self.subsidy_budget.save(ctx.deps.storage, &budget)?;
+
+        let resp = Response::new()
+            .add_attribute("action", "top_up_subsidy")
+            .add_attribute("sender", ctx.info.sender)
+            .add_attribute("amount", amount.to_string())
+            .add_attribute("budget", budget.to_string());
+        Ok(resp)
+    }
+
+    #[sv::msg(query)]
+    fn subsidy(
+        &self,
+        ctx: QueryCtx<custom::ConverterQuery>,
+    ) -> Result<SubsidyResponse, ContractError> {
+        Ok(SubsidyResponse {
+            config: self.subsidy_config.may_load(ctx.deps.storage)?,
+            budget: self
+                .subsidy_budget
+                .may_load(ctx.deps.storage)?
+                .unwrap_or_default(),
+        })
+    }
+
+    /// Sets the tolerance over the collateral reported by the provider, within which stakes are
+    /// still converted. Can only be called by the admin.
+    #[sv::msg(exec)]
+    fn set_collateral_tolerance(
+        &self,
+        ctx: ExecCtx<custom::ConverterQuery>,
+        tolerance: Decimal,
+    ) -> Result<custom::Response, ContractError> {
+        nonpayable(&ctx.info)?;
+        let config = self.config.load(ctx.deps.storage)?;
+        ensure!(
+            config.admin.as_ref() == Some(&ctx.info.sender),
+            ContractError::Unauthorized
+        );
+
+        self.collateral_tolerance
+            .save(ctx.deps.storage, &tolerance)?;
+
+        let resp = Response::new()
+            .add_attribute("action", "set_collateral_tolerance")
+            .add_attribute("tolerance", tolerance.to_string());
+        Ok(resp)
+    }
+
+    /// Sets the recipient of the rewards forwarded by the virtual staking contract (e.g. a rewards
+    /// escrow contract), instead of this contract. `None` removes the override.
+    /// Can only be called by the admin.
+    #[sv::msg(exec)]
+    fn set_rewards_recipient(
+        &self,
+        ctx: ExecCtx<custom::ConverterQuery>,
+        recipient: Option<String>,
+    ) -> Result<custom::Response, ContractError> {
+        nonpayable(&ctx.info)?;
+        let config = self.config.load(ctx.deps.storage)?;
+        ensure!(
+            config.admin.as_ref() == Some(&ctx.info.sender),
+            ContractError::Unauthorized
+        );
+        if let Some(recipient) = &recipient {
+            ctx.deps.api.addr_validate(recipient)?;
+        }
+
+        let msg = virtual_staking_api::sv::ExecMsg::SetRewardsRecipient {
+            recipient: recipient.clone(),
+        };
+        let msg = WasmMsg::Execute {
+            contract_addr: self.virtual_stake.load(ctx.deps.storage)?.into(),
+            msg: to_json_binary(&msg)?,
+            funds: vec![],
+        };
+
+        let resp = Response::new()
+            .add_message(msg)
+            .add_attribute("action", "set_rewards_recipient")
+            .add_attribute("recipient", recipient.unwrap_or_default());
+        Ok(resp)
+    }
+
+    /// Returns the collateral last reported by the provider, and the stake converted against it,
+    /// in total and for `delegator` if given
+    #[sv::msg(query)]
+    fn collateral_bound(
+        &self,
+        ctx: QueryCtx<custom::ConverterQuery>,
+        delegator: Option<String>,
+    ) -> Result<CollateralBoundResponse, ContractError> {
+        let delegator_converted = delegator
+            .map(|delegator| {
+                self.delegator_stakes
+                    .may_load(ctx.deps.storage, &delegator)
+                    .map(Option::unwrap_or_default)
+            })
+            .transpose()?;
+        let bound = self.collateral_bound.may_load(ctx.deps.storage)?;
+        let converted = self
+            .provider_stake
+            .may_load(ctx.deps.storage)?
+            .unwrap_or_default();
+        let collateralization = bound
+            .as_ref()
+            .and_then(|bound| collateralization_ratio(bound.reported, converted));
+        Ok(CollateralBoundResponse {
+            bound,
+            tolerance: self.load_collateral_tolerance(ctx.deps.storage)?,
+            converted,
+            delegator_converted,
+            collateralization,
         })
     }
 
+    /// Counters for monitoring. Packets, price and stake conversion, and epoch processing.
+    #[sv::msg(query)]
+    fn metrics(
+        &self,
+        ctx: QueryCtx<custom::ConverterQuery>,
+    ) -> Result<MetricsResponse, ContractError> {
+        let metrics = self.metrics.may_load(ctx.deps.storage)?.unwrap_or_default();
+        Ok(metrics)
+    }
+
+    /// Returns the cumulative contributions of the provider on every channel since genesis
+    #[sv::msg(query)]
+    fn provider_contributions(
+        &self,
+        ctx: QueryCtx<custom::ConverterQuery>,
+    ) -> Result<ProviderContributionsResponse, ContractError> {
+        let channels = self
+            .contributions
+            .range(ctx.deps.storage, None, None, Order::Ascending)
+            .map(|item| {
+                let (channel_id, contributions) = item?;
+                Ok(ChannelContributions {
+                    channel_id,
+                    contributions,
+                })
+            })
+            .collect::<StdResult<_>>()?;
+        Ok(ProviderContributionsResponse { channels })
+    }
+
+    /// Updates the contributions of the provider on the open channel, if any
+    pub(crate) fn update_contributions(
+        &self,
+        storage: &mut dyn Storage,
+        update: impl FnOnce(&mut ProviderContributions),
+    ) -> StdResult<()> {
+        let Some(channel) = IBC_CHANNEL.may_load(storage)? else {
+            return Ok(());
+        };
+        let channel_id = channel.endpoint.channel_id.as_str();
+        let mut contributions = self
+            .contributions
+            .may_load(storage, channel_id)?
+            .unwrap_or_default();
+        update(&mut contributions);
+        self.contributions.save(storage, channel_id, &contributions)
+    }
+
+    pub(crate) fn update_metrics(
+        &self,
+        storage: &mut dyn Storage,
+        update: impl FnOnce(&mut Metrics),
+    ) -> StdResult<()> {
+        let mut metrics = self.metrics.may_load(storage)?.unwrap_or_default();
+        update(&mut metrics);
+        self.metrics.save(storage, &metrics)
+    }
+
+    /// Applies a valset update to the tracked active validators. This only touches the validators
+    /// in the update, so it is cheap regardless of the valset size.
+    ///
+    /// Returns the additions of validators not active yet, as those are the only ones the
+    /// provider has to be told about.
+    pub(crate) fn track_valset_update(
+        &self,
+        storage: &mut dyn Storage,
+        additions: Vec<Validator>,
+        removals: &[String],
+        jailed: &[String],
+        tombstoned: &[String],
+    ) -> StdResult<Vec<Validator>> {
+        let mut new_additions = vec![];
+        for validator in additions {
+            if !self.active_validators.has(storage, &validator.address) {
+                self.active_validators
+                    .save(storage, &validator.address, &())?;
+                new_additions.push(validator);
+            }
+        }
+        for valoper in removals.iter().chain(jailed).chain(tombstoned) {
+            self.active_validators.remove(storage, valoper);
+        }
+        Ok(new_additions)
+    }
+
+    /// Tracks the unjailed validators that are back in the active set of this chain.
+    ///
+    /// Returns the ones that were not tracked as active already.
+    pub(crate) fn track_reactivations(
+        &self,
+        storage: &mut dyn Storage,
+        querier: &QuerierWrapper<custom::ConverterQuery>,
+        unjailed: &[String],
+        removals: &[String],
+    ) -> StdResult<Vec<String>> {
+        let mut reactivated = vec![];
+        for valoper in unjailed {
+            // Only validators in the active set are returned by the staking module
+            if removals.contains(valoper)
+                || self.active_validators.has(storage, valoper)
+                || querier.query_validator(valoper)?.is_none()
+            {
+                continue;
+            }
+            self.active_validators.save(storage, valoper, &())?;
+            reactivated.push(valoper.clone());
+        }
+        Ok(reactivated)
+    }
+
+    /// Replaces the tracked active validators with `validators`, diffing the full set.
+    ///
+    /// Returns the validators that were missing, and the ones that were no longer active.
+    pub(crate) fn reset_active_validators(
+        &self,
+        storage: &mut dyn Storage,
+        validators: &[Validator],
+    ) -> StdResult<(Vec<String>, Vec<String>)> {
+        let active: BTreeSet<&str> = validators.iter().map(|v| v.address.as_str()).collect();
+        let removed = self
+            .active_validators
+            .keys(storage, None, None, Order::Ascending)
+            .filter(|valoper| {
+                valoper
+                    .as_ref()
+                    .map(|valoper| !active.contains(valoper.as_str()))
+                    .unwrap_or(true) // Keep errors, so they are reported
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+        for valoper in &removed {
+            self.active_validators.remove(storage, valoper);
+        }
+        let mut added = vec![];
+        for valoper in active {
+            if !self.active_validators.has(storage, valoper) {
+                self.active_validators.save(storage, valoper, &())?;
+                added.push(valoper.to_owned());
+            }
+        }
+        Ok((added, removed))
+    }
+
     /// This is called by ibc_packet_receive.
-    /// It is pulled out into a method, so it can also be called by test_stake for testing
+    /// It is pulled out into a method, so it can also be called by test_stake for testing.
+    /// Returns the terms of the conversion along with the response, to be acked
     pub(crate) fn stake(
         &self,
         deps: DepsMut<custom::ConverterQuery>,
+        env: &Env,
         delegator: String,
         validator: String,
         stake: Coin,
-    ) -> Result<custom::Response, ContractError> {
-        let amount = self.normalize_price(deps.as_ref(), stake)?;
+    ) -> Result<(custom::Response, ConversionTerms), ContractError> {
+        let staked = stake.amount;
+        let conversion = self.conversion_terms(deps.as_ref(), env, stake)?;
+        self.track_provider_stake(deps.storage, &delegator, staked)?;
+        let amount = conversion.converted.clone();
+        self.update_metrics(deps.storage, |m| {
+            m.total_converted_stake += amount.amount;
+        })?;
+        self.update_contributions(deps.storage, |c| c.stake_received += amount.amount)?;
 
         let event = Event::new("mesh-bond")
             .add_attribute("validator", &validator)
@@ -245,19 +1059,31 @@ impl ConverterContract<'_> {
             funds: vec![],
         };
 
-        Ok(Response::new().add_message(msg).add_event(event))
+        Ok((
+            Response::new().add_message(msg).add_event(event),
+            conversion,
+        ))
     }
 
     /// This is called by ibc_packet_receive.
-    /// It is pulled out into a method, so it can also be called by test_unstake for testing
+    /// It is pulled out into a method, so it can also be called by test_unstake for testing.
+    /// Returns the terms of the conversion along with the response, to be acked
     pub(crate) fn unstake(
         &self,
         deps: DepsMut<custom::ConverterQuery>,
+        env: &Env,
         delegator: String,
         validator: String,
         unstake: Coin,
-    ) -> Result<custom::Response, ContractError> {
-        let amount = self.normalize_price(deps.as_ref(), unstake)?;
+    ) -> Result<(custom::Response, ConversionTerms), ContractError> {
+        let unstaked = unstake.amount;
+        let conversion = self.conversion_terms(deps.as_ref(), env, unstake)?;
+        self.untrack_provider_stake(deps.storage, Some(&delegator), unstaked)?;
+        let amount = conversion.converted.clone();
+        self.update_metrics(deps.storage, |m| {
+            m.total_converted_stake = m.total_converted_stake.saturating_sub(amount.amount);
+        })?;
+        self.update_contributions(deps.storage, |c| c.unstaked += amount.amount)?;
 
         let event = Event::new("mesh-unbond")
             .add_attribute("validator", &validator)
@@ -274,7 +1100,45 @@ impl ConverterContract<'_> {
             funds: vec![],
         };
 
-        Ok(Response::new().add_message(msg).add_event(event))
+        Ok((
+            Response::new().add_message(msg).add_event(event),
+            conversion,
+        ))
+    }
+
+    /// This is called by ibc_packet_receive.
+    /// Moves `rewards` out of the rewards pool into the delegation: the rewards are burned, and
+    /// `stake` (liened on the provider side) is bonded in their place, as any other stake.
+    /// Returns the terms of the conversion along with the response, to be acked
+    pub(crate) fn compound_rewards(
+        &self,
+        deps: DepsMut<custom::ConverterQuery>,
+        env: &Env,
+        delegator: String,
+        validator: String,
+        stake: Coin,
+        rewards: Coin,
+    ) -> Result<(custom::Response, ConversionTerms), ContractError> {
+        let config = self.config.load(deps.storage)?;
+        ensure_eq!(
+            config.local_denom,
+            rewards.denom,
+            ContractError::WrongDenom {
+                sent: rewards.denom,
+                expected: config.local_denom
+            }
+        );
+
+        let event = Event::new("mesh-compound-rewards")
+            .add_attribute("delegator", &delegator)
+            .add_attribute("validator", &validator)
+            .add_attribute("rewards", rewards.amount.to_string());
+        let (response, conversion) = self.stake(deps, env, delegator, validator, stake)?;
+        let burn = BankMsg::Burn {
+            amount: vec![rewards],
+        };
+
+        Ok((response.add_message(burn).add_event(event), conversion))
     }
 
     /// This is called by ibc_packet_receive.
@@ -285,7 +1149,14 @@ impl ConverterContract<'_> {
         validators: &[String],
         burn: Coin,
     ) -> Result<custom::Response, ContractError> {
-        let amount = self.normalize_price(deps.as_ref(), burn)?;
+        let burned = burn.amount;
+        let (amount, _, _) = self.normalize_price(deps.as_ref(), burn)?;
+        // Burns are not attributed to delegators
+        self.untrack_provider_stake(deps.storage, None, burned)?;
+        self.update_metrics(deps.storage, |m| {
+            m.total_converted_stake = m.total_converted_stake.saturating_sub(amount.amount);
+        })?;
+        self.update_contributions(deps.storage, |c| c.burned += amount.amount)?;
 
         let event = Event::new("mesh-burn")
             .add_attribute("validators", validators.join(","))
@@ -304,40 +1175,190 @@ impl ConverterContract<'_> {
         Ok(Response::new().add_message(msg).add_event(event))
     }
 
-    fn normalize_price(
+    /// This is called by ibc_packet_receive.
+    /// It is pulled out into a method, so it can also be called by test_collateral_summary for
+    /// testing
+    pub(crate) fn collateral_summary(
         &self,
-        deps: Deps<custom::ConverterQuery>,
-        amount: Coin,
-    ) -> Result<Coin, ContractError> {
+        deps: DepsMut<custom::ConverterQuery>,
+        env: &Env,
+        total_collateral: Coin,
+    ) -> Result<custom::Response, ContractError> {
         let config = self.config.load(deps.storage)?;
         ensure_eq!(
             config.remote_denom,
-            amount.denom,
+            total_collateral.denom,
             ContractError::WrongDenom {
-                sent: amount.denom,
+                sent: total_collateral.denom,
                 expected: config.remote_denom
             }
         );
+        let bound = CollateralBound {
+            reported: total_collateral.amount,
+            reported_at: env.block.time,
+        };
+        self.collateral_bound.save(deps.storage, &bound)?;
+
+        let converted = self
+            .provider_stake
+            .may_load(deps.storage)?
+            .unwrap_or_default();
+        let mut event = Event::new("mesh-collateral-summary")
+            .add_attribute("total_collateral", total_collateral.amount.to_string())
+            .add_attribute("converted", converted.to_string());
+        if let Some(ratio) = collateralization_ratio(bound.reported, converted) {
+            event = event.add_attribute("collateralization", ratio.to_string());
+        }
+        Ok(Response::new().add_event(event))
+    }
+
+    fn load_collateral_tolerance(&self, storage: &dyn Storage) -> StdResult<Decimal> {
+        Ok(self
+            .collateral_tolerance
+            .may_load(storage)?
+            .unwrap_or(DEFAULT_COLLATERAL_TOLERANCE))
+    }
+
+    /// Adds `amount` (in remote denom) to the stake converted for the provider and `delegator`.
+    /// Rejects it if either would exceed the collateral last reported by the provider, plus the
+    /// tolerance. A defense in depth check against a compromised provider inflating the stake
+    fn track_provider_stake(
+        &self,
+        storage: &mut dyn Storage,
+        delegator: &str,
+        amount: Uint128,
+    ) -> Result<(), ContractError> {
+        let total = self.provider_stake.may_load(storage)?.unwrap_or_default() + amount;
+        let delegated = self
+            .delegator_stakes
+            .may_load(storage, delegator)?
+            .unwrap_or_default()
+            + amount;
+        if let Some(bound) = self.collateral_bound.may_load(storage)? {
+            let tolerance = self.load_collateral_tolerance(storage)?;
+            let max = bound.reported + bound.reported * tolerance;
+            let converted = total.max(delegated);
+            ensure!(
+                converted <= max,
+                ContractError::CollateralBoundExceeded {
+                    converted,
+                    bound: max,
+                }
+            );
+        }
+        self.provider_stake.save(storage, &total)?;
+        self.delegator_stakes.save(storage, delegator, &delegated)?;
+        Ok(())
+    }
+
+    /// Removes `amount` (in remote denom) from the stake converted for the provider, and for
+    /// `delegator` if given
+    fn untrack_provider_stake(
+        &self,
+        storage: &mut dyn Storage,
+        delegator: Option<&str>,
+        amount: Uint128,
+    ) -> StdResult<()> {
+        let total = self.provider_stake.may_load(storage)?.unwrap_or_default();
+        self.provider_stake
+            .save(storage, &total.saturating_sub(amount))?;
+        if let Some(delegator) = delegator {
+            let delegated = self
+                .delegator_stakes
+                .may_load(storage, delegator)?
+                .unwrap_or_default()
+                .saturating_sub(amount);
+            if delegated.is_zero() {
+                self.delegator_stakes.remove(storage, delegator);
+            } else {
+                self.delegator_stakes.save(storage, delegator, &delegated)?;
+            }
+        }
+        Ok(())
+    }
 
+    /// Converts `amount` at the current price, and returns the terms of the conversion
+    fn conversion_terms(
+        &self,
+        deps: Deps<custom::ConverterQuery>,
+        env: &Env,
+        amount: Coin,
+    ) -> Result<ConversionTerms, ContractError> {
+        let (converted, price, price_adjustment) = self.normalize_price(deps, amount)?;
+        let epoch = self
+            .metrics
+            .may_load(deps.storage)?
+            .and_then(|m| m.last_epoch_height);
+        Ok(ConversionTerms {
+            converted,
+            price,
+            price_adjustment,
+            height: env.block.height,
+            epoch,
+        })
+    }
+
+    fn query_price(
+        &self,
+        deps: Deps<custom::ConverterQuery>,
+        price_feed: Addr,
+    ) -> StdResult<PriceResponse> {
         // FIXME not sure how to get this to compile with latest sylvia
         // get the price value (usage is a bit clunky, need to use trait and cannot chain Remote::new() with .querier())
         // also see https://github.com/CosmWasm/sylvia/issues/181 to just store Remote in state
         use price_feed_api::sv::Querier;
         use sylvia::types::Remote;
+        // Note: it doesn't seem to matter which error type goes here...
         let remote = Remote::<
             &dyn price_feed_api::PriceFeedApi<
                 Error = StdError,
                 ExecC = custom::ConverterMsg,
                 QueryC = custom::ConverterQuery,
             >,
-        >::new(config.price_feed);
-        let price = remote.querier(&deps.querier).price()?.native_per_foreign;
+        >::new(price_feed);
+        remote.querier(&deps.querier).price()
+    }
+
+    /// Time the current price was observed by the price feed, or the block time for feeds not
+    /// reporting it. `None` if the price can't be queried
+    pub(crate) fn price_time(
+        &self,
+        deps: Deps<custom::ConverterQuery>,
+        env: &Env,
+    ) -> StdResult<Option<Timestamp>> {
+        let config = self.config.load(deps.storage)?;
+        Ok(self
+            .query_price(deps, config.price_feed)
+            .ok()
+            .map(|price| price.time.unwrap_or(env.block.time)))
+    }
+
+    /// Returns the converted amount, along with the price and the price adjustment used
+    fn normalize_price(
+        &self,
+        deps: Deps<custom::ConverterQuery>,
+        amount: Coin,
+    ) -> Result<(Coin, Decimal, Decimal), ContractError> {
+        let config = self.config.load(deps.storage)?;
+        ensure_eq!(
+            config.remote_denom,
+            amount.denom,
+            ContractError::WrongDenom {
+                sent: amount.denom,
+                expected: config.remote_denom
+            }
+        );
+
+        let price = self
+            .query_price(deps, config.price_feed)?
+            .native_per_foreign;
         let converted = (amount.amount * price) * config.price_adjustment;
 
-        Ok(Coin {
+        let converted = Coin {
             denom: config.local_denom,
             amount: converted,
-        })
+        };
+        Ok((converted, price, config.price_adjustment))
     }
 
     fn invert_price(
@@ -355,19 +1376,9 @@ impl ConverterContract<'_> {
             }
         );
 
-        // get the price value (usage is a bit clunky, need to use trait and cannot chain Remote::new() with .querier())
-        // also see https://github.com/CosmWasm/sylvia/issues/181 to just store Remote in state
-        use price_feed_api::sv::Querier;
-        use sylvia::types::Remote;
-        // Note: it doesn't seem to matter which error type goes here...
-        let remote = Remote::<
-            &dyn price_feed_api::PriceFeedApi<
-                Error = StdError,
-                ExecC = custom::ConverterMsg,
-                QueryC = custom::ConverterQuery,
-            >,
-        >::new(config.price_feed);
-        let price = remote.querier(&deps.querier).price()?.native_per_foreign;
+        let price = self
+            .query_price(deps, config.price_feed)?
+            .native_per_foreign;
         let converted = (amount.amount * price.inv().ok_or(ContractError::InvalidPrice {})?)
             * config
                 .price_adjustment
@@ -408,6 +1419,33 @@ impl ConverterContract<'_> {
         Ok(msg.into())
     }
 
+    /// Adds the subsidy of the epoch to the rewards `payments`, pro rata to the rewards, out of
+    /// the subsidy budget. Rounding dust is kept in the budget. Returns the subsidy paid
+    fn add_subsidy(
+        &self,
+        storage: &mut dyn Storage,
+        payments: &mut [RewardInfo],
+    ) -> StdResult<Uint128> {
+        let Some(subsidy_config) = self.subsidy_config.may_load(storage)? else {
+            return Ok(Uint128::zero());
+        };
+        let budget = self.subsidy_budget.may_load(storage)?.unwrap_or_default();
+        let subsidy = min(subsidy_config.per_epoch, budget);
+        let total_rewards: Uint128 = payments.iter().map(|payment| payment.reward).sum();
+        if subsidy.is_zero() || total_rewards.is_zero() {
+            return Ok(Uint128::zero());
+        }
+
+        let mut paid = Uint128::zero();
+        for payment in payments.iter_mut() {
+            let share = subsidy.multiply_ratio(payment.reward, total_rewards);
+            payment.reward += share;
+            paid += share;
+        }
+        self.subsidy_budget.save(storage, &(budget - paid))?;
+        Ok(paid)
+    }
+
     fn ensure_authorized(
         &self,
         deps: &DepsMut<custom::ConverterQuery>,
@@ -438,12 +1476,19 @@ impl ConverterApi for ConverterContract<'_> {
         let denom = config.local_denom;
         must_pay(&ctx.info, &denom)?;
         let rewards = ctx.info.funds.remove(0);
+        let rewards_amount = rewards.amount;
 
         let event = Event::new("distribute_reward")
             .add_attribute("validator", &validator)
             .add_attribute("amount", rewards.amount.to_string());
 
         let msg = make_ibc_packet(&mut ctx, ConsumerPacket::Distribute { validator, rewards })?;
+        let height = ctx.env.block.height;
+        self.update_metrics(ctx.deps.storage, |m| {
+            m.packets_sent.distribute += 1;
+            m.last_epoch_height = Some(height);
+        })?;
+        self.update_contributions(ctx.deps.storage, |c| c.rewards_paid += rewards_amount)?;
         Ok(Response::new().add_message(msg).add_event(event))
     }
 
@@ -452,10 +1497,12 @@ impl ConverterApi for ConverterContract<'_> {
     ///
     /// info.funds sent along with the message should be the sum of all rewards for all validators,
     /// in the native staking denom.
+    ///
+    /// This is called once per epoch. The subsidy of the epoch, if any, is added to the rewards.
     fn distribute_rewards(
         &self,
         mut ctx: ExecCtx<custom::ConverterQuery>,
-        payments: Vec<RewardInfo>,
+        mut payments: Vec<RewardInfo>,
     ) -> Result<custom::Response, Self::Error> {
         self.ensure_authorized(&ctx.deps, &ctx.info)?;
 
@@ -472,7 +1519,25 @@ impl ConverterApi for ConverterContract<'_> {
             });
         }
 
-        Ok(Response::new()
+        let subsidy = self.add_subsidy(ctx.deps.storage, &mut payments)?;
+
+        let height = ctx.env.block.height;
+        self.update_metrics(ctx.deps.storage, |m| {
+            m.packets_sent.distribute_batch += 1;
+            m.last_epoch_height = Some(height);
+        })?;
+        self.update_contributions(ctx.deps.storage, |c| {
+            c.rewards_paid += sent;
+            c.subsidies_paid += subsidy;
+        })?;
+
+        let mut resp = Response::new();
+        if !subsidy.is_zero() {
+            resp = resp.add_event(
+                Event::new("distribute_subsidy").add_attribute("amount", subsidy.to_string()),
+            );
+        }
+        Ok(resp
             .add_events(payments.iter().map(|reward_info| {
                 Event::new("distribute_reward")
                     .add_attribute("validator", &reward_info.validator)
@@ -508,6 +1573,13 @@ impl ConverterApi for ConverterContract<'_> {
         // Send over IBC to the Consumer
         let channel = IBC_CHANNEL.load(ctx.deps.storage)?;
 
+        // Validators already active don't have to be added again
+        let additions =
+            self.track_valset_update(ctx.deps.storage, additions, &removals, &jailed, &tombstoned)?;
+        // Unjailed validators that are back in the active set are reactivated on the Provider
+        let reactivated =
+            self.track_reactivations(ctx.deps.storage, &ctx.deps.querier, &unjailed, &removals)?;
+
         let mut event = Event::new("valset_update");
         let mut is_empty = true;
 
@@ -545,6 +1617,9 @@ impl ConverterApi for ConverterContract<'_> {
             event = event.add_attribute("unjailed", unjailed.join(","));
             is_empty = false;
         }
+        if !reactivated.is_empty() {
+            event = event.add_attribute("reactivated", reactivated.join(","));
+        }
         if !tombstoned.is_empty() {
             event = event.add_attribute("tombstoned", tombstoned.join(","));
             is_empty = false;
@@ -580,7 +1655,13 @@ impl ConverterApi for ConverterContract<'_> {
                     .collect::<Vec<String>>()
                     .join(","),
             );
+            let slash_amount: Uint128 = slashed.iter().map(|v| v.slash_amount.amount).sum();
+            self.update_contributions(ctx.deps.storage, |c| c.slashed += slash_amount)?;
             // Convert slash amounts to Provider's coin
+            let price_time = self.price_time(ctx.deps.as_ref(), &ctx.env)?;
+            self.update_metrics(ctx.deps.storage, |m| {
+                m.last_price_time = price_time.or(m.last_price_time)
+            })?;
             slashed
                 .iter_mut()
                 .map(|v| {
@@ -615,10 +1696,12 @@ impl ConverterApi for ConverterContract<'_> {
                 &updated,
                 &jailed,
                 &unjailed,
+                &reactivated,
                 &tombstoned,
                 &slashed,
             )?;
             resp = resp.add_message(valset_msg);
+            self.update_metrics(ctx.deps.storage, |m| m.packets_sent.valset_update += 1)?;
         }
         resp = resp.add_event(event);
         Ok(resp)
@@ -644,6 +1727,11 @@ impl ConverterApi for ConverterContract<'_> {
 
         // Recalculate the price when unbond
         let inverted_amount = self.invert_price(ctx.deps.as_ref(), amount.clone())?;
+        let price_time = self.price_time(ctx.deps.as_ref(), &ctx.env)?;
+        self.update_metrics(ctx.deps.storage, |m| {
+            m.packets_sent.internal_unstake += 1;
+            m.last_price_time = price_time.or(m.last_price_time);
+        })?;
         let packet = ConsumerPacket::InternalUnstake {
             delegator,
             validator,
@@ -659,4 +1747,29 @@ impl ConverterApi for ConverterContract<'_> {
         resp = resp.add_message(msg);
         Ok(resp)
     }
+
+    fn slashing_params_update(
+        &self,
+        mut ctx: ExecCtx<custom::ConverterQuery>,
+        double_sign: Decimal,
+        offline: Decimal,
+    ) -> Result<custom::Response, Self::Error> {
+        self.ensure_authorized(&ctx.deps, &ctx.info)?;
+
+        let packet = ConsumerPacket::SlashingParams {
+            height: ctx.env.block.height,
+            time: ctx.env.block.time.seconds(),
+            double_sign,
+            offline,
+        };
+        let msg = make_ibc_packet(&mut ctx, packet)?;
+        self.update_metrics(ctx.deps.storage, |m| m.packets_sent.slashing_params += 1)?;
+
+        let resp = Response::new()
+            .add_message(msg)
+            .add_attribute("action", "slashing_params_update")
+            .add_attribute("double_sign", double_sign.to_string())
+            .add_attribute("offline", offline.to_string());
+        Ok(resp)
+    }
 }
@@ -1,4 +1,4 @@
-use cosmwasm_std::{StdError, Uint128};
+use cosmwasm_std::{StdError, Timestamp, Uint128};
 use cw_utils::{ParseReplyError, PaymentError};
 use mesh_apis::ibc::VersionError;
 use thiserror::Error;
@@ -32,6 +32,24 @@ pub enum ContractError {
     #[error("You must start the channel close on provider side")]
     IbcChannelCloseInitDisallowed,
 
+    #[error("Channel endpoint doesn't match the expected provider: {0}")]
+    UnexpectedProviderEndpoint(String),
+
+    #[error("Provider chain id mismatch: {actual:?}, expected {expected}")]
+    ProviderChainIdMismatch {
+        expected: String,
+        actual: Option<String>,
+    },
+
+    #[error("Invalid provider identity: {0}")]
+    InvalidProviderIdentity(String),
+
+    #[error("Provider endpoint {port_id} on {connection_id} is disabled")]
+    ProviderEndpointDisabled {
+        connection_id: String,
+        port_id: String,
+    },
+
     #[error("Sent wrong denom over IBC: {sent}, expected {expected}")]
     WrongDenom { sent: String, expected: String },
 
@@ -44,9 +62,24 @@ pub enum ContractError {
     #[error("Invalid discount, must be greater or equal than 0.0 and less than 1.0")]
     InvalidDiscount,
 
+    #[error("Invalid validator share, must not be over 1.0 (100%)")]
+    InvalidValidatorShare,
+
     #[error("Invalid denom: {0}")]
     InvalidDenom(String),
 
+    #[error("Keeper tasks are not enabled")]
+    KeeperDisabled,
+
+    #[error("Keeper task {task} can't be run again before {next_run}")]
+    KeeperTaskTooEarly { task: String, next_run: Timestamp },
+
+    #[error("Keeper min interval must be positive")]
+    InvalidKeeperConfig,
+
     #[error("Sum of rewards ({sum}) doesn't match funds sent ({sent})")]
     DistributeRewardsInvalidAmount { sum: Uint128, sent: Uint128 },
+
+    #[error("Converted stake of {converted} would exceed the collateral reported by the provider ({bound})")]
+    CollateralBoundExceeded { converted: Uint128, bound: Uint128 },
 }
@@ -11,8 +11,9 @@ use cw_storage_plus::Item;
 
 use mesh_apis::converter_api::ValidatorSlashInfo;
 use mesh_apis::ibc::{
-    ack_success, validate_channel_order, AckWrapper, AddValidator, ConsumerPacket, ProtocolVersion,
-    ProviderPacket, StakeAck, TransferRewardsAck, UnstakeAck, PROTOCOL_NAME,
+    ack_success, validate_channel_order, AckWrapper, AddValidator, CollateralSummaryAck,
+    ConsumerPacket, ProtocolVersion, ProviderPacket, StakeAck, TransferRewardsAck, UnstakeAck,
+    PROTOCOL_NAME,
 };
 use mesh_apis::virtual_staking_api;
 use sylvia::types::ExecCtx;
@@ -29,6 +30,8 @@ const MIN_IBC_PROTOCOL_VERSION: &str = "0.11.0";
 
 // IBC specific state
 pub const IBC_CHANNEL: Item<IbcChannel> = Item::new("ibc_channel");
+/// Chain id the provider announced when the channel was opened
+pub const PROVIDER_CHAIN_ID: Item<String> = Item::new("provider_chain_id");
 
 // Let those validator syncs take a day...
 const DEFAULT_VALIDATOR_TIMEOUT: u64 = 24 * 60 * 60;
@@ -81,6 +84,13 @@ pub fn ibc_channel_open(
     // verify the ordering is correct
     validate_channel_order(&channel.order)?;
 
+    // assert an authorized provider endpoint, if any
+    ConverterContract::new().authorize_provider_endpoint(
+        deps.storage,
+        &channel.connection_id,
+        &channel.counterparty_endpoint.port_id,
+    )?;
+
     // Check the version. If provided, ensure it is compatible.
     // If not provided, use our most recent version.
     let version = if channel.version.is_empty() {
@@ -127,12 +137,45 @@ pub fn ibc_channel_connect(
     let v: ProtocolVersion = from_json(counterparty_version.as_bytes())?;
     v.verify_compatibility(SUPPORTED_IBC_PROTOCOL_VERSION, MIN_IBC_PROTOCOL_VERSION)?;
 
+    // Ensure the provider is the expected chain, if configured
+    let contract = ConverterContract::new();
+    let expected_chain_id = contract.authorize_provider_endpoint(
+        deps.storage,
+        &channel.connection_id,
+        &channel.counterparty_endpoint.port_id,
+    )?;
+    if let Some(expected) = expected_chain_id {
+        if v.chain_id.as_ref() != Some(&expected) {
+            return Err(ContractError::ProviderChainIdMismatch {
+                expected,
+                actual: v.chain_id,
+            });
+        }
+    }
+
     // store the channel
     IBC_CHANNEL.save(deps.storage, &channel)?;
+    match &v.chain_id {
+        Some(chain_id) => PROVIDER_CHAIN_ID.save(deps.storage, chain_id)?,
+        None => PROVIDER_CHAIN_ID.remove(deps.storage),
+    }
 
     // Send a validator sync packet to arrive with the newly established channel
     let validators = deps.querier.query_all_validators()?;
-    let msg = valset_update_msg(&env, &channel, &validators, &[], &[], &[], &[], &[], &[])?;
+    let msg = valset_update_msg(
+        &env,
+        &channel,
+        &validators,
+        &[],
+        &[],
+        &[],
+        &[],
+        &[],
+        &[],
+        &[],
+    )?;
+    contract.update_metrics(deps.storage, |m| m.packets_sent.valset_update += 1)?;
+    contract.reset_active_validators(deps.storage, &validators)?;
 
     Ok(IbcBasicResponse::new().add_message(msg))
 }
@@ -146,6 +189,7 @@ pub(crate) fn valset_update_msg(
     updated: &[Validator],
     jailed: &[String],
     unjailed: &[String],
+    reactivated: &[String],
     tombstoned: &[String],
     slashed: &[ValidatorSlashInfo],
 ) -> Result<IbcMsg, ContractError> {
@@ -173,6 +217,7 @@ pub(crate) fn valset_update_msg(
         updated,
         jailed: jailed.to_vec(),
         unjailed: unjailed.to_vec(),
+        reactivated: reactivated.to_vec(),
         tombstoned: tombstoned.to_vec(),
         slashed: slashed.to_vec(),
     };
@@ -184,6 +229,55 @@ pub(crate) fn valset_update_msg(
     Ok(msg)
 }
 
+/// Builds the packets of a full valset resync, splitting `validators` into chunks of at most
+/// `chunk_size` validators, ordered by address
+pub(crate) fn valset_resync_msgs(
+    env: &Env,
+    channel: &IbcChannel,
+    validators: &[Validator],
+    chunk_size: usize,
+) -> Result<Vec<IbcMsg>, ContractError> {
+    let mut valopers: Vec<_> = validators.iter().map(|v| v.address.clone()).collect();
+    valopers.sort();
+    // An empty valset is still sent, as a single unbounded chunk
+    let chunks: Vec<&[String]> = if valopers.is_empty() {
+        vec![&[]]
+    } else {
+        valopers.chunks(chunk_size.max(1)).collect()
+    };
+    let last = chunks.len() - 1;
+    let mut start_after = None;
+    let mut msgs = vec![];
+    for (i, chunk) in chunks.into_iter().enumerate() {
+        let end = if i == last {
+            None
+        } else {
+            chunk.last().cloned()
+        };
+        let packet = ConsumerPacket::ValsetResync {
+            height: env.block.height,
+            time: env.block.time.seconds(),
+            start_after: start_after.clone(),
+            end: end.clone(),
+            validators: chunk
+                .iter()
+                .map(|valoper| AddValidator {
+                    valoper: valoper.clone(),
+                    // TODO: not yet available in CosmWasm APIs. See https://github.com/CosmWasm/cosmwasm/issues/1828
+                    pub_key: "TODO".to_string(),
+                })
+                .collect(),
+        };
+        msgs.push(IbcMsg::SendPacket {
+            channel_id: channel.endpoint.channel_id.clone(),
+            data: to_json_binary(&packet)?,
+            timeout: packet_timeout_validator(env),
+        });
+        start_after = end;
+    }
+    Ok(msgs)
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 /// On closed channel, we take all tokens from reflect contract to this contract.
 /// We also delete the channel entry from accounts.
@@ -193,6 +287,8 @@ pub fn ibc_channel_close(
     _msg: IbcChannelCloseMsg,
 ) -> Result<IbcBasicResponse, ContractError> {
     let contract = ConverterContract::new();
+    // A new channel can be opened, possibly to another authorized provider endpoint
+    IBC_CHANNEL.remove(deps.storage);
     let msg = virtual_staking_api::sv::ExecMsg::HandleCloseChannel {};
     let msg = WasmMsg::Execute {
         contract_addr: contract.virtual_stake.load(deps.storage)?.into(),
@@ -208,11 +304,41 @@ pub fn ibc_channel_close(
 /// of execution. We just return ok if we dispatched, error if we failed to dispatch
 pub fn ibc_packet_receive(
     deps: DepsMut<custom::ConverterQuery>,
-    _env: Env,
+    env: Env,
     msg: IbcPacketReceiveMsg,
 ) -> Result<IbcReceiveResponse<custom::ConverterMsg>, ContractError> {
-    let packet: ProviderPacket = from_json(msg.packet.data)?;
     let contract = ConverterContract::new();
+    // Packets are only accepted on the open channel, from an authorized provider endpoint
+    let channel = IBC_CHANNEL.load(deps.storage)?;
+    if msg.packet.dest.channel_id != channel.endpoint.channel_id {
+        return Err(ContractError::IbcChannelNotMatch);
+    }
+    contract.authorize_provider_endpoint(
+        deps.storage,
+        &channel.connection_id,
+        &channel.counterparty_endpoint.port_id,
+    )?;
+    let packet: ProviderPacket = from_json(msg.packet.data)?;
+    // Stakes, unstakes and burns are converted with the price feed
+    let price_time = match packet {
+        ProviderPacket::Stake { .. }
+        | ProviderPacket::Unstake { .. }
+        | ProviderPacket::Burn { .. }
+        | ProviderPacket::CompoundRewards { .. } => contract.price_time(deps.as_ref(), &env)?,
+        _ => None,
+    };
+    contract.update_metrics(deps.storage, |m| {
+        let received = &mut m.packets_received;
+        match packet {
+            ProviderPacket::Stake { .. } => received.stake += 1,
+            ProviderPacket::Unstake { .. } => received.unstake += 1,
+            ProviderPacket::Burn { .. } => received.burn += 1,
+            ProviderPacket::TransferRewards { .. } => received.transfer_rewards += 1,
+            ProviderPacket::CompoundRewards { .. } => received.compound_rewards += 1,
+            ProviderPacket::CollateralSummary { .. } => received.collateral_summary += 1,
+        }
+        m.last_price_time = price_time.or(m.last_price_time);
+    })?;
     let res = match packet {
         ProviderPacket::Stake {
             delegator,
@@ -220,8 +346,10 @@ pub fn ibc_packet_receive(
             stake,
             tx_id: _,
         } => {
-            let response = contract.stake(deps, delegator, validator, stake)?;
-            let ack = ack_success(&StakeAck {})?;
+            let (response, conversion) = contract.stake(deps, &env, delegator, validator, stake)?;
+            let ack = ack_success(&StakeAck {
+                conversion: Some(conversion),
+            })?;
             IbcReceiveResponse::new()
                 .set_ack(ack)
                 .add_submessages(response.messages)
@@ -234,8 +362,11 @@ pub fn ibc_packet_receive(
             unstake,
             tx_id: _,
         } => {
-            let response = contract.unstake(deps, delegator, validator, unstake)?;
-            let ack: cosmwasm_std::Binary = ack_success(&UnstakeAck {})?;
+            let (response, conversion) =
+                contract.unstake(deps, &env, delegator, validator, unstake)?;
+            let ack = ack_success(&UnstakeAck {
+                conversion: Some(conversion),
+            })?;
             IbcReceiveResponse::new()
                 .set_ack(ack)
                 .add_submessages(response.messages)
@@ -244,7 +375,7 @@ pub fn ibc_packet_receive(
         }
         ProviderPacket::Burn { validators, burn } => {
             let response = contract.burn(deps, &validators, burn)?;
-            let ack = ack_success(&UnstakeAck {})?;
+            let ack = ack_success(&UnstakeAck { conversion: None })?;
             IbcReceiveResponse::new()
                 .set_ack(ack)
                 .add_submessages(response.messages)
@@ -265,6 +396,31 @@ pub fn ibc_packet_receive(
                 .add_message(msg)
                 .add_event(event)
         }
+        ProviderPacket::CompoundRewards {
+            delegator,
+            validator,
+            stake,
+            rewards,
+            tx_id: _,
+        } => {
+            let (response, conversion) =
+                contract.compound_rewards(deps, &env, delegator, validator, stake, rewards)?;
+            let ack = ack_success(&StakeAck {
+                conversion: Some(conversion),
+            })?;
+            IbcReceiveResponse::new()
+                .set_ack(ack)
+                .add_submessages(response.messages)
+                .add_events(response.events)
+                .add_attributes(response.attributes)
+        }
+        ProviderPacket::CollateralSummary { total_collateral } => {
+            let response = contract.collateral_summary(deps, &env, total_collateral)?;
+            let ack = ack_success(&CollateralSummaryAck {})?;
+            IbcReceiveResponse::new()
+                .set_ack(ack)
+                .add_events(response.events)
+        }
     };
     Ok(res)
 }
@@ -306,6 +462,7 @@ pub fn ibc_packet_ack(
             }
         }
         AckWrapper::Error(e) => {
+            contract.update_metrics(deps.storage, |m| m.packets_failed += 1)?;
             // The wasmd framework will label this with the contract_addr, which helps us find the port and issue.
             // Provide info to find the actual packet.
             let event = Event::new("mesh_ibc_error")
@@ -321,10 +478,11 @@ pub fn ibc_packet_ack(
 #[cfg_attr(not(feature = "library"), entry_point)]
 /// The most we can do here is retry the packet, hoping it will eventually arrive.
 pub fn ibc_packet_timeout(
-    _deps: DepsMut,
+    deps: DepsMut,
     env: Env,
     msg: IbcPacketTimeoutMsg,
 ) -> Result<IbcBasicResponse, ContractError> {
+    ConverterContract::new().update_metrics(deps.storage, |m| m.packets_failed += 1)?;
     // Play it again, Sam.
     let msg = IbcMsg::SendPacket {
         channel_id: msg.packet.src.channel_id,
@@ -345,3 +503,396 @@ pub(crate) fn make_ibc_packet(
         timeout: packet_timeout_rewards(&ctx.env),
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use std::marker::PhantomData;
+
+    use cosmwasm_std::testing::{
+        mock_dependencies, mock_env, mock_ibc_channel, mock_ibc_packet_recv, mock_info, MockApi,
+        MockQuerier, MockStorage,
+    };
+    use cosmwasm_std::{coin, coins, Addr, CosmosMsg, Decimal, Deps, IbcMsg, OwnedDeps, Uint128};
+    use mesh_apis::converter_api::{ConverterApi, RewardInfo};
+    use mesh_apis::ibc::ORDERING;
+    use sylvia::types::QueryCtx;
+
+    use crate::msg::ChannelContributions;
+    use crate::state::{
+        Config, ProviderContributions, ProviderEndpoint, ProviderIdentity, SubsidyConfig,
+    };
+
+    use super::*;
+
+    #[test]
+    fn provider_identity_verified_on_handshake() {
+        let mut deps = mock_dependencies();
+        let channel = mock_ibc_channel("channel-1", ORDERING, "");
+        let provider = ProviderIdentity {
+            chain_id: "osmosis-1".to_string(),
+            connection_id: channel.connection_id.clone(),
+            port_id: channel.counterparty_endpoint.port_id.clone(),
+        };
+        let config = Config {
+            price_adjustment: Decimal::one(),
+            price_feed: Addr::unchecked("price_feed"),
+            local_denom: "ujuno".to_string(),
+            remote_denom: "uosmo".to_string(),
+            admin: None,
+            provider: Some(provider),
+        };
+        ConverterContract::new()
+            .config
+            .save(deps.as_mut().storage, &config)
+            .unwrap();
+
+        // Opening on another connection is rejected
+        let mut wrong_channel = channel.clone();
+        wrong_channel.connection_id = "connection-7".to_string();
+        let err = ibc_channel_open(
+            deps.as_mut(),
+            mock_env(),
+            IbcChannelOpenMsg::new_init(wrong_channel),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::UnexpectedProviderEndpoint("connection-7".to_string())
+        );
+        ibc_channel_open(
+            deps.as_mut(),
+            mock_env(),
+            IbcChannelOpenMsg::new_init(channel.clone()),
+        )
+        .unwrap();
+
+        // The provider must announce the expected chain id
+        let version = ProtocolVersion::new(PROTOCOL_NAME, SUPPORTED_IBC_PROTOCOL_VERSION);
+        let err = ibc_channel_connect(
+            deps.as_mut(),
+            mock_env(),
+            IbcChannelConnectMsg::new_ack(channel.clone(), version.to_string().unwrap()),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::ProviderChainIdMismatch {
+                expected: "osmosis-1".to_string(),
+                actual: None,
+            }
+        );
+        let err = ibc_channel_connect(
+            deps.as_mut(),
+            mock_env(),
+            IbcChannelConnectMsg::new_ack(
+                channel.clone(),
+                version.clone().with_chain_id("juno-1").to_string().unwrap(),
+            ),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::ProviderChainIdMismatch {
+                expected: "osmosis-1".to_string(),
+                actual: Some("juno-1".to_string()),
+            }
+        );
+
+        ibc_channel_connect(
+            deps.as_mut(),
+            mock_env(),
+            IbcChannelConnectMsg::new_ack(
+                channel,
+                version.with_chain_id("osmosis-1").to_string().unwrap(),
+            ),
+        )
+        .unwrap();
+        assert_eq!(
+            PROVIDER_CHAIN_ID.load(deps.as_ref().storage).unwrap(),
+            "osmosis-1"
+        );
+    }
+
+    #[test]
+    fn authorized_provider_endpoints() {
+        let mut deps = OwnedDeps {
+            storage: MockStorage::default(),
+            api: MockApi::default(),
+            querier: MockQuerier::<custom::ConverterQuery>::new(&[]),
+            custom_query_type: PhantomData,
+        };
+        let contract = ConverterContract::new();
+        let old = mock_ibc_channel("channel-1", ORDERING, "");
+        let mut new = mock_ibc_channel("channel-2", ORDERING, "");
+        new.counterparty_endpoint.port_id = "wasm.new_provider".to_string();
+        let config = Config {
+            price_adjustment: Decimal::one(),
+            price_feed: Addr::unchecked("price_feed"),
+            local_denom: "ujuno".to_string(),
+            remote_denom: "uosmo".to_string(),
+            admin: None,
+            provider: Some(ProviderIdentity {
+                chain_id: "osmosis-1".to_string(),
+                connection_id: old.connection_id.clone(),
+                port_id: old.counterparty_endpoint.port_id.clone(),
+            }),
+        };
+        contract
+            .config
+            .save(deps.as_mut().storage, &config)
+            .unwrap();
+        let set_endpoint =
+            |deps: DepsMut<custom::ConverterQuery>, channel: &IbcChannel, enabled: bool| {
+                let endpoint = ProviderEndpoint {
+                    chain_id: "osmosis-1".to_string(),
+                    enabled,
+                };
+                contract
+                    .provider_endpoints
+                    .save(
+                        deps.storage,
+                        (
+                            &channel.connection_id,
+                            &channel.counterparty_endpoint.port_id,
+                        ),
+                        &endpoint,
+                    )
+                    .unwrap();
+            };
+        let authorize = |deps: Deps<custom::ConverterQuery>, channel: &IbcChannel| {
+            contract.authorize_provider_endpoint(
+                deps.storage,
+                &channel.connection_id,
+                &channel.counterparty_endpoint.port_id,
+            )
+        };
+
+        // Endpoints set by the admin are authorized along with the configured one
+        set_endpoint(deps.as_mut(), &new, true);
+        assert_eq!(
+            authorize(deps.as_ref(), &old).unwrap(),
+            Some("osmosis-1".to_string())
+        );
+        assert_eq!(
+            authorize(deps.as_ref(), &new).unwrap(),
+            Some("osmosis-1".to_string())
+        );
+        let mut other = new.clone();
+        other.counterparty_endpoint.port_id = "wasm.other".to_string();
+        assert_eq!(
+            authorize(deps.as_ref(), &other).unwrap_err(),
+            ContractError::UnexpectedProviderEndpoint("wasm.other".to_string())
+        );
+
+        // Packets are rejected once their endpoint is disabled
+        IBC_CHANNEL.save(deps.as_mut().storage, &old).unwrap();
+        set_endpoint(deps.as_mut(), &old, false);
+        let packet = ProviderPacket::Burn {
+            validators: vec![],
+            burn: coin(0, "uosmo"),
+        };
+        let err = ibc_packet_receive(
+            deps.as_mut(),
+            mock_env(),
+            mock_ibc_packet_recv("channel-1", &packet).unwrap(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::ProviderEndpointDisabled {
+                connection_id: old.connection_id.clone(),
+                port_id: old.counterparty_endpoint.port_id.clone(),
+            }
+        );
+
+        // As are packets on any other channel
+        let err = ibc_packet_receive(
+            deps.as_mut(),
+            mock_env(),
+            mock_ibc_packet_recv("channel-2", &packet).unwrap(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::IbcChannelNotMatch);
+    }
+
+    #[test]
+    fn provider_contributions_tracked_per_channel() {
+        let mut deps = OwnedDeps {
+            storage: MockStorage::default(),
+            api: MockApi::default(),
+            querier: MockQuerier::<custom::ConverterQuery>::new(&[]),
+            custom_query_type: PhantomData,
+        };
+        let contract = ConverterContract::new();
+        let config = Config {
+            price_adjustment: Decimal::one(),
+            price_feed: Addr::unchecked("price_feed"),
+            local_denom: "ujuno".to_string(),
+            remote_denom: "uosmo".to_string(),
+            admin: None,
+            provider: None,
+        };
+        contract
+            .config
+            .save(deps.as_mut().storage, &config)
+            .unwrap();
+        contract
+            .virtual_stake
+            .save(deps.as_mut().storage, &Addr::unchecked("virtual_staking"))
+            .unwrap();
+
+        let distribute = |deps: DepsMut<custom::ConverterQuery>, channel_id: &str, amount: u128| {
+            let channel = mock_ibc_channel(channel_id, ORDERING, "");
+            IBC_CHANNEL.save(deps.storage, &channel).unwrap();
+            contract
+                .distribute_reward(
+                    ExecCtx {
+                        deps,
+                        env: mock_env(),
+                        info: mock_info("virtual_staking", &coins(amount, "ujuno")),
+                    },
+                    "alice".to_string(),
+                )
+                .unwrap();
+        };
+        distribute(deps.as_mut(), "channel-1", 100);
+        distribute(deps.as_mut(), "channel-1", 20);
+        // Contributions on a previous channel are kept
+        distribute(deps.as_mut(), "channel-2", 50);
+
+        let contributions = contract
+            .provider_contributions(QueryCtx {
+                deps: deps.as_ref(),
+                env: mock_env(),
+            })
+            .unwrap();
+        let rewards_paid = |amount: u128| ProviderContributions {
+            rewards_paid: Uint128::new(amount),
+            ..Default::default()
+        };
+        assert_eq!(
+            contributions.channels,
+            [
+                ChannelContributions {
+                    channel_id: "channel-1".to_string(),
+                    contributions: rewards_paid(120),
+                },
+                ChannelContributions {
+                    channel_id: "channel-2".to_string(),
+                    contributions: rewards_paid(50),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn subsidy_added_to_distributed_rewards() {
+        let mut deps = OwnedDeps {
+            storage: MockStorage::default(),
+            api: MockApi::default(),
+            querier: MockQuerier::<custom::ConverterQuery>::new(&[]),
+            custom_query_type: PhantomData,
+        };
+        let contract = ConverterContract::new();
+        let config = Config {
+            price_adjustment: Decimal::one(),
+            price_feed: Addr::unchecked("price_feed"),
+            local_denom: "ujuno".to_string(),
+            remote_denom: "uosmo".to_string(),
+            admin: None,
+            provider: None,
+        };
+        contract
+            .config
+            .save(deps.as_mut().storage, &config)
+            .unwrap();
+        contract
+            .virtual_stake
+            .save(deps.as_mut().storage, &Addr::unchecked("virtual_staking"))
+            .unwrap();
+        IBC_CHANNEL
+            .save(
+                deps.as_mut().storage,
+                &mock_ibc_channel("channel-1", ORDERING, ""),
+            )
+            .unwrap();
+        contract
+            .subsidy_config
+            .save(
+                deps.as_mut().storage,
+                &SubsidyConfig {
+                    funder: "mint".to_string(),
+                    per_epoch: Uint128::new(50),
+                },
+            )
+            .unwrap();
+        contract
+            .subsidy_budget
+            .save(deps.as_mut().storage, &Uint128::new(70))
+            .unwrap();
+
+        // Returns the rewards sent to the provider
+        let distribute = |deps: DepsMut<custom::ConverterQuery>| {
+            let payments = vec![
+                RewardInfo {
+                    validator: "alice".to_string(),
+                    reward: Uint128::new(30),
+                },
+                RewardInfo {
+                    validator: "bob".to_string(),
+                    reward: Uint128::new(10),
+                },
+            ];
+            let resp = contract
+                .distribute_rewards(
+                    ExecCtx {
+                        deps,
+                        env: mock_env(),
+                        info: mock_info("virtual_staking", &coins(40, "ujuno")),
+                    },
+                    payments,
+                )
+                .unwrap();
+            let CosmosMsg::Ibc(IbcMsg::SendPacket { data, .. }) = &resp.messages[0].msg else {
+                panic!("Expected an IBC packet");
+            };
+            let ConsumerPacket::DistributeBatch { rewards, .. } = from_json(data).unwrap() else {
+                panic!("Expected a distribute batch packet");
+            };
+            rewards
+                .into_iter()
+                .map(|reward| reward.reward.u128())
+                .collect::<Vec<_>>()
+        };
+
+        // Subsidy is split pro rata to the rewards. Rounding dust stays in the budget
+        assert_eq!(distribute(deps.as_mut()), [30 + 37, 10 + 12]);
+        assert_eq!(
+            contract.subsidy_budget.load(deps.as_ref().storage).unwrap(),
+            Uint128::new(21)
+        );
+
+        // Subsidy is capped by the budget left
+        assert_eq!(distribute(deps.as_mut()), [30 + 15, 10 + 5]);
+        assert_eq!(
+            contract.subsidy_budget.load(deps.as_ref().storage).unwrap(),
+            Uint128::new(1)
+        );
+
+        // Subsidies are accounted for separately from rewards
+        let contributions = contract
+            .provider_contributions(QueryCtx {
+                deps: deps.as_ref(),
+                env: mock_env(),
+            })
+            .unwrap();
+        assert_eq!(
+            contributions.channels[0].contributions,
+            ProviderContributions {
+                rewards_paid: Uint128::new(80),
+                subsidies_paid: Uint128::new(69),
+                ..Default::default()
+            }
+        );
+    }
+}
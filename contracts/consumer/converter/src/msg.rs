@@ -1,6 +1,12 @@
 use cosmwasm_schema::cw_serde;
 use cosmwasm_std::Decimal;
 
+use cosmwasm_std::Uint128;
+
+use crate::state::{
+    CollateralBound, KeeperConfig, ProviderContributions, ProviderIdentity, SubsidyConfig,
+};
+
 #[cw_serde]
 pub struct ConfigResponse {
     pub adjustment: Decimal,
@@ -10,4 +16,101 @@ pub struct ConfigResponse {
 
     /// Address of the virtual staking contract.
     pub virtual_staking: String,
+
+    /// Admin allowed to trigger maintenance operations
+    pub admin: Option<String>,
+}
+
+#[cw_serde]
+pub struct ProviderIdentityResponse {
+    /// Provider the channel must be opened with, if configured
+    pub expected: Option<ProviderIdentity>,
+    /// Chain id announced by the provider the channel was opened with
+    pub chain_id: Option<String>,
+    /// Connection of the open channel
+    pub connection_id: Option<String>,
+    /// Provider port of the open channel
+    pub port_id: Option<String>,
+}
+
+#[cw_serde]
+pub struct ProviderEndpointInfo {
+    pub provider: ProviderIdentity,
+    pub enabled: bool,
+}
+
+#[cw_serde]
+pub struct ProviderEndpointsResponse {
+    /// Provider endpoints authorized by the admin, by connection and port
+    pub endpoints: Vec<ProviderEndpointInfo>,
+}
+
+pub type MetricsResponse = crate::state::Metrics;
+
+#[cw_serde]
+pub struct ChannelContributions {
+    pub channel_id: String,
+    pub contributions: ProviderContributions,
+}
+
+#[cw_serde]
+pub struct ProviderContributionsResponse {
+    /// Contributions on every provider channel since genesis, including closed ones
+    pub channels: Vec<ChannelContributions>,
+}
+
+/// Maintenance tasks anyone can run, for a reward
+#[cw_serde]
+#[derive(Copy)]
+pub enum KeeperTask {
+    /// Sends a full valset resync to the provider
+    ResyncValset,
+    /// Asks the price feed to refresh its price
+    RefreshPrice,
+}
+
+impl KeeperTask {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::ResyncValset => "resync_valset",
+            Self::RefreshPrice => "refresh_price",
+        }
+    }
+}
+
+#[cw_serde]
+pub struct KeeperResponse {
+    /// Keeper rewards configuration. Tasks can't be run if not set
+    pub config: Option<KeeperConfig>,
+    /// Funds left to pay rewards with, in local denom
+    pub pot: Uint128,
+}
+
+#[cw_serde]
+pub struct SubsidyResponse {
+    /// Subsidy configuration. No subsidy is paid if not set
+    pub config: Option<SubsidyConfig>,
+    /// Funds left to pay subsidies with, in local denom
+    pub budget: Uint128,
+}
+
+#[cw_serde]
+pub struct CollateralBoundResponse {
+    /// Collateral reported in the last provider summary. Stakes are unchecked until then
+    pub bound: Option<CollateralBound>,
+    /// Tolerance applied on top of the reported collateral
+    pub tolerance: Decimal,
+    /// Stake converted for the provider, in remote denom, net of unstakes and burns
+    pub converted: Uint128,
+    /// Stake converted for the requested delegator, in remote denom, net of unstakes
+    pub delegator_converted: Option<Uint128>,
+    /// Ratio of the reported collateral to the converted stake. Not set until a summary is
+    /// received, nor while no stake is converted
+    pub collateralization: Option<Decimal>,
+}
+
+/// Execute message of price feeds refreshing their price on request, like the remote ones
+#[cw_serde]
+pub enum PriceFeedExecMsg {
+    Request {},
 }
@@ -1,7 +1,11 @@
-use cosmwasm_std::{coin, coins, Addr, Decimal, StdError, Uint128, Validator};
+use cosmwasm_std::testing::{mock_env, mock_ibc_channel, MockStorage};
+use cosmwasm_std::{
+    coin, coins, from_json, Addr, Decimal, IbcMsg, IbcOrder, Order, StdError, Uint128, Validator,
+};
 use cw_multi_test::{no_init, AppBuilder};
 use mesh_apis::converter_api::sv::mt::ConverterApiProxy;
 use mesh_apis::converter_api::RewardInfo;
+use mesh_apis::ibc::{ConsumerPacket, ConversionTerms, StakeAck, UnstakeAck};
 use mesh_simple_price_feed::contract::sv::mt::CodeId as PriceFeedCodeId;
 use mesh_simple_price_feed::contract::SimplePriceFeedContract;
 use mesh_virtual_staking::contract::sv::mt::{
@@ -15,6 +19,11 @@ use crate::contract::sv::mt::ConverterContractProxy;
 use crate::contract::{custom, ConverterContract};
 use crate::error::ContractError;
 use crate::error::ContractError::Unauthorized;
+use crate::ibc::valset_resync_msgs;
+use crate::msg::{
+    KeeperResponse, KeeperTask, PriceFeedExecMsg, ProviderEndpointInfo, SubsidyResponse,
+};
+use crate::state::{KeeperConfig, ProviderIdentity, SubsidyConfig};
 
 const JUNO: &str = "ujuno";
 
@@ -62,8 +71,10 @@ fn setup<'a>(app: &'a App<MtApp>, args: SetupArgs<'a>) -> SetupResponse<'a> {
             JUNO.to_owned(),
             virtual_staking_code.code_id(),
             true,
+            false,
             Some(admin.to_owned()),
             50,
+            None,
         )
         .with_label("Juno Converter")
         .with_admin(admin)
@@ -113,6 +124,7 @@ fn instantiation() {
     assert_eq!(config.price_feed, price_feed.contract_addr.to_string());
     assert_eq!(config.adjustment, Decimal::percent(60));
     assert!(!config.virtual_staking.is_empty());
+    assert_eq!(config.admin, Some(admin.to_string()));
 
     // let's check we passed the admin here properly
     let vs_info = app
@@ -172,7 +184,7 @@ fn ibc_stake_and_unstake() {
     );
 
     // let's stake some
-    converter
+    let resp = converter
         .test_stake(owner.to_string(), val1.to_string(), coin(1000, JUNO))
         .call(owner)
         .unwrap();
@@ -181,11 +193,27 @@ fn ibc_stake_and_unstake() {
         .call(owner)
         .unwrap();
 
+    // the ack tells the terms of the conversion
+    let local_denom = app.app().wrap().query_bonded_denom().unwrap();
+    let ack: StakeAck = from_json(resp.data.unwrap()).unwrap();
+    assert_eq!(
+        ack.conversion,
+        Some(ConversionTerms {
+            converted: coin(300, &local_denom),
+            price: native_per_foreign,
+            price_adjustment: Decimal::one() - discount,
+            height: app.block_info().height,
+            epoch: None,
+        })
+    );
+
     // and unstake some
-    converter
+    let resp = converter
         .test_unstake(owner.to_string(), val2.to_string(), coin(2000, JUNO))
         .call(owner)
         .unwrap();
+    let ack: UnstakeAck = from_json(resp.data.unwrap()).unwrap();
+    assert_eq!(ack.conversion.unwrap().converted, coin(600, &local_denom));
 
     // new epoch to update all stake values
     virtual_staking.test_handle_epoch().call(owner).unwrap();
@@ -214,6 +242,10 @@ fn ibc_stake_and_unstake() {
             (val2.to_string(), Uint128::new(600)),
         ]
     );
+
+    // converted stake is tracked in the metrics
+    let metrics = converter.metrics().unwrap();
+    assert_eq!(metrics.total_converted_stake.u128(), 900);
 }
 
 #[test]
@@ -304,6 +336,126 @@ fn ibc_stake_and_burn() {
     );
 }
 
+#[test]
+fn collateral_bound_is_enforced() {
+    let app = new_app();
+
+    let owner = "sunny";
+    let admin = "theman";
+    let discount = Decimal::percent(40);
+    let native_per_foreign = Decimal::percent(50);
+
+    let SetupResponse {
+        price_feed: _,
+        converter,
+        virtual_staking: _,
+    } = setup(
+        &app,
+        SetupArgs {
+            owner,
+            admin,
+            discount,
+            native_per_foreign,
+        },
+    );
+
+    let delegator1 = "delegator1";
+    let delegator2 = "delegator2";
+    let val1 = "Val Kilmer";
+
+    // Stakes are not checked until the provider reports its collateral
+    converter
+        .test_stake(delegator1.to_string(), val1.to_string(), coin(1000, JUNO))
+        .call(owner)
+        .unwrap();
+    let bound = converter.collateral_bound(None).unwrap();
+    assert_eq!(bound.bound, None);
+    assert_eq!(bound.collateralization, None);
+    assert_eq!(bound.tolerance, Decimal::percent(10));
+    assert_eq!(bound.converted.u128(), 1000);
+
+    // The summary must be in the provider denom
+    let err = converter
+        .test_collateral_summary(coin(2000, "uosmo"))
+        .call(owner)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::WrongDenom {
+            sent: "uosmo".to_string(),
+            expected: JUNO.to_string(),
+        }
+    );
+    converter
+        .test_collateral_summary(coin(2000, JUNO))
+        .call(owner)
+        .unwrap();
+    let bound = converter
+        .collateral_bound(Some(delegator1.to_string()))
+        .unwrap();
+    assert_eq!(bound.bound.unwrap().reported.u128(), 2000);
+    assert_eq!(bound.delegator_converted, Some(Uint128::new(1000)));
+    assert_eq!(bound.collateralization, Some(Decimal::percent(200)));
+
+    // Stakes are converted up to the reported collateral, plus 10%
+    converter
+        .test_stake(delegator2.to_string(), val1.to_string(), coin(1200, JUNO))
+        .call(owner)
+        .unwrap();
+    let err = converter
+        .test_stake(delegator2.to_string(), val1.to_string(), coin(1, JUNO))
+        .call(owner)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::CollateralBoundExceeded {
+            converted: Uint128::new(2201),
+            bound: Uint128::new(2200),
+        }
+    );
+
+    // Unstakes and burns free up room
+    converter
+        .test_unstake(delegator1.to_string(), val1.to_string(), coin(400, JUNO))
+        .call(owner)
+        .unwrap();
+    converter
+        .test_burn(vec![val1.to_string()], coin(100, JUNO))
+        .call(owner)
+        .unwrap();
+    let bound = converter
+        .collateral_bound(Some(delegator1.to_string()))
+        .unwrap();
+    assert_eq!(bound.converted.u128(), 1700);
+    assert_eq!(bound.delegator_converted, Some(Uint128::new(600)));
+
+    // Only the admin can change the tolerance
+    let err = converter
+        .set_collateral_tolerance(Decimal::zero())
+        .call(owner)
+        .unwrap_err();
+    assert_eq!(err, Unauthorized);
+    converter
+        .set_collateral_tolerance(Decimal::zero())
+        .call(admin)
+        .unwrap();
+    converter
+        .test_stake(delegator2.to_string(), val1.to_string(), coin(300, JUNO))
+        .call(owner)
+        .unwrap();
+    let err = converter
+        .test_stake(delegator2.to_string(), val1.to_string(), coin(1, JUNO))
+        .call(owner)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::CollateralBoundExceeded {
+            converted: Uint128::new(2001),
+            bound: Uint128::new(2000),
+        }
+    );
+}
+
 #[test]
 fn valset_update_works() {
     let app = new_app();
@@ -375,6 +527,282 @@ fn valset_update_works() {
     );
 }
 
+#[test]
+fn set_delegation_policy_works() {
+    let app = new_app();
+
+    let owner = "sunny";
+    let admin = "theman";
+    let SetupResponse { converter, .. } = setup(
+        &app,
+        SetupArgs {
+            owner,
+            admin,
+            discount: Decimal::percent(10),
+            native_per_foreign: Decimal::percent(40),
+        },
+    );
+
+    // Only the admin can set the policy
+    let err = converter
+        .set_delegation_policy(Some(3), None)
+        .call(owner)
+        .unwrap_err();
+    assert_eq!(err, Unauthorized {});
+
+    // Shares are capped to 100%
+    let err = converter
+        .set_delegation_policy(None, Some(Decimal::percent(101)))
+        .call(admin)
+        .unwrap_err();
+    assert_eq!(err, ContractError::InvalidValidatorShare);
+
+    // This fails because of lack of IBC support in mt now.
+    let err = converter
+        .set_delegation_policy(Some(3), Some(Decimal::percent(50)))
+        .call(admin)
+        .unwrap_err();
+    assert!(matches!(err, ContractError::Std(StdError::NotFound { .. })));
+}
+
+#[test]
+fn provider_endpoints_works() {
+    let app = new_app();
+
+    let owner = "sunny";
+    let admin = "theman";
+    let SetupResponse { converter, .. } = setup(
+        &app,
+        SetupArgs {
+            owner,
+            admin,
+            discount: Decimal::percent(10),
+            native_per_foreign: Decimal::percent(40),
+        },
+    );
+    let provider = ProviderIdentity {
+        chain_id: "osmosis-1".to_string(),
+        connection_id: "connection-1".to_string(),
+        port_id: "wasm.new_provider".to_string(),
+    };
+
+    // Only the admin can authorize endpoints
+    let err = converter
+        .set_provider_endpoint(provider.clone(), true)
+        .call(owner)
+        .unwrap_err();
+    assert_eq!(err, Unauthorized {});
+    let err = converter
+        .set_provider_endpoint(
+            ProviderIdentity {
+                chain_id: String::new(),
+                ..provider.clone()
+            },
+            true,
+        )
+        .call(admin)
+        .unwrap_err();
+    assert!(matches!(err, ContractError::InvalidProviderIdentity(_)));
+
+    converter
+        .set_provider_endpoint(provider.clone(), true)
+        .call(admin)
+        .unwrap();
+    converter
+        .set_provider_endpoint(provider.clone(), false)
+        .call(admin)
+        .unwrap();
+    assert_eq!(
+        converter.provider_endpoints().unwrap().endpoints,
+        [ProviderEndpointInfo {
+            provider: provider.clone(),
+            enabled: false,
+        }]
+    );
+
+    converter
+        .remove_provider_endpoint(provider.connection_id, provider.port_id)
+        .call(admin)
+        .unwrap();
+    assert_eq!(converter.provider_endpoints().unwrap().endpoints, []);
+}
+
+#[test]
+fn slashing_params_update_works() {
+    let app = new_app();
+
+    let owner = "sunny";
+    let admin = "theman";
+    let SetupResponse {
+        converter,
+        virtual_staking,
+        ..
+    } = setup(
+        &app,
+        SetupArgs {
+            owner,
+            admin,
+            discount: Decimal::percent(10),
+            native_per_foreign: Decimal::percent(40),
+        },
+    );
+
+    // Only the virtual staking contract can send the params
+    let err = converter
+        .slashing_params_update(Decimal::percent(5), Decimal::percent(1))
+        .call(admin)
+        .unwrap_err();
+    assert_eq!(err, Unauthorized {});
+
+    // This fails because of lack of IBC support in mt now.
+    let err = converter
+        .slashing_params_update(Decimal::percent(5), Decimal::percent(1))
+        .call(virtual_staking.contract_addr.as_ref())
+        .unwrap_err();
+    assert!(matches!(err, ContractError::Std(StdError::NotFound { .. })));
+}
+
+#[test]
+fn resync_valset_works() {
+    let app = new_app();
+
+    let owner = "sunny";
+    let admin = "theman";
+    let SetupResponse { converter, .. } = setup(
+        &app,
+        SetupArgs {
+            owner,
+            admin,
+            discount: Decimal::percent(10),
+            native_per_foreign: Decimal::percent(40),
+        },
+    );
+
+    // Only the admin can trigger a resync
+    let err = converter.resync_valset(None).call(owner).unwrap_err();
+    assert_eq!(err, Unauthorized {});
+
+    // This fails because of lack of IBC support in mt now.
+    let err = converter.resync_valset(None).call(admin).unwrap_err();
+    assert!(matches!(err, ContractError::Std(StdError::NotFound { .. })));
+
+    // Chunks cover the whole address space, in order
+    let validator = |address: &str| Validator {
+        address: address.to_string(),
+        commission: Default::default(),
+        max_commission: Default::default(),
+        max_change_rate: Default::default(),
+    };
+    let env = mock_env();
+    let channel = mock_ibc_channel("channel-1", IbcOrder::Unordered, "mesh-security");
+    let chunks = |validators: &[Validator], chunk_size| {
+        valset_resync_msgs(&env, &channel, validators, chunk_size)
+            .unwrap()
+            .into_iter()
+            .map(|msg| match msg {
+                IbcMsg::SendPacket { data, .. } => match from_json(data).unwrap() {
+                    ConsumerPacket::ValsetResync {
+                        start_after,
+                        end,
+                        validators,
+                        ..
+                    } => (
+                        start_after,
+                        end,
+                        validators
+                            .into_iter()
+                            .map(|v| v.valoper)
+                            .collect::<Vec<_>>(),
+                    ),
+                    packet => panic!("unexpected packet {packet:?}"),
+                },
+                msg => panic!("unexpected msg {msg:?}"),
+            })
+            .collect::<Vec<_>>()
+    };
+    let some = |valoper: &str| Some(valoper.to_string());
+    let vals = |valopers: &[&str]| valopers.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+
+    assert_eq!(
+        chunks(
+            &[
+                validator("carl"),
+                validator("alice"),
+                validator("dave"),
+                validator("bob"),
+                validator("erin")
+            ],
+            2
+        ),
+        [
+            (None, some("bob"), vals(&["alice", "bob"])),
+            (some("bob"), some("dave"), vals(&["carl", "dave"])),
+            (some("dave"), None, vals(&["erin"])),
+        ]
+    );
+    // An empty valset is still sent
+    assert_eq!(chunks(&[], 2), [(None, None, vec![])]);
+}
+
+#[test]
+fn active_validators_tracking() {
+    let contract = ConverterContract::new();
+    let mut storage = MockStorage::new();
+    let validator = |address: &str| Validator {
+        address: address.to_string(),
+        commission: Default::default(),
+        max_commission: Default::default(),
+        max_change_rate: Default::default(),
+    };
+    let vals = |valopers: &[&str]| valopers.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+    let active = |storage: &MockStorage| {
+        contract
+            .active_validators
+            .keys(storage, None, None, Order::Ascending)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap()
+    };
+
+    // Initial full sync
+    let (added, removed) = contract
+        .reset_active_validators(&mut storage, &[validator("alice"), validator("bob")])
+        .unwrap();
+    assert_eq!((added, removed), (vals(&["alice", "bob"]), vals(&[])));
+
+    // Only new validators are reported as additions
+    let additions = contract
+        .track_valset_update(
+            &mut storage,
+            vec![validator("bob"), validator("carl")],
+            &vals(&["alice"]),
+            &[],
+            &[],
+        )
+        .unwrap();
+    assert_eq!(additions, [validator("carl")]);
+    assert_eq!(active(&storage), vals(&["bob", "carl"]));
+
+    // Jailed and tombstoned validators are no longer active
+    let additions = contract
+        .track_valset_update(
+            &mut storage,
+            vec![validator("dave")],
+            &[],
+            &vals(&["bob"]),
+            &vals(&["carl"]),
+        )
+        .unwrap();
+    assert_eq!(additions, [validator("dave")]);
+    assert_eq!(active(&storage), vals(&["dave"]));
+
+    // A resync diffs the full set
+    let (added, removed) = contract
+        .reset_active_validators(&mut storage, &[validator("alice"), validator("bob")])
+        .unwrap();
+    assert_eq!((added, removed), (vals(&["alice", "bob"]), vals(&["dave"])));
+    assert_eq!(active(&storage), vals(&["alice", "bob"]));
+}
+
 #[test]
 fn unauthorized() {
     let app = new_app();
@@ -551,3 +979,261 @@ fn distribute_rewards_valid_amount() {
         .call(virtual_staking.contract_addr.as_str())
         .unwrap();
 }
+
+/// Price feed stub, accepting refresh requests
+fn store_price_feed_stub(app: &App<MtApp>) -> u64 {
+    use cosmwasm_std::{Binary, Deps, DepsMut, Empty, Env, MessageInfo, Response, StdResult};
+    use cw_multi_test::ContractWrapper;
+
+    type QueryC = custom::ConverterQuery;
+    type ExecC = custom::ConverterMsg;
+
+    fn execute(
+        _deps: DepsMut<QueryC>,
+        _env: Env,
+        _info: MessageInfo,
+        msg: PriceFeedExecMsg,
+    ) -> StdResult<Response<ExecC>> {
+        let PriceFeedExecMsg::Request {} = msg;
+        Ok(Response::new().add_attribute("action", "request"))
+    }
+
+    fn instantiate(
+        _deps: DepsMut<QueryC>,
+        _env: Env,
+        _info: MessageInfo,
+        _msg: Empty,
+    ) -> StdResult<Response<ExecC>> {
+        Ok(Response::new())
+    }
+
+    fn query(_deps: Deps<QueryC>, _env: Env, _msg: Empty) -> StdResult<Binary> {
+        Ok(Binary::default())
+    }
+
+    app.app_mut()
+        .store_code(Box::new(ContractWrapper::new(execute, instantiate, query)))
+}
+
+#[test]
+fn keeper_rewards() {
+    let app = new_app();
+
+    let owner = "sunny";
+    let admin = "theman";
+    let keeper = "keeper";
+    let denom = "TOKEN";
+
+    app.app_mut().init_modules(|router, _, storage| {
+        router
+            .bank
+            .init_balance(storage, &Addr::unchecked(owner), coins(1000, denom))
+            .unwrap();
+    });
+
+    let price_feed_code = store_price_feed_stub(&app);
+    let price_feed = cw_multi_test::Executor::instantiate_contract(
+        &mut *app.app_mut(),
+        price_feed_code,
+        Addr::unchecked(owner),
+        &cosmwasm_std::Empty {},
+        &[],
+        "Price Feed",
+        None,
+    )
+    .unwrap();
+    let virtual_staking_code = VirtualStakingCodeId::store_code(&app);
+    let converter = ConverterCodeId::store_code(&app)
+        .instantiate(
+            price_feed.to_string(),
+            Decimal::percent(10),
+            JUNO.to_owned(),
+            virtual_staking_code.code_id(),
+            true,
+            false,
+            Some(admin.to_owned()),
+            50,
+            None,
+        )
+        .with_label("Juno Converter")
+        .with_admin(admin)
+        .call(owner)
+        .unwrap();
+
+    // Keeper tasks are disabled by default
+    let err = converter
+        .run_keeper_task(KeeperTask::RefreshPrice)
+        .call(keeper)
+        .unwrap_err();
+    assert_eq!(err, ContractError::KeeperDisabled);
+
+    // Only the admin can configure rewards
+    let keeper_config = KeeperConfig {
+        reward: Uint128::new(30),
+        min_interval: 600,
+    };
+    let err = converter
+        .set_keeper_config(Some(keeper_config.clone()))
+        .call(owner)
+        .unwrap_err();
+    assert_eq!(err, Unauthorized {});
+    let err = converter
+        .set_keeper_config(Some(KeeperConfig {
+            min_interval: 0,
+            ..keeper_config.clone()
+        }))
+        .call(admin)
+        .unwrap_err();
+    assert_eq!(err, ContractError::InvalidKeeperConfig);
+    converter
+        .set_keeper_config(Some(keeper_config.clone()))
+        .call(admin)
+        .unwrap();
+
+    // Anyone can fund the pot
+    converter
+        .fund_keeper_pot()
+        .with_funds(&coins(50, denom))
+        .call(owner)
+        .unwrap();
+    assert_eq!(
+        converter.keeper().unwrap(),
+        KeeperResponse {
+            config: Some(keeper_config),
+            pot: Uint128::new(50),
+        }
+    );
+
+    // The keeper is rewarded for running the task
+    converter
+        .run_keeper_task(KeeperTask::RefreshPrice)
+        .call(keeper)
+        .unwrap();
+    let balance = |addr: &str| {
+        app.app()
+            .wrap()
+            .query_balance(addr, denom)
+            .unwrap()
+            .amount
+            .u128()
+    };
+    assert_eq!(balance(keeper), 30);
+    assert_eq!(converter.keeper().unwrap().pot, Uint128::new(20));
+
+    // But not more often than the min interval
+    let next_run = app.block_info().time.plus_seconds(600);
+    let err = converter
+        .run_keeper_task(KeeperTask::RefreshPrice)
+        .call(keeper)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::KeeperTaskTooEarly {
+            task: "refresh_price".to_owned(),
+            next_run,
+        }
+    );
+
+    // Rewards are capped by the pot
+    app.app_mut().update_block(|block| {
+        block.time = block.time.plus_seconds(600);
+    });
+    converter
+        .run_keeper_task(KeeperTask::RefreshPrice)
+        .call(keeper)
+        .unwrap();
+    assert_eq!(balance(keeper), 50);
+    assert_eq!(converter.keeper().unwrap().pot, Uint128::zero());
+
+    // This fails because of lack of IBC support in mt now.
+    let err = converter
+        .run_keeper_task(KeeperTask::ResyncValset)
+        .call(keeper)
+        .unwrap_err();
+    assert!(matches!(err, ContractError::Std(StdError::NotFound { .. })));
+}
+
+#[test]
+fn subsidy_top_ups() {
+    let owner = "sunny";
+    let admin = "theman";
+    let funder = "mint";
+    let denom = "TOKEN";
+
+    let app = new_app();
+
+    let SetupResponse { converter, .. } = setup(
+        &app,
+        SetupArgs {
+            owner,
+            admin,
+            discount: Decimal::percent(10),
+            native_per_foreign: Decimal::percent(40),
+        },
+    );
+
+    app.app_mut().init_modules(|router, _, storage| {
+        for addr in [owner, admin, funder] {
+            router
+                .bank
+                .init_balance(storage, &Addr::unchecked(addr), coins(1000, denom))
+                .unwrap();
+        }
+    });
+
+    // Only the admin can configure subsidies
+    let subsidy_config = SubsidyConfig {
+        funder: funder.to_owned(),
+        per_epoch: Uint128::new(100),
+    };
+    let err = converter
+        .set_subsidy_config(Some(subsidy_config.clone()))
+        .call(owner)
+        .unwrap_err();
+    assert_eq!(err, Unauthorized {});
+    converter
+        .set_subsidy_config(Some(subsidy_config.clone()))
+        .call(admin)
+        .unwrap();
+
+    // The funder and the admin can top up the budget, but not anyone else
+    let err = converter
+        .top_up_subsidy()
+        .with_funds(&coins(300, denom))
+        .call(owner)
+        .unwrap_err();
+    assert_eq!(err, Unauthorized {});
+    converter
+        .top_up_subsidy()
+        .with_funds(&coins(300, denom))
+        .call(funder)
+        .unwrap();
+    converter
+        .top_up_subsidy()
+        .with_funds(&coins(50, denom))
+        .call(admin)
+        .unwrap();
+    assert_eq!(
+        converter.subsidy().unwrap(),
+        SubsidyResponse {
+            config: Some(subsidy_config),
+            budget: Uint128::new(350),
+        }
+    );
+
+    // Disabling subsidies keeps the budget, and the funder can't top it up anymore
+    converter.set_subsidy_config(None).call(admin).unwrap();
+    assert_eq!(
+        converter.subsidy().unwrap(),
+        SubsidyResponse {
+            config: None,
+            budget: Uint128::new(350),
+        }
+    );
+    let err = converter
+        .top_up_subsidy()
+        .with_funds(&coins(300, denom))
+        .call(funder)
+        .unwrap_err();
+    assert_eq!(err, Unauthorized {});
+}
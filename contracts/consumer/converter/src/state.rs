@@ -1,5 +1,7 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Decimal};
+use cosmwasm_std::{Addr, Decimal, Timestamp, Uint128};
+
+use crate::error::ContractError;
 
 #[cw_serde]
 pub struct Config {
@@ -19,4 +21,141 @@ pub struct Config {
     /// Token being "virtually sent" over IBC.
     /// use remote via, eg "uosmo", not "ibc/4EF183..."
     pub remote_denom: String,
+
+    /// Admin allowed to trigger maintenance operations, like a full valset resync.
+    /// This is also the admin of the virtual staking contract.
+    #[serde(default)]
+    pub admin: Option<Addr>,
+
+    /// Provider the channel must be opened with. If not set, any provider is accepted.
+    #[serde(default)]
+    pub provider: Option<ProviderIdentity>,
+}
+
+/// Rewards paid to callers of the permissionless keeper tasks, out of the keeper pot
+#[cw_serde]
+pub struct KeeperConfig {
+    /// Reward per task run, in local denom. Capped by the funds left in the pot
+    pub reward: Uint128,
+    /// Minimum time (in seconds) between two runs of the same task
+    pub min_interval: u64,
+}
+
+/// Fixed subsidy paid to the provider on top of delegation rewards, out of the subsidy budget.
+/// For consumers paying providers a fixed amount (e.g. out of minting or a treasury) rather than
+/// a share of fees
+#[cw_serde]
+pub struct SubsidyConfig {
+    /// Account allowed to top up the budget besides the admin, e.g. the mint or treasury module
+    pub funder: String,
+    /// Subsidy paid every epoch, in local denom. Capped by the budget left
+    pub per_epoch: Uint128,
+}
+
+/// Collateral the provider reported in its last summary, bounding the stake it can convert
+#[cw_serde]
+pub struct CollateralBound {
+    /// Total collateral liened to the external staking contract in the provider vault,
+    /// in remote denom
+    pub reported: Uint128,
+    /// When the summary was received
+    pub reported_at: Timestamp,
+}
+
+/// Identity of the provider side of the channel
+#[cw_serde]
+pub struct ProviderIdentity {
+    /// Chain id of the provider, as announced in its handshake version
+    pub chain_id: String,
+    /// Connection to the provider chain, on this side
+    pub connection_id: String,
+    /// Port of the external staking contract on the provider chain
+    pub port_id: String,
+}
+
+impl ProviderIdentity {
+    pub fn validate(&self) -> Result<(), ContractError> {
+        if self.chain_id.is_empty() || self.connection_id.is_empty() || self.port_id.is_empty() {
+            return Err(ContractError::InvalidProviderIdentity(format!(
+                "{:?}",
+                self
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Provider endpoint authorized by the admin, besides the one set at instantiation
+#[cw_serde]
+pub struct ProviderEndpoint {
+    /// Chain id the provider must announce in its handshake version
+    pub chain_id: String,
+    /// Disabled endpoints can't open a channel, and packets from them are rejected
+    pub enabled: bool,
+}
+
+/// Counters for monitoring, exposed through the `metrics` query
+#[cw_serde]
+#[derive(Default)]
+pub struct Metrics {
+    /// Packets received from the provider, by type
+    pub packets_received: ProviderPacketCounters,
+    /// Packets sent to the provider, by type
+    pub packets_sent: ConsumerPacketCounters,
+    /// Packets sent to the provider that were acked with an error, or timed out
+    pub packets_failed: u64,
+    /// Time the price last used to convert amounts was observed by the price feed (or the time
+    /// it was used, for feeds not reporting it)
+    pub last_price_time: Option<Timestamp>,
+    /// Total stake bonded through the converter (in local denom), net of unbonds and burns
+    pub total_converted_stake: Uint128,
+    /// Height of the last epoch processed (rewards distribution by virtual staking)
+    pub last_epoch_height: Option<u64>,
+}
+
+/// Cumulative contributions of the provider on a channel, in local denom
+#[cw_serde]
+#[derive(Default)]
+pub struct ProviderContributions {
+    /// Stake received from the provider
+    pub stake_received: Uint128,
+    /// Stake unstaked by the provider
+    pub unstaked: Uint128,
+    /// Stake burned by the provider
+    pub burned: Uint128,
+    /// Slash amounts reported to the provider
+    pub slashed: Uint128,
+    /// Rewards paid to the provider
+    pub rewards_paid: Uint128,
+    /// Subsidies paid to the provider, on top of the rewards
+    #[serde(default)]
+    pub subsidies_paid: Uint128,
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct ProviderPacketCounters {
+    pub stake: u64,
+    pub unstake: u64,
+    pub burn: u64,
+    pub transfer_rewards: u64,
+    #[serde(default)]
+    pub collateral_summary: u64,
+    #[serde(default)]
+    pub compound_rewards: u64,
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct ConsumerPacketCounters {
+    pub valset_update: u64,
+    #[serde(default)]
+    pub valset_resync: u64,
+    pub internal_unstake: u64,
+    pub distribute: u64,
+    pub distribute_batch: u64,
+    #[serde(default)]
+    pub delegation_policy: u64,
+    #[serde(default)]
+    pub slashing_params: u64,
 }
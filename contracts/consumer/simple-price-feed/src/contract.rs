@@ -31,6 +31,12 @@ pub struct SimplePriceFeedContract<'a> {
     pub config: Item<'a, Config>,
 }
 
+impl Default for SimplePriceFeedContract<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[cfg_attr(not(feature = "library"), sylvia::entry_points)]
 #[contract]
 #[sv::error(ContractError)]
@@ -63,6 +69,7 @@ impl SimplePriceFeedContract<'_> {
         let config = Config {
             native_per_foreign,
             owner,
+            price_time: Some(ctx.env.block.time),
         };
         self.config.save(ctx.deps.storage, &config)?;
 
@@ -88,6 +95,7 @@ impl SimplePriceFeedContract<'_> {
         );
 
         config.native_per_foreign = native_per_foreign;
+        config.price_time = Some(ctx.env.block.time);
         self.config.save(ctx.deps.storage, &config)?;
         Ok(Response::new())
     }
@@ -116,6 +124,7 @@ impl PriceFeedApi for SimplePriceFeedContract<'_> {
         let config = self.config.load(ctx.deps.storage)?;
         Ok(PriceResponse {
             native_per_foreign: config.native_per_foreign,
+            time: config.price_time,
         })
     }
 
@@ -1,5 +1,5 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Decimal};
+use cosmwasm_std::{Addr, Decimal, Timestamp};
 
 #[cw_serde]
 pub struct Config {
@@ -8,4 +8,8 @@ pub struct Config {
 
     /// The current set price
     pub native_per_foreign: Decimal,
+
+    /// Time the price was set
+    #[serde(default)]
+    pub price_time: Option<Timestamp>,
 }
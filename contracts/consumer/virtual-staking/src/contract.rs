@@ -1,10 +1,10 @@
 use std::cmp::Ordering;
 use std::collections::{BTreeMap, HashMap, HashSet};
-use std::u32;
 
 use cosmwasm_std::{
-    coin, ensure_eq, to_json_binary, Coin, CosmosMsg, CustomQuery, DepsMut, DistributionMsg, Env,
-    Event, Reply, Response, StdResult, Storage, SubMsg, Uint128, Validator, WasmMsg,
+    coin, ensure, ensure_eq, to_json_binary, Addr, Coin, CosmosMsg, CustomQuery, Decimal, DepsMut,
+    DistributionMsg, Env, Event, Reply, Response, StdResult, Storage, SubMsg, Uint128, Validator,
+    WasmMsg,
 };
 use cw2::set_contract_version;
 use cw_storage_plus::{Item, Map};
@@ -19,8 +19,8 @@ use sylvia::{contract, schemars};
 use mesh_apis::virtual_staking_api::{self, ValidatorSlash, VirtualStakingApi};
 
 use crate::error::ContractError;
-use crate::msg::{AllStakeResponse, ConfigResponse, StakeResponse};
-use crate::state::Config;
+use crate::msg::{AllStakeResponse, ConfigResponse, RewardsRecipientResponse, StakeResponse};
+use crate::state::{Config, SlashingParams};
 
 pub const CONTRACT_NAME: &str = env!("CARGO_PKG_NAME");
 pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -49,6 +49,19 @@ pub struct VirtualStakingContract<'a> {
     /// This is just for accounting / tracking reasons, as token "burning" is being implemented as unbonding,
     /// and there's no real need to discount the burned amount in this contract.
     burned: Map<'a, &'a str, u128>,
+    /// Validators jailed while `jailed_unbond_enable` is set. Their stake is unbonded at the next
+    /// epoch, but their bond requests are kept, so it is bonded again once they are active.
+    pub jailed: Map<'a, &'a str, ()>,
+    /// Slashing params of the chain, as last sent to the converter
+    pub slashing_params: Item<'a, SlashingParams>,
+    /// Recipient of the forwarded rewards, set by the converter instead of itself
+    pub rewards_recipient: Item<'a, Addr>,
+}
+
+impl Default for VirtualStakingContract<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 #[cfg_attr(not(feature = "library"), sylvia::entry_points)]
@@ -67,6 +80,9 @@ impl VirtualStakingContract<'_> {
             slash_requests: Item::new("slashed"),
             inactive: Item::new("inactive"),
             burned: Map::new("burned"),
+            jailed: Map::new("jailed"),
+            slashing_params: Item::new("slashing_params"),
+            rewards_recipient: Item::new("rewards_recipient"),
         }
     }
 
@@ -77,6 +93,7 @@ impl VirtualStakingContract<'_> {
         ctx: InstantiateCtx<VirtualStakeCustomQuery>,
         max_retrieve: u32,
         tombstoned_unbond_enable: bool,
+        #[serde(default)] jailed_unbond_enable: bool,
     ) -> Result<Response<VirtualStakeCustomMsg>, ContractError> {
         nonpayable(&ctx.info)?;
         let denom = ctx.deps.querier.query_bonded_denom()?;
@@ -85,6 +102,7 @@ impl VirtualStakingContract<'_> {
             converter: ctx.info.sender,
             max_retrieve,
             tombstoned_unbond_enable,
+            jailed_unbond_enable,
         };
         self.config.save(ctx.deps.storage, &config)?;
         // initialize these to no one, so no issue when reading for the first time
@@ -105,33 +123,204 @@ impl VirtualStakingContract<'_> {
         Ok(self.config.load(ctx.deps.storage)?.into())
     }
 
+    /// Returns the address the rewards are forwarded to at every epoch
+    #[sv::msg(query)]
+    fn rewards_recipient(
+        &self,
+        ctx: QueryCtx<VirtualStakeCustomQuery>,
+    ) -> Result<RewardsRecipientResponse, ContractError> {
+        let resp = match self.rewards_recipient.may_load(ctx.deps.storage)? {
+            Some(recipient) => RewardsRecipientResponse {
+                recipient: recipient.into_string(),
+                overridden: true,
+            },
+            None => RewardsRecipientResponse {
+                recipient: self.config.load(ctx.deps.storage)?.converter.into_string(),
+                overridden: false,
+            },
+        };
+        Ok(resp)
+    }
+
+    /// Adds `amount` to the bond request of `validator`
+    fn add_bond_request(
+        &self,
+        storage: &mut dyn Storage,
+        validator: &str,
+        amount: Uint128,
+    ) -> StdResult<()> {
+        let request = self
+            .bond_requests
+            .may_load(storage, validator)?
+            .unwrap_or_default();
+        self.bond_requests
+            .save(storage, validator, &(request + amount))
+    }
+
+    /// Subtracts `amount` from the bond request of `validator`, saturating at zero.
+    ///
+    /// Returns the amount actually subtracted, and a `bond_underflow` event if the request was
+    /// lower than `amount` (e.g. because of a slash racing with an unbond)
+    fn sub_bond_request(
+        &self,
+        storage: &mut dyn Storage,
+        validator: &str,
+        amount: Uint128,
+        source: &str,
+    ) -> StdResult<(Uint128, Option<Event>)> {
+        let request = self
+            .bond_requests
+            .may_load(storage, validator)?
+            .unwrap_or_default();
+        let (new_request, underflow) =
+            saturating_sub_reported(validator, "bond_request", source, request, amount);
+        self.bond_requests.save(storage, validator, &new_request)?;
+        Ok((request - new_request, underflow))
+    }
+
+    /// Aligns the bonded amounts with the actual delegations of this contract, in case a slash
+    /// was not reported. The missing amount is deducted from the bond requests as well, the
+    /// same way reported slashes are, so the next epoch doesn't try to unbond more than is
+    /// delegated.
+    /// Anyone can call this, as it only follows what the chain reports.
+    #[sv::msg(exec)]
+    fn reconcile_bonded(
+        &self,
+        ctx: ExecCtx<VirtualStakeCustomQuery>,
+    ) -> Result<Response<VirtualStakeCustomMsg>, ContractError> {
+        nonpayable(&ctx.info)?;
+        let ExecCtx { deps, env, .. } = ctx;
+
+        let mut bonded = self.bonded.load(deps.storage)?;
+        let mut events = vec![];
+        for (validator, amount) in bonded.iter_mut() {
+            let actual = TokenQuerier::new(&deps.querier)
+                .total_delegations(env.contract.address.to_string(), validator.clone())?
+                .delegation
+                .amount;
+            if actual >= *amount {
+                continue;
+            }
+            let missing = *amount - actual;
+            let (_, underflow) =
+                self.sub_bond_request(deps.storage, validator, missing, "reconcile")?;
+            events.push(
+                Event::new("bonded_reconciled")
+                    .add_attribute("validator", validator.as_str())
+                    .add_attribute("pre_bonded", *amount)
+                    .add_attribute("post_bonded", actual)
+                    .add_attribute("missing", missing),
+            );
+            events.extend(underflow);
+            *amount = actual;
+        }
+        self.bonded.save(deps.storage, &bonded)?;
+
+        Ok(Response::new()
+            .add_events(events)
+            .add_attribute("action", "reconcile_bonded"))
+    }
+
+    /// Sends the slashing params of the chain to the converter, to be forwarded to the provider,
+    /// if they changed since they were last sent. They can change through governance at any
+    /// time, so this is meant to be called periodically.
+    /// Anyone can call this, as it only follows what the chain reports.
+    #[sv::msg(exec)]
+    fn sync_slashing_params(
+        &self,
+        ctx: ExecCtx<VirtualStakeCustomQuery>,
+    ) -> Result<Response<VirtualStakeCustomMsg>, ContractError> {
+        nonpayable(&ctx.info)?;
+        let ExecCtx { deps, .. } = ctx;
+
+        let ratio = TokenQuerier::new(&deps.querier).slash_ratio()?;
+        let params = SlashingParams {
+            double_sign: ratio.slash_fraction_double_sign.parse::<Decimal>()?,
+            offline: ratio.slash_fraction_downtime.parse::<Decimal>()?,
+        };
+        ensure!(
+            self.slashing_params.may_load(deps.storage)?.as_ref() != Some(&params),
+            ContractError::SlashingParamsUnchanged
+        );
+        self.slashing_params.save(deps.storage, &params)?;
+
+        let cfg = self.config.load(deps.storage)?;
+        let msg = converter_api::sv::ExecMsg::SlashingParamsUpdate {
+            double_sign: params.double_sign,
+            offline: params.offline,
+        };
+        let msg = WasmMsg::Execute {
+            contract_addr: cfg.converter.to_string(),
+            msg: to_json_binary(&msg)?,
+            funds: vec![],
+        };
+
+        Ok(Response::new()
+            .add_message(msg)
+            .add_attribute("action", "sync_slashing_params")
+            .add_attribute("double_sign", params.double_sign.to_string())
+            .add_attribute("offline", params.offline.to_string()))
+    }
+
+    /// Applies the pending slashes to the bonded amounts and bond requests.
+    ///
+    /// Returns a `slash_applied` event per affected validator, with the amounts before and after
+    /// the slash and the slash request they originate from
     fn adjust_slashings(
         &self,
         deps: DepsMut<VirtualStakeCustomQuery>,
         current: &mut [(String, Uint128)],
         slash: &[ValidatorSlash],
-    ) -> StdResult<()> {
+    ) -> StdResult<Vec<Event>> {
         let slashes: HashMap<String, ValidatorSlash> =
             HashMap::from_iter(slash.iter().map(|s| (s.address.clone(), s.clone())));
 
+        let mut events = vec![];
         // this is linear over current, but better than turn it in to a map
         for (validator, prev) in current {
             match slashes.get(validator) {
                 None => continue,
                 Some(s) => {
+                    let pre_bonded = *prev;
                     // Just deduct the slash amount passed by the chain
-                    *prev -= s.slash_amount;
+                    let (post_bonded, underflow) = saturating_sub_reported(
+                        validator,
+                        "bonded",
+                        "slash",
+                        pre_bonded,
+                        s.slash_amount,
+                    );
+                    *prev = post_bonded;
+                    events.extend(underflow);
                     // Apply to request as well (to avoid unbonding msg)
-                    let mut request = self
+                    let pre_request = self
                         .bond_requests
                         .may_load(deps.storage, validator)?
                         .unwrap_or_default();
-                    request = request.saturating_sub(s.slash_amount);
-                    self.bond_requests.save(deps.storage, validator, &request)?;
+                    let (deducted, underflow) =
+                        self.sub_bond_request(deps.storage, validator, s.slash_amount, "slash")?;
+                    let request = pre_request - deducted;
+                    events.extend(underflow);
+
+                    events.push(
+                        Event::new("slash_applied")
+                            .add_attribute("validator", validator.as_str())
+                            .add_attribute("pre_bonded", pre_bonded)
+                            .add_attribute("post_bonded", *prev)
+                            .add_attribute("pre_request", pre_request)
+                            .add_attribute("post_request", request)
+                            .add_attribute("slash_amount", s.slash_amount)
+                            .add_attribute("slash_ratio", &s.slash_ratio)
+                            .add_attribute("slash_height", s.height.to_string())
+                            .add_attribute("slash_time", s.time.to_string())
+                            .add_attribute("infraction_height", s.infraction_height.to_string())
+                            .add_attribute("infraction_time", s.infraction_time.to_string())
+                            .add_attribute("is_tombstoned", s.is_tombstoned.to_string()),
+                    );
                 }
             }
         }
-        Ok(())
+        Ok(events)
     }
 
     #[sv::msg(reply)]
@@ -206,11 +395,15 @@ impl VirtualStakingContract<'_> {
             let all_rewards = all_rewards(deps.storage)?;
             BATCH.wipe(deps.storage)?;
 
+            let recipient = self
+                .rewards_recipient
+                .may_load(deps.storage)?
+                .unwrap_or(cfg.converter);
             let msg = converter_api::sv::ExecMsg::DistributeRewards {
                 payments: all_rewards,
             };
             let msg = WasmMsg::Execute {
-                contract_addr: cfg.converter.into_string(),
+                contract_addr: recipient.into_string(),
                 msg: to_json_binary(&msg)?,
                 funds: vec![coin(total.into(), cfg.denom)],
             };
@@ -294,6 +487,33 @@ fn pop_target(deps: DepsMut<VirtualStakeCustomQuery>) -> StdResult<(String, bool
     Ok((target, targets.is_empty()))
 }
 
+/// Subtracts `amount` from `value`, saturating at zero.
+///
+/// Slashes can race with unbonds and burns, so bonded amounts and requests can be lower than
+/// what is subtracted from them. Instead of failing (and blocking epoch processing), the
+/// shortfall is reported with a `bond_underflow` event.
+fn saturating_sub_reported(
+    validator: &str,
+    field: &str,
+    source: &str,
+    value: Uint128,
+    amount: Uint128,
+) -> (Uint128, Option<Event>) {
+    match value.checked_sub(amount) {
+        Ok(result) => (result, None),
+        Err(_) => {
+            let evt = Event::new("bond_underflow")
+                .add_attribute("validator", validator)
+                .add_attribute("field", field)
+                .add_attribute("source", source)
+                .add_attribute("value", value)
+                .add_attribute("amount", amount)
+                .add_attribute("shortfall", amount - value);
+            (Uint128::zero(), Some(evt))
+        }
+    }
+}
+
 fn calculate_rebalance(
     current: Vec<(String, Uint128)>,
     desired: Vec<(String, Uint128)>,
@@ -444,13 +664,7 @@ impl VirtualStakingApi for VirtualStakingContract<'_> {
         );
 
         // Update the amount requested
-        let mut bonded = self
-            .bond_requests
-            .may_load(ctx.deps.storage, &validator)?
-            .unwrap_or_default();
-        bonded += amount.amount;
-        self.bond_requests
-            .save(ctx.deps.storage, &validator, &bonded)?;
+        self.add_bond_request(ctx.deps.storage, &validator, amount.amount)?;
 
         let msg = VirtualStakeMsg::UpdateDelegation {
             amount,
@@ -488,13 +702,14 @@ impl VirtualStakingApi for VirtualStakingContract<'_> {
             ContractError::WrongDenom(cfg.denom)
         );
 
-        // Update the amount requested
-        let bonded = self.bond_requests.load(ctx.deps.storage, &validator)?;
-        let bonded = bonded
-            .checked_sub(amount.amount)
-            .map_err(|_| ContractError::InsufficientBond(validator.clone(), amount.amount))?;
-        self.bond_requests
-            .save(ctx.deps.storage, &validator, &bonded)?;
+        // Update the amount requested. It may have been lowered by a slash in the meantime
+        ensure!(
+            self.bond_requests.has(ctx.deps.storage, &validator),
+            ContractError::InsufficientBond(validator, amount.amount)
+        );
+        let (_, underflow) =
+            self.sub_bond_request(ctx.deps.storage, &validator, amount.amount, "unbond")?;
+        let resp = Response::new().add_events(underflow);
 
         let msg = VirtualStakeMsg::UpdateDelegation {
             amount,
@@ -505,11 +720,11 @@ impl VirtualStakingApi for VirtualStakingContract<'_> {
         #[cfg(any(test, feature = "mt"))]
         {
             let _ = msg;
-            Ok(Response::new())
+            Ok(resp)
         }
         #[cfg(not(any(test, feature = "mt")))]
         {
-            Ok(Response::new().add_message(msg))
+            Ok(resp.add_message(msg))
         }
     }
 
@@ -552,12 +767,16 @@ impl VirtualStakingApi for VirtualStakingContract<'_> {
 
         let (burned, burns) = mesh_burn::distribute_burn(bonds.as_slice(), amount.amount.u128());
 
+        let mut events = vec![];
         for (validator, burn_amount) in burns {
             // Update bond requests
-            self.bond_requests
-                .update::<_, ContractError>(ctx.deps.storage, validator, |old| {
-                    Ok(old.unwrap_or_default() - Uint128::new(burn_amount))
-                })?;
+            let (_, underflow) = self.sub_bond_request(
+                ctx.deps.storage,
+                validator,
+                Uint128::new(burn_amount),
+                "burn",
+            )?;
+            events.extend(underflow);
             // Accounting trick to avoid burning stake
             self.burned.update(ctx.deps.storage, validator, |old| {
                 Ok::<_, ContractError>(old.unwrap_or_default() + burn_amount)
@@ -572,7 +791,7 @@ impl VirtualStakingApi for VirtualStakingContract<'_> {
             ));
         }
 
-        Ok(Response::new())
+        Ok(Response::new().add_events(events))
     }
 
     /// Immediately unbond the given amount due to zero max cap
@@ -592,13 +811,17 @@ impl VirtualStakingApi for VirtualStakingContract<'_> {
             ContractError::WrongDenom(cfg.denom)
         );
 
-        // Immediately unbond
-        let bonded = self.bond_requests.load(ctx.deps.storage, &validator)?;
-        let bonded = bonded
-            .checked_sub(amount.amount)
-            .map_err(|_| ContractError::InsufficientBond(validator.clone(), amount.amount))?;
-        self.bond_requests
-            .save(ctx.deps.storage, &validator, &bonded)?;
+        // Immediately unbond. Only what is left after slashes can be unbonded
+        ensure!(
+            self.bond_requests.has(ctx.deps.storage, &validator),
+            ContractError::InsufficientBond(validator, amount.amount)
+        );
+        let (unbonded, underflow) = self.sub_bond_request(
+            ctx.deps.storage,
+            &validator,
+            amount.amount,
+            "internal_unbond",
+        )?;
 
         let requests: Vec<(String, Uint128)> = self
             .bond_requests
@@ -618,9 +841,12 @@ impl VirtualStakingApi for VirtualStakingContract<'_> {
                 delegator,
                 validator: validator.clone(),
             },
-            VirtualStakeMsg::Unbond { amount, validator },
+            VirtualStakeMsg::Unbond {
+                amount: coin(unbonded.u128(), amount.denom),
+                validator,
+            },
         ];
-        Ok(Response::new().add_messages(msgs))
+        Ok(Response::new().add_messages(msgs).add_events(underflow))
     }
 
     fn handle_close_channel(
@@ -669,6 +895,33 @@ impl VirtualStakingApi for VirtualStakingContract<'_> {
         Ok(Response::new().add_messages(msgs))
     }
 
+    fn set_rewards_recipient(
+        &self,
+        ctx: ExecCtx<Self::QueryC>,
+        recipient: Option<String>,
+    ) -> Result<Response<VirtualStakeCustomMsg>, Self::Error> {
+        nonpayable(&ctx.info)?;
+        let cfg = self.config.load(ctx.deps.storage)?;
+        // only the converter can call this
+        ensure_eq!(ctx.info.sender, cfg.converter, ContractError::Unauthorized);
+
+        let recipient = match recipient {
+            Some(recipient) => {
+                let recipient = ctx.deps.api.addr_validate(&recipient)?;
+                self.rewards_recipient.save(ctx.deps.storage, &recipient)?;
+                recipient
+            }
+            None => {
+                self.rewards_recipient.remove(ctx.deps.storage);
+                cfg.converter
+            }
+        };
+
+        Ok(Response::new()
+            .add_attribute("action", "set_rewards_recipient")
+            .add_attribute("recipient", recipient))
+    }
+
     // FIXME: need to handle custom message types and queries
     /**
      * This is called once per epoch to withdraw all rewards and rebalance the bonded tokens.
@@ -680,8 +933,8 @@ impl VirtualStakingApi for VirtualStakingContract<'_> {
      * 2. Sum the total amount
      * 3. If the sum <= max_cap then use collected requests as is
      * 4. If the sum > max_cap,
-     *   a. calculate multiplier Decimal(max_cap / sum)
-     *   b. multiply every element of the collected requests in place.
+     *    a. calculate multiplier Decimal(max_cap / sum)
+     *    b. multiply every element of the collected requests in place.
      * 5. Find diff between collected (normalized) requests and last bonding amounts (which go up, which down).
      * 6. Transform diff into unbond and bond requests, sorting so all unbond happen first
      */
@@ -744,7 +997,8 @@ impl VirtualStakingApi for VirtualStakingContract<'_> {
         // Process slashes due to tombstoning (unbonded) or jailing, over bond_requests and current
         let slash = self.slash_requests.load(deps.storage)?;
         if !slash.is_empty() {
-            self.adjust_slashings(deps.branch(), &mut current, &slash)?;
+            let events = self.adjust_slashings(deps.branch(), &mut current, &slash)?;
+            resp = resp.add_events(events);
             // Update inactive list. Defensive, as it should already been updated in handle_valset_update, due to removals
             self.inactive.update(deps.branch().storage, |mut old| {
                 old.extend_from_slice(
@@ -776,6 +1030,12 @@ impl VirtualStakingApi for VirtualStakingContract<'_> {
                 *v = (*v * max_cap) / total_requested;
             }
         }
+        // Unbond from jailed validators, keeping their bond requests
+        for (val, amount) in requests.iter_mut() {
+            if self.jailed.has(deps.storage, val) {
+                *amount = Uint128::zero();
+            }
+        }
 
         // Force the tombstoned validator to auto unbond
         let mut tombstoned_list: HashMap<String, Coin> = HashMap::new();
@@ -865,8 +1125,18 @@ impl VirtualStakingApi for VirtualStakingContract<'_> {
                 Ok::<_, ContractError>(old)
             })?;
         }
-        // Send all updates to the converter.
+        // Track jailed validators to unbond from, until they are active again.
+        // Tombstoned ones are unbonded for good instead.
         let cfg = self.config.load(deps.storage)?;
+        if cfg.jailed_unbond_enable {
+            for val in jailed.iter().filter(|val| !tombstoned.contains(val)) {
+                self.jailed.save(deps.storage, val, &())?;
+            }
+        }
+        for val in additions.iter().map(|a| &a.address).chain(tombstoned) {
+            self.jailed.remove(deps.storage, val);
+        }
+        // Send all updates to the converter.
         let msg = converter_api::sv::ExecMsg::ValsetUpdate {
             additions: additions.to_vec(),
             removals: removals.to_vec(),
@@ -1165,6 +1435,56 @@ mod tests {
             .assert_rewards(&["val1", "val2"]);
     }
 
+    #[test]
+    fn validator_jail_unbond() {
+        let (mut deps, knobs) = mock_dependencies();
+
+        let contract = VirtualStakingContract::new();
+        contract.quick_inst(deps.as_mut());
+        contract
+            .config
+            .update(deps.as_mut().storage, |mut config| {
+                config.jailed_unbond_enable = true;
+                Ok::<_, ContractError>(config)
+            })
+            .unwrap();
+        let denom = contract.config.load(&deps.storage).unwrap().denom;
+
+        knobs.bond_status.update_cap(100u128);
+        contract.quick_bond(deps.as_mut(), "owner", "val1", 10);
+        contract.quick_bond(deps.as_mut(), "owner", "val2", 20);
+        contract
+            .hit_epoch(deps.as_mut())
+            .assert_bond(&[("val1", (10u128, &denom)), ("val2", (20u128, &denom))])
+            .assert_rewards(&[]);
+
+        // val1 is jailed and slashed. Its remaining stake is unbonded at the next epoch
+        contract.jail(deps.as_mut(), "val1", Decimal::percent(10), Uint128::one());
+        contract
+            .hit_epoch(deps.as_mut())
+            .assert_bond(&[])
+            .assert_unbond(&[("val1", (9u128, &denom))])
+            .assert_rewards(&["val2"]);
+        contract
+            .hit_epoch(deps.as_mut())
+            .assert_bond(&[])
+            .assert_unbond(&[]);
+
+        // Its bond request is kept, and bonded again once it is active
+        assert_eq!(
+            contract
+                .bond_requests
+                .load(deps.as_ref().storage, "val1")
+                .unwrap(),
+            Uint128::new(9)
+        );
+        contract.add_val(deps.as_mut(), "val1");
+        contract
+            .hit_epoch(deps.as_mut())
+            .assert_bond(&[("val1", (9u128, &denom))])
+            .assert_unbond(&[]);
+    }
+
     #[test]
     fn validator_jail_pending_bond() {
         let (mut deps, knobs) = mock_dependencies();
@@ -1244,6 +1564,242 @@ mod tests {
         assert_eq!(bonded, [("val1".to_string(), Uint128::new(0)),]);
     }
 
+    #[test]
+    fn validator_jail_slash_events() {
+        let (mut deps, knobs) = mock_dependencies();
+
+        let contract = VirtualStakingContract::new();
+        contract.quick_inst(deps.as_mut());
+        let denom = contract.config.load(&deps.storage).unwrap().denom;
+
+        knobs.bond_status.update_cap(100u128);
+        contract.quick_bond(deps.as_mut(), "owner", "val1", 10);
+        contract.quick_bond(deps.as_mut(), "owner", "val2", 20);
+        contract
+            .hit_epoch(deps.as_mut())
+            .assert_bond(&[("val1", (10u128, &denom)), ("val2", (20u128, &denom))]);
+
+        contract.jail(deps.as_mut(), "val1", Decimal::percent(10), Uint128::one());
+
+        let resp = contract
+            .handle_epoch(SudoCtx {
+                deps: deps.as_mut(),
+                env: mock_env(),
+            })
+            .unwrap();
+        // Only the slashed validator is reported
+        let events = resp
+            .events
+            .iter()
+            .filter(|evt| evt.ty == "slash_applied")
+            .collect::<Vec<_>>();
+        assert_eq!(events.len(), 1);
+        let attr = |key: &str| {
+            events[0]
+                .attributes
+                .iter()
+                .find(|attr| attr.key == key)
+                .map(|attr| attr.value.as_str())
+        };
+        assert_eq!(attr("validator"), Some("val1"));
+        assert_eq!(attr("pre_bonded"), Some("10"));
+        assert_eq!(attr("post_bonded"), Some("9"));
+        assert_eq!(attr("pre_request"), Some("10"));
+        assert_eq!(attr("post_request"), Some("9"));
+        assert_eq!(attr("slash_amount"), Some("1"));
+        assert_eq!(attr("is_tombstoned"), Some("false"));
+
+        // Slashes are applied only once
+        let resp = contract
+            .handle_epoch(SudoCtx {
+                deps: deps.as_mut(),
+                env: mock_env(),
+            })
+            .unwrap();
+        assert!(resp.events.iter().all(|evt| evt.ty != "slash_applied"));
+    }
+
+    #[test]
+    fn bond_underflows_are_reported() {
+        let (mut deps, knobs) = mock_dependencies();
+
+        let contract = VirtualStakingContract::new();
+        contract.quick_inst(deps.as_mut());
+        let denom = contract.config.load(&deps.storage).unwrap().denom;
+
+        knobs.bond_status.update_cap(100u128);
+        contract.quick_bond(deps.as_mut(), "owner", "val1", 10);
+        contract.quick_bond(deps.as_mut(), "owner", "val2", 10);
+        contract
+            .hit_epoch(deps.as_mut())
+            .assert_bond(&[("val1", (10u128, &denom)), ("val2", (10u128, &denom))]);
+
+        // Val1 is slashed
+        contract.jail(deps.as_mut(), "val1", Decimal::percent(10), Uint128::one());
+        contract.hit_epoch(deps.as_mut());
+
+        // An unbond of the pre-slash amount races with the slash. It saturates
+        let resp = contract
+            .unbond(
+                ExecCtx {
+                    deps: deps.as_mut(),
+                    env: mock_env(),
+                    info: mock_info("me", &[]),
+                },
+                "owner".to_string(),
+                "val1".to_string(),
+                coin(10, &denom),
+            )
+            .unwrap();
+        let underflow = resp
+            .events
+            .iter()
+            .find(|evt| evt.ty == "bond_underflow")
+            .unwrap();
+        let attr = |key: &str| {
+            underflow
+                .attributes
+                .iter()
+                .find(|attr| attr.key == key)
+                .map(|attr| attr.value.as_str())
+        };
+        assert_eq!(attr("validator"), Some("val1"));
+        assert_eq!(attr("field"), Some("bond_request"));
+        assert_eq!(attr("source"), Some("unbond"));
+        assert_eq!(attr("shortfall"), Some("1"));
+        assert_eq!(
+            contract.bond_requests.load(&deps.storage, "val1").unwrap(),
+            Uint128::zero()
+        );
+
+        // A slash over the bonded amount doesn't fail the epoch either
+        contract.jail(
+            deps.as_mut(),
+            "val2",
+            Decimal::percent(10),
+            Uint128::new(15),
+        );
+        let resp = contract
+            .handle_epoch(SudoCtx {
+                deps: deps.as_mut(),
+                env: mock_env(),
+            })
+            .unwrap();
+        let underflows = resp
+            .events
+            .iter()
+            .filter(|evt| evt.ty == "bond_underflow")
+            .count();
+        assert_eq!(underflows, 2);
+        let bonded = contract.bonded.load(deps.as_ref().storage).unwrap();
+        assert_eq!(
+            bonded,
+            [
+                ("val1".to_string(), Uint128::zero()),
+                ("val2".to_string(), Uint128::zero())
+            ]
+        );
+    }
+
+    #[test]
+    fn reconciling_bonded() {
+        let (mut deps, knobs) = mock_dependencies();
+
+        let contract = VirtualStakingContract::new();
+        contract.quick_inst(deps.as_mut());
+        let denom = contract.config.load(&deps.storage).unwrap().denom;
+
+        knobs.bond_status.update_cap(100u128);
+        contract.quick_bond(deps.as_mut(), "owner", "val1", 20);
+        contract
+            .hit_epoch(deps.as_mut())
+            .assert_bond(&[("val1", (20u128, &denom))]);
+
+        // Val1 is slashed without it being reported
+        knobs
+            .total_delegation
+            .update_total_delegation(15u128, &denom);
+        let reconcile = |deps: DepsMut| {
+            contract
+                .reconcile_bonded(ExecCtx {
+                    deps,
+                    env: mock_env(),
+                    info: mock_info("anyone", &[]),
+                })
+                .unwrap()
+        };
+        let resp = reconcile(deps.as_mut());
+        assert_eq!(
+            resp.events
+                .iter()
+                .filter(|evt| evt.ty == "bonded_reconciled")
+                .count(),
+            1
+        );
+        let bonded = contract.bonded.load(deps.as_ref().storage).unwrap();
+        assert_eq!(bonded, [("val1".to_string(), Uint128::new(15))]);
+        assert_eq!(
+            contract.bond_requests.load(&deps.storage, "val1").unwrap(),
+            Uint128::new(15)
+        );
+
+        // Nothing else to reconcile
+        let resp = reconcile(deps.as_mut());
+        assert!(resp.events.is_empty());
+
+        // The next epoch doesn't unbond what is no longer delegated
+        contract
+            .hit_epoch(deps.as_mut())
+            .assert_bond(&[])
+            .assert_unbond(&[]);
+    }
+
+    #[test]
+    fn syncing_slashing_params() {
+        let (mut deps, knobs) = mock_dependencies();
+
+        let contract = VirtualStakingContract::new();
+        contract.quick_inst(deps.as_mut());
+
+        let sync = |deps: DepsMut| {
+            contract.sync_slashing_params(ExecCtx {
+                deps,
+                env: mock_env(),
+                info: mock_info("anyone", &[]),
+            })
+        };
+        let sent_params = |resp: Response<VirtualStakeCustomMsg>| {
+            assert_eq!(resp.messages.len(), 1);
+            let CosmosMsg::Wasm(WasmMsg::Execute { msg, .. }) = &resp.messages[0].msg else {
+                panic!("unexpected message: {:?}", resp.messages[0].msg);
+            };
+            match from_json(msg).unwrap() {
+                converter_api::sv::ExecMsg::SlashingParamsUpdate {
+                    double_sign,
+                    offline,
+                } => (double_sign, offline),
+                msg => panic!("unexpected converter message: {:?}", msg),
+            }
+        };
+
+        let resp = sync(deps.as_mut()).unwrap();
+        assert_eq!(
+            sent_params(resp),
+            (Decimal::percent(25), Decimal::percent(10))
+        );
+
+        // Params are only sent again once they change
+        let err = sync(deps.as_mut()).unwrap_err();
+        assert!(matches!(err, ContractError::SlashingParamsUnchanged));
+
+        knobs.slash_ratio.update_downtime("0.05");
+        let resp = sync(deps.as_mut()).unwrap();
+        assert_eq!(
+            sent_params(resp),
+            (Decimal::percent(25), Decimal::percent(5))
+        );
+    }
+
     #[test]
     fn validator_remove() {
         let (mut deps, knobs) = mock_dependencies();
@@ -1474,6 +2030,52 @@ mod tests {
             .assert_eq(&[("val1", 20), ("val2", 10)]);
     }
 
+    #[test]
+    fn reply_rewards_to_recipient() {
+        let (mut deps, _) = mock_dependencies();
+        let contract = VirtualStakingContract::new();
+        contract.quick_inst(deps.as_mut());
+        let set_recipient = |deps: DepsMut, sender: &str, recipient: Option<&str>| {
+            contract.set_rewards_recipient(
+                ExecCtx {
+                    deps,
+                    env: mock_env(),
+                    info: mock_info(sender, &[]),
+                },
+                recipient.map(str::to_owned),
+            )
+        };
+
+        // Only the converter can set the recipient
+        let err = set_recipient(deps.as_mut(), "someone", Some("escrow")).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized));
+
+        set_recipient(deps.as_mut(), "me", Some("escrow")).unwrap();
+        set_reward_targets(&mut deps.storage, &["val1"]);
+        let denom = contract.config.load(&deps.storage).unwrap().denom;
+        deps.querier =
+            MockQuerier::new(&[(mock_env().contract.address.as_str(), &coins(10, &denom))]);
+        let resp = contract.reply_rewards(deps.as_mut(), mock_env()).unwrap();
+        match &resp.messages[..] {
+            [SubMsg {
+                msg: CosmosMsg::Wasm(WasmMsg::Execute { contract_addr, .. }),
+                ..
+            }] => assert_eq!(contract_addr, "escrow"),
+            _ => panic!("invalid response"),
+        }
+
+        // Removing the override sends the rewards to the converter again
+        set_recipient(deps.as_mut(), "me", None).unwrap();
+        let recipient = contract
+            .rewards_recipient(QueryCtx {
+                deps: deps.as_ref(),
+                env: mock_env(),
+            })
+            .unwrap();
+        assert_eq!(recipient.recipient, "me");
+        assert!(!recipient.overridden);
+    }
+
     fn mock_dependencies() -> (OwnedDeps, StakingKnobs) {
         let bond_status = MockBondStatus::new(BondStatusResponse {
             cap: coin(0, "DOES NOT MATTER"),
@@ -1490,6 +2092,7 @@ mod tests {
             delegations: vec![],
         });
 
+        let sr_copy = slash_ratio.clone();
         let handler = {
             let bs_copy = bond_status.clone();
             let td_copy = total_delegation.clone();
@@ -1529,6 +2132,7 @@ mod tests {
             },
             StakingKnobs {
                 bond_status,
+                slash_ratio: sr_copy,
                 total_delegation,
             },
         )
@@ -1536,6 +2140,7 @@ mod tests {
 
     struct StakingKnobs {
         bond_status: MockBondStatus,
+        slash_ratio: MockSlashRatio,
         total_delegation: MockTotalDelegation,
     }
 
@@ -1568,6 +2173,11 @@ mod tests {
         fn borrow(&self) -> Ref<'_, SlashRatioResponse> {
             self.0.borrow()
         }
+
+        fn update_downtime(&self, ratio: impl Into<String>) {
+            let mut mut_obj = self.0.borrow_mut();
+            mut_obj.slash_fraction_downtime = ratio.into();
+        }
     }
 
     #[derive(Clone)]
@@ -1653,6 +2263,7 @@ mod tests {
                 },
                 50,
                 true,
+                false,
             )
             .unwrap();
         }
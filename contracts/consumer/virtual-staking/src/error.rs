@@ -27,4 +27,7 @@ pub enum ContractError {
 
     #[error("Virtual staking {0} has not enough delegated funds: {1}")]
     InsufficientDelegations(String, Uint128),
+
+    #[error("Slashing params didn't change since they were last sent")]
+    SlashingParamsUnchanged,
 }
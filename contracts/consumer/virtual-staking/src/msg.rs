@@ -12,6 +12,14 @@ pub struct ConfigResponse {
     pub converter: String,
 }
 
+#[cw_serde]
+pub struct RewardsRecipientResponse {
+    /// The address the rewards are forwarded to
+    pub recipient: String,
+    /// Whether the recipient was set instead of the converter
+    pub overridden: bool,
+}
+
 #[cw_serde]
 pub struct StakeResponse {
     pub stake: Uint128,
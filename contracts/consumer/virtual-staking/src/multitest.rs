@@ -7,6 +7,7 @@ use mesh_converter::contract::sv::mt::ConverterContractProxy;
 
 use crate::contract;
 use crate::contract::sv::mt::VirtualStakingContractProxy;
+use crate::error::ContractError;
 
 const JUNO: &str = "ujuno";
 
@@ -60,8 +61,10 @@ fn setup<'a>(app: &'a App, args: SetupArgs<'a>) -> SetupResponse<'a> {
             JUNO.to_owned(),
             virtual_staking_code.code_id(),
             true,
+            false,
             Some(admin.to_owned()),
             50,
+            None,
         )
         .with_label("Juno Converter")
         .with_admin(admin)
@@ -176,3 +179,55 @@ fn valset_update_sudo() {
     println!("res: {:?}", res);
     res.unwrap();
 }
+
+#[test]
+fn rewards_recipient_override() {
+    let app = new_app();
+
+    let owner = "sunny";
+    let admin = "theman";
+
+    let SetupResponse {
+        price_feed: _,
+        converter,
+        virtual_staking,
+    } = setup(
+        &app,
+        SetupArgs {
+            owner,
+            admin,
+            discount: Decimal::percent(40),
+            native_per_foreign: Decimal::percent(50),
+        },
+    );
+
+    // Rewards go to the converter by default
+    let recipient = virtual_staking.rewards_recipient().unwrap();
+    assert_eq!(recipient.recipient, converter.contract_addr.to_string());
+    assert!(!recipient.overridden);
+
+    // Only the converter admin can set the recipient
+    converter
+        .set_rewards_recipient(Some("escrow".to_owned()))
+        .call(owner)
+        .unwrap_err();
+    converter
+        .set_rewards_recipient(Some("escrow".to_owned()))
+        .call(admin)
+        .unwrap();
+    let recipient = virtual_staking.rewards_recipient().unwrap();
+    assert_eq!(recipient.recipient, "escrow");
+    assert!(recipient.overridden);
+
+    // The virtual staking contract only takes it from the converter
+    let err = virtual_staking
+        .set_rewards_recipient(None)
+        .call(admin)
+        .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized));
+
+    converter.set_rewards_recipient(None).call(admin).unwrap();
+    let recipient = virtual_staking.rewards_recipient().unwrap();
+    assert_eq!(recipient.recipient, converter.contract_addr.to_string());
+    assert!(!recipient.overridden);
+}
@@ -1,5 +1,5 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::Addr;
+use cosmwasm_std::{Addr, Decimal};
 
 #[cw_serde]
 pub struct Config {
@@ -14,4 +14,16 @@ pub struct Config {
 
     /// If it enable, tombstoned validators will be unbond automatically
     pub tombstoned_unbond_enable: bool,
+
+    /// If it enable, jailed validators will be unbond at the next epoch, and bond again once they
+    /// are active
+    #[serde(default)]
+    pub jailed_unbond_enable: bool,
+}
+
+/// Slashing params of the chain, as last sent to the converter
+#[cw_serde]
+pub struct SlashingParams {
+    pub double_sign: Decimal,
+    pub offline: Decimal,
 }
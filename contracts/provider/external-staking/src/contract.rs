@@ -1,19 +1,22 @@
 use cosmwasm_std::{
-    coin, ensure, ensure_eq, to_json_binary, Addr, Coin, Decimal, DepsMut, Env, Event, IbcMsg,
-    Order, Response, StdResult, Storage, Uint128, Uint256, WasmMsg,
+    coin, ensure, ensure_eq, to_json_binary, Addr, Coin, Decimal, Deps, DepsMut, Env, Event,
+    IbcMsg, Order, Reply, Response, StdResult, Storage, SubMsg, SubMsgResult, Timestamp, Uint128,
+    Uint256, WasmMsg,
 };
 use cw2::set_contract_version;
-use cw_storage_plus::{Bounder, Item, Map};
+use cw_storage_plus::{Bound, Bounder, Item, Map};
 use cw_utils::{nonpayable, PaymentError};
 use std::cmp::min;
 use std::collections::HashSet;
 
 use mesh_apis::converter_api::{RewardInfo, ValidatorSlashInfo};
 use sylvia::contract;
-use sylvia::types::{ExecCtx, InstantiateCtx, QueryCtx};
+use sylvia::types::{ExecCtx, InstantiateCtx, MigrateCtx, QueryCtx, ReplyCtx, SudoCtx};
 
 use mesh_apis::cross_staking_api::{self};
-use mesh_apis::ibc::{AddValidator, ProviderPacket};
+use mesh_apis::ibc::{AddValidator, ConversionTerms, ProviderPacket};
+use mesh_apis::staker_callback_api::sv::StakerCallbackApiExecMsg;
+use mesh_apis::staker_callback_api::StakerCallbackApiHelper;
 use mesh_apis::vault_api::{SlashInfo, VaultApiHelper};
 use mesh_sync::{Tx, ValueRange};
 
@@ -21,12 +24,24 @@ use crate::crdt::{CrdtState, State};
 use crate::error::ContractError;
 use crate::ibc::{packet_timeout, IBC_CHANNEL};
 use crate::msg::{
-    AllPendingRewards, AllTxsResponse, AuthorizedEndpointResponse, ConfigResponse,
-    IbcChannelResponse, ListActiveValidatorsResponse, ListValidatorsResponse, PendingRewards,
-    StakeInfo, StakesResponse, TxResponse, ValidatorPendingRewards,
+    AllPendingRewards, AllPendingUnbondsResponse, AllTxsResponse, AuthorizedEndpointResponse,
+    ConfigResponse, CrdtStatsResponse, DelegationPolicyResponse, FeeConfig, FeeConfigResponse,
+    IbcChannelResponse, ImportedStake, ListActiveValidatorsResponse, ListValidatorsResponse,
+    MaxExposureResponse, MetricsResponse, MigrationResponse, PausedValidatorsResponse,
+    PendingJailSlashInfo, PendingJailSlashesResponse, PendingRewards, PendingUnbondInfo,
+    QueuedPacketInfo, RelockResponse, RetryQueueResponse, RewardsSweepConfig, RewardsSweepResponse,
+    SlashRatioSourceResponse, StakeConversionInfo, StakeConversionsResponse, StakeInfo,
+    StakerCallbacksResponse, StakesResponse, TxResponse, UnbondingInfo, UnbondingsResponse,
+    UnclaimedRewardsResponse, ValidatorAtHeightResponse, ValidatorPendingRewards,
+    VaultVerificationResponse,
 };
 use crate::stakes::Stakes;
-use crate::state::{Config, Distribution, PendingUnbond, SlashRatio, Stake};
+use crate::state::{
+    Config, ConsumerSlashRatio, DelegationPolicy, Distribution, JailSlashPolicy, JailedStakePolicy,
+    Metrics, PendingJailSlash, PendingUnbond, QueuedPacket, SlashRatio, Stake, StakeConversion,
+    UnbondOrigin, UnbondingOverrides, UserStats, DEFAULT_DISTRIBUTION_PRECISION,
+};
+use crate::txs::Txs;
 
 pub const CONTRACT_NAME: &str = env!("CARGO_PKG_NAME");
 pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -34,13 +49,41 @@ pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 pub const DEFAULT_PAGE_LIMIT: u32 = 10;
 pub const MAX_PAGE_LIMIT: u32 = 30;
 
-pub const DISTRIBUTION_POINTS_SCALE: Uint256 = Uint256::from_u128(1_000_000_000);
+pub const REPLY_ID_STAKER_CALLBACK: u64 = 1;
+
+/// Gas available to a staker callback, so that it can't exhaust the gas of IBC packet handling
+pub const STAKER_CALLBACK_GAS_LIMIT: u64 = 500_000;
+
+/// Bounds of the number of decimals of the reward points per stake
+pub const MIN_DISTRIBUTION_PRECISION: u32 = 3;
+pub const MAX_DISTRIBUTION_PRECISION: u32 = 24;
+
+/// If no packet is received from the consumer for this long (in seconds), the consumer is
+/// considered halted for the purpose of unbonding estimates
+pub const CONSUMER_HALT_THRESHOLD: u64 = 24 * 60 * 60;
+
+/// Times a timed out packet is resent, before its tx is rolled back
+pub const MAX_PACKET_RETRIES: u32 = 3;
+/// Delay (in seconds) before a timed out packet can be resent. Doubled on every attempt
+pub const PACKET_RETRY_BACKOFF: u64 = 60;
+
+pub const COLLATERAL_SUMMARY_INTERVAL: u64 = 60 * 60;
 
 /// Aligns pagination limit
 fn clamp_page_limit(limit: Option<u32>) -> usize {
     limit.unwrap_or(DEFAULT_PAGE_LIMIT).max(MAX_PAGE_LIMIT) as usize
 }
 
+/// Event describing a newly scheduled unbond
+fn unbond_event(user: &Addr, validator: &str, unbond: &PendingUnbond) -> Event {
+    Event::new("unbond")
+        .add_attribute("owner", user)
+        .add_attribute("validator", validator)
+        .add_attribute("amount", unbond.amount.to_string())
+        .add_attribute("release_at", unbond.release_at.seconds().to_string())
+        .add_attribute("unbond_origin", unbond.origin.as_str())
+}
+
 pub struct ExternalStakingContract<'a> {
     pub config: Item<'a, Config>,
     /// Stakes indexed by `(owner, validator)` pair
@@ -49,9 +92,73 @@ pub struct ExternalStakingContract<'a> {
     pub distribution: Map<'a, &'a str, Distribution>,
     /// Pending txs information
     pub tx_count: Item<'a, u64>,
-    pub pending_txs: Map<'a, u64, Tx>,
+    /// Pending txs, indexed by user
+    pub pending_txs: Txs<'a>,
+    /// Stake txs waiting for their jailed validator to become active again, indexed by
+    /// `(validator, tx_id)`
+    pub queued_stakes: Map<'a, (&'a str, u64), ()>,
     /// Valset CRDT
     pub val_set: CrdtState<'a>,
+    /// Local block time of the last packet received from the consumer
+    pub last_consumer_packet: Item<'a, Timestamp>,
+    /// Monitoring information
+    pub metrics: Item<'a, Metrics>,
+    /// Contract stakes are exported to, when migrating to a new deployment
+    pub migration_target: Item<'a, Addr>,
+    /// Contract stakes are imported from, when migrating from a previous deployment
+    pub migration_source: Item<'a, Addr>,
+    /// Ids of the packets sent without a pending tx, e.g. to move migrated stakes on the
+    /// consumer. Their outcome is only reported
+    pub untracked_packets: Map<'a, u64, ()>,
+    /// Stake txs aborted by the vault while their packet was in flight
+    pub aborted_stakes: Map<'a, u64, ()>,
+    /// Governance default for the max total stake of a single user
+    pub default_max_exposure: Item<'a, Uint128>,
+    /// Max total stake of a single user, as set by the user. Overrides the default
+    pub max_exposures: Map<'a, &'a Addr, Uint128>,
+    /// Validators new stake is paused on, e.g. during an investigation
+    pub paused_validators: Map<'a, &'a str, ()>,
+    /// Protocol fee taken on withdrawn rewards. No fee if not set
+    pub fee_config: Item<'a, FeeConfig>,
+    /// Protocol fees collected, and not sent to the treasury yet
+    pub accrued_fees: Item<'a, Uint128>,
+    /// Protocol fee of each pending rewards withdrawal, accrued once it is committed
+    pub pending_fees: Map<'a, u64, Uint128>,
+    /// Pending fee transfers to the treasury, by tx id
+    pub fee_transfers: Map<'a, u64, Uint128>,
+    /// Validator each user re-stakes their released collateral to, if opted in
+    pub relock_validators: Map<'a, &'a Addr, String>,
+    /// Pending stake txs created by relocking. They are not known to the vault
+    pub relocked_txs: Map<'a, u64, ()>,
+    /// Rewards being compounded by pending stake txs. They are not known to the vault either
+    pub compounded_rewards: Map<'a, u64, Uint128>,
+    /// Jail slashes delayed by the jail slash policy, by `(validator, infraction_height)`
+    pub pending_jail_slashes: Map<'a, (&'a str, u64), PendingJailSlash>,
+    /// Delegation policy sent by the consumer. Not limited if not set
+    pub delegation_policy: Item<'a, DelegationPolicy>,
+    /// Slash ratio sent by the consumer. The configured one is used if not set
+    pub consumer_slash_ratio: Item<'a, ConsumerSlashRatio>,
+    /// Sweeping of unclaimed rewards. Disabled if not set
+    pub rewards_sweep: Item<'a, RewardsSweepConfig>,
+    /// Time sweeping was enabled at, used as last claim for stakes that never claimed since
+    pub rewards_sweep_since: Item<'a, Timestamp>,
+    /// Last time rewards were claimed (or first staked), per `(user, validator)`
+    pub last_claims: Map<'a, (&'a Addr, &'a str), Timestamp>,
+    /// Time unclaimed rewards can be swept at, per notified `(user, validator)`
+    pub sweep_notices: Map<'a, (&'a Addr, &'a str), Timestamp>,
+    /// Rewards transfer txs sweeping unclaimed rewards
+    pub sweep_txs: Map<'a, u64, ()>,
+    /// Staker contracts called back on the outcome of their stakes and unstakes
+    pub callback_stakers: Map<'a, &'a Addr, ()>,
+    /// Terms committed stakes were converted with on the consumer, by `(user, tx_id)`
+    pub stake_conversions: Map<'a, (&'a Addr, u64), StakeConversion>,
+    /// Timed out packets waiting to be resent, by tx id
+    pub retry_queue: Map<'a, u64, QueuedPacket>,
+    /// Times the in-flight packet of a pending tx was already resent, by tx id
+    pub packet_retries: Map<'a, u64, u32>,
+    pub last_collateral_summary: Item<'a, Timestamp>,
+    pub collateral_summaries: Item<'a, bool>,
+    pub user_stats: Map<'a, &'a Addr, UserStats>,
 }
 
 impl Default for ExternalStakingContract<'_> {
@@ -69,11 +176,43 @@ impl ExternalStakingContract<'_> {
     pub fn new() -> Self {
         Self {
             config: Item::new("config"),
-            stakes: Stakes::new("stakes", "vals"),
+            stakes: Stakes::new("stakes", "vals", "pending_unbonds_total"),
             distribution: Map::new("distribution"),
-            pending_txs: Map::new("pending_txs"),
+            pending_txs: Txs::new("pending_txs", "users", "pending_tx_counters"),
             tx_count: Item::new("tx_count"),
+            queued_stakes: Map::new("queued_stakes"),
             val_set: CrdtState::new(),
+            last_consumer_packet: Item::new("last_consumer_packet"),
+            metrics: Item::new("metrics"),
+            migration_target: Item::new("migration_target"),
+            migration_source: Item::new("migration_source"),
+            untracked_packets: Map::new("untracked_packets"),
+            aborted_stakes: Map::new("aborted_stakes"),
+            default_max_exposure: Item::new("default_max_exposure"),
+            max_exposures: Map::new("max_exposures"),
+            paused_validators: Map::new("paused_validators"),
+            fee_config: Item::new("fee_config"),
+            accrued_fees: Item::new("accrued_fees"),
+            pending_fees: Map::new("pending_fees"),
+            fee_transfers: Map::new("fee_transfers"),
+            relock_validators: Map::new("relock_validators"),
+            relocked_txs: Map::new("relocked_txs"),
+            compounded_rewards: Map::new("compounded_rewards"),
+            pending_jail_slashes: Map::new("pending_jail_slashes"),
+            delegation_policy: Item::new("delegation_policy"),
+            consumer_slash_ratio: Item::new("consumer_slash_ratio"),
+            rewards_sweep: Item::new("rewards_sweep"),
+            rewards_sweep_since: Item::new("rewards_sweep_since"),
+            last_claims: Map::new("last_claims"),
+            sweep_notices: Map::new("sweep_notices"),
+            sweep_txs: Map::new("sweep_txs"),
+            callback_stakers: Map::new("callback_stakers"),
+            stake_conversions: Map::new("stake_conversions"),
+            retry_queue: Map::new("retry_queue"),
+            packet_retries: Map::new("packet_retries"),
+            last_collateral_summary: Item::new("last_collateral_summary"),
+            collateral_summaries: Item::new("collateral_summaries"),
+            user_stats: Map::new("user_stats"),
         }
     }
 
@@ -96,6 +235,9 @@ impl ExternalStakingContract<'_> {
         unbonding_period: u64,
         remote_contact: crate::msg::AuthorizedEndpoint,
         slash_ratio: SlashRatio,
+        jailed_stake_policy: Option<JailedStakePolicy>,
+        fee_config: Option<FeeConfig>,
+        distribution_precision: Option<u32>,
     ) -> Result<Response, ContractError> {
         let vault = ctx.deps.api.addr_validate(&vault)?;
         let vault = VaultApiHelper(vault);
@@ -103,6 +245,14 @@ impl ExternalStakingContract<'_> {
         if slash_ratio.double_sign > Decimal::one() || slash_ratio.offline > Decimal::one() {
             return Err(ContractError::InvalidSlashRatio);
         }
+        // Fixed for the lifetime of the contract, as it is baked into the distributions
+        let distribution_precision =
+            distribution_precision.unwrap_or(DEFAULT_DISTRIBUTION_PRECISION);
+        ensure!(
+            (MIN_DISTRIBUTION_PRECISION..=MAX_DISTRIBUTION_PRECISION)
+                .contains(&distribution_precision),
+            ContractError::InvalidDistributionPrecision(distribution_precision)
+        );
 
         let config = Config {
             denom,
@@ -110,10 +260,19 @@ impl ExternalStakingContract<'_> {
             vault,
             unbonding_period,
             slash_ratio,
+            jailed_stake_policy: jailed_stake_policy.unwrap_or_default(),
+            jail_slash_policy: JailSlashPolicy::default(),
+            unbonding_overrides: UnbondingOverrides::default(),
+            distribution_precision,
         };
 
         self.config.save(ctx.deps.storage, &config)?;
 
+        if let Some(fee_config) = fee_config {
+            fee_config.validate()?;
+            self.fee_config.save(ctx.deps.storage, &fee_config)?;
+        }
+
         set_contract_version(ctx.deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
         remote_contact.validate()?;
@@ -133,11 +292,28 @@ impl ExternalStakingContract<'_> {
         Ok(Response::new())
     }
 
+    /// Recomputes the running counters of the `metrics` query, so that contracts instantiated
+    /// before they were tracked report accurate values
+    #[sv::msg(migrate)]
+    pub fn migrate(&self, ctx: MigrateCtx) -> Result<Response, ContractError> {
+        self.pending_txs.seed_counters(ctx.deps.storage)?;
+        self.stakes.seed_pending_total(ctx.deps.storage)?;
+
+        set_contract_version(ctx.deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+        Ok(Response::new())
+    }
+
     /// In test code, this is called from `test_commit_stake`.
     /// In non-test code, this is called from `ibc_packet_ack`
-    pub(crate) fn commit_stake(&self, deps: DepsMut, tx_id: u64) -> Result<WasmMsg, ContractError> {
+    pub(crate) fn commit_stake(
+        &self,
+        deps: DepsMut,
+        tx_id: u64,
+        conversion: Option<ConversionTerms>,
+    ) -> Result<Vec<SubMsg>, ContractError> {
         // Load tx
-        let tx = self.pending_txs.load(deps.storage, tx_id)?;
+        let tx = self.pending_txs.txs.load(deps.storage, tx_id)?;
 
         // Verify tx is of the right type
         ensure!(
@@ -155,6 +331,12 @@ impl ExternalStakingContract<'_> {
             _ => unreachable!(),
         };
 
+        // Queued stakes were not sent to the consumer yet
+        ensure!(
+            !self.queued_stakes.has(deps.storage, (&tx_validator, tx_id)),
+            ContractError::StakeQueued(tx_id)
+        );
+
         // Load stake
         let mut stake = self
             .stakes
@@ -178,7 +360,6 @@ impl ExternalStakingContract<'_> {
 
         // Save stake
         self.stakes
-            .stake
             .save(deps.storage, (&tx_user, &tx_validator), &stake)?;
 
         // Save distribution
@@ -186,12 +367,49 @@ impl ExternalStakingContract<'_> {
             .save(deps.storage, &tx_validator, &distribution)?;
 
         // Remove tx
-        self.pending_txs.remove(deps.storage, tx_id);
+        self.pending_txs.remove(deps.storage, tx_id)?;
+        self.update_user_stats(deps.storage, &tx_user, |stats| {
+            stats.total_staked += tx_amount
+        })?;
+
+        // Record the terms the stake was converted with, if the consumer sent them
+        if let Some(conversion) = conversion {
+            self.stake_conversions.save(
+                deps.storage,
+                (&tx_user, tx_id),
+                &StakeConversion {
+                    validator: tx_validator.clone(),
+                    amount: tx_amount,
+                    conversion,
+                },
+            )?;
+        }
 
-        // Call commit hook on vault
         let cfg = self.config.load(deps.storage)?;
-        let msg = cfg.vault.commit_tx(tx_id)?;
-        Ok(msg)
+        let mut msgs = vec![];
+
+        // Relocked and compounded stakes were liened right away, there is no vault tx to commit
+        if self.relocked_txs.has(deps.storage, tx_id) {
+            self.relocked_txs.remove(deps.storage, tx_id);
+        } else if self.compounded_rewards.has(deps.storage, tx_id) {
+            self.compounded_rewards.remove(deps.storage, tx_id);
+            // The fee was kept by the protocol
+            if let Some(fee) = self.pending_fees.may_load(deps.storage, tx_id)? {
+                self.pending_fees.remove(deps.storage, tx_id);
+                self.accrue_fee(deps.storage, fee)?;
+            }
+        } else {
+            // Call commit hook on vault
+            msgs.push(SubMsg::new(cfg.vault.commit_tx(tx_id)?));
+        }
+
+        let callback = StakerCallbackApiExecMsg::StakeCommitted {
+            tx_id,
+            validator: tx_validator,
+            amount: coin(tx_amount.u128(), &cfg.denom),
+        };
+        msgs.extend(self.staker_callback(deps.storage, &tx_user, &callback)?);
+        Ok(msgs)
     }
 
     /// In test code, this is called from `test_rollback_stake`.
@@ -200,9 +418,20 @@ impl ExternalStakingContract<'_> {
         &self,
         deps: DepsMut,
         tx_id: u64,
-    ) -> Result<WasmMsg, ContractError> {
+    ) -> Result<Vec<SubMsg>, ContractError> {
+        self.undo_stake(deps, tx_id, true)
+    }
+
+    /// Rolls back a pending stake. The vault tx is only rolled back along if `rollback_vault_tx`
+    /// is set, as the vault rolls it back on its own when it aborts the stake
+    fn undo_stake(
+        &self,
+        deps: DepsMut,
+        tx_id: u64,
+        rollback_vault_tx: bool,
+    ) -> Result<Vec<SubMsg>, ContractError> {
         // Load tx
-        let tx = self.pending_txs.load(deps.storage, tx_id)?;
+        let tx = self.pending_txs.txs.load(deps.storage, tx_id)?;
 
         // Verify tx is of the right type
         ensure!(
@@ -229,18 +458,150 @@ impl ExternalStakingContract<'_> {
         // Rollback add amount (saturating up if slashed)
         stake.stake.rollback_add_saturating(tx_amount);
 
+        // Rewards that were being compounded can be withdrawn again
+        let compounded = self.compounded_rewards.may_load(deps.storage, tx_id)?;
+        if let Some(rewards) = compounded {
+            stake.withdrawn_funds -= rewards;
+        }
+
         // Save stake
         self.stakes
-            .stake
             .save(deps.storage, (&tx_user, &tx_validator), &stake)?;
 
         // Remove tx
-        self.pending_txs.remove(deps.storage, tx_id);
+        self.pending_txs.remove(deps.storage, tx_id)?;
+        self.queued_stakes
+            .remove(deps.storage, (&tx_validator, tx_id));
 
-        // Call rollback hook on vault
         let cfg = self.config.load(deps.storage)?;
-        let msg = cfg.vault.rollback_tx(tx_id)?;
-        Ok(msg)
+
+        // A failed relock or compounding releases the collateral, as `withdraw_unbonded` would have
+        let mut msgs = vec![];
+        if self.relocked_txs.has(deps.storage, tx_id) || compounded.is_some() {
+            self.relocked_txs.remove(deps.storage, tx_id);
+            self.compounded_rewards.remove(deps.storage, tx_id);
+            self.pending_fees.remove(deps.storage, tx_id);
+            msgs.push(SubMsg::new(cfg.vault.release_cross_stake(
+                tx_user.to_string(),
+                coin(tx_amount.u128(), &cfg.denom),
+                vec![],
+            )?));
+        } else if rollback_vault_tx {
+            // Call rollback hook on vault
+            msgs.push(SubMsg::new(cfg.vault.rollback_tx(tx_id)?));
+        }
+
+        let callback = StakerCallbackApiExecMsg::StakeRolledBack {
+            tx_id,
+            validator: tx_validator,
+            amount: coin(tx_amount.u128(), &cfg.denom),
+        };
+        msgs.extend(self.staker_callback(deps.storage, &tx_user, &callback)?);
+        Ok(msgs)
+    }
+
+    fn queued_stake_ids(&self, storage: &dyn Storage, valoper: &str) -> StdResult<Vec<u64>> {
+        self.queued_stakes
+            .prefix(valoper)
+            .keys(storage, None, None, Order::Ascending)
+            .collect()
+    }
+
+    /// Rolls back all stakes queued for `valoper`.
+    /// Called when the validator is tombstoned, as it will never become active again.
+    pub(crate) fn rollback_queued_stakes(
+        &self,
+        mut deps: DepsMut,
+        valoper: &str,
+    ) -> Result<Vec<SubMsg>, ContractError> {
+        let mut msgs = vec![];
+        for tx_id in self.queued_stake_ids(deps.storage, valoper)? {
+            msgs.extend(self.rollback_stake(deps.branch(), tx_id)?);
+        }
+        Ok(msgs)
+    }
+
+    /// Sends all stakes queued for `valoper` to the consumer, if the validator is active again.
+    /// In test code, this is called from `test_set_active_validator`.
+    /// In non-test code, this is called from `ibc_packet_receive`
+    pub(crate) fn release_queued_stakes(
+        &self,
+        storage: &mut dyn Storage,
+        env: &Env,
+        valoper: &str,
+    ) -> Result<Vec<IbcMsg>, ContractError> {
+        if !self.val_set.is_active_validator(storage, valoper)? {
+            return Ok(vec![]);
+        }
+        self.send_queued_stakes(storage, env, valoper)
+    }
+
+    /// Sends all stakes queued for `valoper` to the consumer, once the validator is reported as
+    /// unjailed. The validator is not set to active until it is added back to the active set, so
+    /// this only checks that it is still jailed (i.e. not tombstoned or removed in the meantime).
+    /// In test code, this is called from `test_unjail_validator`.
+    /// In non-test code, this is called from `ibc_packet_receive`
+    pub(crate) fn release_unjailed_stakes(
+        &self,
+        storage: &mut dyn Storage,
+        env: &Env,
+        valoper: &str,
+    ) -> Result<Vec<IbcMsg>, ContractError> {
+        if !matches!(
+            self.val_set.validator_state(storage, valoper)?,
+            State::Jailed {}
+        ) {
+            return Ok(vec![]);
+        }
+        self.send_queued_stakes(storage, env, valoper)
+    }
+
+    fn send_queued_stakes(
+        &self,
+        storage: &mut dyn Storage,
+        env: &Env,
+        valoper: &str,
+    ) -> Result<Vec<IbcMsg>, ContractError> {
+        let denom = self.config.load(storage)?.denom;
+        let mut msgs = vec![];
+        for tx_id in self.queued_stake_ids(storage, valoper)? {
+            self.queued_stakes.remove(storage, (valoper, tx_id));
+            let (user, amount) = match self.pending_txs.txs.load(storage, tx_id)? {
+                Tx::InFlightRemoteStaking { user, amount, .. } => (user, amount),
+                tx => return Err(ContractError::WrongTypeTx(tx_id, tx)),
+            };
+            msgs.push(Self::stake_packet(
+                storage,
+                env,
+                &user,
+                valoper,
+                coin(amount.u128(), &denom),
+                tx_id,
+            )?);
+        }
+        Ok(msgs)
+    }
+
+    fn stake_packet(
+        storage: &dyn Storage,
+        env: &Env,
+        owner: &Addr,
+        validator: &str,
+        stake: Coin,
+        tx_id: u64,
+    ) -> Result<IbcMsg, ContractError> {
+        let channel = IBC_CHANNEL.load(storage)?;
+        let packet = ProviderPacket::Stake {
+            delegator: owner.to_string(),
+            validator: validator.to_string(),
+            stake,
+            tx_id,
+        };
+        Ok(IbcMsg::SendPacket {
+            channel_id: channel.endpoint.channel_id,
+            data: to_json_binary(&packet)?,
+            timeout: packet_timeout(env),
+        })
     }
 
     /// Schedules tokens for release, adding them to the pending unbonds. After the unbonding period
@@ -277,7 +638,6 @@ impl ExternalStakingContract<'_> {
         stake.stake.prepare_sub(amount.amount, Uint128::zero())?;
 
         self.stakes
-            .stake
             .save(deps.storage, (&info.sender, &validator), &stake)?;
 
         // Create new tx
@@ -331,11 +691,11 @@ impl ExternalStakingContract<'_> {
         deps: DepsMut,
         env: Env,
         tx_id: u64,
-    ) -> Result<(), ContractError> {
+    ) -> Result<(Option<SubMsg>, Event), ContractError> {
         use crate::state::PendingUnbond;
 
         // Load tx
-        let tx = self.pending_txs.load(deps.storage, tx_id)?;
+        let tx = self.pending_txs.txs.load(deps.storage, tx_id)?;
 
         // Verify tx is of the right type
         ensure!(
@@ -371,19 +731,30 @@ impl ExternalStakingContract<'_> {
         let amount = min(tx_amount, stake.stake.high());
         stake.stake.commit_sub(amount);
 
-        let immediate_release = matches!(
-            self.val_set.validator_state(deps.storage, &tx_validator)?,
-            State::Unbonded {} | State::Tombstoned {}
-        );
+        let validator_state = self.val_set.validator_state(deps.storage, &tx_validator)?;
+        let unbonding_period = match validator_state {
+            State::Unbonded {} | State::Tombstoned {} => 0,
+            State::Jailed {} => config
+                .unbonding_overrides
+                .jailed
+                .unwrap_or(config.unbonding_period),
+            _ => config.unbonding_period,
+        };
+        let origin = if matches!(validator_state, State::Tombstoned {}) {
+            UnbondOrigin::Tombstone
+        } else {
+            UnbondOrigin::User
+        };
 
         // FIXME? Release period being computed after successful IBC tx
         // (Note: this is good for now, but can be revisited in v1 design)
-        let release_at = if immediate_release {
-            env.block.time
-        } else {
-            env.block.time.plus_seconds(config.unbonding_period)
+        let release_at = env.block.time.plus_seconds(unbonding_period);
+        let unbond = PendingUnbond {
+            amount,
+            release_at,
+            origin,
         };
-        let unbond = PendingUnbond { amount, release_at };
+        let event = unbond_event(&tx_user, &tx_validator, &unbond);
         stake.pending_unbonds.push(unbond);
 
         // Distribution alignment
@@ -394,7 +765,6 @@ impl ExternalStakingContract<'_> {
 
         // Save stake
         self.stakes
-            .stake
             .save(deps.storage, (&tx_user, &tx_validator), &stake)?;
 
         // Save distribution
@@ -402,15 +772,30 @@ impl ExternalStakingContract<'_> {
             .save(deps.storage, &tx_validator, &distribution)?;
 
         // Remove tx
-        self.pending_txs.remove(deps.storage, tx_id);
-        Ok(())
+        self.pending_txs.remove(deps.storage, tx_id)?;
+        self.update_user_stats(deps.storage, &tx_user, |stats| {
+            stats.total_unstaked += amount
+        })?;
+
+        let callback = StakerCallbackApiExecMsg::UnstakeCommitted {
+            tx_id,
+            validator: tx_validator,
+            amount: coin(amount.u128(), &config.denom),
+            release_at: release_at.seconds(),
+        };
+        let msg = self.staker_callback(deps.storage, &tx_user, &callback)?;
+        Ok((msg, event))
     }
 
     /// In test code, this is called from `test_rollback_unstake`.
     /// In non-test code, this is called from `ibc_packet_ack` or `ibc_packet_timeout`
-    pub(crate) fn rollback_unstake(&self, deps: DepsMut, tx_id: u64) -> Result<(), ContractError> {
+    pub(crate) fn rollback_unstake(
+        &self,
+        deps: DepsMut,
+        tx_id: u64,
+    ) -> Result<Option<SubMsg>, ContractError> {
         // Load tx
-        let tx = self.pending_txs.load(deps.storage, tx_id)?;
+        let tx = self.pending_txs.txs.load(deps.storage, tx_id)?;
 
         // Verify tx is of the right type
         ensure!(
@@ -438,120 +823,476 @@ impl ExternalStakingContract<'_> {
 
         // Save stake
         self.stakes
-            .stake
             .save(deps.storage, (&tx_user, &tx_validator), &stake)?;
 
         // Remove tx
-        self.pending_txs.remove(deps.storage, tx_id);
-        Ok(())
+        self.pending_txs.remove(deps.storage, tx_id)?;
+
+        let cfg = self.config.load(deps.storage)?;
+        let callback = StakerCallbackApiExecMsg::UnstakeRolledBack {
+            tx_id,
+            validator: tx_validator,
+            amount: coin(tx_amount.u128(), &cfg.denom),
+        };
+        let msg = self.staker_callback(deps.storage, &tx_user, &callback)?;
+        Ok(msg)
     }
 
-    // immediate unstake assets
-    pub(crate) fn internal_unstake(
+    /// Returns the callback to `staker`, if it opted into callbacks.
+    /// Failing callbacks are ignored, so they can't block IBC packet handling.
+    fn staker_callback(
         &self,
-        deps: DepsMut,
-        env: Env,
-        delegator: String,
-        validator: String,
-        amount: Coin,
-    ) -> Result<Event, ContractError> {
-        let user = deps.api.addr_validate(&delegator)?;
-        // Load stake
-        let mut stake = self.stakes.stake.load(deps.storage, (&user, &validator))?;
+        storage: &dyn Storage,
+        staker: &Addr,
+        callback: &StakerCallbackApiExecMsg,
+    ) -> StdResult<Option<SubMsg>> {
+        if !self.callback_stakers.has(storage, staker) {
+            return Ok(None);
+        }
+        let msg = StakerCallbackApiHelper(staker.clone()).callback(callback)?;
+        let msg = SubMsg::reply_on_error(msg, REPLY_ID_STAKER_CALLBACK)
+            .with_gas_limit(STAKER_CALLBACK_GAS_LIMIT);
+        Ok(Some(msg))
+    }
 
-        // Load distribution
-        let mut distribution = self
-            .distribution
-            .may_load(deps.storage, &validator)?
-            .unwrap_or_default();
+    /// Opts the calling contract in or out of callbacks on the outcome of its stakes and
+    /// unstakes, and on the release of its unbondings. The caller has to implement the
+    /// `StakerCallbackApi` interface.
+    #[sv::msg(exec)]
+    pub fn set_staker_callbacks(
+        &self,
+        ctx: ExecCtx,
+        enabled: bool,
+    ) -> Result<Response, ContractError> {
+        nonpayable(&ctx.info)?;
+        if enabled {
+            ctx.deps
+                .querier
+                .query_wasm_contract_info(&ctx.info.sender)
+                .map_err(|_| ContractError::NotAContract(ctx.info.sender.to_string()))?;
+            self.callback_stakers
+                .save(ctx.deps.storage, &ctx.info.sender, &())?;
+        } else {
+            self.callback_stakers
+                .remove(ctx.deps.storage, &ctx.info.sender);
+        }
+        Ok(Response::new()
+            .add_attribute("action", "set_staker_callbacks")
+            .add_attribute("owner", ctx.info.sender)
+            .add_attribute("enabled", enabled.to_string()))
+    }
 
-        // Commit sub amount, saturating if slashed
-        let amount = min(amount.amount, stake.stake.high());
-        stake.stake.sub(amount, Uint128::zero())?;
+    #[sv::msg(query)]
+    pub fn staker_callbacks(
+        &self,
+        ctx: QueryCtx,
+        user: String,
+    ) -> Result<StakerCallbacksResponse, ContractError> {
+        let user = ctx.deps.api.addr_validate(&user)?;
+        let enabled = self.callback_stakers.has(ctx.deps.storage, &user);
+        Ok(StakerCallbacksResponse { enabled })
+    }
 
-        let unbond = PendingUnbond {
-            amount,
-            release_at: env.block.time,
-        };
-        stake.pending_unbonds.push(unbond);
+    #[sv::msg(reply)]
+    fn reply(&self, _ctx: ReplyCtx, reply: Reply) -> Result<Response, ContractError> {
+        match reply.id {
+            REPLY_ID_STAKER_CALLBACK => {
+                let mut evt = Event::new("staker_callback_failed");
+                if let SubMsgResult::Err(err) = reply.result {
+                    evt = evt.add_attribute("error", err);
+                }
+                Ok(Response::new().add_event(evt))
+            }
+            _ => Err(ContractError::InvalidReplyId(reply.id)),
+        }
+    }
 
-        // Distribution alignment
-        stake
-            .points_alignment
-            .stake_decreased(amount, distribution.points_per_stake);
-        distribution.total_stake -= amount;
+    /// Cancels (part of) the user's pending unbonds on the given validator, staking the
+    /// tokens again. Only unbonds which are not released yet can be cancelled, starting from the
+    /// most recent ones.
+    #[sv::msg(exec)]
+    pub fn cancel_unbond(
+        &self,
+        ctx: ExecCtx,
+        validator: String,
+        amount: Coin,
+    ) -> Result<Response, ContractError> {
+        let ExecCtx { info, deps, env } = ctx;
+        nonpayable(&info)?;
 
-        // Save stake
-        self.stakes
-            .stake
-            .save(deps.storage, (&user, &validator), &stake)?;
+        let config = self.config.load(deps.storage)?;
 
-        // Save distribution
-        self.distribution
-            .save(deps.storage, &validator, &distribution)?;
-        let event = Event::new("internal_unstake")
-            .add_attribute("delegator", delegator)
-            .add_attribute("validator", validator)
-            .add_attribute("amount", amount.to_string());
+        ensure_eq!(
+            amount.denom,
+            config.denom,
+            ContractError::InvalidDenom(config.denom)
+        );
 
-        Ok(event)
-    }
+        if !self.val_set.is_active_validator(deps.storage, &validator)? {
+            return Err(ContractError::ValidatorNotActive(validator));
+        }
 
-    pub(crate) fn handle_close_channel(
-        &self,
-        deps: DepsMut,
-        env: Env,
-    ) -> Result<(), ContractError> {
-        let stakes: Vec<((Addr, String), Stake)> = self
+        let mut stake = self
             .stakes
             .stake
-            .range(
-                deps.as_ref().storage,
-                None,
-                None,
-                cosmwasm_std::Order::Ascending,
-            )
-            .collect::<Result<_, _>>()?;
-
-        for ((user, validator), stake) in stakes.iter() {
-            let mut new_stake = stake.clone();
-            let amount = new_stake.stake.low();
-            let unbond = PendingUnbond {
-                amount,
-                release_at: env.block.time,
-            };
-            new_stake.pending_unbonds.push(unbond);
-            new_stake.stake = ValueRange::new_val(Uint128::zero());
+            .may_load(deps.storage, (&info.sender, &validator))?
+            .unwrap_or_default();
 
-            let mut distribution = self
-                .distribution
-                .may_load(deps.storage, validator)?
-                .unwrap_or_default();
-            new_stake
-                .points_alignment
-                .stake_decreased(amount, distribution.points_per_stake);
+        let unbonding: Uint128 = stake
+            .pending_unbonds
+            .iter()
+            .filter(|pending| pending.release_at > env.block.time)
+            .map(|pending| pending.amount)
+            .sum();
+        ensure!(
+            unbonding >= amount.amount,
+            ContractError::NotEnoughUnbonding(unbonding)
+        );
 
-            distribution.total_stake -= amount;
+        // Take from the most recent unbonds first, recording what was taken from each one
+        let mut unbonds = vec![];
+        let mut remaining = amount.amount;
+        while !remaining.is_zero() {
+            let pending = stake.pending_unbonds.last_mut().unwrap();
+            let taken = min(remaining, pending.amount);
+            pending.amount -= taken;
+            remaining -= taken;
+            unbonds.push((taken, pending.release_at));
+            if pending.amount.is_zero() {
+                stake.pending_unbonds.pop();
+            }
+        }
 
-            // Save stake
-            self.stakes
-                .stake
-                .save(deps.storage, (user, validator), &new_stake)?;
+        stake.stake.prepare_add(amount.amount, None)?;
 
-            // Save distribution
-            self.distribution
-                .save(deps.storage, validator, &distribution)?;
-        }
+        self.stakes
+            .save(deps.storage, (&info.sender, &validator), &stake)?;
 
-        Ok(())
-    }
+        // Create new tx
+        let tx_id = self.next_tx_id(deps.storage)?;
 
-    /// In non-test code, this is called from `ibc_packet_ack`
-    #[allow(clippy::too_many_arguments)]
-    pub(crate) fn valset_update(
+        // Save tx
+        let new_tx = Tx::InFlightRemoteRestaking {
+            id: tx_id,
+            amount: amount.amount,
+            user: info.sender.clone(),
+            validator: validator.clone(),
+            unbonds,
+        };
+        self.pending_txs.save(deps.storage, tx_id, &new_tx)?;
+
+        #[allow(unused_mut)]
+        let mut resp = Response::new()
+            .add_attribute("action", "cancel_unbond")
+            .add_attribute("amount", amount.amount.to_string())
+            .add_attribute("owner", info.sender.clone())
+            .add_attribute("tx_id", tx_id.to_string());
+
+        // The consumer re-bonds the virtual stake, same as for a regular stake
+        let channel = IBC_CHANNEL.load(deps.storage)?;
+        let packet = ProviderPacket::Stake {
+            delegator: info.sender.to_string(),
+            validator,
+            stake: amount,
+            tx_id,
+        };
+        let msg = IbcMsg::SendPacket {
+            channel_id: channel.endpoint.channel_id,
+            data: to_json_binary(&packet)?,
+            timeout: packet_timeout(&env),
+        };
+        // send packet if we are ibc enabled
+        #[cfg(not(any(test, feature = "mt")))]
+        {
+            resp = resp.add_message(msg);
+        }
+        #[cfg(any(test, feature = "mt"))]
+        {
+            let _ = msg;
+        }
+
+        Ok(resp)
+    }
+
+    /// `Stake` packets are sent both for new stakes and for unbond cancellations.
+    /// This tells them apart by the pending tx type.
+    pub(crate) fn is_cancel_unbond_tx(
+        &self,
+        storage: &dyn Storage,
+        tx_id: u64,
+    ) -> Result<bool, ContractError> {
+        let tx = self.pending_txs.txs.load(storage, tx_id)?;
+        Ok(matches!(tx, Tx::InFlightRemoteRestaking { .. }))
+    }
+
+    /// In test code, this is called from `test_commit_cancel_unbond`.
+    /// In non-test code, this is called from `ibc_packet_ack`
+    pub(crate) fn commit_cancel_unbond(
+        &self,
+        deps: DepsMut,
+        tx_id: u64,
+    ) -> Result<(), ContractError> {
+        // Load tx
+        let tx = self.pending_txs.txs.load(deps.storage, tx_id)?;
+
+        let (tx_amount, tx_user, tx_validator) = match tx {
+            Tx::InFlightRemoteRestaking {
+                amount,
+                user,
+                validator,
+                ..
+            } => (amount, user, validator),
+            _ => return Err(ContractError::WrongTypeTx(tx_id, tx)),
+        };
+
+        // Load stake
+        let mut stake = self
+            .stakes
+            .stake
+            .load(deps.storage, (&tx_user, &tx_validator))?;
+
+        // Load distribution
+        let mut distribution = self
+            .distribution
+            .may_load(deps.storage, &tx_validator)?
+            .unwrap_or_default();
+
+        // Commit stake (saturating up if slashed)
+        stake.stake.commit_add_saturating(tx_amount);
+
+        // Distribution alignment
+        stake
+            .points_alignment
+            .stake_increased(tx_amount, distribution.points_per_stake);
+        distribution.total_stake += tx_amount;
+
+        // Save stake
+        self.stakes
+            .save(deps.storage, (&tx_user, &tx_validator), &stake)?;
+
+        // Save distribution
+        self.distribution
+            .save(deps.storage, &tx_validator, &distribution)?;
+
+        // Remove tx
+        self.pending_txs.remove(deps.storage, tx_id)?;
+        Ok(())
+    }
+
+    /// In test code, this is called from `test_rollback_cancel_unbond`.
+    /// In non-test code, this is called from `ibc_packet_ack` or `ibc_packet_timeout`
+    pub(crate) fn rollback_cancel_unbond(
+        &self,
+        deps: DepsMut,
+        tx_id: u64,
+    ) -> Result<(), ContractError> {
+        // Load tx
+        let tx = self.pending_txs.txs.load(deps.storage, tx_id)?;
+
+        let (tx_amount, tx_user, tx_validator, tx_unbonds) = match tx {
+            Tx::InFlightRemoteRestaking {
+                amount,
+                user,
+                validator,
+                unbonds,
+                ..
+            } => (amount, user, validator, unbonds),
+            _ => return Err(ContractError::WrongTypeTx(tx_id, tx)),
+        };
+
+        // Load stake
+        let mut stake = self
+            .stakes
+            .stake
+            .load(deps.storage, (&tx_user, &tx_validator))?;
+
+        // Rollback add amount (saturating up if slashed)
+        stake.stake.rollback_add_saturating(tx_amount);
+
+        // Restore the cancelled amounts to their pending unbonds, keeping them sorted by release
+        // time. A partially cancelled unbond is topped up again; fully cancelled ones are
+        // re-inserted. Only still-unbonding entries can be cancelled, and only user unstakes are
+        // not released right away
+        for (amount, release_at) in tx_unbonds {
+            match stake
+                .pending_unbonds
+                .iter_mut()
+                .rev()
+                .find(|pending| pending.release_at == release_at)
+            {
+                Some(pending) => pending.amount += amount,
+                None => {
+                    let idx = stake
+                        .pending_unbonds
+                        .partition_point(|pending| pending.release_at <= release_at);
+                    stake.pending_unbonds.insert(
+                        idx,
+                        PendingUnbond {
+                            amount,
+                            release_at,
+                            origin: UnbondOrigin::User,
+                        },
+                    );
+                }
+            }
+        }
+
+        // Save stake
+        self.stakes
+            .save(deps.storage, (&tx_user, &tx_validator), &stake)?;
+
+        // Remove tx
+        self.pending_txs.remove(deps.storage, tx_id)?;
+        Ok(())
+    }
+
+    // immediate unstake assets
+    pub(crate) fn internal_unstake(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        delegator: String,
+        validator: String,
+        amount: Coin,
+    ) -> Result<Event, ContractError> {
+        let user = deps.api.addr_validate(&delegator)?;
+        // Load stake
+        let mut stake = self.stakes.stake.load(deps.storage, (&user, &validator))?;
+
+        // Load distribution
+        let mut distribution = self
+            .distribution
+            .may_load(deps.storage, &validator)?
+            .unwrap_or_default();
+
+        // Commit sub amount, saturating if slashed
+        let amount = min(amount.amount, stake.stake.high());
+        stake.stake.sub(amount, Uint128::zero())?;
+
+        let unbond = PendingUnbond {
+            amount,
+            release_at: env.block.time,
+            origin: UnbondOrigin::ConsumerForced,
+        };
+        stake.pending_unbonds.push(unbond);
+
+        // Distribution alignment
+        stake
+            .points_alignment
+            .stake_decreased(amount, distribution.points_per_stake);
+        distribution.total_stake -= amount;
+
+        // Save stake
+        self.stakes
+            .save(deps.storage, (&user, &validator), &stake)?;
+
+        // Save distribution
+        self.distribution
+            .save(deps.storage, &validator, &distribution)?;
+        self.update_user_stats(deps.storage, &user, |stats| stats.total_unstaked += amount)?;
+        let event = Event::new("internal_unstake")
+            .add_attribute("delegator", delegator)
+            .add_attribute("validator", validator)
+            .add_attribute("amount", amount.to_string())
+            .add_attribute("unbond_origin", UnbondOrigin::ConsumerForced.as_str());
+
+        Ok(event)
+    }
+
+    pub(crate) fn handle_close_channel(
         &self,
         deps: DepsMut,
         env: Env,
+    ) -> Result<Event, ContractError> {
+        let stakes: Vec<((Addr, String), Stake)> = self
+            .stakes
+            .stake
+            .range(
+                deps.as_ref().storage,
+                None,
+                None,
+                cosmwasm_std::Order::Ascending,
+            )
+            .collect::<Result<_, _>>()?;
+
+        let mut total = Uint128::zero();
+        for ((user, validator), stake) in stakes.iter() {
+            let mut new_stake = stake.clone();
+            let amount = new_stake.stake.low();
+            let unbond = PendingUnbond {
+                amount,
+                release_at: env.block.time,
+                origin: UnbondOrigin::ChannelClosed,
+            };
+            total += amount;
+            new_stake.pending_unbonds.push(unbond);
+            new_stake.stake = ValueRange::new_val(Uint128::zero());
+
+            let mut distribution = self
+                .distribution
+                .may_load(deps.storage, validator)?
+                .unwrap_or_default();
+            new_stake
+                .points_alignment
+                .stake_decreased(amount, distribution.points_per_stake);
+
+            distribution.total_stake -= amount;
+
+            // Save stake
+            self.stakes
+                .save(deps.storage, (user, validator), &new_stake)?;
+
+            // Save distribution
+            self.distribution
+                .save(deps.storage, validator, &distribution)?;
+        }
+
+        let event = Event::new("close_channel_unstake")
+            .add_attribute("stakes", stakes.len().to_string())
+            .add_attribute("amount", total.to_string())
+            .add_attribute("unbond_origin", UnbondOrigin::ChannelClosed.as_str());
+        Ok(event)
+    }
+
+    /// Queues the timed out packet of a pending tx to be resent, unless it was already resent
+    /// `MAX_PACKET_RETRIES` times. Returns the retry attempt, or `None` if the tx has to be
+    /// rolled back.
+    /// In non-test code, this is called from `ibc_packet_timeout`
+    pub(crate) fn queue_packet_retry(
+        &self,
+        storage: &mut dyn Storage,
+        env: &Env,
+        tx_id: u64,
+        packet: ProviderPacket,
+    ) -> StdResult<Option<u32>> {
+        let attempts = self
+            .packet_retries
+            .may_load(storage, tx_id)?
+            .unwrap_or_default();
+        self.packet_retries.remove(storage, tx_id);
+        if attempts >= MAX_PACKET_RETRIES {
+            return Ok(None);
+        }
+
+        let retry_at = env
+            .block
+            .time
+            .plus_seconds(PACKET_RETRY_BACKOFF << attempts);
+        let queued = QueuedPacket {
+            packet,
+            attempts,
+            retry_at,
+        };
+        self.retry_queue.save(storage, tx_id, &queued)?;
+        Ok(Some(attempts + 1))
+    }
+
+    /// In non-test code, this is called from `ibc_packet_ack`
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn valset_update(
+        &self,
+        mut deps: DepsMut,
+        env: Env,
         height: u64,
         time: u64,
         additions: &[AddValidator],
@@ -559,12 +1300,14 @@ impl ExternalStakingContract<'_> {
         updated: &[AddValidator],
         jailed: &[String],
         unjailed: &[String],
+        reactivated: &[String],
         tombstoned: &[String],
         slashed: &[ValidatorSlashInfo],
-    ) -> Result<(Event, Vec<WasmMsg>), ContractError> {
+    ) -> Result<(Event, Vec<SubMsg>), ContractError> {
         let cfg = self.config.load(deps.storage)?;
         let mut msgs = vec![];
         let mut valopers: HashSet<String> = HashSet::new();
+        let mut delayed = vec![];
         // Process slashing events first.
         for valinfo in slashed {
             let valoper = &valinfo.address;
@@ -581,6 +1324,32 @@ impl ExternalStakingContract<'_> {
                         return Err(ContractError::InvalidSlashRatio);
                     }
                 };
+                // Jail slashes follow the jail slash policy. Tombstones are always slashed
+                let jail_slash = jailed.contains(valoper) && !tombstoned.contains(valoper);
+                let grace_period = match cfg.jail_slash_policy {
+                    JailSlashPolicy::Delayed { grace_period } if jail_slash => Some(grace_period),
+                    JailSlashPolicy::Disabled if jail_slash => {
+                        valopers.insert(valoper.clone());
+                        continue;
+                    }
+                    _ => None,
+                };
+                if let Some(grace_period) = grace_period {
+                    let pending = PendingJailSlash {
+                        slash_ratio,
+                        slash_amount: valinfo.slash_amount.amount,
+                        infraction_time: valinfo.infraction_time,
+                        due: env.block.time.seconds() + grace_period,
+                    };
+                    self.pending_jail_slashes.save(
+                        deps.storage,
+                        (valoper, valinfo.infraction_height),
+                        &pending,
+                    )?;
+                    delayed.push(valoper.clone());
+                    valopers.insert(valoper.clone());
+                    continue;
+                }
                 // Slash the validator, if bonded
                 let slash_msg = self.handle_slashing(
                     &env,
@@ -592,7 +1361,7 @@ impl ExternalStakingContract<'_> {
                     valinfo.infraction_time,
                 )?;
                 if let Some(msg) = slash_msg {
-                    msgs.push(msg)
+                    msgs.push(SubMsg::new(msg))
                 }
             }
             // Maintenance
@@ -602,6 +1371,8 @@ impl ExternalStakingContract<'_> {
         for valoper in tombstoned {
             self.val_set
                 .tombstone_validator(deps.storage, valoper, height, time)?;
+            // Queued stakes will never reach the consumer
+            msgs.extend(self.rollback_queued_stakes(deps.branch(), valoper)?);
             // Maintenance
             valopers.insert(valoper.clone());
         }
@@ -631,8 +1402,15 @@ impl ExternalStakingContract<'_> {
             // Maintenance
             valopers.insert((*valoper).clone());
         }
-        // Process unjailings. Does nothing at the moment, as we don't have a way to know if we the
-        // validator must go to the active or the unbonded state.
+        // Process unjailings. Validators back in the active set are reactivated. The others stay
+        // jailed, as we don't know if they must go to the active or the unbonded state.
+        // Stakes queued for them are released by the caller.
+        for valoper in reactivated {
+            self.val_set
+                .unjail_validator(deps.storage, valoper, height, time)?;
+            // Maintenance
+            valopers.insert(valoper.clone());
+        }
 
         // Process updates. Non-existent and tombstoned validators will be ignored.
         for AddValidator { valoper, pub_key } in updated {
@@ -641,6 +1419,12 @@ impl ExternalStakingContract<'_> {
             // Maintenance
             valopers.insert(valoper.clone());
         }
+        // Apply the delayed jail slashes that are due, and drop those of active validators
+        msgs.extend(
+            self.process_pending_jail_slashes(deps.storage, &env, &cfg)?
+                .into_iter()
+                .map(SubMsg::new),
+        );
         // Maintenance. Drain events that are older than unbonding period from now
         // Assumes time keeping is the same in both chains
         let max_time = env
@@ -651,6 +1435,10 @@ impl ExternalStakingContract<'_> {
         for valoper in valopers {
             self.val_set.drain_older(deps.storage, &valoper, max_time)?;
         }
+        let mut metrics = self.metrics.may_load(deps.storage)?.unwrap_or_default();
+        metrics.last_valset_update_height = Some(height);
+        metrics.last_valset_update_time = Some(time);
+        self.metrics.save(deps.storage, &metrics)?;
         let mut event = Event::new("valset_update");
         if !additions.is_empty() {
             event = event.add_attribute(
@@ -681,9 +1469,15 @@ impl ExternalStakingContract<'_> {
         if !unjailed.is_empty() {
             event = event.add_attribute("unjailed", unjailed.join(","));
         }
+        if !reactivated.is_empty() {
+            event = event.add_attribute("reactivated", reactivated.join(","));
+        }
         if !tombstoned.is_empty() {
             event = event.add_attribute("tombstoned", tombstoned.join(","));
         }
+        if !delayed.is_empty() {
+            event = event.add_attribute("jail_slashes_delayed", delayed.join(","));
+        }
         if !slashed.is_empty() {
             event = event.add_attribute(
                 "slashed",
@@ -697,915 +1491,4795 @@ impl ExternalStakingContract<'_> {
         Ok((event, msgs))
     }
 
-    /// Withdraws all of their released tokens to the calling user.
-    ///
-    /// Tokens to be claimed have to be unbond before by calling the `unbond` message, and
-    /// their unbonding period must have passed.
-    #[sv::msg(exec)]
-    pub fn withdraw_unbonded(&self, ctx: ExecCtx) -> Result<Response, ContractError> {
-        nonpayable(&ctx.info)?;
-
-        let config = self.config.load(ctx.deps.storage)?;
-
-        let stakes: Vec<_> = self
-            .stakes
-            .stake
-            .prefix(&ctx.info.sender)
-            .range(ctx.deps.storage, None, None, Order::Ascending)
-            .collect::<Result<_, _>>()?;
-
-        let released: Uint128 = stakes
+    /// Reconciles the validator set with an authoritative snapshot of the consumer's active
+    /// validators in the `(start_after, end]` range.
+    /// Validators in the snapshot are set as active (unless tombstoned), and any other active
+    /// validator in the range is marked as unbonded. Stakes queued for reactivated validators
+    /// are sent.
+    /// Resyncs never slash: slashing is only applied by the jail and tombstone updates carrying
+    /// the evidence, so replaying a snapshot cannot slash a validator twice.
+    /// In non-test code, this is called from `ibc_packet_receive`
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn valset_resync(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        height: u64,
+        time: u64,
+        start_after: Option<&str>,
+        end: Option<&str>,
+        validators: &[AddValidator],
+    ) -> Result<(Event, Vec<IbcMsg>), ContractError> {
+        let cfg = self.config.load(deps.storage)?;
+        let snapshot: HashSet<_> = validators.iter().map(|v| v.valoper.as_str()).collect();
+        let removals: Vec<_> = self
+            .val_set
+            .list_active_validators_in_range(deps.storage, start_after, end)?
             .into_iter()
-            .map(|(validator, mut stake)| -> Result<_, ContractError> {
-                let released = stake.release_pending(&ctx.env.block);
-
-                if !released.is_zero() {
-                    self.stakes.stake.save(
-                        ctx.deps.storage,
-                        (&ctx.info.sender, &validator),
-                        &stake,
-                    )?
+            .filter(|valoper| !snapshot.contains(valoper.as_str()))
+            .collect();
+        for valoper in &removals {
+            self.val_set
+                .remove_validator(deps.storage, valoper, height, time)?;
+        }
+        let mut msgs = vec![];
+        let mut added = vec![];
+        let mut unchanged = 0;
+        let mut skipped = vec![];
+        for AddValidator { valoper, pub_key } in validators {
+            match self.val_set.validator_state(deps.storage, valoper)? {
+                State::Active {} => unchanged += 1,
+                // Tombstoning is final
+                State::Tombstoned {} => {
+                    skipped.push(valoper.as_str());
+                    continue;
                 }
-
-                Ok(released)
-            })
-            .try_fold(Uint128::zero(), |acc, released| {
-                released.map(|released| released + acc)
-            })?;
-
-        let mut resp = Response::new()
-            .add_attribute("action", "withdraw_unbonded")
-            .add_attribute("owner", ctx.info.sender.to_string())
-            .add_attribute("amount", released.to_string());
-
-        if !released.is_zero() {
-            let release_msg = config.vault.release_cross_stake(
-                ctx.info.sender.into_string(),
-                coin(released.u128(), &config.denom),
-                vec![],
-            )?;
-
-            resp = resp.add_message(release_msg);
+                _ => added.push(valoper.as_str()),
+            }
+            self.val_set
+                .add_validator(deps.storage, valoper, pub_key, height, time)?;
+            msgs.extend(self.release_queued_stakes(deps.storage, &env, valoper)?);
         }
-
-        Ok(resp)
+        // Maintenance. Drain events that are older than unbonding period from now
+        let max_time = env
+            .block
+            .time
+            .seconds()
+            .saturating_sub(cfg.unbonding_period);
+        for valoper in removals.iter().map(String::as_str).chain(snapshot) {
+            self.val_set.drain_older(deps.storage, valoper, max_time)?;
+        }
+        let mut metrics = self.metrics.may_load(deps.storage)?.unwrap_or_default();
+        metrics.last_valset_update_height = Some(height);
+        metrics.last_valset_update_time = Some(time);
+        self.metrics.save(deps.storage, &metrics)?;
+
+        let mut event = Event::new("valset_resync")
+            .add_attribute("start_after", start_after.unwrap_or_default())
+            .add_attribute("end", end.unwrap_or_default())
+            .add_attribute("validators", validators.len().to_string())
+            .add_attribute("unchanged", unchanged.to_string());
+        if !added.is_empty() {
+            event = event.add_attribute("added", added.join(","));
+        }
+        if !removals.is_empty() {
+            event = event.add_attribute("unbonded", removals.join(","));
+        }
+        if !skipped.is_empty() {
+            event = event.add_attribute("tombstoned", skipped.join(","));
+        }
+        Ok((event, msgs))
     }
 
-    /// Distributes reward among users staking via particular validator. Distribution is performed
-    /// proportionally to amount of tokens staked by user.
-    /// In test code, this is called from `test_distribute_rewards`.
-    /// In non-test code, this is called from `ibc_packet_receive`
-    pub(crate) fn distribute_rewards(
+    /// Sets the contract stakes can be exported to. `None` disables exports.
+    /// Can only be called by governance (sudo).
+    #[sv::msg(sudo)]
+    pub fn set_migration_target(
         &self,
-        mut deps: DepsMut,
-        validator: &str,
-        rewards: Coin,
-    ) -> Result<Event, ContractError> {
-        // check we have the proper denom
-        let config = self.config.load(deps.storage)?;
-        ensure_eq!(
-            rewards.denom,
-            config.rewards_denom,
-            PaymentError::MissingDenom(rewards.denom)
-        );
-
-        self.distribute_rewards_unchecked(&mut deps, validator, rewards.amount)
+        ctx: SudoCtx,
+        target: Option<String>,
+    ) -> Result<Response, ContractError> {
+        let resp = Response::new().add_attribute("action", "set_migration_target");
+        match target {
+            Some(target) => {
+                let target = ctx.deps.api.addr_validate(&target)?;
+                self.migration_target.save(ctx.deps.storage, &target)?;
+                Ok(resp.add_attribute("target", target))
+            }
+            None => {
+                self.migration_target.remove(ctx.deps.storage);
+                Ok(resp)
+            }
+        }
     }
 
-    fn distribute_rewards_unchecked(
+    /// Sets the contract stakes can be imported from. `None` disables imports.
+    /// Can only be called by governance (sudo).
+    #[sv::msg(sudo)]
+    pub fn set_migration_source(
         &self,
-        deps: &mut DepsMut,
-        validator: &str,
-        amount: Uint128,
-    ) -> Result<Event, ContractError> {
-        let mut distribution = self
-            .distribution
-            .may_load(deps.storage, validator)?
-            .unwrap_or_default();
-
-        let total_stake = Uint256::from(distribution.total_stake);
-        let points_distributed =
-            Uint256::from(amount) * DISTRIBUTION_POINTS_SCALE + distribution.points_leftover;
-        let points_per_stake = points_distributed / total_stake;
+        ctx: SudoCtx,
+        source: Option<String>,
+    ) -> Result<Response, ContractError> {
+        let resp = Response::new().add_attribute("action", "set_migration_source");
+        match source {
+            Some(source) => {
+                let source = ctx.deps.api.addr_validate(&source)?;
+                self.migration_source.save(ctx.deps.storage, &source)?;
+                Ok(resp.add_attribute("source", source))
+            }
+            None => {
+                self.migration_source.remove(ctx.deps.storage);
+                Ok(resp)
+            }
+        }
+    }
 
-        distribution.points_leftover = points_distributed - points_per_stake * total_stake;
-        distribution.points_per_stake += points_per_stake;
+    /// Replaces the endpoint the consumer is allowed to connect from, e.g. after the consumer
+    /// redeployed its converter on a new connection.
+    /// Only possible while no channel is open. Can only be called by governance (sudo).
+    #[sv::msg(sudo)]
+    pub fn set_authorized_endpoint(
+        &self,
+        ctx: SudoCtx,
+        endpoint: crate::msg::AuthorizedEndpoint,
+    ) -> Result<Response, ContractError> {
+        let evt = self.replace_authorized_endpoint(ctx.deps.storage, endpoint)?;
 
-        self.distribution
-            .save(deps.storage, validator, &distribution)?;
+        Ok(Response::new()
+            .add_event(evt)
+            .add_attribute("action", "set_authorized_endpoint"))
+    }
 
-        let event = Event::new("distribute_rewards")
-            .add_attribute("validator", validator)
-            .add_attribute("amount", amount.to_string());
+    /// Replaces the endpoint the consumer is allowed to connect from, so that a new channel
+    /// handshake from it can succeed, e.g. after the consumer migrated its converter or
+    /// connection.
+    /// Only possible while no channel is open. Can only be called by the contract admin.
+    #[sv::msg(exec)]
+    pub fn update_authorized_endpoint(
+        &self,
+        ctx: ExecCtx,
+        endpoint: crate::msg::AuthorizedEndpoint,
+    ) -> Result<Response, ContractError> {
+        nonpayable(&ctx.info)?;
+        self.ensure_admin(ctx.deps.as_ref(), &ctx.env, &ctx.info.sender)?;
+        let evt = self.replace_authorized_endpoint(ctx.deps.storage, endpoint)?;
 
-        Ok(event)
+        Ok(Response::new()
+            .add_event(evt)
+            .add_attribute("action", "update_authorized_endpoint"))
     }
 
-    pub(crate) fn distribute_rewards_batch(
+    /// Validates and saves the new authorized endpoint, returning an event with the old and the
+    /// new endpoints. Fails if a channel is open.
+    fn replace_authorized_endpoint(
         &self,
-        mut deps: DepsMut,
-        rewards: &[RewardInfo],
-        denom: &str,
-    ) -> Result<Vec<Event>, ContractError> {
-        // check we have the proper denom
-        let config = self.config.load(deps.storage)?;
-        ensure_eq!(
-            denom,
-            config.rewards_denom,
-            ContractError::InvalidDenom(config.rewards_denom)
+        storage: &mut dyn Storage,
+        endpoint: crate::msg::AuthorizedEndpoint,
+    ) -> Result<Event, ContractError> {
+        ensure!(
+            IBC_CHANNEL.may_load(storage)?.is_none(),
+            ContractError::IbcChannelAlreadyOpen
         );
+        endpoint.validate()?;
+        let old = crate::ibc::AUTH_ENDPOINT.load(storage)?;
+        crate::ibc::AUTH_ENDPOINT.save(storage, &endpoint)?;
+
+        Ok(Event::new("authorized_endpoint_update")
+            .add_attribute("old_connection_id", old.connection_id)
+            .add_attribute("old_port_id", old.port_id)
+            .add_attribute("connection_id", endpoint.connection_id)
+            .add_attribute("port_id", endpoint.port_id))
+    }
 
-        rewards
-            .iter()
-            .map(|reward_info| {
-                self.distribute_rewards_unchecked(
-                    &mut deps,
-                    &reward_info.validator,
-                    reward_info.reward,
-                )
-            })
-            .collect()
+    /// Clears the stored IBC channel, e.g. after it expired on the consumer side without a
+    /// close callback. All stakes are unbonded, like on a channel close, and a new channel can
+    /// then be opened from the authorized endpoint.
+    /// Can only be called by the contract admin.
+    #[sv::msg(exec)]
+    pub fn reset_ibc_channel(&self, mut ctx: ExecCtx) -> Result<Response, ContractError> {
+        nonpayable(&ctx.info)?;
+        self.ensure_admin(ctx.deps.as_ref(), &ctx.env, &ctx.info.sender)?;
+
+        let channel = IBC_CHANNEL
+            .may_load(ctx.deps.storage)?
+            .ok_or(ContractError::NoIbcChannel)?;
+        let evt = self.handle_close_channel(ctx.deps.branch(), ctx.env)?;
+        IBC_CHANNEL.remove(ctx.deps.storage);
+
+        Ok(Response::new()
+            .add_event(evt)
+            .add_attribute("action", "reset_ibc_channel")
+            .add_attribute("channel_id", channel.endpoint.channel_id))
     }
 
-    /// Withdraw rewards from staking via given validator
+    /// Resends up to `limit` timed out packets whose backoff is over, in tx order. Their txs
+    /// stay pending until the packets are acked, or rolled back once they timed out
+    /// `MAX_PACKET_RETRIES` more times.
+    /// Anyone can call it.
     #[sv::msg(exec)]
-    pub fn withdraw_rewards(
+    pub fn retry_packets(
         &self,
         ctx: ExecCtx,
-        validator: String,
-        /// Address on the consumer side to receive the rewards
-        remote_recipient: String,
+        limit: Option<u32>,
     ) -> Result<Response, ContractError> {
         nonpayable(&ctx.info)?;
+        let limit = clamp_page_limit(limit);
+        let now = ctx.env.block.time;
 
-        let stake = self
-            .stakes
-            .stake
-            .may_load(ctx.deps.storage, (&ctx.info.sender, &validator))?
-            .unwrap_or_default();
+        let due: Vec<(u64, QueuedPacket)> = self
+            .retry_queue
+            .range(ctx.deps.storage, None, None, Order::Ascending)
+            .filter(|item| !matches!(item, Ok((_, queued)) if queued.retry_at > now))
+            .take(limit)
+            .collect::<StdResult<_>>()?;
+        ensure!(!due.is_empty(), ContractError::NoPacketsToRetry);
 
-        let distribution = self
-            .distribution
-            .may_load(ctx.deps.storage, &validator)?
-            .unwrap_or_default();
+        #[allow(unused_mut)]
+        let mut resp = Response::new().add_attribute("action", "retry_packets");
+        let channel = IBC_CHANNEL.load(ctx.deps.storage)?;
+        let mut tx_ids = Vec::with_capacity(due.len());
+        for (tx_id, queued) in due {
+            self.retry_queue.remove(ctx.deps.storage, tx_id);
+            self.packet_retries
+                .save(ctx.deps.storage, tx_id, &(queued.attempts + 1))?;
+            let msg = IbcMsg::SendPacket {
+                channel_id: channel.endpoint.channel_id.clone(),
+                data: to_json_binary(&queued.packet)?,
+                timeout: packet_timeout(&ctx.env),
+            };
+            // send packet if we are ibc enabled
+            #[cfg(not(any(test, feature = "mt")))]
+            {
+                resp = resp.add_message(msg);
+            }
+            #[cfg(any(test, feature = "mt"))]
+            {
+                let _ = msg;
+            }
+            tx_ids.push(tx_id.to_string());
+        }
 
-        let amount = Self::calculate_reward(&stake, &distribution)?;
+        Ok(resp.add_attribute("tx_ids", tx_ids.join(",")))
+    }
 
-        if amount.is_zero() {
-            return Err(ContractError::NoRewards);
+    /// Sends the total collateral liened to this contract in the vault to the consumer, which
+    /// uses it as a sanity bound on the stake it converts. At most once every
+    /// `COLLATERAL_SUMMARY_INTERVAL`.
+    /// Anyone can call it.
+    #[sv::msg(exec)]
+    pub fn send_collateral_summary(&self, ctx: ExecCtx) -> Result<Response, ContractError> {
+        nonpayable(&ctx.info)?;
+        if let Some(next) = self.next_collateral_summary(ctx.deps.storage)? {
+            ensure!(
+                ctx.env.block.time >= next,
+                ContractError::CollateralSummaryNotDue(next)
+            );
         }
+        let (msg, total_collateral) = self.collateral_summary_packet(ctx.deps, &ctx.env)?;
 
         #[allow(unused_mut)]
         let mut resp = Response::new()
-            .add_attribute("action", "withdraw_rewards")
-            .add_attribute("owner", ctx.info.sender.to_string())
-            .add_attribute("validator", &validator)
-            .add_attribute("recipient", &remote_recipient)
-            .add_attribute("amount", amount.to_string());
-
-        // prepare the pending tx
-        let tx_id = self.next_tx_id(ctx.deps.storage)?;
-        let new_tx = Tx::InFlightTransferFunds {
-            id: tx_id,
-            amount,
-            staker: ctx.info.sender,
-            validator,
-        };
-        self.pending_txs.save(ctx.deps.storage, tx_id, &new_tx)?;
-
-        // Crate the IBC packet
-        let config = self.config.load(ctx.deps.storage)?;
-        let rewards = coin(amount.u128(), config.rewards_denom);
-        let packet = ProviderPacket::TransferRewards {
-            rewards,
-            recipient: remote_recipient,
-            tx_id,
-        };
-        let channel_id = IBC_CHANNEL.load(ctx.deps.storage)?.endpoint.channel_id;
-        let send_msg = IbcMsg::SendPacket {
-            channel_id,
-            data: to_json_binary(&packet)?,
-            timeout: packet_timeout(&ctx.env),
-        };
-
-        // TODO: send in test code when we can handle it
+            .add_attribute("action", "send_collateral_summary")
+            .add_attribute("total_collateral", total_collateral.amount.to_string());
+        // send packet if we are ibc enabled
         #[cfg(not(any(test, feature = "mt")))]
         {
-            resp = resp.add_message(send_msg);
+            resp = resp.add_message(msg);
         }
         #[cfg(any(test, feature = "mt"))]
         {
-            let _ = send_msg;
+            let _ = msg;
         }
 
         Ok(resp)
     }
 
-    /// In test code, this is called from `test_rollback_withdraw_rewards`.
-    /// In non-test code, this is called from `ibc_packet_ack` or `ibc_packet_timeout`
-    pub(crate) fn rollback_withdraw_rewards(
+    /// Enables or disables sending the collateral summary to the consumer along with the
+    /// packets received from it, whenever one is due. `send_collateral_summary` can still be
+    /// called when disabled.
+    /// Can only be called by governance (sudo).
+    #[sv::msg(sudo)]
+    pub fn set_collateral_summaries(
         &self,
-        deps: DepsMut,
-        tx_id: u64,
-    ) -> Result<(), ContractError> {
-        let tx = self.pending_txs.load(deps.storage, tx_id)?;
-
-        // Verify tx is of the right type and remove it from the map
-        match tx {
-            Tx::InFlightTransferFunds { .. } => {
-                self.pending_txs.remove(deps.storage, tx_id);
-            }
-            _ => {
-                return Err(ContractError::WrongTypeTx(tx_id, tx));
-            }
-        };
+        ctx: SudoCtx,
+        enabled: bool,
+    ) -> Result<Response, ContractError> {
+        self.collateral_summaries.save(ctx.deps.storage, &enabled)?;
+        let resp = Response::new()
+            .add_attribute("action", "set_collateral_summaries")
+            .add_attribute("enabled", enabled.to_string());
+        Ok(resp)
+    }
 
-        Ok(())
+    /// Returns when the next collateral summary can be sent, if one was sent already
+    fn next_collateral_summary(&self, storage: &dyn Storage) -> StdResult<Option<Timestamp>> {
+        Ok(self
+            .last_collateral_summary
+            .may_load(storage)?
+            .map(|last| last.plus_seconds(COLLATERAL_SUMMARY_INTERVAL)))
     }
 
-    /// In test code, this is called from `test_commit_withdraw_rewards`.
-    /// In non-test code, this is called from `ibc_packet_ack`
-    pub(crate) fn commit_withdraw_rewards(
+    /// Builds the collateral summary packet, with the collateral liened to this contract in the
+    /// vault, and records it as sent
+    fn collateral_summary_packet(
         &self,
         deps: DepsMut,
-        tx_id: u64,
-    ) -> Result<(), ContractError> {
-        // Load tx
-        let tx = self.pending_txs.load(deps.storage, tx_id)?;
-        self.pending_txs.remove(deps.storage, tx_id);
+        env: &Env,
+    ) -> Result<(IbcMsg, Coin), ContractError> {
+        let config = self.config.load(deps.storage)?;
+        let total = config.vault.lienholder_total(
+            deps.as_ref(),
+            env.contract.address.to_string(),
+            config.denom.clone(),
+        )?;
+        let total_collateral = coin(total.high.u128(), config.denom);
+        self.last_collateral_summary
+            .save(deps.storage, &env.block.time)?;
 
-        // Verify tx is of the right type and get data
-        let (amount, staker, validator) = match tx {
-            Tx::InFlightTransferFunds {
-                amount,
-                staker,
-                validator,
-                ..
-            } => (amount, staker, validator),
-            _ => {
-                return Err(ContractError::WrongTypeTx(tx_id, tx));
-            }
+        let channel = IBC_CHANNEL.load(deps.storage)?;
+        let packet = ProviderPacket::CollateralSummary {
+            total_collateral: total_collateral.clone(),
         };
+        let msg = IbcMsg::SendPacket {
+            channel_id: channel.endpoint.channel_id,
+            data: to_json_binary(&packet)?,
+            timeout: packet_timeout(env),
+        };
+        Ok((msg, total_collateral))
+    }
 
-        // Update withdrawn_funds to hold this transfer
-        let mut stake = self
-            .stakes
-            .stake
-            .load(deps.storage, (&staker, &validator))?;
-        stake.withdrawn_funds += amount;
-
-        self.stakes
-            .stake
-            .save(deps.storage, (&staker, &validator), &stake)?;
-
-        Ok(())
-    }
-
-    /// Slashes a validator.
-    ///
-    /// In test code, this is called from `test_handle_slashing`.
-    /// In non-test code, this is being called from `ibc_packet_receive` (in the `ConsumerPacket::RemoveValidators`
-    /// handler)
-    #[allow(clippy::too_many_arguments)]
-    pub(crate) fn handle_slashing(
+    /// Returns the collateral summary packet to send along with a packet from the consumer, if
+    /// periodic summaries are enabled and one is due. Failures are ignored, so as not to fail
+    /// the received packet
+    pub(crate) fn scheduled_collateral_summary(
         &self,
+        deps: DepsMut,
         env: &Env,
-        storage: &mut dyn Storage,
-        config: &Config,
-        validator: &str,
-        slash_ratio: Decimal,
-        slash_amount: Uint128,
-        infraction_time: u64,
-    ) -> Result<Option<WasmMsg>, ContractError> {
-        // Get the list of users staking via this validator
-        // FIXME: It should be over the *historical* (at infraction height) stake. Not over the *current* stake
-        let users = self
-            .stakes
-            .stake
-            .idx
-            .rev
-            .sub_prefix(validator.to_string())
-            .range(storage, None, None, Order::Ascending)
-            .map(|item| {
-                let ((user, _), stake) = item?;
-                Ok::<_, ContractError>((user, stake))
-            })
-            .collect::<Result<Vec<_>, _>>()?;
-        if users.is_empty() {
+    ) -> StdResult<Option<IbcMsg>> {
+        if !self
+            .collateral_summaries
+            .may_load(deps.storage)?
+            .unwrap_or_default()
+        {
             return Ok(None);
         }
-        // Compute effective slash ratio
-        let total_amount = users
-            .iter()
-            .map(|(_, stake)| stake.stake.high())
-            .sum::<Uint128>();
-        let effective_slash_ratio = Decimal::from_ratio(slash_amount, total_amount);
-
-        // Slash their stake in passing
-        let mut slash_infos = vec![];
-        for (user, ref mut stake) in users {
-            let stake_low = stake.stake.low();
-            let stake_high = stake.stake.high();
-            // Calculating slashing with always the `high` value of the range goes against the user
-            // in some scenario (pending stakes while slashing); but the scenario is relatively
-            // unlikely.
-            if stake_high.is_zero() {
-                continue;
+        if let Some(next) = self.next_collateral_summary(deps.storage)? {
+            if env.block.time < next {
+                return Ok(None);
             }
-            let stake_slash = stake_high * effective_slash_ratio;
-            // Requires proper saturating methods in commit/rollback_stake/unstake
-            stake.stake = ValueRange::new(
-                stake_low.saturating_sub(stake_slash),
-                stake_high - stake_slash,
-            );
-
-            // Distribution alignment
-            let mut distribution = self
-                .distribution
-                .may_load(storage, validator)?
-                .unwrap_or_default();
-            stake
-                .points_alignment
-                .stake_decreased(stake_slash, distribution.points_per_stake);
-            distribution.total_stake = distribution.total_stake.saturating_sub(stake_slash); // Don't fail if pending bond tx
-            self.distribution.save(storage, validator, &distribution)?;
-
-            // Slash the unbondings. We use the nominal slash ratio here, like in the blockchain
-            let pending_slashed = stake.slash_pending(
-                &env.block,
-                slash_ratio,
-                config.unbonding_period,
-                infraction_time,
-            );
-
-            self.stakes.stake.save(storage, (&user, validator), stake)?;
-
-            slash_infos.push(SlashInfo {
-                user: user.to_string(),
-                slash: stake_slash + pending_slashed,
-            });
-        }
-        if slash_infos.is_empty() {
-            return Ok(None);
         }
-
-        // Route associated users to vault for slashing of their collateral
-        let msg = config
-            .vault
-            .process_cross_slashing(slash_infos, validator)?;
-        Ok(Some(msg))
-    }
-
-    /// Queries for contract configuration
-    #[sv::msg(query)]
-    pub fn config(&self, ctx: QueryCtx) -> Result<ConfigResponse, ContractError> {
-        let resp = self.config.load(ctx.deps.storage)?.into();
-        Ok(resp)
+        Ok(self
+            .collateral_summary_packet(deps, env)
+            .ok()
+            .map(|(msg, _)| msg))
     }
 
-    /// Query for the endpoint that can connect
-    #[sv::msg(query)]
-    pub fn authorized_endpoint(
+    /// Sets the default max total stake of a single user via this contract. `None` removes
+    /// the default cap. Users can override it with `set_max_exposure`.
+    /// Can only be called by governance (sudo).
+    #[sv::msg(sudo)]
+    pub fn set_default_max_exposure(
         &self,
-        ctx: QueryCtx,
-    ) -> Result<AuthorizedEndpointResponse, ContractError> {
-        let resp = crate::ibc::AUTH_ENDPOINT.load(ctx.deps.storage)?;
-        Ok(resp)
+        ctx: SudoCtx,
+        cap: Option<Uint128>,
+    ) -> Result<Response, ContractError> {
+        let resp = Response::new().add_attribute("action", "set_default_max_exposure");
+        match cap {
+            Some(cap) => {
+                self.default_max_exposure.save(ctx.deps.storage, &cap)?;
+                Ok(resp.add_attribute("cap", cap))
+            }
+            None => {
+                self.default_max_exposure.remove(ctx.deps.storage);
+                Ok(resp)
+            }
+        }
     }
 
-    /// Query for the endpoint that can connect
-    #[sv::msg(query)]
-    pub fn ibc_channel(&self, ctx: QueryCtx) -> Result<IbcChannelResponse, ContractError> {
-        let channel = IBC_CHANNEL.load(ctx.deps.storage)?;
-        Ok(IbcChannelResponse { channel })
+    /// Sets the max total stake of the calling user via this contract. New stakes over the cap
+    /// are rejected, existing ones are not affected. `None` falls back to the governance default.
+    #[sv::msg(exec)]
+    pub fn set_max_exposure(
+        &self,
+        ctx: ExecCtx,
+        cap: Option<Uint128>,
+    ) -> Result<Response, ContractError> {
+        nonpayable(&ctx.info)?;
+        let resp = Response::new()
+            .add_attribute("action", "set_max_exposure")
+            .add_attribute("owner", ctx.info.sender.as_str());
+        match cap {
+            Some(cap) => {
+                self.max_exposures
+                    .save(ctx.deps.storage, &ctx.info.sender, &cap)?;
+                Ok(resp.add_attribute("cap", cap))
+            }
+            None => {
+                self.max_exposures
+                    .remove(ctx.deps.storage, &ctx.info.sender);
+                Ok(resp)
+            }
+        }
     }
 
-    /// Show all external validators that we know to be active (and can delegate to)
-    #[sv::msg(query)]
-    pub fn list_active_validators(
+    /// Pauses (or resumes) new stake to the given consumer validators, e.g. during an
+    /// investigation. Unstaking and rewards are not affected.
+    /// Can only be called by the contract admin.
+    #[sv::msg(exec)]
+    pub fn set_validators_paused(
         &self,
-        ctx: QueryCtx,
-        start_after: Option<String>,
-        limit: Option<u64>,
-    ) -> Result<ListActiveValidatorsResponse, ContractError> {
-        let limit = limit.unwrap_or(100) as usize;
-        let validators =
-            self.val_set
-                .list_active_validators(ctx.deps.storage, start_after.as_deref(), limit)?;
-        Ok(ListActiveValidatorsResponse { validators })
+        ctx: ExecCtx,
+        validators: Vec<String>,
+        paused: bool,
+    ) -> Result<Response, ContractError> {
+        nonpayable(&ctx.info)?;
+        self.ensure_admin(ctx.deps.as_ref(), &ctx.env, &ctx.info.sender)?;
+
+        for validator in &validators {
+            if paused {
+                self.paused_validators
+                    .save(ctx.deps.storage, validator, &())?;
+            } else {
+                self.paused_validators.remove(ctx.deps.storage, validator);
+            }
+        }
+
+        Ok(Response::new()
+            .add_attribute("action", "set_validators_paused")
+            .add_attribute("validators", validators.join(","))
+            .add_attribute("paused", paused.to_string()))
     }
 
-    /// Show all external validators that we know about, along with their state.
-    #[sv::msg(query)]
-    pub fn list_validators(
+    /// Sets the protocol fee taken on withdrawn rewards. `None` removes the fee. Fees already
+    /// accrued are kept, and can still be sent to the treasury.
+    /// Can only be called by the contract admin.
+    #[sv::msg(exec)]
+    pub fn set_fee_config(
         &self,
-        ctx: QueryCtx,
-        start_after: Option<String>,
-        limit: Option<u64>,
-    ) -> Result<ListValidatorsResponse, ContractError> {
-        let limit = limit.unwrap_or(100) as usize;
-        let validators = self
-            .val_set
-            .list_validators(ctx.deps.storage, start_after.as_deref(), limit)?
-            .into_iter()
-            .map(|(valoper, state)| {
-                Ok(crate::msg::ValidatorState {
-                    validator: valoper,
-                    state,
-                })
-            })
-            .collect::<StdResult<Vec<_>>>()?;
-        Ok(ListValidatorsResponse { validators })
+        ctx: ExecCtx,
+        fee_config: Option<FeeConfig>,
+    ) -> Result<Response, ContractError> {
+        nonpayable(&ctx.info)?;
+        self.ensure_admin(ctx.deps.as_ref(), &ctx.env, &ctx.info.sender)?;
+
+        let resp = Response::new().add_attribute("action", "set_fee_config");
+        match fee_config {
+            Some(fee_config) => {
+                fee_config.validate()?;
+                self.fee_config.save(ctx.deps.storage, &fee_config)?;
+                Ok(resp
+                    .add_attribute("commission", fee_config.commission.to_string())
+                    .add_attribute("treasury", fee_config.treasury))
+            }
+            None => {
+                self.fee_config.remove(ctx.deps.storage);
+                Ok(resp)
+            }
+        }
     }
 
-    /// Queries for stake info
-    ///
-    /// If stake does not exist for (user, validator) pair, the zero-stake is returned
-    #[sv::msg(query)]
-    pub fn stake(
+    /// Sets how slashes of jailed (but not tombstoned) validators are applied. Slashes already
+    /// delayed keep their due time.
+    /// Can only be called by the contract admin.
+    #[sv::msg(exec)]
+    pub fn set_jail_slash_policy(
         &self,
-        ctx: QueryCtx,
-        user: String,
-        validator: String,
-    ) -> Result<Stake, ContractError> {
-        let user = ctx.deps.api.addr_validate(&user)?;
-        let stake = self
-            .stakes
-            .stake
-            .may_load(ctx.deps.storage, (&user, &validator))?
-            .unwrap_or_default();
+        ctx: ExecCtx,
+        policy: JailSlashPolicy,
+    ) -> Result<Response, ContractError> {
+        nonpayable(&ctx.info)?;
+        self.ensure_admin(ctx.deps.as_ref(), &ctx.env, &ctx.info.sender)?;
 
-        Ok(stake)
+        let mut config = self.config.load(ctx.deps.storage)?;
+        config.jail_slash_policy = policy;
+        self.config.save(ctx.deps.storage, &config)?;
+
+        Ok(Response::new().add_attribute("action", "set_jail_slash_policy"))
     }
 
-    /// Paginated list of user stakes.
-    ///
-    /// `start_after` is the last validator of previous page
-    #[sv::msg(query)]
-    pub fn stakes(
+    /// Sets the shortened unbonding periods for unstakes from jailed validators, and for pending
+    /// unbonds while the consumer is halted. Unbonds already scheduled keep their release time,
+    /// unless the consumer halts.
+    /// Can only be called by the contract admin.
+    #[sv::msg(exec)]
+    pub fn set_unbonding_overrides(
         &self,
-        ctx: QueryCtx,
-        user: String,
-        start_after: Option<String>,
-        limit: Option<u32>,
-    ) -> Result<StakesResponse, ContractError> {
-        let limit = clamp_page_limit(limit);
-        let user = ctx.deps.api.addr_validate(&user)?;
+        ctx: ExecCtx,
+        overrides: UnbondingOverrides,
+    ) -> Result<Response, ContractError> {
+        nonpayable(&ctx.info)?;
+        self.ensure_admin(ctx.deps.as_ref(), &ctx.env, &ctx.info.sender)?;
 
-        let bound = start_after.as_deref().and_then(Bounder::exclusive_bound);
+        let mut config = self.config.load(ctx.deps.storage)?;
+        ensure!(
+            [overrides.jailed, overrides.consumer_halted]
+                .into_iter()
+                .flatten()
+                .all(|period| period <= config.unbonding_period),
+            ContractError::InvalidUnbondingOverride(config.unbonding_period)
+        );
+        config.unbonding_overrides = overrides;
+        self.config.save(ctx.deps.storage, &config)?;
 
-        let stakes = self
-            .stakes
-            .stake
-            .prefix(&user)
-            .range(ctx.deps.storage, bound, None, Order::Ascending)
-            .map(|item| {
-                item.map(|(validator, stake)| {
-                    Ok::<StakeInfo, ContractError>(StakeInfo {
-                        owner: user.to_string(),
-                        validator,
-                        stake,
-                    })
-                })?
-            })
-            .take(limit)
-            .collect::<Result<_, _>>()?;
+        Ok(Response::new().add_attribute("action", "set_unbonding_overrides"))
+    }
 
-        let resp = StakesResponse { stakes };
+    /// Sends the accrued protocol fees to the treasury, on the consumer side.
+    /// Anyone can call this.
+    #[sv::msg(exec)]
+    pub fn withdraw_fees(&self, ctx: ExecCtx) -> Result<Response, ContractError> {
+        nonpayable(&ctx.info)?;
 
-        Ok(resp)
-    }
+        let fee_config = self
+            .fee_config
+            .may_load(ctx.deps.storage)?
+            .ok_or(ContractError::NoFeeConfig)?;
+        let amount = self
+            .accrued_fees
+            .may_load(ctx.deps.storage)?
+            .unwrap_or_default();
+        if amount.is_zero() {
+            return Err(ContractError::NoFees);
+        }
+        self.accrued_fees.save(ctx.deps.storage, &Uint128::zero())?;
 
-    /// Queries a pending tx.
-    #[sv::msg(query)]
-    fn pending_tx(&self, ctx: QueryCtx, tx_id: u64) -> Result<TxResponse, ContractError> {
-        let resp = self.pending_txs.load(ctx.deps.storage, tx_id)?;
-        Ok(resp)
-    }
+        // Fee transfers are tracked apart from the stakers' txs, and accrued again if they fail
+        let tx_id = self.next_tx_id(ctx.deps.storage)?;
+        self.fee_transfers.save(ctx.deps.storage, tx_id, &amount)?;
 
-    /// Queries for all pending txs.
-    /// Reports txs in descending order (newest first).
-    /// `start_after` is the last tx id included in previous page
-    #[sv::msg(query)]
-    fn all_pending_txs_desc(
-        &self,
-        ctx: QueryCtx,
-        start_after: Option<u64>,
-        limit: Option<u32>,
-    ) -> Result<AllTxsResponse, ContractError> {
-        let limit = clamp_page_limit(limit);
-        let bound = start_after.and_then(Bounder::exclusive_bound);
+        #[allow(unused_mut)]
+        let mut resp = Response::new()
+            .add_attribute("action", "withdraw_fees")
+            .add_attribute("treasury", &fee_config.treasury)
+            .add_attribute("amount", amount.to_string())
+            .add_attribute("tx_id", tx_id.to_string());
 
-        let txs = self
-            .pending_txs
-            .range(ctx.deps.storage, None, bound, Order::Descending)
-            .map(|item| {
-                let (_id, tx) = item?;
-                Ok::<TxResponse, ContractError>(tx)
-            })
-            .take(limit)
-            .collect::<Result<_, _>>()?;
+        let config = self.config.load(ctx.deps.storage)?;
+        let packet = ProviderPacket::TransferRewards {
+            rewards: coin(amount.u128(), config.rewards_denom),
+            recipient: fee_config.treasury,
+            tx_id,
+        };
+        let channel_id = IBC_CHANNEL.load(ctx.deps.storage)?.endpoint.channel_id;
+        let send_msg = IbcMsg::SendPacket {
+            channel_id,
+            data: to_json_binary(&packet)?,
+            timeout: packet_timeout(&ctx.env),
+        };
 
-        let resp = AllTxsResponse { txs };
+        #[cfg(not(any(test, feature = "mt")))]
+        {
+            resp = resp.add_message(send_msg);
+        }
+        #[cfg(any(test, feature = "mt"))]
+        {
+            let _ = send_msg;
+        }
 
         Ok(resp)
     }
 
-    /// Returns how much rewards are to be withdrawn by particular user, from the particular
-    /// validator staking
-    #[sv::msg(query)]
-    pub fn pending_rewards(
+    /// Drops the valset CRDT history of validators that have been unbonded or tombstoned for
+    /// longer than the unbonding period, and that nothing refers to anymore (no stakes, pending
+    /// unbonds, queued stakes or delayed jail slashes).
+    /// Scans up to `limit` validators after `start_after`. The last one scanned is returned in
+    /// the `last` attribute, to continue from.
+    /// Anyone can call this.
+    #[sv::msg(exec)]
+    pub fn prune_validators(
         &self,
-        ctx: QueryCtx,
+        ctx: ExecCtx,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> Result<Response, ContractError> {
+        nonpayable(&ctx.info)?;
+
+        let config = self.config.load(ctx.deps.storage)?;
+        let max_time = ctx
+            .env
+            .block
+            .time
+            .seconds()
+            .saturating_sub(config.unbonding_period);
+        let (inactive, last) = self.val_set.inactive_validators(
+            ctx.deps.storage,
+            start_after.as_deref(),
+            clamp_page_limit(limit),
+            max_time,
+        )?;
+
+        let mut pruned = vec![];
+        for valoper in inactive {
+            if self.is_validator_referenced(ctx.deps.storage, &valoper)? {
+                continue;
+            }
+            self.val_set.prune_validator(ctx.deps.storage, &valoper);
+            pruned.push(valoper);
+        }
+
+        Ok(Response::new()
+            .add_attribute("action", "prune_validators")
+            .add_attribute("pruned", pruned.join(","))
+            .add_attribute("last", last.unwrap_or_default()))
+    }
+
+    /// Whether any stake (including pending unbonds), queued stake or delayed jail slash still
+    /// refers to `validator`
+    fn is_validator_referenced(&self, storage: &dyn Storage, validator: &str) -> StdResult<bool> {
+        let staked = self
+            .stakes
+            .stake
+            .idx
+            .rev
+            .sub_prefix(validator.to_string())
+            .range_raw(storage, None, None, Order::Ascending)
+            .next()
+            .is_some();
+        let queued = self
+            .queued_stakes
+            .prefix(validator)
+            .range_raw(storage, None, None, Order::Ascending)
+            .next()
+            .is_some();
+        let jail_slashed = self
+            .pending_jail_slashes
+            .prefix(validator)
+            .range_raw(storage, None, None, Order::Ascending)
+            .next()
+            .is_some();
+        Ok(staked || queued || jail_slashed)
+    }
+
+    /// Enables sweeping of rewards unclaimed for longer than `claim_period` to `recipient`, on the
+    /// consumer side. `None` disables it, and drops the notices already sent.
+    /// Can only be called by the contract admin.
+    #[sv::msg(exec)]
+    pub fn set_rewards_sweep(
+        &self,
+        ctx: ExecCtx,
+        config: Option<RewardsSweepConfig>,
+    ) -> Result<Response, ContractError> {
+        nonpayable(&ctx.info)?;
+        self.ensure_admin(ctx.deps.as_ref(), &ctx.env, &ctx.info.sender)?;
+
+        let resp = Response::new().add_attribute("action", "set_rewards_sweep");
+        match config {
+            Some(config) => {
+                config.validate()?;
+                if !self.rewards_sweep_since.exists(ctx.deps.storage) {
+                    self.rewards_sweep_since
+                        .save(ctx.deps.storage, &ctx.env.block.time)?;
+                }
+                self.rewards_sweep.save(ctx.deps.storage, &config)?;
+                Ok(resp
+                    .add_attribute("claim_period", config.claim_period.to_string())
+                    .add_attribute("notice_period", config.notice_period.to_string())
+                    .add_attribute("recipient", config.recipient))
+            }
+            None => {
+                self.rewards_sweep.remove(ctx.deps.storage);
+                self.rewards_sweep_since.remove(ctx.deps.storage);
+                self.sweep_notices.clear(ctx.deps.storage);
+                Ok(resp)
+            }
+        }
+    }
+
+    /// Notifies `user` that their rewards on `validator` were not claimed for longer than the
+    /// claim period, and will be swept once the notice period is over unless claimed.
+    /// Anyone can call this.
+    #[sv::msg(exec)]
+    pub fn notify_unclaimed_rewards(
+        &self,
+        ctx: ExecCtx,
         user: String,
         validator: String,
-    ) -> Result<PendingRewards, ContractError> {
+    ) -> Result<Response, ContractError> {
+        nonpayable(&ctx.info)?;
+
+        let sweep = self
+            .rewards_sweep
+            .may_load(ctx.deps.storage)?
+            .ok_or(ContractError::NoRewardsSweep)?;
         let user = ctx.deps.api.addr_validate(&user)?;
 
-        let stake = self
-            .stakes
-            .stake
+        if let Some(sweepable_at) = self
+            .sweep_notices
             .may_load(ctx.deps.storage, (&user, &validator))?
-            .unwrap_or_default();
+        {
+            return Err(ContractError::SweepAlreadyNotified(sweepable_at));
+        }
 
-        let distribution = self
-            .distribution
-            .may_load(ctx.deps.storage, &validator)?
-            .unwrap_or_default();
+        let last_claim = self.last_claim(ctx.deps.storage, &user, &validator)?;
+        let claimable_until = last_claim.plus_seconds(sweep.claim_period);
+        ensure!(
+            ctx.env.block.time >= claimable_until,
+            ContractError::ClaimPeriodActive(claimable_until)
+        );
 
-        let amount = Self::calculate_reward(&stake, &distribution)?;
-        let config = self.config.load(ctx.deps.storage)?;
+        let amount = self.unclaimed_amount(ctx.deps.storage, &user, &validator)?;
+        if amount.is_zero() {
+            return Err(ContractError::NoRewards);
+        }
 
-        Ok(PendingRewards {
-            rewards: coin(amount.u128(), config.rewards_denom),
-        })
+        let sweepable_at = ctx.env.block.time.plus_seconds(sweep.notice_period);
+        self.sweep_notices
+            .save(ctx.deps.storage, (&user, &validator), &sweepable_at)?;
+
+        let event = Event::new("unclaimed_rewards_notice")
+            .add_attribute("user", &user)
+            .add_attribute("validator", &validator)
+            .add_attribute("amount", amount.to_string())
+            .add_attribute("sweepable_at", sweepable_at.seconds().to_string());
+
+        Ok(Response::new()
+            .add_attribute("action", "notify_unclaimed_rewards")
+            .add_event(event))
     }
 
-    /// Returns how much rewards are to be withdrawn by particular user, iterating over all validators.
-    /// This is like stakes is to stake query, but for rewards.
-    #[sv::msg(query)]
-    pub fn all_pending_rewards(
+    /// Sends the unclaimed rewards of a notified `user` on `validator` to the sweep recipient,
+    /// once the notice period is over. Anyone can call this.
+    #[sv::msg(exec)]
+    pub fn sweep_unclaimed_rewards(
         &self,
-        ctx: QueryCtx,
+        ctx: ExecCtx,
         user: String,
-        start_after: Option<String>,
-        limit: Option<u32>,
-    ) -> Result<AllPendingRewards, ContractError> {
-        let limit: usize = clamp_page_limit(limit);
+        validator: String,
+    ) -> Result<Response, ContractError> {
+        nonpayable(&ctx.info)?;
+
+        let sweep = self
+            .rewards_sweep
+            .may_load(ctx.deps.storage)?
+            .ok_or(ContractError::NoRewardsSweep)?;
         let user = ctx.deps.api.addr_validate(&user)?;
 
-        let bound = start_after.as_deref().and_then(Bounder::exclusive_bound);
+        let sweepable_at = self
+            .sweep_notices
+            .may_load(ctx.deps.storage, (&user, &validator))?
+            .ok_or(ContractError::NoSweepNotice)?;
+        ensure!(
+            ctx.env.block.time >= sweepable_at,
+            ContractError::SweepNotDue(sweepable_at)
+        );
+        self.sweep_notices
+            .remove(ctx.deps.storage, (&user, &validator));
+
+        let amount = self.unclaimed_amount(ctx.deps.storage, &user, &validator)?;
+        if amount.is_zero() {
+            return Err(ContractError::NoRewards);
+        }
+
+        #[allow(unused_mut)]
+        let mut resp = Response::new()
+            .add_attribute("action", "sweep_unclaimed_rewards")
+            .add_attribute("owner", user.to_string())
+            .add_attribute("validator", &validator)
+            .add_attribute("recipient", &sweep.recipient)
+            .add_attribute("amount", amount.to_string());
+
+        // Swept rewards are withdrawn on behalf of the user, with no protocol fee
+        let tx_id = self.next_tx_id(ctx.deps.storage)?;
+        let new_tx = Tx::InFlightTransferFunds {
+            id: tx_id,
+            amount,
+            staker: user,
+            validator,
+        };
+        self.pending_txs.save(ctx.deps.storage, tx_id, &new_tx)?;
+        self.sweep_txs.save(ctx.deps.storage, tx_id, &())?;
 
         let config = self.config.load(ctx.deps.storage)?;
+        let packet = ProviderPacket::TransferRewards {
+            rewards: coin(amount.u128(), config.rewards_denom),
+            recipient: sweep.recipient,
+            tx_id,
+        };
+        let channel_id = IBC_CHANNEL.load(ctx.deps.storage)?.endpoint.channel_id;
+        let send_msg = IbcMsg::SendPacket {
+            channel_id,
+            data: to_json_binary(&packet)?,
+            timeout: packet_timeout(&ctx.env),
+        };
 
-        let rewards: Vec<_> = self
-            .stakes
-            .stake
-            .prefix(&user)
-            .range(ctx.deps.storage, bound, None, Order::Ascending)
-            .take(limit)
-            .map(|item| {
-                let (validator, stake) = item?;
-                let distribution = self
-                    .distribution
-                    .may_load(ctx.deps.storage, &validator)?
-                    .unwrap_or_default();
-                let amount = Self::calculate_reward(&stake, &distribution)?;
-                Ok::<_, ContractError>(ValidatorPendingRewards::new(
-                    validator,
-                    amount.u128(),
-                    &config.rewards_denom,
-                ))
-            })
-            .collect::<Result<_, _>>()?;
+        #[cfg(not(any(test, feature = "mt")))]
+        {
+            resp = resp.add_message(send_msg);
+        }
+        #[cfg(any(test, feature = "mt"))]
+        {
+            let _ = send_msg;
+        }
 
-        Ok(AllPendingRewards { rewards })
+        Ok(resp)
     }
 
-    /// Calculates reward for the user basing on the `Stake` he want to withdraw rewards from, and
-    /// the corresponding validator `Distribution`.
-    //
-    // It is important to make sure the distribution passed matches the validator for stake. It
-    // could be enforced by taking user and validator in arguments, then fetching data, but
-    // sometimes data are used also for different calculations so we want to avoid double
-    // fetching.
-    fn calculate_reward(
-        stake: &Stake,
-        distribution: &Distribution,
-    ) -> Result<Uint128, ContractError> {
-        // Calculating rewards with always the `low` value of the range goes against the user in some
-        // scenario (pending unstakes), but the possible errors are small and temporary.
-        let points = distribution.points_per_stake * Uint256::from(stake.stake.low());
+    /// Last time rewards were claimed from the `(user, validator)` stake. Stakes that never
+    /// claimed since sweeping was enabled count from the time it was enabled
+    fn last_claim(
+        &self,
+        storage: &dyn Storage,
+        user: &Addr,
+        validator: &str,
+    ) -> Result<Timestamp, ContractError> {
+        match self.last_claims.may_load(storage, (user, validator))? {
+            Some(last_claim) => Ok(last_claim),
+            None => Ok(self.rewards_sweep_since.load(storage)?),
+        }
+    }
 
-        let points = stake.points_alignment.align(points);
-        let total = Uint128::try_from(points / DISTRIBUTION_POINTS_SCALE)?;
+    /// Records a rewards claim, dropping any sweep notice
+    fn record_claim(
+        &self,
+        storage: &mut dyn Storage,
+        env: &Env,
+        user: &Addr,
+        validator: &str,
+    ) -> StdResult<()> {
+        self.last_claims
+            .save(storage, (user, validator), &env.block.time)?;
+        self.sweep_notices.remove(storage, (user, validator));
+        Ok(())
+    }
 
-        Ok(total - stake.withdrawn_funds)
+    fn unclaimed_amount(
+        &self,
+        storage: &dyn Storage,
+        user: &Addr,
+        validator: &str,
+    ) -> Result<Uint128, ContractError> {
+        let stake = self
+            .stakes
+            .stake
+            .may_load(storage, (user, validator))?
+            .unwrap_or_default();
+        let distribution = self
+            .distribution
+            .may_load(storage, validator)?
+            .unwrap_or_default();
+        let points_scale = self.config.load(storage)?.distribution_points_scale();
+        Self::calculate_reward(&stake, &distribution, points_scale)
     }
-}
 
-pub mod cross_staking {
-    use crate::msg::ReceiveVirtualStake;
+    /// Protocol fee on withdrawing `amount` of rewards
+    fn protocol_fee(&self, storage: &dyn Storage, amount: Uint128) -> StdResult<Uint128> {
+        let fee = self
+            .fee_config
+            .may_load(storage)?
+            .map(|fee_config| amount * fee_config.commission)
+            .unwrap_or_default();
+        Ok(fee)
+    }
 
-    use super::*;
-    use cosmwasm_std::{from_json, Binary};
-    use mesh_apis::{cross_staking_api::CrossStakingApi, local_staking_api::SlashRatioResponse};
+    /// Time (in seconds) the consumer has been silent for, if it is considered halted: the
+    /// channel is closed, or no packet was received for longer than `CONSUMER_HALT_THRESHOLD`
+    fn consumer_stale_for(&self, storage: &dyn Storage, env: &Env) -> StdResult<Option<u64>> {
+        let channel_open = IBC_CHANNEL.may_load(storage)?.is_some();
+        let stale_for = self
+            .last_consumer_packet
+            .may_load(storage)?
+            .map(|last| env.block.time.seconds().saturating_sub(last.seconds()))
+            .unwrap_or_default();
+        let halted = !channel_open || stale_for > CONSUMER_HALT_THRESHOLD;
+        Ok(halted.then_some(stale_for))
+    }
 
-    #[contract(module=crate::contract)]
-    #[sv::messages(mesh_apis::cross_staking_api as CrossStakingApi)]
-    impl CrossStakingApi for ExternalStakingContract<'_> {
-        type Error = ContractError;
+    /// Time (in seconds) pending unbonds are released earlier while the consumer is halted, if
+    /// the unbonding period is shortened in halt mode
+    fn halted_unbonding_reduction(&self, config: &Config) -> Option<u64> {
+        config
+            .unbonding_overrides
+            .consumer_halted
+            .map(|period| config.unbonding_period.saturating_sub(period))
+    }
 
-        #[sv::msg(exec)]
-        fn receive_virtual_stake(
-            &self,
-            ctx: ExecCtx,
-            owner: String,
-            amount: Coin,
-            tx_id: u64,
-            msg: Binary,
-        ) -> Result<Response, Self::Error> {
-            let config = self.config.load(ctx.deps.storage)?;
-            ensure_eq!(ctx.info.sender, config.vault.0, ContractError::Unauthorized);
+    /// Time up to which pending unbonds are released now. While the consumer is halted, unbonds
+    /// are released as if scheduled with the shortened period
+    fn unbond_release_time(
+        &self,
+        storage: &dyn Storage,
+        env: &Env,
+        config: &Config,
+    ) -> StdResult<Timestamp> {
+        let halted = self.consumer_stale_for(storage, env)?;
+        Ok(match (halted, self.halted_unbonding_reduction(config)) {
+            (Some(_), Some(reduction)) => env.block.time.plus_seconds(reduction),
+            _ => env.block.time,
+        })
+    }
 
-            // sending proper denom
-            ensure_eq!(
-                amount.denom,
-                config.denom,
-                ContractError::InvalidDenom(config.denom)
-            );
+    /// Fails unless `sender` is the admin of this contract
+    fn ensure_admin(&self, deps: Deps, env: &Env, sender: &Addr) -> Result<(), ContractError> {
+        let info = deps
+            .querier
+            .query_wasm_contract_info(&env.contract.address)?;
+        ensure!(
+            info.admin.as_deref() == Some(sender.as_str()),
+            ContractError::Unauthorized
+        );
+        Ok(())
+    }
 
-            let owner = ctx.deps.api.addr_validate(&owner)?;
+    /// Max total stake of `owner`, if any. The user's own cap takes precedence over the default
+    fn effective_max_exposure(
+        &self,
+        storage: &dyn Storage,
+        owner: &Addr,
+    ) -> StdResult<Option<Uint128>> {
+        match self.max_exposures.may_load(storage, owner)? {
+            Some(cap) => Ok(Some(cap)),
+            None => self.default_max_exposure.may_load(storage),
+        }
+    }
 
-            // parse and validate message
-            let msg: ReceiveVirtualStake = from_json(msg)?;
-            if !self
-                .val_set
-                .is_active_validator(ctx.deps.storage, &msg.validator)?
-            {
-                return Err(ContractError::ValidatorNotActive(msg.validator));
-            }
-            let mut stake = self
-                .stakes
-                .stake
-                .may_load(ctx.deps.storage, (&owner, &msg.validator))?
-                .unwrap_or_default();
+    /// Checks a new stake of `amount` by `owner` to `validator` against the delegation policy
+    /// sent by the consumer
+    fn ensure_delegation_policy(
+        &self,
+        storage: &dyn Storage,
+        owner: &Addr,
+        validator: &str,
+        amount: Uint128,
+    ) -> Result<(), ContractError> {
+        let policy = self
+            .delegation_policy
+            .may_load(storage)?
+            .unwrap_or_default();
 
-            // Prepare stake addition and save stake.
-            // We don't check for max here, as this call can only come from the `vault` contract, which already
-            // performed the proper check.
-            stake.stake.prepare_add(amount.amount, None)?;
-            self.stakes
+        if let Some(max_validators) = policy.max_validators {
+            let mut validators = 0;
+            let mut staked = false;
+            for item in self
+                .stakes
                 .stake
-                .save(ctx.deps.storage, (&owner, &msg.validator), &stake)?;
-
-            // Save tx
-            let new_tx = Tx::InFlightRemoteStaking {
-                id: tx_id,
-                amount: amount.amount,
-                user: owner.clone(),
-                validator: msg.validator.clone(),
-            };
-            self.pending_txs.save(ctx.deps.storage, tx_id, &new_tx)?;
-
-            let mut resp = Response::new();
+                .prefix(owner)
+                .range(storage, None, None, Order::Ascending)
+            {
+                let (stake_validator, stake) = item?;
+                if stake.stake.high().is_zero() {
+                    continue;
+                }
+                staked |= stake_validator == validator;
+                validators += 1;
+            }
+            ensure!(
+                staked || validators < max_validators,
+                ContractError::MaxValidatorsExceeded(max_validators)
+            );
+        }
 
-            let channel = IBC_CHANNEL.load(ctx.deps.storage)?;
-            let packet = ProviderPacket::Stake {
-                delegator: owner.to_string(),
-                validator: msg.validator,
-                stake: amount.clone(),
-                tx_id,
-            };
-            let msg = IbcMsg::SendPacket {
-                channel_id: channel.endpoint.channel_id,
-                data: to_json_binary(&packet)?,
-                timeout: packet_timeout(&ctx.env),
-            };
-            // add ibc packet if we are ibc enabled (skip in tests)
-            #[cfg(not(any(feature = "mt", test)))]
+        // The share is taken on the total stake of the contract, counting the stakes still in
+        // flight. The very first stake is not limited, as it is the whole total
+        if let Some(share) = policy.max_validator_share {
+            let mut total = Uint128::zero();
+            let mut validator_total = Uint128::zero();
+            for item in self
+                .distribution
+                .range(storage, None, None, Order::Ascending)
             {
-                resp = resp.add_message(msg);
+                let (stake_validator, distribution) = item?;
+                total += distribution.total_stake;
+                if stake_validator == validator {
+                    validator_total += distribution.total_stake;
+                }
             }
-            #[cfg(any(feature = "mt", test))]
+            for item in self
+                .pending_txs
+                .txs
+                .range(storage, None, None, Order::Ascending)
             {
-                let _ = msg;
+                if let (
+                    _,
+                    Tx::InFlightRemoteStaking {
+                        amount: tx_amount,
+                        validator: tx_validator,
+                        ..
+                    },
+                ) = item?
+                {
+                    total += tx_amount;
+                    if tx_validator == validator {
+                        validator_total += tx_amount;
+                    }
+                }
             }
-
-            resp = resp
-                .add_attribute("action", "receive_virtual_stake")
-                .add_attribute("owner", owner)
-                .add_attribute("amount", amount.amount.to_string())
-                .add_attribute("tx_id", tx_id.to_string());
-
-            Ok(resp)
+            ensure!(
+                total.is_zero() || validator_total + amount <= (total + amount) * share,
+                ContractError::ValidatorShareExceeded(share)
+            );
         }
 
-        #[sv::msg(exec)]
-        fn burn_virtual_stake(
-            &self,
-            ctx: ExecCtx,
-            owner: String,
-            amount: Coin,
-            validator: Option<String>,
-        ) -> Result<Response, Self::Error> {
-            let config = self.config.load(ctx.deps.storage)?;
-            ensure_eq!(ctx.info.sender, config.vault.0, ContractError::Unauthorized);
-
-            // sending proper denom
-            ensure_eq!(
-                amount.denom,
-                config.denom,
-                ContractError::InvalidDenom(config.denom)
-            );
+        Ok(())
+    }
 
-            let owner = ctx.deps.api.addr_validate(&owner)?;
+    /// Total stake of `owner` across all validators, including pending stakes
+    fn total_exposure(&self, storage: &dyn Storage, owner: &Addr) -> StdResult<Uint128> {
+        self.stakes
+            .stake
+            .prefix(owner)
+            .range(storage, None, None, Order::Ascending)
+            .map(|item| item.map(|(_, stake)| stake.stake.high()))
+            .sum()
+    }
 
-            let stakes: Vec<_> = match validator {
-                Some(validator) => {
-                    // Burn from validator
-                    // TODO: Preferentially, i.e. burn remaining amount, if any, from other validators
-                    let stake = self
-                        .stakes
-                        .stake
-                        .may_load(ctx.deps.storage, (&owner, &validator))?
-                        .unwrap_or_default();
-                    vec![(validator, stake.stake.high().u128())] // Burn takes precedence over any pending txs
-                }
-                None => {
-                    // Burn proportionally from all validators associated to the user
-                    self.stakes
-                        .stake
-                        .prefix(&owner)
-                        .range(ctx.deps.storage, None, None, Order::Ascending)
-                        .map(|item| {
-                            let (validator, stake) = item?;
-                            Ok::<_, Self::Error>((validator, stake.stake.high().u128()))
-                            // Burn takes precedence over any pending txs
-                        })
-                        .collect::<Result<_, _>>()?
-                }
-            };
+    /// Moves all the stakes of `owner` to the migration target, together with their vault lien.
+    /// Pending unbonds are moved too, and released by the target. Rewards earned so far stay
+    /// here, and can still be withdrawn.
+    /// The consumer is told to unbond the moved stakes from this contract's channel, and the
+    /// target tells it to bond them on its own channel.
+    /// Can be called by the owner, or by the contract admin once a migration target is set.
+    /// Fails if any of the owner's stakes has a pending tx.
+    #[sv::msg(exec)]
+    pub fn export_stakes(&self, ctx: ExecCtx, owner: String) -> Result<Response, ContractError> {
+        nonpayable(&ctx.info)?;
+        let target = self
+            .migration_target
+            .may_load(ctx.deps.storage)?
+            .ok_or(ContractError::NoMigrationTarget)?;
+        let config = self.config.load(ctx.deps.storage)?;
+        let owner_addr = ctx.deps.api.addr_validate(&owner)?;
+        if ctx.info.sender != owner_addr {
+            self.ensure_admin(ctx.deps.as_ref(), &ctx.env, &ctx.info.sender)?;
+        }
 
-            // Error if no stakes
-            if stakes.is_empty() {
-                return Err(ContractError::InsufficientDelegations(
-                    owner.to_string(),
-                    amount.amount,
-                ));
+        let stakes: Vec<_> = self
+            .stakes
+            .stake
+            .prefix(&owner_addr)
+            .range(ctx.deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<_>>()?;
+        let mut exported = vec![];
+        let mut total = Uint128::zero();
+        for (validator, mut stake) in stakes {
+            ensure!(
+                stake.stake.low() == stake.stake.high(),
+                ContractError::StakeInFlight(validator)
+            );
+            let amount = stake.stake.high();
+            let pending_unbonds = std::mem::take(&mut stake.pending_unbonds);
+            if amount.is_zero() && pending_unbonds.is_empty() {
+                continue;
+            }
+            if !amount.is_zero() {
+                let mut distribution = self
+                    .distribution
+                    .may_load(ctx.deps.storage, &validator)?
+                    .unwrap_or_default();
+                stake
+                    .points_alignment
+                    .stake_decreased(amount, distribution.points_per_stake);
+                distribution.total_stake -= amount;
+                self.distribution
+                    .save(ctx.deps.storage, &validator, &distribution)?;
+                stake.stake = ValueRange::new_val(Uint128::zero());
             }
+            // The entry is kept, as it tracks the rewards earned here
+            self.stakes
+                .save(ctx.deps.storage, (&owner_addr, &validator), &stake)?;
 
-            let (burned, burns) = mesh_burn::distribute_burn(&stakes, amount.amount.u128());
+            total += amount;
+            total += pending_unbonds.iter().map(|p| p.amount).sum::<Uint128>();
+            exported.push(ImportedStake {
+                validator,
+                stake: amount,
+                pending_unbonds,
+            });
+        }
+        ensure!(!exported.is_empty(), ContractError::NothingToMigrate);
+        let packets =
+            self.migration_packets(ctx.deps.storage, &ctx.env, &owner_addr, &exported, false)?;
+
+        // Proposes the lien transfer. The target performs it when importing the stakes
+        let transfer_msg = config.vault.transfer_lien(
+            owner.clone(),
+            ctx.env.contract.address.to_string(),
+            target.to_string(),
+            coin(total.u128(), &config.denom),
+        )?;
+        let import_msg = WasmMsg::Execute {
+            contract_addr: target.to_string(),
+            msg: to_json_binary(&sv::ExecMsg::ImportStakes {
+                owner: owner.clone(),
+                stakes: exported,
+            })?,
+            funds: vec![],
+        };
 
-            // Bail if we don't have enough stake
-            if burned < amount.amount.u128() {
-                return Err(ContractError::InsufficientDelegations(
-                    owner.to_string(),
-                    amount.amount,
-                ));
-            }
+        #[allow(unused_mut)]
+        let mut resp = Response::new()
+            .add_message(transfer_msg)
+            .add_message(import_msg)
+            .add_attribute("action", "export_stakes")
+            .add_attribute("owner", owner)
+            .add_attribute("target", target)
+            .add_attribute("amount", total.to_string());
+
+        // send packets if we are ibc enabled
+        #[cfg(not(any(test, feature = "mt")))]
+        {
+            resp = resp.add_messages(packets);
+        }
+        #[cfg(any(test, feature = "mt"))]
+        {
+            let _ = packets;
+        }
 
-            for (validator, burn_amount) in &burns {
-                let burn_amount = Uint128::new(*burn_amount);
-                // Perform stake subtraction
-                let mut stake = self
-                    .stakes
-                    .stake
-                    .load(ctx.deps.storage, (&owner, validator))?;
-                // We don't check for min here, as this call can only come from the `vault` contract, which already
-                // performed the proper check.
-                stake.stake.sub(burn_amount, None)?;
+        Ok(resp)
+    }
 
-                // Load distribution
+    /// Receives the stakes of `owner` exported by the migration source, and takes over their
+    /// vault lien.
+    /// Can only be called by the migration source.
+    #[sv::msg(exec)]
+    pub fn import_stakes(
+        &self,
+        ctx: ExecCtx,
+        owner: String,
+        stakes: Vec<ImportedStake>,
+    ) -> Result<Response, ContractError> {
+        nonpayable(&ctx.info)?;
+        let source = self.migration_source.may_load(ctx.deps.storage)?;
+        ensure!(
+            source.as_ref() == Some(&ctx.info.sender),
+            ContractError::Unauthorized {}
+        );
+        let config = self.config.load(ctx.deps.storage)?;
+        let owner_addr = ctx.deps.api.addr_validate(&owner)?;
+        let packets =
+            self.migration_packets(ctx.deps.storage, &ctx.env, &owner_addr, &stakes, true)?;
+
+        // Agrees on the lien transfer proposed by the source
+        let total = stakes
+            .iter()
+            .flat_map(|stake| {
+                std::iter::once(stake.stake)
+                    .chain(stake.pending_unbonds.iter().map(|pending| pending.amount))
+            })
+            .sum::<Uint128>();
+        let transfer_msg = config.vault.transfer_lien(
+            owner.clone(),
+            ctx.info.sender.to_string(),
+            ctx.env.contract.address.to_string(),
+            coin(total.u128(), &config.denom),
+        )?;
+
+        for ImportedStake {
+            validator,
+            stake: amount,
+            pending_unbonds,
+        } in stakes
+        {
+            let mut stake = self
+                .stakes
+                .stake
+                .may_load(ctx.deps.storage, (&owner_addr, &validator))?
+                .unwrap_or_default();
+            if !amount.is_zero() {
                 let mut distribution = self
                     .distribution
-                    .may_load(ctx.deps.storage, validator)?
+                    .may_load(ctx.deps.storage, &validator)?
                     .unwrap_or_default();
-
-                // Distribution alignment
+                stake.stake.add(amount, None)?;
                 stake
                     .points_alignment
-                    .stake_decreased(burn_amount, distribution.points_per_stake);
-                distribution.total_stake -= burn_amount;
-
-                // Save stake
-                self.stakes
-                    .stake
-                    .save(ctx.deps.storage, (&owner, validator), &stake)?;
-
-                // Save distribution
+                    .stake_increased(amount, distribution.points_per_stake);
+                distribution.total_stake += amount;
                 self.distribution
-                    .save(ctx.deps.storage, validator, &distribution)?;
-            }
-
-            let channel = IBC_CHANNEL.load(ctx.deps.storage)?;
-            let packet = ProviderPacket::Burn {
-                validators: burns.iter().map(|v| v.0.to_string()).collect(),
-                burn: amount.clone(),
-            };
-            let msg = IbcMsg::SendPacket {
-                channel_id: channel.endpoint.channel_id,
-                data: to_json_binary(&packet)?,
-                timeout: packet_timeout(&ctx.env),
-            };
-            let mut resp = Response::new();
-            // add ibc packet if we are ibc enabled (skip in tests)
-            #[cfg(not(any(feature = "mt", test)))]
-            {
-                resp = resp.add_message(msg);
-            }
-            #[cfg(any(feature = "mt", test))]
-            {
-                let _ = msg;
+                    .save(ctx.deps.storage, &validator, &distribution)?;
             }
+            // Keep pending unbonds sorted by release time
+            stake.pending_unbonds.extend(pending_unbonds);
+            stake
+                .pending_unbonds
+                .sort_by_key(|pending| pending.release_at);
+            self.stakes
+                .save(ctx.deps.storage, (&owner_addr, &validator), &stake)?;
+        }
 
-            resp = resp
-                .add_attribute("action", "burn_virtual_stake")
-                .add_attribute("owner", owner)
-                .add_attribute(
-                    "validators",
-                    stakes
-                        .into_iter()
-                        .map(|s| s.0)
-                        .collect::<Vec<_>>()
-                        .join(", "),
-                )
-                .add_attribute("amount", amount.to_string());
+        #[allow(unused_mut)]
+        let mut resp = Response::new()
+            .add_message(transfer_msg)
+            .add_attribute("action", "import_stakes")
+            .add_attribute("owner", owner)
+            .add_attribute("source", ctx.info.sender);
 
-            Ok(resp)
+        // send packets if we are ibc enabled
+        #[cfg(not(any(test, feature = "mt")))]
+        {
+            resp = resp.add_messages(packets);
         }
-
-        #[sv::msg(query)]
-        fn max_slash(&self, ctx: QueryCtx) -> Result<SlashRatioResponse, ContractError> {
-            let Config { slash_ratio, .. } = self.config.load(ctx.deps.storage)?;
-            Ok(SlashRatioResponse {
-                slash_ratio_dsign: slash_ratio.double_sign,
-                slash_ratio_offline: slash_ratio.offline,
-            })
+        #[cfg(any(test, feature = "mt"))]
+        {
+            let _ = packets;
         }
+
+        Ok(resp)
+    }
+
+    /// Packets moving the migrated `stakes` of `owner` on the consumer: bonding them on this
+    /// contract's channel if `bond`, unbonding them otherwise.
+    /// They are untracked, so their acks are told apart from those of pending txs.
+    fn migration_packets(
+        &self,
+        storage: &mut dyn Storage,
+        env: &Env,
+        owner: &Addr,
+        stakes: &[ImportedStake],
+        bond: bool,
+    ) -> Result<Vec<IbcMsg>, ContractError> {
+        let denom = self.config.load(storage)?.denom;
+        let channel = IBC_CHANNEL.load(storage)?;
+        let mut msgs = vec![];
+        for stake in stakes.iter().filter(|stake| !stake.stake.is_zero()) {
+            let tx_id = self.next_tx_id(storage)?;
+            self.untracked_packets.save(storage, tx_id, &())?;
+            let amount = coin(stake.stake.u128(), &denom);
+            let packet = if bond {
+                ProviderPacket::Stake {
+                    delegator: owner.to_string(),
+                    validator: stake.validator.clone(),
+                    stake: amount,
+                    tx_id,
+                }
+            } else {
+                ProviderPacket::Unstake {
+                    delegator: owner.to_string(),
+                    validator: stake.validator.clone(),
+                    unstake: amount,
+                    tx_id,
+                }
+            };
+            msgs.push(IbcMsg::SendPacket {
+                channel_id: channel.endpoint.channel_id.clone(),
+                data: to_json_binary(&packet)?,
+                timeout: packet_timeout(env),
+            });
+        }
+        Ok(msgs)
+    }
+
+    /// Removes the record of untracked packet `tx_id`. Returns whether it was one
+    pub(crate) fn take_untracked_packet(
+        &self,
+        storage: &mut dyn Storage,
+        tx_id: u64,
+    ) -> StdResult<bool> {
+        let found = self.untracked_packets.has(storage, tx_id);
+        if found {
+            self.untracked_packets.remove(storage, tx_id);
+        }
+        Ok(found)
+    }
+
+    /// Removes the record of aborted stake `tx_id`. Returns whether it was one
+    pub(crate) fn take_aborted_stake(
+        &self,
+        storage: &mut dyn Storage,
+        tx_id: u64,
+    ) -> StdResult<bool> {
+        let found = self.aborted_stakes.has(storage, tx_id);
+        if found {
+            self.aborted_stakes.remove(storage, tx_id);
+        }
+        Ok(found)
+    }
+
+    /// Packet unbonding on the consumer the aborted stake `tx_id` it bonded anyway
+    pub(crate) fn undo_aborted_stake(
+        &self,
+        storage: &mut dyn Storage,
+        env: &Env,
+        packet: ProviderPacket,
+    ) -> Result<Option<IbcMsg>, ContractError> {
+        let (delegator, validator, stake) = match packet {
+            ProviderPacket::Stake {
+                delegator,
+                validator,
+                stake,
+                ..
+            } => (delegator, validator, stake),
+            _ => return Ok(None),
+        };
+        let tx_id = self.next_tx_id(storage)?;
+        self.untracked_packets.save(storage, tx_id, &())?;
+        let channel = IBC_CHANNEL.load(storage)?;
+        let packet = ProviderPacket::Unstake {
+            delegator,
+            validator,
+            unstake: stake,
+            tx_id,
+        };
+        Ok(Some(IbcMsg::SendPacket {
+            channel_id: channel.endpoint.channel_id,
+            data: to_json_binary(&packet)?,
+            timeout: packet_timeout(env),
+        }))
+    }
+
+    #[sv::msg(query)]
+    pub fn migration(&self, ctx: QueryCtx) -> Result<MigrationResponse, ContractError> {
+        let target = self.migration_target.may_load(ctx.deps.storage)?;
+        let source = self.migration_source.may_load(ctx.deps.storage)?;
+        Ok(MigrationResponse {
+            target: target.map(Addr::into_string),
+            source: source.map(Addr::into_string),
+        })
+    }
+
+    #[sv::msg(query)]
+    pub fn rewards_sweep(&self, ctx: QueryCtx) -> Result<RewardsSweepResponse, ContractError> {
+        let config = self.rewards_sweep.may_load(ctx.deps.storage)?;
+        Ok(RewardsSweepResponse { config })
+    }
+
+    /// Returns when rewards were last claimed from the `(user, validator)` stake, and when they can
+    /// be swept if the user was notified
+    #[sv::msg(query)]
+    pub fn unclaimed_rewards(
+        &self,
+        ctx: QueryCtx,
+        user: String,
+        validator: String,
+    ) -> Result<UnclaimedRewardsResponse, ContractError> {
+        let user = ctx.deps.api.addr_validate(&user)?;
+        let last_claim = self
+            .last_claims
+            .may_load(ctx.deps.storage, (&user, &validator))?;
+        let sweepable_at = self
+            .sweep_notices
+            .may_load(ctx.deps.storage, (&user, &validator))?;
+        Ok(UnclaimedRewardsResponse {
+            last_claim,
+            sweepable_at,
+        })
+    }
+
+    /// Returns the delegation policy sent by the consumer
+    #[sv::msg(query)]
+    pub fn delegation_policy(
+        &self,
+        ctx: QueryCtx,
+    ) -> Result<DelegationPolicyResponse, ContractError> {
+        let policy = self
+            .delegation_policy
+            .may_load(ctx.deps.storage)?
+            .unwrap_or_default();
+        Ok(DelegationPolicyResponse {
+            max_validators: policy.max_validators,
+            max_validator_share: policy.max_validator_share,
+        })
+    }
+
+    /// Returns the configured slash ratio, the one sent by the consumer, and the one in effect
+    #[sv::msg(query)]
+    pub fn slash_ratio_source(
+        &self,
+        ctx: QueryCtx,
+    ) -> Result<SlashRatioSourceResponse, ContractError> {
+        let configured = self.config.load(ctx.deps.storage)?.slash_ratio;
+        let consumer = self.consumer_slash_ratio.may_load(ctx.deps.storage)?;
+        let effective = consumer.as_ref().map_or_else(
+            || configured.clone(),
+            |consumer| consumer.slash_ratio.clone(),
+        );
+        Ok(SlashRatioSourceResponse {
+            configured,
+            consumer,
+            effective,
+        })
+    }
+
+    #[sv::msg(query)]
+    pub fn max_exposure(
+        &self,
+        ctx: QueryCtx,
+        user: String,
+    ) -> Result<MaxExposureResponse, ContractError> {
+        let user = ctx.deps.api.addr_validate(&user)?;
+        let user_cap = self.max_exposures.may_load(ctx.deps.storage, &user)?;
+        let default_cap = self.default_max_exposure.may_load(ctx.deps.storage)?;
+        let exposure = self.total_exposure(ctx.deps.storage, &user)?;
+        Ok(MaxExposureResponse {
+            cap: user_cap.or(default_cap),
+            user_cap,
+            default_cap,
+            exposure,
+        })
+    }
+
+    /// Paginated list of validators new stake is paused on.
+    ///
+    /// `start_after` is the last validator of previous page
+    #[sv::msg(query)]
+    pub fn paused_validators(
+        &self,
+        ctx: QueryCtx,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> Result<PausedValidatorsResponse, ContractError> {
+        let limit = clamp_page_limit(limit);
+        let bound = start_after.as_deref().and_then(Bounder::exclusive_bound);
+
+        let validators = self
+            .paused_validators
+            .keys(ctx.deps.storage, bound, None, Order::Ascending)
+            .take(limit)
+            .collect::<StdResult<_>>()?;
+
+        Ok(PausedValidatorsResponse { validators })
+    }
+
+    /// Returns the jail slashes delayed by the jail slash policy
+    #[sv::msg(query)]
+    pub fn pending_jail_slashes(
+        &self,
+        ctx: QueryCtx,
+    ) -> Result<PendingJailSlashesResponse, ContractError> {
+        let slashes = self
+            .pending_jail_slashes
+            .range(ctx.deps.storage, None, None, Order::Ascending)
+            .map(|item| {
+                let ((validator, infraction_height), slash) = item?;
+                Ok::<_, ContractError>(PendingJailSlashInfo {
+                    validator,
+                    infraction_height,
+                    slash_ratio: slash.slash_ratio,
+                    slash_amount: slash.slash_amount,
+                    due: slash.due,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(PendingJailSlashesResponse { slashes })
+    }
+
+    #[sv::msg(query)]
+    pub fn fee_config(&self, ctx: QueryCtx) -> Result<FeeConfigResponse, ContractError> {
+        let fee_config = self.fee_config.may_load(ctx.deps.storage)?;
+        let accrued = self
+            .accrued_fees
+            .may_load(ctx.deps.storage)?
+            .unwrap_or_default();
+        Ok(FeeConfigResponse {
+            fee_config,
+            accrued,
+        })
+    }
+
+    /// Compares the stakes and pending unbonds of `user` with their lien in the vault, which
+    /// should always match
+    #[sv::msg(query)]
+    pub fn vault_verification(
+        &self,
+        ctx: QueryCtx,
+        user: String,
+    ) -> Result<VaultVerificationResponse, ContractError> {
+        let user = ctx.deps.api.addr_validate(&user)?;
+        self.verify_user_lien(ctx.deps, &ctx.env, &user)
+    }
+
+    /// Compares the stakes and pending unbonds of `user` with their lien in the vault, emitting a
+    /// `vault_drift` event if they diverge. An early warning for accounting bugs across the two
+    /// contracts. Can be called by anyone.
+    #[sv::msg(exec)]
+    pub fn verify_against_vault(
+        &self,
+        ctx: ExecCtx,
+        user: String,
+    ) -> Result<Response, ContractError> {
+        nonpayable(&ctx.info)?;
+        let user = ctx.deps.api.addr_validate(&user)?;
+        let verification = self.verify_user_lien(ctx.deps.as_ref(), &ctx.env, &user)?;
+
+        let mut resp = Response::new()
+            .add_attribute("action", "verify_against_vault")
+            .add_attribute("user", user.as_str())
+            .add_attribute("in_sync", verification.in_sync.to_string());
+        if !verification.in_sync {
+            let event = Event::new("vault_drift")
+                .add_attribute("user", user)
+                .add_attribute("lien", verification.lien.high().to_string())
+                .add_attribute("stake", verification.stake.high().to_string())
+                .add_attribute("pending_unbonds", verification.pending_unbonds.to_string());
+            resp = resp.add_event(event);
+        }
+
+        Ok(resp)
+    }
+
+    fn verify_user_lien(
+        &self,
+        deps: Deps,
+        env: &Env,
+        user: &Addr,
+    ) -> Result<VaultVerificationResponse, ContractError> {
+        let config = self.config.load(deps.storage)?;
+        let lien = config
+            .vault
+            .lien(deps, user.to_string(), env.contract.address.to_string())?;
+        let lien = ValueRange::new(lien.low, lien.high);
+
+        let stakes = self
+            .stakes
+            .stake
+            .prefix(user)
+            .range(deps.storage, None, None, Order::Ascending)
+            .map(|item| item.map(|(_, stake)| stake))
+            .collect::<StdResult<Vec<_>>>()?;
+        let stake: ValueRange<Uint128> = stakes.iter().map(|stake| stake.stake).sum();
+        let pending_unbonds: Uint128 = stakes
+            .iter()
+            .flat_map(|stake| &stake.pending_unbonds)
+            .map(|unbond| unbond.amount)
+            .sum();
+        let in_sync = lien.high() == stake.high() + pending_unbonds;
+
+        Ok(VaultVerificationResponse {
+            lien,
+            stake,
+            pending_unbonds,
+            in_sync,
+        })
+    }
+
+    /// Withdraws all of their released tokens to the calling user.
+    ///
+    /// Tokens to be claimed have to be unbond before by calling the `unbond` message, and
+    /// their unbonding period must have passed.
+    #[sv::msg(exec)]
+    pub fn withdraw_unbonded(&self, ctx: ExecCtx) -> Result<Response, ContractError> {
+        nonpayable(&ctx.info)?;
+        let resp = Response::new().add_attribute("action", "withdraw_unbonded");
+        self.release_unbonded(ctx.deps, &ctx.env, &ctx.info.sender, true, resp)
+    }
+
+    /// Withdraws all the released tokens of `user`, releasing them back to the vault. Can be
+    /// called by anyone, so that matured unbonds don't stay liened when users forget about
+    /// them. The relocking preference of the user is not applied.
+    #[sv::msg(exec)]
+    pub fn withdraw_unbonded_for(
+        &self,
+        ctx: ExecCtx,
+        user: String,
+    ) -> Result<Response, ContractError> {
+        nonpayable(&ctx.info)?;
+        let user = ctx.deps.api.addr_validate(&user)?;
+        let resp = Response::new()
+            .add_attribute("action", "withdraw_unbonded_for")
+            .add_attribute("sender", ctx.info.sender);
+        self.release_unbonded(ctx.deps, &ctx.env, &user, false, resp)
+    }
+
+    /// Releases the matured pending unbonds of `user`, relocking them if `relock` is set and
+    /// the user opted into it, or releasing them back to the vault otherwise
+    fn release_unbonded(
+        &self,
+        deps: DepsMut,
+        env: &Env,
+        user: &Addr,
+        relock: bool,
+        resp: Response,
+    ) -> Result<Response, ContractError> {
+        let config = self.config.load(deps.storage)?;
+
+        let mut block = env.block.clone();
+        block.time = self.unbond_release_time(deps.storage, env, &config)?;
+
+        let stakes: Vec<_> = self
+            .stakes
+            .stake
+            .prefix(user)
+            .range(deps.storage, None, None, Order::Ascending)
+            .collect::<Result<_, _>>()?;
+
+        let released: Uint128 = stakes
+            .into_iter()
+            .map(|(validator, mut stake)| -> Result<_, ContractError> {
+                let released = stake.release_pending(&block);
+
+                if !released.is_zero() {
+                    self.stakes.save(deps.storage, (user, &validator), &stake)?
+                }
+
+                Ok(released)
+            })
+            .try_fold(Uint128::zero(), |acc, released| {
+                released.map(|released| released + acc)
+            })?;
+
+        let mut resp = resp
+            .add_attribute("owner", user.to_string())
+            .add_attribute("amount", released.to_string());
+
+        if released.is_zero() {
+            return Ok(resp);
+        }
+
+        let relocked = if relock {
+            self.relock_released(deps.storage, env, &config, user, released)?
+        } else {
+            None
+        };
+        let relocked = match relocked {
+            Some((validator, tx_id, packet)) => {
+                resp = resp
+                    .add_attribute("relock_validator", validator)
+                    .add_attribute("tx_id", tx_id.to_string());
+                // add ibc packet if we are ibc enabled (skip in tests)
+                #[cfg(not(any(feature = "mt", test)))]
+                {
+                    resp = resp.add_messages(packet);
+                }
+                #[cfg(any(feature = "mt", test))]
+                {
+                    let _ = packet;
+                }
+                true
+            }
+            None => {
+                let release_msg = config.vault.release_cross_stake(
+                    user.to_string(),
+                    coin(released.u128(), &config.denom),
+                    vec![],
+                )?;
+                resp = resp.add_message(release_msg);
+                false
+            }
+        };
+
+        let callback = StakerCallbackApiExecMsg::UnbondingReleased {
+            amount: coin(released.u128(), &config.denom),
+            relocked,
+        };
+        let callback = self.staker_callback(deps.storage, user, &callback)?;
+
+        Ok(resp.add_submessages(callback))
+    }
+
+    /// Opts into relocking: collateral released by `withdraw_unbonded` is re-staked to
+    /// `validator` right away, instead of being released back to the vault. `None` opts out.
+    ///
+    /// If new stake to the validator is not possible at withdrawal time (e.g. it is paused or
+    /// not active), the collateral is released as usual.
+    #[sv::msg(exec)]
+    pub fn set_relock(
+        &self,
+        ctx: ExecCtx,
+        validator: Option<String>,
+    ) -> Result<Response, ContractError> {
+        nonpayable(&ctx.info)?;
+        let resp = Response::new()
+            .add_attribute("action", "set_relock")
+            .add_attribute("owner", ctx.info.sender.as_str());
+        match validator {
+            Some(validator) => {
+                ensure!(
+                    self.val_set
+                        .is_active_validator(ctx.deps.storage, &validator)?,
+                    ContractError::ValidatorNotActive(validator)
+                );
+                self.relock_validators
+                    .save(ctx.deps.storage, &ctx.info.sender, &validator)?;
+                Ok(resp.add_attribute("validator", validator))
+            }
+            None => {
+                self.relock_validators
+                    .remove(ctx.deps.storage, &ctx.info.sender);
+                Ok(resp)
+            }
+        }
+    }
+
+    #[sv::msg(query)]
+    pub fn relock(&self, ctx: QueryCtx, user: String) -> Result<RelockResponse, ContractError> {
+        let user = ctx.deps.api.addr_validate(&user)?;
+        let validator = self.relock_validators.may_load(ctx.deps.storage, &user)?;
+        Ok(RelockResponse { validator })
+    }
+
+    /// Re-stakes `amount` released for `owner` to their relock validator, keeping it under the
+    /// vault lien. Returns the validator, the stake tx id, and the packet to send unless the
+    /// stake is queued.
+    /// Returns `None` if the owner didn't opt into relocking, or new stake to the validator is
+    /// not possible at the moment.
+    fn relock_released(
+        &self,
+        storage: &mut dyn Storage,
+        env: &Env,
+        config: &Config,
+        owner: &Addr,
+        amount: Uint128,
+    ) -> Result<Option<(String, u64, Option<IbcMsg>)>, ContractError> {
+        let validator = match self.relock_validators.may_load(storage, owner)? {
+            Some(validator) => validator,
+            None => return Ok(None),
+        };
+        let queued = match self.new_stake_queued(storage, config, &validator) {
+            Ok(queued) => queued,
+            Err(
+                ContractError::ValidatorPaused(_)
+                | ContractError::ValidatorJailed(_)
+                | ContractError::ValidatorNotActive(_),
+            ) => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        if let Some(cap) = self.effective_max_exposure(storage, owner)? {
+            if self.total_exposure(storage, owner)? + amount > cap {
+                return Ok(None);
+            }
+        }
+        match self.ensure_delegation_policy(storage, owner, &validator, amount) {
+            Ok(()) => {}
+            Err(
+                ContractError::MaxValidatorsExceeded(_) | ContractError::ValidatorShareExceeded(_),
+            ) => return Ok(None),
+            Err(err) => return Err(err),
+        }
+
+        let mut stake = self
+            .stakes
+            .stake
+            .may_load(storage, (owner, &validator))?
+            .unwrap_or_default();
+        stake.stake.prepare_add(amount, None)?;
+        self.stakes.save(storage, (owner, &validator), &stake)?;
+
+        let tx_id = self.next_tx_id(storage)?;
+        let new_tx = Tx::InFlightRemoteStaking {
+            id: tx_id,
+            amount,
+            user: owner.clone(),
+            validator: validator.clone(),
+        };
+        self.pending_txs.save(storage, tx_id, &new_tx)?;
+        self.relocked_txs.save(storage, tx_id, &())?;
+
+        let packet = if queued {
+            self.queued_stakes.save(storage, (&validator, tx_id), &())?;
+            None
+        } else {
+            Some(Self::stake_packet(
+                storage,
+                env,
+                owner,
+                &validator,
+                coin(amount.u128(), &config.denom),
+                tx_id,
+            )?)
+        };
+        Ok(Some((validator, tx_id, packet)))
+    }
+
+    /// Checks new stake to `validator` is accepted. Returns whether it has to be queued until
+    /// the validator is active again
+    fn new_stake_queued(
+        &self,
+        storage: &dyn Storage,
+        config: &Config,
+        validator: &str,
+    ) -> Result<bool, ContractError> {
+        ensure!(
+            !self.paused_validators.has(storage, validator),
+            ContractError::ValidatorPaused(validator.to_owned())
+        );
+        match self.val_set.validator_state(storage, validator)? {
+            State::Active {} => Ok(false),
+            State::Jailed {} => match config.jailed_stake_policy {
+                JailedStakePolicy::Reject => {
+                    Err(ContractError::ValidatorJailed(validator.to_owned()))
+                }
+                JailedStakePolicy::Allow => Ok(false),
+                JailedStakePolicy::Queue => Ok(true),
+            },
+            _ => Err(ContractError::ValidatorNotActive(validator.to_owned())),
+        }
+    }
+
+    /// Distributes reward among users staking via particular validator. Distribution is performed
+    /// proportionally to amount of tokens staked by user.
+    /// In test code, this is called from `test_distribute_rewards`.
+    /// In non-test code, this is called from `ibc_packet_receive`
+    /// Replaces the delegation policy with the one sent by the consumer.
+    /// It only applies to new stakes.
+    pub(crate) fn set_delegation_policy(
+        &self,
+        deps: DepsMut,
+        max_validators: Option<u32>,
+        max_validator_share: Option<Decimal>,
+    ) -> Result<Event, ContractError> {
+        if let Some(share) = max_validator_share {
+            ensure!(
+                share <= Decimal::one(),
+                ContractError::InvalidValidatorShare
+            );
+        }
+
+        let policy = DelegationPolicy {
+            max_validators,
+            max_validator_share,
+        };
+        self.delegation_policy.save(deps.storage, &policy)?;
+
+        let event = Event::new("delegation_policy")
+            .add_attribute(
+                "max_validators",
+                max_validators.map_or("none".to_owned(), |max| max.to_string()),
+            )
+            .add_attribute(
+                "max_validator_share",
+                max_validator_share.map_or("none".to_owned(), |share| share.to_string()),
+            );
+        Ok(event)
+    }
+
+    /// Returns the slash ratio in effect: the one sent by the consumer, if any, or the configured
+    /// one otherwise
+    pub fn effective_slash_ratio(&self, storage: &dyn Storage) -> StdResult<SlashRatio> {
+        match self.consumer_slash_ratio.may_load(storage)? {
+            Some(consumer) => Ok(consumer.slash_ratio),
+            None => Ok(self.config.load(storage)?.slash_ratio),
+        }
+    }
+
+    /// Replaces the slash ratio with the one from the slashing params sent by the consumer.
+    /// Params read at a lower consumer height than the current ones are outdated, and ignored.
+    pub(crate) fn set_slashing_params(
+        &self,
+        deps: DepsMut,
+        height: u64,
+        time: u64,
+        double_sign: Decimal,
+        offline: Decimal,
+    ) -> Result<Event, ContractError> {
+        ensure!(
+            double_sign <= Decimal::one() && offline <= Decimal::one(),
+            ContractError::InvalidSlashRatio
+        );
+
+        let outdated = matches!(
+            self.consumer_slash_ratio.may_load(deps.storage)?,
+            Some(current) if current.height > height
+        );
+        if !outdated {
+            let consumer = ConsumerSlashRatio {
+                slash_ratio: SlashRatio {
+                    double_sign,
+                    offline,
+                },
+                height,
+                time,
+            };
+            self.consumer_slash_ratio.save(deps.storage, &consumer)?;
+        }
+
+        let event = Event::new("slashing_params")
+            .add_attribute("height", height.to_string())
+            .add_attribute("double_sign", double_sign.to_string())
+            .add_attribute("offline", offline.to_string())
+            .add_attribute("outdated", outdated.to_string());
+        Ok(event)
+    }
+
+    pub(crate) fn distribute_rewards(
+        &self,
+        mut deps: DepsMut,
+        validator: &str,
+        rewards: Coin,
+    ) -> Result<Event, ContractError> {
+        // check we have the proper denom
+        let config = self.config.load(deps.storage)?;
+        ensure_eq!(
+            rewards.denom,
+            config.rewards_denom,
+            PaymentError::MissingDenom(rewards.denom)
+        );
+
+        self.distribute_rewards_unchecked(&mut deps, validator, rewards.amount)
+    }
+
+    fn distribute_rewards_unchecked(
+        &self,
+        deps: &mut DepsMut,
+        validator: &str,
+        amount: Uint128,
+    ) -> Result<Event, ContractError> {
+        let points_scale = self.config.load(deps.storage)?.distribution_points_scale();
+        let mut distribution = self
+            .distribution
+            .may_load(deps.storage, validator)?
+            .unwrap_or_default();
+
+        let total_stake = Uint256::from(distribution.total_stake);
+        let points_distributed =
+            Uint256::from(amount) * points_scale + distribution.points_leftover;
+        let points_per_stake = points_distributed / total_stake;
+
+        distribution.points_leftover = points_distributed - points_per_stake * total_stake;
+        distribution.points_per_stake += points_per_stake;
+
+        self.distribution
+            .save(deps.storage, validator, &distribution)?;
+
+        let event = Event::new("distribute_rewards")
+            .add_attribute("validator", validator)
+            .add_attribute("amount", amount.to_string());
+
+        Ok(event)
+    }
+
+    pub(crate) fn distribute_rewards_batch(
+        &self,
+        mut deps: DepsMut,
+        rewards: &[RewardInfo],
+        denom: &str,
+    ) -> Result<Vec<Event>, ContractError> {
+        // check we have the proper denom
+        let config = self.config.load(deps.storage)?;
+        ensure_eq!(
+            denom,
+            config.rewards_denom,
+            ContractError::InvalidDenom(config.rewards_denom)
+        );
+
+        rewards
+            .iter()
+            .map(|reward_info| {
+                self.distribute_rewards_unchecked(
+                    &mut deps,
+                    &reward_info.validator,
+                    reward_info.reward,
+                )
+            })
+            .collect()
+    }
+
+    /// Withdraw rewards from staking via given validator
+    #[sv::msg(exec)]
+    pub fn withdraw_rewards(
+        &self,
+        ctx: ExecCtx,
+        validator: String,
+        /// Address on the consumer side to receive the rewards
+        remote_recipient: String,
+    ) -> Result<Response, ContractError> {
+        nonpayable(&ctx.info)?;
+
+        let stake = self
+            .stakes
+            .stake
+            .may_load(ctx.deps.storage, (&ctx.info.sender, &validator))?
+            .unwrap_or_default();
+
+        let distribution = self
+            .distribution
+            .may_load(ctx.deps.storage, &validator)?
+            .unwrap_or_default();
+
+        let points_scale = self
+            .config
+            .load(ctx.deps.storage)?
+            .distribution_points_scale();
+        let amount = Self::calculate_reward(&stake, &distribution, points_scale)?;
+
+        if amount.is_zero() {
+            return Err(ContractError::NoRewards);
+        }
+        let fee = self.protocol_fee(ctx.deps.storage, amount)?;
+        self.record_claim(ctx.deps.storage, &ctx.env, &ctx.info.sender, &validator)?;
+
+        #[allow(unused_mut)]
+        let mut resp = Response::new()
+            .add_attribute("action", "withdraw_rewards")
+            .add_attribute("owner", ctx.info.sender.to_string())
+            .add_attribute("validator", &validator)
+            .add_attribute("recipient", &remote_recipient)
+            .add_attribute("amount", amount.to_string())
+            .add_attribute("fee", fee.to_string());
+
+        // prepare the pending tx
+        let tx_id = self.next_tx_id(ctx.deps.storage)?;
+        let new_tx = Tx::InFlightTransferFunds {
+            id: tx_id,
+            amount,
+            staker: ctx.info.sender,
+            validator,
+        };
+        self.pending_txs.save(ctx.deps.storage, tx_id, &new_tx)?;
+        if !fee.is_zero() {
+            self.pending_fees.save(ctx.deps.storage, tx_id, &fee)?;
+        }
+
+        // Crate the IBC packet, the fee is kept out of the transferred rewards
+        let config = self.config.load(ctx.deps.storage)?;
+        let rewards = coin((amount - fee).u128(), config.rewards_denom);
+        let packet = ProviderPacket::TransferRewards {
+            rewards,
+            recipient: remote_recipient,
+            tx_id,
+        };
+        let channel_id = IBC_CHANNEL.load(ctx.deps.storage)?.endpoint.channel_id;
+        let send_msg = IbcMsg::SendPacket {
+            channel_id,
+            data: to_json_binary(&packet)?,
+            timeout: packet_timeout(&ctx.env),
+        };
+
+        // TODO: send in test code when we can handle it
+        #[cfg(not(any(test, feature = "mt")))]
+        {
+            resp = resp.add_message(send_msg);
+        }
+        #[cfg(any(test, feature = "mt"))]
+        {
+            let _ = send_msg;
+        }
+
+        Ok(resp)
+    }
+
+    /// Withdraws the rewards from all the validators the caller is staking with, in a single
+    /// transfer to `remote_recipient`.
+    #[sv::msg(exec)]
+    pub fn withdraw_all_rewards(
+        &self,
+        ctx: ExecCtx,
+        /// Address on the consumer side to receive the rewards
+        remote_recipient: String,
+    ) -> Result<Response, ContractError> {
+        nonpayable(&ctx.info)?;
+
+        let points_scale = self
+            .config
+            .load(ctx.deps.storage)?
+            .distribution_points_scale();
+        let amounts = self
+            .stakes
+            .stake
+            .prefix(&ctx.info.sender)
+            .range(ctx.deps.storage, None, None, Order::Ascending)
+            .map(|item| {
+                let (validator, stake) = item?;
+                let distribution = self
+                    .distribution
+                    .may_load(ctx.deps.storage, &validator)?
+                    .unwrap_or_default();
+                let amount = Self::calculate_reward(&stake, &distribution, points_scale)?;
+                Ok::<_, ContractError>((validator, amount))
+            })
+            .filter(|item| !matches!(item, Ok((_, amount)) if amount.is_zero()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let amount: Uint128 = amounts.iter().map(|(_, amount)| amount).sum();
+        if amount.is_zero() {
+            return Err(ContractError::NoRewards);
+        }
+        let fee = self.protocol_fee(ctx.deps.storage, amount)?;
+        for (validator, _) in &amounts {
+            self.record_claim(ctx.deps.storage, &ctx.env, &ctx.info.sender, validator)?;
+        }
+
+        #[allow(unused_mut)]
+        let mut resp = Response::new()
+            .add_attribute("action", "withdraw_all_rewards")
+            .add_attribute("owner", ctx.info.sender.to_string())
+            .add_attribute(
+                "validators",
+                amounts
+                    .iter()
+                    .map(|(validator, _)| validator.as_str())
+                    .collect::<Vec<_>>()
+                    .join(","),
+            )
+            .add_attribute("recipient", &remote_recipient)
+            .add_attribute("amount", amount.to_string())
+            .add_attribute("fee", fee.to_string());
+
+        // prepare the pending tx
+        let tx_id = self.next_tx_id(ctx.deps.storage)?;
+        let new_tx = Tx::InFlightTransferAllFunds {
+            id: tx_id,
+            amounts,
+            staker: ctx.info.sender,
+        };
+        self.pending_txs.save(ctx.deps.storage, tx_id, &new_tx)?;
+        if !fee.is_zero() {
+            self.pending_fees.save(ctx.deps.storage, tx_id, &fee)?;
+        }
+
+        // Create a single IBC packet for all the rewards, minus the fee
+        let config = self.config.load(ctx.deps.storage)?;
+        let rewards = coin((amount - fee).u128(), config.rewards_denom);
+        let packet = ProviderPacket::TransferRewards {
+            rewards,
+            recipient: remote_recipient,
+            tx_id,
+        };
+        let channel_id = IBC_CHANNEL.load(ctx.deps.storage)?.endpoint.channel_id;
+        let send_msg = IbcMsg::SendPacket {
+            channel_id,
+            data: to_json_binary(&packet)?,
+            timeout: packet_timeout(&ctx.env),
+        };
+
+        #[cfg(not(any(test, feature = "mt")))]
+        {
+            resp = resp.add_message(send_msg);
+        }
+        #[cfg(any(test, feature = "mt"))]
+        {
+            let _ = send_msg;
+        }
+
+        Ok(resp)
+    }
+
+    /// Compounds the rewards from staking via `validator` into more stake to it. The rewards are
+    /// moved into the delegation on the consumer, and valued at the terms the caller's last stake to
+    /// `validator` was converted with. As any other stake, the compounded stake is backed by a lien
+    /// on the caller's free collateral in the vault, taken right away. So compounding needs as much
+    /// free collateral as the compounded value, and fails in the vault without it.
+    #[sv::msg(exec)]
+    pub fn compound_rewards(
+        &self,
+        ctx: ExecCtx,
+        validator: String,
+    ) -> Result<Response, ContractError> {
+        nonpayable(&ctx.info)?;
+
+        let config = self.config.load(ctx.deps.storage)?;
+        let owner = ctx.info.sender;
+        let mut stake = self
+            .stakes
+            .stake
+            .may_load(ctx.deps.storage, (&owner, &validator))?
+            .unwrap_or_default();
+        let distribution = self
+            .distribution
+            .may_load(ctx.deps.storage, &validator)?
+            .unwrap_or_default();
+        let rewards =
+            Self::calculate_reward(&stake, &distribution, config.distribution_points_scale())?;
+        if rewards.is_zero() {
+            return Err(ContractError::NoRewards);
+        }
+        let fee = self.protocol_fee(ctx.deps.storage, rewards)?;
+        let compounded = rewards - fee;
+
+        // Value the rewards back in the provider-side denom
+        let terms = self
+            .last_conversion(ctx.deps.storage, &owner, &validator)?
+            .ok_or_else(|| ContractError::NoConversionTerms(validator.clone()))?;
+        ensure_eq!(
+            terms.converted.denom,
+            config.rewards_denom,
+            ContractError::InvalidDenom(config.rewards_denom)
+        );
+        let amount = match (terms.price.inv(), terms.price_adjustment.inv()) {
+            (Some(price), Some(adjustment)) => (compounded * price) * adjustment,
+            _ => return Err(ContractError::NoConversionTerms(validator)),
+        };
+        if amount.is_zero() {
+            return Err(ContractError::NoRewards);
+        }
+
+        if let Some(cap) = self.effective_max_exposure(ctx.deps.storage, &owner)? {
+            let exposure = self.total_exposure(ctx.deps.storage, &owner)? + amount;
+            ensure!(exposure <= cap, ContractError::MaxExposureExceeded(cap));
+        }
+        self.ensure_delegation_policy(ctx.deps.storage, &owner, &validator, amount)?;
+        // Queued stakes are sent as plain stakes, once the validator is back
+        ensure!(
+            !self.new_stake_queued(ctx.deps.storage, &config, &validator)?,
+            ContractError::ValidatorJailed(validator)
+        );
+
+        // The rewards are taken right away, so they cannot be withdrawn while in flight
+        stake.stake.prepare_add(amount, None)?;
+        stake.withdrawn_funds += rewards;
+        self.stakes
+            .save(ctx.deps.storage, (&owner, &validator), &stake)?;
+        self.record_claim(ctx.deps.storage, &ctx.env, &owner, &validator)?;
+
+        let tx_id = self.next_tx_id(ctx.deps.storage)?;
+        let new_tx = Tx::InFlightRemoteStaking {
+            id: tx_id,
+            amount,
+            user: owner.clone(),
+            validator: validator.clone(),
+        };
+        self.pending_txs.save(ctx.deps.storage, tx_id, &new_tx)?;
+        self.compounded_rewards
+            .save(ctx.deps.storage, tx_id, &rewards)?;
+        if !fee.is_zero() {
+            self.pending_fees.save(ctx.deps.storage, tx_id, &fee)?;
+        }
+
+        let stake = coin(amount.u128(), &config.denom);
+        let lien_msg = config
+            .vault
+            .compound_cross_stake(owner.to_string(), stake.clone())?;
+
+        #[allow(unused_mut)]
+        let mut resp = Response::new()
+            .add_message(lien_msg)
+            .add_attribute("action", "compound_rewards")
+            .add_attribute("owner", owner.to_string())
+            .add_attribute("validator", &validator)
+            .add_attribute("rewards", rewards.to_string())
+            .add_attribute("fee", fee.to_string())
+            .add_attribute("amount", amount.to_string())
+            .add_attribute("tx_id", tx_id.to_string());
+
+        // The fee is kept out of the compounded rewards
+        let packet = ProviderPacket::CompoundRewards {
+            delegator: owner.into_string(),
+            validator,
+            stake,
+            rewards: coin(compounded.u128(), config.rewards_denom),
+            tx_id,
+        };
+        let channel_id = IBC_CHANNEL.load(ctx.deps.storage)?.endpoint.channel_id;
+        let send_msg = IbcMsg::SendPacket {
+            channel_id,
+            data: to_json_binary(&packet)?,
+            timeout: packet_timeout(&ctx.env),
+        };
+
+        #[cfg(not(any(test, feature = "mt")))]
+        {
+            resp = resp.add_message(send_msg);
+        }
+        #[cfg(any(test, feature = "mt"))]
+        {
+            let _ = send_msg;
+        }
+
+        Ok(resp)
+    }
+
+    /// Terms the last stake of `owner` to `validator` was converted with, if the consumer sent any
+    fn last_conversion(
+        &self,
+        storage: &dyn Storage,
+        owner: &Addr,
+        validator: &str,
+    ) -> StdResult<Option<ConversionTerms>> {
+        let conversions = self
+            .stake_conversions
+            .prefix(owner)
+            .range(storage, None, None, Order::Ascending)
+            .map(|item| item.map(|(_, conversion)| conversion))
+            .collect::<StdResult<Vec<_>>>()?;
+        Ok(conversions
+            .into_iter()
+            .filter(|conversion| conversion.validator == validator)
+            .map(|conversion| conversion.conversion)
+            .max_by_key(|terms| terms.height))
+    }
+
+    /// In test code, this is called from `test_rollback_withdraw_rewards`.
+    /// In non-test code, this is called from `ibc_packet_ack` or `ibc_packet_timeout`
+    pub(crate) fn rollback_withdraw_rewards(
+        &self,
+        deps: DepsMut,
+        tx_id: u64,
+    ) -> Result<(), ContractError> {
+        // Failed fee transfers are accrued again, to be retried
+        if let Some(fee) = self.fee_transfers.may_load(deps.storage, tx_id)? {
+            self.fee_transfers.remove(deps.storage, tx_id);
+            self.accrue_fee(deps.storage, fee)?;
+            return Ok(());
+        }
+
+        let tx = self.pending_txs.txs.load(deps.storage, tx_id)?;
+
+        // Verify tx is of the right type and remove it from the map
+        match tx {
+            Tx::InFlightTransferFunds { .. } | Tx::InFlightTransferAllFunds { .. } => {
+                self.pending_txs.remove(deps.storage, tx_id)?;
+                self.pending_fees.remove(deps.storage, tx_id);
+                self.sweep_txs.remove(deps.storage, tx_id);
+            }
+            _ => {
+                return Err(ContractError::WrongTypeTx(tx_id, tx));
+            }
+        };
+
+        Ok(())
+    }
+
+    /// In test code, this is called from `test_commit_withdraw_rewards`.
+    /// In non-test code, this is called from `ibc_packet_ack`
+    pub(crate) fn commit_withdraw_rewards(
+        &self,
+        deps: DepsMut,
+        tx_id: u64,
+    ) -> Result<(), ContractError> {
+        // Fee transfer reached the treasury
+        if self.fee_transfers.has(deps.storage, tx_id) {
+            self.fee_transfers.remove(deps.storage, tx_id);
+            return Ok(());
+        }
+
+        // Load tx
+        let tx = self.pending_txs.txs.load(deps.storage, tx_id)?;
+        self.pending_txs.remove(deps.storage, tx_id)?;
+
+        // Verify tx is of the right type and get data
+        let (amounts, staker) = match tx {
+            Tx::InFlightTransferFunds {
+                amount,
+                staker,
+                validator,
+                ..
+            } => (vec![(validator, amount)], staker),
+            Tx::InFlightTransferAllFunds {
+                amounts, staker, ..
+            } => (amounts, staker),
+            _ => {
+                return Err(ContractError::WrongTypeTx(tx_id, tx));
+            }
+        };
+
+        let swept = self.sweep_txs.has(deps.storage, tx_id);
+        self.sweep_txs.remove(deps.storage, tx_id);
+        if !swept {
+            let withdrawn: Uint128 = amounts.iter().map(|(_, amount)| amount).sum();
+            self.update_user_stats(deps.storage, &staker, |stats| {
+                stats.total_rewards_withdrawn += withdrawn
+            })?;
+        }
+
+        // Update withdrawn_funds to hold this transfer
+        for (validator, amount) in amounts {
+            let mut stake = self
+                .stakes
+                .stake
+                .load(deps.storage, (&staker, &validator))?;
+            stake.withdrawn_funds += amount;
+
+            // Abandoned stakes with nothing left are dropped once swept
+            if swept && stake.stake.high().is_zero() && stake.pending_unbonds.is_empty() {
+                self.stakes.remove(deps.storage, (&staker, &validator))?;
+                self.last_claims.remove(deps.storage, (&staker, &validator));
+                continue;
+            }
+            self.stakes
+                .save(deps.storage, (&staker, &validator), &stake)?;
+        }
+
+        // The fee was kept by the protocol
+        if let Some(fee) = self.pending_fees.may_load(deps.storage, tx_id)? {
+            self.pending_fees.remove(deps.storage, tx_id);
+            self.accrue_fee(deps.storage, fee)?;
+        }
+
+        Ok(())
+    }
+
+    fn update_user_stats(
+        &self,
+        storage: &mut dyn Storage,
+        user: &Addr,
+        update: impl FnOnce(&mut UserStats),
+    ) -> StdResult<()> {
+        let mut stats = self.user_stats.may_load(storage, user)?.unwrap_or_default();
+        update(&mut stats);
+        self.user_stats.save(storage, user, &stats)
+    }
+
+    fn accrue_fee(&self, storage: &mut dyn Storage, fee: Uint128) -> StdResult<()> {
+        let accrued = self.accrued_fees.may_load(storage)?.unwrap_or_default();
+        self.accrued_fees.save(storage, &(accrued + fee))
+    }
+
+    /// Slashes a validator.
+    ///
+    /// In test code, this is called from `test_handle_slashing`.
+    /// In non-test code, this is being called from `ibc_packet_receive` (in the `ConsumerPacket::RemoveValidators`
+    /// handler)
+    #[allow(clippy::too_many_arguments)]
+    /// Applies the pending jail slashes whose grace period ended, if their validator is still
+    /// not active. Slashes of validators that are active again are dropped.
+    fn process_pending_jail_slashes(
+        &self,
+        storage: &mut dyn Storage,
+        env: &Env,
+        config: &Config,
+    ) -> Result<Vec<WasmMsg>, ContractError> {
+        let pending = self
+            .pending_jail_slashes
+            .range(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        let now = env.block.time.seconds();
+
+        let mut msgs = vec![];
+        for ((valoper, infraction_height), slash) in pending {
+            if self.val_set.is_active_validator(storage, &valoper)? {
+                self.pending_jail_slashes
+                    .remove(storage, (&valoper, infraction_height));
+                continue;
+            }
+            if slash.due > now {
+                continue;
+            }
+            self.pending_jail_slashes
+                .remove(storage, (&valoper, infraction_height));
+            let slash_msg = self.handle_slashing(
+                env,
+                storage,
+                config,
+                &valoper,
+                slash.slash_ratio,
+                slash.slash_amount,
+                slash.infraction_time,
+            )?;
+            msgs.extend(slash_msg);
+        }
+        Ok(msgs)
+    }
+
+    pub(crate) fn handle_slashing(
+        &self,
+        env: &Env,
+        storage: &mut dyn Storage,
+        config: &Config,
+        validator: &str,
+        slash_ratio: Decimal,
+        slash_amount: Uint128,
+        infraction_time: u64,
+    ) -> Result<Option<WasmMsg>, ContractError> {
+        let mut metrics = self.metrics.may_load(storage)?.unwrap_or_default();
+        metrics.slash_incidents += 1;
+        self.metrics.save(storage, &metrics)?;
+
+        // Get the list of users staking via this validator
+        // FIXME: It should be over the *historical* (at infraction height) stake. Not over the *current* stake
+        let users = self
+            .stakes
+            .stake
+            .idx
+            .rev
+            .sub_prefix(validator.to_string())
+            .range(storage, None, None, Order::Ascending)
+            .map(|item| {
+                let ((user, _), stake) = item?;
+                Ok::<_, ContractError>((user, stake))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if users.is_empty() {
+            return Ok(None);
+        }
+        // Compute effective slash ratio
+        let total_amount = users
+            .iter()
+            .map(|(_, stake)| stake.stake.high())
+            .sum::<Uint128>();
+        let effective_slash_ratio = Decimal::from_ratio(slash_amount, total_amount);
+
+        // Slash their stake in passing
+        let mut slash_infos = vec![];
+        for (user, ref mut stake) in users {
+            let stake_low = stake.stake.low();
+            let stake_high = stake.stake.high();
+            // Calculating slashing with always the `high` value of the range goes against the user
+            // in some scenario (pending stakes while slashing); but the scenario is relatively
+            // unlikely.
+            if stake_high.is_zero() {
+                continue;
+            }
+            let stake_slash = stake_high * effective_slash_ratio;
+            // Requires proper saturating methods in commit/rollback_stake/unstake
+            stake.stake = ValueRange::new(
+                stake_low.saturating_sub(stake_slash),
+                stake_high - stake_slash,
+            );
+
+            // Distribution alignment
+            let mut distribution = self
+                .distribution
+                .may_load(storage, validator)?
+                .unwrap_or_default();
+            stake
+                .points_alignment
+                .stake_decreased(stake_slash, distribution.points_per_stake);
+            distribution.total_stake = distribution.total_stake.saturating_sub(stake_slash); // Don't fail if pending bond tx
+            self.distribution.save(storage, validator, &distribution)?;
+
+            // Slash the unbondings. We use the nominal slash ratio here, like in the blockchain
+            let pending_slashed = stake.slash_pending(
+                &env.block,
+                slash_ratio,
+                config.unbonding_period,
+                infraction_time,
+            );
+
+            self.stakes.save(storage, (&user, validator), stake)?;
+
+            let slash = stake_slash + pending_slashed;
+            self.update_user_stats(storage, &user, |stats| {
+                stats.total_slashed += slash;
+                stats.slash_count += 1;
+            })?;
+            slash_infos.push(SlashInfo {
+                user: user.to_string(),
+                slash,
+            });
+        }
+        if slash_infos.is_empty() {
+            return Ok(None);
+        }
+
+        // Route associated users to vault for slashing of their collateral
+        let msg = config
+            .vault
+            .process_cross_slashing(slash_infos, validator)?;
+        Ok(Some(msg))
+    }
+
+    /// Queries for contract configuration
+    #[sv::msg(query)]
+    pub fn config(&self, ctx: QueryCtx) -> Result<ConfigResponse, ContractError> {
+        let resp = self.config.load(ctx.deps.storage)?.into();
+        Ok(resp)
+    }
+
+    /// Query for the endpoint that can connect
+    #[sv::msg(query)]
+    pub fn authorized_endpoint(
+        &self,
+        ctx: QueryCtx,
+    ) -> Result<AuthorizedEndpointResponse, ContractError> {
+        let resp = crate::ibc::AUTH_ENDPOINT.load(ctx.deps.storage)?;
+        Ok(resp)
+    }
+
+    /// Query for the endpoint that can connect
+    #[sv::msg(query)]
+    pub fn ibc_channel(&self, ctx: QueryCtx) -> Result<IbcChannelResponse, ContractError> {
+        let channel = IBC_CHANNEL.load(ctx.deps.storage)?;
+        Ok(IbcChannelResponse { channel })
+    }
+
+    /// Show all external validators that we know to be active (and can delegate to)
+    #[sv::msg(query)]
+    pub fn list_active_validators(
+        &self,
+        ctx: QueryCtx,
+        start_after: Option<String>,
+        limit: Option<u64>,
+    ) -> Result<ListActiveValidatorsResponse, ContractError> {
+        let limit = limit.unwrap_or(100) as usize;
+        let validators =
+            self.val_set
+                .list_active_validators(ctx.deps.storage, start_after.as_deref(), limit)?;
+        Ok(ListActiveValidatorsResponse { validators })
+    }
+
+    /// Show all external validators that we know about, along with their state.
+    #[sv::msg(query)]
+    pub fn list_validators(
+        &self,
+        ctx: QueryCtx,
+        start_after: Option<String>,
+        limit: Option<u64>,
+    ) -> Result<ListValidatorsResponse, ContractError> {
+        let limit = limit.unwrap_or(100) as usize;
+        let validators = self
+            .val_set
+            .list_validators(ctx.deps.storage, start_after.as_deref(), limit)?
+            .into_iter()
+            .map(|(valoper, state)| {
+                Ok(crate::msg::ValidatorState {
+                    validator: valoper,
+                    state,
+                })
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+        Ok(ListValidatorsResponse { validators })
+    }
+
+    /// Show the state of an external validator at a given consumer height, from the valset
+    /// history. Useful to verify slashing evidence.
+    /// History older than the unbonding period is dropped, so the state at such heights is
+    /// `Unknown`.
+    #[sv::msg(query)]
+    pub fn validator_at_height(
+        &self,
+        ctx: QueryCtx,
+        valoper: String,
+        height: u64,
+    ) -> Result<ValidatorAtHeightResponse, ContractError> {
+        let update = self
+            .val_set
+            .validator_at_height(ctx.deps.storage, &valoper, height)?;
+        let history_start = self.val_set.history_start(ctx.deps.storage, &valoper)?;
+        let state = update
+            .as_ref()
+            .map_or(State::Unknown {}, |val_state| val_state.state);
+        Ok(ValidatorAtHeightResponse {
+            validator: valoper,
+            height,
+            state,
+            active: state == State::Active {},
+            update,
+            history_start,
+        })
+    }
+
+    /// Queries for stake info
+    ///
+    /// If stake does not exist for (user, validator) pair, the zero-stake is returned
+    #[sv::msg(query)]
+    pub fn stake(
+        &self,
+        ctx: QueryCtx,
+        user: String,
+        validator: String,
+    ) -> Result<Stake, ContractError> {
+        let user = ctx.deps.api.addr_validate(&user)?;
+        let stake = self
+            .stakes
+            .stake
+            .may_load(ctx.deps.storage, (&user, &validator))?
+            .unwrap_or_default();
+
+        Ok(stake)
+    }
+
+    /// Paginated list of user stakes.
+    ///
+    /// `start_after` is the last validator of previous page
+    #[sv::msg(query)]
+    pub fn stakes(
+        &self,
+        ctx: QueryCtx,
+        user: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> Result<StakesResponse, ContractError> {
+        let limit = clamp_page_limit(limit);
+        let user = ctx.deps.api.addr_validate(&user)?;
+
+        let bound = start_after.as_deref().and_then(Bounder::exclusive_bound);
+
+        let stakes = self
+            .stakes
+            .stake
+            .prefix(&user)
+            .range(ctx.deps.storage, bound, None, Order::Ascending)
+            .map(|item| {
+                item.map(|(validator, stake)| {
+                    Ok::<StakeInfo, ContractError>(StakeInfo {
+                        owner: user.to_string(),
+                        validator,
+                        stake,
+                    })
+                })?
+            })
+            .take(limit)
+            .collect::<Result<_, _>>()?;
+
+        let resp = StakesResponse { stakes };
+
+        Ok(resp)
+    }
+
+    /// Lifetime statistics of `user`: total staked, unstaked, rewards withdrawn and slashed.
+    /// All zero for unknown users
+    #[sv::msg(query)]
+    pub fn user_stats(&self, ctx: QueryCtx, user: String) -> Result<UserStats, ContractError> {
+        let user = ctx.deps.api.addr_validate(&user)?;
+        let stats = self
+            .user_stats
+            .may_load(ctx.deps.storage, &user)?
+            .unwrap_or_default();
+        Ok(stats)
+    }
+
+    /// Paginated list of the stakes of all users, ordered by user and validator. For indexers
+    /// to rebuild the whole stake table, without enumerating the users from the vault.
+    ///
+    /// `start_after` is the last `(user, validator)` of previous page
+    #[sv::msg(query)]
+    pub fn all_stakes(
+        &self,
+        ctx: QueryCtx,
+        start_after: Option<(String, String)>,
+        limit: Option<u32>,
+    ) -> Result<StakesResponse, ContractError> {
+        let limit = clamp_page_limit(limit);
+        let start_after = start_after
+            .map(|(user, validator)| {
+                Ok::<_, ContractError>((ctx.deps.api.addr_validate(&user)?, validator))
+            })
+            .transpose()?;
+        let bound = start_after
+            .as_ref()
+            .map(|(user, validator)| Bound::exclusive((user, validator.as_str())));
+
+        let stakes = self
+            .stakes
+            .stake
+            .range(ctx.deps.storage, bound, None, Order::Ascending)
+            .map(|item| {
+                item.map(|((user, validator), stake)| StakeInfo {
+                    owner: user.to_string(),
+                    validator,
+                    stake,
+                })
+            })
+            .take(limit)
+            .collect::<StdResult<_>>()?;
+
+        Ok(StakesResponse { stakes })
+    }
+
+    /// Paginated list of the terms committed stakes of `user` were converted with on the
+    /// consumer. Stakes committed by consumers not reporting them are not listed.
+    ///
+    /// `start_after` is the last tx id of previous page
+    #[sv::msg(query)]
+    pub fn stake_conversions(
+        &self,
+        ctx: QueryCtx,
+        user: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> Result<StakeConversionsResponse, ContractError> {
+        let limit = clamp_page_limit(limit);
+        let user = ctx.deps.api.addr_validate(&user)?;
+
+        let bound = start_after.map(Bound::exclusive);
+
+        let conversions = self
+            .stake_conversions
+            .prefix(&user)
+            .range(ctx.deps.storage, bound, None, Order::Ascending)
+            .map(|item| {
+                let (tx_id, stake) = item?;
+                Ok::<_, ContractError>(StakeConversionInfo {
+                    tx_id,
+                    validator: stake.validator,
+                    amount: stake.amount,
+                    conversion: stake.conversion,
+                })
+            })
+            .take(limit)
+            .collect::<Result<_, _>>()?;
+
+        Ok(StakeConversionsResponse { conversions })
+    }
+
+    /// Paginated list of timed out packets waiting to be resent, by tx id.
+    ///
+    /// `start_after` is the last tx id of previous page
+    #[sv::msg(query)]
+    pub fn retry_queue(
+        &self,
+        ctx: QueryCtx,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> Result<RetryQueueResponse, ContractError> {
+        let limit = clamp_page_limit(limit);
+        let bound = start_after.map(Bound::exclusive);
+
+        let packets = self
+            .retry_queue
+            .range(ctx.deps.storage, bound, None, Order::Ascending)
+            .map(|item| {
+                let (tx_id, queued) = item?;
+                Ok::<_, ContractError>(QueuedPacketInfo {
+                    tx_id,
+                    packet: queued.packet,
+                    attempts: queued.attempts,
+                    retry_at: queued.retry_at,
+                })
+            })
+            .take(limit)
+            .collect::<Result<_, _>>()?;
+
+        Ok(RetryQueueResponse { packets })
+    }
+
+    /// Paginated list of user pending unbonds, with their best-estimate completion times.
+    ///
+    /// `start_after` is the last validator of previous page
+    #[sv::msg(query)]
+    pub fn unbondings(
+        &self,
+        ctx: QueryCtx,
+        user: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> Result<UnbondingsResponse, ContractError> {
+        let limit = clamp_page_limit(limit);
+        let user = ctx.deps.api.addr_validate(&user)?;
+
+        let bound = start_after.as_deref().and_then(Bounder::exclusive_bound);
+
+        // Unbonds are released on the provider clock, so a halted consumer doesn't delay them.
+        // They are only released earlier if the unbonding period is shortened in halt mode.
+        let consumer_halted = self
+            .consumer_stale_for(ctx.deps.storage, &ctx.env)?
+            .is_some();
+        let reduction = if consumer_halted {
+            let config = self.config.load(ctx.deps.storage)?;
+            self.halted_unbonding_reduction(&config)
+        } else {
+            None
+        };
+        let now = ctx.env.block.time;
+
+        let stakes: Vec<_> = self
+            .stakes
+            .stake
+            .prefix(&user)
+            .range(ctx.deps.storage, bound, None, Order::Ascending)
+            .take(limit)
+            .collect::<Result<_, _>>()?;
+
+        let unbondings = stakes
+            .into_iter()
+            .flat_map(|(validator, stake)| {
+                stake
+                    .pending_unbonds
+                    .into_iter()
+                    .map(move |pending| UnbondingInfo {
+                        validator: validator.clone(),
+                        amount: pending.amount,
+                        release_at: pending.release_at,
+                        origin: pending.origin,
+                        // Already released unbonds are not affected by the consumer status
+                        estimated_release_at: match reduction {
+                            Some(reduction) if pending.release_at > now => {
+                                pending.release_at.minus_seconds(reduction).max(now)
+                            }
+                            _ => pending.release_at,
+                        },
+                    })
+            })
+            .collect();
+
+        Ok(UnbondingsResponse {
+            unbondings,
+            consumer_halted,
+        })
+    }
+
+    /// Paginated list of user pending unbonds across validators, with whether they can be
+    /// withdrawn now.
+    ///
+    /// `start_after` is the last validator of previous page
+    #[sv::msg(query)]
+    pub fn all_pending_unbonds(
+        &self,
+        ctx: QueryCtx,
+        user: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> Result<AllPendingUnbondsResponse, ContractError> {
+        let limit = clamp_page_limit(limit);
+        let user = ctx.deps.api.addr_validate(&user)?;
+
+        let bound = start_after.as_deref().and_then(Bounder::exclusive_bound);
+
+        let config = self.config.load(ctx.deps.storage)?;
+        let release_time = self.unbond_release_time(ctx.deps.storage, &ctx.env, &config)?;
+
+        let stakes: Vec<_> = self
+            .stakes
+            .stake
+            .prefix(&user)
+            .range(ctx.deps.storage, bound, None, Order::Ascending)
+            .take(limit)
+            .collect::<Result<_, _>>()?;
+
+        let unbonds = stakes
+            .into_iter()
+            .flat_map(|(validator, stake)| {
+                stake
+                    .pending_unbonds
+                    .into_iter()
+                    .map(move |pending| PendingUnbondInfo {
+                        validator: validator.clone(),
+                        amount: pending.amount,
+                        release_at: pending.release_at,
+                        claimable: pending.release_at <= release_time,
+                    })
+            })
+            .collect();
+
+        Ok(AllPendingUnbondsResponse { unbonds })
+    }
+
+    /// Queries a pending tx.
+    #[sv::msg(query)]
+    fn pending_tx(&self, ctx: QueryCtx, tx_id: u64) -> Result<TxResponse, ContractError> {
+        let resp = self.pending_txs.txs.load(ctx.deps.storage, tx_id)?;
+        Ok(resp)
+    }
+
+    /// Queries for all pending txs.
+    /// Reports txs in descending order (newest first).
+    /// `start_after` is the last tx id included in previous page
+    #[sv::msg(query)]
+    fn all_pending_txs_desc(
+        &self,
+        ctx: QueryCtx,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> Result<AllTxsResponse, ContractError> {
+        let limit = clamp_page_limit(limit);
+        let bound = start_after.and_then(Bounder::exclusive_bound);
+
+        let txs = self
+            .pending_txs
+            .txs
+            .range(ctx.deps.storage, None, bound, Order::Descending)
+            .map(|item| {
+                let (_id, tx) = item?;
+                Ok::<TxResponse, ContractError>(tx)
+            })
+            .take(limit)
+            .collect::<Result<_, _>>()?;
+
+        let resp = AllTxsResponse { txs };
+
+        Ok(resp)
+    }
+
+    /// Queries for the pending txs of `user`.
+    /// Reports txs in descending order (newest first).
+    /// `start_after` is the last tx id included in previous page
+    #[sv::msg(query)]
+    fn pending_txs_by_user(
+        &self,
+        ctx: QueryCtx,
+        user: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> Result<AllTxsResponse, ContractError> {
+        let user = ctx.deps.api.addr_validate(&user)?;
+        let limit = clamp_page_limit(limit);
+        let bound = start_after.and_then(Bounder::exclusive_bound);
+
+        let txs = self
+            .pending_txs
+            .txs
+            .idx
+            .users
+            .prefix(user)
+            .range(ctx.deps.storage, None, bound, Order::Descending)
+            .map(|item| {
+                let (_id, tx) = item?;
+                Ok::<TxResponse, ContractError>(tx)
+            })
+            .take(limit)
+            .collect::<Result<_, _>>()?;
+
+        let resp = AllTxsResponse { txs };
+
+        Ok(resp)
+    }
+
+    /// Valset CRDT storage stats, and how many validators `prune_validators` can currently prune.
+    ///
+    /// This iterates over all validators, so it is meant for off-chain monitoring only.
+    #[sv::msg(query)]
+    pub fn crdt_stats(&self, ctx: QueryCtx) -> Result<CrdtStatsResponse, ContractError> {
+        let stats = self.val_set.stats(ctx.deps.storage)?;
+
+        let config = self.config.load(ctx.deps.storage)?;
+        let max_time = ctx
+            .env
+            .block
+            .time
+            .seconds()
+            .saturating_sub(config.unbonding_period);
+        let (inactive, _) = self.val_set.inactive_validators(
+            ctx.deps.storage,
+            None,
+            stats.validators as usize,
+            max_time,
+        )?;
+        let prunable = inactive
+            .iter()
+            .map(|valoper| self.is_validator_referenced(ctx.deps.storage, valoper))
+            .collect::<StdResult<Vec<_>>>()?
+            .into_iter()
+            .filter(|referenced| !referenced)
+            .count() as u64;
+
+        Ok(CrdtStatsResponse { stats, prunable })
+    }
+
+    /// Monitoring information, as a single scrapeable query.
+    ///
+    /// Pending txs and unbonds are read from running counters, so only the validators are
+    /// iterated over.
+    #[sv::msg(query)]
+    pub fn metrics(&self, ctx: QueryCtx) -> Result<MetricsResponse, ContractError> {
+        let pending_txs = self
+            .pending_txs
+            .counters
+            .may_load(ctx.deps.storage)?
+            .unwrap_or_default();
+
+        let (crdt_validators, crdt_entries) = self.val_set.size(ctx.deps.storage)?;
+
+        let total_stake = self
+            .distribution
+            .range(ctx.deps.storage, None, None, Order::Ascending)
+            .map(|item| item.map(|(_, distribution)| distribution.total_stake))
+            .sum::<StdResult<Uint128>>()?;
+
+        let total_pending_unbonds = self
+            .stakes
+            .pending_total
+            .may_load(ctx.deps.storage)?
+            .unwrap_or_default();
+
+        let metrics = self.metrics.may_load(ctx.deps.storage)?.unwrap_or_default();
+
+        Ok(MetricsResponse {
+            pending_txs,
+            crdt_validators,
+            crdt_entries,
+            last_valset_update_height: metrics.last_valset_update_height,
+            last_valset_update_time: metrics.last_valset_update_time,
+            total_stake,
+            total_pending_unbonds,
+            slash_incidents: metrics.slash_incidents,
+        })
+    }
+
+    /// Returns how much rewards are to be withdrawn by particular user, from the particular
+    /// validator staking
+    #[sv::msg(query)]
+    pub fn pending_rewards(
+        &self,
+        ctx: QueryCtx,
+        user: String,
+        validator: String,
+    ) -> Result<PendingRewards, ContractError> {
+        let user = ctx.deps.api.addr_validate(&user)?;
+
+        let stake = self
+            .stakes
+            .stake
+            .may_load(ctx.deps.storage, (&user, &validator))?
+            .unwrap_or_default();
+
+        let distribution = self
+            .distribution
+            .may_load(ctx.deps.storage, &validator)?
+            .unwrap_or_default();
+
+        let config = self.config.load(ctx.deps.storage)?;
+        let amount =
+            Self::calculate_reward(&stake, &distribution, config.distribution_points_scale())?;
+
+        Ok(PendingRewards {
+            rewards: coin(amount.u128(), config.rewards_denom),
+        })
+    }
+
+    /// Returns how much rewards are to be withdrawn by particular user, iterating over all validators.
+    /// This is like stakes is to stake query, but for rewards.
+    #[sv::msg(query)]
+    pub fn all_pending_rewards(
+        &self,
+        ctx: QueryCtx,
+        user: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> Result<AllPendingRewards, ContractError> {
+        let limit: usize = clamp_page_limit(limit);
+        let user = ctx.deps.api.addr_validate(&user)?;
+
+        let bound = start_after.as_deref().and_then(Bounder::exclusive_bound);
+
+        let config = self.config.load(ctx.deps.storage)?;
+        let points_scale = config.distribution_points_scale();
+
+        let rewards: Vec<_> = self
+            .stakes
+            .stake
+            .prefix(&user)
+            .range(ctx.deps.storage, bound, None, Order::Ascending)
+            .take(limit)
+            .map(|item| {
+                let (validator, stake) = item?;
+                let distribution = self
+                    .distribution
+                    .may_load(ctx.deps.storage, &validator)?
+                    .unwrap_or_default();
+                let amount = Self::calculate_reward(&stake, &distribution, points_scale)?;
+                Ok::<_, ContractError>(ValidatorPendingRewards::new(
+                    validator,
+                    amount.u128(),
+                    &config.rewards_denom,
+                ))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(AllPendingRewards { rewards })
+    }
+
+    /// Calculates reward for the user basing on the `Stake` he want to withdraw rewards from, and
+    /// the corresponding validator `Distribution`.
+    //
+    // It is important to make sure the distribution passed matches the validator for stake. It
+    // could be enforced by taking user and validator in arguments, then fetching data, but
+    // sometimes data are used also for different calculations so we want to avoid double
+    // fetching.
+    fn calculate_reward(
+        stake: &Stake,
+        distribution: &Distribution,
+        points_scale: Uint256,
+    ) -> Result<Uint128, ContractError> {
+        // Calculating rewards with always the `low` value of the range goes against the user in some
+        // scenario (pending unstakes), but the possible errors are small and temporary.
+        let points = distribution.points_per_stake * Uint256::from(stake.stake.low());
+
+        let points = stake.points_alignment.align(points);
+        let total = Uint128::try_from(points / points_scale)?;
+
+        Ok(total - stake.withdrawn_funds)
+    }
+}
+
+pub mod cross_staking {
+    use crate::msg::ReceiveVirtualStake;
+
+    use super::*;
+    use cosmwasm_std::{from_json, Binary};
+    use mesh_apis::{cross_staking_api::CrossStakingApi, local_staking_api::SlashRatioResponse};
+
+    #[contract(module=crate::contract)]
+    #[sv::messages(mesh_apis::cross_staking_api as CrossStakingApi)]
+    impl CrossStakingApi for ExternalStakingContract<'_> {
+        type Error = ContractError;
+
+        #[sv::msg(exec)]
+        fn receive_virtual_stake(
+            &self,
+            ctx: ExecCtx,
+            owner: String,
+            amount: Coin,
+            tx_id: u64,
+            msg: Binary,
+        ) -> Result<Response, Self::Error> {
+            let config = self.config.load(ctx.deps.storage)?;
+            ensure_eq!(ctx.info.sender, config.vault.0, ContractError::Unauthorized);
+
+            // sending proper denom
+            ensure_eq!(
+                amount.denom,
+                config.denom,
+                ContractError::InvalidDenom(config.denom)
+            );
+
+            let owner = ctx.deps.api.addr_validate(&owner)?;
+
+            // The user may have capped their total stake via this contract
+            if let Some(cap) = self.effective_max_exposure(ctx.deps.storage, &owner)? {
+                let exposure = self.total_exposure(ctx.deps.storage, &owner)? + amount.amount;
+                ensure!(exposure <= cap, ContractError::MaxExposureExceeded(cap));
+            }
+
+            // parse and validate message
+            let msg: ReceiveVirtualStake = from_json(msg)?;
+            self.ensure_delegation_policy(ctx.deps.storage, &owner, &msg.validator, amount.amount)?;
+            let queued = self.new_stake_queued(ctx.deps.storage, &config, &msg.validator)?;
+            let mut stake = self
+                .stakes
+                .stake
+                .may_load(ctx.deps.storage, (&owner, &msg.validator))?
+                .unwrap_or_default();
+
+            // Prepare stake addition and save stake.
+            // We don't check for max here, as this call can only come from the `vault` contract, which already
+            // performed the proper check.
+            stake.stake.prepare_add(amount.amount, None)?;
+            self.stakes
+                .save(ctx.deps.storage, (&owner, &msg.validator), &stake)?;
+            if !self
+                .last_claims
+                .has(ctx.deps.storage, (&owner, &msg.validator))
+            {
+                self.last_claims.save(
+                    ctx.deps.storage,
+                    (&owner, &msg.validator),
+                    &ctx.env.block.time,
+                )?;
+            }
+
+            // Save tx
+            let new_tx = Tx::InFlightRemoteStaking {
+                id: tx_id,
+                amount: amount.amount,
+                user: owner.clone(),
+                validator: msg.validator.clone(),
+            };
+            self.pending_txs.save(ctx.deps.storage, tx_id, &new_tx)?;
+
+            let mut resp = Response::new();
+
+            if queued {
+                // The packet is sent once the validator is active again
+                self.queued_stakes
+                    .save(ctx.deps.storage, (&msg.validator, tx_id), &())?;
+            } else {
+                let msg = Self::stake_packet(
+                    ctx.deps.storage,
+                    &ctx.env,
+                    &owner,
+                    &msg.validator,
+                    amount.clone(),
+                    tx_id,
+                )?;
+                // add ibc packet if we are ibc enabled (skip in tests)
+                #[cfg(not(any(feature = "mt", test)))]
+                {
+                    resp = resp.add_message(msg);
+                }
+                #[cfg(any(feature = "mt", test))]
+                {
+                    let _ = msg;
+                }
+            }
+
+            resp = resp
+                .add_attribute("action", "receive_virtual_stake")
+                .add_attribute("owner", owner)
+                .add_attribute("amount", amount.amount.to_string())
+                .add_attribute("tx_id", tx_id.to_string())
+                .add_attribute("queued", queued.to_string());
+
+            Ok(resp)
+        }
+
+        #[sv::msg(exec)]
+        fn burn_virtual_stake(
+            &self,
+            ctx: ExecCtx,
+            owner: String,
+            amount: Coin,
+            validator: Option<String>,
+        ) -> Result<Response, Self::Error> {
+            let config = self.config.load(ctx.deps.storage)?;
+            ensure_eq!(ctx.info.sender, config.vault.0, ContractError::Unauthorized);
+
+            // sending proper denom
+            ensure_eq!(
+                amount.denom,
+                config.denom,
+                ContractError::InvalidDenom(config.denom)
+            );
+
+            let owner = ctx.deps.api.addr_validate(&owner)?;
+
+            let stakes: Vec<_> = match validator {
+                Some(validator) => {
+                    // Burn from validator
+                    // TODO: Preferentially, i.e. burn remaining amount, if any, from other validators
+                    let stake = self
+                        .stakes
+                        .stake
+                        .may_load(ctx.deps.storage, (&owner, &validator))?
+                        .unwrap_or_default();
+                    vec![(validator, stake.stake.high().u128())] // Burn takes precedence over any pending txs
+                }
+                None => {
+                    // Burn proportionally from all validators associated to the user
+                    self.stakes
+                        .stake
+                        .prefix(&owner)
+                        .range(ctx.deps.storage, None, None, Order::Ascending)
+                        .map(|item| {
+                            let (validator, stake) = item?;
+                            Ok::<_, Self::Error>((validator, stake.stake.high().u128()))
+                            // Burn takes precedence over any pending txs
+                        })
+                        .collect::<Result<_, _>>()?
+                }
+            };
+
+            // Error if no stakes
+            if stakes.is_empty() {
+                return Err(ContractError::InsufficientDelegations(
+                    owner.to_string(),
+                    amount.amount,
+                ));
+            }
+
+            let (burned, burns) = mesh_burn::distribute_burn(&stakes, amount.amount.u128());
+
+            // Bail if we don't have enough stake
+            if burned < amount.amount.u128() {
+                return Err(ContractError::InsufficientDelegations(
+                    owner.to_string(),
+                    amount.amount,
+                ));
+            }
+
+            for (validator, burn_amount) in &burns {
+                let burn_amount = Uint128::new(*burn_amount);
+                // Perform stake subtraction
+                let mut stake = self
+                    .stakes
+                    .stake
+                    .load(ctx.deps.storage, (&owner, validator))?;
+                // We don't check for min here, as this call can only come from the `vault` contract, which already
+                // performed the proper check.
+                stake.stake.sub(burn_amount, None)?;
+
+                // Load distribution
+                let mut distribution = self
+                    .distribution
+                    .may_load(ctx.deps.storage, validator)?
+                    .unwrap_or_default();
+
+                // Distribution alignment
+                stake
+                    .points_alignment
+                    .stake_decreased(burn_amount, distribution.points_per_stake);
+                distribution.total_stake -= burn_amount;
+
+                // Save stake
+                self.stakes
+                    .save(ctx.deps.storage, (&owner, validator), &stake)?;
+
+                // Save distribution
+                self.distribution
+                    .save(ctx.deps.storage, validator, &distribution)?;
+            }
+
+            let channel = IBC_CHANNEL.load(ctx.deps.storage)?;
+            let packet = ProviderPacket::Burn {
+                validators: burns.iter().map(|v| v.0.to_string()).collect(),
+                burn: amount.clone(),
+            };
+            let msg = IbcMsg::SendPacket {
+                channel_id: channel.endpoint.channel_id,
+                data: to_json_binary(&packet)?,
+                timeout: packet_timeout(&ctx.env),
+            };
+            let mut resp = Response::new();
+            // add ibc packet if we are ibc enabled (skip in tests)
+            #[cfg(not(any(feature = "mt", test)))]
+            {
+                resp = resp.add_message(msg);
+            }
+            #[cfg(any(feature = "mt", test))]
+            {
+                let _ = msg;
+            }
+
+            resp = resp
+                .add_attribute("action", "burn_virtual_stake")
+                .add_attribute("owner", owner)
+                .add_attribute(
+                    "validators",
+                    stakes
+                        .into_iter()
+                        .map(|s| s.0)
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                )
+                .add_attribute("amount", amount.to_string());
+
+            Ok(resp)
+        }
+
+        #[sv::msg(exec)]
+        fn abort_stake(&self, ctx: ExecCtx, tx_id: u64) -> Result<Response, Self::Error> {
+            let config = self.config.load(ctx.deps.storage)?;
+            ensure_eq!(ctx.info.sender, config.vault.0, ContractError::Unauthorized);
+
+            let (validator, in_flight) = match self.pending_txs.txs.load(ctx.deps.storage, tx_id)? {
+                Tx::InFlightRemoteStaking { validator, .. } => {
+                    let queued = self
+                        .queued_stakes
+                        .has(ctx.deps.storage, (&validator, tx_id));
+                    (validator, !queued)
+                }
+                tx => return Err(ContractError::WrongTypeTx(tx_id, tx)),
+            };
+            // The consumer may still bond it. That is undone once its ack comes back
+            if in_flight {
+                self.aborted_stakes.save(ctx.deps.storage, tx_id, &())?;
+                self.retry_queue.remove(ctx.deps.storage, tx_id);
+            }
+            // The vault rolled its tx back already
+            let msgs = self.undo_stake(ctx.deps, tx_id, false)?;
+
+            let resp = Response::new()
+                .add_submessages(msgs)
+                .add_attribute("action", "abort_stake")
+                .add_attribute("tx_id", tx_id.to_string())
+                .add_attribute("validator", validator)
+                .add_attribute("in_flight", in_flight.to_string());
+
+            Ok(resp)
+        }
+
+        #[sv::msg(query)]
+        fn max_slash(&self, ctx: QueryCtx) -> Result<SlashRatioResponse, ContractError> {
+            let slash_ratio = self.effective_slash_ratio(ctx.deps.storage)?;
+            Ok(SlashRatioResponse {
+                slash_ratio_dsign: slash_ratio.double_sign,
+                slash_ratio_offline: slash_ratio.offline,
+            })
+        }
+    }
+}
+
+// Some unit tests, to test valset updates and slashing side effects in isolation
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::{Attribute, Decimal, DepsMut};
+
+    use crate::crdt::State;
+    use crate::msg::{AuthorizedEndpoint, ReceiveVirtualStake, UnbondingInfo, ValidatorState};
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use mesh_apis::cross_staking_api::CrossStakingApi;
+    use mesh_apis::vault_api::sv::VaultApiExecMsg::CrossSlash;
+
+    static OSMO: &str = "uosmo";
+    static CREATOR: &str = "creator";
+    static OWNER: &str = "user";
+
+    fn do_instantiate(deps: DepsMut) -> (ExecCtx, ExternalStakingContract) {
+        let contract = ExternalStakingContract::new();
+        let mut ctx = InstantiateCtx {
+            deps,
+            env: mock_env(),
+            info: mock_info(CREATOR, &[]),
+        };
+        contract
+            .instantiate(
+                ctx.branch(),
+                OSMO.to_owned(),
+                "ujuno".to_string(),
+                "vault_addr".to_string(),
+                100,
+                AuthorizedEndpoint {
+                    connection_id: "connection_id_1".to_string(),
+                    port_id: "port_id_1".to_string(),
+                },
+                SlashRatio {
+                    double_sign: Decimal::percent(10),
+                    offline: Decimal::percent(10),
+                },
+                None,
+                None,
+                None,
+            )
+            .unwrap();
+        let exec_ctx = ExecCtx {
+            deps: ctx.deps,
+            info: mock_info(OWNER, &[]),
+            env: ctx.env,
+        };
+        (exec_ctx, contract)
+    }
+
+    #[test]
+    fn distribution_precision_is_validated() {
+        let instantiate = |deps: DepsMut, precision| {
+            ExternalStakingContract::new().instantiate(
+                InstantiateCtx {
+                    deps,
+                    env: mock_env(),
+                    info: mock_info(CREATOR, &[]),
+                },
+                OSMO.to_owned(),
+                "ujuno".to_string(),
+                "vault_addr".to_string(),
+                100,
+                AuthorizedEndpoint {
+                    connection_id: "connection_id_1".to_string(),
+                    port_id: "port_id_1".to_string(),
+                },
+                SlashRatio {
+                    double_sign: Decimal::percent(10),
+                    offline: Decimal::percent(10),
+                },
+                None,
+                None,
+                precision,
+            )
+        };
+
+        let mut deps = mock_dependencies();
+        for precision in [
+            MIN_DISTRIBUTION_PRECISION - 1,
+            MAX_DISTRIBUTION_PRECISION + 1,
+        ] {
+            let err = instantiate(deps.as_mut(), Some(precision)).unwrap_err();
+            assert_eq!(err, ContractError::InvalidDistributionPrecision(precision));
+        }
+
+        // Defaults to 9 decimals
+        instantiate(deps.as_mut(), None).unwrap();
+        let config = ExternalStakingContract::new()
+            .config
+            .load(&deps.storage)
+            .unwrap();
+        assert_eq!(
+            config.distribution_precision,
+            DEFAULT_DISTRIBUTION_PRECISION
+        );
+        assert_eq!(
+            config.distribution_points_scale(),
+            Uint256::from(1_000_000_000u128)
+        );
+
+        instantiate(deps.as_mut(), Some(MAX_DISTRIBUTION_PRECISION)).unwrap();
+        let config = ExternalStakingContract::new()
+            .config
+            .load(&deps.storage)
+            .unwrap();
+        assert_eq!(config.distribution_precision, MAX_DISTRIBUTION_PRECISION);
+        assert_eq!(
+            config.distribution_points_scale(),
+            Uint256::from(10u128.pow(24))
+        );
+    }
+
+    #[test]
+    fn authorized_endpoint_update() {
+        let mut deps = mock_dependencies();
+        let (_, contract) = do_instantiate(deps.as_mut());
+        let new_endpoint = AuthorizedEndpoint {
+            connection_id: "connection_id_2".to_string(),
+            port_id: "port_id_2".to_string(),
+        };
+
+        // Not while the channel is open
+        let err = contract
+            .set_authorized_endpoint(
+                SudoCtx {
+                    deps: deps.as_mut(),
+                    env: mock_env(),
+                },
+                new_endpoint.clone(),
+            )
+            .unwrap_err();
+        assert_eq!(err, ContractError::IbcChannelAlreadyOpen);
+
+        let channel = IBC_CHANNEL.load(&deps.storage).unwrap();
+        crate::ibc::ibc_channel_close(
+            deps.as_mut(),
+            mock_env(),
+            cosmwasm_std::IbcChannelCloseMsg::CloseInit { channel },
+        )
+        .unwrap();
+
+        // Invalid endpoints are rejected
+        let err = contract
+            .set_authorized_endpoint(
+                SudoCtx {
+                    deps: deps.as_mut(),
+                    env: mock_env(),
+                },
+                AuthorizedEndpoint {
+                    connection_id: "".to_string(),
+                    port_id: "port_id_2".to_string(),
+                },
+            )
+            .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidEndpoint(_)));
+
+        contract
+            .set_authorized_endpoint(
+                SudoCtx {
+                    deps: deps.as_mut(),
+                    env: mock_env(),
+                },
+                new_endpoint.clone(),
+            )
+            .unwrap();
+        let endpoint = contract
+            .authorized_endpoint(QueryCtx {
+                deps: deps.as_ref(),
+                env: mock_env(),
+            })
+            .unwrap();
+        assert_eq!(endpoint, new_endpoint);
+    }
+
+    #[test]
+    fn instantiating() {
+        let mut deps = mock_dependencies();
+        let _ = do_instantiate(deps.as_mut());
+    }
+
+    #[test]
+    fn valset_update_happy_path() {
+        let mut deps = mock_dependencies();
+        let (mut ctx, contract) = do_instantiate(deps.as_mut());
+
+        // We add three new validators, and tombstone one
+        let adds = vec![
+            AddValidator {
+                valoper: "alice".to_string(),
+                pub_key: "alice_pub_key".to_string(),
+            },
+            AddValidator {
+                valoper: "bob".to_string(),
+                pub_key: "bob_pub_key".to_string(),
+            },
+            AddValidator {
+                valoper: "carl".to_string(),
+                pub_key: "carl_pub_key".to_string(),
+            },
+        ];
+        let tombs = vec!["bob".to_string()];
+
+        let (evt, msgs) = contract
+            .valset_update(
+                ctx.deps.branch(),
+                ctx.env,
+                100,
+                1234,
+                &adds,
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+                &tombs,
+                &[],
+            )
+            .unwrap();
+
+        // Check the event
+        assert_eq!(
+            evt.attributes,
+            vec![
+                Attribute::new("additions", "alice,bob,carl"),
+                Attribute::new("tombstoned", "bob"),
+            ]
+        );
+
+        // Check there are no messages (edge case of tombstoning a validator that is not yet active)
+        assert!(msgs.is_empty());
+
+        let query_deps = ctx.deps;
+        let query_ctx = QueryCtx {
+            deps: query_deps.as_ref(),
+            env: mock_env(),
+        };
+
+        let metrics = contract
+            .metrics(QueryCtx {
+                deps: query_deps.as_ref(),
+                env: mock_env(),
+            })
+            .unwrap();
+        assert_eq!(metrics.last_valset_update_height, Some(100));
+        assert_eq!(metrics.last_valset_update_time, Some(1234));
+        assert_eq!(metrics.crdt_validators, 3);
+
+        let vals = contract.list_validators(query_ctx, None, None).unwrap();
+        assert_eq!(
+            vals.validators,
+            vec![
+                ValidatorState {
+                    validator: "alice".to_string(),
+                    state: State::Active {}
+                },
+                ValidatorState {
+                    validator: "bob".to_string(),
+                    state: State::Tombstoned {}
+                },
+                ValidatorState {
+                    validator: "carl".to_string(),
+                    state: State::Active {}
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn valset_update_tombstoning_and_slashing() {
+        let mut deps = mock_dependencies();
+        let (mut ctx, contract) = do_instantiate(deps.as_mut());
+
+        // We add two new validators, tombstone one and check slashing
+        let adds = vec![
+            AddValidator {
+                valoper: "alice".to_string(),
+                pub_key: "alice_pub_key".to_string(),
+            },
+            AddValidator {
+                valoper: "bob".to_string(),
+                pub_key: "bob_pub_key".to_string(),
+            },
+        ];
+
+        contract
+            .valset_update(
+                ctx.deps.branch(),
+                ctx.env.clone(),
+                100,
+                1234,
+                &adds,
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+            )
+            .unwrap();
+
+        // Cross stake with bob
+        let mut stake_deps = ctx.deps.branch();
+        let vault_info = mock_info("vault_addr", &[]);
+        let stake_ctx = ExecCtx {
+            deps: stake_deps.branch(),
+            env: mock_env(),
+            info: vault_info,
+        };
+        contract
+            .receive_virtual_stake(
+                stake_ctx,
+                OWNER.to_string(),
+                coin(100, "uosmo"),
+                1,
+                to_json_binary(&ReceiveVirtualStake {
+                    validator: "bob".to_string(),
+                })
+                .unwrap(),
+            )
+            .unwrap();
+        // Commit stake
+        contract.commit_stake(stake_deps, 1, None).unwrap();
+
+        // Bob is slashed and tombstoned next
+        let update_ctx = ctx.branch();
+        let tombs = vec!["bob".to_string()];
+        let (evt, msgs) = contract
+            .valset_update(
+                update_ctx.deps,
+                update_ctx.env,
+                200,
+                2345,
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+                &tombs,
+                &[ValidatorSlashInfo {
+                    address: "bob".to_string(),
+                    infraction_height: 200,
+                    infraction_time: 2345,
+                    power: 100,
+                    slash_amount: coin(10, "uosmo"),
+                    slash_ratio: Decimal::percent(10).to_string(),
+                }],
+            )
+            .unwrap();
+
+        // Check the event
+        assert_eq!(
+            evt.attributes,
+            vec![
+                Attribute::new("tombstoned", "bob"),
+                Attribute::new("slashed", "bob")
+            ]
+        );
+
+        // Check the slashing message
+        assert_eq!(msgs.len(), 1);
+        assert_eq!(
+            msgs[0],
+            WasmMsg::Execute {
+                contract_addr: "vault_addr".to_string(),
+                msg: to_json_binary(&CrossSlash {
+                    slashes: vec![SlashInfo {
+                        user: OWNER.to_string(),
+                        slash: Uint128::new(10),
+                    }],
+                    validator: "bob".to_string(),
+                })
+                .unwrap(),
+                funds: vec![],
+            }
+        );
+
+        let query_deps = ctx.deps;
+        let query_ctx = QueryCtx {
+            deps: query_deps.as_ref(),
+            env: mock_env(),
+        };
+
+        let vals = contract.list_validators(query_ctx, None, None).unwrap();
+        assert_eq!(
+            vals.validators,
+            vec![
+                ValidatorState {
+                    validator: "alice".to_string(),
+                    state: State::Active {}
+                },
+                ValidatorState {
+                    validator: "bob".to_string(),
+                    state: State::Tombstoned {}
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn valset_update_tombstoning_and_slashing_pending_bond() {
+        let mut deps = mock_dependencies();
+        let (mut ctx, contract) = do_instantiate(deps.as_mut());
+
+        // We add two new validators, and tombstone one with a pending bond
+        let adds = vec![
+            AddValidator {
+                valoper: "alice".to_string(),
+                pub_key: "alice_pub_key".to_string(),
+            },
+            AddValidator {
+                valoper: "bob".to_string(),
+                pub_key: "bob_pub_key".to_string(),
+            },
+        ];
+
+        contract
+            .valset_update(
+                ctx.deps.branch(),
+                ctx.env.clone(),
+                100,
+                1234,
+                &adds,
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+            )
+            .unwrap();
+
+        // Cross stake with bob
+        let mut stake_deps = ctx.deps.branch();
+        let vault_info = mock_info("vault_addr", &[]);
+        let stake_ctx = ExecCtx {
+            deps: stake_deps.branch(),
+            env: mock_env(),
+            info: vault_info,
+        };
+        contract
+            .receive_virtual_stake(
+                stake_ctx,
+                OWNER.to_string(),
+                coin(100, "uosmo"),
+                1,
+                to_json_binary(&ReceiveVirtualStake {
+                    validator: "bob".to_string(),
+                })
+                .unwrap(),
+            )
+            .unwrap();
+        // Stake tx is pending
+
+        // Bob is slashed and tombstoned next
+        let update_ctx = ctx.branch();
+        let tombs = vec!["bob".to_string()];
+        let (evt, msgs) = contract
+            .valset_update(
+                update_ctx.deps,
+                update_ctx.env,
+                200,
+                2345,
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+                &tombs,
+                &[ValidatorSlashInfo {
+                    address: "bob".to_string(),
+                    infraction_height: 200,
+                    infraction_time: 2345,
+                    power: 100,
+                    slash_amount: coin(10, "uosmo"),
+                    slash_ratio: Decimal::percent(10).to_string(),
+                }],
+            )
+            .unwrap();
+
+        // Check the event
+        assert_eq!(
+            evt.attributes,
+            vec![
+                Attribute::new("tombstoned", "bob"),
+                Attribute::new("slashed", "bob")
+            ]
+        );
+
+        // Check the slashing message
+        assert_eq!(msgs.len(), 1);
+        assert_eq!(
+            msgs[0],
+            WasmMsg::Execute {
+                contract_addr: "vault_addr".to_string(),
+                msg: to_json_binary(&CrossSlash {
+                    slashes: vec![SlashInfo {
+                        user: OWNER.to_string(),
+                        slash: Uint128::new(10),
+                    }],
+                    validator: "bob".to_string(),
+                })
+                .unwrap(),
+                funds: vec![],
+            }
+        );
+
+        let query_deps = ctx.deps;
+        let query_ctx = QueryCtx {
+            deps: query_deps.as_ref(),
+            env: mock_env(),
+        };
+
+        let vals = contract.list_validators(query_ctx, None, None).unwrap();
+        assert_eq!(
+            vals.validators,
+            vec![
+                ValidatorState {
+                    validator: "alice".to_string(),
+                    state: State::Active {}
+                },
+                ValidatorState {
+                    validator: "bob".to_string(),
+                    state: State::Tombstoned {}
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn valset_update_tombstoning_and_slashing_pending_unbond() {
+        let mut deps = mock_dependencies();
+        let (mut ctx, contract) = do_instantiate(deps.as_mut());
+
+        // We add two new validators, and tombstone one with a pending unbond
+        let adds = vec![
+            AddValidator {
+                valoper: "alice".to_string(),
+                pub_key: "alice_pub_key".to_string(),
+            },
+            AddValidator {
+                valoper: "bob".to_string(),
+                pub_key: "bob_pub_key".to_string(),
+            },
+        ];
+
+        contract
+            .valset_update(
+                ctx.deps.branch(),
+                ctx.env.clone(),
+                100,
+                1234,
+                &adds,
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+            )
+            .unwrap();
+
+        // Cross stake with bob
+        let mut stake_deps = ctx.deps.branch();
+        let vault_info = mock_info("vault_addr", &[]);
+        let stake_ctx = ExecCtx {
+            deps: stake_deps.branch(),
+            env: mock_env(),
+            info: vault_info,
+        };
+        contract
+            .receive_virtual_stake(
+                stake_ctx,
+                OWNER.to_string(),
+                coin(100, "uosmo"),
+                1,
+                to_json_binary(&ReceiveVirtualStake {
+                    validator: "bob".to_string(),
+                })
+                .unwrap(),
+            )
+            .unwrap();
+        // Commit stake
+        contract.commit_stake(stake_deps, 1, None).unwrap();
+
+        // OWNER then cross-unstakes half of the stake
+        let mut stake_deps = ctx.deps.branch();
+        let owner_info = mock_info(OWNER, &[]);
+        let stake_ctx = ExecCtx {
+            deps: stake_deps.branch(),
+            env: mock_env(),
+            info: owner_info,
+        };
+        contract
+            .unstake(stake_ctx, "bob".to_string(), coin(50, "uosmo"))
+            .unwrap();
+        // Unstake tx is pending
+
+        // Bob is slashed and tombstoned next
+        let update_ctx = ctx.branch();
+        let tombs = vec!["bob".to_string()];
+        let (evt, msgs) = contract
+            .valset_update(
+                update_ctx.deps,
+                update_ctx.env,
+                200,
+                2345,
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+                &tombs,
+                &[ValidatorSlashInfo {
+                    address: "bob".to_string(),
+                    infraction_height: 200,
+                    infraction_time: 2345,
+                    power: 100,
+                    slash_amount: coin(10, "uosmo"),
+                    slash_ratio: Decimal::percent(10).to_string(),
+                }],
+            )
+            .unwrap();
+
+        // Check the event
+        assert_eq!(
+            evt.attributes,
+            vec![
+                Attribute::new("tombstoned", "bob"),
+                Attribute::new("slashed", "bob")
+            ]
+        );
+
+        // Check the slashing message
+        assert_eq!(msgs.len(), 1);
+        assert_eq!(
+            msgs[0],
+            WasmMsg::Execute {
+                contract_addr: "vault_addr".to_string(),
+                msg: to_json_binary(&CrossSlash {
+                    slashes: vec![SlashInfo {
+                        user: OWNER.to_string(),
+                        slash: Uint128::new(10), // Owner is slashed over the full stake, including pending
+                    }],
+                    validator: "bob".to_string(),
+                })
+                .unwrap(),
+                funds: vec![],
+            }
+        );
+
+        let query_deps = ctx.deps;
+        let query_ctx = QueryCtx {
+            deps: query_deps.as_ref(),
+            env: mock_env(),
+        };
+
+        let vals = contract.list_validators(query_ctx, None, None).unwrap();
+        assert_eq!(
+            vals.validators,
+            vec![
+                ValidatorState {
+                    validator: "alice".to_string(),
+                    state: State::Active {}
+                },
+                ValidatorState {
+                    validator: "bob".to_string(),
+                    state: State::Tombstoned {}
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn valset_update_tombstoning_and_slashing_no_stake() {
+        let mut deps = mock_dependencies();
+        let (mut ctx, contract) = do_instantiate(deps.as_mut());
+
+        // We add two new validators, and tombstone one unbonded one
+        let adds = vec![
+            AddValidator {
+                valoper: "alice".to_string(),
+                pub_key: "alice_pub_key".to_string(),
+            },
+            AddValidator {
+                valoper: "bob".to_string(),
+                pub_key: "bob_pub_key".to_string(),
+            },
+        ];
+
+        contract
+            .valset_update(
+                ctx.deps.branch(),
+                ctx.env.clone(),
+                100,
+                1234,
+                &adds,
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+            )
+            .unwrap();
+        // Bob has no cross-delegations (which can be possible)
+
+        // Bob is slashed and tombstoned next
+        let update_ctx = ctx.branch();
+        let tombs = vec!["bob".to_string()];
+        let (evt, msgs) = contract
+            .valset_update(
+                update_ctx.deps,
+                update_ctx.env,
+                200,
+                2345,
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+                &tombs,
+                &[],
+            )
+            .unwrap();
+
+        // Check the event
+        assert_eq!(evt.attributes, vec![Attribute::new("tombstoned", "bob"),]);
+
+        // Check that no slashing message is emitted
+        assert_eq!(msgs.len(), 0);
+
+        let query_deps = ctx.deps;
+        let query_ctx = QueryCtx {
+            deps: query_deps.as_ref(),
+            env: mock_env(),
+        };
+
+        let vals = contract.list_validators(query_ctx, None, None).unwrap();
+        assert_eq!(
+            vals.validators,
+            vec![
+                ValidatorState {
+                    validator: "alice".to_string(),
+                    state: State::Active {}
+                },
+                ValidatorState {
+                    validator: "bob".to_string(),
+                    state: State::Tombstoned {}
+                },
+            ]
+        );
+    }
+
+    /// Tombstoning of a nonexistent validator is supported for robustness.
+    /// No slashing is performed (non-existent validator assumed to be inactive).
+    #[test]
+    fn valset_update_tombstoning_nonexistent_validator() {
+        let mut deps = mock_dependencies();
+        let (mut ctx, contract) = do_instantiate(deps.as_mut());
+
+        // We add two new validators, and tombstone no-one
+        let adds = vec![
+            AddValidator {
+                valoper: "alice".to_string(),
+                pub_key: "alice_pub_key".to_string(),
+            },
+            AddValidator {
+                valoper: "bob".to_string(),
+                pub_key: "bob_pub_key".to_string(),
+            },
+        ];
+
+        contract
+            .valset_update(
+                ctx.deps.branch(),
+                ctx.env.clone(),
+                100,
+                1234,
+                &adds,
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+            )
+            .unwrap();
+
+        // Carl is tombstoned next
+        let update_ctx = ctx.branch();
+        let tombs = vec!["carl".to_string()];
+        let (evt, msgs) = contract
+            .valset_update(
+                update_ctx.deps,
+                update_ctx.env,
+                200,
+                2345,
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+                &tombs,
+                &[],
+            )
+            .unwrap();
+
+        // Check the event
+        assert_eq!(evt.attributes, vec![Attribute::new("tombstoned", "carl"),]);
+
+        // Check that no slashing message is emitted
+        assert_eq!(msgs.len(), 0);
+
+        let query_deps = ctx.deps;
+        let query_ctx = QueryCtx {
+            deps: query_deps.as_ref(),
+            env: mock_env(),
+        };
+
+        let vals = contract.list_validators(query_ctx, None, None).unwrap();
+        assert_eq!(
+            vals.validators,
+            vec![
+                ValidatorState {
+                    validator: "alice".to_string(),
+                    state: State::Active {}
+                },
+                ValidatorState {
+                    validator: "bob".to_string(),
+                    state: State::Active {}
+                },
+                ValidatorState {
+                    validator: "carl".to_string(),
+                    state: State::Tombstoned {}
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn valset_update_unjailing_noop() {
+        let mut deps = mock_dependencies();
+        let (mut ctx, contract) = do_instantiate(deps.as_mut());
+
+        // We add two new validators, and jail/unjail one
+        let adds = vec![
+            AddValidator {
+                valoper: "alice".to_string(),
+                pub_key: "alice_pub_key".to_string(),
+            },
+            AddValidator {
+                valoper: "bob".to_string(),
+                pub_key: "bob_pub_key".to_string(),
+            },
+        ];
+
+        contract
+            .valset_update(
+                ctx.deps.branch(),
+                ctx.env.clone(),
+                100,
+                1234,
+                &adds,
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+            )
+            .unwrap();
+
+        // Bob is jailed next
+        let update_ctx = ctx.branch();
+        let jails = vec!["bob".to_string()];
+        let (evt, msgs) = contract
+            .valset_update(
+                update_ctx.deps,
+                update_ctx.env,
+                200,
+                2345,
+                &[],
+                &[],
+                &[],
+                &jails,
+                &[],
+                &[],
+                &[],
+                &[],
+            )
+            .unwrap();
+
+        // Check the event
+        assert_eq!(evt.attributes, vec![Attribute::new("jailed", "bob"),]);
+
+        // Check there's no message
+        assert_eq!(msgs.len(), 0);
+
+        // Bob is unjailed next
+        let update_ctx = ctx.branch();
+        let unjails = vec!["bob".to_string()];
+        let (evt, _msgs) = contract
+            .valset_update(
+                update_ctx.deps,
+                update_ctx.env,
+                200,
+                2345,
+                &[],
+                &[],
+                &[],
+                &[],
+                &unjails,
+                &[],
+                &[],
+                &[],
+            )
+            .unwrap();
+
+        // Check the event
+        assert_eq!(evt.attributes, vec![Attribute::new("unjailed", "bob"),]);
+
+        // Check that unjail is a no-op
+        let query_deps = ctx.deps;
+        let query_ctx = QueryCtx {
+            deps: query_deps.as_ref(),
+            env: mock_env(),
+        };
+
+        let vals = contract.list_validators(query_ctx, None, None).unwrap();
+        assert_eq!(
+            vals.validators,
+            vec![
+                ValidatorState {
+                    validator: "alice".to_string(),
+                    state: State::Active {}
+                },
+                ValidatorState {
+                    validator: "bob".to_string(),
+                    state: State::Jailed {}
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn valset_update_unjailing_reactivation() {
+        let mut deps = mock_dependencies();
+        let (mut ctx, contract) = do_instantiate(deps.as_mut());
+
+        // We add two new validators, and jail both
+        let adds = vec![
+            AddValidator {
+                valoper: "alice".to_string(),
+                pub_key: "alice_pub_key".to_string(),
+            },
+            AddValidator {
+                valoper: "bob".to_string(),
+                pub_key: "bob_pub_key".to_string(),
+            },
+        ];
+        let jails = vec!["alice".to_string(), "bob".to_string()];
+        contract
+            .valset_update(
+                ctx.deps.branch(),
+                ctx.env.clone(),
+                100,
+                1234,
+                &adds,
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+            )
+            .unwrap();
+        contract
+            .valset_update(
+                ctx.deps.branch(),
+                ctx.env.clone(),
+                200,
+                2345,
+                &[],
+                &[],
+                &[],
+                &jails,
+                &[],
+                &[],
+                &[],
+                &[],
+            )
+            .unwrap();
+
+        // Both are unjailed next, but only bob is back in the active set
+        let update_ctx = ctx.branch();
+        let reactivated = vec!["bob".to_string()];
+        let (evt, msgs) = contract
+            .valset_update(
+                update_ctx.deps,
+                update_ctx.env,
+                300,
+                3456,
+                &[],
+                &[],
+                &[],
+                &[],
+                &jails,
+                &reactivated,
+                &[],
+                &[],
+            )
+            .unwrap();
+
+        // Check the event
+        assert_eq!(
+            evt.attributes,
+            vec![
+                Attribute::new("unjailed", "alice,bob"),
+                Attribute::new("reactivated", "bob"),
+            ]
+        );
+        assert_eq!(msgs.len(), 0);
+
+        // Bob is active again, keeping its pubkey
+        let query_ctx = QueryCtx {
+            deps: ctx.deps.as_ref(),
+            env: mock_env(),
+        };
+        let vals = contract.list_validators(query_ctx, None, None).unwrap();
+        assert_eq!(
+            vals.validators,
+            vec![
+                ValidatorState {
+                    validator: "alice".to_string(),
+                    state: State::Jailed {}
+                },
+                ValidatorState {
+                    validator: "bob".to_string(),
+                    state: State::Active {}
+                },
+            ]
+        );
+        let bob = contract
+            .val_set
+            .validator_at_height(ctx.deps.storage, "bob", 300)
+            .unwrap()
+            .unwrap();
+        assert_eq!(bob.pub_key, "bob_pub_key");
+    }
+
+    #[test]
+    fn valset_update_jailing_and_slashing() {
+        let mut deps = mock_dependencies();
+        let (mut ctx, contract) = do_instantiate(deps.as_mut());
+
+        // We add two new validators, and jail one
+        let adds = vec![
+            AddValidator {
+                valoper: "alice".to_string(),
+                pub_key: "alice_pub_key".to_string(),
+            },
+            AddValidator {
+                valoper: "bob".to_string(),
+                pub_key: "bob_pub_key".to_string(),
+            },
+        ];
+
+        contract
+            .valset_update(
+                ctx.deps.branch(),
+                ctx.env.clone(),
+                100,
+                1234,
+                &adds,
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+            )
+            .unwrap();
+
+        // Cross stake with bob
+        let mut stake_deps = ctx.deps.branch();
+        let vault_info = mock_info("vault_addr", &[]);
+        let stake_ctx = ExecCtx {
+            deps: stake_deps.branch(),
+            env: mock_env(),
+            info: vault_info,
+        };
+        contract
+            .receive_virtual_stake(
+                stake_ctx,
+                OWNER.to_string(),
+                coin(100, "uosmo"),
+                1,
+                to_json_binary(&ReceiveVirtualStake {
+                    validator: "bob".to_string(),
+                })
+                .unwrap(),
+            )
+            .unwrap();
+        // Commit stake
+        contract.commit_stake(stake_deps, 1, None).unwrap();
+
+        // Bob is slashed and jailed next
+        let update_ctx = ctx.branch();
+        let jails = vec!["bob".to_string()];
+        let (evt, msgs) = contract
+            .valset_update(
+                update_ctx.deps,
+                update_ctx.env,
+                200,
+                2345,
+                &[],
+                &[],
+                &[],
+                &jails,
+                &[],
+                &[],
+                &[],
+                &[ValidatorSlashInfo {
+                    address: "bob".to_string(),
+                    infraction_height: 200,
+                    infraction_time: 2345,
+                    power: 100,
+                    slash_amount: coin(10, "uosmo"),
+                    slash_ratio: Decimal::percent(10).to_string(),
+                }],
+            )
+            .unwrap();
+
+        // Check the event
+        assert_eq!(
+            evt.attributes,
+            vec![
+                Attribute::new("jailed", "bob"),
+                Attribute::new("slashed", "bob")
+            ]
+        );
+
+        // Check the slashing message
+        assert_eq!(msgs.len(), 1);
+        assert_eq!(
+            msgs[0],
+            WasmMsg::Execute {
+                contract_addr: "vault_addr".to_string(),
+                msg: to_json_binary(&CrossSlash {
+                    slashes: vec![SlashInfo {
+                        user: OWNER.to_string(),
+                        slash: Uint128::new(10),
+                    }],
+                    validator: "bob".to_string(),
+                })
+                .unwrap(),
+                funds: vec![],
+            }
+        );
+
+        let query_deps = ctx.deps;
+        let query_ctx = QueryCtx {
+            deps: query_deps.as_ref(),
+            env: mock_env(),
+        };
+
+        let vals = contract.list_validators(query_ctx, None, None).unwrap();
+        assert_eq!(
+            vals.validators,
+            vec![
+                ValidatorState {
+                    validator: "alice".to_string(),
+                    state: State::Active {}
+                },
+                ValidatorState {
+                    validator: "bob".to_string(),
+                    state: State::Jailed {}
+                },
+            ]
+        );
     }
-}
-
-// Some unit tests, to test valset updates and slashing side effects in isolation
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use cosmwasm_std::{Attribute, Decimal, DepsMut};
 
-    use crate::crdt::State;
-    use crate::msg::{AuthorizedEndpoint, ReceiveVirtualStake, ValidatorState};
-    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-    use mesh_apis::cross_staking_api::CrossStakingApi;
-    use mesh_apis::vault_api::sv::VaultApiExecMsg::CrossSlash;
+    #[test]
+    fn valset_update_delayed_jail_slashing() {
+        let mut deps = mock_dependencies();
+        let (mut ctx, contract) = do_instantiate(deps.as_mut());
+        contract
+            .config
+            .update(ctx.deps.storage, |mut config| {
+                config.jail_slash_policy = JailSlashPolicy::Delayed { grace_period: 100 };
+                Ok::<_, ContractError>(config)
+            })
+            .unwrap();
 
-    static OSMO: &str = "uosmo";
-    static CREATOR: &str = "creator";
-    static OWNER: &str = "user";
+        let adds = vec![
+            AddValidator {
+                valoper: "alice".to_string(),
+                pub_key: "alice_pub_key".to_string(),
+            },
+            AddValidator {
+                valoper: "bob".to_string(),
+                pub_key: "bob_pub_key".to_string(),
+            },
+        ];
+        contract
+            .valset_update(
+                ctx.deps.branch(),
+                ctx.env.clone(),
+                100,
+                1234,
+                &adds,
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+            )
+            .unwrap();
 
-    fn do_instantiate(deps: DepsMut) -> (ExecCtx, ExternalStakingContract) {
-        let contract = ExternalStakingContract::new();
-        let mut ctx = InstantiateCtx {
-            deps,
+        // Cross stake with bob
+        let mut stake_deps = ctx.deps.branch();
+        let stake_ctx = ExecCtx {
+            deps: stake_deps.branch(),
             env: mock_env(),
-            info: mock_info(CREATOR, &[]),
+            info: mock_info("vault_addr", &[]),
         };
         contract
-            .instantiate(
-                ctx.branch(),
-                OSMO.to_owned(),
-                "ujuno".to_string(),
-                "vault_addr".to_string(),
-                100,
-                AuthorizedEndpoint {
-                    connection_id: "connection_id_1".to_string(),
-                    port_id: "port_id_1".to_string(),
-                },
-                SlashRatio {
-                    double_sign: Decimal::percent(10),
-                    offline: Decimal::percent(10),
-                },
+            .receive_virtual_stake(
+                stake_ctx,
+                OWNER.to_string(),
+                coin(100, "uosmo"),
+                1,
+                to_json_binary(&ReceiveVirtualStake {
+                    validator: "bob".to_string(),
+                })
+                .unwrap(),
             )
             .unwrap();
-        let exec_ctx = ExecCtx {
-            deps: ctx.deps,
-            info: mock_info(OWNER, &[]),
-            env: ctx.env,
+        contract.commit_stake(stake_deps, 1, None).unwrap();
+
+        // Both are slashed and jailed. The slashes are delayed
+        let slash_info = |address: &str| ValidatorSlashInfo {
+            address: address.to_string(),
+            infraction_height: 200,
+            infraction_time: 2345,
+            power: 100,
+            slash_amount: coin(10, "uosmo"),
+            slash_ratio: Decimal::percent(10).to_string(),
         };
-        (exec_ctx, contract)
-    }
+        let jails = vec!["alice".to_string(), "bob".to_string()];
+        let (evt, msgs) = contract
+            .valset_update(
+                ctx.deps.branch(),
+                ctx.env.clone(),
+                200,
+                2345,
+                &[],
+                &[],
+                &[],
+                &jails,
+                &[],
+                &[],
+                &[],
+                &[slash_info("alice"), slash_info("bob")],
+            )
+            .unwrap();
+        assert_eq!(msgs, []);
+        assert!(evt
+            .attributes
+            .contains(&Attribute::new("jail_slashes_delayed", "alice,bob")));
+        let query_ctx = QueryCtx {
+            deps: ctx.deps.as_ref(),
+            env: ctx.env.clone(),
+        };
+        let pending = contract.pending_jail_slashes(query_ctx).unwrap().slashes;
+        assert_eq!(pending.len(), 2);
+        assert_eq!(pending[1].due, ctx.env.block.time.seconds() + 100);
 
-    #[test]
-    fn instantiating() {
-        let mut deps = mock_dependencies();
-        let _ = do_instantiate(deps.as_mut());
+        // Alice is unjailed within the grace period, and her slash is dropped
+        contract
+            .valset_update(
+                ctx.deps.branch(),
+                ctx.env.clone(),
+                300,
+                3456,
+                &adds[..1],
+                &[],
+                &[],
+                &[],
+                &["alice".to_string()],
+                &[],
+                &[],
+                &[],
+            )
+            .unwrap();
+
+        // Bob is slashed once the grace period ends
+        ctx.env.block.time = ctx.env.block.time.plus_seconds(100);
+        let (_, msgs) = contract
+            .valset_update(
+                ctx.deps.branch(),
+                ctx.env.clone(),
+                400,
+                4567,
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+            )
+            .unwrap();
+        assert_eq!(
+            msgs,
+            [SubMsg::new(WasmMsg::Execute {
+                contract_addr: "vault_addr".to_string(),
+                msg: to_json_binary(&CrossSlash {
+                    slashes: vec![SlashInfo {
+                        user: OWNER.to_string(),
+                        slash: Uint128::new(10),
+                    }],
+                    validator: "bob".to_string(),
+                })
+                .unwrap(),
+                funds: vec![],
+            })]
+        );
+        let query_ctx = QueryCtx {
+            deps: ctx.deps.as_ref(),
+            env: ctx.env.clone(),
+        };
+        assert_eq!(
+            contract.pending_jail_slashes(query_ctx).unwrap().slashes,
+            []
+        );
     }
 
     #[test]
-    fn valset_update_happy_path() {
+    fn valset_update_removal_works() {
         let mut deps = mock_dependencies();
         let (mut ctx, contract) = do_instantiate(deps.as_mut());
 
-        // We add three new validators, and tombstone one
+        // We add two new validators, and remove one
         let adds = vec![
             AddValidator {
                 valoper: "alice".to_string(),
@@ -1615,17 +6289,12 @@ mod tests {
                 valoper: "bob".to_string(),
                 pub_key: "bob_pub_key".to_string(),
             },
-            AddValidator {
-                valoper: "carl".to_string(),
-                pub_key: "carl_pub_key".to_string(),
-            },
         ];
-        let tombs = vec!["bob".to_string()];
 
-        let (evt, msgs) = contract
+        contract
             .valset_update(
                 ctx.deps.branch(),
-                ctx.env,
+                ctx.env.clone(),
                 100,
                 1234,
                 &adds,
@@ -1633,55 +6302,189 @@ mod tests {
                 &[],
                 &[],
                 &[],
-                &tombs,
                 &[],
+                &[],
+                &[],
+            )
+            .unwrap();
+
+        // Bob is removed next
+        let update_ctx = ctx.branch();
+        let rems = vec!["bob".to_string()];
+        let (evt, msgs) = contract
+            .valset_update(
+                update_ctx.deps,
+                update_ctx.env,
+                200,
+                2345,
+                &[],
+                &rems,
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+            )
+            .unwrap();
+
+        // Check the event
+        assert_eq!(evt.attributes, vec![Attribute::new("removals", "bob"),]);
+
+        // Check there's no slashing message
+        assert_eq!(msgs.len(), 0);
+
+        let query_deps = ctx.deps;
+        let query_ctx = QueryCtx {
+            deps: query_deps.as_ref(),
+            env: mock_env(),
+        };
+
+        let vals = contract.list_validators(query_ctx, None, None).unwrap();
+        assert_eq!(
+            vals.validators,
+            vec![
+                ValidatorState {
+                    validator: "alice".to_string(),
+                    state: State::Active {}
+                },
+                ValidatorState {
+                    validator: "bob".to_string(),
+                    state: State::Unbonded {}
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn valset_resync_reconciles_range() {
+        let mut deps = mock_dependencies();
+        let (mut ctx, contract) = do_instantiate(deps.as_mut());
+
+        let add = |valoper: &str| AddValidator {
+            valoper: valoper.to_string(),
+            pub_key: format!("{valoper}_pub_key"),
+        };
+        contract
+            .valset_update(
+                ctx.deps.branch(),
+                ctx.env.clone(),
+                100,
+                1234,
+                &[
+                    add("alice"),
+                    add("ben"),
+                    add("bert"),
+                    add("bob"),
+                    add("carl"),
+                    add("dave"),
+                ],
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+            )
+            .unwrap();
+        contract
+            .valset_update(
+                ctx.deps.branch(),
+                ctx.env.clone(),
+                150,
+                1300,
+                &[],
+                &[],
+                &[],
+                &["ben".to_string(), "carl".to_string()],
+                &[],
+                &[],
+                &["bert".to_string()],
+                &[],
+            )
+            .unwrap();
+
+        // First chunk. Bob went missing, and carl is active again on the consumer.
+        // Ben is still jailed, and tombstoned bert cannot come back
+        let (evt, msgs) = contract
+            .valset_resync(
+                ctx.deps.branch(),
+                ctx.env.clone(),
+                200,
+                2345,
+                None,
+                Some("carl"),
+                &[add("alice"), add("bert"), add("carl")],
             )
             .unwrap();
-
-        // Check the event
         assert_eq!(
             evt.attributes,
             vec![
-                Attribute::new("additions", "alice,bob,carl"),
-                Attribute::new("tombstoned", "bob"),
+                Attribute::new("start_after", ""),
+                Attribute::new("end", "carl"),
+                Attribute::new("validators", "3"),
+                Attribute::new("unchanged", "1"),
+                Attribute::new("added", "carl"),
+                Attribute::new("unbonded", "bob"),
+                Attribute::new("tombstoned", "bert"),
             ]
         );
-
-        // Check there are no messages (edge case of tombstoning a validator that is not yet active)
         assert!(msgs.is_empty());
 
-        let query_deps = ctx.deps;
+        // Last chunk. Dave is outside the first chunk range, so it was kept until now
+        let (evt, _) = contract
+            .valset_resync(
+                ctx.deps.branch(),
+                ctx.env.clone(),
+                200,
+                2345,
+                Some("carl"),
+                None,
+                &[add("erin")],
+            )
+            .unwrap();
+        assert_eq!(
+            evt.attributes,
+            vec![
+                Attribute::new("start_after", "carl"),
+                Attribute::new("end", ""),
+                Attribute::new("validators", "1"),
+                Attribute::new("unchanged", "0"),
+                Attribute::new("added", "erin"),
+                Attribute::new("unbonded", "dave"),
+            ]
+        );
+
         let query_ctx = QueryCtx {
-            deps: query_deps.as_ref(),
+            deps: ctx.deps.as_ref(),
             env: mock_env(),
         };
-
         let vals = contract.list_validators(query_ctx, None, None).unwrap();
+        let state = |validator: &str, state: State| ValidatorState {
+            validator: validator.to_string(),
+            state,
+        };
         assert_eq!(
             vals.validators,
             vec![
-                ValidatorState {
-                    validator: "alice".to_string(),
-                    state: State::Active {}
-                },
-                ValidatorState {
-                    validator: "bob".to_string(),
-                    state: State::Tombstoned {}
-                },
-                ValidatorState {
-                    validator: "carl".to_string(),
-                    state: State::Active {}
-                }
+                state("alice", State::Active {}),
+                state("ben", State::Jailed {}),
+                state("bert", State::Tombstoned {}),
+                state("bob", State::Unbonded {}),
+                state("carl", State::Active {}),
+                state("dave", State::Unbonded {}),
+                state("erin", State::Active {}),
             ]
         );
     }
 
     #[test]
-    fn valset_update_tombstoning_and_slashing() {
+    fn valset_update_updates_keep_state() {
         let mut deps = mock_dependencies();
         let (mut ctx, contract) = do_instantiate(deps.as_mut());
 
-        // We add two new validators, tombstone one and check slashing
+        // We add two new validators, and remove/update one
         let adds = vec![
             AddValidator {
                 valoper: "alice".to_string(),
@@ -1706,84 +6509,58 @@ mod tests {
                 &[],
                 &[],
                 &[],
+                &[],
             )
             .unwrap();
 
-        // Cross stake with bob
-        let mut stake_deps = ctx.deps.branch();
-        let vault_info = mock_info("vault_addr", &[]);
-        let stake_ctx = ExecCtx {
-            deps: stake_deps.branch(),
-            env: mock_env(),
-            info: vault_info,
-        };
-        contract
-            .receive_virtual_stake(
-                stake_ctx,
-                OWNER.to_string(),
-                coin(100, "uosmo"),
-                1,
-                to_json_binary(&ReceiveVirtualStake {
-                    validator: "bob".to_string(),
-                })
-                .unwrap(),
-            )
-            .unwrap();
-        // Commit stake
-        contract.commit_stake(stake_deps, 1).unwrap();
-
-        // Bob is slashed and tombstoned next
+        // Bob is removed next
         let update_ctx = ctx.branch();
-        let tombs = vec!["bob".to_string()];
-        let (evt, msgs) = contract
+        let rems = vec!["bob".to_string()];
+        let (evt, _msgs) = contract
             .valset_update(
                 update_ctx.deps,
                 update_ctx.env,
                 200,
                 2345,
                 &[],
+                &rems,
+                &[],
+                &[],
                 &[],
                 &[],
                 &[],
                 &[],
-                &tombs,
-                &[ValidatorSlashInfo {
-                    address: "bob".to_string(),
-                    infraction_height: 200,
-                    infraction_time: 2345,
-                    power: 100,
-                    slash_amount: coin(10, "uosmo"),
-                    slash_ratio: Decimal::percent(10).to_string(),
-                }],
             )
             .unwrap();
 
         // Check the event
-        assert_eq!(
-            evt.attributes,
-            vec![
-                Attribute::new("tombstoned", "bob"),
-                Attribute::new("slashed", "bob")
-            ]
-        );
+        assert_eq!(evt.attributes, vec![Attribute::new("removals", "bob"),]);
 
-        // Check the slashing message
-        assert_eq!(msgs.len(), 1);
-        assert_eq!(
-            msgs[0],
-            WasmMsg::Execute {
-                contract_addr: "vault_addr".to_string(),
-                msg: to_json_binary(&CrossSlash {
-                    slashes: vec![SlashInfo {
-                        user: OWNER.to_string(),
-                        slash: Uint128::new(10),
-                    }],
-                    validator: "bob".to_string(),
-                })
-                .unwrap(),
-                funds: vec![],
-            }
-        );
+        // Bob is updated next
+        let update_ctx = ctx.branch();
+        let upds = vec![AddValidator {
+            valoper: "bob".to_string(),
+            pub_key: "bob_pub_key_updated".to_string(),
+        }];
+        let (evt, _msgs) = contract
+            .valset_update(
+                update_ctx.deps,
+                update_ctx.env,
+                300,
+                3456,
+                &[],
+                &[],
+                &upds,
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+            )
+            .unwrap();
+
+        // Check the event
+        assert_eq!(evt.attributes, vec![Attribute::new("updated", "bob"),]);
 
         let query_deps = ctx.deps;
         let query_ctx = QueryCtx {
@@ -1791,6 +6568,7 @@ mod tests {
             env: mock_env(),
         };
 
+        // Check that bob is still unbonded
         let vals = contract.list_validators(query_ctx, None, None).unwrap();
         assert_eq!(
             vals.validators,
@@ -1801,29 +6579,21 @@ mod tests {
                 },
                 ValidatorState {
                     validator: "bob".to_string(),
-                    state: State::Tombstoned {}
+                    state: State::Unbonded {}
                 },
             ]
         );
     }
 
     #[test]
-    fn valset_update_tombstoning_and_slashing_pending_bond() {
+    fn stake_conversions_are_recorded() {
         let mut deps = mock_dependencies();
         let (mut ctx, contract) = do_instantiate(deps.as_mut());
 
-        // We add two new validators, and tombstone one with a pending bond
-        let adds = vec![
-            AddValidator {
-                valoper: "alice".to_string(),
-                pub_key: "alice_pub_key".to_string(),
-            },
-            AddValidator {
-                valoper: "bob".to_string(),
-                pub_key: "bob_pub_key".to_string(),
-            },
-        ];
-
+        let adds = vec![AddValidator {
+            valoper: "alice".to_string(),
+            pub_key: "alice_pub_key".to_string(),
+        }];
         contract
             .valset_update(
                 ctx.deps.branch(),
@@ -1837,123 +6607,80 @@ mod tests {
                 &[],
                 &[],
                 &[],
+                &[],
             )
             .unwrap();
 
-        // Cross stake with bob
-        let mut stake_deps = ctx.deps.branch();
-        let vault_info = mock_info("vault_addr", &[]);
-        let stake_ctx = ExecCtx {
-            deps: stake_deps.branch(),
-            env: mock_env(),
-            info: vault_info,
+        // Two stakes, only the second one committed with conversion terms
+        for tx_id in [1, 2] {
+            let stake_ctx = ExecCtx {
+                deps: ctx.deps.branch(),
+                env: mock_env(),
+                info: mock_info("vault_addr", &[]),
+            };
+            contract
+                .receive_virtual_stake(
+                    stake_ctx,
+                    OWNER.to_string(),
+                    coin(100, OSMO),
+                    tx_id,
+                    to_json_binary(&ReceiveVirtualStake {
+                        validator: "alice".to_string(),
+                    })
+                    .unwrap(),
+                )
+                .unwrap();
+        }
+        let conversion = ConversionTerms {
+            converted: coin(45, "ustake"),
+            price: Decimal::percent(50),
+            price_adjustment: Decimal::percent(90),
+            height: 4321,
+            epoch: Some(4300),
         };
+        contract.commit_stake(ctx.deps.branch(), 1, None).unwrap();
         contract
-            .receive_virtual_stake(
-                stake_ctx,
-                OWNER.to_string(),
-                coin(100, "uosmo"),
-                1,
-                to_json_binary(&ReceiveVirtualStake {
-                    validator: "bob".to_string(),
-                })
-                .unwrap(),
-            )
+            .commit_stake(ctx.deps.branch(), 2, Some(conversion.clone()))
             .unwrap();
-        // Stake tx is pending
 
-        // Bob is slashed and tombstoned next
-        let update_ctx = ctx.branch();
-        let tombs = vec!["bob".to_string()];
-        let (evt, msgs) = contract
-            .valset_update(
-                update_ctx.deps,
-                update_ctx.env,
-                200,
-                2345,
-                &[],
-                &[],
-                &[],
-                &[],
-                &[],
-                &tombs,
-                &[ValidatorSlashInfo {
-                    address: "bob".to_string(),
-                    infraction_height: 200,
-                    infraction_time: 2345,
-                    power: 100,
-                    slash_amount: coin(10, "uosmo"),
-                    slash_ratio: Decimal::percent(10).to_string(),
-                }],
-            )
+        let query_ctx = QueryCtx {
+            deps: ctx.deps.as_ref(),
+            env: mock_env(),
+        };
+        let resp = contract
+            .stake_conversions(query_ctx, OWNER.to_string(), None, None)
             .unwrap();
-
-        // Check the event
-        assert_eq!(
-            evt.attributes,
-            vec![
-                Attribute::new("tombstoned", "bob"),
-                Attribute::new("slashed", "bob")
-            ]
-        );
-
-        // Check the slashing message
-        assert_eq!(msgs.len(), 1);
         assert_eq!(
-            msgs[0],
-            WasmMsg::Execute {
-                contract_addr: "vault_addr".to_string(),
-                msg: to_json_binary(&CrossSlash {
-                    slashes: vec![SlashInfo {
-                        user: OWNER.to_string(),
-                        slash: Uint128::new(10),
-                    }],
-                    validator: "bob".to_string(),
-                })
-                .unwrap(),
-                funds: vec![],
-            }
+            resp.conversions,
+            vec![StakeConversionInfo {
+                tx_id: 2,
+                validator: "alice".to_string(),
+                amount: Uint128::new(100),
+                conversion,
+            }]
         );
 
-        let query_deps = ctx.deps;
+        // Nothing after the last page
         let query_ctx = QueryCtx {
-            deps: query_deps.as_ref(),
+            deps: ctx.deps.as_ref(),
             env: mock_env(),
         };
-
-        let vals = contract.list_validators(query_ctx, None, None).unwrap();
-        assert_eq!(
-            vals.validators,
-            vec![
-                ValidatorState {
-                    validator: "alice".to_string(),
-                    state: State::Active {}
-                },
-                ValidatorState {
-                    validator: "bob".to_string(),
-                    state: State::Tombstoned {}
-                },
-            ]
-        );
+        let resp = contract
+            .stake_conversions(query_ctx, OWNER.to_string(), Some(2), None)
+            .unwrap();
+        assert_eq!(resp.conversions, vec![]);
     }
 
     #[test]
-    fn valset_update_tombstoning_and_slashing_pending_unbond() {
+    fn compounding_rewards() {
         let mut deps = mock_dependencies();
         let (mut ctx, contract) = do_instantiate(deps.as_mut());
+        let vault = VaultApiHelper(Addr::unchecked("vault_addr"));
 
-        // We add two new validators, and tombstone one with a pending unbond
-        let adds = vec![
-            AddValidator {
-                valoper: "alice".to_string(),
-                pub_key: "alice_pub_key".to_string(),
-            },
-            AddValidator {
-                valoper: "bob".to_string(),
-                pub_key: "bob_pub_key".to_string(),
-            },
-        ];
-
+        let adds = vec![AddValidator {
+            valoper: "alice".to_string(),
+            pub_key: "alice_pub_key".to_string(),
+        }];
         contract
             .valset_update(
                 ctx.deps.branch(),
@@ -1967,137 +6694,161 @@ mod tests {
                 &[],
                 &[],
                 &[],
+                &[],
             )
             .unwrap();
 
-        // Cross stake with bob
-        let mut stake_deps = ctx.deps.branch();
-        let vault_info = mock_info("vault_addr", &[]);
+        // Stake with no known conversion terms
         let stake_ctx = ExecCtx {
-            deps: stake_deps.branch(),
+            deps: ctx.deps.branch(),
             env: mock_env(),
-            info: vault_info,
+            info: mock_info("vault_addr", &[]),
         };
         contract
             .receive_virtual_stake(
                 stake_ctx,
                 OWNER.to_string(),
-                coin(100, "uosmo"),
+                coin(100, OSMO),
                 1,
                 to_json_binary(&ReceiveVirtualStake {
-                    validator: "bob".to_string(),
+                    validator: "alice".to_string(),
                 })
                 .unwrap(),
             )
             .unwrap();
-        // Commit stake
-        contract.commit_stake(stake_deps, 1).unwrap();
+        contract.commit_stake(ctx.deps.branch(), 1, None).unwrap();
+        contract
+            .distribute_rewards(ctx.deps.branch(), "alice", coin(90, "ujuno"))
+            .unwrap();
 
-        // OWNER then cross-unstakes half of the stake
-        let mut stake_deps = ctx.deps.branch();
-        let owner_info = mock_info(OWNER, &[]);
+        let compound_ctx = ExecCtx {
+            deps: ctx.deps.branch(),
+            env: mock_env(),
+            info: mock_info(OWNER, &[]),
+        };
+        let err = contract
+            .compound_rewards(compound_ctx, "alice".to_string())
+            .unwrap_err();
+        assert_eq!(err, ContractError::NoConversionTerms("alice".to_string()));
+
+        // Another stake, committed with conversion terms of 2 uosmo per ujuno
         let stake_ctx = ExecCtx {
-            deps: stake_deps.branch(),
+            deps: ctx.deps.branch(),
             env: mock_env(),
-            info: owner_info,
+            info: mock_info("vault_addr", &[]),
         };
         contract
-            .unstake(stake_ctx, "bob".to_string(), coin(50, "uosmo"))
-            .unwrap();
-        // Unstake tx is pending
-
-        // Bob is slashed and tombstoned next
-        let update_ctx = ctx.branch();
-        let tombs = vec!["bob".to_string()];
-        let (evt, msgs) = contract
-            .valset_update(
-                update_ctx.deps,
-                update_ctx.env,
-                200,
-                2345,
-                &[],
-                &[],
-                &[],
-                &[],
-                &[],
-                &tombs,
-                &[ValidatorSlashInfo {
-                    address: "bob".to_string(),
-                    infraction_height: 200,
-                    infraction_time: 2345,
-                    power: 100,
-                    slash_amount: coin(10, "uosmo"),
-                    slash_ratio: Decimal::percent(10).to_string(),
-                }],
+            .receive_virtual_stake(
+                stake_ctx,
+                OWNER.to_string(),
+                coin(100, OSMO),
+                2,
+                to_json_binary(&ReceiveVirtualStake {
+                    validator: "alice".to_string(),
+                })
+                .unwrap(),
             )
             .unwrap();
+        let conversion = ConversionTerms {
+            converted: coin(50, "ujuno"),
+            price: Decimal::percent(50),
+            price_adjustment: Decimal::one(),
+            height: 4321,
+            epoch: None,
+        };
+        contract
+            .commit_stake(ctx.deps.branch(), 2, Some(conversion))
+            .unwrap();
 
-        // Check the event
+        // The rewards are compounded into 180 uosmo more stake, liened in the vault right away
+        let compound_ctx = ExecCtx {
+            deps: ctx.deps.branch(),
+            env: mock_env(),
+            info: mock_info(OWNER, &[]),
+        };
+        let resp = contract
+            .compound_rewards(compound_ctx, "alice".to_string())
+            .unwrap();
         assert_eq!(
-            evt.attributes,
-            vec![
-                Attribute::new("tombstoned", "bob"),
-                Attribute::new("slashed", "bob")
-            ]
+            resp.messages[0].msg,
+            vault
+                .compound_cross_stake(OWNER.to_string(), coin(180, OSMO))
+                .unwrap()
+                .into()
         );
+        let tx_id = contract.tx_count.load(ctx.deps.storage).unwrap();
 
-        // Check the slashing message
-        assert_eq!(msgs.len(), 1);
+        // Rewards cannot be withdrawn while in flight
+        let withdraw_ctx = ExecCtx {
+            deps: ctx.deps.branch(),
+            env: mock_env(),
+            info: mock_info(OWNER, &[]),
+        };
+        let err = contract
+            .withdraw_rewards(withdraw_ctx, "alice".to_string(), "remote".to_string())
+            .unwrap_err();
+        assert_eq!(err, ContractError::NoRewards);
+
+        // A failed compounding gives the rewards back, and releases the lien
+        let msgs = contract.rollback_stake(ctx.deps.branch(), tx_id).unwrap();
         assert_eq!(
-            msgs[0],
-            WasmMsg::Execute {
-                contract_addr: "vault_addr".to_string(),
-                msg: to_json_binary(&CrossSlash {
-                    slashes: vec![SlashInfo {
-                        user: OWNER.to_string(),
-                        slash: Uint128::new(10), // Owner is slashed over the full stake, including pending
-                    }],
-                    validator: "bob".to_string(),
-                })
-                .unwrap(),
-                funds: vec![],
-            }
+            msgs[0].msg,
+            vault
+                .release_cross_stake(OWNER.to_string(), coin(180, OSMO), vec![])
+                .unwrap()
+                .into()
         );
-
-        let query_deps = ctx.deps;
         let query_ctx = QueryCtx {
-            deps: query_deps.as_ref(),
+            deps: ctx.deps.as_ref(),
             env: mock_env(),
         };
+        let rewards = contract
+            .pending_rewards(query_ctx, OWNER.to_string(), "alice".to_string())
+            .unwrap();
+        assert_eq!(rewards.rewards, coin(90, "ujuno"));
 
-        let vals = contract.list_validators(query_ctx, None, None).unwrap();
-        assert_eq!(
-            vals.validators,
-            vec![
-                ValidatorState {
-                    validator: "alice".to_string(),
-                    state: State::Active {}
-                },
-                ValidatorState {
-                    validator: "bob".to_string(),
-                    state: State::Tombstoned {}
-                },
-            ]
-        );
+        // A successful one needs no vault tx to be committed
+        let compound_ctx = ExecCtx {
+            deps: ctx.deps.branch(),
+            env: mock_env(),
+            info: mock_info(OWNER, &[]),
+        };
+        contract
+            .compound_rewards(compound_ctx, "alice".to_string())
+            .unwrap();
+        let tx_id = contract.tx_count.load(ctx.deps.storage).unwrap();
+        let msgs = contract
+            .commit_stake(ctx.deps.branch(), tx_id, None)
+            .unwrap();
+        assert_eq!(msgs, vec![]);
+
+        let query_ctx = QueryCtx {
+            deps: ctx.deps.as_ref(),
+            env: mock_env(),
+        };
+        let stake = contract
+            .stake(query_ctx, OWNER.to_string(), "alice".to_string())
+            .unwrap();
+        assert_eq!(stake.stake.high().u128(), 380);
+        let query_ctx = QueryCtx {
+            deps: ctx.deps.as_ref(),
+            env: mock_env(),
+        };
+        let rewards = contract
+            .pending_rewards(query_ctx, OWNER.to_string(), "alice".to_string())
+            .unwrap();
+        assert_eq!(rewards.rewards, coin(0, "ujuno"));
     }
 
     #[test]
-    fn valset_update_tombstoning_and_slashing_no_stake() {
+    fn unbondings_account_for_consumer_halt() {
         let mut deps = mock_dependencies();
         let (mut ctx, contract) = do_instantiate(deps.as_mut());
 
-        // We add two new validators, and tombstone one unbonded one
-        let adds = vec![
-            AddValidator {
-                valoper: "alice".to_string(),
-                pub_key: "alice_pub_key".to_string(),
-            },
-            AddValidator {
-                valoper: "bob".to_string(),
-                pub_key: "bob_pub_key".to_string(),
-            },
-        ];
-
+        let adds = vec![AddValidator {
+            valoper: "alice".to_string(),
+            pub_key: "alice_pub_key".to_string(),
+        }];
         contract
             .valset_update(
                 ctx.deps.branch(),
@@ -2111,76 +6862,111 @@ mod tests {
                 &[],
                 &[],
                 &[],
+                &[],
             )
             .unwrap();
-        // Bob has no cross-delegations (which can be possible)
 
-        // Bob is slashed and tombstoned next
-        let update_ctx = ctx.branch();
-        let tombs = vec!["bob".to_string()];
-        let (evt, msgs) = contract
-            .valset_update(
-                update_ctx.deps,
-                update_ctx.env,
-                200,
-                2345,
-                &[],
-                &[],
-                &[],
-                &[],
-                &[],
-                &tombs,
-                &[],
+        // Stake and unstake half of it
+        let stake_ctx = ExecCtx {
+            deps: ctx.deps.branch(),
+            env: mock_env(),
+            info: mock_info("vault_addr", &[]),
+        };
+        contract
+            .receive_virtual_stake(
+                stake_ctx,
+                OWNER.to_string(),
+                coin(100, OSMO),
+                1,
+                to_json_binary(&ReceiveVirtualStake {
+                    validator: "alice".to_string(),
+                })
+                .unwrap(),
             )
             .unwrap();
+        contract.commit_stake(ctx.deps.branch(), 1, None).unwrap();
+        let unstake_ctx = ExecCtx {
+            deps: ctx.deps.branch(),
+            env: mock_env(),
+            info: mock_info(OWNER, &[]),
+        };
+        contract
+            .unstake(unstake_ctx, "alice".to_string(), coin(50, OSMO))
+            .unwrap();
+        let tx_id = contract.tx_count.load(ctx.deps.storage).unwrap();
+        contract
+            .commit_unstake(ctx.deps.branch(), mock_env(), tx_id)
+            .unwrap();
+        contract
+            .last_consumer_packet
+            .save(ctx.deps.storage, &mock_env().block.time)
+            .unwrap();
 
-        // Check the event
-        assert_eq!(evt.attributes, vec![Attribute::new("tombstoned", "bob"),]);
-
-        // Check that no slashing message is emitted
-        assert_eq!(msgs.len(), 0);
+        let release_at = mock_env().block.time.plus_seconds(100);
 
-        let query_deps = ctx.deps;
+        // Consumer is live, estimate matches the scheduled release
         let query_ctx = QueryCtx {
-            deps: query_deps.as_ref(),
+            deps: ctx.deps.as_ref(),
             env: mock_env(),
         };
-
-        let vals = contract.list_validators(query_ctx, None, None).unwrap();
+        let resp = contract
+            .unbondings(query_ctx, OWNER.to_string(), None, None)
+            .unwrap();
+        assert!(!resp.consumer_halted);
         assert_eq!(
-            vals.validators,
-            vec![
-                ValidatorState {
-                    validator: "alice".to_string(),
-                    state: State::Active {}
-                },
-                ValidatorState {
-                    validator: "bob".to_string(),
-                    state: State::Tombstoned {}
-                },
-            ]
+            resp.unbondings,
+            vec![UnbondingInfo {
+                validator: "alice".to_string(),
+                amount: Uint128::new(50),
+                release_at,
+                origin: UnbondOrigin::User,
+                estimated_release_at: release_at,
+            }]
         );
+
+        // Consumer is silent for longer than the halt threshold
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(CONSUMER_HALT_THRESHOLD + 1);
+        let query_ctx = QueryCtx {
+            deps: ctx.deps.as_ref(),
+            env,
+        };
+        let resp = contract
+            .unbondings(query_ctx, OWNER.to_string(), None, None)
+            .unwrap();
+        assert!(resp.consumer_halted);
+        // Already released, so not affected
+        assert_eq!(resp.unbondings[0].estimated_release_at, release_at);
+
+        // Halted, and with the unbond still pending
+        contract
+            .last_consumer_packet
+            .save(
+                ctx.deps.storage,
+                &mock_env()
+                    .block
+                    .time
+                    .minus_seconds(CONSUMER_HALT_THRESHOLD + 1),
+            )
+            .unwrap();
+        let query_ctx = QueryCtx {
+            deps: ctx.deps.as_ref(),
+            env: mock_env(),
+        };
+        let resp = contract
+            .unbondings(query_ctx, OWNER.to_string(), None, None)
+            .unwrap();
+        assert!(resp.consumer_halted);
+        // Released on the provider clock regardless, as the period isn't shortened in halt mode
+        assert_eq!(resp.unbondings[0].estimated_release_at, release_at);
     }
 
-    /// Tombstoning of a nonexistent validator is supported for robustness.
-    /// No slashing is performed (non-existent validator assumed to be inactive).
     #[test]
-    fn valset_update_tombstoning_nonexistent_validator() {
+    fn all_pending_unbonds_report_claimability() {
         let mut deps = mock_dependencies();
         let (mut ctx, contract) = do_instantiate(deps.as_mut());
 
-        // We add two new validators, and tombstone no-one
-        let adds = vec![
-            AddValidator {
-                valoper: "alice".to_string(),
-                pub_key: "alice_pub_key".to_string(),
-            },
-            AddValidator {
-                valoper: "bob".to_string(),
-                pub_key: "bob_pub_key".to_string(),
-            },
-        ];
-
+        let adds = vec![AddValidator::mock("alice"), AddValidator::mock("bob")];
         contract
             .valset_update(
                 ctx.deps.branch(),
@@ -2194,77 +6980,103 @@ mod tests {
                 &[],
                 &[],
                 &[],
-            )
-            .unwrap();
-
-        // Carl is tombstoned next
-        let update_ctx = ctx.branch();
-        let tombs = vec!["carl".to_string()];
-        let (evt, msgs) = contract
-            .valset_update(
-                update_ctx.deps,
-                update_ctx.env,
-                200,
-                2345,
-                &[],
-                &[],
-                &[],
-                &[],
-                &[],
-                &tombs,
                 &[],
             )
             .unwrap();
 
-        // Check the event
-        assert_eq!(evt.attributes, vec![Attribute::new("tombstoned", "carl"),]);
-
-        // Check that no slashing message is emitted
-        assert_eq!(msgs.len(), 0);
+        // Stake on both validators, and unstake from them 50 seconds apart
+        for (tx_id, validator, delay) in [(1, "alice", 0), (2, "bob", 50)] {
+            let stake_ctx = ExecCtx {
+                deps: ctx.deps.branch(),
+                env: mock_env(),
+                info: mock_info("vault_addr", &[]),
+            };
+            contract
+                .receive_virtual_stake(
+                    stake_ctx,
+                    OWNER.to_string(),
+                    coin(100, OSMO),
+                    tx_id,
+                    to_json_binary(&ReceiveVirtualStake {
+                        validator: validator.to_string(),
+                    })
+                    .unwrap(),
+                )
+                .unwrap();
+            contract
+                .commit_stake(ctx.deps.branch(), tx_id, None)
+                .unwrap();
+            let mut env = mock_env();
+            env.block.time = env.block.time.plus_seconds(delay);
+            let unstake_ctx = ExecCtx {
+                deps: ctx.deps.branch(),
+                env: env.clone(),
+                info: mock_info(OWNER, &[]),
+            };
+            contract
+                .unstake(unstake_ctx, validator.to_string(), coin(40, OSMO))
+                .unwrap();
+            let tx_id = contract.tx_count.load(ctx.deps.storage).unwrap();
+            contract
+                .commit_unstake(ctx.deps.branch(), env, tx_id)
+                .unwrap();
+        }
 
-        let query_deps = ctx.deps;
-        let query_ctx = QueryCtx {
-            deps: query_deps.as_ref(),
-            env: mock_env(),
+        // Only alice's unbond is released after the unbonding period
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(100);
+        contract
+            .last_consumer_packet
+            .save(ctx.deps.storage, &env.block.time)
+            .unwrap();
+        let query_ctx = QueryCtx {
+            deps: ctx.deps.as_ref(),
+            env: env.clone(),
         };
-
-        let vals = contract.list_validators(query_ctx, None, None).unwrap();
+        let resp = contract
+            .all_pending_unbonds(query_ctx, OWNER.to_string(), None, None)
+            .unwrap();
         assert_eq!(
-            vals.validators,
+            resp.unbonds,
             vec![
-                ValidatorState {
+                PendingUnbondInfo {
                     validator: "alice".to_string(),
-                    state: State::Active {}
+                    amount: Uint128::new(40),
+                    release_at: mock_env().block.time.plus_seconds(100),
+                    claimable: true,
                 },
-                ValidatorState {
+                PendingUnbondInfo {
                     validator: "bob".to_string(),
-                    state: State::Active {}
-                },
-                ValidatorState {
-                    validator: "carl".to_string(),
-                    state: State::Tombstoned {}
+                    amount: Uint128::new(40),
+                    release_at: mock_env().block.time.plus_seconds(150),
+                    claimable: false,
                 },
             ]
         );
+
+        // Paginated by validator
+        let query_ctx = QueryCtx {
+            deps: ctx.deps.as_ref(),
+            env,
+        };
+        let resp = contract
+            .all_pending_unbonds(
+                query_ctx,
+                OWNER.to_string(),
+                Some("alice".to_string()),
+                None,
+            )
+            .unwrap();
+        assert_eq!(resp.unbonds.len(), 1);
+        assert_eq!(resp.unbonds[0].validator, "bob");
     }
 
     #[test]
-    fn valset_update_unjailing_noop() {
+    fn consumer_halt_shortens_unbonding() {
         let mut deps = mock_dependencies();
         let (mut ctx, contract) = do_instantiate(deps.as_mut());
 
-        // We add two new validators, and jail/unjail one
-        let adds = vec![
-            AddValidator {
-                valoper: "alice".to_string(),
-                pub_key: "alice_pub_key".to_string(),
-            },
-            AddValidator {
-                valoper: "bob".to_string(),
-                pub_key: "bob_pub_key".to_string(),
-            },
-        ];
-
+        let adds = vec![AddValidator::mock("alice")];
         contract
             .valset_update(
                 ctx.deps.branch(),
@@ -2278,96 +7090,209 @@ mod tests {
                 &[],
                 &[],
                 &[],
+                &[],
             )
             .unwrap();
+        let mut config = contract.config.load(ctx.deps.storage).unwrap();
+        config.unbonding_overrides.consumer_halted = Some(40);
+        contract.config.save(ctx.deps.storage, &config).unwrap();
 
-        // Bob is jailed next
-        let update_ctx = ctx.branch();
-        let jails = vec!["bob".to_string()];
-        let (evt, msgs) = contract
-            .valset_update(
-                update_ctx.deps,
-                update_ctx.env,
-                200,
-                2345,
-                &[],
-                &[],
-                &[],
-                &jails,
-                &[],
-                &[],
-                &[],
+        // Stake and unstake half of it, with the full unbonding period
+        let stake_ctx = ExecCtx {
+            deps: ctx.deps.branch(),
+            env: mock_env(),
+            info: mock_info("vault_addr", &[]),
+        };
+        contract
+            .receive_virtual_stake(
+                stake_ctx,
+                OWNER.to_string(),
+                coin(100, OSMO),
+                1,
+                to_json_binary(&ReceiveVirtualStake {
+                    validator: "alice".to_string(),
+                })
+                .unwrap(),
+            )
+            .unwrap();
+        contract.commit_stake(ctx.deps.branch(), 1, None).unwrap();
+        let unstake_ctx = ExecCtx {
+            deps: ctx.deps.branch(),
+            env: mock_env(),
+            info: mock_info(OWNER, &[]),
+        };
+        contract
+            .unstake(unstake_ctx, "alice".to_string(), coin(50, OSMO))
+            .unwrap();
+        let tx_id = contract.tx_count.load(ctx.deps.storage).unwrap();
+        contract
+            .commit_unstake(ctx.deps.branch(), mock_env(), tx_id)
+            .unwrap();
+        let now = mock_env().block.time;
+        let stake = contract
+            .stakes
+            .stake
+            .load(ctx.deps.storage, (&Addr::unchecked(OWNER), "alice"))
+            .unwrap();
+        assert_eq!(stake.pending_unbonds[0].release_at, now.plus_seconds(100));
+
+        // The consumer halts. The unbond is released as if scheduled with the shortened period
+        contract
+            .last_consumer_packet
+            .save(
+                ctx.deps.storage,
+                &now.minus_seconds(CONSUMER_HALT_THRESHOLD + 1),
+            )
+            .unwrap();
+        let resp = contract
+            .unbondings(
+                QueryCtx {
+                    deps: ctx.deps.as_ref(),
+                    env: mock_env(),
+                },
+                OWNER.to_string(),
+                None,
+                None,
             )
             .unwrap();
+        assert!(resp.consumer_halted);
+        assert_eq!(
+            resp.unbondings[0].estimated_release_at,
+            now.plus_seconds(40)
+        );
 
-        // Check the event
-        assert_eq!(evt.attributes, vec![Attribute::new("jailed", "bob"),]);
+        let withdraw = |deps: DepsMut, seconds: u64| {
+            let mut env = mock_env();
+            env.block.time = env.block.time.plus_seconds(seconds);
+            let resp = contract
+                .withdraw_unbonded(ExecCtx {
+                    deps,
+                    env,
+                    info: mock_info(OWNER, &[]),
+                })
+                .unwrap();
+            resp.attributes
+                .into_iter()
+                .find(|attr| attr.key == "amount")
+                .unwrap()
+                .value
+        };
+        assert_eq!(withdraw(ctx.deps.branch(), 39), "0");
+        assert_eq!(withdraw(ctx.deps.branch(), 40), "50");
+    }
 
-        // Check there's no message
-        assert_eq!(msgs.len(), 0);
+    #[test]
+    fn unbond_origins_are_tracked() {
+        let mut deps = mock_dependencies();
+        let (mut ctx, contract) = do_instantiate(deps.as_mut());
 
-        // Bob is unjailed next
-        let update_ctx = ctx.branch();
-        let unjails = vec!["bob".to_string()];
-        let (evt, _msgs) = contract
+        let adds = vec![AddValidator::mock("alice")];
+        contract
             .valset_update(
-                update_ctx.deps,
-                update_ctx.env,
-                200,
-                2345,
+                ctx.deps.branch(),
+                ctx.env.clone(),
+                100,
+                1234,
+                &adds,
+                &[],
                 &[],
                 &[],
                 &[],
                 &[],
-                &unjails,
                 &[],
                 &[],
             )
             .unwrap();
 
-        // Check the event
-        assert_eq!(evt.attributes, vec![Attribute::new("unjailed", "bob"),]);
+        let stake_ctx = ExecCtx {
+            deps: ctx.deps.branch(),
+            env: mock_env(),
+            info: mock_info("vault_addr", &[]),
+        };
+        contract
+            .receive_virtual_stake(
+                stake_ctx,
+                OWNER.to_string(),
+                coin(100, OSMO),
+                1,
+                to_json_binary(&ReceiveVirtualStake {
+                    validator: "alice".to_string(),
+                })
+                .unwrap(),
+            )
+            .unwrap();
+        contract.commit_stake(ctx.deps.branch(), 1, None).unwrap();
 
-        // Check that unjail is a no-op
-        let query_deps = ctx.deps;
-        let query_ctx = QueryCtx {
-            deps: query_deps.as_ref(),
+        // User unstake
+        let unstake_ctx = ExecCtx {
+            deps: ctx.deps.branch(),
             env: mock_env(),
+            info: mock_info(OWNER, &[]),
         };
+        contract
+            .unstake(unstake_ctx, "alice".to_string(), coin(30, OSMO))
+            .unwrap();
+        let tx_id = contract.tx_count.load(ctx.deps.storage).unwrap();
+        let (_, evt) = contract
+            .commit_unstake(ctx.deps.branch(), mock_env(), tx_id)
+            .unwrap();
+        assert!(evt
+            .attributes
+            .iter()
+            .any(|attr| attr.key == "unbond_origin" && attr.value == "user"));
 
-        let vals = contract.list_validators(query_ctx, None, None).unwrap();
+        // Consumer forced unstake
+        let evt = contract
+            .internal_unstake(
+                ctx.deps.branch(),
+                mock_env(),
+                OWNER.to_string(),
+                "alice".to_string(),
+                coin(20, OSMO),
+            )
+            .unwrap();
+        assert!(evt
+            .attributes
+            .iter()
+            .any(|attr| attr.key == "unbond_origin" && attr.value == "consumer_forced"));
+
+        // Channel closed, the rest is released
+        let evt = contract
+            .handle_close_channel(ctx.deps.branch(), mock_env())
+            .unwrap();
+        assert!(evt
+            .attributes
+            .iter()
+            .any(|attr| attr.key == "amount" && attr.value == "50"));
+
+        let query_ctx = QueryCtx {
+            deps: ctx.deps.as_ref(),
+            env: mock_env(),
+        };
+        let resp = contract
+            .unbondings(query_ctx, OWNER.to_string(), None, None)
+            .unwrap();
+        let origins: Vec<_> = resp
+            .unbondings
+            .iter()
+            .map(|unbond| (unbond.amount.u128(), unbond.origin))
+            .collect();
         assert_eq!(
-            vals.validators,
+            origins,
             vec![
-                ValidatorState {
-                    validator: "alice".to_string(),
-                    state: State::Active {}
-                },
-                ValidatorState {
-                    validator: "bob".to_string(),
-                    state: State::Jailed {}
-                },
+                (30, UnbondOrigin::User),
+                (20, UnbondOrigin::ConsumerForced),
+                (50, UnbondOrigin::ChannelClosed),
             ]
         );
     }
 
     #[test]
-    fn valset_update_jailing_and_slashing() {
+    fn migration_seeds_metrics_counters() {
         let mut deps = mock_dependencies();
         let (mut ctx, contract) = do_instantiate(deps.as_mut());
 
-        // We add two new validators, and jail one
-        let adds = vec![
-            AddValidator {
-                valoper: "alice".to_string(),
-                pub_key: "alice_pub_key".to_string(),
-            },
-            AddValidator {
-                valoper: "bob".to_string(),
-                pub_key: "bob_pub_key".to_string(),
-            },
-        ];
-
+        let adds = vec![AddValidator::mock("alice")];
         contract
             .valset_update(
                 ctx.deps.branch(),
@@ -2381,130 +7306,87 @@ mod tests {
                 &[],
                 &[],
                 &[],
+                &[],
             )
             .unwrap();
 
-        // Cross stake with bob
-        let mut stake_deps = ctx.deps.branch();
-        let vault_info = mock_info("vault_addr", &[]);
         let stake_ctx = ExecCtx {
-            deps: stake_deps.branch(),
+            deps: ctx.deps.branch(),
             env: mock_env(),
-            info: vault_info,
+            info: mock_info("vault_addr", &[]),
         };
         contract
             .receive_virtual_stake(
                 stake_ctx,
                 OWNER.to_string(),
-                coin(100, "uosmo"),
+                coin(100, OSMO),
                 1,
                 to_json_binary(&ReceiveVirtualStake {
-                    validator: "bob".to_string(),
+                    validator: "alice".to_string(),
                 })
                 .unwrap(),
             )
             .unwrap();
-        // Commit stake
-        contract.commit_stake(stake_deps, 1).unwrap();
-
-        // Bob is slashed and jailed next
-        let update_ctx = ctx.branch();
-        let jails = vec!["bob".to_string()];
-        let (evt, msgs) = contract
-            .valset_update(
-                update_ctx.deps,
-                update_ctx.env,
-                200,
-                2345,
-                &[],
-                &[],
-                &[],
-                &jails,
-                &[],
-                &[],
-                &[ValidatorSlashInfo {
-                    address: "bob".to_string(),
-                    infraction_height: 200,
-                    infraction_time: 2345,
-                    power: 100,
-                    slash_amount: coin(10, "uosmo"),
-                    slash_ratio: Decimal::percent(10).to_string(),
-                }],
-            )
-            .unwrap();
-
-        // Check the event
-        assert_eq!(
-            evt.attributes,
-            vec![
-                Attribute::new("jailed", "bob"),
-                Attribute::new("slashed", "bob")
-            ]
-        );
-
-        // Check the slashing message
-        assert_eq!(msgs.len(), 1);
-        assert_eq!(
-            msgs[0],
-            WasmMsg::Execute {
-                contract_addr: "vault_addr".to_string(),
-                msg: to_json_binary(&CrossSlash {
-                    slashes: vec![SlashInfo {
-                        user: OWNER.to_string(),
-                        slash: Uint128::new(10),
-                    }],
-                    validator: "bob".to_string(),
-                })
-                .unwrap(),
-                funds: vec![],
-            }
-        );
-
-        let query_deps = ctx.deps;
-        let query_ctx = QueryCtx {
-            deps: query_deps.as_ref(),
-            env: mock_env(),
-        };
-
-        let vals = contract.list_validators(query_ctx, None, None).unwrap();
-        assert_eq!(
-            vals.validators,
-            vec![
-                ValidatorState {
-                    validator: "alice".to_string(),
-                    state: State::Active {}
-                },
-                ValidatorState {
-                    validator: "bob".to_string(),
-                    state: State::Jailed {}
-                },
-            ]
-        );
+        contract.commit_stake(ctx.deps.branch(), 1, None).unwrap();
+
+        // One committed unstake, and one in flight
+        for amount in [30, 20] {
+            let unstake_ctx = ExecCtx {
+                deps: ctx.deps.branch(),
+                env: mock_env(),
+                info: mock_info(OWNER, &[]),
+            };
+            contract
+                .unstake(unstake_ctx, "alice".to_string(), coin(amount, OSMO))
+                .unwrap();
+        }
+        let tx_id = contract.tx_count.load(ctx.deps.storage).unwrap();
+        contract
+            .commit_unstake(ctx.deps.branch(), mock_env(), tx_id - 1)
+            .unwrap();
+
+        let metrics = |deps: Deps| {
+            contract
+                .metrics(QueryCtx {
+                    deps,
+                    env: mock_env(),
+                })
+                .unwrap()
+        };
+        let resp = metrics(ctx.deps.as_ref());
+        assert_eq!(resp.pending_txs.unstake, 1);
+        assert_eq!(resp.total_pending_unbonds.u128(), 30);
+
+        // Contract deployed before the counters were tracked
+        contract.pending_txs.counters.remove(ctx.deps.storage);
+        contract.stakes.pending_total.remove(ctx.deps.storage);
+        let resp = metrics(ctx.deps.as_ref());
+        assert_eq!(resp.pending_txs.unstake, 0);
+        assert_eq!(resp.total_pending_unbonds.u128(), 0);
+
+        let migrate_ctx = MigrateCtx {
+            deps: ctx.deps.branch(),
+            env: mock_env(),
+        };
+        contract.migrate(migrate_ctx).unwrap();
+        let resp = metrics(ctx.deps.as_ref());
+        assert_eq!(resp.pending_txs.unstake, 1);
+        assert_eq!(resp.total_pending_unbonds.u128(), 30);
     }
 
     #[test]
-    fn valset_update_removal_works() {
+    fn pruning_validators() {
         let mut deps = mock_dependencies();
         let (mut ctx, contract) = do_instantiate(deps.as_mut());
 
-        // We add two new validators, and remove one
-        let adds = vec![
-            AddValidator {
-                valoper: "alice".to_string(),
-                pub_key: "alice_pub_key".to_string(),
-            },
-            AddValidator {
-                valoper: "bob".to_string(),
-                pub_key: "bob_pub_key".to_string(),
-            },
-        ];
-
+        let now = mock_env().block.time.seconds();
+        let adds = vec![AddValidator::mock("alice"), AddValidator::mock("bob")];
         contract
             .valset_update(
                 ctx.deps.branch(),
                 ctx.env.clone(),
                 100,
-                1234,
+                now,
                 &adds,
                 &[],
                 &[],
@@ -2512,20 +7394,40 @@ mod tests {
                 &[],
                 &[],
                 &[],
+                &[],
             )
             .unwrap();
 
-        // Bob is removed next
-        let update_ctx = ctx.branch();
-        let rems = vec!["bob".to_string()];
-        let (evt, msgs) = contract
+        // Bob is staked on
+        let stake_ctx = ExecCtx {
+            deps: ctx.deps.branch(),
+            env: mock_env(),
+            info: mock_info("vault_addr", &[]),
+        };
+        contract
+            .receive_virtual_stake(
+                stake_ctx,
+                OWNER.to_string(),
+                coin(100, OSMO),
+                1,
+                to_json_binary(&ReceiveVirtualStake {
+                    validator: "bob".to_string(),
+                })
+                .unwrap(),
+            )
+            .unwrap();
+        contract.commit_stake(ctx.deps.branch(), 1, None).unwrap();
+
+        // Both are removed
+        contract
             .valset_update(
-                update_ctx.deps,
-                update_ctx.env,
+                ctx.deps.branch(),
+                ctx.env.clone(),
                 200,
-                2345,
+                now,
+                &[],
+                &["alice".to_string(), "bob".to_string()],
                 &[],
-                &rems,
                 &[],
                 &[],
                 &[],
@@ -2534,51 +7436,51 @@ mod tests {
             )
             .unwrap();
 
-        // Check the event
-        assert_eq!(evt.attributes, vec![Attribute::new("removals", "bob"),]);
-
-        // Check there's no slashing message
-        assert_eq!(msgs.len(), 0);
-
-        let query_deps = ctx.deps;
-        let query_ctx = QueryCtx {
-            deps: query_deps.as_ref(),
-            env: mock_env(),
+        let prune = |deps: DepsMut, env: Env| {
+            let prune_ctx = ExecCtx {
+                deps,
+                env,
+                info: mock_info("anyone", &[]),
+            };
+            let resp = contract.prune_validators(prune_ctx, None, None).unwrap();
+            resp.attributes
+                .into_iter()
+                .find(|attr| attr.key == "pruned")
+                .unwrap()
+                .value
         };
-
-        let vals = contract.list_validators(query_ctx, None, None).unwrap();
+        let stats = |deps: Deps, env: Env| contract.crdt_stats(QueryCtx { deps, env }).unwrap();
+
+        // Not unbonded for long enough
+        assert_eq!(prune(ctx.deps.branch(), mock_env()), "");
+        assert_eq!(stats(ctx.deps.as_ref(), mock_env()).prunable, 0);
+
+        // Only alice can be pruned after the unbonding period, bob is still staked on
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(101);
+        let resp = stats(ctx.deps.as_ref(), env.clone());
+        assert_eq!(resp.stats.unbonded, 2);
+        assert_eq!(resp.prunable, 1);
+        assert_eq!(prune(ctx.deps.branch(), env.clone()), "alice");
         assert_eq!(
-            vals.validators,
-            vec![
-                ValidatorState {
-                    validator: "alice".to_string(),
-                    state: State::Active {}
-                },
-                ValidatorState {
-                    validator: "bob".to_string(),
-                    state: State::Unbonded {}
-                },
-            ]
+            contract
+                .val_set
+                .validator_state(ctx.deps.storage, "alice")
+                .unwrap(),
+            State::Unknown {}
         );
+        let resp = stats(ctx.deps.as_ref(), env);
+        assert_eq!(resp.stats.validators, 1);
+        assert_eq!(resp.prunable, 0);
     }
 
     #[test]
-    fn valset_update_updates_keep_state() {
+    fn querying_validator_at_height() {
         let mut deps = mock_dependencies();
         let (mut ctx, contract) = do_instantiate(deps.as_mut());
 
-        // We add two new validators, and remove/update one
-        let adds = vec![
-            AddValidator {
-                valoper: "alice".to_string(),
-                pub_key: "alice_pub_key".to_string(),
-            },
-            AddValidator {
-                valoper: "bob".to_string(),
-                pub_key: "bob_pub_key".to_string(),
-            },
-        ];
-
+        let adds = vec![AddValidator::mock("alice")];
+        let jails = vec!["alice".to_string()];
         contract
             .valset_update(
                 ctx.deps.branch(),
@@ -2592,46 +7494,70 @@ mod tests {
                 &[],
                 &[],
                 &[],
+                &[],
             )
             .unwrap();
-
-        // Bob is removed next
-        let update_ctx = ctx.branch();
-        let rems = vec!["bob".to_string()];
-        let (evt, _msgs) = contract
+        contract
             .valset_update(
-                update_ctx.deps,
-                update_ctx.env,
+                ctx.deps.branch(),
+                ctx.env.clone(),
                 200,
                 2345,
                 &[],
-                &rems,
                 &[],
                 &[],
+                &jails,
+                &[],
                 &[],
                 &[],
                 &[],
             )
             .unwrap();
 
-        // Check the event
-        assert_eq!(evt.attributes, vec![Attribute::new("removals", "bob"),]);
+        let at_height = |deps: Deps, height: u64| {
+            contract
+                .validator_at_height(
+                    QueryCtx {
+                        deps,
+                        env: mock_env(),
+                    },
+                    "alice".to_string(),
+                    height,
+                )
+                .unwrap()
+        };
+        let resp = at_height(ctx.deps.as_ref(), 150);
+        assert_eq!(resp.state, State::Active {});
+        assert!(resp.active);
+        assert_eq!(resp.update.unwrap().start_height, 100);
+        assert_eq!(resp.history_start, Some(100));
+
+        let resp = at_height(ctx.deps.as_ref(), 200);
+        assert_eq!(resp.state, State::Jailed {});
+        assert!(!resp.active);
+
+        // Not known before it was added
+        let resp = at_height(ctx.deps.as_ref(), 50);
+        assert_eq!(resp.state, State::Unknown {});
+        assert_eq!(resp.update, None);
+    }
 
-        // Bob is updated next
-        let update_ctx = ctx.branch();
-        let upds = vec![AddValidator {
-            valoper: "bob".to_string(),
-            pub_key: "bob_pub_key_updated".to_string(),
-        }];
-        let (evt, _msgs) = contract
+    #[test]
+    fn delegation_policy_limits_new_stakes() {
+        let mut deps = mock_dependencies();
+        let (mut ctx, contract) = do_instantiate(deps.as_mut());
+
+        let adds = vec![AddValidator::mock("alice"), AddValidator::mock("bob")];
+        contract
             .valset_update(
-                update_ctx.deps,
-                update_ctx.env,
-                300,
-                3456,
+                ctx.deps.branch(),
+                ctx.env.clone(),
+                100,
+                1234,
+                &adds,
+                &[],
                 &[],
                 &[],
-                &upds,
                 &[],
                 &[],
                 &[],
@@ -2639,30 +7565,275 @@ mod tests {
             )
             .unwrap();
 
-        // Check the event
-        assert_eq!(evt.attributes, vec![Attribute::new("updated", "bob"),]);
+        // Shares are capped to 100%
+        let err = contract
+            .set_delegation_policy(ctx.deps.branch(), None, Some(Decimal::percent(101)))
+            .unwrap_err();
+        assert_eq!(err, ContractError::InvalidValidatorShare);
 
-        let query_deps = ctx.deps;
-        let query_ctx = QueryCtx {
-            deps: query_deps.as_ref(),
-            env: mock_env(),
+        contract
+            .set_delegation_policy(ctx.deps.branch(), Some(1), Some(Decimal::percent(60)))
+            .unwrap();
+        let policy = contract
+            .delegation_policy(QueryCtx {
+                deps: ctx.deps.as_ref(),
+                env: mock_env(),
+            })
+            .unwrap();
+        assert_eq!(
+            policy,
+            DelegationPolicyResponse {
+                max_validators: Some(1),
+                max_validator_share: Some(Decimal::percent(60)),
+            }
+        );
+
+        let stake = |deps: DepsMut, owner: &str, validator: &str, amount: u128, tx_id: u64| {
+            contract.receive_virtual_stake(
+                ExecCtx {
+                    deps,
+                    env: mock_env(),
+                    info: mock_info("vault_addr", &[]),
+                },
+                owner.to_string(),
+                coin(amount, OSMO),
+                tx_id,
+                to_json_binary(&ReceiveVirtualStake {
+                    validator: validator.to_string(),
+                })
+                .unwrap(),
+            )
         };
 
-        // Check that bob is still unbonded
-        let vals = contract.list_validators(query_ctx, None, None).unwrap();
+        // The user has no cap. The first stake is the whole total, so it is not limited
+        stake(ctx.deps.branch(), OWNER, "alice", 100, 1).unwrap();
+        // But it leaves no room for more on the same validator
+        let err = stake(ctx.deps.branch(), OWNER, "alice", 10, 2).unwrap_err();
         assert_eq!(
-            vals.validators,
-            vec![
-                ValidatorState {
+            err,
+            ContractError::ValidatorShareExceeded(Decimal::percent(60))
+        );
+
+        // A second validator is over the limit
+        let err = stake(ctx.deps.branch(), OWNER, "bob", 50, 2).unwrap_err();
+        assert_eq!(err, ContractError::MaxValidatorsExceeded(1));
+
+        // Stakes by other users count towards the total, in flight or not
+        let err = stake(ctx.deps.branch(), "user2", "bob", 160, 2).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::ValidatorShareExceeded(Decimal::percent(60))
+        );
+        stake(ctx.deps.branch(), "user2", "bob", 90, 2).unwrap();
+        // 110 out of 200 on alice is within 60% now
+        stake(ctx.deps.branch(), OWNER, "alice", 10, 3).unwrap();
+
+        // Lifting the policy allows new stakes again
+        contract
+            .set_delegation_policy(ctx.deps.branch(), None, None)
+            .unwrap();
+        stake(ctx.deps.branch(), "user2", "bob", 200, 4).unwrap();
+    }
+
+    #[test]
+    fn consumer_slashing_params() {
+        let mut deps = mock_dependencies();
+        let (mut ctx, contract) = do_instantiate(deps.as_mut());
+        let configured = SlashRatio {
+            double_sign: Decimal::percent(10),
+            offline: Decimal::percent(10),
+        };
+        let source = |deps: Deps| {
+            contract
+                .slash_ratio_source(QueryCtx {
+                    deps,
+                    env: mock_env(),
+                })
+                .unwrap()
+        };
+
+        // The configured ratio is used until the consumer sends its params
+        assert_eq!(
+            source(ctx.deps.as_ref()),
+            SlashRatioSourceResponse {
+                configured: configured.clone(),
+                consumer: None,
+                effective: configured.clone(),
+            }
+        );
+
+        let err = contract
+            .set_slashing_params(
+                ctx.deps.branch(),
+                100,
+                1234,
+                Decimal::percent(101),
+                Decimal::percent(1),
+            )
+            .unwrap_err();
+        assert_eq!(err, ContractError::InvalidSlashRatio);
+
+        let consumer = SlashRatio {
+            double_sign: Decimal::percent(5),
+            offline: Decimal::percent(1),
+        };
+        contract
+            .set_slashing_params(
+                ctx.deps.branch(),
+                100,
+                1234,
+                consumer.double_sign,
+                consumer.offline,
+            )
+            .unwrap();
+        assert_eq!(
+            source(ctx.deps.as_ref()),
+            SlashRatioSourceResponse {
+                configured,
+                consumer: Some(ConsumerSlashRatio {
+                    slash_ratio: consumer.clone(),
+                    height: 100,
+                    time: 1234,
+                }),
+                effective: consumer.clone(),
+            }
+        );
+
+        // Outdated params are ignored
+        let evt = contract
+            .set_slashing_params(
+                ctx.deps.branch(),
+                99,
+                1230,
+                Decimal::percent(20),
+                Decimal::percent(2),
+            )
+            .unwrap();
+        assert!(evt
+            .attributes
+            .iter()
+            .any(|attr| attr.key == "outdated" && attr.value == "true"));
+        assert_eq!(source(ctx.deps.as_ref()).effective, consumer);
+    }
+
+    #[test]
+    fn timed_out_packets_are_retried() {
+        let mut deps = mock_dependencies();
+        let (mut ctx, contract) = do_instantiate(deps.as_mut());
+
+        let adds = vec![AddValidator {
+            valoper: "alice".to_string(),
+            pub_key: "alice_pub_key".to_string(),
+        }];
+        contract
+            .valset_update(
+                ctx.deps.branch(),
+                ctx.env.clone(),
+                100,
+                1234,
+                &adds,
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+            )
+            .unwrap();
+        let stake_ctx = ExecCtx {
+            deps: ctx.deps.branch(),
+            env: mock_env(),
+            info: mock_info("vault_addr", &[]),
+        };
+        contract
+            .receive_virtual_stake(
+                stake_ctx,
+                OWNER.to_string(),
+                coin(100, OSMO),
+                1,
+                to_json_binary(&ReceiveVirtualStake {
                     validator: "alice".to_string(),
-                    state: State::Active {}
-                },
-                ValidatorState {
-                    validator: "bob".to_string(),
-                    state: State::Unbonded {}
+                })
+                .unwrap(),
+            )
+            .unwrap();
+        let packet = ProviderPacket::Stake {
+            delegator: OWNER.to_string(),
+            validator: "alice".to_string(),
+            stake: coin(100, OSMO),
+            tx_id: 1,
+        };
+
+        let retry = |deps: DepsMut, env: Env| {
+            contract.retry_packets(
+                ExecCtx {
+                    deps,
+                    env,
+                    info: mock_info("anyone", &[]),
                 },
-            ]
-        );
+                None,
+            )
+        };
+        let queue = |deps: Deps| {
+            contract
+                .retry_queue(
+                    QueryCtx {
+                        deps,
+                        env: mock_env(),
+                    },
+                    None,
+                    None,
+                )
+                .unwrap()
+                .packets
+        };
+
+        let err = retry(ctx.deps.branch(), mock_env()).unwrap_err();
+        assert_eq!(err, ContractError::NoPacketsToRetry);
+
+        let mut env = mock_env();
+        for attempts in 0..MAX_PACKET_RETRIES {
+            let timeout =
+                cosmwasm_std::testing::mock_ibc_packet_timeout("channel-172", &packet).unwrap();
+            crate::ibc::ibc_packet_timeout(ctx.deps.branch(), env.clone(), timeout).unwrap();
+
+            // The packet is queued with a growing backoff, and the tx kept pending
+            let retry_at = env
+                .block
+                .time
+                .plus_seconds(PACKET_RETRY_BACKOFF << attempts);
+            assert_eq!(
+                queue(ctx.deps.as_ref()),
+                vec![QueuedPacketInfo {
+                    tx_id: 1,
+                    packet: packet.clone(),
+                    attempts,
+                    retry_at,
+                }]
+            );
+            assert!(contract.pending_txs.txs.has(ctx.deps.storage, 1));
+            let err = retry(ctx.deps.branch(), env.clone()).unwrap_err();
+            assert_eq!(err, ContractError::NoPacketsToRetry);
+
+            env.block.time = retry_at;
+            retry(ctx.deps.branch(), env.clone()).unwrap();
+            assert_eq!(queue(ctx.deps.as_ref()), vec![]);
+        }
+
+        // Out of retries, the tx is rolled back
+        let timeout =
+            cosmwasm_std::testing::mock_ibc_packet_timeout("channel-172", &packet).unwrap();
+        crate::ibc::ibc_packet_timeout(ctx.deps.branch(), env, timeout).unwrap();
+        assert_eq!(queue(ctx.deps.as_ref()), vec![]);
+        assert!(!contract.pending_txs.txs.has(ctx.deps.storage, 1));
+        assert!(!contract.packet_retries.has(ctx.deps.storage, 1));
+        let stake = contract
+            .stakes
+            .stake
+            .load(ctx.deps.storage, (&Addr::unchecked(OWNER), "alice"))
+            .unwrap();
+        assert_eq!(stake.stake, ValueRange::new_val(Uint128::zero()));
     }
 
     #[test]
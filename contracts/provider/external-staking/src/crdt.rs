@@ -12,7 +12,7 @@ impl ValidatorState {
     /// Add one more element to this list, maintaining the constraints
     pub fn insert_unique(&mut self, update: ValState) {
         self.0.push(update);
-        self.0.sort_by(|a, b| b.start_height.cmp(&a.start_height));
+        self.0.sort_by_key(|u| std::cmp::Reverse(u.start_height));
         self.0.dedup();
     }
 
@@ -24,6 +24,11 @@ impl ValidatorState {
         self.0.is_empty()
     }
 
+    /// Start height of the oldest update kept. Older ones are drained after the unbonding period
+    pub fn oldest_height(&self) -> Option<u64> {
+        self.0.last().map(|u| u.start_height)
+    }
+
     pub fn get_state(&self) -> State {
         if self.is_empty() {
             State::Unknown {}
@@ -40,6 +45,18 @@ impl ValidatorState {
         !self.is_empty() && self.0[0].state == State::Tombstoned {}
     }
 
+    /// Start time of the current state, if the validator is unbonded or tombstoned
+    pub fn inactive_since(&self) -> Option<u64> {
+        match self.0.first() {
+            Some(ValState {
+                state: State::Unbonded {} | State::Tombstoned {},
+                start_time,
+                ..
+            }) => Some(*start_time),
+            _ => None,
+        }
+    }
+
     fn drain_newer(&mut self, height: u64) {
         if self.0.is_empty() || self.0[0].start_height < height {
             return;
@@ -91,11 +108,30 @@ impl ValState {
     }
 }
 
+/// Number of validators in the CRDT, by current state
+#[cw_serde]
+#[derive(Default)]
+pub struct CrdtStats {
+    pub validators: u64,
+    /// Number of state entries, across all validators
+    pub entries: u64,
+    pub active: u64,
+    pub unbonded: u64,
+    pub jailed: u64,
+    pub tombstoned: u64,
+}
+
 /// This holds all CRDT related state and logic (related to validators)
 pub struct CrdtState<'a> {
     validators: Map<'a, &'a str, ValidatorState>,
 }
 
+impl Default for CrdtState<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<'a> CrdtState<'a> {
     pub const fn new() -> Self {
         CrdtState {
@@ -243,6 +279,36 @@ impl<'a> CrdtState<'a> {
         Ok(())
     }
 
+    /// Set a jailed validator back to the active set, after it was unjailed.
+    /// If the validator does not exist, or it is not jailed at that height, it does nothing.
+    /// In non-test code, this is called from `ibc_packet_receive`
+    pub fn unjail_validator(
+        &self,
+        storage: &mut dyn Storage,
+        valoper: &str,
+        height: u64,
+        time: u64,
+    ) -> Result<(), StdError> {
+        let mut validator_state = match self.validators.may_load(storage, valoper)? {
+            Some(validator_state) => validator_state,
+            None => return Ok(()),
+        };
+        // Only jailed validators can be unjailed. This also ignores tombstoned ones
+        let old = match validator_state.query_at_height(height) {
+            Some(old) if matches!(old.state, State::Jailed {}) => old.clone(),
+            _ => return Ok(()),
+        };
+        let val_state = ValState {
+            pub_key: old.pub_key,
+            start_height: height,
+            start_time: time,
+            state: State::Active {},
+        };
+        validator_state.insert_unique(val_state);
+        self.validators.save(storage, valoper, &validator_state)?;
+        Ok(())
+    }
+
     /// Tombstone a validator.
     /// In non-test code, this is called from `ibc_packet_receive`
     pub fn tombstone_validator(
@@ -316,6 +382,25 @@ impl<'a> CrdtState<'a> {
             .collect()
     }
 
+    /// This returns the valoper address of all active validators in the `(start_after, end]` range
+    pub fn list_active_validators_in_range(
+        &self,
+        storage: &dyn Storage,
+        start_after: Option<&str>,
+        end: Option<&str>,
+    ) -> StdResult<Vec<String>> {
+        let start = start_after.map(Bound::exclusive);
+        let end = end.map(Bound::inclusive);
+        self.validators
+            .range(storage, start, end, Order::Ascending)
+            .filter_map(|r| match r {
+                Ok((valoper, validator_state)) if validator_state.is_active() => Some(Ok(valoper)),
+                Ok(_) => None,
+                Err(e) => Some(Err(e)),
+            })
+            .collect()
+    }
+
     /// This returns the valoper address and latest state of all validators we are aware of
     pub fn list_validators(
         &self,
@@ -361,6 +446,9 @@ impl<'a> CrdtState<'a> {
         }
     }
 
+    /// This returns the state of a validator in effect at `height`, if any.
+    /// It is `None` if the validator wasn't known at that height, or if its history that far
+    /// back was already drained (see `history_start`)
     pub fn validator_at_height(
         &self,
         storage: &dyn Storage,
@@ -395,6 +483,78 @@ impl<'a> CrdtState<'a> {
         Ok(())
     }
 
+    /// This returns the number of validators we are aware of, and the total number of state
+    /// entries kept for them
+    pub fn size(&self, storage: &dyn Storage) -> StdResult<(u64, u64)> {
+        self.validators
+            .range(storage, None, None, Order::Ascending)
+            .try_fold((0, 0), |(validators, entries), r| {
+                let (_, validator_state) = r?;
+                Ok((validators + 1, entries + validator_state.0.len() as u64))
+            })
+    }
+
+    /// Counts the validators we are aware of by current state, along with the total number of
+    /// state entries kept for them
+    pub fn stats(&self, storage: &dyn Storage) -> StdResult<CrdtStats> {
+        self.validators
+            .range(storage, None, None, Order::Ascending)
+            .try_fold(CrdtStats::default(), |mut stats, r| {
+                let (_, validator_state) = r?;
+                stats.validators += 1;
+                stats.entries += validator_state.0.len() as u64;
+                match validator_state.get_state() {
+                    State::Active {} => stats.active += 1,
+                    State::Unbonded {} => stats.unbonded += 1,
+                    State::Jailed {} => stats.jailed += 1,
+                    State::Tombstoned {} => stats.tombstoned += 1,
+                    State::Unknown {} => {}
+                }
+                Ok(stats)
+            })
+    }
+
+    /// Scans up to `limit` validators after `start_after`, returning the ones that have been
+    /// unbonded or tombstoned since `max_time` or before, and the last validator scanned
+    pub fn inactive_validators(
+        &self,
+        storage: &dyn Storage,
+        start_after: Option<&str>,
+        limit: usize,
+        max_time: u64,
+    ) -> StdResult<(Vec<String>, Option<String>)> {
+        let start = start_after.map(Bound::exclusive);
+        let scanned = self
+            .validators
+            .range(storage, start, None, Order::Ascending)
+            .take(limit)
+            .collect::<StdResult<Vec<_>>>()?;
+        let last = scanned.last().map(|(valoper, _)| valoper.clone());
+        let inactive = scanned
+            .into_iter()
+            .filter(|(_, validator_state)| {
+                matches!(validator_state.inactive_since(), Some(since) if since <= max_time)
+            })
+            .map(|(valoper, _)| valoper)
+            .collect();
+        Ok((inactive, last))
+    }
+
+    /// Forgets about a validator, dropping its whole history.
+    /// It is `Unknown` afterwards, and can be added again
+    pub fn prune_validator(&self, storage: &mut dyn Storage, valoper: &str) {
+        self.validators.remove(storage, valoper);
+    }
+
+    /// This returns the height the kept history of a validator starts at, if we are aware of it.
+    /// Its state at lower heights is not known
+    pub fn history_start(&self, storage: &dyn Storage, valoper: &str) -> StdResult<Option<u64>> {
+        Ok(self
+            .validators
+            .may_load(storage, valoper)?
+            .and_then(|state| state.oldest_height()))
+    }
+
     pub fn validator_state(&self, storage: &dyn Storage, valoper: &str) -> StdResult<State> {
         Ok(self
             .validators
@@ -666,6 +826,60 @@ mod tests {
         );
     }
 
+    #[test]
+    fn unjail_validator_works() {
+        let mut storage = MemoryStorage::new();
+        let crdt = CrdtState::new();
+
+        crdt.add_validator(&mut storage, "alice", "alice_pubkey_1", 100, 1234)
+            .unwrap();
+        crdt.add_validator(&mut storage, "bob", "bob_pubkey_1", 100, 1234)
+            .unwrap();
+        crdt.jail_validator(&mut storage, "alice", 200, 2345)
+            .unwrap();
+
+        // Unjailing sets it back to active, keeping the pubkey
+        crdt.unjail_validator(&mut storage, "alice", 300, 3456)
+            .unwrap();
+        let alice = crdt.validator_at_height(&storage, "alice", 500).unwrap();
+        assert_eq!(
+            alice,
+            Some(ValState {
+                pub_key: "alice_pubkey_1".to_string(),
+                start_height: 300,
+                start_time: 3456,
+                state: State::Active {}
+            })
+        );
+        assert_eq!(
+            crdt.validator_state(&storage, "alice").unwrap(),
+            State::Active {}
+        );
+        // It was still jailed before
+        let alice = crdt.validator_at_height(&storage, "alice", 250).unwrap();
+        assert_eq!(alice.unwrap().state, State::Jailed {});
+
+        // Validators that are not jailed are left alone
+        crdt.unjail_validator(&mut storage, "bob", 300, 3456)
+            .unwrap();
+        let bob = crdt.validator_at_height(&storage, "bob", 500).unwrap();
+        assert_eq!(bob.unwrap().start_height, 100);
+        crdt.tombstone_validator(&mut storage, "alice", 400, 4567)
+            .unwrap();
+        crdt.unjail_validator(&mut storage, "alice", 500, 5678)
+            .unwrap();
+        assert_eq!(
+            crdt.validator_state(&storage, "alice").unwrap(),
+            State::Tombstoned {}
+        );
+        crdt.unjail_validator(&mut storage, "carl", 300, 3456)
+            .unwrap();
+        assert_eq!(
+            crdt.validator_at_height(&storage, "carl", 500).unwrap(),
+            None
+        );
+    }
+
     #[test]
     fn jail_remove_validator_works() {
         let mut storage = MemoryStorage::new();
@@ -860,4 +1074,100 @@ mod tests {
         // State didn't change
         assert!(crdt.is_active_validator(&storage, "alice").unwrap());
     }
+
+    #[test]
+    fn validator_at_height_works() {
+        let mut storage = MemoryStorage::new();
+        let crdt = CrdtState::new();
+
+        crdt.add_validator(&mut storage, "alice", "pk_a", 100, 1234)
+            .unwrap();
+        crdt.jail_validator(&mut storage, "alice", 200, 2345)
+            .unwrap();
+        crdt.add_validator(&mut storage, "alice", "pk_a", 300, 3456)
+            .unwrap();
+
+        // Not known before it was added
+        assert_eq!(
+            crdt.validator_at_height(&storage, "alice", 99).unwrap(),
+            None
+        );
+        assert_eq!(crdt.history_start(&storage, "alice").unwrap(), Some(100));
+        assert_eq!(crdt.history_start(&storage, "bob").unwrap(), None);
+
+        let state_at = |height| {
+            crdt.validator_at_height(&storage, "alice", height)
+                .unwrap()
+                .map(|val_state| val_state.state)
+        };
+        assert_eq!(state_at(100), Some(State::Active {}));
+        assert_eq!(state_at(199), Some(State::Active {}));
+        assert_eq!(state_at(200), Some(State::Jailed {}));
+        assert!(!crdt
+            .is_active_validator_at_height(&storage, "alice", 250)
+            .unwrap());
+        assert_eq!(state_at(300), Some(State::Active {}));
+        assert_eq!(state_at(1000), Some(State::Active {}));
+
+        // Drained history is not known anymore
+        crdt.drain_older(&mut storage, "alice", 3000).unwrap();
+        assert_eq!(crdt.history_start(&storage, "alice").unwrap(), Some(300));
+        assert_eq!(
+            crdt.validator_at_height(&storage, "alice", 250).unwrap(),
+            None
+        );
+        assert!(crdt
+            .is_active_validator_at_height(&storage, "alice", 300)
+            .unwrap());
+    }
+
+    #[test]
+    fn pruning_inactive_validators_works() {
+        let mut storage = MemoryStorage::new();
+        let crdt = CrdtState::new();
+
+        crdt.add_validator(&mut storage, "alice", "pk_a", 100, 1000)
+            .unwrap();
+        crdt.add_validator(&mut storage, "bob", "pk_b", 100, 1000)
+            .unwrap();
+        crdt.add_validator(&mut storage, "carl", "pk_c", 100, 1000)
+            .unwrap();
+        crdt.remove_validator(&mut storage, "alice", 200, 2000)
+            .unwrap();
+        crdt.tombstone_validator(&mut storage, "carl", 300, 3000)
+            .unwrap();
+
+        let stats = crdt.stats(&storage).unwrap();
+        assert_eq!(
+            stats,
+            CrdtStats {
+                validators: 3,
+                entries: 5,
+                active: 1,
+                unbonded: 1,
+                jailed: 0,
+                tombstoned: 1,
+            }
+        );
+
+        // Only validators inactive for long enough are returned
+        let (inactive, last) = crdt.inactive_validators(&storage, None, 10, 2500).unwrap();
+        assert_eq!(inactive, ["alice"]);
+        assert_eq!(last.as_deref(), Some("carl"));
+        let (inactive, _) = crdt.inactive_validators(&storage, None, 10, 3000).unwrap();
+        assert_eq!(inactive, ["alice", "carl"]);
+        // Scanning is paginated
+        let (inactive, last) = crdt
+            .inactive_validators(&storage, Some("alice"), 1, 3000)
+            .unwrap();
+        assert!(inactive.is_empty());
+        assert_eq!(last.as_deref(), Some("bob"));
+
+        crdt.prune_validator(&mut storage, "alice");
+        assert_eq!(
+            crdt.validator_state(&storage, "alice").unwrap(),
+            State::Unknown {}
+        );
+        assert_eq!(crdt.stats(&storage).unwrap().validators, 2);
+    }
 }
@@ -1,4 +1,4 @@
-use cosmwasm_std::{ConversionOverflowError, StdError, Uint128};
+use cosmwasm_std::{ConversionOverflowError, Decimal, StdError, Timestamp, Uint128};
 use cw_utils::PaymentError;
 use mesh_apis::ibc::VersionError;
 use mesh_sync::{RangeError, Tx};
@@ -30,6 +30,9 @@ pub enum ContractError {
     #[error("Not enough tokens staked, up to {0} can be unbond")]
     NotEnoughStake(Uint128),
 
+    #[error("Not enough tokens unbonding, up to {0} can be restaked")]
+    NotEnoughUnbonding(Uint128),
+
     #[error("Not enough tokens released, up to {0} can be claimed")]
     NotEnoughRelease(Uint128),
 
@@ -39,18 +42,51 @@ pub enum ContractError {
     #[error("Cannot stake to {0}, not listed as an active validator on consumer")]
     ValidatorNotActive(String),
 
+    #[error("Cannot stake to {0}, validator is jailed")]
+    ValidatorJailed(String),
+
+    #[error("Cannot stake to {0}, new stake to this validator is paused")]
+    ValidatorPaused(String),
+
+    #[error("Stake tx {0} is queued until its validator is active again")]
+    StakeQueued(u64),
+
+    #[error("Stake would exceed the max exposure of {0} for this user")]
+    MaxExposureExceeded(Uint128),
+
+    #[error("Stake would exceed the max of {0} validators per user")]
+    MaxValidatorsExceeded(u32),
+
+    #[error("Stake would exceed the max share {0} of the total stake on a single validator")]
+    ValidatorShareExceeded(Decimal),
+
+    #[error("Unbonding period overrides must not be over the unbonding period of {0} seconds")]
+    InvalidUnbondingOverride(u64),
+
+    #[error("Max validator share must not be over 1.0 (100%)")]
+    InvalidValidatorShare,
+
     #[error("Contract already has an open IBC channel")]
     IbcChannelAlreadyOpen,
 
     #[error("You must start the channel handshake on the other side, it doesn't support OpenInit")]
     IbcOpenInitDisallowed,
 
+    #[error("No timed out packets are due to be resent")]
+    NoPacketsToRetry,
+
+    #[error("No IBC channel is stored")]
+    NoIbcChannel,
+
     #[error("IBC channels not match")]
     IbcChannelNotMatch,
 
     #[error("You must start the channel close on this side")]
     IbcChannelCloseConfirmDisallowed,
 
+    #[error("Invalid distribution precision: {0}")]
+    InvalidDistributionPrecision(u32),
+
     #[error("Invalid authorized endpoint: {0}")]
     InvalidEndpoint(String),
 
@@ -60,12 +96,66 @@ pub enum ContractError {
     #[error("No staking rewards to be withdrawn")]
     NoRewards,
 
+    #[error("No conversion terms of stakes to {0}, rewards cannot be compounded")]
+    NoConversionTerms(String),
+
+    #[error("Commission must be lower than 1.0 (100%)")]
+    InvalidCommission,
+
+    #[error("Treasury address must not be empty")]
+    InvalidTreasury,
+
+    #[error("No protocol fee is configured")]
+    NoFeeConfig,
+
+    #[error("No protocol fees to be withdrawn")]
+    NoFees,
+
+    #[error("Claim period must be over zero")]
+    InvalidClaimPeriod,
+
+    #[error("Sweep recipient address must not be empty")]
+    InvalidSweepRecipient,
+
+    #[error("Unclaimed rewards sweeping is not enabled")]
+    NoRewardsSweep,
+
+    #[error("Rewards can still be claimed until {0} before being flagged for sweeping")]
+    ClaimPeriodActive(Timestamp),
+
+    #[error("Staker was already notified, rewards can be swept at {0}")]
+    SweepAlreadyNotified(Timestamp),
+
+    #[error("Staker was not notified of the unclaimed rewards")]
+    NoSweepNotice,
+
+    #[error("Unclaimed rewards cannot be swept before {0}")]
+    SweepNotDue(Timestamp),
+
+    #[error("Collateral summary cannot be sent again before {0}")]
+    CollateralSummaryNotDue(Timestamp),
+
     #[error("Validator '{0}' already tombstoned / not found at height {1}")]
     AlreadyTombstoned(String, u64),
 
     #[error("{0}")]
     Range(#[from] RangeError),
 
+    #[error("No migration target is set")]
+    NoMigrationTarget,
+
+    #[error("Stake on {0} has a pending tx, it cannot be migrated yet")]
+    StakeInFlight(String),
+
+    #[error("There are no stakes to migrate")]
+    NothingToMigrate,
+
     #[error("User {0} has not enough delegated funds: {1}")]
     InsufficientDelegations(String, Uint128),
+
+    #[error("Only contracts can opt into staker callbacks, {0} is not one")]
+    NotAContract(String),
+
+    #[error("Invalid reply id: {0}")]
+    InvalidReplyId(u64),
 }
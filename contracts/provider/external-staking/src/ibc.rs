@@ -5,11 +5,13 @@ use cosmwasm_std::{
     from_json, DepsMut, Env, Ibc3ChannelOpenResponse, IbcBasicResponse, IbcChannel,
     IbcChannelCloseMsg, IbcChannelConnectMsg, IbcChannelOpenMsg, IbcChannelOpenResponse,
     IbcPacketAckMsg, IbcPacketReceiveMsg, IbcPacketTimeoutMsg, IbcReceiveResponse, IbcTimeout,
+    Storage,
 };
 use cw_storage_plus::Item;
 use mesh_apis::ibc::{
-    ack_success, validate_channel_order, AckWrapper, ConsumerPacket, DistributeAck,
-    ProtocolVersion, ProviderPacket, ValsetUpdateAck,
+    ack_success, validate_channel_order, AckWrapper, AddValidator, ConsumerPacket,
+    DelegationPolicyAck, DistributeAck, ProtocolVersion, ProviderPacket, SlashingParamsAck,
+    StakeAck, ValsetUpdateAck,
 };
 
 use crate::contract::ExternalStakingContract;
@@ -29,6 +31,22 @@ pub const IBC_CHANNEL: Item<IbcChannel> = Item::new("ibc_channel");
 // This is long enough to allow some clock drift between chains
 const DEFAULT_TIMEOUT: u64 = 10 * 60;
 
+/// Fails unless the channel counterparty is the authorized endpoint. This is checked on every
+/// handshake step, as the endpoint can be replaced while no channel is open.
+fn ensure_authorized_endpoint(
+    storage: &dyn Storage,
+    channel: &IbcChannel,
+) -> Result<(), ContractError> {
+    let authorized = AUTH_ENDPOINT.load(storage)?;
+    if authorized.connection_id != channel.connection_id
+        || authorized.port_id != channel.counterparty_endpoint.port_id
+    {
+        // FIXME: do we need a better error here?
+        return Err(ContractError::Unauthorized);
+    }
+    Ok(())
+}
+
 pub fn packet_timeout(env: &Env) -> IbcTimeout {
     // No idea about their block time, but 24 hours ahead of our view of the clock
     // should be decently in the future.
@@ -36,11 +54,22 @@ pub fn packet_timeout(env: &Env) -> IbcTimeout {
     IbcTimeout::with_timestamp(timeout)
 }
 
+/// Id of the tx a packet belongs to. Burn packets are not part of a tx
+fn packet_tx_id(packet: &ProviderPacket) -> Option<u64> {
+    match packet {
+        ProviderPacket::Stake { tx_id, .. }
+        | ProviderPacket::Unstake { tx_id, .. }
+        | ProviderPacket::TransferRewards { tx_id, .. }
+        | ProviderPacket::CompoundRewards { tx_id, .. } => Some(*tx_id),
+        ProviderPacket::Burn { .. } | ProviderPacket::CollateralSummary { .. } => None,
+    }
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 /// enforces ordering and versioning constraints
 pub fn ibc_channel_open(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     msg: IbcChannelOpenMsg,
 ) -> Result<IbcChannelOpenResponse, ContractError> {
     // ensure we have no channel yet
@@ -60,18 +89,15 @@ pub fn ibc_channel_open(
     validate_channel_order(&channel.order)?;
 
     // assert expected endpoint
-    let authorized = AUTH_ENDPOINT.load(deps.storage)?;
-    if authorized.connection_id != channel.connection_id
-        || authorized.port_id != channel.counterparty_endpoint.port_id
-    {
-        // FIXME: do we need a better error here?
-        return Err(ContractError::Unauthorized);
-    }
+    ensure_authorized_endpoint(deps.storage, &channel)?;
 
     // we handshake with the counterparty version, it must not be empty
     let v: ProtocolVersion = from_json(counterparty_version.as_bytes())?;
     // if we can build a response to this, then it is compatible. And we use the highest version there
-    let version = v.build_response(SUPPORTED_IBC_PROTOCOL_VERSION, MIN_IBC_PROTOCOL_VERSION)?;
+    let version = v
+        .build_response(SUPPORTED_IBC_PROTOCOL_VERSION, MIN_IBC_PROTOCOL_VERSION)?
+        // let the consumer verify it is connected to the expected provider chain
+        .with_chain_id(&env.block.chain_id);
 
     let response = Ibc3ChannelOpenResponse {
         version: version.to_string()?,
@@ -95,6 +121,8 @@ pub fn ibc_channel_connect(
         IbcChannelConnectMsg::OpenConfirm { channel } => channel,
         IbcChannelConnectMsg::OpenAck { .. } => return Err(ContractError::IbcOpenInitDisallowed),
     };
+    // the endpoint may have been replaced since the channel open
+    ensure_authorized_endpoint(deps.storage, &channel)?;
 
     // Version negotiation over, we can only store the channel
     IBC_CHANNEL.save(deps.storage, &channel)?;
@@ -104,7 +132,7 @@ pub fn ibc_channel_connect(
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn ibc_channel_close(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     msg: IbcChannelCloseMsg,
 ) -> Result<IbcBasicResponse, ContractError> {
@@ -120,15 +148,17 @@ pub fn ibc_channel_close(
     };
 
     let contract = ExternalStakingContract::new();
-    contract.handle_close_channel(deps, env)?;
+    let evt = contract.handle_close_channel(deps.branch(), env)?;
+    // A new channel can be opened, possibly from a new authorized endpoint
+    IBC_CHANNEL.remove(deps.storage);
 
-    Ok(IbcBasicResponse::new())
+    Ok(IbcBasicResponse::new().add_event(evt))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 // this accepts validator sync packets and updates the crdt state
 pub fn ibc_packet_receive(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     msg: IbcPacketReceiveMsg,
 ) -> Result<IbcReceiveResponse, ContractError> {
@@ -137,7 +167,13 @@ pub fn ibc_packet_receive(
     // If a validator is in more than one of the events, the end result will depend on the
     // processing order below.
     let contract = ExternalStakingContract::new();
+    // Any packet is a sign of consumer liveness
+    contract
+        .last_consumer_packet
+        .save(deps.storage, &env.block.time)?;
     let packet: ConsumerPacket = from_json(msg.packet.data)?;
+    // Periodic collateral summaries are piggybacked on the consumer packets
+    let summary = contract.scheduled_collateral_summary(deps.branch(), &env)?;
     let resp = match packet {
         ConsumerPacket::ValsetUpdate {
             height,
@@ -147,12 +183,13 @@ pub fn ibc_packet_receive(
             updated,
             jailed,
             unjailed,
+            reactivated,
             tombstoned,
             slashed,
         } => {
             let (evt, msgs) = contract.valset_update(
-                deps,
-                env,
+                deps.branch(),
+                env.clone(),
                 height,
                 time,
                 &additions,
@@ -160,9 +197,44 @@ pub fn ibc_packet_receive(
                 &updated,
                 &jailed,
                 &unjailed,
+                &reactivated,
                 &tombstoned,
                 &slashed,
             )?;
+            // Stakes queued for jailed validators can be sent once they are active again
+            let mut packets = vec![];
+            for AddValidator { valoper, .. } in &additions {
+                packets.extend(contract.release_queued_stakes(deps.storage, &env, valoper)?);
+            }
+            for valoper in &reactivated {
+                packets.extend(contract.release_queued_stakes(deps.storage, &env, valoper)?);
+            }
+            for valoper in &unjailed {
+                packets.extend(contract.release_unjailed_stakes(deps.storage, &env, valoper)?);
+            }
+            let ack = ack_success(&ValsetUpdateAck {})?;
+            IbcReceiveResponse::new()
+                .set_ack(ack)
+                .add_event(evt)
+                .add_submessages(msgs)
+                .add_messages(packets)
+        }
+        ConsumerPacket::ValsetResync {
+            height,
+            time,
+            start_after,
+            end,
+            validators,
+        } => {
+            let (evt, msgs) = contract.valset_resync(
+                deps,
+                env,
+                height,
+                time,
+                start_after.as_deref(),
+                end.as_deref(),
+                &validators,
+            )?;
             let ack = ack_success(&ValsetUpdateAck {})?;
             IbcReceiveResponse::new()
                 .set_ack(ack)
@@ -190,10 +262,28 @@ pub fn ibc_packet_receive(
             let ack = ack_success(&DistributeAck {})?;
             IbcReceiveResponse::new().set_ack(ack).add_events(evts)
         }
+        ConsumerPacket::DelegationPolicy {
+            max_validators,
+            max_validator_share,
+        } => {
+            let evt = contract.set_delegation_policy(deps, max_validators, max_validator_share)?;
+            let ack = ack_success(&DelegationPolicyAck {})?;
+            IbcReceiveResponse::new().set_ack(ack).add_event(evt)
+        }
+        ConsumerPacket::SlashingParams {
+            height,
+            time,
+            double_sign,
+            offline,
+        } => {
+            let evt = contract.set_slashing_params(deps, height, time, double_sign, offline)?;
+            let ack = ack_success(&SlashingParamsAck {})?;
+            IbcReceiveResponse::new().set_ack(ack).add_event(evt)
+        }
     };
 
     // return empty success ack
-    Ok(resp)
+    Ok(resp.add_messages(summary))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -207,34 +297,80 @@ pub fn ibc_packet_ack(
     let contract = ExternalStakingContract::new();
     let ack: AckWrapper = from_json(&msg.acknowledgement.data)?;
     let mut resp = IbcBasicResponse::new();
+    // The packet made it, whatever the outcome
+    if let Some(tx_id) = packet_tx_id(&packet) {
+        contract.packet_retries.remove(deps.storage, tx_id);
+
+        // Untracked packets have no pending tx. Failures are only reported
+        if contract.take_untracked_packet(deps.storage, tx_id)? {
+            resp = resp
+                .add_attribute("tx_id", tx_id.to_string())
+                .add_attribute("packet_type", "untracked");
+            resp = match ack {
+                AckWrapper::Result(_) => resp.add_attribute("success", "true"),
+                AckWrapper::Error(e) => resp.add_attribute("error", e),
+            };
+            return Ok(resp);
+        }
+        // Aborted stakes were rolled back already. The consumer unbonds them if it bonded them
+        if contract.take_aborted_stake(deps.storage, tx_id)? {
+            resp = resp
+                .add_attribute("tx_id", tx_id.to_string())
+                .add_attribute("packet_type", "aborted_stake");
+            resp = match ack {
+                AckWrapper::Result(_) => {
+                    let undo = contract.undo_aborted_stake(deps.storage, &env, packet)?;
+                    resp.add_messages(undo).add_attribute("success", "true")
+                }
+                AckWrapper::Error(e) => resp.add_attribute("error", e),
+            };
+            return Ok(resp);
+        }
+    }
 
     match (packet, ack) {
-        (ProviderPacket::Stake { tx_id, .. }, AckWrapper::Result(_)) => {
-            let msg = contract.commit_stake(deps, tx_id)?;
+        (ProviderPacket::Stake { tx_id, .. }, AckWrapper::Result(data)) => {
+            if contract.is_cancel_unbond_tx(deps.storage, tx_id)? {
+                contract.commit_cancel_unbond(deps, tx_id)?;
+            } else {
+                // Older consumers ack with no (or empty) data. Not being able to parse the
+                // conversion terms must not fail the commit
+                let conversion = from_json::<StakeAck>(&data)
+                    .ok()
+                    .and_then(|ack| ack.conversion);
+                let msgs = contract.commit_stake(deps, tx_id, conversion)?;
+                resp = resp.add_submessages(msgs);
+            }
             resp = resp
-                .add_message(msg)
                 .add_attribute("success", "true")
                 .add_attribute("tx_id", tx_id.to_string())
                 .add_attribute("packet_type", "stake");
         }
         (ProviderPacket::Stake { tx_id, .. }, AckWrapper::Error(e)) => {
-            let msg = contract.rollback_stake(deps, tx_id)?;
+            if contract.is_cancel_unbond_tx(deps.storage, tx_id)? {
+                contract.rollback_cancel_unbond(deps, tx_id)?;
+            } else {
+                let msgs = contract.rollback_stake(deps, tx_id)?;
+                resp = resp.add_submessages(msgs);
+            }
             resp = resp
-                .add_message(msg)
                 .add_attribute("error", e)
                 .add_attribute("tx_id", tx_id.to_string())
                 .add_attribute("packet_type", "stake");
         }
         (ProviderPacket::Unstake { tx_id, .. }, AckWrapper::Result(_)) => {
-            contract.commit_unstake(deps, env, tx_id)?;
+            let (msg, evt) = contract.commit_unstake(deps, env, tx_id)?;
             resp = resp
+                .add_submessages(msg)
+                .add_event(evt)
                 .add_attribute("success", "true")
                 .add_attribute("tx_id", tx_id.to_string())
                 .add_attribute("packet_type", "unstake");
         }
         (ProviderPacket::Unstake { tx_id, .. }, AckWrapper::Error(e)) => {
-            contract.rollback_unstake(deps, tx_id)?;
+            let msg = contract.rollback_unstake(deps, tx_id)?;
             resp = resp
+                .add_submessages(msg)
                 .add_attribute("error", e)
                 .add_attribute("tx_id", tx_id.to_string())
                 .add_attribute("packet_type", "unstake");
@@ -251,6 +387,17 @@ pub fn ibc_packet_ack(
                 .add_attribute("validators", validators.join(","))
                 .add_attribute("amount", burn.amount.to_string());
         }
+        (ProviderPacket::CollateralSummary { .. }, AckWrapper::Result(_)) => {
+            resp = resp
+                .add_attribute("success", "true")
+                .add_attribute("packet_type", "collateral_summary");
+        }
+        (ProviderPacket::CollateralSummary { total_collateral }, AckWrapper::Error(e)) => {
+            resp = resp
+                .add_attribute("error", e)
+                .add_attribute("packet_type", "collateral_summary")
+                .add_attribute("amount", total_collateral.amount.to_string());
+        }
         (ProviderPacket::TransferRewards { tx_id, .. }, AckWrapper::Result(_)) => {
             contract.commit_withdraw_rewards(deps, tx_id)?;
             resp = resp
@@ -265,32 +412,82 @@ pub fn ibc_packet_ack(
                 .add_attribute("tx_id", tx_id.to_string())
                 .add_attribute("packet_type", "transfer_rewards");
         }
+        (ProviderPacket::CompoundRewards { tx_id, .. }, AckWrapper::Result(data)) => {
+            let conversion = from_json::<StakeAck>(&data)
+                .ok()
+                .and_then(|ack| ack.conversion);
+            let msgs = contract.commit_stake(deps, tx_id, conversion)?;
+            resp = resp
+                .add_submessages(msgs)
+                .add_attribute("success", "true")
+                .add_attribute("tx_id", tx_id.to_string())
+                .add_attribute("packet_type", "compound_rewards");
+        }
+        (ProviderPacket::CompoundRewards { tx_id, .. }, AckWrapper::Error(e)) => {
+            let msgs = contract.rollback_stake(deps, tx_id)?;
+            resp = resp
+                .add_submessages(msgs)
+                .add_attribute("error", e)
+                .add_attribute("tx_id", tx_id.to_string())
+                .add_attribute("packet_type", "compound_rewards");
+        }
     }
     Ok(resp)
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-/// This should trigger a rollback of staking/unstaking/burning
+/// This queues tx packets to be resent, and triggers a rollback of staking/unstaking/burning
+/// once they timed out too many times
 pub fn ibc_packet_timeout(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     msg: IbcPacketTimeoutMsg,
 ) -> Result<IbcBasicResponse, ContractError> {
     let packet: ProviderPacket = from_json(msg.packet.data)?;
     let contract = ExternalStakingContract::new();
     let mut resp = IbcBasicResponse::new().add_attribute("action", "ibc_packet_timeout");
+    if let Some(tx_id) = packet_tx_id(&packet) {
+        // Aborted stakes were rolled back already, and are not retried
+        if contract.take_aborted_stake(deps.storage, tx_id)? {
+            contract.packet_retries.remove(deps.storage, tx_id);
+            return Ok(resp
+                .add_attribute("error", "timeout")
+                .add_attribute("tx_id", tx_id.to_string())
+                .add_attribute("packet_type", "aborted_stake"));
+        }
+        if let Some(attempt) =
+            contract.queue_packet_retry(deps.storage, &env, tx_id, packet.clone())?
+        {
+            return Ok(resp
+                .add_attribute("error", "timeout")
+                .add_attribute("tx_id", tx_id.to_string())
+                .add_attribute("retry_attempt", attempt.to_string()));
+        }
+        // Untracked packets have no pending tx. Failures are only reported
+        if contract.take_untracked_packet(deps.storage, tx_id)? {
+            return Ok(resp
+                .add_attribute("error", "timeout")
+                .add_attribute("tx_id", tx_id.to_string())
+                .add_attribute("packet_type", "untracked"));
+        }
+    }
     match packet {
         ProviderPacket::Stake { tx_id, .. } => {
-            let msg = contract.rollback_stake(deps, tx_id)?;
+            if contract.is_cancel_unbond_tx(deps.storage, tx_id)? {
+                contract.rollback_cancel_unbond(deps, tx_id)?;
+            } else {
+                let msgs = contract.rollback_stake(deps, tx_id)?;
+                resp = resp.add_submessages(msgs);
+            }
             resp = resp
-                .add_message(msg)
                 .add_attribute("error", "timeout")
                 .add_attribute("tx_id", tx_id.to_string())
                 .add_attribute("packet_type", "stake");
         }
         ProviderPacket::Unstake { tx_id, .. } => {
-            contract.rollback_unstake(deps, tx_id)?;
+            let msg = contract.rollback_unstake(deps, tx_id)?;
             resp = resp
+                .add_submessages(msg)
                 .add_attribute("error", "timeout")
                 .add_attribute("tx_id", tx_id.to_string())
                 .add_attribute("packet_type", "unstake");
@@ -302,6 +499,12 @@ pub fn ibc_packet_timeout(
                 .add_attribute("validators", validators.join(","))
                 .add_attribute("amount", burn.amount.to_string());
         }
+        ProviderPacket::CollateralSummary { total_collateral } => {
+            resp = resp
+                .add_attribute("error", "timeout")
+                .add_attribute("packet_type", "collateral_summary")
+                .add_attribute("amount", total_collateral.amount.to_string());
+        }
         ProviderPacket::TransferRewards { tx_id, .. } => {
             contract.rollback_withdraw_rewards(deps, tx_id)?;
             resp = resp
@@ -309,6 +512,14 @@ pub fn ibc_packet_timeout(
                 .add_attribute("tx_id", tx_id.to_string())
                 .add_attribute("packet_type", "transfer_rewards");
         }
+        ProviderPacket::CompoundRewards { tx_id, .. } => {
+            let msgs = contract.rollback_stake(deps, tx_id)?;
+            resp = resp
+                .add_submessages(msgs)
+                .add_attribute("error", "timeout")
+                .add_attribute("tx_id", tx_id.to_string())
+                .add_attribute("packet_type", "compound_rewards");
+        }
     };
     Ok(resp)
 }
@@ -10,3 +10,4 @@ mod stakes;
 pub mod state;
 pub mod test_methods;
 pub mod test_methods_impl;
+mod txs;
@@ -1,8 +1,13 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{coin, Coin, IbcChannel};
+use cosmwasm_std::{coin, Coin, Decimal, IbcChannel, Timestamp, Uint128};
+use mesh_apis::ibc::{ConversionTerms, ProviderPacket};
+use mesh_sync::ValueRange;
 
-use crate::crdt::State;
-use crate::state::Stake;
+use crate::crdt::{CrdtStats, State, ValState};
+use crate::state::{
+    ConsumerSlashRatio, JailSlashPolicy, JailedStakePolicy, PendingUnbond, SlashRatio, Stake,
+    UnbondOrigin, UnbondingOverrides,
+};
 use crate::{error::ContractError, state::Config};
 
 #[cw_serde]
@@ -30,6 +35,99 @@ impl AuthorizedEndpoint {
 
 pub type AuthorizedEndpointResponse = AuthorizedEndpoint;
 
+/// Protocol fee taken on withdrawn rewards
+#[cw_serde]
+pub struct FeeConfig {
+    /// Share of the withdrawn rewards taken as fee
+    pub commission: Decimal,
+    /// Address on the consumer side the fees are sent to
+    pub treasury: String,
+}
+
+impl FeeConfig {
+    pub fn validate(&self) -> Result<(), ContractError> {
+        if self.commission >= Decimal::one() {
+            return Err(ContractError::InvalidCommission);
+        }
+        if self.treasury.is_empty() {
+            return Err(ContractError::InvalidTreasury);
+        }
+        Ok(())
+    }
+}
+
+#[cw_serde]
+pub struct FeeConfigResponse {
+    /// Current protocol fee, if any
+    pub fee_config: Option<FeeConfig>,
+    /// Fees collected so far and not sent to the treasury yet
+    pub accrued: Uint128,
+}
+
+/// Sweeping of rewards left unclaimed for too long
+#[cw_serde]
+pub struct RewardsSweepConfig {
+    /// Seconds since the last claim (or the first stake) after which unclaimed rewards can be
+    /// flagged for sweeping
+    pub claim_period: u64,
+    /// Seconds between the notice and the sweep, giving the staker a last chance to claim
+    pub notice_period: u64,
+    /// Address on the consumer side the swept rewards are sent to
+    pub recipient: String,
+}
+
+impl RewardsSweepConfig {
+    pub fn validate(&self) -> Result<(), ContractError> {
+        if self.claim_period == 0 {
+            return Err(ContractError::InvalidClaimPeriod);
+        }
+        if self.recipient.is_empty() {
+            return Err(ContractError::InvalidSweepRecipient);
+        }
+        Ok(())
+    }
+}
+
+#[cw_serde]
+pub struct RewardsSweepResponse {
+    /// Current sweep config, if sweeping is enabled
+    pub config: Option<RewardsSweepConfig>,
+}
+
+#[cw_serde]
+pub struct UnclaimedRewardsResponse {
+    /// Last time rewards were claimed from this stake, if ever
+    pub last_claim: Option<Timestamp>,
+    /// Time the rewards can be swept at, if the staker was notified
+    pub sweepable_at: Option<Timestamp>,
+}
+
+/// Comparison of a user's stakes here with their lien in the vault
+#[cw_serde]
+pub struct VaultVerificationResponse {
+    /// Lien of the user held by this contract in the vault
+    pub lien: ValueRange<Uint128>,
+    /// Sum of the user's stakes, pending txs included
+    pub stake: ValueRange<Uint128>,
+    /// Sum of the user's pending unbonds, still liened until withdrawn
+    pub pending_unbonds: Uint128,
+    /// Whether the highest values of the lien and of the stakes plus pending unbonds match.
+    /// Only highest values are compared, as pending unstakes lower the stake but not the lien
+    pub in_sync: bool,
+}
+
+#[cw_serde]
+pub struct RelockResponse {
+    /// Validator released collateral is re-staked to, if the user opted into relocking
+    pub validator: Option<String>,
+}
+
+#[cw_serde]
+pub struct StakerCallbacksResponse {
+    /// Whether the staker contract is called back on the outcome of its stakes and unstakes
+    pub enabled: bool,
+}
+
 #[cw_serde]
 pub struct IbcChannelResponse {
     pub channel: IbcChannel,
@@ -51,6 +149,22 @@ pub struct ValidatorState {
     pub state: State,
 }
 
+#[cw_serde]
+pub struct ValidatorAtHeightResponse {
+    pub validator: String,
+    /// Consumer height queried
+    pub height: u64,
+    /// State at `height`. `Unknown` if not known at that height
+    pub state: State,
+    /// Whether the validator was active at `height`
+    pub active: bool,
+    /// Update in effect at `height`, if any
+    pub update: Option<ValState>,
+    /// Height the kept history of the validator starts at. Its state at lower heights is not
+    /// known anymore
+    pub history_start: Option<u64>,
+}
+
 /// Config information returned with query
 #[cw_serde]
 pub struct ConfigResponse {
@@ -58,6 +172,11 @@ pub struct ConfigResponse {
     pub vault: String,
     /// In seconds
     pub unbonding_period: u64,
+    pub jailed_stake_policy: JailedStakePolicy,
+    pub jail_slash_policy: JailSlashPolicy,
+    pub unbonding_overrides: UnbondingOverrides,
+    /// Number of decimals of the reward points per stake
+    pub distribution_precision: u32,
 }
 
 impl From<Config> for ConfigResponse {
@@ -66,6 +185,10 @@ impl From<Config> for ConfigResponse {
             denom: value.denom,
             vault: value.vault.0.into(),
             unbonding_period: value.unbonding_period,
+            jailed_stake_policy: value.jailed_stake_policy,
+            jail_slash_policy: value.jail_slash_policy,
+            unbonding_overrides: value.unbonding_overrides,
+            distribution_precision: value.distribution_precision,
         }
     }
 }
@@ -94,6 +217,46 @@ pub struct StakesResponse {
     pub stakes: Vec<StakeInfo>,
 }
 
+/// Single pending unbond of a user
+#[cw_serde]
+pub struct UnbondingInfo {
+    pub validator: String,
+    pub amount: Uint128,
+    /// Time when tokens are released, as scheduled at unstake time
+    pub release_at: Timestamp,
+    /// Why the tokens are unbonding
+    pub origin: UnbondOrigin,
+    /// Time when tokens can be withdrawn, accounting for the shortened unbonding period while
+    /// the consumer chain is halted
+    pub estimated_release_at: Timestamp,
+}
+
+/// Aggregated multiple unbondings response
+#[cw_serde]
+pub struct UnbondingsResponse {
+    pub unbondings: Vec<UnbondingInfo>,
+    /// Whether the consumer chain is considered halted (channel closed, or no packets received
+    /// for longer than `CONSUMER_HALT_THRESHOLD`)
+    pub consumer_halted: bool,
+}
+
+/// Pending unbond of a user on a validator
+#[cw_serde]
+pub struct PendingUnbondInfo {
+    pub validator: String,
+    pub amount: Uint128,
+    /// Time when tokens are released, as scheduled at unstake time
+    pub release_at: Timestamp,
+    /// Whether it is released by `withdraw_unbonded` now
+    pub claimable: bool,
+}
+
+/// Pending unbonds of a user across validators
+#[cw_serde]
+pub struct AllPendingUnbondsResponse {
+    pub unbonds: Vec<PendingUnbondInfo>,
+}
+
 /// Message to be sent as `msg` field on `receive_virtual_stake`
 #[cw_serde]
 pub struct ReceiveVirtualStake {
@@ -147,3 +310,139 @@ pub type TxResponse = mesh_sync::Tx;
 pub struct AllTxsResponse {
     pub txs: Vec<TxResponse>,
 }
+
+/// Pending txs, by type
+#[cw_serde]
+#[derive(Default)]
+pub struct PendingTxCounters {
+    pub stake: u64,
+    pub unstake: u64,
+    pub cancel_unbond: u64,
+    pub transfer_rewards: u64,
+}
+
+/// Valset CRDT storage stats
+#[cw_serde]
+pub struct CrdtStatsResponse {
+    pub stats: CrdtStats,
+    /// Number of validators `prune_validators` can currently prune
+    pub prunable: u64,
+}
+
+/// Monitoring information, as a single scrapeable response
+#[cw_serde]
+pub struct MetricsResponse {
+    pub pending_txs: PendingTxCounters,
+    /// Number of validators in the valset CRDT
+    pub crdt_validators: u64,
+    /// Number of state entries in the valset CRDT, across all validators
+    pub crdt_entries: u64,
+    /// Consumer height of the last valset update
+    pub last_valset_update_height: Option<u64>,
+    /// Consumer time of the last valset update (unix seconds)
+    pub last_valset_update_time: Option<u64>,
+    /// Total committed stake, across all validators
+    pub total_stake: Uint128,
+    /// Total amount in pending unbonds, including the ones ready to be withdrawn
+    pub total_pending_unbonds: Uint128,
+    /// Number of slashing events processed
+    pub slash_incidents: u64,
+}
+
+/// Stake of a single `(owner, validator)` pair moved from a previous deployment
+#[cw_serde]
+pub struct ImportedStake {
+    pub validator: String,
+    pub stake: Uint128,
+    pub pending_unbonds: Vec<PendingUnbond>,
+}
+
+#[cw_serde]
+pub struct MigrationResponse {
+    /// Contract stakes are exported to
+    pub target: Option<String>,
+    /// Contract stakes are imported from
+    pub source: Option<String>,
+}
+
+#[cw_serde]
+pub struct MaxExposureResponse {
+    /// Max total stake of the user via this contract, if capped
+    pub cap: Option<Uint128>,
+    /// Cap set by the user, overriding the default
+    pub user_cap: Option<Uint128>,
+    /// Governance default cap
+    pub default_cap: Option<Uint128>,
+    /// Current total stake of the user, including pending stakes
+    pub exposure: Uint128,
+}
+
+#[cw_serde]
+pub struct DelegationPolicyResponse {
+    /// Max number of validators a single user can stake to, if limited
+    pub max_validators: Option<u32>,
+    /// Max share of the total stake that can be staked to a single validator, if limited
+    pub max_validator_share: Option<Decimal>,
+}
+
+#[cw_serde]
+pub struct SlashRatioSourceResponse {
+    /// Slash ratio set at instantiation
+    pub configured: SlashRatio,
+    /// Slash ratio sent by the consumer, if any
+    pub consumer: Option<ConsumerSlashRatio>,
+    /// Slash ratio in effect: the consumer one if sent, the configured one otherwise
+    pub effective: SlashRatio,
+}
+
+#[cw_serde]
+pub struct PausedValidatorsResponse {
+    pub validators: Vec<String>,
+}
+
+/// Jail slash waiting for its grace period to end
+#[cw_serde]
+pub struct PendingJailSlashInfo {
+    pub validator: String,
+    pub infraction_height: u64,
+    pub slash_ratio: Decimal,
+    pub slash_amount: Uint128,
+    /// Time (in seconds) the slash is applied at, unless the validator is active again
+    pub due: u64,
+}
+
+#[cw_serde]
+pub struct PendingJailSlashesResponse {
+    pub slashes: Vec<PendingJailSlashInfo>,
+}
+
+/// Timed out packet of a pending tx, waiting to be resent
+#[cw_serde]
+pub struct QueuedPacketInfo {
+    pub tx_id: u64,
+    pub packet: ProviderPacket,
+    /// Times the packet was already resent
+    pub attempts: u32,
+    /// Time the packet can be resent from
+    pub retry_at: Timestamp,
+}
+
+#[cw_serde]
+pub struct RetryQueueResponse {
+    pub packets: Vec<QueuedPacketInfo>,
+}
+
+/// Terms a committed stake of a user was converted with on the consumer
+#[cw_serde]
+pub struct StakeConversionInfo {
+    pub tx_id: u64,
+    pub validator: String,
+    /// Amount of provider-side tokens that were staked
+    pub amount: Uint128,
+    pub conversion: ConversionTerms,
+}
+
+#[cw_serde]
+pub struct StakeConversionsResponse {
+    pub conversions: Vec<StakeConversionInfo>,
+}
@@ -2,31 +2,38 @@ mod utils;
 
 use anyhow::Result as AnyResult;
 
-use cosmwasm_std::{coin, coins, to_json_binary, Decimal, Uint128};
-use cw_multi_test::App as MtApp;
+use cosmwasm_std::{coin, coins, to_json_binary, Addr, Decimal, Empty, Event, Uint128};
+use cw_multi_test::{App as MtApp, Executor};
 use mesh_native_staking::contract::sv::mt::CodeId as NativeStakingCodeId;
 use mesh_native_staking::contract::sv::InstantiateMsg as NativeStakingInstantiateMsg;
 use mesh_native_staking_proxy::mock::sv::mt::CodeId as NativeStakingProxyCodeId;
 use mesh_vault::mock::sv::mt::{CodeId as VaultCodeId, VaultMockProxy};
 use mesh_vault::mock::VaultMock;
-use mesh_vault::msg::{LocalStakingInfo, StakingInitInfo};
+use mesh_vault::msg::{LienResponse, LocalStakingInfo, StakingInitInfo};
 
-use mesh_sync::ValueRange;
+use mesh_sync::{Tx, ValueRange};
 
 use sylvia::multitest::{App, Proxy};
 
 use crate::contract::sv::mt::ExternalStakingContractProxy;
 use crate::test_methods::sv::mt::TestMethodsProxy;
 use mesh_apis::cross_staking_api::sv::mt::CrossStakingApiProxy;
+use mesh_apis::ibc::AddValidator;
+use mesh_apis::staker_callback_api::sv::StakerCallbackApiExecMsg;
 
 use crate::contract::sv::mt::CodeId;
 use crate::contract::ExternalStakingContract;
 use crate::error::ContractError;
-use crate::msg::{AuthorizedEndpoint, ReceiveVirtualStake, StakeInfo, ValidatorPendingRewards};
-use crate::state::{SlashRatio, Stake};
+use crate::msg::{
+    AuthorizedEndpoint, FeeConfig, PendingTxCounters, ReceiveVirtualStake, RewardsSweepConfig,
+    StakeInfo, ValidatorPendingRewards,
+};
+use crate::state::{
+    JailSlashPolicy, JailedStakePolicy, SlashRatio, Stake, UnbondingOverrides, UserStats,
+};
 use utils::{
-    assert_rewards, get_last_external_staking_pending_tx_id, AppExt as _, ContractExt as _,
-    VaultExt as _,
+    assert_rewards, get_last_external_staking_pending_tx_id, staker_contract, AppExt as _,
+    ContractExt as _, VaultExt as _,
 };
 
 const OSMO: &str = "osmo";
@@ -48,6 +55,18 @@ fn setup<'app>(
 ) -> AnyResult<(
     Proxy<'app, MtApp, VaultMock<'app>>,
     Proxy<'app, MtApp, ExternalStakingContract<'app>>,
+)> {
+    setup_with_jailed_stake_policy(app, owner, unbond_period, None)
+}
+
+fn setup_with_jailed_stake_policy<'app>(
+    app: &'app App<MtApp>,
+    owner: &'app str,
+    unbond_period: u64,
+    jailed_stake_policy: Option<JailedStakePolicy>,
+) -> AnyResult<(
+    Proxy<'app, MtApp, VaultMock<'app>>,
+    Proxy<'app, MtApp, ExternalStakingContract<'app>>,
 )> {
     let native_staking_proxy_code = NativeStakingProxyCodeId::store_code(app);
     let native_staking_code = NativeStakingCodeId::store_code(app);
@@ -59,6 +78,7 @@ fn setup<'app>(
         proxy_code_id: native_staking_proxy_code.code_id(),
         slash_ratio_dsign: Decimal::percent(LOCAL_SLASHING_PERCENTAGE_DSIGN),
         slash_ratio_offline: Decimal::percent(LOCAL_SLASHING_PERCENTAGE_OFFLINE),
+        deterministic_proxies: None,
     };
 
     let staking_init = StakingInitInfo {
@@ -66,10 +86,12 @@ fn setup<'app>(
         code_id: native_staking_code.code_id(),
         msg: to_json_binary(&native_staking_instantiate)?,
         label: Some("Native staking".to_owned()),
+        salt: None,
     };
 
     let vault = vault_code
         .instantiate(OSMO.to_owned(), Some(LocalStakingInfo::New(staking_init)))
+        .with_admin(owner)
         .call(owner)?;
 
     let remote_contact = AuthorizedEndpoint::new("connection-2", "wasm-osmo1foobarbaz");
@@ -85,7 +107,14 @@ fn setup<'app>(
                 double_sign: Decimal::percent(SLASHING_PERCENTAGE),
                 offline: Decimal::percent(SLASHING_PERCENTAGE),
             },
+            jailed_stake_policy,
+            None,
+            None,
         )
+        .with_admin(owner)
+        .call(owner)?;
+    vault
+        .add_allowed_lienholder(contract.contract_addr.to_string())
         .call(owner)?;
 
     Ok((vault, contract))
@@ -124,13 +153,13 @@ fn staking() {
 
     // Bond tokens
     vault
-        .bond()
+        .bond(None)
         .with_funds(&coins(300, OSMO))
         .call(users[0])
         .unwrap();
 
     vault
-        .bond()
+        .bond(None)
         .with_funds(&coins(300, OSMO))
         .call(users[1])
         .unwrap();
@@ -146,7 +175,7 @@ fn staking() {
     //             .map_err(|err| err.downcast().unwrap())
     // Note that the error didn't happen in vault, but in a SubMsg, so this should be some StdError not ContractError...
     let res = vault
-        .stake_remote(contract.contract_addr.to_string(), coin(100, OSMO), msg)
+        .stake_remote(contract.contract_addr.to_string(), coin(100, OSMO), msg, None)
         .call(users[0]);
     println!("GOT: {:?}", res);
     assert!(res.is_err());
@@ -215,6 +244,28 @@ fn staking() {
             StakeInfo::new(users[1], validators[1], &Stake::from_amount(200u128.into()))
         ]
     );
+
+    // And across all users
+    let stakes = contract.all_stakes(None, None).unwrap();
+    assert_eq!(
+        stakes.stakes,
+        [
+            StakeInfo::new(users[0], validators[0], &Stake::from_amount(200u128.into())),
+            StakeInfo::new(users[0], validators[1], &Stake::from_amount(100u128.into())),
+            StakeInfo::new(users[1], validators[0], &Stake::from_amount(100u128.into())),
+            StakeInfo::new(users[1], validators[1], &Stake::from_amount(200u128.into()))
+        ]
+    );
+    let stakes = contract
+        .all_stakes(Some((users[0].to_owned(), validators[1].to_owned())), None)
+        .unwrap();
+    assert_eq!(
+        stakes.stakes,
+        [
+            StakeInfo::new(users[1], validators[0], &Stake::from_amount(100u128.into())),
+            StakeInfo::new(users[1], validators[1], &Stake::from_amount(200u128.into()))
+        ]
+    );
 }
 
 #[test]
@@ -231,13 +282,13 @@ fn unstaking() {
     let validators = contract.activate_validators(["validator1", "validator2"]);
 
     vault
-        .bond()
+        .bond(None)
         .with_funds(&coins(300, OSMO))
         .call(users[0])
         .unwrap();
 
     vault
-        .bond()
+        .bond(None)
         .with_funds(&coins(300, OSMO))
         .call(users[1])
         .unwrap();
@@ -473,7 +524,7 @@ fn immediate_unstake_if_unbonded_validator() {
     let validators = contract.activate_validators(["validator1"]);
 
     vault
-        .bond()
+        .bond(None)
         .with_funds(&coins(200, OSMO))
         .call(user)
         .unwrap();
@@ -500,42 +551,1018 @@ fn immediate_unstake_if_unbonded_validator() {
 }
 
 #[test]
-fn immediate_unstake_if_tombstoned_validator() {
-    let user = "user1";
-
-    let app = App::new_with_balances(&[(user, &coins(200, OSMO))]);
-
+fn immediate_unstake_if_tombstoned_validator() {
+    let user = "user1";
+
+    let app = App::new_with_balances(&[(user, &coins(200, OSMO))]);
+
+    let owner = "owner";
+
+    let (vault, contract) = setup(&app, owner, 100).unwrap();
+
+    let validators = contract.activate_validators(["validator1"]);
+
+    vault
+        .bond(None)
+        .with_funds(&coins(200, OSMO))
+        .call(user)
+        .unwrap();
+    vault.stake(&contract, user, validators[0], coin(200, OSMO));
+
+    contract.tombstone_validator(validators[0]);
+
+    contract
+        .unstake(validators[0].to_string(), coin(200, OSMO))
+        .call(user)
+        .unwrap();
+    contract
+        .test_commit_unstake(get_last_external_staking_pending_tx_id(&contract).unwrap())
+        .call("test")
+        .unwrap();
+    contract.withdraw_unbonded().call(user).unwrap();
+
+    let err = vault
+        .claim(user.to_string(), contract.contract_addr.to_string())
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains(&mesh_vault::error::ContractError::NoClaim.to_string()));
+}
+
+#[test]
+fn staking_to_jailed_validator() {
+    let user = "user1";
+    let owner = "owner";
+
+    let stake_msg = |validator: &str| {
+        to_json_binary(&ReceiveVirtualStake {
+            validator: validator.to_string(),
+        })
+        .unwrap()
+    };
+
+    // Rejected by default
+    let app = App::new_with_balances(&[(user, &coins(200, OSMO))]);
+    let (vault, contract) = setup(&app, owner, 100).unwrap();
+    let validators = contract.activate_validators(["validator1"]);
+    contract.jail_validator(validators[0]);
+
+    let err = contract
+        .receive_virtual_stake(
+            user.to_owned(),
+            coin(100, OSMO),
+            1,
+            stake_msg(validators[0]),
+        )
+        .call(vault.contract_addr.as_str())
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::ValidatorJailed(validators[0].to_owned())
+    );
+
+    // Allowed, the stake is sent right away
+    let app = App::new_with_balances(&[(user, &coins(200, OSMO))]);
+    let (vault, contract) =
+        setup_with_jailed_stake_policy(&app, owner, 100, Some(JailedStakePolicy::Allow)).unwrap();
+    let validators = contract.activate_validators(["validator1"]);
+    contract.jail_validator(validators[0]);
+    vault
+        .bond(None)
+        .with_funds(&coins(200, OSMO))
+        .call(user)
+        .unwrap();
+
+    vault.stake(&contract, user, validators[0], coin(100, OSMO));
+    let stake = contract
+        .stake(user.to_owned(), validators[0].to_owned())
+        .unwrap();
+    assert_eq!(stake.stake, ValueRange::new_val(Uint128::new(100)));
+
+    // Queued until the validator is active again
+    let app = App::new_with_balances(&[(user, &coins(200, OSMO))]);
+    let (vault, contract) =
+        setup_with_jailed_stake_policy(&app, owner, 100, Some(JailedStakePolicy::Queue)).unwrap();
+    assert_eq!(
+        contract.config().unwrap().jailed_stake_policy,
+        JailedStakePolicy::Queue
+    );
+    let validators = contract.activate_validators(["validator1", "validator2"]);
+    contract.jail_validator(validators[0]);
+    contract.jail_validator(validators[1]);
+    vault
+        .bond(None)
+        .with_funds(&coins(200, OSMO))
+        .call(user)
+        .unwrap();
+
+    vault
+        .stake_remote(
+            contract.contract_addr.to_string(),
+            coin(100, OSMO),
+            stake_msg(validators[0]),
+            None,
+        )
+        .call(user)
+        .unwrap();
+    let queued_tx = get_last_external_staking_pending_tx_id(&contract).unwrap();
+
+    // Cannot be committed, as it was never sent
+    let err = contract
+        .test_commit_stake(queued_tx)
+        .call("test")
+        .unwrap_err();
+    assert_eq!(err, ContractError::StakeQueued(queued_tx));
+    let stake = contract
+        .stake(user.to_owned(), validators[0].to_owned())
+        .unwrap();
+    assert_eq!(
+        stake.stake,
+        ValueRange::new(Uint128::zero(), Uint128::new(100))
+    );
+
+    // Validator is back, so the stake goes through
+    contract
+        .test_set_active_validator(AddValidator::mock(validators[0]), 102, 1235)
+        .call("test")
+        .unwrap();
+    contract.test_commit_stake(queued_tx).call("test").unwrap();
+    let stake = contract
+        .stake(user.to_owned(), validators[0].to_owned())
+        .unwrap();
+    assert_eq!(stake.stake, ValueRange::new_val(Uint128::new(100)));
+
+    // Validator is tombstoned instead, so the stake is rolled back
+    vault
+        .stake_remote(
+            contract.contract_addr.to_string(),
+            coin(100, OSMO),
+            stake_msg(validators[1]),
+            None,
+        )
+        .call(user)
+        .unwrap();
+    let acc = vault.account(user.to_owned()).unwrap();
+    assert_eq!(
+        acc.free,
+        ValueRange::new(Uint128::zero(), Uint128::new(100))
+    );
+
+    contract.tombstone_validator(validators[1]);
+    let stake = contract
+        .stake(user.to_owned(), validators[1].to_owned())
+        .unwrap();
+    assert_eq!(stake.stake, ValueRange::new_val(Uint128::zero()));
+    let acc = vault.account(user.to_owned()).unwrap();
+    assert_eq!(acc.free, ValueRange::new_val(Uint128::new(100)));
+    assert_eq!(
+        contract.metrics().unwrap().pending_txs,
+        PendingTxCounters::default()
+    );
+}
+
+#[test]
+fn staking_to_unjailed_validator() {
+    let user = "user1";
+    let owner = "owner";
+
+    let app = App::new_with_balances(&[(user, &coins(200, OSMO))]);
+    let (vault, contract) =
+        setup_with_jailed_stake_policy(&app, owner, 100, Some(JailedStakePolicy::Queue)).unwrap();
+    let validators = contract.activate_validators(["validator1"]);
+    contract.jail_validator(validators[0]);
+    vault
+        .bond(None)
+        .with_funds(&coins(200, OSMO))
+        .call(user)
+        .unwrap();
+
+    vault
+        .stake_remote(
+            contract.contract_addr.to_string(),
+            coin(100, OSMO),
+            to_json_binary(&ReceiveVirtualStake {
+                validator: validators[0].to_string(),
+            })
+            .unwrap(),
+            None,
+        )
+        .call(user)
+        .unwrap();
+    let queued_tx = get_last_external_staking_pending_tx_id(&contract).unwrap();
+    let err = contract
+        .test_commit_stake(queued_tx)
+        .call("test")
+        .unwrap_err();
+    assert_eq!(err, ContractError::StakeQueued(queued_tx));
+
+    // Validator is reported as unjailed, so the queued stake is sent
+    contract.unjail_validator(validators[0]);
+    contract.test_commit_stake(queued_tx).call("test").unwrap();
+    let stake = contract
+        .stake(user.to_owned(), validators[0].to_owned())
+        .unwrap();
+    assert_eq!(stake.stake, ValueRange::new_val(Uint128::new(100)));
+    let acc = vault.account(user.to_owned()).unwrap();
+    assert_eq!(acc.free, ValueRange::new_val(Uint128::new(100)));
+    assert_eq!(
+        contract.metrics().unwrap().pending_txs,
+        PendingTxCounters::default()
+    );
+}
+
+#[test]
+fn max_exposure() {
+    let user = "user1";
+    let owner = "owner";
+
+    let app = App::new_with_balances(&[(user, &coins(300, OSMO))]);
+    let (vault, contract) = setup(&app, owner, 100).unwrap();
+    let validators = contract.activate_validators(["validator1", "validator2"]);
+    vault
+        .bond(None)
+        .with_funds(&coins(300, OSMO))
+        .call(user)
+        .unwrap();
+
+    let stake_msg = |validator: &str| {
+        to_json_binary(&ReceiveVirtualStake {
+            validator: validator.to_string(),
+        })
+        .unwrap()
+    };
+
+    // Governance default applies to everyone
+    contract
+        .set_default_max_exposure(Some(Uint128::new(150)))
+        .unwrap();
+    vault.stake(&contract, user, validators[0], coin(100, OSMO));
+    let err = contract
+        .receive_virtual_stake(
+            user.to_owned(),
+            coin(100, OSMO),
+            1,
+            stake_msg(validators[1]),
+        )
+        .call(vault.contract_addr.as_str())
+        .unwrap_err();
+    assert_eq!(err, ContractError::MaxExposureExceeded(Uint128::new(150)));
+    vault.stake(&contract, user, validators[1], coin(50, OSMO));
+
+    let resp = contract.max_exposure(user.to_owned()).unwrap();
+    assert_eq!(resp.cap, Some(Uint128::new(150)));
+    assert_eq!(resp.user_cap, None);
+    assert_eq!(resp.exposure, Uint128::new(150));
+
+    // User's own cap overrides the default
+    contract
+        .set_max_exposure(Some(Uint128::new(200)))
+        .call(user)
+        .unwrap();
+    vault.stake(&contract, user, validators[1], coin(50, OSMO));
+    let err = contract
+        .receive_virtual_stake(user.to_owned(), coin(1, OSMO), 2, stake_msg(validators[0]))
+        .call(vault.contract_addr.as_str())
+        .unwrap_err();
+    assert_eq!(err, ContractError::MaxExposureExceeded(Uint128::new(200)));
+
+    let resp = contract.max_exposure(user.to_owned()).unwrap();
+    assert_eq!(resp.cap, Some(Uint128::new(200)));
+    assert_eq!(resp.default_cap, Some(Uint128::new(150)));
+    assert_eq!(resp.exposure, Uint128::new(200));
+
+    // Lowering the cap doesn't affect existing stakes, and removing all caps lifts the limit
+    contract
+        .set_max_exposure(Some(Uint128::new(100)))
+        .call(user)
+        .unwrap();
+    let stake = contract
+        .stake(user.to_owned(), validators[1].to_owned())
+        .unwrap();
+    assert_eq!(stake.stake, ValueRange::new_val(Uint128::new(100)));
+    contract.set_max_exposure(None).call(user).unwrap();
+    contract.set_default_max_exposure(None).unwrap();
+    vault.stake(&contract, user, validators[0], coin(100, OSMO));
+
+    let resp = contract.max_exposure(user.to_owned()).unwrap();
+    assert_eq!(resp.cap, None);
+    assert_eq!(resp.exposure, Uint128::new(300));
+}
+
+#[test]
+fn jail_slash_policy() {
+    let owner = "owner";
+
+    let app = App::default();
+    let (_, contract) = setup(&app, owner, 100).unwrap();
+    assert_eq!(
+        contract.config().unwrap().jail_slash_policy,
+        JailSlashPolicy::Immediate
+    );
+
+    // Only the admin can change the policy
+    let policy = JailSlashPolicy::Delayed { grace_period: 3600 };
+    let err = contract
+        .set_jail_slash_policy(policy.clone())
+        .call("user1")
+        .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized);
+
+    contract
+        .set_jail_slash_policy(policy.clone())
+        .call(owner)
+        .unwrap();
+    assert_eq!(contract.config().unwrap().jail_slash_policy, policy);
+}
+
+#[test]
+fn unbonding_overrides() {
+    let user = "user1";
+    let owner = "owner";
+
+    let app = App::new_with_balances(&[(user, &coins(200, OSMO))]);
+    let (vault, contract) = setup(&app, owner, 100).unwrap();
+    let validators = contract.activate_validators(["validator1", "validator2"]);
+    vault
+        .bond(None)
+        .with_funds(&coins(200, OSMO))
+        .call(user)
+        .unwrap();
+    vault.stake(&contract, user, validators[0], coin(100, OSMO));
+    vault.stake(&contract, user, validators[1], coin(100, OSMO));
+
+    // Only the admin can shorten unbonding periods, and not beyond the unbonding period
+    let overrides = UnbondingOverrides {
+        jailed: Some(10),
+        consumer_halted: Some(50),
+    };
+    let err = contract
+        .set_unbonding_overrides(overrides.clone())
+        .call(user)
+        .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized);
+    let err = contract
+        .set_unbonding_overrides(UnbondingOverrides {
+            jailed: Some(101),
+            consumer_halted: None,
+        })
+        .call(owner)
+        .unwrap_err();
+    assert_eq!(err, ContractError::InvalidUnbondingOverride(100));
+    contract
+        .set_unbonding_overrides(overrides.clone())
+        .call(owner)
+        .unwrap();
+    assert_eq!(contract.config().unwrap().unbonding_overrides, overrides);
+
+    // Unstakes from jailed validators use the shortened period, others the full one
+    contract.jail_validator(validators[0]);
+    for validator in validators {
+        contract
+            .unstake(validator.to_string(), coin(100, OSMO))
+            .call(user)
+            .unwrap();
+        contract
+            .test_commit_unstake(get_last_external_staking_pending_tx_id(&contract).unwrap())
+            .call("test")
+            .unwrap();
+    }
+    let now = app.block_info().time;
+    let release_at = |validator: &str| {
+        contract
+            .stake(user.to_owned(), validator.to_owned())
+            .unwrap()
+            .pending_unbonds[0]
+            .release_at
+    };
+    assert_eq!(release_at(validators[0]), now.plus_seconds(10));
+    assert_eq!(release_at(validators[1]), now.plus_seconds(100));
+}
+
+#[test]
+fn update_authorized_endpoint() {
+    let owner = "owner";
+
+    let app = App::default();
+    let (_, contract) = setup(&app, owner, 100).unwrap();
+    let endpoint = AuthorizedEndpoint::new("connection-3", "wasm-osmo1newconverter");
+
+    // Only the admin can update the endpoint, and only while no channel is open
+    let err = contract
+        .update_authorized_endpoint(endpoint.clone())
+        .call("user1")
+        .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized);
+    let err = contract
+        .update_authorized_endpoint(endpoint.clone())
+        .call(owner)
+        .unwrap_err();
+    assert_eq!(err, ContractError::IbcChannelAlreadyOpen);
+
+    contract.reset_ibc_channel().call(owner).unwrap();
+    let resp = contract
+        .update_authorized_endpoint(endpoint.clone())
+        .call(owner)
+        .unwrap();
+    assert!(resp.has_event(
+        &Event::new("wasm-authorized_endpoint_update")
+            .add_attribute("old_connection_id", "connection-2")
+            .add_attribute("old_port_id", "wasm-osmo1foobarbaz")
+            .add_attribute("connection_id", "connection-3")
+            .add_attribute("port_id", "wasm-osmo1newconverter")
+    ));
+    assert_eq!(contract.authorized_endpoint().unwrap(), endpoint);
+}
+
+#[test]
+fn paused_validators() {
+    let user = "user1";
+    let owner = "owner";
+
+    let app = App::new_with_balances(&[(user, &coins(300, OSMO))]);
+    let (vault, contract) = setup(&app, owner, 100).unwrap();
+    let validators = contract.activate_validators(["validator1", "validator2"]);
+    vault
+        .bond(None)
+        .with_funds(&coins(300, OSMO))
+        .call(user)
+        .unwrap();
+    vault.stake(&contract, user, validators[0], coin(100, OSMO));
+
+    // Only the admin can pause validators
+    let err = contract
+        .set_validators_paused(vec![validators[0].to_owned()], true)
+        .call(user)
+        .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized);
+
+    contract
+        .set_validators_paused(vec![validators[0].to_owned()], true)
+        .call(owner)
+        .unwrap();
+    let resp = contract.paused_validators(None, None).unwrap();
+    assert_eq!(resp.validators, [validators[0]]);
+
+    // New stake to the paused validator is rejected, other validators are not affected
+    let err = contract
+        .receive_virtual_stake(
+            user.to_owned(),
+            coin(50, OSMO),
+            1,
+            to_json_binary(&ReceiveVirtualStake {
+                validator: validators[0].to_string(),
+            })
+            .unwrap(),
+        )
+        .call(vault.contract_addr.as_str())
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::ValidatorPaused(validators[0].to_owned())
+    );
+    vault.stake(&contract, user, validators[1], coin(50, OSMO));
+
+    // Unstaking from the paused validator still works
+    contract
+        .unstake(validators[0].to_string(), coin(40, OSMO))
+        .call(user)
+        .unwrap();
+    contract
+        .test_commit_unstake(get_last_external_staking_pending_tx_id(&contract).unwrap())
+        .call("test")
+        .unwrap();
+    let stake = contract
+        .stake(user.to_owned(), validators[0].to_owned())
+        .unwrap();
+    assert_eq!(stake.stake, ValueRange::new_val(Uint128::new(60)));
+
+    // Resuming allows new stake again
+    contract
+        .set_validators_paused(vec![validators[0].to_owned()], false)
+        .call(owner)
+        .unwrap();
+    let resp = contract.paused_validators(None, None).unwrap();
+    assert!(resp.validators.is_empty());
+    vault.stake(&contract, user, validators[0], coin(50, OSMO));
+}
+
+#[test]
+fn reset_ibc_channel() {
+    let user = "user1";
+    let owner = "owner";
+
+    let app = App::new_with_balances(&[(user, &coins(300, OSMO))]);
+    let (vault, contract) = setup(&app, owner, 100).unwrap();
+    let validators = contract.activate_validators(["validator1"]);
+    vault
+        .bond(None)
+        .with_funds(&coins(300, OSMO))
+        .call(user)
+        .unwrap();
+    vault.stake(&contract, user, validators[0], coin(100, OSMO));
+
+    // Only the admin can reset the channel
+    let err = contract.reset_ibc_channel().call(user).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized);
+
+    // Stakes are unbonded, as on a channel close
+    contract.reset_ibc_channel().call(owner).unwrap();
+    let stake = contract
+        .stake(user.to_owned(), validators[0].to_owned())
+        .unwrap();
+    assert_eq!(stake.stake, ValueRange::new_val(Uint128::zero()));
+    assert_eq!(stake.pending_unbonds.len(), 1);
+    assert_eq!(stake.pending_unbonds[0].amount, Uint128::new(100));
+
+    // No channel is left to reset, nor to stake through
+    let err = contract.reset_ibc_channel().call(owner).unwrap_err();
+    assert_eq!(err, ContractError::NoIbcChannel);
+    contract
+        .receive_virtual_stake(
+            user.to_owned(),
+            coin(50, OSMO),
+            1,
+            to_json_binary(&ReceiveVirtualStake {
+                validator: validators[0].to_string(),
+            })
+            .unwrap(),
+        )
+        .call(vault.contract_addr.as_str())
+        .unwrap_err();
+}
+
+#[test]
+fn cancel_unbond() {
+    let user = "user1";
+
+    let app = App::new_with_balances(&[(user, &coins(300, OSMO))]);
+
+    let owner = "owner";
+
+    let (vault, contract) = setup(&app, owner, 100).unwrap();
+
+    let validators = contract.activate_validators(["validator1"]);
+
+    vault
+        .bond(None)
+        .with_funds(&coins(300, OSMO))
+        .call(user)
+        .unwrap();
+    vault.stake(&contract, user, validators[0], coin(300, OSMO));
+
+    // Unstake twice, so there are two pending unbonds
+    for amount in [100, 50] {
+        contract
+            .unstake(validators[0].to_string(), coin(amount, OSMO))
+            .call(user)
+            .unwrap();
+        contract
+            .test_commit_unstake(get_last_external_staking_pending_tx_id(&contract).unwrap())
+            .call("test")
+            .unwrap();
+        app.update_block(|block| {
+            block.height += 1;
+            block.time = block.time.plus_seconds(10);
+        });
+    }
+
+    // Cannot cancel more than what is unbonding
+    let err = contract
+        .cancel_unbond(validators[0].to_string(), coin(151, OSMO))
+        .call(user)
+        .unwrap_err();
+    assert_eq!(err, ContractError::NotEnoughUnbonding(Uint128::new(150)));
+
+    // Cancel more than the last unbond, so it spans both entries
+    contract
+        .cancel_unbond(validators[0].to_string(), coin(70, OSMO))
+        .call(user)
+        .unwrap();
+    let tx_id = get_last_external_staking_pending_tx_id(&contract).unwrap();
+
+    let stake = contract
+        .stake(user.to_string(), validators[0].to_string())
+        .unwrap();
+    assert_eq!(
+        stake.stake,
+        ValueRange::new(Uint128::new(150), Uint128::new(220))
+    );
+    assert_eq!(stake.pending_unbonds.len(), 1);
+    assert_eq!(stake.pending_unbonds[0].amount, Uint128::new(80));
+
+    // Rollback restores the pending unbonds exactly as they were
+    contract
+        .test_rollback_cancel_unbond(tx_id)
+        .call("test")
+        .unwrap();
+    let stake = contract
+        .stake(user.to_string(), validators[0].to_string())
+        .unwrap();
+    assert_eq!(stake.stake, ValueRange::new_val(Uint128::new(150)));
+    assert_eq!(
+        stake
+            .pending_unbonds
+            .iter()
+            .map(|pending| pending.amount.u128())
+            .collect::<Vec<_>>(),
+        [100, 50]
+    );
+
+    // Cancel and commit this time
+    contract
+        .cancel_unbond(validators[0].to_string(), coin(70, OSMO))
+        .call(user)
+        .unwrap();
+    contract
+        .test_commit_cancel_unbond(get_last_external_staking_pending_tx_id(&contract).unwrap())
+        .call("test")
+        .unwrap();
+    let stake = contract
+        .stake(user.to_string(), validators[0].to_string())
+        .unwrap();
+    assert_eq!(stake.stake, ValueRange::new_val(Uint128::new(220)));
+    assert_eq!(stake.pending_unbonds.len(), 1);
+    assert_eq!(stake.pending_unbonds[0].amount, Uint128::new(80));
+
+    // Released unbonds cannot be cancelled
+    app.update_block(|block| {
+        block.height += 100;
+        block.time = block.time.plus_seconds(100);
+    });
+    let err = contract
+        .cancel_unbond(validators[0].to_string(), coin(1, OSMO))
+        .call(user)
+        .unwrap_err();
+    assert_eq!(err, ContractError::NotEnoughUnbonding(Uint128::zero()));
+}
+
+#[test]
+fn stake_migration() {
+    let user = "user1";
+    let owner = "owner";
+    let keeper = "keeper";
+
+    let app = App::new_with_balances(&[(user, &coins(300, OSMO))]);
+    let (vault, contract) = setup(&app, owner, 100).unwrap();
+    let validators = contract.activate_validators(["validator1", "validator2"]);
+
+    // New deployment for the same consumer
+    let new_contract = CodeId::store_code(&app)
+        .instantiate(
+            OSMO.to_owned(),
+            STAR.to_owned(),
+            vault.contract_addr.to_string(),
+            100,
+            AuthorizedEndpoint::new("connection-3", "wasm-osmo1foobarbaz"),
+            SlashRatio {
+                double_sign: Decimal::percent(SLASHING_PERCENTAGE),
+                offline: Decimal::percent(SLASHING_PERCENTAGE),
+            },
+            None,
+            None,
+            None,
+        )
+        .call(owner)
+        .unwrap();
+    new_contract.activate_validators(["validator1", "validator2"]);
+
+    vault
+        .bond(None)
+        .with_funds(&coins(300, OSMO))
+        .call(user)
+        .unwrap();
+    vault.stake(&contract, user, validators[0], coin(200, OSMO));
+    vault.stake(&contract, user, validators[1], coin(100, OSMO));
+    contract
+        .test_distribute_rewards(validators[0].to_owned(), coin(20, STAR))
+        .call(owner)
+        .unwrap();
+
+    // Nothing can be exported until governance sets a target
+    let err = contract
+        .export_stakes(user.to_owned())
+        .call(keeper)
+        .unwrap_err();
+    assert_eq!(err, ContractError::NoMigrationTarget);
+    contract
+        .set_migration_target(Some(new_contract.contract_addr.to_string()))
+        .unwrap();
+    vault
+        .add_allowed_lienholder(new_contract.contract_addr.to_string())
+        .call(owner)
+        .unwrap();
+
+    // The new contract only imports from its source
+    let err = contract
+        .export_stakes(user.to_owned())
+        .call(user)
+        .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    new_contract
+        .set_migration_source(Some(contract.contract_addr.to_string()))
+        .unwrap();
+    let err = new_contract
+        .import_stakes(user.to_owned(), vec![])
+        .call(keeper)
+        .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    // Only the owner or the admin can move stakes
+    let err = contract
+        .export_stakes(user.to_owned())
+        .call(keeper)
+        .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    // Stakes with pending txs cannot be moved
+    contract
+        .unstake(validators[0].to_string(), coin(50, OSMO))
+        .call(user)
+        .unwrap();
+    let err = contract
+        .export_stakes(user.to_owned())
+        .call(user)
+        .unwrap_err();
+    assert_eq!(err, ContractError::StakeInFlight(validators[0].to_owned()));
+    contract
+        .test_commit_unstake(get_last_external_staking_pending_tx_id(&contract).unwrap())
+        .call("test")
+        .unwrap();
+
+    // The admin can move them on the owner's behalf
+    contract.export_stakes(user.to_owned()).call(owner).unwrap();
+
+    // Stakes and pending unbonds are moved
+    let stake = contract
+        .stake(user.to_owned(), validators[0].to_owned())
+        .unwrap();
+    assert_eq!(stake.stake, ValueRange::new_val(Uint128::zero()));
+    assert!(stake.pending_unbonds.is_empty());
+    let stake = new_contract
+        .stake(user.to_owned(), validators[0].to_owned())
+        .unwrap();
+    assert_eq!(stake.stake, ValueRange::new_val(Uint128::new(150)));
+    assert_eq!(stake.pending_unbonds.len(), 1);
+    assert_eq!(stake.pending_unbonds[0].amount, Uint128::new(50));
+    let stake = new_contract
+        .stake(user.to_owned(), validators[1].to_owned())
+        .unwrap();
+    assert_eq!(stake.stake, ValueRange::new_val(Uint128::new(100)));
+
+    // And so is the lien
+    let claims = vault.account_claims(user.to_owned(), None, None).unwrap();
+    assert_eq!(
+        claims.claims,
+        [LienResponse {
+            lienholder: new_contract.contract_addr.to_string(),
+            denom: OSMO.to_owned(),
+            amount: ValueRange::new_val(Uint128::new(300)),
+        }]
+    );
+
+    // Rewards earned before the migration stay with the old contract
+    let rewards = contract
+        .pending_rewards(user.to_owned(), validators[0].to_owned())
+        .unwrap()
+        .rewards;
+    assert_eq!(rewards.amount.u128(), 20);
+    new_contract
+        .test_distribute_rewards(validators[0].to_owned(), coin(30, STAR))
+        .call(owner)
+        .unwrap();
+    let rewards = new_contract
+        .pending_rewards(user.to_owned(), validators[0].to_owned())
+        .unwrap()
+        .rewards;
+    assert_eq!(rewards.amount.u128(), 30);
+
+    let err = contract
+        .export_stakes(user.to_owned())
+        .call(user)
+        .unwrap_err();
+    assert_eq!(err, ContractError::NothingToMigrate);
+
+    // Migrated unbonds are released by the new contract
+    app.update_block(|block| {
+        block.height += 100;
+        block.time = block.time.plus_seconds(101);
+    });
+    new_contract.withdraw_unbonded().call(user).unwrap();
+    let claims = vault.account_claims(user.to_owned(), None, None).unwrap();
+    assert_eq!(
+        claims.claims[0].amount,
+        ValueRange::new_val(Uint128::new(250))
+    );
+}
+
+#[test]
+fn metrics() {
+    let user = "user1";
+
+    let app = App::new_with_balances(&[(user, &coins(300, OSMO))]);
+
+    let owner = "owner";
+
+    let (vault, contract) = setup(&app, owner, 100).unwrap();
+
+    let validators = contract.activate_validators(["validator1", "validator2"]);
+
+    vault
+        .bond(None)
+        .with_funds(&coins(300, OSMO))
+        .call(user)
+        .unwrap();
+    vault.stake(&contract, user, validators[0], coin(200, OSMO));
+    vault.stake(&contract, user, validators[1], coin(100, OSMO));
+
+    // One committed unstake, and one in flight
+    contract
+        .unstake(validators[0].to_string(), coin(50, OSMO))
+        .call(user)
+        .unwrap();
+    contract
+        .test_commit_unstake(get_last_external_staking_pending_tx_id(&contract).unwrap())
+        .call("test")
+        .unwrap();
+    contract
+        .unstake(validators[1].to_string(), coin(20, OSMO))
+        .call(user)
+        .unwrap();
+
+    contract.remove_validator(validators[1]);
+
+    let metrics = contract.metrics().unwrap();
+    assert_eq!(
+        metrics.pending_txs,
+        PendingTxCounters {
+            unstake: 1,
+            ..Default::default()
+        }
+    );
+    assert_eq!(metrics.crdt_validators, 2);
+    assert_eq!(metrics.crdt_entries, 3);
+    assert_eq!(metrics.total_stake.u128(), 250);
+    assert_eq!(metrics.total_pending_unbonds.u128(), 50);
+    assert_eq!(metrics.slash_incidents, 0);
+
+    contract
+        .test_handle_slashing(validators[0].to_string(), Uint128::new(15))
+        .call("test")
+        .unwrap();
+
+    let metrics = contract.metrics().unwrap();
+    assert_eq!(metrics.slash_incidents, 1);
+}
+
+#[test]
+fn user_stats() {
+    let owner = "owner";
+    let user = "user1";
+    let remote = "remote1";
+
+    let app = App::new_with_balances(&[(user, &coins(300, OSMO))]);
+    let (vault, contract) = setup(&app, owner, 100).unwrap();
+    let validators = contract.activate_validators(["validator1", "validator2"]);
+
+    assert_eq!(
+        contract.user_stats(user.to_owned()).unwrap(),
+        UserStats::default()
+    );
+
+    vault
+        .bond(None)
+        .with_funds(&coins(300, OSMO))
+        .call(user)
+        .unwrap();
+    vault.stake(&contract, user, validators[0], coin(200, OSMO));
+    vault.stake(&contract, user, validators[1], coin(100, OSMO));
+
+    contract
+        .distribute_batch(owner, STAR, &[(validators[0], 50), (validators[1], 30)])
+        .unwrap();
+    contract
+        .withdraw_all_rewards(remote.to_owned())
+        .call(user)
+        .unwrap();
+    contract
+        .test_commit_withdraw_rewards(get_last_external_staking_pending_tx_id(&contract).unwrap())
+        .call("test")
+        .unwrap();
+
+    contract
+        .test_handle_slashing(validators[0].to_string(), Uint128::new(20))
+        .call("test")
+        .unwrap();
+
+    // Only committed unstakes are counted
+    contract
+        .unstake(validators[0].to_string(), coin(50, OSMO))
+        .call(user)
+        .unwrap();
+    contract
+        .test_commit_unstake(get_last_external_staking_pending_tx_id(&contract).unwrap())
+        .call("test")
+        .unwrap();
+    contract
+        .unstake(validators[1].to_string(), coin(20, OSMO))
+        .call(user)
+        .unwrap();
+
+    assert_eq!(
+        contract.user_stats(user.to_owned()).unwrap(),
+        UserStats {
+            total_staked: Uint128::new(300),
+            total_unstaked: Uint128::new(50),
+            total_rewards_withdrawn: Uint128::new(80),
+            total_slashed: Uint128::new(20),
+            slash_count: 1,
+        }
+    );
+}
+
+#[test]
+fn pending_txs_by_user() {
     let owner = "owner";
+    let users = ["user1", "user2"];
 
+    let app =
+        App::new_with_balances(&[(users[0], &coins(300, OSMO)), (users[1], &coins(300, OSMO))]);
     let (vault, contract) = setup(&app, owner, 100).unwrap();
+    let validators = contract.activate_validators(["validator1", "validator2"]);
 
-    let validators = contract.activate_validators(["validator1"]);
+    for user in users {
+        vault
+            .bond(None)
+            .with_funds(&coins(300, OSMO))
+            .call(user)
+            .unwrap();
+        vault.stake(&contract, user, validators[0], coin(200, OSMO));
+        vault.stake(&contract, user, validators[1], coin(100, OSMO));
+    }
+    assert_eq!(
+        contract
+            .pending_txs_by_user(users[0].to_owned(), None, None)
+            .unwrap()
+            .txs,
+        vec![]
+    );
 
-    vault
-        .bond()
-        .with_funds(&coins(200, OSMO))
-        .call(user)
+    // Pending unstakes and reward withdrawals are listed for their user only
+    contract
+        .unstake(validators[0].to_string(), coin(50, OSMO))
+        .call(users[0])
         .unwrap();
-    vault.stake(&contract, user, validators[0], coin(200, OSMO));
-
-    contract.tombstone_validator(validators[0]);
-
     contract
-        .unstake(validators[0].to_string(), coin(200, OSMO))
-        .call(user)
+        .unstake(validators[1].to_string(), coin(50, OSMO))
+        .call(users[1])
         .unwrap();
     contract
-        .test_commit_unstake(get_last_external_staking_pending_tx_id(&contract).unwrap())
-        .call("test")
+        .test_distribute_rewards(validators[0].to_owned(), coin(100, STAR))
+        .call(owner)
+        .unwrap();
+    contract
+        .withdraw_rewards(validators[0].to_owned(), "remote1".to_owned())
+        .call(users[0])
         .unwrap();
-    contract.withdraw_unbonded().call(user).unwrap();
 
-    let err = vault
-        .claim(user.to_string(), contract.contract_addr.to_string())
-        .unwrap_err();
-    assert!(err
-        .to_string()
-        .contains(&mesh_vault::error::ContractError::NoClaim.to_string()));
+    let txs = contract
+        .pending_txs_by_user(users[0].to_owned(), None, None)
+        .unwrap()
+        .txs;
+    assert_eq!(txs.len(), 2);
+    assert!(matches!(txs[0], Tx::InFlightTransferFunds { .. }));
+    assert!(matches!(txs[1], Tx::InFlightRemoteUnstaking { .. }));
+    assert!(txs.iter().all(|tx| tx.user() == &Addr::unchecked(users[0])));
+
+    // Paginated by tx id, newest first
+    let next = contract
+        .pending_txs_by_user(users[0].to_owned(), Some(txs[0].id()), None)
+        .unwrap()
+        .txs;
+    assert_eq!(next, txs[1..]);
+
+    let txs = contract
+        .pending_txs_by_user(users[1].to_owned(), None, None)
+        .unwrap()
+        .txs;
+    assert_eq!(txs.len(), 1);
+    assert!(matches!(
+        &txs[0],
+        Tx::InFlightRemoteUnstaking { user, .. } if user == users[1]
+    ));
+
+    // Committed txs are no longer listed
+    contract
+        .test_commit_unstake(txs[0].id())
+        .call("test")
+        .unwrap();
+    assert_eq!(
+        contract
+            .pending_txs_by_user(users[1].to_owned(), None, None)
+            .unwrap()
+            .txs,
+        vec![]
+    );
 }
 
 #[test]
@@ -559,13 +1586,13 @@ fn distribution() {
     // 3/5 of validators[0] to users[1]
     // all of validators[1] to users[1]
     vault
-        .bond()
+        .bond(None)
         .with_funds(&coins(600, OSMO))
         .call(users[0])
         .unwrap();
 
     vault
-        .bond()
+        .bond(None)
         .with_funds(&coins(600, OSMO))
         .call(users[1])
         .unwrap();
@@ -826,6 +1853,7 @@ fn distribution() {
                 validator: validators[1].to_string(),
             })
             .unwrap(),
+            None,
         )
         .call(users[1])
         .unwrap();
@@ -1110,90 +2138,418 @@ fn distribution() {
 
     // And try to withdraw all, previous balances:
     contract
-        .withdraw_rewards(validators[0].to_string(), remote[0].to_owned())
-        .call(users[0])
+        .withdraw_rewards(validators[0].to_string(), remote[0].to_owned())
+        .call(users[0])
+        .unwrap();
+    let tx_id = get_last_external_staking_pending_tx_id(&contract).unwrap();
+    contract
+        .test_commit_withdraw_rewards(tx_id)
+        .call(users[0])
+        .unwrap();
+
+    contract
+        .withdraw_rewards(validators[1].to_string(), remote[0].to_owned())
+        .call(users[0])
+        .unwrap();
+    let tx_id = get_last_external_staking_pending_tx_id(&contract).unwrap();
+    contract
+        .test_commit_withdraw_rewards(tx_id)
+        .call(users[0])
+        .unwrap();
+
+    contract
+        .withdraw_rewards(validators[0].to_string(), remote[1].to_owned())
+        .call(users[1])
+        .unwrap();
+    let tx_id = get_last_external_staking_pending_tx_id(&contract).unwrap();
+    contract
+        .test_commit_withdraw_rewards(tx_id)
+        .call(users[0])
+        .unwrap();
+
+    contract
+        .withdraw_rewards(validators[1].to_string(), remote[1].to_owned())
+        .call(users[1])
+        .unwrap();
+    let tx_id = get_last_external_staking_pending_tx_id(&contract).unwrap();
+    contract
+        .test_commit_withdraw_rewards(tx_id)
+        .call(users[0])
+        .unwrap();
+}
+
+#[test]
+fn batch_distribution() {
+    let owner = "owner";
+    let users = ["user1", "user2"];
+
+    let app =
+        App::new_with_balances(&[(users[0], &coins(600, OSMO)), (users[1], &coins(600, OSMO))]);
+
+    let (vault, contract) = setup(&app, owner, 100).unwrap();
+
+    let validators = contract.activate_validators(["validator1", "validator2"]);
+
+    vault
+        .bond(None)
+        .with_funds(&coins(600, OSMO))
+        .call(users[0])
+        .unwrap();
+    vault
+        .bond(None)
+        .with_funds(&coins(600, OSMO))
+        .call(users[1])
+        .unwrap();
+
+    vault.stake(&contract, users[0], validators[0], coin(200, OSMO));
+    vault.stake(&contract, users[0], validators[1], coin(100, OSMO));
+    vault.stake(&contract, users[1], validators[0], coin(300, OSMO));
+
+    contract
+        .distribute_batch(owner, STAR, &[(validators[0], 50), (validators[1], 30)])
+        .unwrap();
+
+    assert_rewards!(contract, users[0], validators[0], 20);
+    assert_rewards!(contract, users[1], validators[0], 30);
+    assert_rewards!(contract, users[0], validators[1], 30);
+    assert_rewards!(contract, users[1], validators[1], 0);
+
+    contract
+        .distribute_batch(owner, STAR, &[(validators[0], 100), (validators[1], 30)])
+        .unwrap();
+
+    assert_rewards!(contract, users[0], validators[0], 60);
+    assert_rewards!(contract, users[1], validators[0], 90);
+    assert_rewards!(contract, users[0], validators[1], 60);
+    assert_rewards!(contract, users[1], validators[1], 0);
+}
+
+#[test]
+fn withdraw_all_rewards() {
+    let owner = "owner";
+    let user = "user1";
+    let remote = "remote1";
+
+    let app = App::new_with_balances(&[(user, &coins(600, OSMO))]);
+
+    let (vault, contract) = setup(&app, owner, 100).unwrap();
+
+    let validators = contract.activate_validators(["validator1", "validator2", "validator3"]);
+
+    vault
+        .bond(None)
+        .with_funds(&coins(600, OSMO))
+        .call(user)
+        .unwrap();
+
+    vault.stake(&contract, user, validators[0], coin(200, OSMO));
+    vault.stake(&contract, user, validators[1], coin(100, OSMO));
+    vault.stake(&contract, user, validators[2], coin(100, OSMO));
+
+    // Nothing to withdraw yet
+    let err = contract
+        .withdraw_all_rewards(remote.to_owned())
+        .call(user)
+        .unwrap_err();
+    assert_eq!(err, ContractError::NoRewards);
+
+    // No rewards for the third validator
+    contract
+        .distribute_batch(owner, STAR, &[(validators[0], 50), (validators[1], 30)])
+        .unwrap();
+
+    contract
+        .withdraw_all_rewards(remote.to_owned())
+        .call(user)
+        .unwrap();
+    let tx_id = get_last_external_staking_pending_tx_id(&contract).unwrap();
+    let tx = contract.pending_tx(tx_id).unwrap();
+    assert_eq!(
+        tx,
+        mesh_sync::Tx::InFlightTransferAllFunds {
+            id: tx_id,
+            amounts: vec![
+                (validators[0].to_owned(), Uint128::new(50)),
+                (validators[1].to_owned(), Uint128::new(30)),
+            ],
+            staker: cosmwasm_std::Addr::unchecked(user),
+        }
+    );
+
+    // Rollback keeps the rewards
+    contract
+        .test_rollback_withdraw_rewards(tx_id)
+        .call("test")
+        .unwrap();
+    assert_rewards!(contract, user, validators[0], 50);
+    assert_rewards!(contract, user, validators[1], 30);
+
+    // Commit withdraws from all of them
+    contract
+        .withdraw_all_rewards(remote.to_owned())
+        .call(user)
+        .unwrap();
+    let tx_id = get_last_external_staking_pending_tx_id(&contract).unwrap();
+    contract
+        .test_commit_withdraw_rewards(tx_id)
+        .call("test")
+        .unwrap();
+    assert_rewards!(contract, user, validators[0], 0);
+    assert_rewards!(contract, user, validators[1], 0);
+    assert_rewards!(contract, user, validators[2], 0);
+}
+
+#[test]
+fn protocol_fee() {
+    let owner = "owner";
+    let user = "user1";
+    let remote = "remote1";
+    let treasury = "treasury1";
+
+    let app = App::new_with_balances(&[(user, &coins(600, OSMO))]);
+
+    let (vault, contract) = setup(&app, owner, 100).unwrap();
+    let validator = contract.activate_validators(["validator1"])[0];
+
+    vault
+        .bond(None)
+        .with_funds(&coins(600, OSMO))
+        .call(user)
+        .unwrap();
+    vault.stake(&contract, user, validator, coin(200, OSMO));
+
+    // Only the admin can set the fee
+    let fee_config = FeeConfig {
+        commission: Decimal::percent(10),
+        treasury: treasury.to_owned(),
+    };
+    let err = contract
+        .set_fee_config(Some(fee_config.clone()))
+        .call(user)
+        .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized);
+    let err = contract
+        .set_fee_config(Some(FeeConfig {
+            commission: Decimal::one(),
+            treasury: treasury.to_owned(),
+        }))
+        .call(owner)
+        .unwrap_err();
+    assert_eq!(err, ContractError::InvalidCommission);
+    contract
+        .set_fee_config(Some(fee_config.clone()))
+        .call(owner)
+        .unwrap();
+
+    // The fee is only accrued once the withdrawal is committed
+    contract
+        .distribute_batch(owner, STAR, &[(validator, 50)])
+        .unwrap();
+    let resp = contract
+        .withdraw_rewards(validator.to_owned(), remote.to_owned())
+        .call(user)
+        .unwrap();
+    assert!(resp.has_event(
+        &Event::new("wasm")
+            .add_attribute("amount", "50")
+            .add_attribute("fee", "5")
+    ));
+    let tx_id = get_last_external_staking_pending_tx_id(&contract).unwrap();
+    contract
+        .test_rollback_withdraw_rewards(tx_id)
+        .call("test")
+        .unwrap();
+    assert_eq!(contract.fee_config().unwrap().accrued, Uint128::zero());
+
+    contract
+        .withdraw_rewards(validator.to_owned(), remote.to_owned())
+        .call(user)
+        .unwrap();
+    let tx_id = get_last_external_staking_pending_tx_id(&contract).unwrap();
+    contract
+        .test_commit_withdraw_rewards(tx_id)
+        .call("test")
+        .unwrap();
+    assert_rewards!(contract, user, validator, 0);
+    let resp = contract.fee_config().unwrap();
+    assert_eq!(resp.fee_config, Some(fee_config));
+    assert_eq!(resp.accrued, Uint128::new(5));
+
+    // Failed transfers to the treasury are accrued again
+    let resp = contract.withdraw_fees().call(owner).unwrap();
+    let fee_tx_id: u64 = resp
+        .events
+        .iter()
+        .flat_map(|ev| &ev.attributes)
+        .find(|attr| attr.key == "tx_id")
+        .unwrap()
+        .value
+        .parse()
+        .unwrap();
+    assert_eq!(contract.fee_config().unwrap().accrued, Uint128::zero());
+    contract
+        .test_rollback_withdraw_rewards(fee_tx_id)
+        .call("test")
+        .unwrap();
+    assert_eq!(contract.fee_config().unwrap().accrued, Uint128::new(5));
+
+    let resp = contract.withdraw_fees().call(user).unwrap();
+    assert!(resp.has_event(
+        &Event::new("wasm")
+            .add_attribute("treasury", treasury)
+            .add_attribute("amount", "5")
+    ));
+    let err = contract.withdraw_fees().call(user).unwrap_err();
+    assert_eq!(err, ContractError::NoFees);
+}
+
+#[test]
+fn rewards_sweep() {
+    let owner = "owner";
+    let user = "user1";
+    let remote = "remote1";
+    let treasury = "treasury1";
+
+    let app = App::new_with_balances(&[(user, &coins(600, OSMO))]);
+
+    let (vault, contract) = setup(&app, owner, 100).unwrap();
+    let validator = contract.activate_validators(["validator1"])[0];
+
+    vault
+        .bond(None)
+        .with_funds(&coins(600, OSMO))
+        .call(user)
+        .unwrap();
+    vault.stake(&contract, user, validator, coin(200, OSMO));
+    let staked_at = app.block_info().time;
+    let advance = |seconds: u64| {
+        app.update_block(|block| {
+            block.height += 1;
+            block.time = block.time.plus_seconds(seconds);
+        })
+    };
+
+    // Only the admin can enable sweeping
+    let sweep = RewardsSweepConfig {
+        claim_period: 100,
+        notice_period: 50,
+        recipient: treasury.to_owned(),
+    };
+    let err = contract
+        .notify_unclaimed_rewards(user.to_owned(), validator.to_owned())
+        .call(owner)
+        .unwrap_err();
+    assert_eq!(err, ContractError::NoRewardsSweep);
+    let err = contract
+        .set_rewards_sweep(Some(sweep.clone()))
+        .call(user)
+        .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized);
+    contract
+        .set_rewards_sweep(Some(sweep.clone()))
+        .call(owner)
         .unwrap();
-    let tx_id = get_last_external_staking_pending_tx_id(&contract).unwrap();
+    assert_eq!(contract.rewards_sweep().unwrap().config, Some(sweep));
+
+    // Rewards cannot be flagged until the claim period is over
     contract
-        .test_commit_withdraw_rewards(tx_id)
-        .call(users[0])
+        .distribute_batch(owner, STAR, &[(validator, 50)])
         .unwrap();
+    let err = contract
+        .notify_unclaimed_rewards(user.to_owned(), validator.to_owned())
+        .call(owner)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::ClaimPeriodActive(staked_at.plus_seconds(100))
+    );
+
+    // Anyone can notify the staker, who is then given the notice period to claim
+    advance(100);
+    let resp = contract
+        .notify_unclaimed_rewards(user.to_owned(), validator.to_owned())
+        .call("keeper")
+        .unwrap();
+    let sweepable_at = staked_at.plus_seconds(150);
+    assert!(resp.has_event(
+        &Event::new("wasm-unclaimed_rewards_notice")
+            .add_attribute("user", user)
+            .add_attribute("amount", "50")
+            .add_attribute("sweepable_at", sweepable_at.seconds().to_string())
+    ));
+    let err = contract
+        .notify_unclaimed_rewards(user.to_owned(), validator.to_owned())
+        .call("keeper")
+        .unwrap_err();
+    assert_eq!(err, ContractError::SweepAlreadyNotified(sweepable_at));
+    let err = contract
+        .sweep_unclaimed_rewards(user.to_owned(), validator.to_owned())
+        .call("keeper")
+        .unwrap_err();
+    assert_eq!(err, ContractError::SweepNotDue(sweepable_at));
 
+    // Claiming drops the notice
     contract
-        .withdraw_rewards(validators[1].to_string(), remote[0].to_owned())
-        .call(users[0])
+        .withdraw_rewards(validator.to_owned(), remote.to_owned())
+        .call(user)
         .unwrap();
     let tx_id = get_last_external_staking_pending_tx_id(&contract).unwrap();
     contract
         .test_commit_withdraw_rewards(tx_id)
-        .call(users[0])
+        .call("test")
+        .unwrap();
+    let unclaimed = contract
+        .unclaimed_rewards(user.to_owned(), validator.to_owned())
         .unwrap();
+    assert_eq!(unclaimed.last_claim, Some(staked_at.plus_seconds(100)));
+    assert_eq!(unclaimed.sweepable_at, None);
+    advance(50);
+    let err = contract
+        .sweep_unclaimed_rewards(user.to_owned(), validator.to_owned())
+        .call("keeper")
+        .unwrap_err();
+    assert_eq!(err, ContractError::NoSweepNotice);
 
+    // Rewards left unclaimed after the notice are swept, with no fee
     contract
-        .withdraw_rewards(validators[0].to_string(), remote[1].to_owned())
-        .call(users[1])
+        .distribute_batch(owner, STAR, &[(validator, 30)])
         .unwrap();
-    let tx_id = get_last_external_staking_pending_tx_id(&contract).unwrap();
+    advance(50);
     contract
-        .test_commit_withdraw_rewards(tx_id)
-        .call(users[0])
+        .notify_unclaimed_rewards(user.to_owned(), validator.to_owned())
+        .call("keeper")
         .unwrap();
-
-    contract
-        .withdraw_rewards(validators[1].to_string(), remote[1].to_owned())
-        .call(users[1])
+    advance(50);
+    let resp = contract
+        .sweep_unclaimed_rewards(user.to_owned(), validator.to_owned())
+        .call("keeper")
         .unwrap();
+    assert!(resp.has_event(
+        &Event::new("wasm")
+            .add_attribute("recipient", treasury)
+            .add_attribute("amount", "30")
+    ));
     let tx_id = get_last_external_staking_pending_tx_id(&contract).unwrap();
     contract
         .test_commit_withdraw_rewards(tx_id)
-        .call(users[0])
-        .unwrap();
-}
-
-#[test]
-fn batch_distribution() {
-    let owner = "owner";
-    let users = ["user1", "user2"];
-
-    let app =
-        App::new_with_balances(&[(users[0], &coins(600, OSMO)), (users[1], &coins(600, OSMO))]);
-
-    let (vault, contract) = setup(&app, owner, 100).unwrap();
-
-    let validators = contract.activate_validators(["validator1", "validator2"]);
-
-    vault
-        .bond()
-        .with_funds(&coins(600, OSMO))
-        .call(users[0])
-        .unwrap();
-    vault
-        .bond()
-        .with_funds(&coins(600, OSMO))
-        .call(users[1])
+        .call("test")
         .unwrap();
+    assert_rewards!(contract, user, validator, 0);
 
-    vault.stake(&contract, users[0], validators[0], coin(200, OSMO));
-    vault.stake(&contract, users[0], validators[1], coin(100, OSMO));
-    vault.stake(&contract, users[1], validators[0], coin(300, OSMO));
-
+    // Disabling sweeping drops the notices
     contract
-        .distribute_batch(owner, STAR, &[(validators[0], 50), (validators[1], 30)])
+        .distribute_batch(owner, STAR, &[(validator, 20)])
         .unwrap();
-
-    assert_rewards!(contract, users[0], validators[0], 20);
-    assert_rewards!(contract, users[1], validators[0], 30);
-    assert_rewards!(contract, users[0], validators[1], 30);
-    assert_rewards!(contract, users[1], validators[1], 0);
-
+    advance(100);
     contract
-        .distribute_batch(owner, STAR, &[(validators[0], 100), (validators[1], 30)])
+        .notify_unclaimed_rewards(user.to_owned(), validator.to_owned())
+        .call("keeper")
         .unwrap();
-
-    assert_rewards!(contract, users[0], validators[0], 60);
-    assert_rewards!(contract, users[1], validators[0], 90);
-    assert_rewards!(contract, users[0], validators[1], 60);
-    assert_rewards!(contract, users[1], validators[1], 0);
+    contract.set_rewards_sweep(None).call(owner).unwrap();
+    let unclaimed = contract
+        .unclaimed_rewards(user.to_owned(), validator.to_owned())
+        .unwrap();
+    assert_eq!(unclaimed.sweepable_at, None);
 }
 
 #[test]
@@ -1208,7 +2564,7 @@ fn batch_distribution_invalid_token() {
     let validator = contract.activate_validators(["validator1"])[0];
 
     vault
-        .bond()
+        .bond(None)
         .with_funds(&coins(600, OSMO))
         .call(user)
         .unwrap();
@@ -1234,7 +2590,7 @@ fn slashing() {
     let validators = contract.activate_validators(["validator1", "validator2"]);
 
     vault
-        .bond()
+        .bond(None)
         .with_funds(&coins(300, OSMO))
         .call(user)
         .unwrap();
@@ -1379,7 +2735,7 @@ fn slashing_pending_tx_partial_unbond() {
     let validators = contract.activate_validators(["validator1", "validator2"]);
 
     vault
-        .bond()
+        .bond(None)
         .with_funds(&coins(300, OSMO))
         .call(user)
         .unwrap();
@@ -1466,7 +2822,7 @@ fn slashing_pending_tx_full_unbond() {
     let validators = contract.activate_validators(["validator1", "validator2"]);
 
     vault
-        .bond()
+        .bond(None)
         .with_funds(&coins(200, OSMO))
         .call(user)
         .unwrap();
@@ -1549,7 +2905,7 @@ fn slashing_pending_tx_full_unbond_rolled_back() {
     let validators = contract.activate_validators(["validator1"]);
 
     vault
-        .bond()
+        .bond(None)
         .with_funds(&coins(200, OSMO))
         .call(user)
         .unwrap();
@@ -1630,7 +2986,7 @@ fn slashing_pending_tx_bond() {
     let validators = contract.activate_validators(["validator1", "validator2"]);
 
     vault
-        .bond()
+        .bond(None)
         .with_funds(&coins(300, OSMO))
         .call(user)
         .unwrap();
@@ -1647,6 +3003,7 @@ fn slashing_pending_tx_bond() {
                 validator: validators[0].into(),
             })
             .unwrap(),
+            None,
         )
         .call(user)
         .unwrap();
@@ -1715,7 +3072,7 @@ fn slashing_pending_tx_bond_rolled_back() {
     let validators = contract.activate_validators(["validator1", "validator2"]);
 
     vault
-        .bond()
+        .bond(None)
         .with_funds(&coins(300, OSMO))
         .call(user)
         .unwrap();
@@ -1732,6 +3089,7 @@ fn slashing_pending_tx_bond_rolled_back() {
                 validator: validators[0].into(),
             })
             .unwrap(),
+            None,
         )
         .call(user)
         .unwrap();
@@ -1786,3 +3144,397 @@ fn slashing_pending_tx_bond_rolled_back() {
         .unwrap();
     assert_eq!(claim.amount.val().unwrap().u128(), 230);
 }
+
+#[test]
+fn relock() {
+    let user = "user1";
+    let owner = "owner";
+
+    let app = App::new_with_balances(&[(user, &coins(300, OSMO))]);
+    let (vault, contract) = setup(&app, owner, 100).unwrap();
+    let validators = contract.activate_validators(["validator1", "validator2", "validator3"]);
+    vault
+        .bond(None)
+        .with_funds(&coins(300, OSMO))
+        .call(user)
+        .unwrap();
+    vault.stake(&contract, user, validators[0], coin(200, OSMO));
+
+    let claim = |vault: &Proxy<'_, MtApp, VaultMock<'_>>| {
+        vault
+            .claim(user.to_owned(), contract.contract_addr.to_string())
+            .unwrap()
+            .amount
+    };
+    let unstake_and_wait = |amount: u128| {
+        contract
+            .unstake(validators[0].to_string(), coin(amount, OSMO))
+            .call(user)
+            .unwrap();
+        contract
+            .test_commit_unstake(get_last_external_staking_pending_tx_id(&contract).unwrap())
+            .call("test")
+            .unwrap();
+        app.app_mut().update_block(|block| {
+            block.height += 1;
+            block.time = block.time.plus_seconds(101);
+        });
+    };
+
+    // Only active validators can be chosen
+    let err = contract
+        .set_relock(Some("unknown".to_owned()))
+        .call(user)
+        .unwrap_err();
+    assert_eq!(err, ContractError::ValidatorNotActive("unknown".to_owned()));
+
+    contract
+        .set_relock(Some(validators[1].to_owned()))
+        .call(user)
+        .unwrap();
+    assert_eq!(
+        contract.relock(user.to_owned()).unwrap().validator,
+        Some(validators[1].to_owned())
+    );
+
+    // Released collateral is re-staked to the relock validator, and stays liened
+    unstake_and_wait(100);
+    contract.withdraw_unbonded().call(user).unwrap();
+    assert_eq!(claim(&vault), ValueRange::new_val(Uint128::new(200)));
+    let stake = contract
+        .stake(user.to_owned(), validators[1].to_owned())
+        .unwrap();
+    assert_eq!(
+        stake.stake,
+        ValueRange::new(Uint128::zero(), Uint128::new(100))
+    );
+
+    contract
+        .test_commit_stake(get_last_external_staking_pending_tx_id(&contract).unwrap())
+        .call("test")
+        .unwrap();
+    let stake = contract
+        .stake(user.to_owned(), validators[1].to_owned())
+        .unwrap();
+    assert_eq!(stake.stake, ValueRange::new_val(Uint128::new(100)));
+    assert_eq!(claim(&vault), ValueRange::new_val(Uint128::new(200)));
+
+    // A failed relock releases the collateral instead
+    unstake_and_wait(30);
+    contract.withdraw_unbonded().call(user).unwrap();
+    contract
+        .test_rollback_stake(get_last_external_staking_pending_tx_id(&contract).unwrap())
+        .call("test")
+        .unwrap();
+    let stake = contract
+        .stake(user.to_owned(), validators[1].to_owned())
+        .unwrap();
+    assert_eq!(stake.stake, ValueRange::new_val(Uint128::new(100)));
+    assert_eq!(claim(&vault), ValueRange::new_val(Uint128::new(170)));
+
+    // If the relock validator doesn't accept new stake, the collateral is released as usual
+    contract
+        .set_validators_paused(vec![validators[1].to_owned()], true)
+        .call(owner)
+        .unwrap();
+    unstake_and_wait(20);
+    contract.withdraw_unbonded().call(user).unwrap();
+    assert_eq!(get_last_external_staking_pending_tx_id(&contract), None);
+    assert_eq!(claim(&vault), ValueRange::new_val(Uint128::new(150)));
+
+    // Opting out
+    contract.set_relock(None).call(user).unwrap();
+    assert_eq!(contract.relock(user.to_owned()).unwrap().validator, None);
+    contract
+        .set_validators_paused(vec![validators[1].to_owned()], false)
+        .call(owner)
+        .unwrap();
+    unstake_and_wait(10);
+    contract.withdraw_unbonded().call(user).unwrap();
+    assert_eq!(claim(&vault), ValueRange::new_val(Uint128::new(140)));
+
+    // Anyone can withdraw for the user, always releasing the collateral
+    contract
+        .set_relock(Some(validators[1].to_owned()))
+        .call(user)
+        .unwrap();
+    unstake_and_wait(10);
+    contract
+        .withdraw_unbonded_for(user.to_owned())
+        .call("keeper")
+        .unwrap();
+    assert_eq!(get_last_external_staking_pending_tx_id(&contract), None);
+    assert_eq!(claim(&vault), ValueRange::new_val(Uint128::new(130)));
+}
+
+#[test]
+fn verify_against_vault() {
+    use mesh_apis::vault_api::sv::mt::VaultApiProxy;
+
+    let user = "user1";
+    let owner = "owner";
+
+    let app = App::new_with_balances(&[(user, &coins(300, OSMO))]);
+    let (vault, contract) = setup(&app, owner, 100).unwrap();
+    let validators = contract.activate_validators(["validator1", "validator2"]);
+    vault
+        .bond(None)
+        .with_funds(&coins(300, OSMO))
+        .call(user)
+        .unwrap();
+    vault.stake(&contract, user, validators[0], coin(100, OSMO));
+    vault.stake(&contract, user, validators[1], coin(50, OSMO));
+
+    let verification = contract.vault_verification(user.to_owned()).unwrap();
+    assert_eq!(verification.lien, ValueRange::new_val(Uint128::new(150)));
+    assert_eq!(verification.stake, ValueRange::new_val(Uint128::new(150)));
+    assert!(verification.in_sync);
+
+    // Pending unbonds are still liened
+    contract
+        .unstake(validators[0].to_string(), coin(40, OSMO))
+        .call(user)
+        .unwrap();
+    assert!(
+        contract
+            .vault_verification(user.to_owned())
+            .unwrap()
+            .in_sync
+    );
+    contract
+        .test_commit_unstake(get_last_external_staking_pending_tx_id(&contract).unwrap())
+        .call("test")
+        .unwrap();
+    let verification = contract.vault_verification(user.to_owned()).unwrap();
+    assert_eq!(verification.stake, ValueRange::new_val(Uint128::new(110)));
+    assert_eq!(verification.pending_unbonds, Uint128::new(40));
+    assert!(verification.in_sync);
+
+    let resp = contract
+        .verify_against_vault(user.to_owned())
+        .call("anyone")
+        .unwrap();
+    assert!(!resp.events.iter().any(|e| e.ty == "wasm-vault_drift"));
+
+    // A lien released behind the contract's back is reported
+    vault
+        .release_cross_stake(user.to_owned(), coin(10, OSMO), vec![])
+        .call(contract.contract_addr.as_str())
+        .unwrap();
+    let verification = contract.vault_verification(user.to_owned()).unwrap();
+    assert_eq!(verification.lien, ValueRange::new_val(Uint128::new(140)));
+    assert!(!verification.in_sync);
+    let resp = contract
+        .verify_against_vault(user.to_owned())
+        .call("anyone")
+        .unwrap();
+    let drift = resp
+        .events
+        .iter()
+        .find(|e| e.ty == "wasm-vault_drift")
+        .unwrap();
+    assert!(drift
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "lien" && attr.value == "140"));
+}
+
+#[test]
+fn send_collateral_summary() {
+    use mesh_apis::vault_api::sv::mt::VaultApiProxy;
+
+    use crate::contract::COLLATERAL_SUMMARY_INTERVAL;
+
+    let user = "user1";
+    let owner = "owner";
+
+    let app = App::new_with_balances(&[(user, &coins(300, OSMO))]);
+    let (vault, contract) = setup(&app, owner, 100).unwrap();
+    let validators = contract.activate_validators(["validator1", "validator2"]);
+    vault
+        .bond(None)
+        .with_funds(&coins(300, OSMO))
+        .call(user)
+        .unwrap();
+    vault.stake(&contract, user, validators[0], coin(100, OSMO));
+    vault.stake(&contract, user, validators[1], coin(50, OSMO));
+
+    let total = vault
+        .lienholder_total(contract.contract_addr.to_string(), OSMO.to_owned())
+        .unwrap();
+    assert_eq!(total.high, Uint128::new(150));
+
+    // Anyone can send the summary, with the collateral liened in the vault
+    let resp = contract.send_collateral_summary().call("anyone").unwrap();
+    assert!(resp.events.iter().any(|e| e
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "total_collateral" && attr.value == "150")));
+
+    // But only once per interval
+    let next = app
+        .block_info()
+        .time
+        .plus_seconds(COLLATERAL_SUMMARY_INTERVAL);
+    let err = contract
+        .send_collateral_summary()
+        .call("anyone")
+        .unwrap_err();
+    assert_eq!(err, ContractError::CollateralSummaryNotDue(next));
+
+    app.update_block(|block| block.time = next);
+    contract.send_collateral_summary().call("anyone").unwrap();
+}
+
+#[test]
+fn staker_callbacks() {
+    let user = "user1";
+    let owner = "owner";
+
+    let app = App::new_with_balances(&[(user, &coins(300, OSMO))]);
+    let (vault, contract) = setup(&app, owner, 100).unwrap();
+    let validators = contract.activate_validators(["validator1"]);
+
+    // Only contracts can opt into callbacks
+    let err = contract.set_staker_callbacks(true).call(user).unwrap_err();
+    assert_eq!(err, ContractError::NotAContract(user.to_owned()));
+
+    let staker_code = app.app_mut().store_code(staker_contract());
+    let staker = app
+        .app_mut()
+        .instantiate_contract(
+            staker_code,
+            Addr::unchecked(owner),
+            &Empty {},
+            &[],
+            "Staker",
+            None,
+        )
+        .unwrap();
+    app.app_mut()
+        .send_tokens(Addr::unchecked(user), staker.clone(), &coins(300, OSMO))
+        .unwrap();
+    let staker = staker.as_str();
+    let callbacks = || -> Vec<StakerCallbackApiExecMsg> {
+        app.app()
+            .wrap()
+            .query_wasm_smart(staker, &Empty {})
+            .unwrap()
+    };
+
+    contract.set_staker_callbacks(true).call(staker).unwrap();
+    assert!(
+        contract
+            .staker_callbacks(staker.to_owned())
+            .unwrap()
+            .enabled
+    );
+    vault
+        .bond(None)
+        .with_funds(&coins(300, OSMO))
+        .call(staker)
+        .unwrap();
+
+    // The staker is told about committed and rolled back stakes
+    let stake_remote = |amount: u128| {
+        vault
+            .stake_remote(
+                contract.contract_addr.to_string(),
+                coin(amount, OSMO),
+                to_json_binary(&ReceiveVirtualStake {
+                    validator: validators[0].to_owned(),
+                })
+                .unwrap(),
+                None,
+            )
+            .call(staker)
+            .unwrap();
+        get_last_external_staking_pending_tx_id(&contract).unwrap()
+    };
+    let committed_stake = stake_remote(100);
+    contract
+        .test_commit_stake(committed_stake)
+        .call("test")
+        .unwrap();
+    let rolled_back_stake = stake_remote(50);
+    contract
+        .test_rollback_stake(rolled_back_stake)
+        .call("test")
+        .unwrap();
+    assert_eq!(
+        callbacks(),
+        [
+            StakerCallbackApiExecMsg::StakeCommitted {
+                tx_id: committed_stake,
+                validator: validators[0].to_owned(),
+                amount: coin(100, OSMO),
+            },
+            StakerCallbackApiExecMsg::StakeRolledBack {
+                tx_id: rolled_back_stake,
+                validator: validators[0].to_owned(),
+                amount: coin(50, OSMO),
+            },
+        ]
+    );
+
+    // Committed unstakes tell when the unbonding matures
+    contract
+        .unstake(validators[0].to_owned(), coin(40, OSMO))
+        .call(staker)
+        .unwrap();
+    let committed_unstake = get_last_external_staking_pending_tx_id(&contract).unwrap();
+    contract
+        .test_commit_unstake(committed_unstake)
+        .call("test")
+        .unwrap();
+    let release_at = app.app().block_info().time.plus_seconds(100).seconds();
+
+    // Failing callbacks don't affect the outcome
+    contract
+        .unstake(validators[0].to_owned(), coin(10, OSMO))
+        .call(staker)
+        .unwrap();
+    let resp = contract
+        .test_rollback_unstake(get_last_external_staking_pending_tx_id(&contract).unwrap())
+        .call("test")
+        .unwrap();
+    assert!(resp
+        .events
+        .iter()
+        .any(|e| e.ty == "wasm-staker_callback_failed"));
+    let stake = contract
+        .stake(staker.to_owned(), validators[0].to_owned())
+        .unwrap();
+    assert_eq!(stake.stake, ValueRange::new_val(Uint128::new(60)));
+
+    // Matured unbondings are released
+    app.app_mut().update_block(|block| {
+        block.height += 1;
+        block.time = block.time.plus_seconds(101);
+    });
+    contract.withdraw_unbonded().call(staker).unwrap();
+    assert_eq!(
+        callbacks()[2..],
+        [
+            StakerCallbackApiExecMsg::UnstakeCommitted {
+                tx_id: committed_unstake,
+                validator: validators[0].to_owned(),
+                amount: coin(40, OSMO),
+                release_at,
+            },
+            StakerCallbackApiExecMsg::UnbondingReleased {
+                amount: coin(40, OSMO),
+                relocked: false,
+            },
+        ]
+    );
+
+    // Opting out
+    contract.set_staker_callbacks(false).call(staker).unwrap();
+    assert!(
+        !contract
+            .staker_callbacks(staker.to_owned())
+            .unwrap()
+            .enabled
+    );
+}
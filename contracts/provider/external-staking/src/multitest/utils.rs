@@ -1,5 +1,10 @@
-use cosmwasm_std::{to_json_binary, Addr, Coin};
-use cw_multi_test::{App as MtApp, AppResponse};
+use cosmwasm_std::{
+    to_json_binary, Addr, Binary, Coin, Deps, DepsMut, Empty, Env, MessageInfo, Response, StdError,
+    StdResult,
+};
+use cw_multi_test::{App as MtApp, AppResponse, Contract as MtContract, ContractWrapper};
+use cw_storage_plus::Item;
+use mesh_apis::staker_callback_api::sv::StakerCallbackApiExecMsg;
 use mesh_apis::{converter_api::RewardInfo, ibc::AddValidator};
 use mesh_sync::Tx;
 use mesh_vault::mock::{sv::mt::VaultMockProxy, VaultMock};
@@ -69,6 +74,8 @@ pub(crate) trait ContractExt {
     ) -> [&'static str; N];
 
     fn remove_validator(&self, validator: &'static str);
+    fn jail_validator(&self, validator: &'static str);
+    fn unjail_validator(&self, validator: &'static str);
     fn tombstone_validator(&self, validator: &'static str);
 
     fn distribute_batch(
@@ -102,6 +109,20 @@ impl ContractExt for Contract<'_> {
             .unwrap();
     }
 
+    #[track_caller]
+    fn jail_validator(&self, validator: &'static str) {
+        self.test_jail_validator(validator.to_string(), 101, 1234)
+            .call("test")
+            .unwrap();
+    }
+
+    #[track_caller]
+    fn unjail_validator(&self, validator: &'static str) {
+        self.test_unjail_validator(validator.to_string())
+            .call("test")
+            .unwrap();
+    }
+
     #[track_caller]
     fn tombstone_validator(&self, validator: &'static str) {
         self.test_tombstone_validator(validator.to_string(), 101, 1234)
@@ -140,6 +161,7 @@ impl VaultExt for Vault<'_> {
                 validator: validator.into(),
             })
             .unwrap(),
+            None,
         )
         .call(user)
         .unwrap();
@@ -154,6 +176,42 @@ impl VaultExt for Vault<'_> {
     }
 }
 
+const STAKER_CALLBACKS: Item<Vec<StakerCallbackApiExecMsg>> = Item::new("callbacks");
+
+/// Staker contract recording the callbacks it receives, queried with an empty message.
+/// It rejects rolled back unstakes, to check failing callbacks don't affect their outcome.
+pub(crate) fn staker_contract() -> Box<dyn MtContract<Empty>> {
+    fn execute(
+        deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        msg: StakerCallbackApiExecMsg,
+    ) -> StdResult<Response> {
+        if matches!(msg, StakerCallbackApiExecMsg::UnstakeRolledBack { .. }) {
+            return Err(StdError::generic_err("Rolled back unstakes are rejected"));
+        }
+        let mut callbacks = STAKER_CALLBACKS.may_load(deps.storage)?.unwrap_or_default();
+        callbacks.push(msg);
+        STAKER_CALLBACKS.save(deps.storage, &callbacks)?;
+        Ok(Response::new())
+    }
+
+    fn instantiate(
+        _deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        _msg: Empty,
+    ) -> StdResult<Response> {
+        Ok(Response::new())
+    }
+
+    fn query(deps: Deps, _env: Env, _msg: Empty) -> StdResult<Binary> {
+        to_json_binary(&STAKER_CALLBACKS.may_load(deps.storage)?.unwrap_or_default())
+    }
+
+    Box::new(ContractWrapper::new(execute, instantiate, query))
+}
+
 fn reward_info(validator: impl Into<String>, reward: u128) -> RewardInfo {
     RewardInfo {
         validator: validator.into(),
@@ -1,6 +1,6 @@
 use crate::state::Stake;
-use cosmwasm_std::{Addr, Order, StdResult, Storage};
-use cw_storage_plus::{Index, IndexList, IndexedMap, KeyDeserialize, MultiIndex};
+use cosmwasm_std::{Addr, Order, StdResult, Storage, Uint128};
+use cw_storage_plus::{Index, IndexList, IndexedMap, Item, KeyDeserialize, MultiIndex};
 
 pub struct StakeIndexes<'a> {
     // Last type param defines the pk deserialization type
@@ -16,6 +16,8 @@ impl<'a> IndexList<Stake> for StakeIndexes<'a> {
 
 pub struct Stakes<'a> {
     pub stake: IndexedMap<'a, (&'a Addr, &'a str), Stake, StakeIndexes<'a>>,
+    /// Running total of the pending unbonds, across all stakes
+    pub pending_total: Item<'a, Uint128>,
 }
 
 impl<'a> Stakes<'a> {
@@ -23,7 +25,11 @@ impl<'a> Stakes<'a> {
         <(Addr, String)>::from_slice(pk).unwrap() // mustn't fail
     }
 
-    pub fn new(storage_key: &'a str, validator_subkey: &'a str) -> Self {
+    pub fn new(
+        storage_key: &'a str,
+        validator_subkey: &'a str,
+        pending_total_key: &'a str,
+    ) -> Self {
         let indexes = StakeIndexes {
             rev: MultiIndex::new(
                 |pk, _| {
@@ -36,7 +42,56 @@ impl<'a> Stakes<'a> {
         };
         let stakes = IndexedMap::new(storage_key, indexes);
 
-        Self { stake: stakes }
+        Self {
+            stake: stakes,
+            pending_total: Item::new(pending_total_key),
+        }
+    }
+
+    /// Saves a stake, keeping the pending unbonds total in sync
+    pub fn save(
+        &self,
+        storage: &mut dyn Storage,
+        key: (&Addr, &str),
+        stake: &Stake,
+    ) -> StdResult<()> {
+        let old = self.stake.may_load(storage, key)?.unwrap_or_default();
+        self.update_pending_total(storage, old.total_pending(), stake.total_pending())?;
+        self.stake.save(storage, key, stake)
+    }
+
+    /// Removes a stake, keeping the pending unbonds total in sync
+    pub fn remove(&self, storage: &mut dyn Storage, key: (&Addr, &str)) -> StdResult<()> {
+        if let Some(old) = self.stake.may_load(storage, key)? {
+            self.update_pending_total(storage, old.total_pending(), Uint128::zero())?;
+        }
+        self.stake.remove(storage, key)
+    }
+
+    /// Recomputes the pending unbonds total from the stored stakes, for unbonds pending before it
+    /// was tracked
+    pub fn seed_pending_total(&self, storage: &mut dyn Storage) -> StdResult<()> {
+        let total = self
+            .stake
+            .range(storage, None, None, Order::Ascending)
+            .map(|item| item.map(|(_, stake)| stake.total_pending()))
+            .sum::<StdResult<Uint128>>()?;
+        self.pending_total.save(storage, &total)
+    }
+
+    fn update_pending_total(
+        &self,
+        storage: &mut dyn Storage,
+        old: Uint128,
+        new: Uint128,
+    ) -> StdResult<()> {
+        if old == new {
+            return Ok(());
+        }
+        let total = self.pending_total.may_load(storage)?.unwrap_or_default();
+        // Saturating, as unbonds pending before the total was tracked are not accounted
+        let total = (total + new).saturating_sub(old);
+        self.pending_total.save(storage, &total)
     }
 
     pub fn stakes_by_validator(
@@ -1,5 +1,6 @@
 use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{BlockInfo, Decimal, Timestamp, Uint128, Uint256};
+use mesh_apis::ibc::{ConversionTerms, ProviderPacket};
 use mesh_apis::vault_api::VaultApiHelper;
 use mesh_sync::ValueRange;
 
@@ -18,6 +19,93 @@ pub struct Config {
     pub unbonding_period: u64,
     /// The slash ratio
     pub slash_ratio: SlashRatio,
+    /// How stakes to jailed (but not tombstoned) validators are handled
+    #[serde(default)]
+    pub jailed_stake_policy: JailedStakePolicy,
+    /// How slashes of jailed (but not tombstoned) validators are applied
+    #[serde(default)]
+    pub jail_slash_policy: JailSlashPolicy,
+    /// Shortened unbonding periods, overriding `unbonding_period` in some cases
+    #[serde(default)]
+    pub unbonding_overrides: UnbondingOverrides,
+    /// Number of decimals of the reward points per stake
+    #[serde(default = "default_distribution_precision")]
+    pub distribution_precision: u32,
+}
+
+/// Number of decimals of the reward points per stake, unless set at instantiation
+pub const DEFAULT_DISTRIBUTION_PRECISION: u32 = 9;
+
+fn default_distribution_precision() -> u32 {
+    DEFAULT_DISTRIBUTION_PRECISION
+}
+
+impl Config {
+    /// Scale of the reward points, i.e. the reward points of a whole reward token
+    pub fn distribution_points_scale(&self) -> Uint256 {
+        Uint256::from(10u128.pow(self.distribution_precision))
+    }
+}
+
+/// Behaviour of `receive_virtual_stake` when the target validator is jailed
+#[cw_serde]
+#[derive(Default)]
+pub enum JailedStakePolicy {
+    /// Staking to jailed validators fails
+    #[default]
+    Reject,
+    /// Stake is sent to the consumer as if the validator was active
+    Allow,
+    /// Stake is kept pending, and only sent to the consumer once the validator is active again.
+    /// If the validator is tombstoned instead, the stake is rolled back.
+    Queue,
+}
+
+/// Behaviour of `valset_update` when a validator is slashed and jailed, but not tombstoned
+#[cw_serde]
+#[derive(Default)]
+pub enum JailSlashPolicy {
+    /// Slash is applied as soon as it is reported
+    #[default]
+    Immediate,
+    /// Slash is only applied if the validator is not active again `grace_period` seconds
+    /// after it was reported, giving it time to unjail
+    Delayed { grace_period: u64 },
+    /// Jail slashes are ignored. Only tombstoned validators are slashed
+    Disabled,
+}
+
+/// Shortened unbonding periods (in seconds). Unbonds from unbonded or tombstoned validators are
+/// always released immediately
+#[cw_serde]
+#[derive(Default)]
+pub struct UnbondingOverrides {
+    /// Unbonding period of unstakes from jailed validators
+    pub jailed: Option<u64>,
+    /// Unbonding period while the consumer is halted. Pending unbonds are released as if they
+    /// were scheduled with this period instead
+    pub consumer_halted: Option<u64>,
+}
+
+/// Delegation policy sent by the consumer, enforced on new stakes
+#[cw_serde]
+#[derive(Default)]
+pub struct DelegationPolicy {
+    /// Max number of validators a single user can stake to
+    pub max_validators: Option<u32>,
+    /// Max share of the contract's total stake that can be staked to a single validator
+    pub max_validator_share: Option<Decimal>,
+}
+
+/// Jail slash waiting for its grace period to end
+#[cw_serde]
+pub struct PendingJailSlash {
+    pub slash_ratio: Decimal,
+    pub slash_amount: Uint128,
+    /// Infraction time, in seconds
+    pub infraction_time: u64,
+    /// Time (in seconds) the slash is applied at, unless the validator is active again
+    pub due: u64,
 }
 
 #[cw_serde]
@@ -26,6 +114,16 @@ pub struct SlashRatio {
     pub offline: Decimal,
 }
 
+/// Slash ratio sent by the consumer, from its actual slashing params
+#[cw_serde]
+pub struct ConsumerSlashRatio {
+    pub slash_ratio: SlashRatio,
+    /// Consumer height the params were read at
+    pub height: u64,
+    /// Consumer time the params were read at, as unix seconds
+    pub time: u64,
+}
+
 /// All single stake related information - entry per `(user, validator)` pair, including
 /// distribution alignment
 #[cw_serde]
@@ -64,9 +162,46 @@ pub struct PendingUnbond {
     pub amount: Uint128,
     /// Time when tokens are released
     pub release_at: Timestamp,
+    /// Why the tokens are unbonding. Entries stored before origins were tracked are user unstakes
+    #[serde(default)]
+    pub origin: UnbondOrigin,
+}
+
+/// What triggered a pending unbond
+#[cw_serde]
+#[derive(Default, Copy)]
+pub enum UnbondOrigin {
+    /// The user unstaked
+    #[default]
+    User,
+    /// The user unstaked from a tombstoned validator. Tokens are released right away
+    Tombstone,
+    /// The consumer force-unstaked the tokens (zero max cap)
+    ConsumerForced,
+    /// The consumer channel was closed (consumer sunset), releasing all stakes
+    ChannelClosed,
+}
+
+impl UnbondOrigin {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UnbondOrigin::User => "user",
+            UnbondOrigin::Tombstone => "tombstone",
+            UnbondOrigin::ConsumerForced => "consumer_forced",
+            UnbondOrigin::ChannelClosed => "channel_closed",
+        }
+    }
 }
 
 impl Stake {
+    /// Total amount in pending unbonds, including the ones ready to be released
+    pub fn total_pending(&self) -> Uint128 {
+        self.pending_unbonds
+            .iter()
+            .map(|pending| pending.amount)
+            .sum()
+    }
+
     /// Removes expired entries from `pending_unbonds`, returning amount of tokens released.
     pub fn release_pending(&mut self, info: &BlockInfo) -> Uint128 {
         // The fact that `pending unbonds are always added to the end, so they are always ordered
@@ -127,3 +262,50 @@ pub struct Distribution {
     /// Points which were not distributed previously
     pub points_leftover: Uint256,
 }
+
+/// Event-based monitoring information, exposed through the `metrics` query
+#[cw_serde]
+#[derive(Default)]
+pub struct Metrics {
+    /// Consumer height of the last valset update
+    pub last_valset_update_height: Option<u64>,
+    /// Consumer time of the last valset update (unix seconds)
+    pub last_valset_update_time: Option<u64>,
+    /// Number of slashing events processed
+    pub slash_incidents: u64,
+}
+
+/// Lifetime statistics of a user, exposed through the `user_stats` query
+#[cw_serde]
+#[derive(Default)]
+pub struct UserStats {
+    /// Stake committed by the consumer
+    pub total_staked: Uint128,
+    /// Unstakes committed by the consumer, including the ones it forced
+    pub total_unstaked: Uint128,
+    /// Rewards withdrawn to the consumer, protocol fees included. Swept rewards are not counted
+    pub total_rewards_withdrawn: Uint128,
+    /// Stake and pending unbonds slashed
+    pub total_slashed: Uint128,
+    /// Number of slashing events the user was slashed in
+    pub slash_count: u64,
+}
+
+/// Terms a committed stake was converted with on the consumer
+#[cw_serde]
+pub struct StakeConversion {
+    pub validator: String,
+    /// Amount of provider-side tokens that were staked
+    pub amount: Uint128,
+    pub conversion: ConversionTerms,
+}
+
+/// A timed out packet, waiting to be resent
+#[cw_serde]
+pub struct QueuedPacket {
+    pub packet: ProviderPacket,
+    /// Times the packet was already resent
+    pub attempts: u32,
+    /// Time the packet can be resent from
+    pub retry_at: Timestamp,
+}
@@ -38,6 +38,21 @@ pub trait TestMethods {
         time: u64,
     ) -> Result<Response, Self::Error>;
 
+    /// Sets validator as `jailed`.
+    #[sv::msg(exec)]
+    fn test_jail_validator(
+        &self,
+        ctx: ExecCtx,
+        valoper: String,
+        height: u64,
+        time: u64,
+    ) -> Result<Response, Self::Error>;
+
+    /// Reports validator as unjailed.
+    #[sv::msg(exec)]
+    fn test_unjail_validator(&self, ctx: ExecCtx, valoper: String)
+        -> Result<Response, Self::Error>;
+
     #[sv::msg(exec)]
     fn test_tombstone_validator(
         &self,
@@ -55,6 +70,18 @@ pub trait TestMethods {
     #[sv::msg(exec)]
     fn test_rollback_unstake(&self, ctx: ExecCtx, tx_id: u64) -> Result<Response, Self::Error>;
 
+    /// Commits a pending unbond cancellation.
+    #[sv::msg(exec)]
+    fn test_commit_cancel_unbond(&self, ctx: ExecCtx, tx_id: u64) -> Result<Response, Self::Error>;
+
+    /// Rollbacks a pending unbond cancellation.
+    #[sv::msg(exec)]
+    fn test_rollback_cancel_unbond(
+        &self,
+        ctx: ExecCtx,
+        tx_id: u64,
+    ) -> Result<Response, Self::Error>;
+
     /// Distribute rewards.
     #[sv::msg(exec)]
     fn test_distribute_rewards(
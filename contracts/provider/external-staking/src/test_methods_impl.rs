@@ -19,8 +19,8 @@ impl TestMethods for ExternalStakingContract<'_> {
     fn test_commit_stake(&self, ctx: ExecCtx, tx_id: u64) -> Result<Response, ContractError> {
         #[cfg(any(feature = "mt", test))]
         {
-            let msg = self.commit_stake(ctx.deps, tx_id)?;
-            Ok(Response::new().add_message(msg))
+            let msgs = self.commit_stake(ctx.deps, tx_id, None)?;
+            Ok(Response::new().add_submessages(msgs))
         }
         #[cfg(not(any(feature = "mt", test)))]
         {
@@ -34,8 +34,8 @@ impl TestMethods for ExternalStakingContract<'_> {
     fn test_rollback_stake(&self, ctx: ExecCtx, tx_id: u64) -> Result<Response, ContractError> {
         #[cfg(any(test, feature = "mt"))]
         {
-            let msg = self.rollback_stake(ctx.deps, tx_id)?;
-            Ok(Response::new().add_message(msg))
+            let msgs = self.rollback_stake(ctx.deps, tx_id)?;
+            Ok(Response::new().add_submessages(msgs))
         }
         #[cfg(not(any(test, feature = "mt")))]
         {
@@ -58,6 +58,8 @@ impl TestMethods for ExternalStakingContract<'_> {
             let AddValidator { valoper, pub_key } = validator;
             self.val_set
                 .add_validator(ctx.deps.storage, &valoper, &pub_key, height, time)?;
+            // Packets are not sent in tests
+            let _ = self.release_queued_stakes(ctx.deps.storage, &ctx.env, &valoper)?;
             Ok(Response::new())
         }
         #[cfg(not(any(feature = "mt", test)))]
@@ -89,7 +91,49 @@ impl TestMethods for ExternalStakingContract<'_> {
         }
     }
 
-    /// Sets validator as `unbonded`.
+    /// Sets validator as `jailed`.
+    #[sv::msg(exec)]
+    fn test_jail_validator(
+        &self,
+        ctx: ExecCtx,
+        valoper: String,
+        height: u64,
+        time: u64,
+    ) -> Result<Response, ContractError> {
+        #[cfg(any(feature = "mt", test))]
+        {
+            self.val_set
+                .jail_validator(ctx.deps.storage, &valoper, height, time)?;
+            Ok(Response::new())
+        }
+        #[cfg(not(any(feature = "mt", test)))]
+        {
+            let _ = (ctx, valoper, height, time);
+            Err(ContractError::Unauthorized {})
+        }
+    }
+
+    /// Reports validator as unjailed.
+    #[sv::msg(exec)]
+    fn test_unjail_validator(
+        &self,
+        ctx: ExecCtx,
+        valoper: String,
+    ) -> Result<Response, ContractError> {
+        #[cfg(any(feature = "mt", test))]
+        {
+            // Packets are not sent in tests
+            let _ = self.release_unjailed_stakes(ctx.deps.storage, &ctx.env, &valoper)?;
+            Ok(Response::new())
+        }
+        #[cfg(not(any(feature = "mt", test)))]
+        {
+            let _ = (ctx, valoper);
+            Err(ContractError::Unauthorized {})
+        }
+    }
+
+    /// Sets validator as `tombstoned`.
     #[sv::msg(exec)]
     fn test_tombstone_validator(
         &self,
@@ -102,7 +146,8 @@ impl TestMethods for ExternalStakingContract<'_> {
         {
             self.val_set
                 .tombstone_validator(ctx.deps.storage, &valoper, height, time)?;
-            Ok(Response::new())
+            let msgs = self.rollback_queued_stakes(ctx.deps, &valoper)?;
+            Ok(Response::new().add_messages(msgs))
         }
         #[cfg(not(any(feature = "mt", test)))]
         {
@@ -116,8 +161,8 @@ impl TestMethods for ExternalStakingContract<'_> {
     fn test_commit_unstake(&self, ctx: ExecCtx, tx_id: u64) -> Result<Response, ContractError> {
         #[cfg(any(test, feature = "mt"))]
         {
-            self.commit_unstake(ctx.deps, ctx.env, tx_id)?;
-            Ok(Response::new())
+            let (msg, evt) = self.commit_unstake(ctx.deps, ctx.env, tx_id)?;
+            Ok(Response::new().add_submessages(msg).add_event(evt))
         }
         #[cfg(not(any(test, feature = "mt")))]
         {
@@ -131,7 +176,45 @@ impl TestMethods for ExternalStakingContract<'_> {
     fn test_rollback_unstake(&self, ctx: ExecCtx, tx_id: u64) -> Result<Response, ContractError> {
         #[cfg(any(test, feature = "mt"))]
         {
-            self.rollback_unstake(ctx.deps, tx_id)?;
+            let msg = self.rollback_unstake(ctx.deps, tx_id)?;
+            Ok(Response::new().add_submessages(msg))
+        }
+        #[cfg(not(any(test, feature = "mt")))]
+        {
+            let _ = (ctx, tx_id);
+            Err(ContractError::Unauthorized {})
+        }
+    }
+
+    /// Commits a pending unbond cancellation.
+    #[sv::msg(exec)]
+    fn test_commit_cancel_unbond(
+        &self,
+        ctx: ExecCtx,
+        tx_id: u64,
+    ) -> Result<Response, ContractError> {
+        #[cfg(any(test, feature = "mt"))]
+        {
+            self.commit_cancel_unbond(ctx.deps, tx_id)?;
+            Ok(Response::new())
+        }
+        #[cfg(not(any(test, feature = "mt")))]
+        {
+            let _ = (ctx, tx_id);
+            Err(ContractError::Unauthorized {})
+        }
+    }
+
+    /// Rollbacks a pending unbond cancellation.
+    #[sv::msg(exec)]
+    fn test_rollback_cancel_unbond(
+        &self,
+        ctx: ExecCtx,
+        tx_id: u64,
+    ) -> Result<Response, ContractError> {
+        #[cfg(any(test, feature = "mt"))]
+        {
+            self.rollback_cancel_unbond(ctx.deps, tx_id)?;
             Ok(Response::new())
         }
         #[cfg(not(any(test, feature = "mt")))]
@@ -230,12 +313,13 @@ impl TestMethods for ExternalStakingContract<'_> {
         #[cfg(any(test, feature = "mt"))]
         {
             let cfg = self.config.load(ctx.deps.storage)?;
+            let slash_ratio = self.effective_slash_ratio(ctx.deps.storage)?;
             let slash_msg = self.handle_slashing(
                 &ctx.env,
                 ctx.deps.storage,
                 &cfg,
                 &validator,
-                cfg.slash_ratio.double_sign, // TODO: Add slash ratio parameter
+                slash_ratio.double_sign, // TODO: Add slash ratio parameter
                 slash_amount,
                 0, // TODO: Add infraction time parameter
             )?;
@@ -0,0 +1,93 @@
+use cosmwasm_std::{Addr, Order, StdResult, Storage};
+use cw_storage_plus::{Index, IndexList, IndexedMap, Item, MultiIndex};
+use mesh_sync::Tx;
+
+use crate::msg::PendingTxCounters;
+
+pub struct TxIndexes<'a> {
+    // Last type param defines the pk deserialization type
+    pub users: MultiIndex<'a, Addr, Tx, u64>,
+}
+
+impl<'a> IndexList<Tx> for TxIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Tx>> + '_> {
+        let v: Vec<&dyn Index<Tx>> = vec![&self.users];
+        Box::new(v.into_iter())
+    }
+}
+
+/// Pending txs, indexed by the user they were made for
+pub struct Txs<'a> {
+    pub txs: IndexedMap<'a, u64, Tx, TxIndexes<'a>>,
+    /// Running count of the pending txs, by kind
+    pub counters: Item<'a, PendingTxCounters>,
+}
+
+impl<'a> Txs<'a> {
+    pub fn new(storage_key: &'a str, user_subkey: &'a str, counters_key: &'a str) -> Self {
+        let indexes = TxIndexes {
+            users: MultiIndex::new(|_, tx| tx.user().clone(), storage_key, user_subkey),
+        };
+        let txs = IndexedMap::new(storage_key, indexes);
+
+        Self {
+            txs,
+            counters: Item::new(counters_key),
+        }
+    }
+
+    /// Saves a tx, keeping the counters in sync
+    pub fn save(&self, storage: &mut dyn Storage, tx_id: u64, tx: &Tx) -> StdResult<()> {
+        let old = self.txs.may_load(storage, tx_id)?;
+        self.update_counters(storage, old.as_ref(), Some(tx))?;
+        self.txs.save(storage, tx_id, tx)
+    }
+
+    /// Removes a tx, keeping the counters in sync
+    pub fn remove(&self, storage: &mut dyn Storage, tx_id: u64) -> StdResult<()> {
+        let old = self.txs.may_load(storage, tx_id)?;
+        self.update_counters(storage, old.as_ref(), None)?;
+        self.txs.remove(storage, tx_id)
+    }
+
+    /// Recomputes the counters from the stored txs, for txs pending before they were tracked
+    pub fn seed_counters(&self, storage: &mut dyn Storage) -> StdResult<()> {
+        let mut counters = PendingTxCounters::default();
+        for item in self.txs.range(storage, None, None, Order::Ascending) {
+            if let Some(counter) = Self::counter(&mut counters, &item?.1) {
+                *counter += 1;
+            }
+        }
+        self.counters.save(storage, &counters)
+    }
+
+    fn update_counters(
+        &self,
+        storage: &mut dyn Storage,
+        old: Option<&Tx>,
+        new: Option<&Tx>,
+    ) -> StdResult<()> {
+        let mut counters = self.counters.may_load(storage)?.unwrap_or_default();
+        if let Some(counter) = old.and_then(|tx| Self::counter(&mut counters, tx)) {
+            // Saturating, as txs pending before the counters were tracked are not accounted
+            *counter = counter.saturating_sub(1);
+        }
+        if let Some(counter) = new.and_then(|tx| Self::counter(&mut counters, tx)) {
+            *counter += 1;
+        }
+        self.counters.save(storage, &counters)
+    }
+
+    /// The counter of the given tx kind. Vault txs are not counted
+    fn counter<'c>(counters: &'c mut PendingTxCounters, tx: &Tx) -> Option<&'c mut u64> {
+        match tx {
+            Tx::InFlightRemoteStaking { .. } => Some(&mut counters.stake),
+            Tx::InFlightRemoteUnstaking { .. } => Some(&mut counters.unstake),
+            Tx::InFlightRemoteRestaking { .. } => Some(&mut counters.cancel_unbond),
+            Tx::InFlightTransferFunds { .. } | Tx::InFlightTransferAllFunds { .. } => {
+                Some(&mut counters.transfer_rewards)
+            }
+            Tx::InFlightStaking { .. } => None,
+        }
+    }
+}
@@ -16,6 +16,12 @@ pub enum ContractError {
     #[error("Try to send wrong denom: {0}")]
     InvalidDenom(String),
 
+    #[error("Weighted vote options must be distinct, and their weights must add up to one")]
+    InvalidVoteWeights,
+
+    #[error("Cannot restake to the same validator {0}")]
+    SameValidator(String),
+
     #[error("Validator {0} has not enough delegated funds: {1}")]
     InsufficientDelegation(String, Uint128),
 
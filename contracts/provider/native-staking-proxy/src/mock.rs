@@ -1,17 +1,17 @@
 use cosmwasm_std::WasmMsg::Execute;
 use cosmwasm_std::{
-    coin, ensure_eq, to_json_binary, Coin, DistributionMsg, GovMsg, Response, StakingMsg,
-    VoteOption, WeightedVoteOption,
+    coin, ensure, ensure_eq, to_json_binary, BankMsg, Coin, Coins, DistributionMsg, GovMsg,
+    Response, StakingMsg, Uint128, VoteOption, WeightedVoteOption,
 };
 use cw2::set_contract_version;
 use cw_storage_plus::Item;
 
 use cw_utils::{must_pay, nonpayable};
-use sylvia::types::{ExecCtx, InstantiateCtx, QueryCtx};
+use sylvia::types::{ExecCtx, InstantiateCtx, MigrateCtx, QueryCtx};
 use sylvia::{contract, schemars};
 
 use crate::error::ContractError;
-use crate::msg::{ConfigResponse, OwnerMsg};
+use crate::msg::{cancel_unbonding_msg, validate_vote_weights, ConfigResponse, OwnerMsg};
 use crate::native_staking_callback;
 use crate::state::Config;
 
@@ -23,6 +23,12 @@ pub struct NativeStakingProxyMock<'a> {
     burned: Item<'a, u128>,
 }
 
+impl Default for NativeStakingProxyMock<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[contract]
 #[sv::error(ContractError)]
 impl NativeStakingProxyMock<'_> {
@@ -72,6 +78,14 @@ impl NativeStakingProxyMock<'_> {
         Ok(res.add_message(set_withdrawal).set_data(owner_msg))
     }
 
+    /// Migrations are sent by the native-staking contract (the proxy admin) when its proxy code id
+    /// is updated
+    #[sv::msg(migrate)]
+    pub fn migrate(&self, ctx: MigrateCtx) -> Result<Response, ContractError> {
+        set_contract_version(ctx.deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+        Ok(Response::new())
+    }
+
     /// Stakes the tokens from `info.funds` to the given validator.
     /// Can only be called by the parent contract
     #[sv::msg(exec)]
@@ -177,6 +191,8 @@ impl NativeStakingProxyMock<'_> {
     }
 
     /// Re-stakes the given amount from the one validator to another on behalf of the calling user.
+    /// The lien in the vault is kept as is. The parent is told about the new validator via
+    /// `register_delegation`, so that its slashes reach this stake.
     /// Returns an error if the user doesn't have such stake
     #[sv::msg(exec)]
     fn restake(
@@ -197,6 +213,53 @@ impl NativeStakingProxyMock<'_> {
             ContractError::InvalidDenom(amount.denom)
         );
 
+        ensure!(
+            src_validator != dst_validator,
+            ContractError::SameValidator(src_validator)
+        );
+
+        let msg = StakingMsg::Redelegate {
+            src_validator,
+            dst_validator: dst_validator.clone(),
+            amount,
+        };
+        let register_msg = Execute {
+            contract_addr: cfg.parent.to_string(),
+            msg: to_json_binary(&native_staking_callback::sv::ExecMsg::RegisterDelegation {
+                validator: dst_validator,
+            })?,
+            funds: vec![],
+        };
+        Ok(Response::new().add_message(msg).add_message(register_msg))
+    }
+
+    /// Re-stakes the given amount from one validator to another on behalf of the parent, e.g.
+    /// away from a tombstoned validator. The parent keeps track of the new validator itself.
+    /// Can only be called by the parent contract
+    #[sv::msg(exec)]
+    fn redelegate(
+        &self,
+        ctx: ExecCtx,
+        src_validator: String,
+        dst_validator: String,
+        amount: Coin,
+    ) -> Result<Response, ContractError> {
+        let cfg = self.config.load(ctx.deps.storage)?;
+        ensure_eq!(cfg.parent, ctx.info.sender, ContractError::Unauthorized {});
+
+        nonpayable(&ctx.info)?;
+
+        ensure_eq!(
+            amount.denom,
+            cfg.denom,
+            ContractError::InvalidDenom(amount.denom)
+        );
+
+        ensure!(
+            src_validator != dst_validator,
+            ContractError::SameValidator(src_validator)
+        );
+
         let msg = StakingMsg::Redelegate {
             src_validator,
             dst_validator,
@@ -234,6 +297,7 @@ impl NativeStakingProxyMock<'_> {
         ensure_eq!(cfg.owner, ctx.info.sender, ContractError::Unauthorized {});
 
         nonpayable(&ctx.info)?;
+        validate_vote_weights(&vote)?;
 
         let msg = GovMsg::VoteWeighted {
             proposal_id,
@@ -244,28 +308,152 @@ impl NativeStakingProxyMock<'_> {
 
     /// If the caller has any delegations, withdraw all rewards from those delegations and
     /// send the tokens to the caller.
+    /// If `validators` is provided, only rewards from those validators are withdrawn. This allows
+    /// claiming in bounded-gas calls when delegating to many validators.
+    /// If `recipient` is provided, the rewards are sent there instead, e.g. to a cold wallet or a
+    /// DAO treasury.
     /// NOTE: must make sure not to release unbonded tokens
     #[sv::msg(exec)]
-    fn withdraw_rewards(&self, ctx: ExecCtx) -> Result<Response, ContractError> {
+    fn withdraw_rewards(
+        &self,
+        ctx: ExecCtx,
+        validators: Option<Vec<String>>,
+        recipient: Option<String>,
+    ) -> Result<Response, ContractError> {
         let cfg = self.config.load(ctx.deps.storage)?;
         ensure_eq!(cfg.owner, ctx.info.sender, ContractError::Unauthorized {});
 
         nonpayable(&ctx.info)?;
 
-        // Withdraw all delegations to the owner (already set as withdrawal address in instantiate)
-        let msgs: Vec<_> = ctx
-            .deps
-            .querier
-            .query_all_delegations(ctx.env.contract.address)?
+        let validators = match validators {
+            Some(validators) => validators,
+            None => ctx
+                .deps
+                .querier
+                .query_all_delegations(ctx.env.contract.address)?
+                .into_iter()
+                .map(|delegation| delegation.validator)
+                .collect(),
+        };
+        // Withdraw from delegations to the owner (already set as withdrawal address in instantiate)
+        let msgs: Vec<_> = validators
             .into_iter()
-            .map(|delegation| DistributionMsg::WithdrawDelegatorReward {
-                validator: delegation.validator,
-            })
+            .map(|validator| DistributionMsg::WithdrawDelegatorReward { validator })
             .collect();
-        let res = Response::new().add_messages(msgs);
+        let res = match recipient {
+            Some(recipient) => {
+                // Withdraw to the recipient, and restore the owner as withdrawal address afterwards
+                let recipient = ctx.deps.api.addr_validate(&recipient)?;
+                Response::new()
+                    .add_message(DistributionMsg::SetWithdrawAddress {
+                        address: recipient.to_string(),
+                    })
+                    .add_messages(msgs)
+                    .add_message(DistributionMsg::SetWithdrawAddress {
+                        address: cfg.owner.to_string(),
+                    })
+                    .add_attribute("recipient", recipient)
+            }
+            None => Response::new().add_messages(msgs),
+        };
         Ok(res)
     }
 
+    /// Withdraws the rewards of the given delegations (all of them if `validators` is not set),
+    /// and re-delegates them to the same validators.
+    /// The compounded stake is reported to the parent via `compound_proxy_stake`, so that the
+    /// vault adds it to the owner's collateral. Rewards in other denoms are sent to the owner
+    #[sv::msg(exec)]
+    fn compound(
+        &self,
+        ctx: ExecCtx,
+        validators: Option<Vec<String>>,
+    ) -> Result<Response, ContractError> {
+        let cfg = self.config.load(ctx.deps.storage)?;
+        ensure_eq!(cfg.owner, ctx.info.sender, ContractError::Unauthorized {});
+
+        nonpayable(&ctx.info)?;
+
+        let validators = match validators {
+            Some(validators) => validators,
+            None => ctx
+                .deps
+                .querier
+                .query_all_delegations(&ctx.env.contract.address)?
+                .into_iter()
+                .map(|delegation| delegation.validator)
+                .collect(),
+        };
+
+        let mut withdrawals = vec![];
+        let mut delegations = vec![];
+        let mut other_rewards = Coins::default();
+        let mut compounded = Uint128::zero();
+        for validator in validators {
+            let Some(delegation) = ctx
+                .deps
+                .querier
+                .query_delegation(&ctx.env.contract.address, &validator)?
+            else {
+                continue;
+            };
+            let rewards: Vec<_> = delegation
+                .accumulated_rewards
+                .into_iter()
+                .filter(|reward| !reward.amount.is_zero())
+                .collect();
+            if rewards.is_empty() {
+                continue;
+            }
+            for reward in rewards {
+                if reward.denom == cfg.denom {
+                    compounded += reward.amount;
+                    delegations.push(StakingMsg::Delegate {
+                        validator: validator.clone(),
+                        amount: reward,
+                    });
+                } else {
+                    other_rewards.add(reward)?;
+                }
+            }
+            withdrawals.push(DistributionMsg::WithdrawDelegatorReward { validator });
+        }
+
+        // Short circuit if there are no rewards to compound
+        if withdrawals.is_empty() {
+            return Ok(Response::new());
+        }
+
+        // Withdraw to the proxy itself, and restore the owner as withdrawal address afterwards
+        let mut res = Response::new()
+            .add_message(DistributionMsg::SetWithdrawAddress {
+                address: ctx.env.contract.address.to_string(),
+            })
+            .add_messages(withdrawals)
+            .add_message(DistributionMsg::SetWithdrawAddress {
+                address: cfg.owner.to_string(),
+            })
+            .add_messages(delegations);
+        if !other_rewards.is_empty() {
+            res = res.add_message(BankMsg::Send {
+                to_address: cfg.owner.to_string(),
+                amount: other_rewards.into_vec(),
+            });
+        }
+        if !compounded.is_zero() {
+            let msg = to_json_binary(&native_staking_callback::sv::ExecMsg::CompoundProxyStake {
+                amount: coin(compounded.u128(), &cfg.denom),
+            })?;
+            res = res.add_message(Execute {
+                contract_addr: cfg.parent.to_string(),
+                msg,
+                funds: vec![],
+            });
+        }
+
+        Ok(res.add_attribute("compounded", compounded.to_string()))
+    }
+
     /// Unstakes the given amount from the given validator on behalf of the calling user.
     /// Returns an error if the user doesn't have such stake.
     /// After the unbonding period, it will allow the user to claim the tokens (returning to vault)
@@ -291,13 +479,61 @@ impl NativeStakingProxyMock<'_> {
         Ok(Response::new().add_message(msg))
     }
 
+    /// Cancels the unbonding of `amount` from `validator` started at `creation_height`, delegating
+    /// it back to `validator`. The lien in the vault is kept as is, as unbonding tokens are only
+    /// released from it once returned. The parent is told about the delegation via
+    /// `register_delegation`, so that its slashes reach this stake again.
+    /// Requires Cosmos SDK 0.46 or later
+    #[sv::msg(exec)]
+    fn cancel_unbonding(
+        &self,
+        ctx: ExecCtx,
+        validator: String,
+        amount: Coin,
+        creation_height: u64,
+    ) -> Result<Response, ContractError> {
+        let cfg = self.config.load(ctx.deps.storage)?;
+        ensure_eq!(cfg.owner, ctx.info.sender, ContractError::Unauthorized {});
+
+        nonpayable(&ctx.info)?;
+
+        ensure_eq!(
+            amount.denom,
+            cfg.denom,
+            ContractError::InvalidDenom(amount.denom)
+        );
+
+        let msg = cancel_unbonding_msg(
+            ctx.env.contract.address.as_str(),
+            &validator,
+            amount,
+            creation_height,
+        );
+        let register_msg = Execute {
+            contract_addr: cfg.parent.to_string(),
+            msg: to_json_binary(&native_staking_callback::sv::ExecMsg::RegisterDelegation {
+                validator,
+            })?,
+            funds: vec![],
+        };
+        Ok(Response::new().add_message(msg).add_message(register_msg))
+    }
+
     /// Releases any tokens that have fully unbonded from a previous unstake.
     /// This will go back to the parent via `release_proxy_stake`.
+    /// Can be called by the owner, or by the parent on the owner's behalf.
     /// Errors if the proxy doesn't have any liquid tokens
     #[sv::msg(exec)]
     fn release_unbonded(&self, ctx: ExecCtx) -> Result<Response, ContractError> {
         let cfg = self.config.load(ctx.deps.storage)?;
-        ensure_eq!(cfg.owner, ctx.info.sender, ContractError::Unauthorized {});
+        // The parent releases on behalf of owners that opted into auto-renew, and this contract
+        // right after unstaking from a tombstoned validator
+        ensure!(
+            ctx.info.sender == cfg.owner
+                || ctx.info.sender == cfg.parent
+                || ctx.info.sender == ctx.env.contract.address,
+            ContractError::Unauthorized {}
+        );
 
         nonpayable(&ctx.info)?;
 
@@ -327,6 +563,48 @@ impl NativeStakingProxyMock<'_> {
         Ok(Response::new().add_message(wasm_msg))
     }
 
+    /// Unstakes the whole delegation to the tombstoned `validator`, and releases it right away
+    /// via `release_unbonded`. The chain unbonds delegations to tombstoned validators instantly,
+    /// so there is no unbonding period to wait for.
+    /// Can only be called by the parent, which checks that `validator` was tombstoned
+    #[sv::msg(exec)]
+    fn release_tombstoned(
+        &self,
+        ctx: ExecCtx,
+        validator: String,
+    ) -> Result<Response, ContractError> {
+        let cfg = self.config.load(ctx.deps.storage)?;
+        ensure_eq!(cfg.parent, ctx.info.sender, ContractError::Unauthorized {});
+
+        nonpayable(&ctx.info)?;
+
+        let delegation = ctx
+            .deps
+            .querier
+            .query_delegation(&ctx.env.contract.address, &validator)?
+            .map(|full_delegation| full_delegation.amount)
+            .unwrap_or_else(|| coin(0, &cfg.denom));
+        if delegation.amount.is_zero() {
+            return Ok(Response::new());
+        }
+
+        let msg = StakingMsg::Undelegate {
+            validator: validator.clone(),
+            amount: delegation.clone(),
+        };
+        let release_msg = Execute {
+            contract_addr: ctx.env.contract.address.to_string(),
+            msg: to_json_binary(&sv::ExecMsg::ReleaseUnbonded {})?,
+            funds: vec![],
+        };
+        Ok(Response::new()
+            .add_message(msg)
+            .add_message(release_msg)
+            .add_attribute("action", "release_tombstoned")
+            .add_attribute("validator", validator)
+            .add_attribute("amount", delegation.amount))
+    }
+
     #[sv::msg(query)]
     fn config(&self, ctx: QueryCtx) -> Result<ConfigResponse, ContractError> {
         Ok(self.config.load(ctx.deps.storage)?)
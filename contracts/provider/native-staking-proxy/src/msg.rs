@@ -1,5 +1,8 @@
+use crate::error::ContractError;
 use crate::state::Config;
 use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Binary, Coin, CosmosMsg, Decimal, WeightedVoteOption};
+use prost::Message;
 
 pub type ConfigResponse = Config;
 
@@ -8,3 +11,67 @@ pub type ConfigResponse = Config;
 pub struct OwnerMsg {
     pub owner: String,
 }
+
+/// Checks weighted vote options are distinct, and their weights add up to one
+pub fn validate_vote_weights(options: &[WeightedVoteOption]) -> Result<(), ContractError> {
+    let distinct = options
+        .iter()
+        .enumerate()
+        .all(|(i, opt)| options[..i].iter().all(|prev| prev.option != opt.option));
+    let total = options
+        .iter()
+        .try_fold(Decimal::zero(), |total, opt| total.checked_add(opt.weight));
+    if !distinct || total.map_or(true, |total| total != Decimal::one()) {
+        return Err(ContractError::InvalidVoteWeights);
+    }
+    Ok(())
+}
+
+pub const CANCEL_UNBONDING_TYPE_URL: &str = "/cosmos.staking.v1beta1.MsgCancelUnbondingDelegation";
+
+/// `cosmos.staking.v1beta1.MsgCancelUnbondingDelegation`, not covered by `StakingMsg`
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MsgCancelUnbondingDelegation {
+    #[prost(string, tag = "1")]
+    pub delegator_address: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub validator_address: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "3")]
+    pub amount: ::core::option::Option<ProtoCoin>,
+    #[prost(int64, tag = "4")]
+    pub creation_height: i64,
+}
+
+/// `cosmos.base.v1beta1.Coin`
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ProtoCoin {
+    #[prost(string, tag = "1")]
+    pub denom: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub amount: ::prost::alloc::string::String,
+}
+
+/// Cancels the unbonding of `amount` from `validator` started at `creation_height`, delegating it
+/// back to `validator`
+pub fn cancel_unbonding_msg<T>(
+    delegator: &str,
+    validator: &str,
+    amount: Coin,
+    creation_height: u64,
+) -> CosmosMsg<T> {
+    let msg = MsgCancelUnbondingDelegation {
+        delegator_address: delegator.to_owned(),
+        validator_address: validator.to_owned(),
+        amount: Some(ProtoCoin {
+            denom: amount.denom,
+            amount: amount.amount.to_string(),
+        }),
+        creation_height: creation_height as i64,
+    };
+    CosmosMsg::Stargate {
+        type_url: CANCEL_UNBONDING_TYPE_URL.to_owned(),
+        value: Binary(msg.encode_to_vec()),
+    }
+}
@@ -1,15 +1,19 @@
 use anyhow::Result as AnyResult;
 
 use cosmwasm_std::testing::mock_env;
-use cosmwasm_std::{coin, coins, to_json_binary, Addr, Decimal, Validator};
+use cosmwasm_std::{
+    coin, coins, to_json_binary, Addr, Decimal, Validator, VoteOption, WeightedVoteOption,
+};
 
 use cw_multi_test::{App as MtApp, StakingInfo};
 use sylvia::multitest::{App, Proxy};
 
+use mesh_native_staking::contract::NativeStakingContract;
 use mesh_vault::mock::sv::mt::VaultMockProxy;
 use mesh_vault::mock::VaultMock;
 use mesh_vault::msg::LocalStakingInfo;
 
+use crate::error::ContractError;
 use crate::mock::sv::mt::NativeStakingProxyMockProxy;
 use crate::mock::NativeStakingProxyMock;
 use crate::msg::ConfigResponse;
@@ -72,9 +76,11 @@ fn setup<'app>(
             proxy_code_id: staking_proxy_code.code_id(),
             slash_ratio_dsign: Decimal::percent(5),
             slash_ratio_offline: Decimal::percent(5),
+            deterministic_proxies: None,
         })
         .unwrap(),
         label: None,
+        salt: None,
     };
 
     // Instantiates vault and staking
@@ -89,7 +95,7 @@ fn setup<'app>(
 
     // Bond some funds to the vault
     vault
-        .bond()
+        .bond(None)
         .with_funds(&coins(200, OSMO))
         .call(user)
         .unwrap();
@@ -103,6 +109,7 @@ fn setup<'app>(
                     validator: validator.to_owned(),
                 })
                 .unwrap(),
+                None,
             )
             .call(user)
             .unwrap();
@@ -180,6 +187,7 @@ fn staking() {
                 validator: validator.to_owned(),
             })
             .unwrap(),
+            None,
         )
         .call(user)
         .unwrap();
@@ -205,6 +213,7 @@ fn staking() {
 fn restaking() {
     let owner = "vault_admin";
 
+    let staking_addr = "contract1"; // Second contract (instantiated by vault)
     let proxy_addr = "contract2"; // Third contract (instantiated by staking contract on stake)
 
     let user = "user1"; // One who wants to local stake (uses the proxy)
@@ -218,6 +227,13 @@ fn restaking() {
     let staking_proxy: Proxy<'_, MtApp, NativeStakingProxyMock<'_>> =
         Proxy::new(Addr::unchecked(proxy_addr), &app);
 
+    // Restaking to the same validator is rejected
+    let err = staking_proxy
+        .restake(validator.to_owned(), validator.to_owned(), coin(30, OSMO))
+        .call(user)
+        .unwrap_err();
+    assert_eq!(err, ContractError::SameValidator(validator.to_owned()));
+
     // Restake 30% to a different validator
     staking_proxy
         .restake(validator.to_owned(), validator2.to_owned(), coin(30, OSMO))
@@ -239,6 +255,80 @@ fn restaking() {
         .unwrap()
         .unwrap();
     assert_eq!(delegation2.amount, coin(30, OSMO));
+
+    // The parent routes slashes of the new validator to the user
+    let delegator = NativeStakingContract::new()
+        .delegators
+        .query(
+            &app.app().wrap(),
+            Addr::unchecked(staking_addr),
+            (validator2, &Addr::unchecked(user)),
+        )
+        .unwrap();
+    assert_eq!(delegator, Some(true));
+}
+
+#[test]
+fn voting() {
+    let owner = "vault_admin";
+
+    let proxy_addr = "contract2"; // Third contract (instantiated by staking contract on stake)
+
+    let user = "user1"; // One who wants to local stake (uses the proxy)
+    let validator = "validator1"; // Where to stake / unstake
+
+    let app = init_app(user, &[validator]); // Fund user, create validator
+    setup(&app, owner, user, &[validator]).unwrap();
+
+    // Access staking proxy instance
+    let staking_proxy: Proxy<'_, MtApp, NativeStakingProxyMock<'_>> =
+        Proxy::new(Addr::unchecked(proxy_addr), &app);
+
+    // Only the owner can vote
+    let err = staking_proxy
+        .vote(1, VoteOption::Yes)
+        .call(owner)
+        .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    let options = vec![
+        WeightedVoteOption {
+            option: VoteOption::Yes,
+            weight: Decimal::percent(70),
+        },
+        WeightedVoteOption {
+            option: VoteOption::Abstain,
+            weight: Decimal::percent(30),
+        },
+    ];
+    let err = staking_proxy
+        .vote_weighted(1, options.clone())
+        .call(owner)
+        .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    // Weights must add up to one
+    let err = staking_proxy
+        .vote_weighted(1, options[..1].to_vec())
+        .call(user)
+        .unwrap_err();
+    assert_eq!(err, ContractError::InvalidVoteWeights);
+
+    // Options must be distinct
+    let duplicated = vec![
+        WeightedVoteOption {
+            option: VoteOption::Yes,
+            weight: Decimal::percent(50),
+        },
+        WeightedVoteOption {
+            option: VoteOption::Yes,
+            weight: Decimal::percent(50),
+        },
+    ];
+    let err = staking_proxy
+        .vote_weighted(1, duplicated)
+        .call(user)
+        .unwrap_err();
+    assert_eq!(err, ContractError::InvalidVoteWeights);
 }
 
 #[test]
@@ -523,7 +613,10 @@ fn withdrawing_rewards() {
     });
 
     // Withdraw rewards
-    staking_proxy.withdraw_rewards().call(user).unwrap();
+    staking_proxy
+        .withdraw_rewards(None, None)
+        .call(user)
+        .unwrap();
 
     // User now has some rewards
     let current_funds = app.app().wrap().query_balance(user, OSMO).unwrap();
@@ -542,6 +635,177 @@ fn withdrawing_rewards() {
     assert_eq!(original_vault_funds, vault_funds);
 }
 
+#[test]
+fn withdrawing_rewards_from_validators() {
+    let owner = "vault_admin";
+
+    let proxy_addr = "contract2"; // Third contract (instantiated by staking contract on stake)
+
+    let user = "user1"; // One who wants to local stake (uses the proxy)
+    let validators = ["validator1", "validator2"]; // Where to stake / unstake
+
+    let app = init_app(user, &validators); // Fund user, create validators
+    setup(&app, owner, user, &validators).unwrap();
+
+    let original_user_funds = app.app().wrap().query_balance(user, OSMO).unwrap();
+
+    // Access staking proxy instance
+    let staking_proxy: Proxy<'_, MtApp, NativeStakingProxyMock<'_>> =
+        Proxy::new(Addr::unchecked(proxy_addr), &app);
+
+    // Advance time enough for rewards to accrue
+    app.update_block(|block| {
+        block.height += 12345678;
+        block.time = block.time.plus_seconds(123456789);
+    });
+
+    // Withdraw rewards from the first validator only
+    staking_proxy
+        .withdraw_rewards(Some(vec![validators[0].to_owned()]), None)
+        .call(user)
+        .unwrap();
+
+    // User now has some rewards
+    let first_funds = app.app().wrap().query_balance(user, OSMO).unwrap();
+    assert!(first_funds.amount > original_user_funds.amount);
+
+    // Rewards from the second validator are still pending
+    let delegation = app
+        .app()
+        .wrap()
+        .query_delegation(proxy_addr, validators[1])
+        .unwrap()
+        .unwrap();
+    assert!(!delegation.accumulated_rewards.is_empty());
+
+    // They can be withdrawn separately
+    staking_proxy
+        .withdraw_rewards(Some(vec![validators[1].to_owned()]), None)
+        .call(user)
+        .unwrap();
+    let second_funds = app.app().wrap().query_balance(user, OSMO).unwrap();
+    assert!(second_funds.amount > first_funds.amount);
+}
+
+#[test]
+fn withdrawing_rewards_to_recipient() {
+    let owner = "vault_admin";
+
+    let proxy_addr = "contract2"; // Third contract (instantiated by staking contract on stake)
+
+    let user = "user1"; // One who wants to local stake (uses the proxy)
+    let treasury = "treasury"; // Where the user wants their rewards to go
+    let validator = "validator1"; // Where to stake / unstake
+
+    let app = init_app(user, &[validator]); // Fund user, create validator
+    setup(&app, owner, user, &[validator]).unwrap();
+
+    let original_user_funds = app.app().wrap().query_balance(user, OSMO).unwrap();
+
+    // Access staking proxy instance
+    let staking_proxy: Proxy<'_, MtApp, NativeStakingProxyMock<'_>> =
+        Proxy::new(Addr::unchecked(proxy_addr), &app);
+
+    // Advance time enough for rewards to accrue
+    app.update_block(|block| {
+        block.height += 12345678;
+        block.time = block.time.plus_seconds(123456789);
+    });
+
+    staking_proxy
+        .withdraw_rewards(None, Some(treasury.to_owned()))
+        .call(user)
+        .unwrap();
+
+    // The recipient got the rewards, not the user
+    let treasury_funds = app.app().wrap().query_balance(treasury, OSMO).unwrap();
+    assert!(!treasury_funds.amount.is_zero());
+    let user_funds = app.app().wrap().query_balance(user, OSMO).unwrap();
+    assert_eq!(original_user_funds, user_funds);
+
+    // Later rewards go to the user again by default
+    app.update_block(|block| {
+        block.height += 12345678;
+        block.time = block.time.plus_seconds(123456789);
+    });
+    staking_proxy
+        .withdraw_rewards(None, None)
+        .call(user)
+        .unwrap();
+    let user_funds = app.app().wrap().query_balance(user, OSMO).unwrap();
+    assert!(user_funds.amount > original_user_funds.amount);
+    let new_treasury_funds = app.app().wrap().query_balance(treasury, OSMO).unwrap();
+    assert_eq!(treasury_funds, new_treasury_funds);
+}
+
+#[test]
+fn compounding() {
+    let owner = "vault_admin";
+
+    let proxy_addr = "contract2"; // Third contract (instantiated by staking contract on stake)
+
+    let user = "user1"; // One who wants to local stake (uses the proxy)
+    let validator = "validator1"; // Where to stake / unstake
+
+    let app = init_app(user, &[validator]); // Fund user, create validator
+    let vault = setup(&app, owner, user, &[validator]).unwrap();
+
+    let original_user_funds = app.app().wrap().query_balance(user, OSMO).unwrap();
+
+    // Access staking proxy instance
+    let staking_proxy: Proxy<'_, MtApp, NativeStakingProxyMock<'_>> =
+        Proxy::new(Addr::unchecked(proxy_addr), &app);
+
+    // Only the owner can compound
+    let err = staking_proxy.compound(None).call(owner).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    // Advance time enough for rewards to accrue
+    app.update_block(|block| {
+        block.height += 12345678;
+        block.time = block.time.plus_seconds(123456789);
+    });
+    let delegation = app
+        .app()
+        .wrap()
+        .query_delegation(proxy_addr, validator)
+        .unwrap()
+        .unwrap();
+    let rewards = delegation.accumulated_rewards[0].amount;
+    assert!(!rewards.is_zero());
+
+    staking_proxy.compound(None).call(user).unwrap();
+
+    // Rewards are re-delegated
+    let delegation = app
+        .app()
+        .wrap()
+        .query_delegation(proxy_addr, validator)
+        .unwrap()
+        .unwrap();
+    assert_eq!(delegation.amount.amount.u128(), 100 + rewards.u128());
+    assert!(delegation.accumulated_rewards.is_empty());
+
+    // User hasn't received any rewards
+    let user_funds = app.app().wrap().query_balance(user, OSMO).unwrap();
+    assert_eq!(original_user_funds, user_funds);
+
+    // The compounded stake is added to the user's collateral, and locked by native-staking
+    let account = vault.account(user.to_owned()).unwrap();
+    assert_eq!(account.bonded.u128(), 200 + rewards.u128());
+    assert_eq!(account.free.low().u128(), 100);
+
+    // Nothing is left to compound
+    staking_proxy.compound(None).call(user).unwrap();
+    let delegation = app
+        .app()
+        .wrap()
+        .query_delegation(proxy_addr, validator)
+        .unwrap()
+        .unwrap();
+    assert_eq!(delegation.amount.amount.u128(), 100 + rewards.u128());
+}
+
 fn process_staking_unbondings(app: &App<MtApp>) {
     // Advance unbonding period
     app.app_mut().update_block(|block| {
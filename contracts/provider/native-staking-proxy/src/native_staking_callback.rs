@@ -1,4 +1,4 @@
-use cosmwasm_std::{Response, StdError};
+use cosmwasm_std::{Coin, Response, StdError};
 use sylvia::types::ExecCtx;
 use sylvia::{interface, schemars};
 
@@ -12,4 +12,19 @@ pub trait NativeStakingCallback {
     /// The native-staking contract will then send those tokens back to vault and release the claim.
     #[sv::msg(exec)]
     fn release_proxy_stake(&self, _ctx: ExecCtx) -> Result<Response, Self::Error>;
+
+    /// This tells native-staking the proxy re-delegated `amount` of staking rewards.
+    /// The native-staking contract will then have the vault add it to the proxy owner's collateral
+    /// and lien.
+    #[sv::msg(exec)]
+    fn compound_proxy_stake(&self, _ctx: ExecCtx, amount: Coin) -> Result<Response, Self::Error>;
+
+    /// This tells native-staking the proxy now also delegates to `validator`, after a restake.
+    /// The native-staking contract uses it to route slashes of that validator to the proxy owner.
+    #[sv::msg(exec)]
+    fn register_delegation(
+        &self,
+        _ctx: ExecCtx,
+        validator: String,
+    ) -> Result<Response, Self::Error>;
 }
@@ -1,26 +1,75 @@
 use cosmwasm_std::Order::Ascending;
 use cosmwasm_std::{
-    from_json, Addr, Decimal, DepsMut, Event, Reply, Response, StdResult, SubMsgResponse, WasmMsg,
+    coin, ensure, from_json, instantiate2_address, to_json_binary, to_json_vec, Addr, Api, Binary,
+    CodeInfoResponse, ContractResult, Decimal, Deps, DepsMut, Empty, Env, Event, QueryRequest,
+    Reply, Response, StdError, StdResult, SubMsg, SubMsgResponse, SubMsgResult, SystemResult,
+    Uint128, WasmMsg,
 };
 use cw2::set_contract_version;
-use cw_storage_plus::{Item, Map};
-use cw_utils::parse_instantiate_response_data;
+use cw_storage_plus::{Bound, Item, Map};
+use cw_utils::{nonpayable, parse_instantiate_response_data};
+use prost::Message;
 use sylvia::types::{ExecCtx, InstantiateCtx, QueryCtx, ReplyCtx, SudoCtx};
 use sylvia::{contract, schemars};
 
 use mesh_apis::local_staking_api;
-use mesh_apis::vault_api::{SlashInfo, VaultApiHelper};
+use mesh_apis::vault_api::{AutoRenewResponse, LienAmountResponse, SlashInfo, VaultApiHelper};
 use mesh_native_staking_proxy::msg::OwnerMsg;
 use mesh_native_staking_proxy::native_staking_callback;
 
 use crate::error::ContractError;
-use crate::msg::{ConfigResponse, OwnerByProxyResponse, ProxyByOwnerResponse};
-use crate::state::Config;
+use crate::msg::{
+    decode_slash_ratios, decode_unbonding_balances, AllProxiesResponse, AutoRedelegationResponse,
+    ConfigResponse, OwnerByProxyResponse, PredictedProxyResponse, ProxyByOwnerResponse,
+    ProxyCodeIdsResponse, ProxyCodeInfo, ProxyInfo, QueryDelegatorUnbondingsRequest,
+    DELEGATOR_UNBONDINGS_QUERY_PATH, SLASHING_PARAMS_QUERY_PATH,
+};
+use crate::state::{AutoRedelegation, Config, SlashRecord};
 
 pub const CONTRACT_NAME: &str = env!("CARGO_PKG_NAME");
 pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 pub const REPLY_ID_INSTANTIATE: u64 = 2;
+pub const REPLY_ID_REDELEGATE: u64 = 3;
+
+pub const DEFAULT_PAGE_LIMIT: u32 = 10;
+pub const MAX_PAGE_LIMIT: u32 = 30;
+
+/// Aligns pagination limit
+fn clamp_page_limit(limit: Option<u32>) -> usize {
+    limit.unwrap_or(DEFAULT_PAGE_LIMIT).min(MAX_PAGE_LIMIT) as usize
+}
+
+/// Runs a stargate query, returning the protobuf encoded response
+fn stargate_query(deps: Deps, path: &str, data: Binary) -> Result<Binary, ContractError> {
+    let request = QueryRequest::<Empty>::Stargate {
+        path: path.to_owned(),
+        data,
+    };
+    match deps.querier.raw_query(&to_json_vec(&request)?) {
+        SystemResult::Ok(ContractResult::Ok(data)) => Ok(data),
+        SystemResult::Ok(ContractResult::Err(err)) => {
+            Err(StdError::generic_err(format!("Query {path}: {err}")).into())
+        }
+        SystemResult::Err(err) => Err(StdError::generic_err(format!("Query {path}: {err}")).into()),
+    }
+}
+
+/// Salt of the proxy of `owner`, when instantiated with `instantiate2`.
+/// There is at most one proxy per owner, so the owner is a unique salt
+/// Message releasing the matured unbondings held by `proxy` back into the vault
+pub(crate) fn release_unbonded_msg(proxy: &Addr) -> StdResult<WasmMsg> {
+    let msg = WasmMsg::Execute {
+        contract_addr: proxy.to_string(),
+        msg: to_json_binary(&mesh_native_staking_proxy::contract::sv::ExecMsg::ReleaseUnbonded {})?,
+        funds: vec![],
+    };
+    Ok(msg)
+}
+
+pub(crate) fn proxy_salt(api: &dyn Api, owner: &Addr) -> StdResult<Binary> {
+    Ok(api.addr_canonicalize(owner.as_str())?.into())
+}
 
 pub struct NativeStakingContract<'a> {
     pub config: Item<'a, Config>,
@@ -31,6 +80,20 @@ pub struct NativeStakingContract<'a> {
     /// Map of delegators per validator
     // This is used for prefixing and ranging during slashing
     pub delegators: Map<'a, (&'a str, &'a Addr), bool>,
+    /// Slashes waiting to be reconciled with the actual delegations, by `(validator, owner)`
+    pub slash_records: Map<'a, (&'a str, &'a Addr), SlashRecord>,
+    /// Auto-redelegation policy applying to owners without their own
+    pub default_auto_redelegation: Item<'a, AutoRedelegation>,
+    /// Auto-redelegation policy by owner address
+    pub auto_redelegation: Map<'a, &'a Addr, AutoRedelegation>,
+    /// Validators reported as tombstoned
+    pub tombstoned: Map<'a, &'a str, ()>,
+}
+
+impl Default for NativeStakingContract<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 pub(crate) enum SlashingReason {
@@ -50,6 +113,10 @@ impl NativeStakingContract<'_> {
             proxy_by_owner: Map::new("proxies"),
             owner_by_proxy: Map::new("owners"),
             delegators: Map::new("delegators"),
+            slash_records: Map::new("slash_records"),
+            default_auto_redelegation: Item::new("default_auto_redelegation"),
+            auto_redelegation: Map::new("auto_redelegation"),
+            tombstoned: Map::new("tombstoned"),
         }
     }
 
@@ -62,6 +129,7 @@ impl NativeStakingContract<'_> {
         proxy_code_id: u64,
         slash_ratio_dsign: Decimal,
         slash_ratio_offline: Decimal,
+        deterministic_proxies: Option<bool>,
     ) -> Result<Response, ContractError> {
         if slash_ratio_dsign > Decimal::one() || slash_ratio_offline > Decimal::one() {
             return Err(ContractError::InvalidSlashRatio);
@@ -73,6 +141,7 @@ impl NativeStakingContract<'_> {
             vault: VaultApiHelper(ctx.info.sender),
             slash_ratio_dsign,
             slash_ratio_offline,
+            deterministic_proxies: deterministic_proxies.unwrap_or_default(),
         };
         self.config.save(ctx.deps.storage, &config)?;
         set_contract_version(ctx.deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
@@ -93,13 +162,17 @@ impl NativeStakingContract<'_> {
 
         let cfg = self.config.load(deps.storage)?;
         let mut msgs = vec![];
+        let mut redelegations = vec![];
         for validator in tombstoned {
+            self.tombstoned.save(deps.storage, validator, &())?;
             // Slash the validator (if bonded)
             let slash_msg =
                 self.handle_slashing(&mut deps, &cfg, validator, SlashingReason::DoubleSign)?;
             if let Some(msg) = slash_msg {
                 msgs.push(msg)
             }
+            // Move what is left of the stake of opted-in owners
+            redelegations.extend(self.handle_redelegation(&mut deps, &cfg, validator)?);
         }
         for validator in jailed {
             // Slash the validator (if bonded)
@@ -116,7 +189,10 @@ impl NativeStakingContract<'_> {
         if !tombstoned.is_empty() {
             evt = evt.add_attribute("tombstoned", tombstoned.join(","));
         }
-        Ok(Response::new().add_event(evt).add_messages(msgs))
+        Ok(Response::new()
+            .add_event(evt)
+            .add_messages(msgs)
+            .add_submessages(redelegations))
     }
 
     pub(crate) fn handle_slashing(
@@ -159,6 +235,22 @@ impl NativeStakingContract<'_> {
             }
 
             let slash_amount = delegation * slash_ratio;
+            // Keep track of it, so that it can be reconciled with the actual slash
+            let record = match self
+                .slash_records
+                .may_load(deps.storage, (validator, owner))?
+            {
+                Some(record) => SlashRecord {
+                    slashed: record.slashed + slash_amount,
+                    ..record
+                },
+                None => SlashRecord {
+                    delegation,
+                    slashed: slash_amount,
+                },
+            };
+            self.slash_records
+                .save(deps.storage, (validator, owner), &record)?;
 
             slash_infos.push(SlashInfo {
                 user: owner.to_string(),
@@ -175,6 +267,552 @@ impl NativeStakingContract<'_> {
         Ok(Some(msg))
     }
 
+    /// Redelegates the stake left on a tombstoned `validator` by owners with auto-redelegation
+    /// enabled, to the first active validator of their list.
+    /// Failed redelegations (e.g. transitive ones) are ignored, so they don't block slashing.
+    fn handle_redelegation(
+        &self,
+        deps: &mut DepsMut,
+        config: &Config,
+        validator: &str,
+    ) -> Result<Vec<SubMsg>, ContractError> {
+        let owners = self
+            .delegators
+            .prefix(validator)
+            .keys(deps.storage, None, None, Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+
+        let mut msgs = vec![];
+        for owner in &owners {
+            let policy = self.effective_auto_redelegation(deps.as_ref(), owner)?;
+            if !policy.enabled {
+                continue;
+            }
+            let Some(dst_validator) =
+                self.select_fallback_validator(deps.as_ref(), &policy.validators, validator)?
+            else {
+                continue;
+            };
+
+            let proxy = self.proxy_by_owner.load(deps.storage, owner)?;
+            let delegation = deps
+                .querier
+                .query_delegation(&proxy, validator)?
+                .map(|full_delegation| full_delegation.amount.amount)
+                .unwrap_or_default();
+            // Only what is expected to be left after the slash is moved
+            let amount = match self
+                .slash_records
+                .may_load(deps.storage, (validator, owner))?
+            {
+                Some(mut record) => {
+                    let amount = delegation.min(record.delegation.saturating_sub(record.slashed));
+                    // The moved stake is no longer expected on `validator` when reconciling
+                    record.delegation -= amount;
+                    self.slash_records
+                        .save(deps.storage, (validator, owner), &record)?;
+                    amount
+                }
+                None => delegation,
+            };
+            if amount.is_zero() {
+                continue;
+            }
+
+            self.delegators
+                .save(deps.storage, (dst_validator.as_str(), owner), &true)?;
+            let msg = WasmMsg::Execute {
+                contract_addr: proxy.to_string(),
+                msg: to_json_binary(
+                    &mesh_native_staking_proxy::contract::sv::ExecMsg::Redelegate {
+                        src_validator: validator.to_owned(),
+                        dst_validator,
+                        amount: coin(amount.u128(), &config.denom),
+                    },
+                )?,
+                funds: vec![],
+            };
+            msgs.push(SubMsg::reply_on_error(msg, REPLY_ID_REDELEGATE));
+        }
+        Ok(msgs)
+    }
+
+    /// Returns the first active validator of `validators` other than `tombstoned`
+    fn select_fallback_validator(
+        &self,
+        deps: Deps,
+        validators: &[String],
+        tombstoned: &str,
+    ) -> StdResult<Option<String>> {
+        for validator in validators {
+            if validator != tombstoned && deps.querier.query_validator(validator)?.is_some() {
+                return Ok(Some(validator.clone()));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Returns the auto-redelegation policy of `owner`, falling back to the default one
+    fn effective_auto_redelegation(&self, deps: Deps, owner: &Addr) -> StdResult<AutoRedelegation> {
+        let default = self
+            .default_auto_redelegation
+            .may_load(deps.storage)?
+            .unwrap_or_default();
+        let policy = match self.auto_redelegation.may_load(deps.storage, owner)? {
+            Some(policy) if policy.validators.is_empty() => AutoRedelegation {
+                validators: default.validators,
+                ..policy
+            },
+            Some(policy) => policy,
+            None => default,
+        };
+        Ok(policy)
+    }
+
+    /// Fails unless all `validators` are active
+    fn ensure_active_validators(
+        &self,
+        deps: Deps,
+        validators: &[String],
+    ) -> Result<(), ContractError> {
+        for validator in validators {
+            ensure!(
+                deps.querier.query_validator(validator)?.is_some(),
+                ContractError::InactiveValidator(validator.clone())
+            );
+        }
+        Ok(())
+    }
+
+    /// Sets the auto-redelegation policy of the caller, overriding the default one.
+    /// When enabled, their stake on a validator that gets tombstoned is redelegated to the first
+    /// active validator of `validators`, or of the default validators if `validators` is empty.
+    #[sv::msg(exec)]
+    fn set_auto_redelegation(
+        &self,
+        ctx: ExecCtx,
+        enabled: bool,
+        validators: Vec<String>,
+    ) -> Result<Response, ContractError> {
+        nonpayable(&ctx.info)?;
+        self.ensure_active_validators(ctx.deps.as_ref(), &validators)?;
+
+        self.auto_redelegation.save(
+            ctx.deps.storage,
+            &ctx.info.sender,
+            &AutoRedelegation {
+                enabled,
+                validators,
+            },
+        )?;
+
+        Ok(Response::new()
+            .add_attribute("action", "set_auto_redelegation")
+            .add_attribute("owner", ctx.info.sender)
+            .add_attribute("enabled", enabled.to_string()))
+    }
+
+    /// Sets the auto-redelegation policy of owners that didn't set their own.
+    /// Can only be called by the contract admin.
+    #[sv::msg(exec)]
+    fn set_default_auto_redelegation(
+        &self,
+        ctx: ExecCtx,
+        enabled: bool,
+        validators: Vec<String>,
+    ) -> Result<Response, ContractError> {
+        nonpayable(&ctx.info)?;
+        self.ensure_admin(ctx.deps.as_ref(), &ctx.env, &ctx.info.sender)?;
+        self.ensure_active_validators(ctx.deps.as_ref(), &validators)?;
+
+        self.default_auto_redelegation.save(
+            ctx.deps.storage,
+            &AutoRedelegation {
+                enabled,
+                validators,
+            },
+        )?;
+
+        Ok(Response::new()
+            .add_attribute("action", "set_default_auto_redelegation")
+            .add_attribute("enabled", enabled.to_string()))
+    }
+
+    /// Updates the slash ratios to the ones of the chain's slashing params.
+    /// The ones given on instantiation are only used until this is called.
+    /// Anyone can call this, e.g. periodically or after a params change.
+    #[sv::msg(exec)]
+    fn sync_slash_ratios(&self, ctx: ExecCtx) -> Result<Response, ContractError> {
+        nonpayable(&ctx.info)?;
+        let (slash_ratio_dsign, slash_ratio_offline) =
+            self.query_slash_ratios(ctx.deps.as_ref())?;
+
+        let mut cfg = self.config.load(ctx.deps.storage)?;
+        cfg.slash_ratio_dsign = slash_ratio_dsign;
+        cfg.slash_ratio_offline = slash_ratio_offline;
+        self.config.save(ctx.deps.storage, &cfg)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "sync_slash_ratios")
+            .add_attribute("slash_ratio_dsign", slash_ratio_dsign.to_string())
+            .add_attribute("slash_ratio_offline", slash_ratio_offline.to_string()))
+    }
+
+    /// Queries the `(double sign, offline)` slash ratios from the chain's slashing params
+    fn query_slash_ratios(&self, deps: Deps) -> Result<(Decimal, Decimal), ContractError> {
+        let data = stargate_query(deps, SLASHING_PARAMS_QUERY_PATH, Binary::default())?;
+        decode_slash_ratios(&data)
+    }
+
+    /// Queries the `(validator, amount)` balances `delegator` is unbonding, after slashing.
+    /// Only the first page (100 validators) is queried
+    fn query_unbonding_balances(
+        &self,
+        deps: Deps,
+        delegator: &Addr,
+    ) -> Result<Vec<(String, Uint128)>, ContractError> {
+        let request = QueryDelegatorUnbondingsRequest {
+            delegator_addr: delegator.to_string(),
+        };
+        let data = stargate_query(
+            deps,
+            DELEGATOR_UNBONDINGS_QUERY_PATH,
+            Binary(request.encode_to_vec()),
+        )?;
+        decode_unbonding_balances(&data)
+    }
+
+    /// Compares the holdings of `owner`'s proxy (delegations, unbondings and liquid tokens) with
+    /// their lien in the vault. If the holdings fall short, e.g. because of a slash the jailing
+    /// handler didn't report, the difference is slashed in the vault, on the validator most of
+    /// the stake is on. Excess holdings are only reported, as liens can't be increased back.
+    /// Anyone can call this.
+    #[sv::msg(exec)]
+    fn sync_slash(&self, ctx: ExecCtx, owner: String) -> Result<Response, ContractError> {
+        nonpayable(&ctx.info)?;
+        let cfg = self.config.load(ctx.deps.storage)?;
+        let owner_addr = ctx.deps.api.addr_validate(&owner)?;
+        let proxy = self
+            .proxy_by_owner
+            .may_load(ctx.deps.storage, &owner_addr)?
+            .ok_or_else(|| ContractError::NoProxy(owner.clone()))?;
+
+        let delegations = ctx.deps.querier.query_all_delegations(&proxy)?;
+        let unbondings = self.query_unbonding_balances(ctx.deps.as_ref(), &proxy)?;
+        let liquid = ctx.deps.querier.query_balance(&proxy, &cfg.denom)?.amount;
+        let holdings = delegations
+            .iter()
+            .map(|delegation| (delegation.validator.as_str(), delegation.amount.amount))
+            .chain(
+                unbondings
+                    .iter()
+                    .map(|(validator, amount)| (validator.as_str(), *amount)),
+            )
+            .collect::<Vec<_>>();
+        let actual = holdings.iter().map(|(_, amount)| *amount).sum::<Uint128>() + liquid;
+
+        let LienAmountResponse { low: lien, .. } = cfg.vault.lien(
+            ctx.deps.as_ref(),
+            owner.clone(),
+            ctx.env.contract.address.to_string(),
+        )?;
+        let shortfall = lien.saturating_sub(actual);
+
+        let mut resp = Response::new().add_event(
+            Event::new("slash_sync")
+                .add_attribute("owner", &owner)
+                .add_attribute("lien", lien)
+                .add_attribute("actual", actual)
+                .add_attribute("shortfall", shortfall)
+                .add_attribute("excess", actual.saturating_sub(lien)),
+        );
+        let validator = holdings
+            .iter()
+            .max_by_key(|(_, amount)| *amount)
+            .map(|(validator, _)| *validator);
+        if let (false, Some(validator)) = (shortfall.is_zero(), validator) {
+            let msg = cfg.vault.process_local_slashing(
+                vec![SlashInfo {
+                    user: owner,
+                    slash: shortfall,
+                }],
+                validator,
+            )?;
+            resp = resp.add_message(msg);
+        }
+
+        Ok(resp)
+    }
+
+    /// Releases the matured unbondings of `owner` back into the vault.
+    /// Anyone can call this for owners that enabled auto-renew in the vault. Their matured
+    /// unbondings are also released every time they stake locally again.
+    #[sv::msg(exec)]
+    fn release_unbonded_for(&self, ctx: ExecCtx, owner: String) -> Result<Response, ContractError> {
+        nonpayable(&ctx.info)?;
+        let cfg = self.config.load(ctx.deps.storage)?;
+        let owner_addr = ctx.deps.api.addr_validate(&owner)?;
+        let proxy = self
+            .proxy_by_owner
+            .may_load(ctx.deps.storage, &owner_addr)?
+            .ok_or_else(|| ContractError::NoProxy(owner.clone()))?;
+        let AutoRenewResponse { enabled } =
+            cfg.vault.auto_renew(ctx.deps.as_ref(), owner.clone())?;
+        ensure!(enabled, ContractError::AutoRenewDisabled(owner));
+
+        let resp = Response::new()
+            .add_message(release_unbonded_msg(&proxy)?)
+            .add_attribute("action", "release_unbonded_for")
+            .add_attribute("owner", owner)
+            .add_attribute("proxy", proxy);
+
+        Ok(resp)
+    }
+
+    /// Reconciles a local slash of `owner` on `validator` with their actual delegation.
+    /// The vault lien was reduced at the nominal slash ratio. If the delegation was slashed
+    /// further, the difference is slashed in the vault too. Slashes below the nominal ratio are
+    /// only reported, as liens can't be increased back.
+    /// Anyone can call this. It should be called once the slash is applied on-chain, before the
+    /// owner changes their delegation to `validator`.
+    #[sv::msg(exec)]
+    fn reconcile_slash(
+        &self,
+        ctx: ExecCtx,
+        owner: String,
+        validator: String,
+    ) -> Result<Response, ContractError> {
+        nonpayable(&ctx.info)?;
+        let cfg = self.config.load(ctx.deps.storage)?;
+        let owner_addr = ctx.deps.api.addr_validate(&owner)?;
+        let record = self
+            .slash_records
+            .may_load(ctx.deps.storage, (&validator, &owner_addr))?
+            .ok_or_else(|| ContractError::NoSlashRecord {
+                owner: owner.clone(),
+                validator: validator.clone(),
+            })?;
+        self.slash_records
+            .remove(ctx.deps.storage, (&validator, &owner_addr));
+        let proxy = self
+            .proxy_by_owner
+            .may_load(ctx.deps.storage, &owner_addr)?
+            .ok_or_else(|| ContractError::NoProxy(owner.clone()))?;
+
+        let (evt, msg) = self.reconcile_slash_record(
+            ctx.deps.as_ref(),
+            &cfg,
+            owner,
+            &validator,
+            &proxy,
+            record,
+        )?;
+        Ok(Response::new().add_event(evt).add_messages(msg))
+    }
+
+    /// Compares a slash record of `owner` on `validator` with the actual delegation of `proxy`,
+    /// returning the reconciliation event and the vault slash for any further slashing
+    fn reconcile_slash_record(
+        &self,
+        deps: Deps,
+        cfg: &Config,
+        owner: String,
+        validator: &str,
+        proxy: &Addr,
+        record: SlashRecord,
+    ) -> Result<(Event, Option<WasmMsg>), ContractError> {
+        let actual = deps
+            .querier
+            .query_delegation(proxy, validator)?
+            .map(|full_delegation| full_delegation.amount.amount)
+            .unwrap_or_default();
+        let expected = record.delegation.saturating_sub(record.slashed);
+        let correction = expected.saturating_sub(actual);
+
+        let evt = Event::new("slash_reconciliation")
+            .add_attribute("owner", &owner)
+            .add_attribute("validator", validator)
+            .add_attribute("expected", expected)
+            .add_attribute("actual", actual)
+            .add_attribute("correction", correction)
+            .add_attribute("excess", actual.saturating_sub(expected));
+        let msg = if correction.is_zero() {
+            None
+        } else {
+            Some(cfg.vault.process_local_slashing(
+                vec![SlashInfo {
+                    user: owner,
+                    slash: correction,
+                }],
+                validator,
+            )?)
+        };
+        Ok((evt, msg))
+    }
+
+    /// Releases what is left of the delegation of `owner` to the tombstoned `validator` back into
+    /// the vault, without waiting for the unbonding period. The chain unbonds delegations to
+    /// tombstoned validators instantly.
+    /// A pending slash of `owner` on `validator` is reconciled first, as the delegation is gone
+    /// afterwards.
+    /// Anyone can call this.
+    #[sv::msg(exec)]
+    fn release_tombstoned(
+        &self,
+        ctx: ExecCtx,
+        owner: String,
+        validator: String,
+    ) -> Result<Response, ContractError> {
+        nonpayable(&ctx.info)?;
+        ensure!(
+            self.tombstoned.has(ctx.deps.storage, &validator),
+            ContractError::NotTombstoned(validator)
+        );
+        let cfg = self.config.load(ctx.deps.storage)?;
+        let owner_addr = ctx.deps.api.addr_validate(&owner)?;
+        let proxy = self
+            .proxy_by_owner
+            .may_load(ctx.deps.storage, &owner_addr)?
+            .ok_or_else(|| ContractError::NoProxy(owner.clone()))?;
+
+        let mut resp = Response::new();
+        if let Some(record) = self
+            .slash_records
+            .may_load(ctx.deps.storage, (&validator, &owner_addr))?
+        {
+            self.slash_records
+                .remove(ctx.deps.storage, (&validator, &owner_addr));
+            let (evt, msg) = self.reconcile_slash_record(
+                ctx.deps.as_ref(),
+                &cfg,
+                owner.clone(),
+                &validator,
+                &proxy,
+                record,
+            )?;
+            resp = resp.add_event(evt).add_messages(msg);
+        }
+        // Nothing is left for slashes of `validator` to reach
+        self.delegators
+            .remove(ctx.deps.storage, (&validator, &owner_addr));
+
+        let msg = WasmMsg::Execute {
+            contract_addr: proxy.to_string(),
+            msg: to_json_binary(
+                &mesh_native_staking_proxy::contract::sv::ExecMsg::ReleaseTombstoned {
+                    validator: validator.clone(),
+                },
+            )?,
+            funds: vec![],
+        };
+
+        Ok(resp
+            .add_message(msg)
+            .add_attribute("action", "release_tombstoned")
+            .add_attribute("owner", owner)
+            .add_attribute("validator", validator)
+            .add_attribute("proxy", proxy))
+    }
+
+    /// Sets the code id new proxies are instantiated with. Existing proxies are moved to it with
+    /// `migrate_proxies`.
+    /// Can only be called by the contract admin.
+    #[sv::msg(exec)]
+    fn set_proxy_code_id(
+        &self,
+        ctx: ExecCtx,
+        proxy_code_id: u64,
+    ) -> Result<Response, ContractError> {
+        nonpayable(&ctx.info)?;
+        self.ensure_admin(ctx.deps.as_ref(), &ctx.env, &ctx.info.sender)?;
+
+        let mut cfg = self.config.load(ctx.deps.storage)?;
+        cfg.proxy_code_id = proxy_code_id;
+        self.config.save(ctx.deps.storage, &cfg)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "set_proxy_code_id")
+            .add_attribute("proxy_code_id", proxy_code_id.to_string()))
+    }
+
+    /// Migrates a page of proxies, ordered by address, to the current proxy code id. Proxies
+    /// already running it are skipped. The last proxy of the page is returned in the `last_proxy`
+    /// attribute, to be passed as `start_after` for the next page.
+    /// Can only be called by the contract admin.
+    #[sv::msg(exec)]
+    fn migrate_proxies(
+        &self,
+        ctx: ExecCtx,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> Result<Response, ContractError> {
+        nonpayable(&ctx.info)?;
+        self.ensure_admin(ctx.deps.as_ref(), &ctx.env, &ctx.info.sender)?;
+        let cfg = self.config.load(ctx.deps.storage)?;
+
+        let proxies = self.proxy_code_infos(ctx.deps.as_ref(), start_after, limit)?;
+        let msgs = proxies
+            .iter()
+            .filter(|info| info.code_id != cfg.proxy_code_id)
+            .map(|info| {
+                Ok(WasmMsg::Migrate {
+                    contract_addr: info.proxy.clone(),
+                    new_code_id: cfg.proxy_code_id,
+                    msg: to_json_binary(&mesh_native_staking_proxy::contract::sv::MigrateMsg {})?,
+                })
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+
+        let mut resp = Response::new()
+            .add_attribute("action", "migrate_proxies")
+            .add_attribute("proxy_code_id", cfg.proxy_code_id.to_string())
+            .add_attribute("migrated", msgs.len().to_string());
+        if let Some(last) = proxies.last() {
+            resp = resp.add_attribute("last_proxy", &last.proxy);
+        }
+
+        Ok(resp.add_messages(msgs))
+    }
+
+    /// Fails unless `sender` is the admin of this contract
+    fn ensure_admin(&self, deps: Deps, env: &Env, sender: &Addr) -> Result<(), ContractError> {
+        let info = deps
+            .querier
+            .query_wasm_contract_info(&env.contract.address)?;
+        ensure!(
+            info.admin.as_deref() == Some(sender.as_str()),
+            ContractError::Unauthorized {}
+        );
+        Ok(())
+    }
+
+    fn proxy_code_infos(
+        &self,
+        deps: Deps,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> Result<Vec<ProxyCodeInfo>, ContractError> {
+        let limit = clamp_page_limit(limit);
+        let start_after = start_after
+            .map(|proxy| deps.api.addr_validate(&proxy))
+            .transpose()?;
+        let bound = start_after.as_ref().map(Bound::exclusive);
+
+        self.owner_by_proxy
+            .range(deps.storage, bound, None, Ascending)
+            .take(limit)
+            .map(|item| {
+                let (proxy, owner) = item?;
+                let code_id = deps.querier.query_wasm_contract_info(&proxy)?.code_id;
+                Ok(ProxyCodeInfo {
+                    proxy: proxy.into_string(),
+                    owner: owner.into_string(),
+                    code_id,
+                })
+            })
+            .collect()
+    }
+
     #[sv::msg(query)]
     fn config(&self, ctx: QueryCtx) -> Result<ConfigResponse, ContractError> {
         self.config.load(ctx.deps.storage).map_err(Into::into)
@@ -184,6 +822,7 @@ impl NativeStakingContract<'_> {
     fn reply(&self, ctx: ReplyCtx, reply: Reply) -> Result<Response, ContractError> {
         match reply.id {
             REPLY_ID_INSTANTIATE => self.reply_init_callback(ctx.deps, reply.result.unwrap()),
+            REPLY_ID_REDELEGATE => Ok(self.reply_redelegate(reply.result)),
             _ => Err(ContractError::InvalidReplyId(reply.id)),
         }
     }
@@ -208,6 +847,88 @@ impl NativeStakingContract<'_> {
         Ok(Response::new())
     }
 
+    fn reply_redelegate(&self, result: SubMsgResult) -> Response {
+        let mut evt = Event::new("auto_redelegation_failed");
+        if let SubMsgResult::Err(err) = result {
+            evt = evt.add_attribute("error", err);
+        }
+        Response::new().add_event(evt)
+    }
+
+    /// Returns the auto-redelegation policy applying to `owner`
+    #[sv::msg(query)]
+    fn auto_redelegation(
+        &self,
+        ctx: QueryCtx,
+        owner: String,
+    ) -> Result<AutoRedelegationResponse, ContractError> {
+        let owner_addr = ctx.deps.api.addr_validate(&owner)?;
+        let AutoRedelegation {
+            enabled,
+            validators,
+        } = self.effective_auto_redelegation(ctx.deps, &owner_addr)?;
+        Ok(AutoRedelegationResponse {
+            enabled,
+            validators,
+        })
+    }
+
+    /// Returns the auto-redelegation policy of owners that didn't set their own
+    #[sv::msg(query)]
+    fn default_auto_redelegation(
+        &self,
+        ctx: QueryCtx,
+    ) -> Result<AutoRedelegationResponse, ContractError> {
+        let AutoRedelegation {
+            enabled,
+            validators,
+        } = self
+            .default_auto_redelegation
+            .may_load(ctx.deps.storage)?
+            .unwrap_or_default();
+        Ok(AutoRedelegationResponse {
+            enabled,
+            validators,
+        })
+    }
+
+    /// Returns the proxy address of `owner`, before it is created by their first stake.
+    /// Only available with `deterministic_proxies`, unless the proxy exists already
+    #[sv::msg(query)]
+    fn predicted_proxy(
+        &self,
+        ctx: QueryCtx,
+        owner: String,
+    ) -> Result<PredictedProxyResponse, ContractError> {
+        let owner_addr = ctx.deps.api.addr_validate(&owner)?;
+        if let Some(proxy) = self
+            .proxy_by_owner
+            .may_load(ctx.deps.storage, &owner_addr)?
+        {
+            return Ok(PredictedProxyResponse {
+                proxy: proxy.to_string(),
+                instantiated: true,
+            });
+        }
+        let cfg = self.config.load(ctx.deps.storage)?;
+        ensure!(
+            cfg.deterministic_proxies,
+            ContractError::NonDeterministicProxies
+        );
+        let CodeInfoResponse { checksum, .. } =
+            ctx.deps.querier.query_wasm_code_info(cfg.proxy_code_id)?;
+        let creator = ctx
+            .deps
+            .api
+            .addr_canonicalize(ctx.env.contract.address.as_str())?;
+        let salt = proxy_salt(ctx.deps.api, &owner_addr)?;
+        let proxy = instantiate2_address(&checksum, &creator, &salt)?;
+        Ok(PredictedProxyResponse {
+            proxy: ctx.deps.api.addr_humanize(&proxy)?.to_string(),
+            instantiated: false,
+        })
+    }
+
     #[sv::msg(query)]
     fn proxy_by_owner(
         &self,
@@ -221,6 +942,52 @@ impl NativeStakingContract<'_> {
         })
     }
 
+    /// Lists the proxies with the code id they are running, ordered by proxy address
+    #[sv::msg(query)]
+    fn proxy_code_ids(
+        &self,
+        ctx: QueryCtx,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> Result<ProxyCodeIdsResponse, ContractError> {
+        let proxies = self.proxy_code_infos(ctx.deps, start_after, limit)?;
+        Ok(ProxyCodeIdsResponse { proxies })
+    }
+
+    /// Paginated list of all proxies, with their owners and delegations.
+    ///
+    /// `start_after` is the last owner of previous page
+    #[sv::msg(query)]
+    fn all_proxies(
+        &self,
+        ctx: QueryCtx,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> Result<AllProxiesResponse, ContractError> {
+        let limit = clamp_page_limit(limit);
+        let start_after = start_after
+            .map(|owner| ctx.deps.api.addr_validate(&owner))
+            .transpose()?;
+        let bound = start_after.as_ref().map(Bound::exclusive);
+
+        let proxies = self
+            .proxy_by_owner
+            .range(ctx.deps.storage, bound, None, Ascending)
+            .take(limit)
+            .map(|item| {
+                let (owner, proxy) = item?;
+                let delegations = ctx.deps.querier.query_all_delegations(&proxy)?;
+                Ok(ProxyInfo {
+                    owner: owner.into_string(),
+                    proxy: proxy.into_string(),
+                    delegations,
+                })
+            })
+            .collect::<Result<_, ContractError>>()?;
+
+        Ok(AllProxiesResponse { proxies })
+    }
+
     #[sv::msg(query)]
     fn owner_by_proxy(
         &self,
@@ -1,4 +1,4 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{Instantiate2AddressError, StdError};
 use cw_utils::{ParseReplyError, PaymentError};
 use thiserror::Error;
 
@@ -13,18 +13,42 @@ pub enum ContractError {
     #[error("{0}")]
     Payment(#[from] PaymentError),
 
+    #[error("{0}")]
+    Instantiate2Address(#[from] Instantiate2AddressError),
+
     #[error("Unauthorized")]
     Unauthorized {},
 
+    #[error("Try to send wrong denom: {0}")]
+    InvalidDenom(String),
+
     #[error("Invalid reply id: {0}")]
     InvalidReplyId(u64),
 
     #[error("Missing instantiate reply data")]
     NoInstantiateData {},
 
+    #[error("Account {0} has not enabled auto-renew")]
+    AutoRenewDisabled(String),
+
     #[error("Missing proxy contract for {0}")]
     NoProxy(String),
 
+    #[error("Proxy addresses are not deterministic")]
+    NonDeterministicProxies,
+
     #[error("You cannot specify a slash ratio over 1.0 (100%)")]
     InvalidSlashRatio,
+
+    #[error("Chain returned no slashing params")]
+    NoSlashingParams,
+
+    #[error("Validator {0} is not active")]
+    InactiveValidator(String),
+
+    #[error("Validator {0} is not tombstoned")]
+    NotTombstoned(String),
+
+    #[error("No slash of {owner} on {validator} to reconcile")]
+    NoSlashRecord { owner: String, validator: String },
 }
@@ -4,8 +4,11 @@ use sylvia::types::{ExecCtx, QueryCtx};
 
 #[allow(unused_imports)]
 use mesh_apis::local_staking_api::{self, LocalStakingApi, SlashRatioResponse};
+use mesh_apis::vault_api::AutoRenewResponse;
 
-use crate::contract::{NativeStakingContract, REPLY_ID_INSTANTIATE};
+use crate::contract::{
+    proxy_salt, release_unbonded_msg, NativeStakingContract, REPLY_ID_INSTANTIATE,
+};
 use crate::error::ContractError;
 use crate::msg::StakeMsg;
 
@@ -52,12 +55,25 @@ impl LocalStakingApi for NativeStakingContract<'_> {
                         owner: owner.clone(),
                         validator,
                     })?;
-                let wasm_msg = WasmMsg::Instantiate {
-                    admin: Some(ctx.env.contract.address.into()),
-                    code_id: cfg.proxy_code_id,
-                    msg,
-                    funds: ctx.info.funds,
-                    label: format!("LSP for {owner}"),
+                let admin = Some(ctx.env.contract.address.into());
+                let label = format!("LSP for {owner}");
+                let wasm_msg = if cfg.deterministic_proxies {
+                    WasmMsg::Instantiate2 {
+                        admin,
+                        code_id: cfg.proxy_code_id,
+                        label,
+                        msg,
+                        funds: ctx.info.funds,
+                        salt: proxy_salt(ctx.deps.api, &owner_addr)?,
+                    }
+                } else {
+                    WasmMsg::Instantiate {
+                        admin,
+                        code_id: cfg.proxy_code_id,
+                        msg,
+                        funds: ctx.info.funds,
+                        label,
+                    }
                 };
                 let sub_msg = SubMsg::reply_on_success(wasm_msg, REPLY_ID_INSTANTIATE);
                 Ok(Response::new().add_submessage(sub_msg))
@@ -69,11 +85,26 @@ impl LocalStakingApi for NativeStakingContract<'_> {
                         validator,
                     })?;
                 let wasm_msg = WasmMsg::Execute {
-                    contract_addr: proxy_addr.into(),
+                    contract_addr: proxy_addr.to_string(),
                     msg,
                     funds: ctx.info.funds,
                 };
-                Ok(Response::new().add_message(wasm_msg))
+                let mut resp = Response::new().add_message(wasm_msg);
+
+                // Owners with auto-renew get their matured unbondings released on every stake
+                let matured = ctx
+                    .deps
+                    .querier
+                    .query_balance(&proxy_addr, &cfg.denom)?
+                    .amount;
+                if !matured.is_zero() {
+                    let AutoRenewResponse { enabled } =
+                        cfg.vault.auto_renew(ctx.deps.as_ref(), owner)?;
+                    if enabled {
+                        resp = resp.add_message(release_unbonded_msg(&proxy_addr)?);
+                    }
+                }
+                Ok(resp)
             }
         }
     }
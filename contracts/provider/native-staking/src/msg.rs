@@ -1,5 +1,10 @@
+use std::str::from_utf8;
+
+use crate::error::ContractError;
 use crate::state::Config;
 use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Decimal, Delegation, StdError, Uint128};
+use prost::Message;
 
 pub type ConfigResponse = Config;
 
@@ -8,13 +13,152 @@ pub struct ProxyByOwnerResponse {
     pub proxy: String,
 }
 
+#[cw_serde]
+pub struct PredictedProxyResponse {
+    /// Address of the proxy of the owner, whether it exists already or not
+    pub proxy: String,
+    /// Whether the proxy was already instantiated
+    pub instantiated: bool,
+}
+
 #[cw_serde]
 pub struct OwnerByProxyResponse {
     pub owner: String,
 }
 
+#[cw_serde]
+pub struct ProxyCodeIdsResponse {
+    pub proxies: Vec<ProxyCodeInfo>,
+}
+
+#[cw_serde]
+pub struct ProxyCodeInfo {
+    pub proxy: String,
+    pub owner: String,
+    /// Code id the proxy is running
+    pub code_id: u64,
+}
+
+#[cw_serde]
+pub struct AllProxiesResponse {
+    pub proxies: Vec<ProxyInfo>,
+}
+
+#[cw_serde]
+pub struct ProxyInfo {
+    pub owner: String,
+    pub proxy: String,
+    /// Delegations of the proxy, as reported by the staking module
+    pub delegations: Vec<Delegation>,
+}
+
+#[cw_serde]
+pub struct AutoRedelegationResponse {
+    /// Whether stake is redelegated away from tombstoned validators
+    pub enabled: bool,
+    /// Validators the stake is redelegated to, in order of preference
+    pub validators: Vec<String>,
+}
+
 /// The message that is binary encoded in `receive_stake(..msg)`
 #[cw_serde]
 pub struct StakeMsg {
     pub validator: String,
 }
+
+pub const SLASHING_PARAMS_QUERY_PATH: &str = "/cosmos.slashing.v1beta1.Query/Params";
+
+/// `cosmos.slashing.v1beta1.QueryParamsResponse`
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct QuerySlashingParamsResponse {
+    #[prost(message, optional, tag = "1")]
+    pub params: ::core::option::Option<SlashingParams>,
+}
+
+/// `cosmos.slashing.v1beta1.Params`, restricted to the slash fractions.
+/// Both are `cosmos.Dec`, encoded as the string of their 18 decimal places atomics
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SlashingParams {
+    #[prost(bytes = "vec", tag = "4")]
+    pub slash_fraction_double_sign: ::prost::alloc::vec::Vec<u8>,
+    #[prost(bytes = "vec", tag = "5")]
+    pub slash_fraction_downtime: ::prost::alloc::vec::Vec<u8>,
+}
+
+/// Decodes the `(double sign, downtime)` slash ratios out of a slashing params query response
+pub fn decode_slash_ratios(data: &[u8]) -> Result<(Decimal, Decimal), ContractError> {
+    let params = QuerySlashingParamsResponse::decode(data)
+        .map_err(|err| StdError::parse_err("QuerySlashingParamsResponse", err))?
+        .params
+        .ok_or(ContractError::NoSlashingParams)?;
+    let dsign = decode_dec(&params.slash_fraction_double_sign)?;
+    let offline = decode_dec(&params.slash_fraction_downtime)?;
+    if dsign > Decimal::one() || offline > Decimal::one() {
+        return Err(ContractError::InvalidSlashRatio);
+    }
+    Ok((dsign, offline))
+}
+
+fn decode_dec(bytes: &[u8]) -> Result<Decimal, ContractError> {
+    let atomics = from_utf8(bytes).map_err(|err| StdError::parse_err("cosmos.Dec", err))?;
+    let atomics: Uint128 = atomics.parse()?;
+    Decimal::from_atomics(atomics, 18).map_err(|_| ContractError::InvalidSlashRatio)
+}
+
+pub const DELEGATOR_UNBONDINGS_QUERY_PATH: &str =
+    "/cosmos.staking.v1beta1.Query/DelegatorUnbondingDelegations";
+
+/// `cosmos.staking.v1beta1.QueryDelegatorUnbondingDelegationsRequest`, without pagination
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct QueryDelegatorUnbondingsRequest {
+    #[prost(string, tag = "1")]
+    pub delegator_addr: ::prost::alloc::string::String,
+}
+
+/// `cosmos.staking.v1beta1.QueryDelegatorUnbondingDelegationsResponse`, without pagination
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct QueryDelegatorUnbondingsResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub unbonding_responses: ::prost::alloc::vec::Vec<UnbondingDelegation>,
+}
+
+/// `cosmos.staking.v1beta1.UnbondingDelegation`, without the delegator
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UnbondingDelegation {
+    #[prost(string, tag = "2")]
+    pub validator_address: ::prost::alloc::string::String,
+    #[prost(message, repeated, tag = "3")]
+    pub entries: ::prost::alloc::vec::Vec<UnbondingDelegationEntry>,
+}
+
+/// `cosmos.staking.v1beta1.UnbondingDelegationEntry`, restricted to the balance left after
+/// slashing. It is a `cosmos.Int`, encoded as a string
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UnbondingDelegationEntry {
+    #[prost(string, tag = "4")]
+    pub balance: ::prost::alloc::string::String,
+}
+
+/// Decodes the `(validator, amount)` unbonding balances out of a delegator unbondings query
+/// response
+pub fn decode_unbonding_balances(data: &[u8]) -> Result<Vec<(String, Uint128)>, ContractError> {
+    QueryDelegatorUnbondingsResponse::decode(data)
+        .map_err(|err| StdError::parse_err("QueryDelegatorUnbondingDelegationsResponse", err))?
+        .unbonding_responses
+        .into_iter()
+        .map(|unbonding| {
+            let amount = unbonding
+                .entries
+                .iter()
+                .map(|entry| entry.balance.parse::<Uint128>())
+                .sum::<Result<Uint128, _>>()?;
+            Ok::<_, ContractError>((unbonding.validator_address, amount))
+        })
+        .collect()
+}
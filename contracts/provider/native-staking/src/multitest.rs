@@ -1,5 +1,5 @@
 use cosmwasm_std::{
-    coin, coins, to_json_binary, Addr, Decimal, Delegation, StdError, Uint128, Validator,
+    coin, coins, to_json_binary, Addr, Decimal, Delegation, Event, StdError, Uint128, Validator,
 };
 
 use cw_multi_test::{App as MtApp, StakingInfo};
@@ -18,7 +18,7 @@ use crate::contract;
 use crate::contract::sv::mt::NativeStakingContractProxy;
 use crate::error::ContractError;
 use crate::msg;
-use crate::msg::{OwnerByProxyResponse, ProxyByOwnerResponse};
+use crate::msg::{OwnerByProxyResponse, PredictedProxyResponse, ProxyByOwnerResponse};
 
 const OSMO: &str = "OSMO";
 
@@ -118,6 +118,7 @@ fn instantiation() {
             staking_proxy_code.code_id(),
             slashing_rate_dsign(),
             slashing_rate_offline(),
+            None,
         )
         .with_label("Staking")
         .call(owner)
@@ -152,6 +153,7 @@ fn receiving_stake() {
             staking_proxy_code.code_id(),
             slashing_rate_dsign(),
             slashing_rate_offline(),
+            None,
         )
         .with_label("Staking")
         .call(owner)
@@ -163,6 +165,9 @@ fn receiving_stake() {
         err,
         ContractError::Std(StdError::GenericErr { .. }) // Addr not found
     ));
+    // Its address cannot be predicted either, without deterministic proxies
+    let err = staking.predicted_proxy(user1.to_owned()).unwrap_err();
+    assert_eq!(err, ContractError::NonDeterministicProxies);
 
     // Receive some stake on behalf of user1 for validator
     let stake_msg = to_json_binary(&msg::StakeMsg {
@@ -176,6 +181,13 @@ fn receiving_stake() {
         .unwrap();
 
     let proxy1 = staking.proxy_by_owner(user1.to_owned()).unwrap().proxy;
+    assert_eq!(
+        staking.predicted_proxy(user1.to_owned()).unwrap(),
+        PredictedProxyResponse {
+            proxy: proxy1.clone(),
+            instantiated: true,
+        }
+    );
     // Reverse query
     assert_eq!(
         staking.owner_by_proxy(proxy1.clone()).unwrap(),
@@ -239,6 +251,35 @@ fn receiving_stake() {
 
     // Check that funds are in the corresponding proxy contract
     assert_delegations(&app, &proxy2, &[(validator, 10)]);
+
+    // All proxies are listed by owner, with their delegations
+    let proxies = staking.all_proxies(None, None).unwrap().proxies;
+    let listed = proxies
+        .iter()
+        .map(|info| {
+            let delegations = info
+                .delegations
+                .iter()
+                .map(|d| (d.validator.as_str(), d.amount.amount.u128()))
+                .collect::<Vec<_>>();
+            (info.owner.as_str(), info.proxy.as_str(), delegations)
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        listed,
+        [
+            (user1, proxy1.as_str(), vec![(validator, 150)]),
+            (user2, proxy2.as_str(), vec![(validator, 10)]),
+        ]
+    );
+
+    // Paginated by owner
+    let proxies = staking
+        .all_proxies(Some(user1.to_owned()), Some(1))
+        .unwrap()
+        .proxies;
+    assert_eq!(proxies.len(), 1);
+    assert_eq!(proxies[0].owner, user2);
 }
 
 #[test]
@@ -269,9 +310,11 @@ fn releasing_proxy_stake() {
             proxy_code_id: staking_proxy_code.code_id(),
             slash_ratio_dsign: slashing_rate_dsign(),
             slash_ratio_offline: slashing_rate_offline(),
+            deterministic_proxies: None,
         })
         .unwrap(),
         label: None,
+        salt: None,
     };
 
     // Instantiates vault and staking contracts
@@ -296,7 +339,7 @@ fn releasing_proxy_stake() {
 
     // User bonds some funds to the vault
     vault
-        .bond()
+        .bond(None)
         .with_funds(&coins(200, OSMO))
         .call(user)
         .unwrap();
@@ -316,6 +359,7 @@ fn releasing_proxy_stake() {
                 validator: validator.to_owned(),
             })
             .unwrap(),
+            None,
         )
         .call(user)
         .unwrap();
@@ -332,6 +376,7 @@ fn releasing_proxy_stake() {
         claims.claims,
         [mesh_vault::msg::LienResponse {
             lienholder: staking_addr.to_owned(),
+            denom: OSMO.to_owned(),
             amount: ValueRange::new_val(Uint128::new(100))
         }]
     );
@@ -358,6 +403,346 @@ fn releasing_proxy_stake() {
     assert_eq!(claims.claims, []);
 }
 
+#[test]
+fn reconciling_slashes() {
+    let owner = "vault_admin"; // Owner of the vault contract
+
+    let staking_addr = "contract1"; // Second contract (instantiated by vault)
+    let proxy_addr = "contract2"; // Staking proxy contract for user1 (instantiated by staking contract on stake)
+
+    let user = "user1";
+    let validator = "validator1";
+
+    let app = app(&[(user, (300, OSMO))], &[validator]);
+
+    let vault_code = mesh_vault::mock::sv::mt::CodeId::store_code(&app);
+    let staking_code = contract::sv::mt::CodeId::store_code(&app);
+    let staking_proxy_code = NativeStakingProxyCodeId::store_code(&app);
+
+    let staking_init_info = mesh_vault::msg::StakingInitInfo {
+        admin: None,
+        code_id: staking_code.code_id(),
+        msg: to_json_binary(&crate::contract::sv::InstantiateMsg {
+            denom: OSMO.to_owned(),
+            proxy_code_id: staking_proxy_code.code_id(),
+            slash_ratio_dsign: slashing_rate_dsign(),
+            slash_ratio_offline: slashing_rate_offline(),
+            deterministic_proxies: None,
+        })
+        .unwrap(),
+        label: None,
+        salt: None,
+    };
+    let vault = vault_code
+        .instantiate(
+            OSMO.to_owned(),
+            Some(LocalStakingInfo::New(staking_init_info)),
+        )
+        .with_label("Vault")
+        .call(owner)
+        .unwrap();
+    let staking: Proxy<'_, MtApp, contract::NativeStakingContract<'_>> =
+        Proxy::new(Addr::unchecked(staking_addr), &app);
+
+    vault
+        .bond(None)
+        .with_funds(&coins(200, OSMO))
+        .call(user)
+        .unwrap();
+    vault
+        .stake_local(
+            coin(100, OSMO),
+            to_json_binary(&msg::StakeMsg {
+                validator: validator.to_owned(),
+            })
+            .unwrap(),
+            None,
+        )
+        .call(user)
+        .unwrap();
+
+    // Nothing to reconcile before a slash
+    let err = staking
+        .reconcile_slash(user.to_owned(), validator.to_owned())
+        .call(owner)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::NoSlashRecord {
+            owner: user.to_owned(),
+            validator: validator.to_owned(),
+        }
+    );
+
+    // The validator is jailed. The lien is slashed at the nominal (offline) ratio
+    staking
+        .test_handle_jailing(vec![validator.to_owned()], vec![])
+        .call(owner)
+        .unwrap();
+    let lien_amount = || {
+        let claims = vault.account_claims(user.to_owned(), None, None).unwrap();
+        assert_eq!(claims.claims[0].lienholder, staking_addr);
+        claims.claims[0].amount
+    };
+    assert_eq!(lien_amount(), ValueRange::new_val(Uint128::new(90)));
+
+    // But the delegation is slashed further on-chain
+    app.app_mut()
+        .sudo(cw_multi_test::SudoMsg::Staking(
+            cw_multi_test::StakingSudo::Slash {
+                validator: validator.to_owned(),
+                percentage: Decimal::percent(20),
+            },
+        ))
+        .unwrap();
+    assert_delegations(&app, proxy_addr, &[(validator, 80)]);
+
+    // Reconciliation slashes the difference in the vault
+    staking
+        .reconcile_slash(user.to_owned(), validator.to_owned())
+        .call(owner)
+        .unwrap();
+    assert_eq!(lien_amount(), ValueRange::new_val(Uint128::new(80)));
+
+    // Only once
+    staking
+        .reconcile_slash(user.to_owned(), validator.to_owned())
+        .call(owner)
+        .unwrap_err();
+}
+
+#[test]
+fn releasing_from_tombstoned_validators() {
+    let owner = "vault_admin"; // Owner of the vault contract
+
+    let staking_addr = "contract1"; // Second contract (instantiated by vault)
+    let proxy_addr = "contract2"; // Staking proxy contract for user1 (instantiated by staking contract on stake)
+
+    let user = "user1";
+    let validator = "validator1";
+
+    let app = app(&[(user, (300, OSMO))], &[validator]);
+
+    let vault_code = mesh_vault::mock::sv::mt::CodeId::store_code(&app);
+    let staking_code = contract::sv::mt::CodeId::store_code(&app);
+    let staking_proxy_code = NativeStakingProxyCodeId::store_code(&app);
+
+    let staking_init_info = mesh_vault::msg::StakingInitInfo {
+        admin: None,
+        code_id: staking_code.code_id(),
+        msg: to_json_binary(&crate::contract::sv::InstantiateMsg {
+            denom: OSMO.to_owned(),
+            proxy_code_id: staking_proxy_code.code_id(),
+            slash_ratio_dsign: slashing_rate_dsign(),
+            slash_ratio_offline: slashing_rate_offline(),
+            deterministic_proxies: None,
+        })
+        .unwrap(),
+        label: None,
+        salt: None,
+    };
+    let vault = vault_code
+        .instantiate(
+            OSMO.to_owned(),
+            Some(LocalStakingInfo::New(staking_init_info)),
+        )
+        .with_label("Vault")
+        .call(owner)
+        .unwrap();
+    let staking: Proxy<'_, MtApp, contract::NativeStakingContract<'_>> =
+        Proxy::new(Addr::unchecked(staking_addr), &app);
+
+    vault
+        .bond(None)
+        .with_funds(&coins(200, OSMO))
+        .call(user)
+        .unwrap();
+    vault
+        .stake_local(
+            coin(100, OSMO),
+            to_json_binary(&msg::StakeMsg {
+                validator: validator.to_owned(),
+            })
+            .unwrap(),
+            None,
+        )
+        .call(user)
+        .unwrap();
+
+    // Only stake on tombstoned validators can be released early
+    let err = staking
+        .release_tombstoned(user.to_owned(), validator.to_owned())
+        .call(owner)
+        .unwrap_err();
+    assert_eq!(err, ContractError::NotTombstoned(validator.to_owned()));
+
+    // The validator is tombstoned. The lien is slashed at the nominal (double sign) ratio, but
+    // the delegation is slashed further on-chain
+    staking
+        .test_handle_jailing(vec![], vec![validator.to_owned()])
+        .call(owner)
+        .unwrap();
+    app.app_mut()
+        .sudo(cw_multi_test::SudoMsg::Staking(
+            cw_multi_test::StakingSudo::Slash {
+                validator: validator.to_owned(),
+                percentage: Decimal::percent(20),
+            },
+        ))
+        .unwrap();
+    let lien_amount = || {
+        let claims = vault.account_claims(user.to_owned(), None, None).unwrap();
+        claims.claims[0].amount
+    };
+    assert_eq!(lien_amount(), ValueRange::new_val(Uint128::new(85)));
+
+    // Anyone can release it. The slash is reconciled before the delegation is gone
+    staking
+        .release_tombstoned(user.to_owned(), validator.to_owned())
+        .call("anyone")
+        .unwrap();
+    assert_eq!(lien_amount(), ValueRange::new_val(Uint128::new(80)));
+    assert_delegations(&app, proxy_addr, &[]);
+    let err = staking
+        .reconcile_slash(user.to_owned(), validator.to_owned())
+        .call(owner)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::NoSlashRecord {
+            owner: user.to_owned(),
+            validator: validator.to_owned(),
+        }
+    );
+
+    // The multi-test staking module doesn't unbond instantly, so the tokens still wait for the
+    // unbonding period there
+    app.update_block(advance_unbonding_period);
+    let staking_proxy: Proxy<'_, MtApp, NativeStakingProxyMock<'_>> =
+        Proxy::new(Addr::unchecked(proxy_addr), &app);
+    staking_proxy.release_unbonded().call(user).unwrap();
+
+    // The lien is released
+    let claims = vault.account_claims(user.to_owned(), None, None).unwrap();
+    assert_eq!(claims.claims, []);
+}
+
+#[test]
+fn auto_redelegating_from_tombstoned_validators() {
+    let owner = "vault_admin"; // Owner of the vault contract
+
+    let staking_addr = "contract1"; // Second contract (instantiated by vault)
+    let proxy_addr = "contract2"; // Staking proxy contract for user1 (instantiated by staking contract on stake)
+
+    let user = "user1";
+    let validator = "validator1";
+    let fallback = "validator2";
+
+    let app = app(&[(user, (300, OSMO))], &[validator, fallback]);
+
+    let vault_code = mesh_vault::mock::sv::mt::CodeId::store_code(&app);
+    let staking_code = contract::sv::mt::CodeId::store_code(&app);
+    let staking_proxy_code = NativeStakingProxyCodeId::store_code(&app);
+
+    let staking_init_info = mesh_vault::msg::StakingInitInfo {
+        admin: None,
+        code_id: staking_code.code_id(),
+        msg: to_json_binary(&crate::contract::sv::InstantiateMsg {
+            denom: OSMO.to_owned(),
+            proxy_code_id: staking_proxy_code.code_id(),
+            slash_ratio_dsign: slashing_rate_dsign(),
+            slash_ratio_offline: slashing_rate_offline(),
+            deterministic_proxies: None,
+        })
+        .unwrap(),
+        label: None,
+        salt: None,
+    };
+    let vault = vault_code
+        .instantiate(
+            OSMO.to_owned(),
+            Some(LocalStakingInfo::New(staking_init_info)),
+        )
+        .with_label("Vault")
+        .call(owner)
+        .unwrap();
+    let staking: Proxy<'_, MtApp, contract::NativeStakingContract<'_>> =
+        Proxy::new(Addr::unchecked(staking_addr), &app);
+
+    vault
+        .bond(None)
+        .with_funds(&coins(200, OSMO))
+        .call(user)
+        .unwrap();
+    vault
+        .stake_local(
+            coin(100, OSMO),
+            to_json_binary(&msg::StakeMsg {
+                validator: validator.to_owned(),
+            })
+            .unwrap(),
+            None,
+        )
+        .call(user)
+        .unwrap();
+
+    // Disabled by default
+    let policy = staking.auto_redelegation(user.to_owned()).unwrap();
+    assert!(!policy.enabled);
+
+    // Only the admin can set the default policy
+    let err = staking
+        .set_default_auto_redelegation(true, vec![fallback.to_owned()])
+        .call(owner)
+        .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    // Fallback validators must be active
+    let err = staking
+        .set_auto_redelegation(true, vec!["unknown".to_owned()])
+        .call(user)
+        .unwrap_err();
+    assert_eq!(err, ContractError::InactiveValidator("unknown".to_owned()));
+
+    staking
+        .set_auto_redelegation(true, vec![validator.to_owned(), fallback.to_owned()])
+        .call(user)
+        .unwrap();
+    let policy = staking.auto_redelegation(user.to_owned()).unwrap();
+    assert!(policy.enabled);
+    assert_eq!(policy.validators, [validator, fallback]);
+
+    // The validator is tombstoned. What is expected to be left after the slash is moved to the
+    // first fallback validator other than the tombstoned one
+    staking
+        .test_handle_jailing(vec![], vec![validator.to_owned()])
+        .call(owner)
+        .unwrap();
+    assert_delegations(&app, proxy_addr, &[(validator, 15), (fallback, 85)]);
+
+    // The moved stake is not slashed again on reconciliation
+    staking
+        .reconcile_slash(user.to_owned(), validator.to_owned())
+        .call(owner)
+        .unwrap();
+    let claims = vault.account_claims(user.to_owned(), None, None).unwrap();
+    assert_eq!(
+        claims.claims[0].amount,
+        ValueRange::new_val(Uint128::new(85))
+    );
+
+    // The new validator is tracked, so that its slashes reach the owner
+    staking
+        .test_handle_jailing(vec![fallback.to_owned()], vec![])
+        .call(owner)
+        .unwrap();
+    let claims = vault.account_claims(user.to_owned(), None, None).unwrap();
+    assert_eq!(
+        claims.claims[0].amount,
+        ValueRange::new_val(Uint128::new(77))
+    );
+}
+
 pub fn advance_unbonding_period(block: &mut cosmwasm_std::BlockInfo) {
     // Default unbonding time in cw_multi_test is 60, from looking at the code...
     // Wish I could find this somewhere in this setup somewhere.
@@ -365,3 +750,160 @@ pub fn advance_unbonding_period(block: &mut cosmwasm_std::BlockInfo) {
     block.time = block.time.plus_seconds(5 * UNBONDING_TIME);
     block.height += UNBONDING_TIME;
 }
+
+#[test]
+fn migrating_proxies() {
+    let owner = "vault"; // Owner of the staking contract (i. e. the vault contract)
+    let admin = "admin";
+    let users = ["user1", "user2"];
+    let validator = "validator1";
+
+    let app = app(&[(owner, (300, OSMO))], &[validator]);
+
+    let old_proxy_code = NativeStakingProxyCodeId::store_code(&app);
+    let new_proxy_code = NativeStakingProxyCodeId::store_code(&app);
+    let staking_code = contract::sv::mt::CodeId::store_code(&app);
+
+    let staking = staking_code
+        .instantiate(
+            OSMO.to_owned(),
+            old_proxy_code.code_id(),
+            slashing_rate_dsign(),
+            slashing_rate_offline(),
+            None,
+        )
+        .with_label("Staking")
+        .with_admin(admin)
+        .call(owner)
+        .unwrap();
+
+    for user in users {
+        let stake_msg = to_json_binary(&msg::StakeMsg {
+            validator: validator.to_owned(),
+        })
+        .unwrap();
+        staking
+            .receive_stake(user.to_owned(), stake_msg)
+            .with_funds(&coins(100, OSMO))
+            .call(owner)
+            .unwrap();
+    }
+    let code_ids = || {
+        staking
+            .proxy_code_ids(None, None)
+            .unwrap()
+            .proxies
+            .into_iter()
+            .map(|info| info.code_id)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        code_ids(),
+        [old_proxy_code.code_id(), old_proxy_code.code_id()]
+    );
+
+    // Only the admin can update and migrate proxies
+    let err = staking
+        .set_proxy_code_id(new_proxy_code.code_id())
+        .call(owner)
+        .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    let err = staking.migrate_proxies(None, None).call(owner).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    staking
+        .set_proxy_code_id(new_proxy_code.code_id())
+        .call(admin)
+        .unwrap();
+    assert_eq!(
+        staking.config().unwrap().proxy_code_id,
+        new_proxy_code.code_id()
+    );
+
+    // Proxies are migrated page by page
+    staking.migrate_proxies(None, Some(1)).call(admin).unwrap();
+    assert_eq!(
+        code_ids(),
+        [new_proxy_code.code_id(), old_proxy_code.code_id()]
+    );
+
+    // Already migrated proxies are skipped
+    let resp = staking.migrate_proxies(None, None).call(admin).unwrap();
+    assert!(resp.has_event(&Event::new("wasm").add_attribute("migrated", "1")));
+    assert_eq!(
+        code_ids(),
+        [new_proxy_code.code_id(), new_proxy_code.code_id()]
+    );
+
+    // The proxies are still usable
+    let proxy = staking.proxy_by_owner(users[1].to_owned()).unwrap().proxy;
+    assert_delegations(&app, &proxy, &[(validator, 100)]);
+}
+
+#[test]
+fn decoding_slash_ratios() {
+    use prost::Message;
+
+    let encode = |dsign: &str, offline: &str| {
+        msg::QuerySlashingParamsResponse {
+            params: Some(msg::SlashingParams {
+                slash_fraction_double_sign: dsign.as_bytes().to_vec(),
+                slash_fraction_downtime: offline.as_bytes().to_vec(),
+            }),
+        }
+        .encode_to_vec()
+    };
+
+    // cosmos.Dec is encoded as its atomics, with 18 decimal places
+    let data = encode("50000000000000000", "10000000000000000");
+    let ratios = msg::decode_slash_ratios(&data).unwrap();
+    assert_eq!(ratios, (Decimal::percent(5), Decimal::percent(1)));
+
+    // Ratios over 1.0 are rejected
+    let data = encode("1000000000000000001", "0");
+    let err = msg::decode_slash_ratios(&data).unwrap_err();
+    assert_eq!(err, ContractError::InvalidSlashRatio);
+
+    // Missing params are rejected
+    let data = msg::QuerySlashingParamsResponse { params: None }.encode_to_vec();
+    let err = msg::decode_slash_ratios(&data).unwrap_err();
+    assert_eq!(err, ContractError::NoSlashingParams);
+}
+
+#[test]
+fn decoding_unbonding_balances() {
+    use prost::Message;
+
+    let unbonding = |validator: &str, balances: &[&str]| msg::UnbondingDelegation {
+        validator_address: validator.to_owned(),
+        entries: balances
+            .iter()
+            .map(|balance| msg::UnbondingDelegationEntry {
+                balance: balance.to_string(),
+            })
+            .collect(),
+    };
+    let data = msg::QueryDelegatorUnbondingsResponse {
+        unbonding_responses: vec![
+            unbonding("validator1", &["100", "20"]),
+            unbonding("validator2", &["7"]),
+        ],
+    }
+    .encode_to_vec();
+
+    // Entries are summed up per validator
+    assert_eq!(
+        msg::decode_unbonding_balances(&data).unwrap(),
+        [
+            ("validator1".to_owned(), Uint128::new(120)),
+            ("validator2".to_owned(), Uint128::new(7)),
+        ]
+    );
+
+    // Balances must be integers
+    let data = msg::QueryDelegatorUnbondingsResponse {
+        unbonding_responses: vec![unbonding("validator1", &["1.5"])],
+    }
+    .encode_to_vec();
+    msg::decode_unbonding_balances(&data).unwrap_err();
+}
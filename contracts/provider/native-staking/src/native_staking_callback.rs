@@ -1,5 +1,5 @@
-use cosmwasm_std::Response;
-use cw_utils::must_pay;
+use cosmwasm_std::{ensure_eq, Coin, Response};
+use cw_utils::{must_pay, nonpayable};
 use sylvia::types::ExecCtx;
 
 #[allow(unused_imports)]
@@ -33,4 +33,55 @@ impl NativeStakingCallback for NativeStakingContract<'_> {
 
         Ok(Response::new().add_message(msg))
     }
+
+    /// Passes the rewards compounded by the proxy on to the vault, as collateral of the proxy
+    /// owner locked by this contract.
+    fn compound_proxy_stake(&self, ctx: ExecCtx, amount: Coin) -> Result<Response, Self::Error> {
+        nonpayable(&ctx.info)?;
+
+        let cfg = self.config.load(ctx.deps.storage)?;
+        ensure_eq!(
+            amount.denom,
+            cfg.denom,
+            ContractError::InvalidDenom(amount.denom)
+        );
+
+        // Asserts the caller is a valid proxy
+        let owner_addr = self
+            .owner_by_proxy
+            .load(ctx.deps.storage, &ctx.info.sender)?;
+
+        let msg = cfg
+            .vault
+            .compound_local_stake(owner_addr.to_string(), amount.clone())?;
+
+        Ok(Response::new()
+            .add_message(msg)
+            .add_attribute("action", "compound_proxy_stake")
+            .add_attribute("owner", owner_addr)
+            .add_attribute("amount", amount.amount.to_string()))
+    }
+
+    /// Adds the proxy owner to the delegators of `validator`, so that its slashes are routed to
+    /// them.
+    fn register_delegation(
+        &self,
+        ctx: ExecCtx,
+        validator: String,
+    ) -> Result<Response, Self::Error> {
+        nonpayable(&ctx.info)?;
+
+        // Asserts the caller is a valid proxy
+        let owner_addr = self
+            .owner_by_proxy
+            .load(ctx.deps.storage, &ctx.info.sender)?;
+
+        self.delegators
+            .save(ctx.deps.storage, (&validator, &owner_addr), &true)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "register_delegation")
+            .add_attribute("owner", owner_addr)
+            .add_attribute("validator", validator))
+    }
 }
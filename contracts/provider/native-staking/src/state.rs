@@ -1,5 +1,5 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::Decimal;
+use cosmwasm_std::{Decimal, Uint128};
 use mesh_apis::vault_api::VaultApiHelper;
 
 #[cw_serde]
@@ -18,4 +18,28 @@ pub struct Config {
 
     /// The slash ratio for being offline
     pub slash_ratio_offline: Decimal,
+
+    /// Whether proxies are instantiated with `instantiate2`, salted with their owner address,
+    /// so that the proxy address of every owner is known beforehand
+    #[serde(default)]
+    pub deterministic_proxies: bool,
+}
+
+/// Local slash sent to the vault, waiting to be reconciled with the actual delegation
+#[cw_serde]
+pub struct SlashRecord {
+    /// Delegation to the validator when the slash was processed
+    pub delegation: Uint128,
+    /// Amount slashed in the vault, at the nominal slash ratio
+    pub slashed: Uint128,
+}
+
+/// Policy for redelegating stake away from tombstoned validators
+#[cw_serde]
+#[derive(Default)]
+pub struct AutoRedelegation {
+    pub enabled: bool,
+    /// Validators to redelegate to, in order of preference. The first active one is used.
+    /// When empty, the default validators are used.
+    pub validators: Vec<String>,
 }
@@ -1,41 +1,79 @@
 use cosmwasm_std::{
-    coin, ensure, to_json_binary, Addr, Binary, Coin, Decimal, DepsMut, Fraction, Order, Reply,
-    Response, StdResult, Storage, SubMsg, SubMsgResponse, Uint128, WasmMsg,
+    coin, ensure, from_json, to_json_binary, Addr, BankMsg, Binary, Coin, CosmosMsg, Decimal, Deps,
+    DepsMut, Env, Event, Fraction, Order, Reply, Response, StdError, StdResult, Storage, SubMsg,
+    SubMsgResponse, Timestamp, Uint128, WasmMsg,
 };
 use cw2::set_contract_version;
-use cw_storage_plus::{Bounder, Item, Map};
-use cw_utils::{must_pay, nonpayable, parse_instantiate_response_data};
+use cw_storage_plus::{Bound, Bounder, Item, Map, PrefixBound, SnapshotMap, Strategy};
+use cw_utils::{must_pay, nonpayable, one_coin, parse_instantiate_response_data, Expiration};
 use std::cmp::min;
+use std::collections::BTreeMap;
 
 use mesh_apis::cross_staking_api::CrossStakingApiHelper;
 use mesh_apis::local_staking_api::{
     sv::LocalStakingApiQueryMsg, LocalStakingApiHelper, SlashRatioResponse,
 };
-use mesh_apis::vault_api::{self, SlashInfo, VaultApi};
+use mesh_apis::vault_api::{self, AutoRenewResponse, LienAmountResponse, SlashInfo, VaultApi};
 use mesh_bindings::{ProviderCustomMsg, ProviderMsg};
 use mesh_sync::Tx::InFlightStaking;
-use mesh_sync::{max_range, ValueRange};
+use mesh_sync::{max_range, Tx, ValueRange};
 
-use sylvia::types::{ExecCtx, InstantiateCtx, QueryCtx, ReplyCtx};
+use sylvia::types::{ExecCtx, InstantiateCtx, MigrateCtx, QueryCtx, ReplyCtx, SudoCtx};
 use sylvia::{contract, schemars};
 
+use crate::cw20::{cw20_denom, cw20_token, Cw20ExecuteMsg};
 use crate::error::ContractError;
+use crate::lst::{query_exchange_rate, ExchangeRateResponse};
 use crate::msg::{
-    AccountClaimsResponse, AccountDetailsResponse, AccountResponse, AllAccountsResponse,
+    AccountAtHeightResponse, AccountClaimsResponse, AccountCollateralResponse,
+    AccountDetailsResponse, AccountLienDetails, AccountResponse, AllAccountsResponse,
     AllAccountsResponseItem, AllActiveExternalStakingResponse, AllTxsResponse, AllTxsResponseItem,
-    ConfigResponse, LienResponse, LocalStakingInfo, TxResponse,
+    AllowedLienholdersResponse, ApprovalResponse, CollateralDenomsResponse, ConfigResponse,
+    Cw20HookMsg, DefunctLienholderResponse, DefunctLienholdersResponse, DenomSlashDust,
+    DenomTxHistorySummary, DormantAccount, DormantAccountsResponse, FeeAccountingResponse,
+    FeeBucketInfo, LeverageResponse, LienResponse, LienholderCapResponse, LienholderLienResponse,
+    LienholderTotal, LiensByLienholderResponse, LocalStakingInfo, MetricsResponse,
+    MultiQueryResponse, OwnershipResponse, PausableOperation, PauseStatusResponse,
+    PendingUnbondsResponse, SlashDustResponse, SubQuery, SubQueryResponse, SwapAdapterResponse,
+    TotalStatsResponse, TxHistoryItem, TxHistoryResponse, TxMemoResponse, TxResponse,
+    TxTimeoutResponse, UnbondRequestResponse,
 };
-use crate::state::{Config, Lien, LocalStaking, UserInfo};
+use crate::state::{
+    liens, Approval, Config, FeeBucket, FeeDisbursement, Lien, LienTransfer, Liens, LocalStaking,
+    LstRate, PendingOwnership, PendingSwap, SlashDust, SlashDustPolicy, TxHistorySummary,
+    TxOutcome, TxRecord, UnbondRequest, UserInfo,
+};
+use crate::swap::SwapAdapterExecuteMsg;
 use crate::txs::Txs;
 
 pub const CONTRACT_NAME: &str = env!("CARGO_PKG_NAME");
 pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 pub const REPLY_ID_INSTANTIATE: u64 = 1;
+pub const REPLY_ID_SWAP: u64 = 2;
+pub const REPLY_ID_ABORT_STAKE: u64 = 3;
 
 pub const DEFAULT_PAGE_LIMIT: u32 = 10;
 pub const MAX_PAGE_LIMIT: u32 = 30;
 
+/// Maximum length (in bytes) of the memo attached to stake operations
+pub const MAX_MEMO_LEN: usize = 256;
+
+/// Maximum number of queries batched in a single `multi_query`
+pub const MAX_MULTI_QUERIES: usize = 20;
+
+/// Default time (in seconds) after which a pending tx can be expired: two weeks
+pub const DEFAULT_TX_TIMEOUT: u64 = 14 * 24 * 60 * 60;
+
+/// Default time (in seconds) after which a pending tx can be cancelled by its staker: three days
+pub const DEFAULT_CANCEL_GRACE_PERIOD: u64 = 3 * 24 * 60 * 60;
+
+/// Number of completed txs kept per user in the tx history before they can be compacted
+pub const TX_HISTORY_RETENTION: usize = 20;
+
+/// Maximum number of completed txs compacted in a single `compact_tx_history`
+pub const TX_HISTORY_COMPACTION_BATCH: usize = 50;
+
 /// Aligns pagination limit
 fn clamp_page_limit(limit: Option<u32>) -> usize {
     limit.unwrap_or(DEFAULT_PAGE_LIMIT).max(MAX_PAGE_LIMIT) as usize
@@ -51,17 +89,98 @@ pub struct VaultContract<'a> {
     pub config: Item<'a, Config>,
     /// Local staking info
     pub local_staking: Item<'a, Option<LocalStaking>>,
+    /// Denoms accepted as collateral. Always contains `Config::denom`
+    pub collateral_denoms: Map<'a, &'a str, ()>,
+    /// Exchange rates of liquid staking token collateral, by denom
+    pub lst_rates: Map<'a, &'a str, LstRate>,
     /// All liens in the protocol
     ///
-    /// Liens are indexed with (user, lien_holder), as this pair has to be unique
-    pub liens: Map<'a, (&'a Addr, &'a Addr), Lien>,
+    /// Liens are indexed with (user, lien_holder), as this pair has to be unique, and
+    /// additionally by lienholder
+    pub liens: Liens<'a>,
     /// Per-user information
-    pub users: Map<'a, &'a Addr, UserInfo>,
+    ///
+    /// Collateral is accounted separately for every denom, indexed with (user, denom)
+    pub users: Map<'a, (&'a Addr, &'a str), UserInfo>,
+    /// Unbond requests waiting for collateral to be released from liens
+    ///
+    /// Requests are indexed with (user, request id), so they are fulfilled in order
+    pub unbond_requests: Map<'a, (&'a Addr, u64), UnbondRequest>,
+    pub unbond_request_count: Item<'a, u64>,
+    /// Collateral unbonded by fulfilled requests, ready to be claimed, per (user, denom)
+    pub unbonded: Map<'a, (&'a Addr, &'a str), Uint128>,
+    /// Maximum total lien per (lienholder, denom), if capped
+    pub lienholder_caps: Map<'a, (&'a Addr, &'a str), Uint128>,
+    /// Running total of liens per (lienholder, denom)
+    pub lienholder_totals: Map<'a, (&'a Addr, &'a str), ValueRange<Uint128>>,
+    /// Maximum ratio of the total liens of an account to its collateral, per denom, if capped
+    pub max_leverage: Item<'a, Decimal>,
+    /// History of per (user, denom) bonded collateral, by block height
+    pub collateral_history: SnapshotMap<'a, (&'a Addr, &'a str), Uint128>,
+    /// Running total of bonded collateral per denom
+    pub collateral_totals: Map<'a, &'a str, Uint128>,
+    /// Running total of users' slashable amounts per denom
+    pub slashable_totals: Map<'a, &'a str, ValueRange<Uint128>>,
+    /// Number of accounts that ever bonded collateral
+    pub account_count: Item<'a, u64>,
+    /// Proposed lien transfers, indexed with (user, from lienholder, to lienholder)
+    pub lien_transfers: Map<'a, (&'a Addr, &'a Addr, &'a Addr), LienTransfer>,
+    /// Accounts that opted into auto-renew of their local unbondings
+    pub auto_renew_accounts: Map<'a, &'a Addr, ()>,
+    /// Cross-staking contracts users are allowed to stake remotely on
+    pub allowed_lienholders: Map<'a, &'a Addr, ()>,
+    /// Lienholders marked as defunct by governance, whose liens can be force-released
+    pub defunct_lienholders: Map<'a, &'a Addr, DefunctLienholder>,
     /// All active external staking contracts in use by this vault
     pub active_external: Map<'a, &'a Addr, ()>,
     /// Pending txs information
     pub tx_count: Item<'a, u64>,
     pub pending: Txs<'a>,
+    /// Block time at which each pending tx was created
+    pub pending_started: Map<'a, u64, Timestamp>,
+    /// Time (in seconds) after which a pending tx can be expired by anyone
+    pub tx_timeout: Item<'a, u64>,
+    /// Time (in seconds) after which a pending tx can be cancelled by its staker
+    pub cancel_grace_period: Item<'a, u64>,
+    /// Vault owner, allowed to run privileged configuration actions.
+    /// Falls back to the contract admin until ownership is first transferred
+    pub owner: Item<'a, Addr>,
+    /// Ownership transfer proposed by the owner, waiting for the new owner to accept it
+    pub pending_owner: Item<'a, PendingOwnership>,
+    /// User-provided memo of each pending tx, if any
+    pub tx_memos: Map<'a, u64, String>,
+    /// Stake approvals given by users to operators, indexed by `(owner, operator, denom)`
+    pub approvals: Map<'a, (&'a Addr, &'a Addr, &'a str), Approval>,
+    /// Operations currently paused by the circuit breaker
+    pub paused: Item<'a, Vec<PausableOperation>>,
+    /// Address allowed to pause and unpause operations, besides governance
+    pub pause_authority: Item<'a, Option<Addr>>,
+    /// Swap adapter used to convert other denoms to the primary collateral denom, if configured
+    pub swap_adapter: Item<'a, Addr>,
+    /// Swap-and-bond in progress, waiting for the swap adapter reply
+    pub pending_swap: Item<'a, PendingSwap>,
+    /// Treatment of slash rounding dust. Burned if not set
+    pub slash_dust_policy: Item<'a, SlashDustPolicy>,
+    /// Slash rounding dust, by collateral denom
+    pub slash_dust: Map<'a, &'a str, SlashDust>,
+    /// Time (in seconds) without activity after which an account can be flagged as dormant
+    pub dormancy_period: Item<'a, u64>,
+    /// Time dormancy tracking was enabled at, used as last activity for accounts with none since
+    pub dormancy_since: Item<'a, Timestamp>,
+    /// Last activity of an account
+    pub last_activity: Map<'a, &'a Addr, Timestamp>,
+    /// Accounts flagged as dormant, with the time they were flagged at
+    pub dormant_accounts: Map<'a, &'a Addr, Timestamp>,
+    /// Completed stake txs of every user, by tx id
+    pub tx_history: Map<'a, (&'a Addr, u64), TxRecord>,
+    /// Aggregates of the compacted tx history of every user, per denom
+    pub tx_history_summaries: Map<'a, (&'a Addr, &'a str), TxHistorySummary>,
+    /// Scheduled disbursement of collected fees. Fees are not collected if not set
+    pub fee_disbursement: Item<'a, FeeDisbursement>,
+    /// Collected fees, by source
+    pub fee_buckets: Map<'a, &'a str, FeeBucket>,
+    /// Time of the last fee disbursement
+    pub last_fee_disbursement: Item<'a, Timestamp>,
 }
 
 #[cfg_attr(not(feature = "library"), sylvia::entry_points)]
@@ -75,11 +194,54 @@ impl VaultContract<'_> {
         Self {
             config: Item::new("config"),
             local_staking: Item::new("local_staking"),
-            liens: Map::new("liens"),
-            users: Map::new("users"),
-            pending: Txs::new("pending_txs", "users"),
+            collateral_denoms: Map::new("collateral_denoms"),
+            lst_rates: Map::new("lst_rates"),
+            liens: liens("liens", "lienholders"),
+            users: Map::new("user_collateral"),
+            unbond_requests: Map::new("unbond_requests"),
+            unbond_request_count: Item::new("unbond_request_count"),
+            unbonded: Map::new("unbonded"),
+            lienholder_caps: Map::new("lienholder_caps"),
+            lienholder_totals: Map::new("lienholder_totals"),
+            max_leverage: Item::new("max_leverage"),
+            collateral_history: SnapshotMap::new(
+                "collateral_history",
+                "collateral_history__checkpoints",
+                "collateral_history__changelog",
+                Strategy::EveryBlock,
+            ),
+            collateral_totals: Map::new("collateral_totals"),
+            slashable_totals: Map::new("slashable_totals"),
+            account_count: Item::new("account_count"),
+            lien_transfers: Map::new("lien_transfers"),
+            pending: Txs::new("pending_txs", "users", "pending_tx_count"),
+            pending_started: Map::new("pending_started"),
+            tx_timeout: Item::new("tx_timeout"),
+            cancel_grace_period: Item::new("cancel_grace_period"),
+            owner: Item::new("owner"),
+            pending_owner: Item::new("pending_owner"),
+            tx_memos: Map::new("tx_memos"),
+            approvals: Map::new("approvals"),
             tx_count: Item::new("tx_count"),
+            auto_renew_accounts: Map::new("auto_renew_accounts"),
+            allowed_lienholders: Map::new("allowed_lienholders"),
+            defunct_lienholders: Map::new("defunct_lienholders"),
             active_external: Map::new("active_external"),
+            paused: Item::new("paused"),
+            pause_authority: Item::new("pause_authority"),
+            swap_adapter: Item::new("swap_adapter"),
+            pending_swap: Item::new("pending_swap"),
+            slash_dust_policy: Item::new("slash_dust_policy"),
+            slash_dust: Map::new("slash_dust"),
+            dormancy_period: Item::new("dormancy_period"),
+            dormancy_since: Item::new("dormancy_since"),
+            last_activity: Map::new("last_activity"),
+            dormant_accounts: Map::new("dormant_accounts"),
+            tx_history: Map::new("tx_history"),
+            tx_history_summaries: Map::new("tx_history_summaries"),
+            fee_disbursement: Item::new("fee_disbursement"),
+            fee_buckets: Map::new("fee_buckets"),
+            last_fee_disbursement: Item::new("last_fee_disbursement"),
         }
     }
 
@@ -89,6 +251,16 @@ impl VaultContract<'_> {
         Ok(id)
     }
 
+    pub fn next_unbond_request_id(&self, store: &mut dyn Storage) -> StdResult<u64> {
+        let id: u64 = self
+            .unbond_request_count
+            .may_load(store)?
+            .unwrap_or_default()
+            + 1;
+        self.unbond_request_count.save(store, &id)?;
+        Ok(id)
+    }
+
     #[sv::msg(instantiate)]
     pub fn instantiate(
         &self,
@@ -98,6 +270,7 @@ impl VaultContract<'_> {
     ) -> Result<Response<ProviderCustomMsg>, ContractError> {
         nonpayable(&ctx.info)?;
 
+        self.collateral_denoms.save(ctx.deps.storage, &denom, &())?;
         let config = Config { denom };
         self.config.save(ctx.deps.storage, &config)?;
         set_contract_version(ctx.deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
@@ -124,14 +297,26 @@ impl VaultContract<'_> {
                 }
                 LocalStakingInfo::New(local_staking) => {
                     // instantiate local_staking and handle reply
-                    let msg = WasmMsg::Instantiate {
-                        admin: local_staking.admin,
-                        code_id: local_staking.code_id,
-                        msg: local_staking.msg,
-                        funds: vec![],
-                        label: local_staking
-                            .label
-                            .unwrap_or_else(|| "Mesh Security Local Staking".to_string()),
+                    let label = local_staking
+                        .label
+                        .unwrap_or_else(|| "Mesh Security Local Staking".to_string());
+                    // With a salt, the local staking address is known before instantiation
+                    let msg = match local_staking.salt {
+                        Some(salt) => WasmMsg::Instantiate2 {
+                            admin: local_staking.admin,
+                            code_id: local_staking.code_id,
+                            label,
+                            msg: local_staking.msg,
+                            funds: vec![],
+                            salt,
+                        },
+                        None => WasmMsg::Instantiate {
+                            admin: local_staking.admin,
+                            code_id: local_staking.code_id,
+                            msg: local_staking.msg,
+                            funds: vec![],
+                            label,
+                        },
                     };
                     let sub_msg = SubMsg::reply_on_success(msg, REPLY_ID_INSTANTIATE);
                     Ok(Response::new().add_submessage(sub_msg))
@@ -143,23 +328,89 @@ impl VaultContract<'_> {
         }
     }
 
+    /// Migrates the single-denom accounting to per-denom accounting.
+    ///
+    /// User collateral stored under the old `users` key is re-keyed under the primary denom,
+    /// and liens taken before they recorded a denom are assigned the primary denom.
+    /// The running count of pending txs is recomputed, for txs pending before it was tracked.
+    #[sv::msg(migrate)]
+    pub fn migrate(&self, ctx: MigrateCtx) -> Result<Response<ProviderCustomMsg>, ContractError> {
+        let denom = self.config.load(ctx.deps.storage)?.denom;
+        self.collateral_denoms.save(ctx.deps.storage, &denom, &())?;
+
+        let legacy_users: Map<&Addr, UserInfo> = Map::new("users");
+        let owners = legacy_users
+            .keys_raw(ctx.deps.storage, None, None, Order::Ascending)
+            // The pending txs user index shares the namespace. Its keys are length-prefixed, so
+            // they start with a zero byte, unlike addresses
+            .filter(|key| key.first() != Some(&0))
+            .map(|key| String::from_utf8(key).map(Addr::unchecked))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(StdError::from)?;
+        for owner in owners {
+            let user = legacy_users.load(ctx.deps.storage, &owner)?;
+            self.save_user_at(
+                ctx.deps.storage,
+                (&owner, &denom),
+                &user,
+                ctx.env.block.height,
+            )?;
+            legacy_users.remove(ctx.deps.storage, &owner);
+        }
+
+        let liens = self
+            .liens
+            .range(ctx.deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        for ((user, lienholder), mut lien) in liens {
+            if lien.denom.is_empty() {
+                lien.denom = denom.clone();
+            }
+            // Also indexes liens taken before the lienholder index existed
+            self.liens
+                .save(ctx.deps.storage, (&user, &lienholder), &lien)?;
+        }
+
+        self.pending.seed_count(ctx.deps.storage)?;
+        self.seed_totals(ctx.deps.storage)?;
+
+        set_contract_version(ctx.deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+        Ok(Response::new())
+    }
+
+    /// Bonds `amount` as collateral, paid by the sender.
+    ///
+    /// The collateral is credited to `on_behalf_of` if given, or to the sender otherwise
     #[sv::msg(exec)]
     fn bond(
         &self,
         ctx: ExecCtx,
         amount: Coin,
+        on_behalf_of: Option<String>,
     ) -> Result<Response<ProviderCustomMsg>, ContractError> {
         nonpayable(&ctx.info)?;
+        self.record_activity(ctx.deps.storage, &ctx.env, &ctx.info.sender)?;
+        self.ensure_not_paused(ctx.deps.storage, PausableOperation::Bond)?;
 
-        let denom = self.config.load(ctx.deps.storage)?.denom;
-        ensure!(denom == amount.denom, ContractError::UnexpectedDenom(denom));
+        // CW20 collateral is bonded through `receive`
+        ensure!(
+            cw20_token(&amount.denom).is_none(),
+            ContractError::Cw20Denom(amount.denom)
+        );
+        self.ensure_collateral_denom(ctx.deps.storage, &amount.denom)?;
 
+        let owner = match on_behalf_of {
+            Some(owner) => ctx.deps.api.addr_validate(&owner)?,
+            None => ctx.info.sender.clone(),
+        };
+        let key = (&owner, amount.denom.as_str());
         let mut user = self
             .users
-            .may_load(ctx.deps.storage, &ctx.info.sender)?
+            .may_load(ctx.deps.storage, key)?
             .unwrap_or_default();
         user.collateral += amount.amount;
-        self.users.save(ctx.deps.storage, &ctx.info.sender, &user)?;
+        self.save_user_at(ctx.deps.storage, key, &user, ctx.env.block.height)?;
         let amt = amount.amount;
         let msg = ProviderMsg::Bond {
             delegator: ctx.info.sender.clone().into_string(),
@@ -169,6 +420,7 @@ impl VaultContract<'_> {
             .add_message(msg)
             .add_attribute("action", "unbond")
             .add_attribute("sender", ctx.info.sender)
+            .add_attribute("owner", owner)
             .add_attribute("amount", amt.to_string());
 
         Ok(resp)
@@ -181,30 +433,28 @@ impl VaultContract<'_> {
         amount: Coin,
     ) -> Result<Response<ProviderCustomMsg>, ContractError> {
         nonpayable(&ctx.info)?;
+        self.record_activity(ctx.deps.storage, &ctx.env, &ctx.info.sender)?;
+        self.ensure_not_paused(ctx.deps.storage, PausableOperation::Unbond)?;
 
-        let denom = self.config.load(ctx.deps.storage)?.denom;
-        ensure!(denom == amount.denom, ContractError::UnexpectedDenom(denom));
-
+        // Collateral of denoms no longer accepted can still be unbonded
+        let key = (&ctx.info.sender, amount.denom.as_str());
         let mut user = self
             .users
-            .may_load(ctx.deps.storage, &ctx.info.sender)?
+            .may_load(ctx.deps.storage, key)?
             .unwrap_or_default();
 
-        let free_collateral = user.free_collateral();
+        let rate = self.collateral_rate(ctx.deps.storage, &amount.denom)?;
+        let free_collateral = user.valued(rate).free_collateral();
         ensure!(
-            free_collateral.low() >= amount.amount,
+            free_collateral.low() >= amount.amount.mul_ceil(rate),
             ContractError::ClaimsLocked(free_collateral)
         );
 
         user.collateral -= amount.amount;
-        self.users.save(ctx.deps.storage, &ctx.info.sender, &user)?;
+        self.save_user_at(ctx.deps.storage, key, &user, ctx.env.block.height)?;
         let amt = amount.amount;
-        let msg = ProviderMsg::Unbond {
-            delegator: ctx.info.sender.clone().into_string(),
-            amount,
-        };
         let resp = Response::new()
-            .add_message(msg)
+            .add_message(self.unbond_msg(&ctx.info.sender, amount)?)
             .add_attribute("action", "unbond")
             .add_attribute("sender", ctx.info.sender)
             .add_attribute("amount", amt.to_string());
@@ -212,6 +462,144 @@ impl VaultContract<'_> {
         Ok(resp)
     }
 
+    /// Moves free collateral of the sender to the `recipient` account, without unbonding it.
+    ///
+    /// Both accounts have to keep covering their liens afterwards.
+    #[sv::msg(exec)]
+    fn transfer_collateral(
+        &self,
+        ctx: ExecCtx,
+        recipient: String,
+        amount: Coin,
+    ) -> Result<Response<ProviderCustomMsg>, ContractError> {
+        nonpayable(&ctx.info)?;
+        self.record_activity(ctx.deps.storage, &ctx.env, &ctx.info.sender)?;
+        self.ensure_not_paused(ctx.deps.storage, PausableOperation::Unbond)?;
+        self.ensure_not_paused(ctx.deps.storage, PausableOperation::Bond)?;
+        // The recipient bonds it anew, so it has to be accepted collateral
+        self.ensure_collateral_denom(ctx.deps.storage, &amount.denom)?;
+        let recipient = ctx.deps.api.addr_validate(&recipient)?;
+        let rate = self.collateral_rate(ctx.deps.storage, &amount.denom)?;
+
+        let sender_key = (&ctx.info.sender, amount.denom.as_str());
+        let mut sender = self
+            .users
+            .may_load(ctx.deps.storage, sender_key)?
+            .unwrap_or_default();
+        let free_collateral = sender.valued(rate).free_collateral();
+        ensure!(
+            free_collateral.low() >= amount.amount.mul_ceil(rate),
+            ContractError::ClaimsLocked(free_collateral)
+        );
+        sender.collateral -= amount.amount;
+        ensure!(
+            sender.valued(rate).verify_collateral(),
+            ContractError::InsufficentBalance
+        );
+        self.save_user_at(ctx.deps.storage, sender_key, &sender, ctx.env.block.height)?;
+
+        let recipient_key = (&recipient, amount.denom.as_str());
+        let mut user = self
+            .users
+            .may_load(ctx.deps.storage, recipient_key)?
+            .unwrap_or_default();
+        user.collateral += amount.amount;
+        ensure!(
+            user.valued(rate).verify_collateral(),
+            ContractError::InsufficentBalance
+        );
+        self.save_user_at(ctx.deps.storage, recipient_key, &user, ctx.env.block.height)?;
+
+        let resp = Response::new()
+            .add_attribute("action", "transfer_collateral")
+            .add_attribute("sender", ctx.info.sender)
+            .add_attribute("recipient", recipient)
+            .add_attribute("amount", amount.to_string());
+
+        Ok(resp)
+    }
+
+    /// Requests to unbond collateral, even if it is currently under lien.
+    ///
+    /// The request is fulfilled as far as the collateral is free, right away and then whenever
+    /// liens of the sender are released. Requests are fulfilled in order, and fulfilled
+    /// collateral can be withdrawn with `claim_unbonded`.
+    #[sv::msg(exec)]
+    fn request_unbond(
+        &self,
+        ctx: ExecCtx,
+        amount: Coin,
+    ) -> Result<Response<ProviderCustomMsg>, ContractError> {
+        nonpayable(&ctx.info)?;
+        self.record_activity(ctx.deps.storage, &ctx.env, &ctx.info.sender)?;
+        self.ensure_not_paused(ctx.deps.storage, PausableOperation::Unbond)?;
+        ensure!(!amount.amount.is_zero(), ContractError::ZeroUnbondRequest);
+
+        // Collateral can only be requested once
+        let user = self
+            .users
+            .may_load(ctx.deps.storage, (&ctx.info.sender, &amount.denom))?
+            .unwrap_or_default();
+        let requested =
+            self.requested_unbonds(ctx.deps.storage, &ctx.info.sender, &amount.denom)?;
+        ensure!(
+            requested + amount.amount <= user.collateral,
+            ContractError::InsufficentBalance
+        );
+
+        let request_id = self.next_unbond_request_id(ctx.deps.storage)?;
+        let request = UnbondRequest {
+            denom: amount.denom.clone(),
+            amount: amount.amount,
+        };
+        self.unbond_requests
+            .save(ctx.deps.storage, (&ctx.info.sender, request_id), &request)?;
+        let fulfilled = self.fulfil_unbond_requests(
+            ctx.deps.storage,
+            &ctx.info.sender,
+            &amount.denom,
+            ctx.env.block.height,
+        )?;
+
+        let resp = Response::new()
+            .add_attribute("action", "request_unbond")
+            .add_attribute("sender", ctx.info.sender)
+            .add_attribute("amount", amount.to_string())
+            .add_attribute("request_id", request_id.to_string())
+            .add_attribute("fulfilled", fulfilled.to_string());
+
+        Ok(resp)
+    }
+
+    /// Withdraws the `denom` collateral unbonded by fulfilled unbond requests
+    #[sv::msg(exec)]
+    fn claim_unbonded(
+        &self,
+        ctx: ExecCtx,
+        denom: String,
+    ) -> Result<Response<ProviderCustomMsg>, ContractError> {
+        nonpayable(&ctx.info)?;
+        self.record_activity(ctx.deps.storage, &ctx.env, &ctx.info.sender)?;
+        self.ensure_not_paused(ctx.deps.storage, PausableOperation::Unbond)?;
+
+        let key = (&ctx.info.sender, denom.as_str());
+        let amount = self
+            .unbonded
+            .may_load(ctx.deps.storage, key)?
+            .unwrap_or_default();
+        ensure!(!amount.is_zero(), ContractError::NoClaim);
+        self.unbonded.remove(ctx.deps.storage, key);
+
+        let amount = coin(amount.u128(), denom);
+        let resp = Response::new()
+            .add_message(self.unbond_msg(&ctx.info.sender, amount.clone())?)
+            .add_attribute("action", "claim_unbonded")
+            .add_attribute("sender", ctx.info.sender)
+            .add_attribute("amount", amount.to_string());
+
+        Ok(resp)
+    }
+
     #[sv::msg(exec)]
     fn restake(
         &self,
@@ -220,16 +608,19 @@ impl VaultContract<'_> {
         validator: String,
     ) -> Result<Response<ProviderCustomMsg>, ContractError> {
         nonpayable(&ctx.info)?;
+        self.ensure_not_paused(ctx.deps.storage, PausableOperation::Bond)?;
+        self.ensure_not_paused(ctx.deps.storage, PausableOperation::StakeLocal)?;
 
         let denom = self.config.load(ctx.deps.storage)?.denom;
         ensure!(denom == amount.denom, ContractError::UnexpectedDenom(denom));
 
+        let key = (&ctx.info.sender, amount.denom.as_str());
         let mut user = self
             .users
-            .may_load(ctx.deps.storage, &ctx.info.sender)?
+            .may_load(ctx.deps.storage, key)?
             .unwrap_or_default();
         user.collateral += amount.amount;
-        self.users.save(ctx.deps.storage, &ctx.info.sender, &user)?;
+        self.save_user_at(ctx.deps.storage, key, &user, ctx.env.block.height)?;
 
         let amt = amount.amount;
         let mut resp = Response::new()
@@ -243,11 +634,11 @@ impl VaultContract<'_> {
         };
         resp = resp.add_message(restake_msg);
 
-        let config = self.config.load(ctx.deps.storage)?;
         if let Some(local_staking) = self.local_staking.load(ctx.deps.storage)? {
+            let owner = ctx.info.sender.clone();
             self.stake(
                 &mut ctx,
-                &config,
+                &owner,
                 &local_staking.contract.0,
                 local_staking.max_slash,
                 amount.clone(),
@@ -267,28 +658,115 @@ impl VaultContract<'_> {
         }
     }
 
+    /// CW20 receive hook. Bonds the sent tokens as collateral of `sender`.
+    ///
+    /// Only token contracts accepted as collateral by governance can call it.
+    #[sv::msg(exec)]
+    fn receive(
+        &self,
+        ctx: ExecCtx,
+        sender: String,
+        amount: Uint128,
+        msg: Binary,
+    ) -> Result<Response<ProviderCustomMsg>, ContractError> {
+        nonpayable(&ctx.info)?;
+        self.ensure_not_paused(ctx.deps.storage, PausableOperation::Bond)?;
+
+        let denom = cw20_denom(&ctx.info.sender);
+        self.ensure_collateral_denom(ctx.deps.storage, &denom)?;
+        let sender = ctx.deps.api.addr_validate(&sender)?;
+        self.record_activity(ctx.deps.storage, &ctx.env, &sender)?;
+
+        match from_json(msg)? {
+            Cw20HookMsg::Bond {} => {
+                let key = (&sender, denom.as_str());
+                let mut user = self
+                    .users
+                    .may_load(ctx.deps.storage, key)?
+                    .unwrap_or_default();
+                user.collateral += amount;
+                self.save_user_at(ctx.deps.storage, key, &user, ctx.env.block.height)?;
+            }
+        }
+
+        let resp = Response::new()
+            .add_attribute("action", "bond")
+            .add_attribute("sender", sender)
+            .add_attribute("denom", denom)
+            .add_attribute("amount", amount.to_string());
+
+        Ok(resp)
+    }
+
     /// This assigns a claim of amount tokens to the remote contract, which can take some action with it
     #[sv::msg(exec)]
     fn stake_remote(
+        &self,
+        ctx: ExecCtx,
+        // address of the contract to virtually stake on
+        contract: String,
+        // amount to stake on that contract
+        amount: Coin,
+        // action to take with that stake
+        msg: Binary,
+        // optional memo, e.g. an internal ledger reference
+        memo: Option<String>,
+    ) -> Result<Response<ProviderCustomMsg>, ContractError> {
+        let owner = ctx.info.sender.clone();
+        self.stake_remote_for(ctx, owner, contract, amount, msg, memo)
+    }
+
+    /// Stakes remotely the collateral of `owner`, spending the approval `owner` gave the sender.
+    /// The spent approval is not restored if the stake is rolled back.
+    #[sv::msg(exec)]
+    fn stake_remote_from(
+        &self,
+        ctx: ExecCtx,
+        owner: String,
+        contract: String,
+        amount: Coin,
+        msg: Binary,
+        memo: Option<String>,
+    ) -> Result<Response<ProviderCustomMsg>, ContractError> {
+        let owner = ctx.deps.api.addr_validate(&owner)?;
+        let operator = ctx.info.sender.clone();
+        self.spend_approval(ctx.deps.storage, &ctx.env, &owner, &operator, &amount)?;
+
+        let resp = self
+            .stake_remote_for(ctx, owner.clone(), contract, amount, msg, memo)?
+            .add_attribute("owner", owner);
+
+        Ok(resp)
+    }
+
+    fn stake_remote_for(
         &self,
         mut ctx: ExecCtx,
+        owner: Addr,
         // address of the contract to virtually stake on
         contract: String,
         // amount to stake on that contract
         amount: Coin,
         // action to take with that stake
         msg: Binary,
+        // optional memo, e.g. an internal ledger reference
+        memo: Option<String>,
     ) -> Result<Response<ProviderCustomMsg>, ContractError> {
         nonpayable(&ctx.info)?;
+        self.ensure_memo(&memo)?;
+        self.ensure_not_paused(ctx.deps.storage, PausableOperation::StakeRemote)?;
 
-        let config = self.config.load(ctx.deps.storage)?;
         let contract = ctx.deps.api.addr_validate(&contract)?;
+        ensure!(
+            self.allowed_lienholders.has(ctx.deps.storage, &contract),
+            ContractError::LienholderNotAllowed(contract.into_string())
+        );
         let contract = CrossStakingApiHelper(contract);
         let slashable = contract.max_slash(ctx.deps.as_ref())?;
 
         let tx_id = self.stake(
             &mut ctx,
-            &config,
+            &owner,
             &contract.0,
             slashable.slash_ratio_dsign,
             amount.clone(),
@@ -296,7 +774,7 @@ impl VaultContract<'_> {
         )?;
 
         let stake_msg = contract.receive_virtual_stake(
-            ctx.info.sender.to_string(),
+            owner.to_string(),
             amount.clone(),
             tx_id,
             msg,
@@ -306,12 +784,16 @@ impl VaultContract<'_> {
         self.active_external
             .save(ctx.deps.storage, &contract.0, &())?;
 
-        let resp = Response::new()
+        let mut resp = Response::new()
             .add_message(stake_msg)
             .add_attribute("action", "stake_remote")
             .add_attribute("sender", ctx.info.sender)
             .add_attribute("amount", amount.amount.to_string())
             .add_attribute("tx_id", tx_id.to_string());
+        if let Some(memo) = memo {
+            self.tx_memos.save(ctx.deps.storage, tx_id, &memo)?;
+            resp = resp.add_attribute("memo", memo);
+        }
 
         Ok(resp)
     }
@@ -319,20 +801,63 @@ impl VaultContract<'_> {
     /// This sends actual tokens to the local staking contract
     #[sv::msg(exec)]
     fn stake_local(
+        &self,
+        ctx: ExecCtx,
+        // amount to stake on that contract
+        amount: Coin,
+        // action to take with that stake
+        msg: Binary,
+        // optional memo, e.g. an internal ledger reference
+        memo: Option<String>,
+    ) -> Result<Response<ProviderCustomMsg>, ContractError> {
+        let owner = ctx.info.sender.clone();
+        self.stake_local_for(ctx, owner, amount, msg, memo)
+    }
+
+    /// Stakes locally the collateral of `owner`, spending the approval `owner` gave the sender.
+    #[sv::msg(exec)]
+    fn stake_local_from(
+        &self,
+        ctx: ExecCtx,
+        owner: String,
+        amount: Coin,
+        msg: Binary,
+        memo: Option<String>,
+    ) -> Result<Response<ProviderCustomMsg>, ContractError> {
+        let owner = ctx.deps.api.addr_validate(&owner)?;
+        let operator = ctx.info.sender.clone();
+        self.spend_approval(ctx.deps.storage, &ctx.env, &owner, &operator, &amount)?;
+
+        let resp = self
+            .stake_local_for(ctx, owner.clone(), amount, msg, memo)?
+            .add_attribute("owner", owner);
+
+        Ok(resp)
+    }
+
+    fn stake_local_for(
         &self,
         mut ctx: ExecCtx,
+        owner: Addr,
         // amount to stake on that contract
         amount: Coin,
         // action to take with that stake
         msg: Binary,
+        // optional memo, e.g. an internal ledger reference
+        memo: Option<String>,
     ) -> Result<Response<ProviderCustomMsg>, ContractError> {
         nonpayable(&ctx.info)?;
+        self.ensure_memo(&memo)?;
+        self.ensure_not_paused(ctx.deps.storage, PausableOperation::StakeLocal)?;
+        ensure!(
+            cw20_token(&amount.denom).is_none(),
+            ContractError::Cw20Denom(amount.denom)
+        );
 
-        let config = self.config.load(ctx.deps.storage)?;
         if let Some(local_staking) = self.local_staking.load(ctx.deps.storage)? {
             self.stake(
                 &mut ctx,
-                &config,
+                &owner,
                 &local_staking.contract.0,
                 local_staking.max_slash,
                 amount.clone(),
@@ -340,16 +865,19 @@ impl VaultContract<'_> {
             )?;
 
             let stake_msg = local_staking.contract.receive_stake(
-                ctx.info.sender.to_string(),
+                owner.to_string(),
                 msg,
                 vec![amount.clone()],
             )?;
 
-            let resp = Response::new()
+            let mut resp = Response::new()
                 .add_message(stake_msg)
                 .add_attribute("action", "stake_local")
                 .add_attribute("sender", ctx.info.sender)
                 .add_attribute("amount", amount.amount.to_string());
+            if let Some(memo) = memo {
+                resp = resp.add_attribute("memo", memo);
+            }
 
             Ok(resp)
         } else {
@@ -357,6 +885,105 @@ impl VaultContract<'_> {
         }
     }
 
+    /// Allows `operator` to stake up to `spend_limit` of the sender's collateral on their behalf,
+    /// via `stake_remote_from` and `stake_local_from`, until `expires` (never by default).
+    /// Replaces any previous approval of `operator` in the same denom.
+    #[sv::msg(exec)]
+    fn approve_operator(
+        &self,
+        ctx: ExecCtx,
+        operator: String,
+        spend_limit: Coin,
+        expires: Option<Expiration>,
+    ) -> Result<Response<ProviderCustomMsg>, ContractError> {
+        nonpayable(&ctx.info)?;
+        self.record_activity(ctx.deps.storage, &ctx.env, &ctx.info.sender)?;
+        let operator = ctx.deps.api.addr_validate(&operator)?;
+        ensure!(operator != ctx.info.sender, ContractError::SelfApproval);
+        self.ensure_collateral_denom(ctx.deps.storage, &spend_limit.denom)?;
+        let expires = expires.unwrap_or_default();
+        ensure!(
+            !expires.is_expired(&ctx.env.block),
+            ContractError::ApprovalExpired
+        );
+
+        let approval = Approval {
+            spend_limit: spend_limit.amount,
+            expires,
+        };
+        self.approvals.save(
+            ctx.deps.storage,
+            (&ctx.info.sender, &operator, spend_limit.denom.as_str()),
+            &approval,
+        )?;
+
+        let resp = Response::new()
+            .add_attribute("action", "approve_operator")
+            .add_attribute("owner", ctx.info.sender)
+            .add_attribute("operator", operator)
+            .add_attribute("spend_limit", spend_limit.to_string())
+            .add_attribute("expires", expires.to_string());
+
+        Ok(resp)
+    }
+
+    /// Removes the approval of `operator` to stake the sender's collateral in `denom`
+    #[sv::msg(exec)]
+    fn revoke_operator(
+        &self,
+        ctx: ExecCtx,
+        operator: String,
+        denom: String,
+    ) -> Result<Response<ProviderCustomMsg>, ContractError> {
+        nonpayable(&ctx.info)?;
+        self.record_activity(ctx.deps.storage, &ctx.env, &ctx.info.sender)?;
+        let operator = ctx.deps.api.addr_validate(&operator)?;
+        self.approvals.remove(
+            ctx.deps.storage,
+            (&ctx.info.sender, &operator, denom.as_str()),
+        );
+
+        let resp = Response::new()
+            .add_attribute("action", "revoke_operator")
+            .add_attribute("owner", ctx.info.sender)
+            .add_attribute("operator", operator)
+            .add_attribute("denom", denom);
+
+        Ok(resp)
+    }
+
+    /// Bonds `amount` as collateral and stakes it right away, in a single atomic flow.
+    ///
+    /// Stakes remotely on `contract` if given, locally otherwise. Remote stakes go through the
+    /// same pending tx handling as `stake_remote`.
+    #[sv::msg(exec)]
+    fn bond_and_stake(
+        &self,
+        mut ctx: ExecCtx,
+        amount: Coin,
+        // address of the contract to virtually stake on, if staking remotely
+        contract: Option<String>,
+        // action to take with that stake
+        msg: Binary,
+        // optional memo, e.g. an internal ledger reference
+        memo: Option<String>,
+    ) -> Result<Response<ProviderCustomMsg>, ContractError> {
+        let bond = self.bond(ctx.branch(), amount.clone(), None)?;
+        let stake = match contract {
+            Some(contract) => self.stake_remote(ctx, contract, amount, msg, memo)?,
+            None => self.stake_local(ctx, amount, msg, memo)?,
+        };
+
+        let resp = Response::new()
+            .add_submessages(bond.messages)
+            .add_submessages(stake.messages)
+            .add_attributes(bond.attributes)
+            .add_attributes(stake.attributes);
+
+        Ok(resp)
+    }
+
+    /// Returns the account collateral in the primary denom
     #[sv::msg(query)]
     fn account(&self, ctx: QueryCtx, account: String) -> Result<AccountResponse, ContractError> {
         let denom = self.config.load(ctx.deps.storage)?.denom;
@@ -364,7 +991,7 @@ impl VaultContract<'_> {
 
         let user = self
             .users
-            .may_load(ctx.deps.storage, &account)?
+            .may_load(ctx.deps.storage, (&account, &denom))?
             .unwrap_or_default();
         Ok(AccountResponse {
             denom,
@@ -373,6 +1000,7 @@ impl VaultContract<'_> {
         })
     }
 
+    /// Returns the account collateral details in the primary denom
     #[sv::msg(query)]
     fn account_details(
         &self,
@@ -384,41 +1012,124 @@ impl VaultContract<'_> {
 
         let user = self
             .users
-            .may_load(ctx.deps.storage, &account)?
+            .may_load(ctx.deps.storage, (&account, &denom))?
             .unwrap_or_default();
+        let liens = self.account_lien_details(ctx.deps.storage, &account, &denom)?;
         Ok(AccountDetailsResponse {
             denom,
             bonded: user.collateral,
             free: user.free_collateral(),
             max_lien: user.max_lien,
             total_slashable: user.total_slashable,
+            liens,
         })
     }
 
+    /// Returns the account collateral details for every denom it has bonded
     #[sv::msg(query)]
-    fn config(&self, ctx: QueryCtx) -> Result<ConfigResponse, ContractError> {
-        let config = self.config.load(ctx.deps.storage)?;
-        let local_staking = self.local_staking.load(ctx.deps.storage)?;
+    fn account_collateral(
+        &self,
+        ctx: QueryCtx,
+        account: String,
+    ) -> Result<AccountCollateralResponse, ContractError> {
+        let account = ctx.deps.api.addr_validate(&account)?;
 
-        let resp = ConfigResponse {
-            denom: config.denom,
-            local_staking: local_staking.map(|ls| ls.contract.0.into()),
-        };
+        let collateral = self
+            .users
+            .prefix(&account)
+            .range(ctx.deps.storage, None, None, Order::Ascending)
+            .map(|item| {
+                let (denom, user) = item?;
+                let rate = self.collateral_rate(ctx.deps.storage, &denom)?;
+                let liens = self.account_lien_details(ctx.deps.storage, &account, &denom)?;
+                Ok::<_, ContractError>(AccountDetailsResponse {
+                    free: user.valued(rate).free_collateral(),
+                    denom,
+                    bonded: user.collateral,
+                    max_lien: user.max_lien,
+                    total_slashable: user.total_slashable,
+                    liens,
+                })
+            })
+            .collect::<Result<_, _>>()?;
 
-        Ok(resp)
+        Ok(AccountCollateralResponse { collateral })
     }
 
+    /// Returns the unbond requests of an account still waiting for collateral, and the
+    /// collateral it can already claim
     #[sv::msg(query)]
-    fn active_external_staking(
+    fn pending_unbonds(
         &self,
         ctx: QueryCtx,
-    ) -> Result<AllActiveExternalStakingResponse, ContractError> {
-        let active = self
-            .active_external
-            .keys(ctx.deps.storage, None, None, Order::Ascending)
-            .collect::<StdResult<Vec<_>>>()?;
+        account: String,
+    ) -> Result<PendingUnbondsResponse, ContractError> {
+        let account = ctx.deps.api.addr_validate(&account)?;
 
-        let resp = AllActiveExternalStakingResponse {
+        let requests = self
+            .unbond_requests
+            .prefix(&account)
+            .range(ctx.deps.storage, None, None, Order::Ascending)
+            .map(|item| {
+                let (id, request) = item?;
+                Ok::<_, ContractError>(UnbondRequestResponse {
+                    id,
+                    denom: request.denom,
+                    amount: request.amount,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        let claimable = self
+            .unbonded
+            .prefix(&account)
+            .range(ctx.deps.storage, None, None, Order::Ascending)
+            .map(|item| {
+                let (denom, amount) = item?;
+                Ok(Coin { denom, amount })
+            })
+            .collect::<StdResult<_>>()?;
+
+        Ok(PendingUnbondsResponse {
+            requests,
+            claimable,
+        })
+    }
+
+    /// Returns the denoms currently accepted as collateral
+    #[sv::msg(query)]
+    fn collateral_denoms(&self, ctx: QueryCtx) -> Result<CollateralDenomsResponse, ContractError> {
+        let denoms = self
+            .collateral_denoms
+            .keys(ctx.deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<_>>()?;
+
+        Ok(CollateralDenomsResponse { denoms })
+    }
+
+    #[sv::msg(query)]
+    fn config(&self, ctx: QueryCtx) -> Result<ConfigResponse, ContractError> {
+        let config = self.config.load(ctx.deps.storage)?;
+        let local_staking = self.local_staking.load(ctx.deps.storage)?;
+
+        let resp = ConfigResponse {
+            denom: config.denom,
+            local_staking: local_staking.map(|ls| ls.contract.0.into()),
+        };
+
+        Ok(resp)
+    }
+
+    #[sv::msg(query)]
+    fn active_external_staking(
+        &self,
+        ctx: QueryCtx,
+    ) -> Result<AllActiveExternalStakingResponse, ContractError> {
+        let active = self
+            .active_external
+            .keys(ctx.deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+
+        let resp = AllActiveExternalStakingResponse {
             contracts: active.into_iter().map(|addr| addr.to_string()).collect(),
         };
 
@@ -441,6 +1152,41 @@ impl VaultContract<'_> {
             .ok_or(ContractError::NoClaim)
     }
 
+    /// Returns the collateral bonded by an account at the beginning of block `height`, in all
+    /// collateral denoms it ever bonded.
+    ///
+    /// Only collateral changes since the history was introduced are recorded, earlier bonds are
+    /// reported as zero.
+    #[sv::msg(query)]
+    fn account_at_height(
+        &self,
+        ctx: QueryCtx,
+        account: String,
+        height: u64,
+    ) -> Result<AccountAtHeightResponse, ContractError> {
+        let account = ctx.deps.api.addr_validate(&account)?;
+        let denoms = self
+            .users
+            .prefix(&account)
+            .keys(ctx.deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        let collateral = denoms
+            .into_iter()
+            .map(|denom| {
+                let amount = self
+                    .collateral_history
+                    .may_load_at_height(ctx.deps.storage, (&account, &denom), height)?
+                    .unwrap_or_default();
+                Ok(Coin { denom, amount })
+            })
+            .collect::<StdResult<Vec<_>>>()?
+            .into_iter()
+            .filter(|coin| !coin.amount.is_zero())
+            .collect();
+
+        Ok(AccountAtHeightResponse { height, collateral })
+    }
+
     /// Returns paginated claims list for an user
     ///
     /// `start_after` is a last lienholder of the previous page, and it will not be included
@@ -465,6 +1211,7 @@ impl VaultContract<'_> {
                 let (lienholder, lien) = item?;
                 Ok::<_, ContractError>(LienResponse {
                     lienholder: lienholder.to_string(),
+                    denom: lien.denom,
                     amount: lien.amount,
                 })
             })
@@ -476,89 +1223,1669 @@ impl VaultContract<'_> {
         Ok(resp)
     }
 
+    /// Returns paginated list of liens held by a lienholder, together with their users
+    ///
+    /// `start_after` is a last user of the previous page, and it will not be included
+    #[sv::msg(query)]
+    fn liens_by_lienholder(
+        &self,
+        ctx: QueryCtx,
+        lienholder: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> Result<LiensByLienholderResponse, ContractError> {
+        let limit = clamp_page_limit(limit);
+        let lienholder = ctx.deps.api.addr_validate(&lienholder)?;
+        let bound =
+            start_after.map(|user| Bound::exclusive((Addr::unchecked(user), lienholder.clone())));
+
+        let liens = self
+            .liens
+            .idx
+            .lienholder
+            .prefix(lienholder)
+            .range(ctx.deps.storage, bound, None, Order::Ascending)
+            .map(|item| {
+                let ((user, _), lien) = item?;
+                Ok::<_, ContractError>(LienholderLienResponse {
+                    user: user.into_string(),
+                    denom: lien.denom,
+                    amount: lien.amount,
+                })
+            })
+            .take(limit)
+            .collect::<Result<_, _>>()?;
+
+        Ok(LiensByLienholderResponse { liens })
+    }
+
     /// Queries for all users ever performing action in the system, paginating over
     /// them.
     ///
     /// `start_after` is the last account included in previous page
     ///
     /// `with_collateral` flag filters out users with no collateral, defaulted to false
+    ///
+    /// `lienholder` filters out accounts with no lien with this lienholder
+    ///
+    /// Every user is reported once per collateral denom, and `limit` applies to users, so a
+    /// single user's denoms are never split across pages
     #[sv::msg(query)]
     fn all_accounts(
         &self,
-        ctx: QueryCtx,
-        #[serde(default = "def_false")] with_collateral: bool,
-        start_after: Option<String>,
-        limit: Option<u32>,
-    ) -> Result<AllAccountsResponse, ContractError> {
-        let limit = clamp_page_limit(limit);
-        let start_after = start_after.map(Addr::unchecked);
-        let bound = start_after.as_ref().and_then(Bounder::exclusive_bound);
+        ctx: QueryCtx,
+        #[serde(default = "def_false")] with_collateral: bool,
+        start_after: Option<String>,
+        limit: Option<u32>,
+        lienholder: Option<String>,
+    ) -> Result<AllAccountsResponse, ContractError> {
+        let limit = clamp_page_limit(limit);
+        if let Some(lienholder) = lienholder {
+            let lienholder = ctx.deps.api.addr_validate(&lienholder)?;
+            return self.accounts_by_lienholder(
+                ctx.deps.storage,
+                lienholder,
+                with_collateral,
+                start_after,
+                limit,
+            );
+        }
+        let start_after = start_after.map(Addr::unchecked);
+        let bound = start_after.as_ref().map(PrefixBound::exclusive);
+
+        let mut accounts = vec![];
+        let mut users = 0;
+        let mut last_user = None;
+        for item in self
+            .users
+            .prefix_range(ctx.deps.storage, bound, None, Order::Ascending)
+        {
+            let ((addr, denom), account) = item?;
+            if with_collateral && account.collateral.is_zero() {
+                // Skip zero collateral
+                continue;
+            }
+            if last_user.as_ref() != Some(&addr) {
+                if users == limit {
+                    break;
+                }
+                users += 1;
+                last_user = Some(addr.clone());
+            }
+            let rate = self.collateral_rate(ctx.deps.storage, &denom)?;
+            accounts.push(AllAccountsResponseItem {
+                user: addr.to_string(),
+                account: AccountResponse {
+                    free: account.valued(rate).free_collateral(),
+                    denom,
+                    bonded: account.collateral,
+                },
+            });
+        }
+
+        let resp = AllAccountsResponse { accounts };
+
+        Ok(resp)
+    }
+
+    /// Accounts with a lien with `lienholder`, ranging over its liens index.
+    /// A user has a single lien per lienholder, so it is reported with a single account.
+    fn accounts_by_lienholder(
+        &self,
+        storage: &dyn Storage,
+        lienholder: Addr,
+        with_collateral: bool,
+        start_after: Option<String>,
+        limit: usize,
+    ) -> Result<AllAccountsResponse, ContractError> {
+        let bound =
+            start_after.map(|user| Bound::exclusive((Addr::unchecked(user), lienholder.clone())));
+
+        let mut accounts = vec![];
+        for item in self.liens.idx.lienholder.prefix(lienholder).range(
+            storage,
+            bound,
+            None,
+            Order::Ascending,
+        ) {
+            if accounts.len() == limit {
+                break;
+            }
+            let ((user, _), lien) = item?;
+            let account = self.users.load(storage, (&user, &lien.denom))?;
+            if with_collateral && account.collateral.is_zero() {
+                continue;
+            }
+            let rate = self.collateral_rate(storage, &lien.denom)?;
+            accounts.push(AllAccountsResponseItem {
+                user: user.into_string(),
+                account: AccountResponse {
+                    free: account.valued(rate).free_collateral(),
+                    denom: lien.denom,
+                    bonded: account.collateral,
+                },
+            });
+        }
+
+        Ok(AllAccountsResponse { accounts })
+    }
+
+    /// Queries a pending tx.
+    #[sv::msg(query)]
+    fn pending_tx(&self, ctx: QueryCtx, tx_id: u64) -> Result<TxResponse, ContractError> {
+        let resp = self.pending.txs.load(ctx.deps.storage, tx_id)?;
+        Ok(resp)
+    }
+
+    /// Returns the approval `owner` gave `operator` to stake their collateral in `denom`.
+    /// A missing approval is reported with a zero spend limit.
+    #[sv::msg(query)]
+    fn approval(
+        &self,
+        ctx: QueryCtx,
+        owner: String,
+        operator: String,
+        denom: String,
+    ) -> Result<ApprovalResponse, ContractError> {
+        let owner = ctx.deps.api.addr_validate(&owner)?;
+        let operator = ctx.deps.api.addr_validate(&operator)?;
+        let approval = self
+            .approvals
+            .may_load(ctx.deps.storage, (&owner, &operator, denom.as_str()))?;
+        let resp = match approval {
+            Some(approval) => ApprovalResponse {
+                spend_limit: coin(approval.spend_limit.u128(), denom),
+                expires: approval.expires,
+                expired: approval.expires.is_expired(&ctx.env.block),
+            },
+            None => ApprovalResponse {
+                spend_limit: coin(0, denom),
+                expires: Expiration::Never {},
+                expired: false,
+            },
+        };
+        Ok(resp)
+    }
+
+    /// Returns the memo attached to a pending tx, if any
+    #[sv::msg(query)]
+    fn tx_memo(&self, ctx: QueryCtx, tx_id: u64) -> Result<TxMemoResponse, ContractError> {
+        let memo = self.tx_memos.may_load(ctx.deps.storage, tx_id)?;
+        Ok(TxMemoResponse { memo })
+    }
+
+    /// Queries for all pending txs.
+    /// Reports txs in descending order (newest first).
+    /// `start_after` is the last tx id included in previous page
+    #[sv::msg(query)]
+    fn all_pending_txs_desc(
+        &self,
+        ctx: QueryCtx,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> Result<AllTxsResponse, ContractError> {
+        let limit = clamp_page_limit(limit);
+        let bound = start_after.and_then(Bounder::exclusive_bound);
+
+        let txs = self
+            .pending
+            .txs
+            .range(ctx.deps.storage, None, bound, Order::Descending)
+            .map(|item| {
+                let (_id, tx) = item?;
+                Ok::<AllTxsResponseItem, ContractError>(tx)
+            })
+            .take(limit)
+            .collect::<Result<_, _>>()?;
+
+        let resp = AllTxsResponse { txs };
+
+        Ok(resp)
+    }
+
+    /// Runs a batch of queries in a single call, to save front-ends round-trips.
+    /// Responses are returned in the order of `queries`. Fails if any of the queries fails.
+    #[sv::msg(query)]
+    fn multi_query(
+        &self,
+        ctx: QueryCtx,
+        queries: Vec<SubQuery>,
+    ) -> Result<MultiQueryResponse, ContractError> {
+        ensure!(
+            queries.len() <= MAX_MULTI_QUERIES,
+            ContractError::TooManyQueries(MAX_MULTI_QUERIES)
+        );
+
+        let responses = queries
+            .into_iter()
+            .map(|query| {
+                let ctx = QueryCtx {
+                    deps: ctx.deps,
+                    env: ctx.env.clone(),
+                };
+                let resp = match query {
+                    SubQuery::Account { account } => {
+                        SubQueryResponse::Account(self.account(ctx, account)?)
+                    }
+                    SubQuery::AccountDetails { account } => {
+                        SubQueryResponse::AccountDetails(self.account_details(ctx, account)?)
+                    }
+                    SubQuery::AccountClaims {
+                        account,
+                        start_after,
+                        limit,
+                    } => SubQueryResponse::AccountClaims(self.account_claims(
+                        ctx,
+                        account,
+                        start_after,
+                        limit,
+                    )?),
+                    SubQuery::PendingTx { tx_id } => {
+                        SubQueryResponse::PendingTx(self.pending_tx(ctx, tx_id)?)
+                    }
+                    SubQuery::AllPendingTxsDesc { start_after, limit } => {
+                        SubQueryResponse::AllPendingTxsDesc(self.all_pending_txs_desc(
+                            ctx,
+                            start_after,
+                            limit,
+                        )?)
+                    }
+                };
+                Ok(resp)
+            })
+            .collect::<Result<_, ContractError>>()?;
+
+        Ok(MultiQueryResponse { responses })
+    }
+
+    /// Adds a denom to the set of accepted collateral denoms.
+    /// Can only be called by governance (sudo).
+    #[sv::msg(sudo)]
+    fn add_collateral_denom(
+        &self,
+        ctx: SudoCtx,
+        denom: String,
+    ) -> Result<Response<ProviderCustomMsg>, ContractError> {
+        // CW20 tokens are added through `add_token_collateral`, so the contract is validated
+        ensure!(
+            cw20_token(&denom).is_none(),
+            ContractError::Cw20Denom(denom)
+        );
+        self.collateral_denoms.save(ctx.deps.storage, &denom, &())?;
+
+        let resp = Response::new()
+            .add_attribute("action", "add_collateral_denom")
+            .add_attribute("denom", denom);
+
+        Ok(resp)
+    }
+
+    /// Accepts a CW20 token contract as collateral, under the `cw20:<contract>` denom.
+    /// It can be removed again with `remove_collateral_denom`.
+    /// Can only be called by governance (sudo).
+    #[sv::msg(sudo)]
+    fn add_token_collateral(
+        &self,
+        ctx: SudoCtx,
+        token: String,
+    ) -> Result<Response<ProviderCustomMsg>, ContractError> {
+        let token = ctx.deps.api.addr_validate(&token)?;
+        let denom = cw20_denom(&token);
+        self.collateral_denoms.save(ctx.deps.storage, &denom, &())?;
+
+        let resp = Response::new()
+            .add_attribute("action", "add_collateral_denom")
+            .add_attribute("denom", denom);
+
+        Ok(resp)
+    }
+
+    /// Accepts a liquid staking token as collateral. Its collateral is valued at the exchange
+    /// rate reported by `rate_provider`, which can be refreshed with `refresh_exchange_rate`.
+    /// It can be removed again with `remove_collateral_denom`.
+    /// Can only be called by governance (sudo).
+    #[sv::msg(sudo)]
+    fn add_lst_collateral(
+        &self,
+        ctx: SudoCtx,
+        denom: String,
+        rate_provider: String,
+    ) -> Result<Response<ProviderCustomMsg>, ContractError> {
+        let config = self.config.load(ctx.deps.storage)?;
+        ensure!(config.denom != denom, ContractError::PrimaryDenom(denom));
+        ensure!(
+            cw20_token(&denom).is_none(),
+            ContractError::Cw20Denom(denom)
+        );
+        let rate_provider = ctx.deps.api.addr_validate(&rate_provider)?;
+        self.collateral_denoms.save(ctx.deps.storage, &denom, &())?;
+        let rate = self.update_lst_rate(ctx.deps, &ctx.env, &denom, rate_provider)?;
+
+        let resp = Response::new()
+            .add_attribute("action", "add_collateral_denom")
+            .add_attribute("denom", denom)
+            .add_attribute("rate", rate.to_string());
+
+        Ok(resp)
+    }
+
+    /// Refreshes the exchange rate of a liquid staking token collateral from its rate provider.
+    /// Can be called by anyone, e.g. a keeper.
+    #[sv::msg(exec)]
+    fn refresh_exchange_rate(
+        &self,
+        ctx: ExecCtx,
+        denom: String,
+    ) -> Result<Response<ProviderCustomMsg>, ContractError> {
+        nonpayable(&ctx.info)?;
+
+        let lst = self
+            .lst_rates
+            .may_load(ctx.deps.storage, &denom)?
+            .ok_or_else(|| ContractError::NotLstCollateral(denom.clone()))?;
+        let rate = self.update_lst_rate(ctx.deps, &ctx.env, &denom, lst.provider)?;
+
+        let resp = Response::new()
+            .add_attribute("action", "refresh_exchange_rate")
+            .add_attribute("denom", denom)
+            .add_attribute("rate", rate.to_string());
+
+        Ok(resp)
+    }
+
+    /// Returns the value of a single `denom` collateral token, in units of the underlying token.
+    /// This is one for all but liquid staking token collateral.
+    #[sv::msg(query)]
+    fn exchange_rate(
+        &self,
+        ctx: QueryCtx,
+        denom: String,
+    ) -> Result<ExchangeRateResponse, ContractError> {
+        let rate = self.collateral_rate(ctx.deps.storage, &denom)?;
+        Ok(ExchangeRateResponse { rate })
+    }
+
+    /// Sets the swap adapter used by `swap_and_bond`. `None` disables swap-and-bond.
+    /// Can only be called by governance (sudo).
+    #[sv::msg(sudo)]
+    fn set_swap_adapter(
+        &self,
+        ctx: SudoCtx,
+        adapter: Option<String>,
+    ) -> Result<Response<ProviderCustomMsg>, ContractError> {
+        let resp = Response::new().add_attribute("action", "set_swap_adapter");
+        match adapter {
+            Some(adapter) => {
+                let adapter = ctx.deps.api.addr_validate(&adapter)?;
+                self.swap_adapter.save(ctx.deps.storage, &adapter)?;
+                Ok(resp.add_attribute("adapter", adapter))
+            }
+            None => {
+                self.swap_adapter.remove(ctx.deps.storage);
+                Ok(resp)
+            }
+        }
+    }
+
+    /// Sets the treatment of slash rounding dust.
+    /// Can only be called by governance (sudo).
+    #[sv::msg(sudo)]
+    fn set_slash_dust_policy(
+        &self,
+        ctx: SudoCtx,
+        policy: SlashDustPolicy,
+    ) -> Result<Response<ProviderCustomMsg>, ContractError> {
+        if let SlashDustPolicy::Donate { recipient } = &policy {
+            ctx.deps.api.addr_validate(recipient.as_str())?;
+        }
+        self.slash_dust_policy.save(ctx.deps.storage, &policy)?;
+
+        let resp = Response::new().add_attribute("action", "set_slash_dust_policy");
+
+        Ok(resp)
+    }
+
+    /// Returns the treatment of slash rounding dust, and the dust of every collateral denom
+    #[sv::msg(query)]
+    fn slash_dust(&self, ctx: QueryCtx) -> Result<SlashDustResponse, ContractError> {
+        let policy = self
+            .slash_dust_policy
+            .may_load(ctx.deps.storage)?
+            .unwrap_or_default();
+        let dust = self
+            .slash_dust
+            .range(ctx.deps.storage, None, None, Order::Ascending)
+            .map(|item| {
+                let (denom, dust) = item?;
+                Ok(DenomSlashDust {
+                    denom,
+                    burned: dust.burned,
+                    donated: dust.donated,
+                    ignored: dust.ignored,
+                })
+            })
+            .collect::<StdResult<_>>()?;
+        Ok(SlashDustResponse { policy, dust })
+    }
+
+    /// Enables flagging accounts without activity for `period` seconds as dormant. `None` disables
+    /// it, and drops the flags already set.
+    /// Can only be called by governance (sudo).
+    #[sv::msg(sudo)]
+    fn set_dormancy_period(
+        &self,
+        ctx: SudoCtx,
+        period: Option<u64>,
+    ) -> Result<Response<ProviderCustomMsg>, ContractError> {
+        let resp = Response::new().add_attribute("action", "set_dormancy_period");
+        match period {
+            Some(period) => {
+                ensure!(period > 0, ContractError::InvalidDormancyPeriod);
+                if !self.dormancy_since.exists(ctx.deps.storage) {
+                    self.dormancy_since
+                        .save(ctx.deps.storage, &ctx.env.block.time)?;
+                }
+                self.dormancy_period.save(ctx.deps.storage, &period)?;
+                Ok(resp.add_attribute("period", period.to_string()))
+            }
+            None => {
+                self.dormancy_period.remove(ctx.deps.storage);
+                self.dormancy_since.remove(ctx.deps.storage);
+                self.dormant_accounts.clear(ctx.deps.storage);
+                Ok(resp)
+            }
+        }
+    }
+
+    /// Flags `account` as dormant, if it holds collateral and had no activity for the dormancy
+    /// period. The flag is dropped on the next activity of the account.
+    /// Anyone can call this.
+    #[sv::msg(exec)]
+    fn flag_dormant_account(
+        &self,
+        ctx: ExecCtx,
+        account: String,
+    ) -> Result<Response<ProviderCustomMsg>, ContractError> {
+        nonpayable(&ctx.info)?;
+        let period = self
+            .dormancy_period
+            .may_load(ctx.deps.storage)?
+            .ok_or(ContractError::DormancyDisabled)?;
+        let account = ctx.deps.api.addr_validate(&account)?;
+        ensure!(
+            !self.dormant_accounts.has(ctx.deps.storage, &account),
+            ContractError::AlreadyDormant
+        );
+
+        let has_collateral = self
+            .users
+            .prefix(&account)
+            .range(ctx.deps.storage, None, None, Order::Ascending)
+            .map(|item| item.map(|(_, user)| !user.collateral.is_zero()))
+            .collect::<StdResult<Vec<_>>>()?
+            .contains(&true);
+        ensure!(has_collateral, ContractError::NoCollateral);
+
+        let last_activity = match self.last_activity.may_load(ctx.deps.storage, &account)? {
+            Some(last_activity) => last_activity,
+            None => self.dormancy_since.load(ctx.deps.storage)?,
+        };
+        let dormant_from = last_activity.plus_seconds(period);
+        ensure!(
+            ctx.env.block.time >= dormant_from,
+            ContractError::AccountActive(dormant_from)
+        );
+        self.dormant_accounts
+            .save(ctx.deps.storage, &account, &ctx.env.block.time)?;
+
+        let resp = Response::new()
+            .add_attribute("action", "flag_dormant_account")
+            .add_attribute("account", account)
+            .add_attribute("last_activity", last_activity.seconds().to_string());
+
+        Ok(resp)
+    }
+
+    /// Returns the accounts flagged as dormant, ordered by address
+    #[sv::msg(query)]
+    fn dormant_accounts(
+        &self,
+        ctx: QueryCtx,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> Result<DormantAccountsResponse, ContractError> {
+        let limit = clamp_page_limit(limit);
+        let start_after = start_after.map(Addr::unchecked);
+        let bound = start_after.as_ref().map(Bound::exclusive);
+
+        let accounts = self
+            .dormant_accounts
+            .range(ctx.deps.storage, bound, None, Order::Ascending)
+            .take(limit)
+            .map(|item| {
+                let (account, flagged_at) = item?;
+                let last_activity = self.last_activity.may_load(ctx.deps.storage, &account)?;
+                Ok(DormantAccount {
+                    account: account.into_string(),
+                    last_activity,
+                    flagged_at,
+                })
+            })
+            .collect::<StdResult<_>>()?;
+
+        Ok(DormantAccountsResponse { accounts })
+    }
+
+    /// Enables fee collection, with the scheduled disbursement of the collected fees to
+    /// `recipients`. `None` disables it. Fees already collected are kept, and disbursed once
+    /// it is enabled again.
+    /// Can only be called by governance (sudo).
+    #[sv::msg(sudo)]
+    fn set_fee_disbursement(
+        &self,
+        ctx: SudoCtx,
+        disbursement: Option<FeeDisbursement>,
+    ) -> Result<Response<ProviderCustomMsg>, ContractError> {
+        let resp = Response::new().add_attribute("action", "set_fee_disbursement");
+        match disbursement {
+            Some(disbursement) => {
+                ensure!(
+                    !disbursement.recipients.is_empty()
+                        && disbursement.recipients.iter().all(|r| !r.share.is_zero())
+                        && disbursement
+                            .recipients
+                            .iter()
+                            .map(|r| r.share)
+                            .sum::<Decimal>()
+                            == Decimal::one(),
+                    ContractError::InvalidFeeRecipients
+                );
+                ensure!(disbursement.interval > 0, ContractError::InvalidFeeInterval);
+                for recipient in &disbursement.recipients {
+                    ctx.deps.api.addr_validate(recipient.address.as_str())?;
+                }
+                self.fee_disbursement
+                    .save(ctx.deps.storage, &disbursement)?;
+                Ok(resp.add_attribute("interval", disbursement.interval.to_string()))
+            }
+            None => {
+                self.fee_disbursement.remove(ctx.deps.storage);
+                Ok(resp)
+            }
+        }
+    }
+
+    /// Collects the sent fees, accounted under `source` (e.g. the contract or the operation
+    /// charging them) until they are disbursed.
+    /// Fees must be paid in the vault denom. Only possible while fee collection is enabled.
+    #[sv::msg(exec)]
+    fn deposit_fees(
+        &self,
+        ctx: ExecCtx,
+        source: String,
+    ) -> Result<Response<ProviderCustomMsg>, ContractError> {
+        ensure!(
+            self.fee_disbursement.exists(ctx.deps.storage),
+            ContractError::FeesDisabled
+        );
+        ensure!(!source.is_empty(), ContractError::InvalidFeeSource);
+        let config = self.config.load(ctx.deps.storage)?;
+        let amount = must_pay(&ctx.info, &config.denom)?;
+
+        let mut bucket = self
+            .fee_buckets
+            .may_load(ctx.deps.storage, &source)?
+            .unwrap_or_default();
+        bucket.pending += amount;
+        self.fee_buckets.save(ctx.deps.storage, &source, &bucket)?;
+
+        let resp = Response::new()
+            .add_attribute("action", "deposit_fees")
+            .add_attribute("source", source)
+            .add_attribute("sender", ctx.info.sender)
+            .add_attribute("amount", amount.to_string());
+
+        Ok(resp)
+    }
+
+    /// Sends the pending fees of every source to the fee recipients, according to their shares.
+    /// Rounding dust goes to the last recipient. Possible once per disbursement interval.
+    /// Anyone can call this.
+    #[sv::msg(exec)]
+    fn disburse_fees(&self, ctx: ExecCtx) -> Result<Response<ProviderCustomMsg>, ContractError> {
+        nonpayable(&ctx.info)?;
+        let disbursement = self
+            .fee_disbursement
+            .may_load(ctx.deps.storage)?
+            .ok_or(ContractError::FeesDisabled)?;
+        if let Some(last) = self.last_fee_disbursement.may_load(ctx.deps.storage)? {
+            let due = last.plus_seconds(disbursement.interval);
+            ensure!(
+                ctx.env.block.time >= due,
+                ContractError::FeeDisbursementNotDue(due)
+            );
+        }
+
+        let buckets = self
+            .fee_buckets
+            .range(ctx.deps.storage, None, None, Order::Ascending)
+            .filter(|item| !matches!(item, Ok((_, bucket)) if bucket.pending.is_zero()))
+            .collect::<StdResult<Vec<_>>>()?;
+        let mut total = Uint128::zero();
+        let mut events = vec![];
+        for (source, mut bucket) in buckets {
+            events.push(
+                Event::new("fee_disbursement")
+                    .add_attribute("source", &source)
+                    .add_attribute("amount", bucket.pending.to_string()),
+            );
+            total += bucket.pending;
+            bucket.disbursed += bucket.pending;
+            bucket.pending = Uint128::zero();
+            self.fee_buckets.save(ctx.deps.storage, &source, &bucket)?;
+        }
+        ensure!(!total.is_zero(), ContractError::NoFees);
+        self.last_fee_disbursement
+            .save(ctx.deps.storage, &ctx.env.block.time)?;
+
+        let config = self.config.load(ctx.deps.storage)?;
+        let mut msgs = vec![];
+        let mut remaining = total;
+        let last = disbursement.recipients.len() - 1;
+        for (i, recipient) in disbursement.recipients.into_iter().enumerate() {
+            let amount = if i == last {
+                remaining
+            } else {
+                total * recipient.share
+            };
+            remaining -= amount;
+            if amount.is_zero() {
+                continue;
+            }
+            events.push(
+                Event::new("fee_payout")
+                    .add_attribute("recipient", &recipient.address)
+                    .add_attribute("amount", amount.to_string()),
+            );
+            msgs.push(BankMsg::Send {
+                to_address: recipient.address.to_string(),
+                amount: vec![coin(amount.u128(), &config.denom)],
+            });
+        }
+
+        let resp = Response::new()
+            .add_messages(msgs)
+            .add_events(events)
+            .add_attribute("action", "disburse_fees")
+            .add_attribute("amount", total.to_string());
+
+        Ok(resp)
+    }
+
+    /// Returns the fee disbursement schedule, and the fees collected from every source
+    #[sv::msg(query)]
+    fn fee_accounting(&self, ctx: QueryCtx) -> Result<FeeAccountingResponse, ContractError> {
+        let buckets = self
+            .fee_buckets
+            .range(ctx.deps.storage, None, None, Order::Ascending)
+            .map(|item| {
+                let (source, bucket) = item?;
+                Ok(FeeBucketInfo {
+                    source,
+                    pending: bucket.pending,
+                    disbursed: bucket.disbursed,
+                })
+            })
+            .collect::<StdResult<_>>()?;
+        Ok(FeeAccountingResponse {
+            disbursement: self.fee_disbursement.may_load(ctx.deps.storage)?,
+            buckets,
+            last_disbursement: self.last_fee_disbursement.may_load(ctx.deps.storage)?,
+        })
+    }
+
+    /// Compacts the tx history of `owner`: completed txs beyond the newest
+    /// `TX_HISTORY_RETENTION` are folded into per-denom summaries and removed, up to
+    /// `TX_HISTORY_COMPACTION_BATCH` per call.
+    /// Anyone can call this.
+    #[sv::msg(exec)]
+    fn compact_tx_history(
+        &self,
+        ctx: ExecCtx,
+        owner: String,
+    ) -> Result<Response<ProviderCustomMsg>, ContractError> {
+        nonpayable(&ctx.info)?;
+        let owner = ctx.deps.api.addr_validate(&owner)?;
+
+        let expired = self
+            .tx_history
+            .prefix(&owner)
+            .range(ctx.deps.storage, None, None, Order::Descending)
+            .skip(TX_HISTORY_RETENTION)
+            .take(TX_HISTORY_COMPACTION_BATCH)
+            .collect::<StdResult<Vec<_>>>()?;
+        ensure!(!expired.is_empty(), ContractError::NothingToCompact);
+
+        for (tx_id, record) in &expired {
+            let key = (&owner, record.denom.as_str());
+            let mut summary = self
+                .tx_history_summaries
+                .may_load(ctx.deps.storage, key)?
+                .unwrap_or_default();
+            summary.add(record);
+            self.tx_history_summaries
+                .save(ctx.deps.storage, key, &summary)?;
+            self.tx_history.remove(ctx.deps.storage, (&owner, *tx_id));
+        }
+
+        let resp = Response::new()
+            .add_attribute("action", "compact_tx_history")
+            .add_attribute("owner", owner)
+            .add_attribute("compacted", expired.len().to_string());
+
+        Ok(resp)
+    }
+
+    /// Returns the retained completed txs of `owner`, newest first, along with the
+    /// summaries of its compacted txs
+    #[sv::msg(query)]
+    fn tx_history(
+        &self,
+        ctx: QueryCtx,
+        owner: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> Result<TxHistoryResponse, ContractError> {
+        let owner = ctx.deps.api.addr_validate(&owner)?;
+        let limit = clamp_page_limit(limit);
+        let bound = start_after.map(Bound::exclusive);
+
+        let txs = self
+            .tx_history
+            .prefix(&owner)
+            .range(ctx.deps.storage, None, bound, Order::Descending)
+            .take(limit)
+            .map(|item| {
+                let (tx_id, record) = item?;
+                Ok(TxHistoryItem {
+                    tx_id,
+                    lienholder: record.lienholder.into_string(),
+                    denom: record.denom,
+                    amount: record.amount,
+                    outcome: record.outcome,
+                    completed_height: record.completed_height,
+                })
+            })
+            .collect::<StdResult<_>>()?;
+        let summaries = self
+            .tx_history_summaries
+            .prefix(&owner)
+            .range(ctx.deps.storage, None, None, Order::Ascending)
+            .map(|item| {
+                let (denom, summary) = item?;
+                Ok(DenomTxHistorySummary { denom, summary })
+            })
+            .collect::<StdResult<_>>()?;
+
+        Ok(TxHistoryResponse { txs, summaries })
+    }
+
+    #[sv::msg(query)]
+    fn swap_adapter(&self, ctx: QueryCtx) -> Result<SwapAdapterResponse, ContractError> {
+        let adapter = self
+            .swap_adapter
+            .may_load(ctx.deps.storage)?
+            .map(Addr::into_string);
+        Ok(SwapAdapterResponse { adapter })
+    }
+
+    /// Swaps the sent funds (e.g. IBC-transferred tokens) to the primary collateral denom through
+    /// the configured swap adapter, and bonds the proceeds for the sender in the same tx.
+    /// The whole operation fails if the proceeds are below `min_output`
+    #[sv::msg(exec)]
+    fn swap_and_bond(
+        &self,
+        ctx: ExecCtx,
+        min_output: Uint128,
+    ) -> Result<Response<ProviderCustomMsg>, ContractError> {
+        let input = one_coin(&ctx.info)?;
+        self.record_activity(ctx.deps.storage, &ctx.env, &ctx.info.sender)?;
+        self.ensure_not_paused(ctx.deps.storage, PausableOperation::Bond)?;
+        let adapter = self
+            .swap_adapter
+            .may_load(ctx.deps.storage)?
+            .ok_or(ContractError::NoSwapAdapter)?;
+        let denom = self.config.load(ctx.deps.storage)?.denom;
+        ensure!(
+            input.denom != denom,
+            ContractError::SwapPrimaryDenom(input.denom)
+        );
+
+        // Proceeds are sent to the owner, and bonded from their balance
+        let recipient = ctx.info.sender.clone();
+        let balance_before = ctx.deps.querier.query_balance(&recipient, &denom)?.amount;
+        let swap = PendingSwap {
+            owner: ctx.info.sender.clone(),
+            input: input.clone(),
+            balance_before,
+            min_output,
+        };
+        self.pending_swap.save(ctx.deps.storage, &swap)?;
+
+        let msg = WasmMsg::Execute {
+            contract_addr: adapter.into_string(),
+            msg: to_json_binary(&SwapAdapterExecuteMsg::Swap {
+                output_denom: denom,
+                min_output,
+                recipient: recipient.into_string(),
+            })?,
+            funds: vec![input.clone()],
+        };
+        let resp = Response::new()
+            .add_submessage(SubMsg::reply_on_success(msg, REPLY_ID_SWAP))
+            .add_attribute("action", "swap")
+            .add_attribute("sender", ctx.info.sender)
+            .add_attribute("input", input.to_string());
+
+        Ok(resp)
+    }
+
+    /// Removes a denom from the set of accepted collateral denoms.
+    /// Existing collateral in that denom can still be released and unbonded, but no new
+    /// collateral or liens are accepted. The primary denom cannot be removed.
+    /// Can only be called by governance (sudo).
+    #[sv::msg(sudo)]
+    fn remove_collateral_denom(
+        &self,
+        ctx: SudoCtx,
+        denom: String,
+    ) -> Result<Response<ProviderCustomMsg>, ContractError> {
+        let config = self.config.load(ctx.deps.storage)?;
+        ensure!(config.denom != denom, ContractError::PrimaryDenom(denom));
+        self.ensure_collateral_denom(ctx.deps.storage, &denom)?;
+        self.collateral_denoms.remove(ctx.deps.storage, &denom);
+
+        let resp = Response::new()
+            .add_attribute("action", "remove_collateral_denom")
+            .add_attribute("denom", denom);
+
+        Ok(resp)
+    }
+
+    /// Opts the sender in or out of auto-renew.
+    /// With auto-renew enabled, the sender's matured local unbondings are released back as free
+    /// collateral whenever they stake locally again, without having to claim them. Anyone can
+    /// also release them in between (through the local staking contract).
+    #[sv::msg(exec)]
+    fn set_auto_renew(
+        &self,
+        ctx: ExecCtx,
+        enabled: bool,
+    ) -> Result<Response<ProviderCustomMsg>, ContractError> {
+        nonpayable(&ctx.info)?;
+        self.record_activity(ctx.deps.storage, &ctx.env, &ctx.info.sender)?;
+        if enabled {
+            self.auto_renew_accounts
+                .save(ctx.deps.storage, &ctx.info.sender, &())?;
+        } else {
+            self.auto_renew_accounts
+                .remove(ctx.deps.storage, &ctx.info.sender);
+        }
+
+        let resp = Response::new()
+            .add_attribute("action", "set_auto_renew")
+            .add_attribute("sender", ctx.info.sender)
+            .add_attribute("enabled", enabled.to_string());
+
+        Ok(resp)
+    }
+
+    /// Proposes `new_owner` as the next vault owner. The transfer only takes effect once
+    /// `new_owner` accepts it, before `expiry` if set. Replaces any previous proposal.
+    /// Can only be called by the vault owner.
+    #[sv::msg(exec)]
+    fn transfer_ownership(
+        &self,
+        ctx: ExecCtx,
+        new_owner: String,
+        expiry: Option<Expiration>,
+    ) -> Result<Response<ProviderCustomMsg>, ContractError> {
+        nonpayable(&ctx.info)?;
+        self.ensure_owner(ctx.deps.as_ref(), &ctx.env, &ctx.info.sender)?;
+        let new_owner = ctx.deps.api.addr_validate(&new_owner)?;
+        if let Some(expiry) = expiry {
+            ensure!(
+                !expiry.is_expired(&ctx.env.block),
+                ContractError::OwnershipTransferExpired {}
+            );
+        }
+        self.pending_owner.save(
+            ctx.deps.storage,
+            &PendingOwnership {
+                new_owner: new_owner.clone(),
+                expiry,
+            },
+        )?;
+
+        let resp = Response::new()
+            .add_attribute("action", "transfer_ownership")
+            .add_attribute("sender", ctx.info.sender)
+            .add_attribute("new_owner", new_owner);
+
+        Ok(resp)
+    }
+
+    /// Accepts a pending ownership transfer. Can only be called by the proposed owner.
+    #[sv::msg(exec)]
+    fn accept_ownership(&self, ctx: ExecCtx) -> Result<Response<ProviderCustomMsg>, ContractError> {
+        nonpayable(&ctx.info)?;
+        let pending = self
+            .pending_owner
+            .may_load(ctx.deps.storage)?
+            .ok_or(ContractError::NoPendingOwnership {})?;
+        ensure!(
+            pending.new_owner == ctx.info.sender,
+            ContractError::Unauthorized {}
+        );
+        if let Some(expiry) = pending.expiry {
+            ensure!(
+                !expiry.is_expired(&ctx.env.block),
+                ContractError::OwnershipTransferExpired {}
+            );
+        }
+        self.owner.save(ctx.deps.storage, &pending.new_owner)?;
+        self.pending_owner.remove(ctx.deps.storage);
+
+        let resp = Response::new()
+            .add_attribute("action", "accept_ownership")
+            .add_attribute("owner", pending.new_owner);
+
+        Ok(resp)
+    }
+
+    /// Cancels a pending ownership transfer. Can only be called by the vault owner.
+    #[sv::msg(exec)]
+    fn cancel_ownership_transfer(
+        &self,
+        ctx: ExecCtx,
+    ) -> Result<Response<ProviderCustomMsg>, ContractError> {
+        nonpayable(&ctx.info)?;
+        self.ensure_owner(ctx.deps.as_ref(), &ctx.env, &ctx.info.sender)?;
+        ensure!(
+            self.pending_owner.exists(ctx.deps.storage),
+            ContractError::NoPendingOwnership {}
+        );
+        self.pending_owner.remove(ctx.deps.storage);
+
+        let resp = Response::new()
+            .add_attribute("action", "cancel_ownership_transfer")
+            .add_attribute("sender", ctx.info.sender);
+
+        Ok(resp)
+    }
+
+    /// Returns the vault owner and the pending ownership transfer, if any
+    #[sv::msg(query)]
+    fn ownership(&self, ctx: QueryCtx) -> Result<OwnershipResponse, ContractError> {
+        let owner = self.current_owner(ctx.deps, &ctx.env)?;
+        let pending = self.pending_owner.may_load(ctx.deps.storage)?;
+        Ok(OwnershipResponse {
+            owner: owner.map(Addr::into_string),
+            pending_owner: pending.as_ref().map(|p| p.new_owner.to_string()),
+            pending_expiry: pending.and_then(|p| p.expiry),
+        })
+    }
+
+    /// Allows users to stake remotely on the `lienholder` cross-staking contract.
+    /// Can only be called by the vault owner.
+    #[sv::msg(exec)]
+    fn add_allowed_lienholder(
+        &self,
+        ctx: ExecCtx,
+        lienholder: String,
+    ) -> Result<Response<ProviderCustomMsg>, ContractError> {
+        nonpayable(&ctx.info)?;
+        self.ensure_owner(ctx.deps.as_ref(), &ctx.env, &ctx.info.sender)?;
+        let lienholder = ctx.deps.api.addr_validate(&lienholder)?;
+        self.allowed_lienholders
+            .save(ctx.deps.storage, &lienholder, &())?;
+
+        let resp = Response::new()
+            .add_attribute("action", "add_allowed_lienholder")
+            .add_attribute("lienholder", lienholder);
+
+        Ok(resp)
+    }
+
+    /// Rejects new remote stakes on the `lienholder` cross-staking contract.
+    /// Existing liens are kept, and can still be released by the lienholder.
+    /// Can only be called by the vault owner.
+    #[sv::msg(exec)]
+    fn remove_allowed_lienholder(
+        &self,
+        ctx: ExecCtx,
+        lienholder: String,
+    ) -> Result<Response<ProviderCustomMsg>, ContractError> {
+        nonpayable(&ctx.info)?;
+        self.ensure_owner(ctx.deps.as_ref(), &ctx.env, &ctx.info.sender)?;
+        let lienholder = ctx.deps.api.addr_validate(&lienholder)?;
+        ensure!(
+            self.allowed_lienholders.has(ctx.deps.storage, &lienholder),
+            ContractError::LienholderNotAllowed(lienholder.into_string())
+        );
+        self.allowed_lienholders
+            .remove(ctx.deps.storage, &lienholder);
+
+        let resp = Response::new()
+            .add_attribute("action", "remove_allowed_lienholder")
+            .add_attribute("lienholder", lienholder);
+
+        Ok(resp)
+    }
+
+    #[sv::msg(query)]
+    fn list_allowed_lienholders(
+        &self,
+        ctx: QueryCtx,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> Result<AllowedLienholdersResponse, ContractError> {
+        let limit = clamp_page_limit(limit);
+        let start_after = start_after.map(Addr::unchecked);
+        let bound = start_after.as_ref().and_then(Bounder::exclusive_bound);
+
+        let lienholders = self
+            .allowed_lienholders
+            .keys(ctx.deps.storage, bound, None, Order::Ascending)
+            .map(|addr| addr.map(Addr::into_string))
+            .take(limit)
+            .collect::<StdResult<_>>()?;
+
+        Ok(AllowedLienholdersResponse { lienholders })
+    }
+
+    /// Marks a cross-staking contract as permanently broken, e.g. because the consumer chain
+    /// halted or its channel closed for good. No new stakes are accepted on it, and once
+    /// `grace_period` (in seconds) has passed, anyone can force-release its liens with
+    /// `release_defunct_lien`.
+    /// Can only be called by governance (sudo).
+    #[sv::msg(sudo)]
+    fn mark_lienholder_defunct(
+        &self,
+        ctx: SudoCtx,
+        lienholder: String,
+        grace_period: u64,
+    ) -> Result<Response<ProviderCustomMsg>, ContractError> {
+        let lienholder = ctx.deps.api.addr_validate(&lienholder)?;
+        // Local stakes are held by the local staking contract, so they can't just be released
+        if let Some(local_staking) = self.local_staking.load(ctx.deps.storage)? {
+            ensure!(
+                local_staking.contract.0 != lienholder,
+                ContractError::LocalStakingDefunct
+            );
+        }
+        let defunct = DefunctLienholder {
+            marked_at: ctx.env.block.time,
+            release_after: ctx.env.block.time.plus_seconds(grace_period),
+        };
+        self.defunct_lienholders
+            .save(ctx.deps.storage, &lienholder, &defunct)?;
+        self.allowed_lienholders
+            .remove(ctx.deps.storage, &lienholder);
+
+        let resp = Response::new()
+            .add_attribute("action", "mark_lienholder_defunct")
+            .add_attribute("lienholder", lienholder)
+            .add_attribute("release_after", defunct.release_after.to_string());
+
+        Ok(resp)
+    }
+
+    /// Withdraws a defunct mark. Liens already released are not restored, and the lienholder
+    /// has to be allowed again for new stakes.
+    /// Can only be called by governance (sudo).
+    #[sv::msg(sudo)]
+    fn unmark_lienholder_defunct(
+        &self,
+        ctx: SudoCtx,
+        lienholder: String,
+    ) -> Result<Response<ProviderCustomMsg>, ContractError> {
+        let lienholder = ctx.deps.api.addr_validate(&lienholder)?;
+        ensure!(
+            self.defunct_lienholders.has(ctx.deps.storage, &lienholder),
+            ContractError::LienholderNotDefunct(lienholder.into_string())
+        );
+        self.defunct_lienholders
+            .remove(ctx.deps.storage, &lienholder);
+
+        let resp = Response::new()
+            .add_attribute("action", "unmark_lienholder_defunct")
+            .add_attribute("lienholder", lienholder);
+
+        Ok(resp)
+    }
+
+    /// Force-releases the `owner` lien held by a defunct `lienholder`, once its grace period is
+    /// over. Pending stakes with it are rolled back. Can be called by anyone.
+    #[sv::msg(exec)]
+    fn release_defunct_lien(
+        &self,
+        ctx: ExecCtx,
+        owner: String,
+        lienholder: String,
+    ) -> Result<Response<ProviderCustomMsg>, ContractError> {
+        nonpayable(&ctx.info)?;
+        let owner = ctx.deps.api.addr_validate(&owner)?;
+        let lienholder = ctx.deps.api.addr_validate(&lienholder)?;
+        let defunct = self
+            .defunct_lienholders
+            .may_load(ctx.deps.storage, &lienholder)?
+            .ok_or_else(|| ContractError::LienholderNotDefunct(lienholder.to_string()))?;
+        ensure!(
+            ctx.env.block.time >= defunct.release_after,
+            ContractError::DefunctGracePeriod(defunct.release_after)
+        );
+
+        // Pending stakes will never be committed or rolled back by the lienholder
+        let mut rolled_back = vec![];
+        for tx in self.pending.txs_by_user(ctx.deps.storage, &owner)? {
+            let tx_id = match &tx {
+                InFlightStaking {
+                    id,
+                    lienholder: tx_lienholder,
+                    ..
+                } if *tx_lienholder == lienholder => *id,
+                _ => continue,
+            };
+            self.revert_stake(ctx.deps.storage, tx_id, tx, ctx.env.block.height)?;
+            rolled_back.push(tx_id);
+        }
+
+        let released = match self
+            .liens
+            .may_load(ctx.deps.storage, (&owner, &lienholder))?
+        {
+            Some(lien) => {
+                // The whole lien, in tokens
+                let rate = self.collateral_rate(ctx.deps.storage, &lien.denom)?;
+                let amount = coin(lien.amount.high().div_ceil(rate).u128(), &lien.denom);
+                self.release_lien(ctx.deps.storage, &owner, &lienholder, amount.clone())?;
+                self.fulfil_unbond_requests(
+                    ctx.deps.storage,
+                    &owner,
+                    &lien.denom,
+                    ctx.env.block.height,
+                )?;
+                amount.amount
+            }
+            None => {
+                ensure!(!rolled_back.is_empty(), ContractError::UnknownLienholder);
+                Uint128::zero()
+            }
+        };
+
+        let resp = Response::new()
+            .add_attribute("action", "release_defunct_lien")
+            .add_attribute("sender", ctx.info.sender)
+            .add_attribute("owner", owner)
+            .add_attribute("lienholder", lienholder)
+            .add_attribute("amount", released.to_string())
+            .add_attribute(
+                "rolled_back_txs",
+                rolled_back
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(","),
+            );
+
+        Ok(resp)
+    }
+
+    /// Sets the time (in seconds) after which a pending tx can be expired with `expire_tx`.
+    /// It applies to the txs already pending as well.
+    /// Can only be called by governance (sudo).
+    #[sv::msg(sudo)]
+    fn set_tx_timeout(
+        &self,
+        ctx: SudoCtx,
+        timeout: u64,
+    ) -> Result<Response<ProviderCustomMsg>, ContractError> {
+        ensure!(timeout > 0, ContractError::InvalidTxTimeout);
+        self.tx_timeout.save(ctx.deps.storage, &timeout)?;
+
+        let resp = Response::new()
+            .add_attribute("action", "set_tx_timeout")
+            .add_attribute("timeout", timeout.to_string());
+
+        Ok(resp)
+    }
+
+    /// Sets the time (in seconds) after which a pending tx can be cancelled by its staker with
+    /// `cancel_my_tx`. It applies to the txs already pending as well.
+    /// Can only be called by governance (sudo).
+    #[sv::msg(sudo)]
+    fn set_cancel_grace_period(
+        &self,
+        ctx: SudoCtx,
+        grace_period: u64,
+    ) -> Result<Response<ProviderCustomMsg>, ContractError> {
+        ensure!(grace_period > 0, ContractError::InvalidTxTimeout);
+        self.cancel_grace_period
+            .save(ctx.deps.storage, &grace_period)?;
+
+        let resp = Response::new()
+            .add_attribute("action", "set_cancel_grace_period")
+            .add_attribute("grace_period", grace_period.to_string());
+
+        Ok(resp)
+    }
+
+    #[sv::msg(query)]
+    fn tx_timeout(&self, ctx: QueryCtx) -> Result<TxTimeoutResponse, ContractError> {
+        let timeout = self
+            .tx_timeout
+            .may_load(ctx.deps.storage)?
+            .unwrap_or(DEFAULT_TX_TIMEOUT);
+        let cancel_grace_period = self
+            .cancel_grace_period
+            .may_load(ctx.deps.storage)?
+            .unwrap_or(DEFAULT_CANCEL_GRACE_PERIOD);
+        Ok(TxTimeoutResponse {
+            timeout,
+            cancel_grace_period,
+        })
+    }
+
+    /// Aborts a pending stake tx that was neither committed nor rolled back by its lienholder
+    /// (e.g. because of a dead IBC channel) within the tx timeout. Can be called by anyone.
+    ///
+    /// The tentative lien is released right away, and the lienholder is asked to drop its side of
+    /// the stake, on a best-effort basis.
+    #[sv::msg(exec)]
+    fn expire_tx(
+        &self,
+        ctx: ExecCtx,
+        tx_id: u64,
+    ) -> Result<Response<ProviderCustomMsg>, ContractError> {
+        nonpayable(&ctx.info)?;
+        let (tx, _, started) = self.pending_stake_tx(ctx.deps.storage, tx_id)?;
+        let timeout = self
+            .tx_timeout
+            .may_load(ctx.deps.storage)?
+            .unwrap_or(DEFAULT_TX_TIMEOUT);
+        let expires_at = started.plus_seconds(timeout);
+        ensure!(
+            ctx.env.block.time >= expires_at,
+            ContractError::TxNotExpired(expires_at)
+        );
+
+        self.abort_stale_stake(ctx, "expire_tx", tx_id, tx, started)
+    }
+
+    /// Aborts a pending stake tx of the sender, once it has been pending for the cancel grace
+    /// period. Like `expire_tx`, but available to the staker earlier, so they don't depend on
+    /// anyone else to recover their collateral.
+    ///
+    /// The tentative lien is released right away, and the lienholder is asked to drop its side of
+    /// the stake, on a best-effort basis.
+    #[sv::msg(exec)]
+    fn cancel_my_tx(
+        &self,
+        ctx: ExecCtx,
+        tx_id: u64,
+    ) -> Result<Response<ProviderCustomMsg>, ContractError> {
+        nonpayable(&ctx.info)?;
+        self.record_activity(ctx.deps.storage, &ctx.env, &ctx.info.sender)?;
+        let (tx, user, started) = self.pending_stake_tx(ctx.deps.storage, tx_id)?;
+        ensure!(user == ctx.info.sender, ContractError::Unauthorized {});
+        let grace_period = self
+            .cancel_grace_period
+            .may_load(ctx.deps.storage)?
+            .unwrap_or(DEFAULT_CANCEL_GRACE_PERIOD);
+        let cancellable_at = started.plus_seconds(grace_period);
+        ensure!(
+            ctx.env.block.time >= cancellable_at,
+            ContractError::TxNotCancellable(cancellable_at)
+        );
+
+        self.abort_stale_stake(ctx, "cancel_tx", tx_id, tx, started)
+    }
+
+    #[sv::msg(query)]
+    fn defunct_lienholders(
+        &self,
+        ctx: QueryCtx,
+    ) -> Result<DefunctLienholdersResponse, ContractError> {
+        let lienholders = self
+            .defunct_lienholders
+            .range(ctx.deps.storage, None, None, Order::Ascending)
+            .map(|item| {
+                let (lienholder, defunct) = item?;
+                Ok::<_, ContractError>(DefunctLienholderResponse {
+                    lienholder: lienholder.into_string(),
+                    marked_at: defunct.marked_at,
+                    release_after: defunct.release_after,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(DefunctLienholdersResponse { lienholders })
+    }
+
+    /// Sets the maximum total lien a lienholder can hold in `denom`. `None` removes the cap.
+    /// Existing liens above a new cap are kept, but no new stakes are accepted until they are
+    /// below it.
+    /// Can only be called by governance (sudo).
+    #[sv::msg(sudo)]
+    fn set_lienholder_cap(
+        &self,
+        ctx: SudoCtx,
+        lienholder: String,
+        denom: String,
+        cap: Option<Uint128>,
+    ) -> Result<Response<ProviderCustomMsg>, ContractError> {
+        self.do_set_lienholder_cap(ctx.deps, lienholder, denom, cap)
+    }
+
+    /// Same as `set_lienholder_cap`, for the vault owner.
+    #[sv::msg(exec)]
+    fn update_lienholder_cap(
+        &self,
+        ctx: ExecCtx,
+        lienholder: String,
+        denom: String,
+        cap: Option<Uint128>,
+    ) -> Result<Response<ProviderCustomMsg>, ContractError> {
+        nonpayable(&ctx.info)?;
+        self.ensure_owner(ctx.deps.as_ref(), &ctx.env, &ctx.info.sender)?;
+        self.do_set_lienholder_cap(ctx.deps, lienholder, denom, cap)
+    }
+
+    fn do_set_lienholder_cap(
+        &self,
+        deps: DepsMut,
+        lienholder: String,
+        denom: String,
+        cap: Option<Uint128>,
+    ) -> Result<Response<ProviderCustomMsg>, ContractError> {
+        let lienholder = deps.api.addr_validate(&lienholder)?;
+        self.ensure_collateral_denom(deps.storage, &denom)?;
+        let mut resp = Response::new()
+            .add_attribute("action", "set_lienholder_cap")
+            .add_attribute("lienholder", lienholder.as_str())
+            .add_attribute("denom", &denom);
+        match cap {
+            Some(cap) => {
+                self.lienholder_caps
+                    .save(deps.storage, (&lienholder, &denom), &cap)?;
+                resp = resp.add_attribute("cap", cap.to_string());
+            }
+            None => self
+                .lienholder_caps
+                .remove(deps.storage, (&lienholder, &denom)),
+        }
+
+        Ok(resp)
+    }
+
+    /// Returns the lien cap of a lienholder in `denom`, and how much can still be staked with it
+    #[sv::msg(query)]
+    fn lienholder_cap(
+        &self,
+        ctx: QueryCtx,
+        lienholder: String,
+        denom: String,
+    ) -> Result<LienholderCapResponse, ContractError> {
+        let lienholder = ctx.deps.api.addr_validate(&lienholder)?;
+        let cap = self
+            .lienholder_caps
+            .may_load(ctx.deps.storage, (&lienholder, &denom))?;
+        let total = self
+            .lienholder_totals
+            .may_load(ctx.deps.storage, (&lienholder, &denom))?
+            .unwrap_or_default();
+        let headroom = cap.map(|cap| cap.saturating_sub(total.high()));
+
+        Ok(LienholderCapResponse {
+            cap,
+            total,
+            headroom,
+        })
+    }
+
+    /// Caps the total liens of every account to `max_leverage` times its collateral, per denom.
+    /// `None` removes the cap. Existing liens above a new cap are kept, but no new stakes are
+    /// accepted until they are below it.
+    /// Can only be called by governance (sudo).
+    #[sv::msg(sudo)]
+    fn set_max_leverage(
+        &self,
+        ctx: SudoCtx,
+        max_leverage: Option<Decimal>,
+    ) -> Result<Response<ProviderCustomMsg>, ContractError> {
+        let resp = Response::new().add_attribute("action", "set_max_leverage");
+        match max_leverage {
+            Some(max_leverage) => {
+                ensure!(
+                    max_leverage >= Decimal::one(),
+                    ContractError::InvalidMaxLeverage
+                );
+                self.max_leverage.save(ctx.deps.storage, &max_leverage)?;
+                Ok(resp.add_attribute("max_leverage", max_leverage.to_string()))
+            }
+            None => {
+                self.max_leverage.remove(ctx.deps.storage);
+                Ok(resp)
+            }
+        }
+    }
+
+    /// Returns the leverage of `account` in `denom` (the primary denom by default): the ratio of
+    /// its total liens to its collateral value. Pending liens are taken at their highest value.
+    #[sv::msg(query)]
+    fn leverage(
+        &self,
+        ctx: QueryCtx,
+        account: String,
+        denom: Option<String>,
+    ) -> Result<LeverageResponse, ContractError> {
+        let account = ctx.deps.api.addr_validate(&account)?;
+        let denom = match denom {
+            Some(denom) => denom,
+            None => self.config.load(ctx.deps.storage)?.denom,
+        };
+        let user = self
+            .users
+            .may_load(ctx.deps.storage, (&account, &denom))?
+            .unwrap_or_default();
+        let collateral = user.collateral * self.collateral_rate(ctx.deps.storage, &denom)?;
+        let total_liens = self.total_liens(ctx.deps.storage, &account, &denom)?;
+        let leverage = Decimal::checked_from_ratio(total_liens, collateral).ok();
+        let max_leverage = self.max_leverage.may_load(ctx.deps.storage)?;
+
+        Ok(LeverageResponse {
+            denom,
+            collateral,
+            total_liens,
+            leverage,
+            max_leverage,
+        })
+    }
+
+    /// Pauses `operations`. Can only be called by the pause authority or the vault owner
+    #[sv::msg(exec)]
+    fn pause(
+        &self,
+        ctx: ExecCtx,
+        operations: Vec<PausableOperation>,
+    ) -> Result<Response<ProviderCustomMsg>, ContractError> {
+        nonpayable(&ctx.info)?;
+        self.ensure_pause_authority(ctx.deps.as_ref(), &ctx.env, &ctx.info.sender)?;
+        self.update_paused(ctx.deps.storage, &operations, true)?;
+
+        Ok(self.pause_response("pause", &operations))
+    }
+
+    /// Unpauses `operations`. Can only be called by the pause authority or the vault owner
+    #[sv::msg(exec)]
+    fn unpause(
+        &self,
+        ctx: ExecCtx,
+        operations: Vec<PausableOperation>,
+    ) -> Result<Response<ProviderCustomMsg>, ContractError> {
+        nonpayable(&ctx.info)?;
+        self.ensure_pause_authority(ctx.deps.as_ref(), &ctx.env, &ctx.info.sender)?;
+        self.update_paused(ctx.deps.storage, &operations, false)?;
 
-        let denom = self.config.load(ctx.deps.storage)?.denom;
+        Ok(self.pause_response("unpause", &operations))
+    }
 
-        let accounts: Vec<_> = self
-            .users
-            .range(ctx.deps.storage, bound, None, Order::Ascending)
-            .filter(|account| {
-                account
-                    .as_ref()
-                    .map(|(_, account)| {
-                        !with_collateral || !account.collateral.is_zero() // Skip zero collateral
-                    })
-                    .unwrap_or(false) // Skip other errors
-            })
-            .map(|account| {
-                account.map(|(addr, account)| AllAccountsResponseItem {
-                    user: addr.to_string(),
-                    account: AccountResponse {
-                        denom: denom.clone(),
-                        bonded: account.collateral,
-                        free: account.free_collateral(),
-                    },
-                })
-            })
-            .take(limit)
-            .collect::<StdResult<_>>()?;
+    /// Pauses or unpauses `operations`.
+    /// Can only be called by governance (sudo).
+    #[sv::msg(sudo)]
+    fn set_paused(
+        &self,
+        ctx: SudoCtx,
+        operations: Vec<PausableOperation>,
+        paused: bool,
+    ) -> Result<Response<ProviderCustomMsg>, ContractError> {
+        self.update_paused(ctx.deps.storage, &operations, paused)?;
 
-        let resp = AllAccountsResponse { accounts };
+        let action = if paused { "pause" } else { "unpause" };
+        Ok(self.pause_response(action, &operations))
+    }
+
+    /// Sets the address allowed to pause and unpause operations. `None` leaves it to governance.
+    /// Can only be called by governance (sudo).
+    #[sv::msg(sudo)]
+    fn set_pause_authority(
+        &self,
+        ctx: SudoCtx,
+        authority: Option<String>,
+    ) -> Result<Response<ProviderCustomMsg>, ContractError> {
+        let authority = authority
+            .map(|addr| ctx.deps.api.addr_validate(&addr))
+            .transpose()?;
+        self.pause_authority.save(ctx.deps.storage, &authority)?;
+
+        let resp = Response::new()
+            .add_attribute("action", "set_pause_authority")
+            .add_attribute(
+                "authority",
+                authority.map(Addr::into_string).unwrap_or_default(),
+            );
 
         Ok(resp)
     }
 
-    /// Queries a pending tx.
     #[sv::msg(query)]
-    fn pending_tx(&self, ctx: QueryCtx, tx_id: u64) -> Result<TxResponse, ContractError> {
-        let resp = self.pending.txs.load(ctx.deps.storage, tx_id)?;
-        Ok(resp)
+    fn pause_status(&self, ctx: QueryCtx) -> Result<PauseStatusResponse, ContractError> {
+        let pause_authority = self
+            .pause_authority
+            .may_load(ctx.deps.storage)?
+            .flatten()
+            .map(Addr::into_string);
+        let paused = self.paused.may_load(ctx.deps.storage)?.unwrap_or_default();
+
+        Ok(PauseStatusResponse {
+            pause_authority,
+            paused,
+        })
     }
 
-    /// Queries for all pending txs.
-    /// Reports txs in descending order (newest first).
-    /// `start_after` is the last tx id included in previous page
+    /// Aggregated vault figures for monitoring.
+    ///
+    /// Totals are read from running counters, so only denoms and lienholders are iterated over.
     #[sv::msg(query)]
-    fn all_pending_txs_desc(
-        &self,
-        ctx: QueryCtx,
-        start_after: Option<u64>,
-        limit: Option<u32>,
-    ) -> Result<AllTxsResponse, ContractError> {
-        let limit = clamp_page_limit(limit);
-        let bound = start_after.and_then(Bounder::exclusive_bound);
+    fn metrics(&self, ctx: QueryCtx) -> Result<MetricsResponse, ContractError> {
+        let accounts = self
+            .account_count
+            .may_load(ctx.deps.storage)?
+            .unwrap_or_default();
+        let total_bonded = self
+            .collateral_totals
+            .range(ctx.deps.storage, None, None, Order::Ascending)
+            .map(|item| item.map(|(denom, amount)| Coin { denom, amount }))
+            .collect::<StdResult<_>>()?;
 
-        let txs = self
+        let mut total_liens = BTreeMap::<String, Uint128>::new();
+        for item in self
+            .lienholder_totals
+            .range(ctx.deps.storage, None, None, Order::Ascending)
+        {
+            let ((_, denom), total) = item?;
+            *total_liens.entry(denom).or_default() += total.high();
+        }
+
+        let pending_txs = self
             .pending
-            .txs
-            .range(ctx.deps.storage, None, bound, Order::Descending)
+            .count
+            .may_load(ctx.deps.storage)?
+            .unwrap_or_default();
+        // Tx ids are increasing, so the first one is the oldest
+        let oldest_pending_tx_age = self
+            .pending_started
+            .range(ctx.deps.storage, None, None, Order::Ascending)
+            .next()
+            .transpose()?
+            .map(|(_, started)| ctx.env.block.time.seconds() - started.seconds());
+
+        Ok(MetricsResponse {
+            accounts,
+            total_bonded,
+            total_liens: total_liens
+                .into_iter()
+                .filter(|(_, amount)| !amount.is_zero())
+                .map(|(denom, amount)| Coin { denom, amount })
+                .collect(),
+            pending_txs,
+            oldest_pending_tx_age,
+        })
+    }
+
+    /// Vault totals, maintained as running counters so they are cheap to query.
+    ///
+    /// Like `metrics`, this doesn't iterate over accounts, only over denoms and lienholders.
+    #[sv::msg(query)]
+    fn total_stats(&self, ctx: QueryCtx) -> Result<TotalStatsResponse, ContractError> {
+        let accounts = self
+            .account_count
+            .may_load(ctx.deps.storage)?
+            .unwrap_or_default();
+        let total_bonded = self
+            .collateral_totals
+            .range(ctx.deps.storage, None, None, Order::Ascending)
+            .map(|item| item.map(|(denom, amount)| Coin { denom, amount }))
+            .collect::<StdResult<_>>()?;
+        let lienholder_totals = self
+            .lienholder_totals
+            .range(ctx.deps.storage, None, None, Order::Ascending)
             .map(|item| {
-                let (_id, tx) = item?;
-                Ok::<AllTxsResponseItem, ContractError>(tx)
+                item.map(|((lienholder, denom), total)| LienholderTotal {
+                    lienholder: lienholder.into_string(),
+                    denom,
+                    total,
+                })
             })
-            .take(limit)
-            .collect::<Result<_, _>>()?;
-
-        let resp = AllTxsResponse { txs };
+            .collect::<StdResult<_>>()?;
+        let total_slashable = self
+            .slashable_totals
+            .range(ctx.deps.storage, None, None, Order::Ascending)
+            .map(|item| {
+                item.map(|(denom, total)| Coin {
+                    denom,
+                    amount: total.high(),
+                })
+            })
+            .collect::<StdResult<_>>()?;
 
-        Ok(resp)
+        Ok(TotalStatsResponse {
+            accounts,
+            total_bonded,
+            lienholder_totals,
+            total_slashable,
+        })
     }
 
     #[sv::msg(reply)]
@@ -569,6 +2896,11 @@ impl VaultContract<'_> {
     ) -> Result<Response<ProviderCustomMsg>, ContractError> {
         match reply.id {
             REPLY_ID_INSTANTIATE => self.reply_init_callback(ctx.deps, reply.result.unwrap()),
+            REPLY_ID_SWAP => self.reply_swap_callback(ctx.deps, &ctx.env),
+            // The stake was rolled back already, a failing lienholder is only reported
+            REPLY_ID_ABORT_STAKE => Ok(Response::new()
+                .add_attribute("action", "abort_stake_failed")
+                .add_attribute("error", reply.result.unwrap_err())),
             _ => Err(ContractError::InvalidReplyId(reply.id)),
         }
     }
@@ -599,68 +2931,511 @@ impl VaultContract<'_> {
         Ok(Response::new())
     }
 
+    /// Bonds the proceeds of a swap-and-bond, enforcing its slippage limit
+    fn reply_swap_callback(
+        &self,
+        deps: DepsMut,
+        env: &Env,
+    ) -> Result<Response<ProviderCustomMsg>, ContractError> {
+        let swap = self.pending_swap.load(deps.storage)?;
+        self.pending_swap.remove(deps.storage);
+
+        let denom = self.config.load(deps.storage)?.denom;
+        let balance_after = deps.querier.query_balance(&swap.owner, &denom)?.amount;
+        let output = balance_after.saturating_sub(swap.balance_before);
+        ensure!(
+            !output.is_zero() && output >= swap.min_output,
+            ContractError::SlippageExceeded {
+                min_output: swap.min_output,
+                output,
+            }
+        );
+
+        let key = (&swap.owner, denom.as_str());
+        let mut user = self.users.may_load(deps.storage, key)?.unwrap_or_default();
+        user.collateral += output;
+        self.save_user_at(deps.storage, key, &user, env.block.height)?;
+
+        let msg = ProviderMsg::Bond {
+            delegator: swap.owner.to_string(),
+            amount: coin(output.u128(), &denom),
+        };
+        let resp = Response::new()
+            .add_message(msg)
+            .add_attribute("action", "swap_and_bond")
+            .add_attribute("owner", swap.owner)
+            .add_attribute("input", swap.input.to_string())
+            .add_attribute("amount", output.to_string());
+
+        Ok(resp)
+    }
+
+    /// Fails unless `sender` is the admin of this contract
+    /// Returns the vault owner, or the contract admin if ownership was never transferred
+    fn current_owner(&self, deps: Deps, env: &Env) -> StdResult<Option<Addr>> {
+        if let Some(owner) = self.owner.may_load(deps.storage)? {
+            return Ok(Some(owner));
+        }
+        let info = deps
+            .querier
+            .query_wasm_contract_info(&env.contract.address)?;
+        Ok(info.admin.map(Addr::unchecked))
+    }
+
+    fn ensure_owner(&self, deps: Deps, env: &Env, sender: &Addr) -> Result<(), ContractError> {
+        let owner = self.current_owner(deps, env)?;
+        ensure!(
+            owner.as_ref() == Some(sender),
+            ContractError::Unauthorized {}
+        );
+        Ok(())
+    }
+
+    /// Fails if staking `amount` more with `lienholder` would exceed its cap
+    fn ensure_lienholder_cap(
+        &self,
+        storage: &dyn Storage,
+        lienholder: &Addr,
+        denom: &str,
+        amount: Uint128,
+    ) -> Result<(), ContractError> {
+        let cap = match self
+            .lienholder_caps
+            .may_load(storage, (lienholder, denom))?
+        {
+            Some(cap) => cap,
+            None => return Ok(()),
+        };
+        // Pending stakes count towards the cap
+        let total = self
+            .lienholder_totals
+            .may_load(storage, (lienholder, denom))?
+            .unwrap_or_default()
+            .high();
+        ensure!(
+            total + amount <= cap,
+            ContractError::LienholderCapReached(cap.saturating_sub(total))
+        );
+        Ok(())
+    }
+
+    /// Moves the running total of `lienholder` liens from `old` to `new`
+    fn update_lienholder_total(
+        &self,
+        storage: &mut dyn Storage,
+        lienholder: &Addr,
+        denom: &str,
+        old: ValueRange<Uint128>,
+        new: ValueRange<Uint128>,
+    ) -> StdResult<()> {
+        if old == new {
+            return Ok(());
+        }
+        let total = self
+            .lienholder_totals
+            .may_load(storage, (lienholder, denom))?
+            .unwrap_or_default();
+        // Saturating, as liens taken before totals were tracked are not accounted
+        let total = ValueRange::new(
+            (total.low() + new.low()).saturating_sub(old.low()),
+            (total.high() + new.high()).saturating_sub(old.high()),
+        );
+        self.lienholder_totals
+            .save(storage, (lienholder, denom), &total)
+    }
+
+    /// Saves user info after a collateral change, recording it in the collateral history
+    fn save_user_at(
+        &self,
+        storage: &mut dyn Storage,
+        key: (&Addr, &str),
+        user: &UserInfo,
+        height: u64,
+    ) -> StdResult<()> {
+        self.save_user(storage, key, user)?;
+        self.collateral_history
+            .save(storage, key, &user.collateral, height)
+    }
+
+    /// Recomputes the vault totals from all accounts and liens, as they were not tracked by
+    /// previous versions
+    fn seed_totals(&self, storage: &mut dyn Storage) -> StdResult<()> {
+        let users = self
+            .users
+            .range(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        let mut owners = BTreeMap::<Addr, ()>::new();
+        let mut collateral = BTreeMap::<String, Uint128>::new();
+        let mut slashable = BTreeMap::<String, ValueRange<Uint128>>::new();
+        for ((owner, denom), user) in users {
+            owners.insert(owner, ());
+            *collateral.entry(denom.clone()).or_default() += user.collateral;
+            let total = slashable.entry(denom).or_default();
+            *total = ValueRange::new(
+                total.low() + user.total_slashable.low(),
+                total.high() + user.total_slashable.high(),
+            );
+        }
+
+        let liens = self
+            .liens
+            .range(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        let mut lienholders = BTreeMap::<(Addr, String), ValueRange<Uint128>>::new();
+        for ((_, lienholder), lien) in liens {
+            let total = lienholders.entry((lienholder, lien.denom)).or_default();
+            *total = ValueRange::new(
+                total.low() + lien.amount.low(),
+                total.high() + lien.amount.high(),
+            );
+        }
+
+        self.account_count.save(storage, &(owners.len() as u64))?;
+        self.collateral_totals.clear(storage);
+        for (denom, total) in collateral {
+            self.collateral_totals.save(storage, &denom, &total)?;
+        }
+        self.slashable_totals.clear(storage);
+        for (denom, total) in slashable {
+            self.slashable_totals.save(storage, &denom, &total)?;
+        }
+        self.lienholder_totals.clear(storage);
+        for ((lienholder, denom), total) in lienholders {
+            self.lienholder_totals
+                .save(storage, (&lienholder, &denom), &total)?;
+        }
+        Ok(())
+    }
+
+    /// Saves user info, keeping the vault totals in sync
+    fn save_user(
+        &self,
+        storage: &mut dyn Storage,
+        key: (&Addr, &str),
+        user: &UserInfo,
+    ) -> StdResult<()> {
+        let (owner, denom) = key;
+        let old = match self.users.may_load(storage, key)? {
+            Some(old) => old,
+            None => {
+                // First denom bonded by this account
+                if self
+                    .users
+                    .prefix(owner)
+                    .keys(storage, None, None, Order::Ascending)
+                    .next()
+                    .is_none()
+                {
+                    let count = self.account_count.may_load(storage)?.unwrap_or_default();
+                    self.account_count.save(storage, &(count + 1))?;
+                }
+                UserInfo::default()
+            }
+        };
+
+        if old.collateral != user.collateral {
+            let total = self
+                .collateral_totals
+                .may_load(storage, denom)?
+                .unwrap_or_default();
+            // Saturating, as collateral bonded before totals were tracked is not accounted
+            let total = (total + user.collateral).saturating_sub(old.collateral);
+            self.collateral_totals.save(storage, denom, &total)?;
+        }
+        if old.total_slashable != user.total_slashable {
+            let total = self
+                .slashable_totals
+                .may_load(storage, denom)?
+                .unwrap_or_default();
+            let total = ValueRange::new(
+                (total.low() + user.total_slashable.low())
+                    .saturating_sub(old.total_slashable.low()),
+                (total.high() + user.total_slashable.high())
+                    .saturating_sub(old.total_slashable.high()),
+            );
+            self.slashable_totals.save(storage, denom, &total)?;
+        }
+
+        self.users.save(storage, key, user)
+    }
+
+    /// Liens of `owner` in `denom`, with their slashable part
+    fn account_lien_details(
+        &self,
+        storage: &dyn Storage,
+        owner: &Addr,
+        denom: &str,
+    ) -> StdResult<Vec<AccountLienDetails>> {
+        self.liens
+            .prefix(owner)
+            .range(storage, None, None, Order::Ascending)
+            .filter(|item| !matches!(item, Ok((_, lien)) if lien.denom != denom))
+            .map(|item| {
+                let (lienholder, lien) = item?;
+                Ok(AccountLienDetails {
+                    lienholder: lienholder.into_string(),
+                    slashable_amount: lien.amount * lien.slashable,
+                    amount: lien.amount,
+                    slashable: lien.slashable,
+                })
+            })
+            .collect()
+    }
+
+    /// Sum of the liens of `owner` in `denom`, taking pending liens at their highest value
+    fn total_liens(&self, storage: &dyn Storage, owner: &Addr, denom: &str) -> StdResult<Uint128> {
+        self.liens
+            .prefix(owner)
+            .range(storage, None, None, Order::Ascending)
+            .filter(|item| !matches!(item, Ok((_, lien)) if lien.denom != denom))
+            .map(|item| item.map(|(_, lien)| lien.amount.high()))
+            .sum()
+    }
+
+    /// Spends `amount` of the approval `owner` gave `operator`
+    fn spend_approval(
+        &self,
+        storage: &mut dyn Storage,
+        env: &Env,
+        owner: &Addr,
+        operator: &Addr,
+        amount: &Coin,
+    ) -> Result<(), ContractError> {
+        let key = (owner, operator, amount.denom.as_str());
+        let mut approval = self
+            .approvals
+            .may_load(storage, key)?
+            .ok_or(ContractError::NoApproval)?;
+        ensure!(
+            !approval.expires.is_expired(&env.block),
+            ContractError::ApprovalExpired
+        );
+        approval.spend_limit = approval
+            .spend_limit
+            .checked_sub(amount.amount)
+            .map_err(|_| ContractError::ApprovalExceeded(approval.spend_limit))?;
+        self.approvals.save(storage, key, &approval)?;
+        Ok(())
+    }
+
+    fn ensure_memo(&self, memo: &Option<String>) -> Result<(), ContractError> {
+        if let Some(memo) = memo {
+            ensure!(
+                memo.len() <= MAX_MEMO_LEN,
+                ContractError::MemoTooLong(MAX_MEMO_LEN)
+            );
+        }
+        Ok(())
+    }
+
+    /// Records an activity of `account`, dropping its dormancy flag
+    fn record_activity(
+        &self,
+        storage: &mut dyn Storage,
+        env: &Env,
+        account: &Addr,
+    ) -> StdResult<()> {
+        self.last_activity.save(storage, account, &env.block.time)?;
+        self.dormant_accounts.remove(storage, account);
+        Ok(())
+    }
+
+    fn ensure_not_paused(
+        &self,
+        storage: &dyn Storage,
+        operation: PausableOperation,
+    ) -> Result<(), ContractError> {
+        let paused = self.paused.may_load(storage)?.unwrap_or_default();
+        ensure!(
+            !paused.contains(&operation),
+            ContractError::Paused(operation)
+        );
+        Ok(())
+    }
+
+    fn ensure_pause_authority(
+        &self,
+        deps: Deps,
+        env: &Env,
+        sender: &Addr,
+    ) -> Result<(), ContractError> {
+        let authority = self.pause_authority.may_load(deps.storage)?.flatten();
+        if authority.as_ref() == Some(sender) {
+            return Ok(());
+        }
+        self.ensure_owner(deps, env, sender)
+    }
+
+    fn update_paused(
+        &self,
+        storage: &mut dyn Storage,
+        operations: &[PausableOperation],
+        paused: bool,
+    ) -> StdResult<()> {
+        let mut current = self.paused.may_load(storage)?.unwrap_or_default();
+        current.retain(|op| !operations.contains(op));
+        if paused {
+            // Operations listed more than once are only stored once
+            for op in operations {
+                if !current.contains(op) {
+                    current.push(*op);
+                }
+            }
+        }
+        self.paused.save(storage, &current)
+    }
+
+    fn pause_response(
+        &self,
+        action: &str,
+        operations: &[PausableOperation],
+    ) -> Response<ProviderCustomMsg> {
+        let operations = operations
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(",");
+        Response::new()
+            .add_attribute("action", action)
+            .add_attribute("operations", operations)
+    }
+
+    /// Returns the value of a single `denom` token. Only LST collateral has a rate other than one
+    fn collateral_rate(&self, storage: &dyn Storage, denom: &str) -> StdResult<Decimal> {
+        let rate = self
+            .lst_rates
+            .may_load(storage, denom)?
+            .map(|lst| lst.rate)
+            .unwrap_or(Decimal::one());
+        Ok(rate)
+    }
+
+    /// Fetches the exchange rate of the `denom` LST from `provider`, and stores it
+    fn update_lst_rate(
+        &self,
+        deps: DepsMut,
+        env: &Env,
+        denom: &str,
+        provider: Addr,
+    ) -> Result<Decimal, ContractError> {
+        let rate = query_exchange_rate(&deps.querier, &provider, denom)?;
+        ensure!(
+            !rate.is_zero(),
+            ContractError::ZeroExchangeRate(denom.to_owned())
+        );
+        let lst = LstRate {
+            provider,
+            rate,
+            updated_at: env.block.time,
+        };
+        self.lst_rates.save(deps.storage, denom, &lst)?;
+        Ok(rate)
+    }
+
+    fn ensure_collateral_denom(
+        &self,
+        storage: &dyn Storage,
+        denom: &str,
+    ) -> Result<(), ContractError> {
+        ensure!(
+            self.collateral_denoms.has(storage, denom),
+            ContractError::UnsupportedCollateralDenom(denom.to_owned())
+        );
+        Ok(())
+    }
+
     /// Updates the local stake for staking on any contract
     ///
-    /// Stake (both local and remote) is always called by the tokens owner, so the `sender` is
-    /// ued as an owner address.
+    /// The stake is taken on behalf of `owner`, who is either the sender or a user that approved
+    /// the sender as an operator.
     ///
-    /// Config is taken in argument as it sometimes is used outside of this function, so
-    /// we want to avoid double-fetching it
+    /// The lien is taken on the user's collateral in the staked denom. A lienholder can only
+    /// hold liens in a single denom per user. For LST collateral, the lien is the value of the
+    /// staked tokens.
     ///
     /// Remote indicates if the stake is remote or local. Remote staking involves transaction
     /// processing.
     fn stake(
         &self,
         ctx: &mut ExecCtx,
-        config: &Config,
+        owner: &Addr,
         lienholder: &Addr,
         slashable: Decimal,
         amount: Coin,
         remote: bool,
     ) -> Result<u64, ContractError> {
-        ensure!(
-            amount.denom == config.denom,
-            ContractError::UnexpectedDenom(config.denom.clone())
-        );
+        self.ensure_collateral_denom(ctx.deps.storage, &amount.denom)?;
+        self.record_activity(ctx.deps.storage, &ctx.env, owner)?;
 
-        let amount = amount.amount;
+        let Coin { denom, amount } = amount;
         let mut lien = self
             .liens
-            .may_load(ctx.deps.storage, (&ctx.info.sender, lienholder))?
+            .may_load(ctx.deps.storage, (owner, lienholder))?
             .unwrap_or_else(|| Lien {
+                denom: denom.clone(),
                 amount: ValueRange::new_val(Uint128::zero()),
                 slashable,
             });
+        ensure!(
+            lien.denom == denom,
+            ContractError::UnexpectedDenom(lien.denom)
+        );
         let mut user = self
             .users
-            .may_load(ctx.deps.storage, &ctx.info.sender)?
+            .may_load(ctx.deps.storage, (owner, &denom))?
             .unwrap_or_default();
+        // Liens are taken on the collateral value, at the current rate
+        let rate = self.collateral_rate(ctx.deps.storage, &denom)?;
+        let collateral = user.collateral * rate;
+        let amount = amount * rate;
+        let old_amount = lien.amount;
         if remote {
+            self.ensure_lienholder_cap(ctx.deps.storage, lienholder, &denom, amount)?;
             lien.amount
-                .prepare_add(amount, user.collateral)
+                .prepare_add(amount, collateral)
                 .map_err(|_| ContractError::InsufficentBalance)?;
             // Tentative value
             user.max_lien = max_range(user.max_lien, lien.amount);
             user.total_slashable
-                .prepare_add(amount * lien.slashable, user.collateral)
+                .prepare_add(amount * lien.slashable, collateral)
                 .map_err(|_| ContractError::InsufficentBalance)?;
         } else {
             // Update lien immediately
             lien.amount
-                .add(amount, user.collateral)
+                .add(amount, collateral)
                 .map_err(|_| ContractError::InsufficentBalance)?;
             // Update max lien and total slashable immediately
             user.max_lien = max_range(user.max_lien, lien.amount);
             user.total_slashable
-                .add(amount * lien.slashable, user.collateral)
+                .add(amount * lien.slashable, collateral)
                 .map_err(|_| ContractError::InsufficentBalance)?;
         }
 
-        ensure!(user.verify_collateral(), ContractError::InsufficentBalance);
+        ensure!(
+            user.valued(rate).verify_collateral(),
+            ContractError::InsufficentBalance
+        );
+        if let Some(max_leverage) = self.max_leverage.may_load(ctx.deps.storage)? {
+            // The lien being updated is replaced by its new value
+            let total_liens = self.total_liens(ctx.deps.storage, owner, &denom)?
+                - old_amount.high()
+                + lien.amount.high();
+            ensure!(
+                total_liens <= collateral * max_leverage,
+                ContractError::LeverageExceeded(max_leverage)
+            );
+        }
 
         self.liens
-            .save(ctx.deps.storage, (&ctx.info.sender, lienholder), &lien)?;
-        self.users.save(ctx.deps.storage, &ctx.info.sender, &user)?;
+            .save(ctx.deps.storage, (owner, lienholder), &lien)?;
+        self.update_lienholder_total(
+            ctx.deps.storage,
+            lienholder,
+            &denom,
+            old_amount,
+            lien.amount,
+        )?;
+        self.save_user(ctx.deps.storage, (owner, &denom), &user)?;
         let tx_id = if remote {
             // Create new tx
             let tx_id = self.next_tx_id(ctx.deps.storage)?;
@@ -669,10 +3444,12 @@ impl VaultContract<'_> {
                 id: tx_id,
                 amount,
                 slashable,
-                user: ctx.info.sender.clone(),
+                user: owner.clone(),
                 lienholder: lienholder.clone(),
             };
-            self.pending.txs.save(ctx.deps.storage, tx_id, &new_tx)?;
+            self.pending.save(ctx.deps.storage, tx_id, &new_tx)?;
+            self.pending_started
+                .save(ctx.deps.storage, tx_id, &ctx.env.block.time)?;
             tx_id
         } else {
             0
@@ -715,21 +3492,43 @@ impl VaultContract<'_> {
             .liens
             .load(ctx.deps.storage, (&tx_user, &tx_lienholder))?;
         // Commit it
+        let old_amount = lien.amount;
         lien.amount.commit_add(tx_amount);
         // Save it
         self.liens
             .save(ctx.deps.storage, (&tx_user, &tx_lienholder), &lien)?;
+        self.update_lienholder_total(
+            ctx.deps.storage,
+            &tx_lienholder,
+            &lien.denom,
+            old_amount,
+            lien.amount,
+        )?;
         // Load user
-        let mut user = self.users.load(ctx.deps.storage, &tx_user)?;
+        let user_key = (&tx_user, lien.denom.as_str());
+        let mut user = self.users.load(ctx.deps.storage, user_key)?;
         // Update max lien definitive value (it depends on the lien's value range)
         user.max_lien = max_range(user.max_lien, lien.amount);
         // Commit total slashable
         user.total_slashable.commit_add(tx_amount * lien.slashable);
         // Save it
-        self.users.save(ctx.deps.storage, &tx_user, &user)?;
+        self.save_user(ctx.deps.storage, user_key, &user)?;
+        self.tx_history.save(
+            ctx.deps.storage,
+            (&tx_user, tx_id),
+            &TxRecord {
+                lienholder: tx_lienholder,
+                denom: lien.denom,
+                amount: tx_amount,
+                outcome: TxOutcome::Committed,
+                completed_height: ctx.env.block.height,
+            },
+        )?;
 
         // Remove tx
-        self.pending.txs.remove(ctx.deps.storage, tx_id)?;
+        self.pending.remove(ctx.deps.storage, tx_id)?;
+        self.pending_started.remove(ctx.deps.storage, tx_id);
+        self.tx_memos.remove(ctx.deps.storage, tx_id);
 
         Ok(())
     }
@@ -754,6 +3553,79 @@ impl VaultContract<'_> {
             ContractError::WrongTypeTx(tx_id, tx)
         );
 
+        self.revert_stake(ctx.deps.storage, tx_id, tx, ctx.env.block.height)
+    }
+
+    /// Loads a pending stake tx, with its staker and start time
+    fn pending_stake_tx(
+        &self,
+        storage: &dyn Storage,
+        tx_id: u64,
+    ) -> Result<(Tx, Addr, Timestamp), ContractError> {
+        let tx = self.pending.txs.load(storage, tx_id)?;
+        let user = match &tx {
+            InFlightStaking { user, .. } => user.clone(),
+            _ => return Err(ContractError::WrongTypeTx(tx_id, tx)),
+        };
+        // Txs created before their start time was tracked are old enough
+        let started = self
+            .pending_started
+            .may_load(storage, tx_id)?
+            .unwrap_or_else(|| Timestamp::from_seconds(0));
+        Ok((tx, user, started))
+    }
+
+    /// Rolls back a pending stake tx its lienholder didn't settle in time, releasing its
+    /// tentative lien, and emits an `action` event. The lienholder is then asked to drop its side
+    /// of the stake. That is best-effort: a failing lienholder cannot keep the collateral locked
+    fn abort_stale_stake(
+        &self,
+        ctx: ExecCtx,
+        action: &str,
+        tx_id: u64,
+        tx: Tx,
+        started: Timestamp,
+    ) -> Result<Response<ProviderCustomMsg>, ContractError> {
+        let (user, lienholder, amount) = match &tx {
+            InFlightStaking {
+                user,
+                lienholder,
+                amount,
+                ..
+            } => (user.clone(), lienholder.clone(), *amount),
+            _ => return Err(ContractError::WrongTypeTx(tx_id, tx)),
+        };
+        let memo = self.tx_memos.may_load(ctx.deps.storage, tx_id)?;
+        self.revert_stake(ctx.deps.storage, tx_id, tx, ctx.env.block.height)?;
+        let msg = CrossStakingApiHelper(lienholder.clone()).abort_stake(tx_id)?;
+
+        let mut event = Event::new(action)
+            .add_attribute("tx_id", tx_id.to_string())
+            .add_attribute("owner", user)
+            .add_attribute("lienholder", lienholder)
+            .add_attribute("amount", amount.to_string())
+            .add_attribute("started", started.seconds().to_string());
+        if let Some(memo) = memo {
+            event = event.add_attribute("memo", memo);
+        }
+        let resp = Response::new()
+            .add_submessage(SubMsg::reply_on_error(msg, REPLY_ID_ABORT_STAKE))
+            .add_event(event)
+            .add_attribute("action", action)
+            .add_attribute("sender", ctx.info.sender)
+            .add_attribute("tx_id", tx_id.to_string());
+
+        Ok(resp)
+    }
+
+    /// Reverts the pending stake `tx`, releasing its tentative lien
+    fn revert_stake(
+        &self,
+        storage: &mut dyn Storage,
+        tx_id: u64,
+        tx: Tx,
+        height: u64,
+    ) -> Result<(), ContractError> {
         let (tx_amount, tx_slashable, tx_user, tx_lienholder) = match tx {
             InFlightStaking {
                 amount,
@@ -766,42 +3638,157 @@ impl VaultContract<'_> {
         };
 
         // Load lien
-        let mut lien = self
-            .liens
-            .load(ctx.deps.storage, (&tx_user, &tx_lienholder))?;
+        let mut lien = self.liens.load(storage, (&tx_user, &tx_lienholder))?;
         // Rollback amount
+        let old_amount = lien.amount;
         lien.amount.rollback_add(tx_amount);
+        self.update_lienholder_total(
+            storage,
+            &tx_lienholder,
+            &lien.denom,
+            old_amount,
+            lien.amount,
+        )?;
         if lien.amount.high().u128() == 0 {
             // Remove lien if it's empty
-            self.liens
-                .remove(ctx.deps.storage, (&tx_user, &tx_lienholder));
+            self.liens.remove(storage, (&tx_user, &tx_lienholder))?;
         } else {
             // Save lien
             self.liens
-                .save(ctx.deps.storage, (&tx_user, &tx_lienholder), &lien)?;
+                .save(storage, (&tx_user, &tx_lienholder), &lien)?;
         }
 
         // Load user
-        let mut user = self.users.load(ctx.deps.storage, &tx_user)?;
+        let user_key = (&tx_user, lien.denom.as_str());
+        let mut user = self.users.load(storage, user_key)?;
         // Rollback user's max_lien
 
         // Max lien has to be recalculated from scratch; the just rolled back lien
         // is already written to storage
-        self.recalculate_max_lien(ctx.deps.storage, &tx_user, &mut user)?;
+        self.recalculate_max_lien(storage, &tx_user, &lien.denom, &mut user)?;
 
         user.total_slashable.rollback_add(tx_amount * tx_slashable);
-        self.users.save(ctx.deps.storage, &tx_user, &user)?;
+        self.save_user(storage, user_key, &user)?;
+        self.fulfil_unbond_requests(storage, &tx_user, &lien.denom, height)?;
+        self.tx_history.save(
+            storage,
+            (&tx_user, tx_id),
+            &TxRecord {
+                lienholder: tx_lienholder,
+                denom: lien.denom,
+                amount: tx_amount,
+                outcome: TxOutcome::RolledBack,
+                completed_height: height,
+            },
+        )?;
 
         // Remove tx
-        self.pending.txs.remove(ctx.deps.storage, tx_id)?;
+        self.pending.remove(storage, tx_id)?;
+        self.pending_started.remove(storage, tx_id);
+        self.tx_memos.remove(storage, tx_id);
         Ok(())
     }
 
-    /// Recalculates the max lien for the user
+    /// Returns the message returning unbonded collateral to `owner`
+    fn unbond_msg(&self, owner: &Addr, amount: Coin) -> StdResult<CosmosMsg<ProviderCustomMsg>> {
+        let msg = match cw20_token(&amount.denom) {
+            Some(token) => Cw20ExecuteMsg::Transfer {
+                recipient: owner.to_string(),
+                amount: amount.amount,
+            }
+            .into_msg(&token)?
+            .into(),
+            None => ProviderMsg::Unbond {
+                delegator: owner.to_string(),
+                amount,
+            }
+            .into(),
+        };
+        Ok(msg)
+    }
+
+    /// Returns the `denom` collateral of `user` waiting in unbond requests
+    fn requested_unbonds(
+        &self,
+        storage: &dyn Storage,
+        user: &Addr,
+        denom: &str,
+    ) -> StdResult<Uint128> {
+        self.unbond_requests
+            .prefix(user)
+            .range(storage, None, None, Order::Ascending)
+            .try_fold(Uint128::zero(), |total, item| {
+                let (_, request) = item?;
+                if request.denom != denom {
+                    return Ok(total);
+                }
+                Ok(total + request.amount)
+            })
+    }
+
+    /// Fulfils the `denom` unbond requests of `user` with its free collateral, oldest first.
+    /// A partially fulfilled request blocks the newer ones.
+    ///
+    /// Returns the amount of collateral unbonded
+    fn fulfil_unbond_requests(
+        &self,
+        storage: &mut dyn Storage,
+        user: &Addr,
+        denom: &str,
+        height: u64,
+    ) -> Result<Uint128, ContractError> {
+        let requests = self
+            .unbond_requests
+            .prefix(user)
+            .range(storage, None, None, Order::Ascending)
+            .filter(|item| {
+                item.as_ref()
+                    .map(|(_, request)| request.denom == denom) // Skip other denoms
+                    .unwrap_or(true) // Keep errors, so they are reported
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+        if requests.is_empty() {
+            return Ok(Uint128::zero());
+        }
+
+        let key = (user, denom);
+        let mut user_info = self.users.may_load(storage, key)?.unwrap_or_default();
+        // Free collateral is expressed in value, requests in tokens
+        let rate = self.collateral_rate(storage, denom)?;
+        let mut free = user_info
+            .valued(rate)
+            .free_collateral()
+            .low()
+            .div_floor(rate);
+        let mut unbonded = Uint128::zero();
+        for (id, mut request) in requests {
+            let amount = min(request.amount, free);
+            request.amount -= amount;
+            free -= amount;
+            unbonded += amount;
+            if request.amount.is_zero() {
+                self.unbond_requests.remove(storage, (user, id));
+            } else {
+                self.unbond_requests.save(storage, (user, id), &request)?;
+                break;
+            }
+        }
+
+        if !unbonded.is_zero() {
+            user_info.collateral -= unbonded;
+            self.save_user_at(storage, key, &user_info, height)?;
+            let claimable = self.unbonded.may_load(storage, key)?.unwrap_or_default();
+            self.unbonded.save(storage, key, &(claimable + unbonded))?;
+        }
+        Ok(unbonded)
+    }
+
+    /// Recalculates the max lien for the user in the given denom
     fn recalculate_max_lien(
         &self,
         storage: &mut dyn Storage,
         user: &Addr,
+        denom: &str,
         user_info: &mut UserInfo,
     ) -> Result<(), ContractError> {
         user_info.max_lien = self
@@ -810,6 +3797,9 @@ impl VaultContract<'_> {
             .range(storage, None, None, Order::Ascending)
             .try_fold(ValueRange::new_val(Uint128::zero()), |max_lien, item| {
                 let (_, lien) = item?;
+                if lien.denom != denom {
+                    return Ok(max_lien);
+                }
                 Ok::<_, ContractError>(max_range(max_lien, lien.amount))
             })?;
         Ok(())
@@ -820,44 +3810,142 @@ impl VaultContract<'_> {
     /// The unstake (both local and remote) is always called by the staking contract
     /// (aka lien_holder), so the `sender` address is used for that.
     fn unstake(&self, ctx: &mut ExecCtx, owner: String, amount: Coin) -> Result<(), ContractError> {
-        let denom = self.config.load(ctx.deps.storage)?.denom;
-        ensure!(amount.denom == denom, ContractError::UnexpectedDenom(denom));
-        let amount = amount.amount;
-
         let owner = Addr::unchecked(owner);
+        self.release_lien(ctx.deps.storage, &owner, &ctx.info.sender, amount)
+    }
+
+    /// Releases `amount` of the `owner` lien held by `lienholder`
+    fn release_lien(
+        &self,
+        storage: &mut dyn Storage,
+        owner: &Addr,
+        lienholder: &Addr,
+        amount: Coin,
+    ) -> Result<(), ContractError> {
         let mut lien = self
             .liens
-            .may_load(ctx.deps.storage, (&owner, &ctx.info.sender))?
+            .may_load(storage, (owner, lienholder))?
             .ok_or(ContractError::UnknownLienholder)?;
+        ensure!(
+            amount.denom == lien.denom,
+            ContractError::UnexpectedDenom(lien.denom)
+        );
+        let amount = self.released_value(storage, &lien, amount.amount)?;
 
         let slashable = lien.slashable;
+        let old_amount = lien.amount;
         lien.amount
             .sub(amount, Uint128::zero())
             .map_err(|_| ContractError::InsufficientLien)?;
+        self.update_lienholder_total(storage, lienholder, &lien.denom, old_amount, lien.amount)?;
 
         if lien.amount.high().u128() == 0 {
             // Remove lien if it's empty
-            self.liens
-                .remove(ctx.deps.storage, (&owner, &ctx.info.sender));
+            self.liens.remove(storage, (owner, lienholder))?;
         } else {
             // Save lien
-            self.liens
-                .save(ctx.deps.storage, (&owner, &ctx.info.sender), &lien)?;
+            self.liens.save(storage, (owner, lienholder), &lien)?;
         }
 
-        let mut user = self.users.load(ctx.deps.storage, &owner)?;
+        let user_key = (owner, lien.denom.as_str());
+        let mut user = self.users.load(storage, user_key)?;
 
         // Max lien has to be recalculated from scratch; the just saved lien
         // is already written to storage
-        self.recalculate_max_lien(ctx.deps.storage, &owner, &mut user)?;
+        self.recalculate_max_lien(storage, owner, &lien.denom, &mut user)?;
 
         user.total_slashable
             .sub(amount * slashable, Uint128::zero())?;
-        self.users.save(ctx.deps.storage, &owner, &user)?;
+        self.save_user(storage, user_key, &user)?;
+
+        Ok(())
+    }
+
+    /// Returns the value of `amount` tokens released or slashed from `lien`, at the current rate.
+    /// LST liens are taken at the rate of their time. Once it went up, the tokens are worth more
+    /// than what is left of the lien, which is then released whole
+    fn released_value(
+        &self,
+        storage: &dyn Storage,
+        lien: &Lien,
+        amount: Uint128,
+    ) -> StdResult<Uint128> {
+        let value = amount * self.collateral_rate(storage, &lien.denom)?;
+        if self.lst_rates.has(storage, &lien.denom) {
+            return Ok(min(value, lien.amount.low()));
+        }
+        Ok(value)
+    }
+
+    /// Takes a committed lien of `amount` for `lienholder` on the `owner` collateral
+    fn take_lien(
+        &self,
+        storage: &mut dyn Storage,
+        owner: &Addr,
+        lienholder: &Addr,
+        slashable: Decimal,
+        amount: Coin,
+    ) -> Result<(), ContractError> {
+        let Coin { denom, amount } = amount;
+        // Liens are taken on the collateral value, at the current rate
+        let rate = self.collateral_rate(storage, &denom)?;
+        let amount = amount * rate;
+        self.ensure_lienholder_cap(storage, lienholder, &denom, amount)?;
+        let mut lien = self
+            .liens
+            .may_load(storage, (owner, lienholder))?
+            .unwrap_or_else(|| Lien {
+                denom: denom.clone(),
+                amount: ValueRange::new_val(Uint128::zero()),
+                slashable,
+            });
+        ensure!(
+            lien.denom == denom,
+            ContractError::UnexpectedDenom(lien.denom)
+        );
+        let mut user = self.users.load(storage, (owner, &denom))?;
+        let collateral = user.collateral * rate;
+        let old_amount = lien.amount;
+        lien.amount
+            .add(amount, collateral)
+            .map_err(|_| ContractError::InsufficentBalance)?;
+        user.max_lien = max_range(user.max_lien, lien.amount);
+        user.total_slashable
+            .add(amount * lien.slashable, collateral)
+            .map_err(|_| ContractError::InsufficentBalance)?;
+        ensure!(
+            user.valued(rate).verify_collateral(),
+            ContractError::InsufficentBalance
+        );
 
+        self.liens.save(storage, (owner, lienholder), &lien)?;
+        self.update_lienholder_total(storage, lienholder, &denom, old_amount, lien.amount)?;
+        self.save_user(storage, (owner, &denom), &user)?;
         Ok(())
     }
 
+    /// Returns the slash ratio of a lienholder liens can be transferred to, i.e. the local
+    /// staking contract or an allowed cross-staking contract
+    fn lienholder_slash_ratio(
+        &self,
+        deps: Deps,
+        lienholder: &Addr,
+    ) -> Result<Decimal, ContractError> {
+        if let Some(local_staking) = self.local_staking.load(deps.storage)? {
+            if local_staking.contract.0 == lienholder {
+                return Ok(local_staking.max_slash);
+            }
+        }
+        ensure!(
+            self.allowed_lienholders.has(deps.storage, lienholder),
+            ContractError::LienholderNotAllowed(lienholder.to_string())
+        );
+        let slashable = CrossStakingApiHelper(lienholder.clone())
+            .max_slash(deps)?
+            .slash_ratio_dsign;
+        Ok(slashable)
+    }
+
     /// Processes a (remote or local) slashing event.
     ///
     /// This slashes the users that have funds delegated to the validator involved in the
@@ -869,9 +3957,13 @@ impl VaultContract<'_> {
         ctx: &mut ExecCtx,
         slashes: &[SlashInfo],
         validator: &str,
-    ) -> Result<Vec<WasmMsg>, ContractError> {
+    ) -> Result<Vec<CosmosMsg<ProviderCustomMsg>>, ContractError> {
         // Process users that belong to lien_holder
         let lien_holder = ctx.info.sender.clone();
+        let dust_policy = self
+            .slash_dust_policy
+            .may_load(ctx.deps.storage)?
+            .unwrap_or_default();
         let mut msgs = vec![];
         for slash in slashes {
             let slash_user = Addr::unchecked(slash.user.clone());
@@ -879,12 +3971,26 @@ impl VaultContract<'_> {
             let mut lien = self
                 .liens
                 .load(ctx.deps.storage, (&slash_user, &lien_holder))?;
-            let slash_amount = slash.slash;
-            let mut user_info = self.users.load(ctx.deps.storage, &slash_user)?;
-            let new_collateral = user_info.collateral - slash_amount;
+            // Only the collateral in the lien's denom is affected
+            let denom = lien.denom.clone();
+            let mut user_info = self.users.load(ctx.deps.storage, (&slash_user, &denom))?;
+            // Slashes are expressed in tokens, while liens are in value. The slashed tokens are
+            // valued at the current rate
+            let rate = self.collateral_rate(ctx.deps.storage, &denom)?;
+            let slash_tokens = min(slash.slash, user_info.collateral);
+            let slash_amount = self.released_value(ctx.deps.storage, &lien, slash.slash)?;
+            let new_collateral = (user_info.collateral - slash_tokens) * rate;
 
             // Slash user
+            let old_amount = lien.amount;
             lien.amount.sub(slash_amount, Uint128::zero())?;
+            self.update_lienholder_total(
+                ctx.deps.storage,
+                &lien_holder,
+                &denom,
+                old_amount,
+                lien.amount,
+            )?;
             // Save lien
             self.liens
                 .save(ctx.deps.storage, (&slash_user, &lien_holder), &lien)?;
@@ -892,28 +3998,53 @@ impl VaultContract<'_> {
             user_info
                 .total_slashable
                 .sub(slash_amount * lien.slashable, Uint128::zero())?;
-            self.recalculate_max_lien(ctx.deps.storage, &slash_user, &mut user_info)?;
+            self.recalculate_max_lien(ctx.deps.storage, &slash_user, &denom, &mut user_info)?;
             // Get free collateral before adjusting collateral, but after slashing
-            let free_collateral = user_info.free_collateral().low(); // For simplicity
+            let free_collateral = user_info.valued(rate).free_collateral().low(); // For simplicity
             if free_collateral < slash_amount {
                 // Check / adjust mesh security invariants according to the new collateral
-                let burn_msgs = self.propagate_slash(
+                let (burn_msgs, donated) = self.propagate_slash(
                     ctx.deps.storage,
                     &slash_user,
+                    &denom,
                     &mut user_info,
                     new_collateral,
                     slash_amount - free_collateral,
                     &lien_holder,
                     validator,
+                    &dust_policy,
                 )?;
-                msgs.extend_from_slice(&burn_msgs);
+                msgs.extend(burn_msgs.into_iter().map(Into::into));
+                if let SlashDustPolicy::Donate { recipient } = &dust_policy {
+                    if !donated.is_zero() {
+                        user_info.collateral -= donated;
+                        msgs.push(self.unbond_msg(recipient, coin(donated.u128(), &denom))?);
+                    }
+                }
             }
             // Adjust collateral
-            user_info.collateral = new_collateral;
+            user_info.collateral -= slash_tokens;
+            // Slashed CW20 collateral is held by the vault, so it has to be burned here
+            if let Some(token) = cw20_token(&denom) {
+                if !slash_tokens.is_zero() {
+                    msgs.push(
+                        Cw20ExecuteMsg::Burn {
+                            amount: slash_tokens,
+                        }
+                        .into_msg(&token)?
+                        .into(),
+                    );
+                }
+            }
             // Recompute max lien
-            self.recalculate_max_lien(ctx.deps.storage, &slash_user, &mut user_info)?;
+            self.recalculate_max_lien(ctx.deps.storage, &slash_user, &denom, &mut user_info)?;
             // Save user info
-            self.users.save(ctx.deps.storage, &slash_user, &user_info)?;
+            self.save_user_at(
+                ctx.deps.storage,
+                (&slash_user, &denom),
+                &user_info,
+                ctx.env.block.height,
+            )?;
         }
         Ok(msgs)
     }
@@ -923,15 +4054,19 @@ impl VaultContract<'_> {
         &self,
         storage: &mut dyn Storage,
         user: &Addr,
+        denom: &str,
         user_info: &mut UserInfo,
         new_collateral: Uint128,
         claimed_collateral: Uint128,
         slashed_lien_holder: &Addr,
         slashed_validator: &str,
-    ) -> Result<Vec<WasmMsg>, ContractError> {
-        let denom = self.config.load(storage)?.denom;
+        dust_policy: &SlashDustPolicy,
+    ) -> Result<(Vec<WasmMsg>, Uint128), ContractError> {
         let native_staking = self.local_staking.load(storage)?;
+        // Lienholders stake tokens, so the value taken from their liens is burned in (whole) tokens
+        let rate = self.collateral_rate(storage, denom)?;
         let mut msgs = vec![];
+        let mut donated = Uint128::zero();
         if user_info.max_lien.high() >= user_info.total_slashable.high() {
             // Liens adjustment
             let broken_liens = self
@@ -940,7 +4075,7 @@ impl VaultContract<'_> {
                 .range(storage, None, None, Order::Ascending)
                 .filter(|item| {
                     item.as_ref()
-                        .map(|(_, lien)| lien.amount.high() > new_collateral) // Skip in range liens
+                        .map(|(_, lien)| lien.denom == denom && lien.amount.high() > new_collateral) // Skip other denoms and in range liens
                         .unwrap_or(false) // Skip other errors
                 })
                 .collect::<StdResult<Vec<_>>>()?;
@@ -955,7 +4090,15 @@ impl VaultContract<'_> {
                     user_info.total_slashable.high() - adjust_amount_high * lien.slashable,
                 );
                 // Keep the invariant over the lien
-                lien.amount = ValueRange::new(new_low_amount, new_high_amount);
+                let new_amount = ValueRange::new(new_low_amount, new_high_amount);
+                self.update_lienholder_total(
+                    storage,
+                    &lien_holder,
+                    denom,
+                    lien.amount,
+                    new_amount,
+                )?;
+                lien.amount = new_amount;
                 self.liens.save(storage, (user, &lien_holder), &lien)?;
                 // Remove the required amount from the user's stake
                 let validator = if lien_holder == slashed_lien_holder {
@@ -965,10 +4108,10 @@ impl VaultContract<'_> {
                 };
                 let burn_msg = self.burn_stake(
                     user,
-                    &denom,
+                    denom,
                     &native_staking,
                     &lien_holder,
-                    adjust_amount_high, // High amount for simplicity
+                    adjust_amount_high.div_ceil(rate), // High amount for simplicity
                     validator,
                 )?;
                 msgs.push(burn_msg);
@@ -981,29 +4124,62 @@ impl VaultContract<'_> {
                 .range(storage, None, None, Order::Ascending)
                 .try_fold(Decimal::zero(), |sum, item| {
                     let (_, lien) = item?;
+                    if lien.denom != denom {
+                        return Ok(sum);
+                    }
                     Ok::<_, ContractError>(sum + lien.slashable)
                 })?;
-            let round_up = if (claimed_collateral * slash_ratio_sum.inv().unwrap())
-                * slash_ratio_sum
-                != claimed_collateral
-            {
+            let exact_amount = claimed_collateral * slash_ratio_sum.inv().unwrap();
+            let round_up = if exact_amount * slash_ratio_sum != claimed_collateral {
                 Uint128::one()
             } else {
                 Uint128::zero()
             };
-            let sub_amount = claimed_collateral * slash_ratio_sum.inv().unwrap() + round_up;
             let all_liens = self
                 .liens
                 .prefix(user)
                 .range(storage, None, None, Order::Ascending)
+                .filter(|item| {
+                    item.as_ref()
+                        .map(|(_, lien)| lien.denom == denom) // Skip other denoms
+                        .unwrap_or(true) // Keep errors, so they are reported
+                })
                 .collect::<StdResult<Vec<_>>>()?;
+            // Rounding up takes some dust from every lien, to keep the mesh security invariants.
+            // The dust policy decides whether it is burned along with the stake, or taken from the
+            // collateral for the donation recipient or left to the user instead
+            let dust = round_up * Uint128::new(all_liens.len() as u128);
+            let sub_amount = match dust_policy {
+                _ if dust.is_zero() => exact_amount,
+                SlashDustPolicy::Burn => {
+                    self.add_slash_dust(storage, denom, |d| d.burned += dust)?;
+                    exact_amount + round_up
+                }
+                SlashDustPolicy::Donate { .. } => {
+                    self.add_slash_dust(storage, denom, |d| d.donated += dust)?;
+                    donated = min(dust.div_ceil(rate), new_collateral.div_floor(rate));
+                    exact_amount
+                }
+                SlashDustPolicy::Ignore => {
+                    self.add_slash_dust(storage, denom, |d| d.ignored += dust)?;
+                    exact_amount
+                }
+            };
             for (lien_holder, mut lien) in all_liens {
                 // Adjust the user's total slashable amount
                 user_info
                     .total_slashable
                     .sub(sub_amount * lien.slashable, Uint128::zero())?;
                 // Keep the invariant over the lien
+                let old_amount = lien.amount;
                 lien.amount.sub(sub_amount, Uint128::zero())?;
+                self.update_lienholder_total(
+                    storage,
+                    &lien_holder,
+                    denom,
+                    old_amount,
+                    lien.amount,
+                )?;
                 self.liens.save(storage, (user, &lien_holder), &lien)?;
                 // Remove the required amount from the user's stake
                 let validator = if lien_holder == slashed_lien_holder {
@@ -1013,22 +4189,36 @@ impl VaultContract<'_> {
                 };
                 let burn_msg = self.burn_stake(
                     user,
-                    &denom,
+                    denom,
                     &native_staking,
                     &lien_holder,
-                    sub_amount,
+                    sub_amount.div_ceil(rate),
                     validator,
                 )?;
                 msgs.push(burn_msg);
             }
         }
-        Ok(msgs)
+        Ok((msgs, donated))
+    }
+
+    fn add_slash_dust(
+        &self,
+        storage: &mut dyn Storage,
+        denom: &str,
+        update: impl FnOnce(&mut SlashDust),
+    ) -> StdResult<()> {
+        let mut dust = self
+            .slash_dust
+            .may_load(storage, denom)?
+            .unwrap_or_default();
+        update(&mut dust);
+        self.slash_dust.save(storage, denom, &dust)
     }
 
     fn burn_stake(
         &self,
         user: &Addr,
-        denom: &String,
+        denom: &str,
         native_staking: &Option<LocalStaking>,
         lien_holder: &Addr,
         amount: Uint128,
@@ -1071,6 +4261,13 @@ impl VaultApi for VaultContract<'_> {
         nonpayable(&ctx.info)?;
 
         self.unstake(&mut ctx, owner.clone(), amount.clone())?;
+        // Released collateral goes to pending unbond requests first
+        self.fulfil_unbond_requests(
+            ctx.deps.storage,
+            &Addr::unchecked(&owner),
+            &amount.denom,
+            ctx.env.block.height,
+        )?;
 
         let resp = Response::new()
             .add_attribute("action", "release_cross_stake")
@@ -1081,6 +4278,68 @@ impl VaultApi for VaultContract<'_> {
         Ok(resp)
     }
 
+    fn transfer_lien(
+        &self,
+        ctx: ExecCtx,
+        owner: String,
+        from_lienholder: String,
+        to_lienholder: String,
+        amount: Coin,
+    ) -> Result<Response<Self::ExecC>, ContractError> {
+        nonpayable(&ctx.info)?;
+        let owner = ctx.deps.api.addr_validate(&owner)?;
+        let from_lienholder = ctx.deps.api.addr_validate(&from_lienholder)?;
+        let to_lienholder = ctx.deps.api.addr_validate(&to_lienholder)?;
+        ensure!(
+            ctx.info.sender == from_lienholder || ctx.info.sender == to_lienholder,
+            ContractError::Unauthorized {}
+        );
+        let slashable = self.lienholder_slash_ratio(ctx.deps.as_ref(), &to_lienholder)?;
+
+        let key = (&owner, &from_lienholder, &to_lienholder);
+        let proposed = self.lien_transfers.may_load(ctx.deps.storage, key)?;
+        let action = match proposed {
+            // The other lienholder agreed on the same transfer
+            Some(transfer) if transfer.proposer != ctx.info.sender && transfer.amount == amount => {
+                self.lien_transfers.remove(ctx.deps.storage, key);
+                self.release_lien(ctx.deps.storage, &owner, &from_lienholder, amount.clone())?;
+                self.take_lien(
+                    ctx.deps.storage,
+                    &owner,
+                    &to_lienholder,
+                    slashable,
+                    amount.clone(),
+                )?;
+                if self
+                    .allowed_lienholders
+                    .has(ctx.deps.storage, &to_lienholder)
+                {
+                    self.active_external
+                        .save(ctx.deps.storage, &to_lienholder, &())?;
+                }
+                "transfer_lien"
+            }
+            _ => {
+                let transfer = LienTransfer {
+                    amount: amount.clone(),
+                    proposer: ctx.info.sender.clone(),
+                };
+                self.lien_transfers.save(ctx.deps.storage, key, &transfer)?;
+                "propose_lien_transfer"
+            }
+        };
+
+        let resp = Response::new()
+            .add_attribute("action", action)
+            .add_attribute("sender", ctx.info.sender)
+            .add_attribute("owner", owner)
+            .add_attribute("from_lienholder", from_lienholder)
+            .add_attribute("to_lienholder", to_lienholder)
+            .add_attribute("amount", amount.to_string());
+
+        Ok(resp)
+    }
+
     /// This must be called by the local staking contract to release this claim
     /// Amount of tokens unstaked are those included in ctx.info.funds
     fn release_local_stake(
@@ -1092,7 +4351,13 @@ impl VaultApi for VaultContract<'_> {
         let denom = self.config.load(ctx.deps.storage)?.denom;
         let amount = must_pay(&ctx.info, &denom)?;
 
-        self.unstake(&mut ctx, owner.clone(), coin(amount.u128(), denom))?;
+        self.unstake(&mut ctx, owner.clone(), coin(amount.u128(), &denom))?;
+        self.fulfil_unbond_requests(
+            ctx.deps.storage,
+            &Addr::unchecked(&owner),
+            &denom,
+            ctx.env.block.height,
+        )?;
 
         let resp = Response::new()
             .add_attribute("action", "release_cross_stake")
@@ -1103,6 +4368,93 @@ impl VaultApi for VaultContract<'_> {
         Ok(resp)
     }
 
+    /// This must be called by the local staking contract after compounding staking rewards
+    fn compound_local_stake(
+        &self,
+        mut ctx: ExecCtx,
+        owner: String,
+        amount: Coin,
+    ) -> Result<Response<Self::ExecC>, Self::Error> {
+        nonpayable(&ctx.info)?;
+
+        let local_staking = self
+            .local_staking
+            .load(ctx.deps.storage)?
+            .ok_or(ContractError::NoLocalStaking)?;
+        ensure!(
+            local_staking.contract.0 == ctx.info.sender,
+            ContractError::Unauthorized {}
+        );
+        let denom = self.config.load(ctx.deps.storage)?.denom;
+        ensure!(denom == amount.denom, ContractError::UnexpectedDenom(denom));
+
+        // The compounded tokens stay delegated, backing both the new collateral and the new lien
+        let owner = ctx.deps.api.addr_validate(&owner)?;
+        let key = (&owner, amount.denom.as_str());
+        let mut user = self
+            .users
+            .may_load(ctx.deps.storage, key)?
+            .unwrap_or_default();
+        user.collateral += amount.amount;
+        self.save_user_at(ctx.deps.storage, key, &user, ctx.env.block.height)?;
+        self.stake(
+            &mut ctx,
+            &owner,
+            &local_staking.contract.0,
+            local_staking.max_slash,
+            amount.clone(),
+            false,
+        )?;
+
+        let resp = Response::new()
+            .add_attribute("action", "compound_local_stake")
+            .add_attribute("sender", ctx.info.sender)
+            .add_attribute("owner", owner)
+            .add_attribute("amount", amount.amount.to_string());
+
+        Ok(resp)
+    }
+
+    /// This must be called by the external staking contract before compounding staking rewards
+    fn compound_cross_stake(
+        &self,
+        mut ctx: ExecCtx,
+        owner: String,
+        amount: Coin,
+    ) -> Result<Response<Self::ExecC>, Self::Error> {
+        nonpayable(&ctx.info)?;
+        self.ensure_not_paused(ctx.deps.storage, PausableOperation::StakeRemote)?;
+
+        let lienholder = ctx.info.sender.clone();
+        ensure!(
+            self.allowed_lienholders.has(ctx.deps.storage, &lienholder),
+            ContractError::LienholderNotAllowed(lienholder.into_string())
+        );
+        let slashable = CrossStakingApiHelper(lienholder.clone())
+            .max_slash(ctx.deps.as_ref())?
+            .slash_ratio_dsign;
+        self.ensure_lienholder_cap(ctx.deps.storage, &lienholder, &amount.denom, amount.amount)?;
+
+        // The rewards stay on the consumer, so the new lien has to fit in the free collateral
+        let owner = ctx.deps.api.addr_validate(&owner)?;
+        self.stake(
+            &mut ctx,
+            &owner,
+            &lienholder,
+            slashable,
+            amount.clone(),
+            false,
+        )?;
+
+        let resp = Response::new()
+            .add_attribute("action", "compound_cross_stake")
+            .add_attribute("sender", ctx.info.sender)
+            .add_attribute("owner", owner)
+            .add_attribute("amount", amount.amount.to_string());
+
+        Ok(resp)
+    }
+
     /// This must be called by the native staking contract to process a misbehaviour
     fn local_slash(
         &self,
@@ -1164,12 +4516,16 @@ impl VaultApi for VaultContract<'_> {
         mut ctx: ExecCtx,
         tx_id: u64,
     ) -> Result<Response<Self::ExecC>, ContractError> {
+        let memo = self.tx_memos.may_load(ctx.deps.storage, tx_id)?;
         self.commit_stake(&mut ctx, tx_id)?;
 
-        let resp = Response::new()
+        let mut resp = Response::new()
             .add_attribute("action", "commit_tx")
             .add_attribute("sender", ctx.info.sender)
             .add_attribute("tx_id", tx_id.to_string());
+        if let Some(memo) = memo {
+            resp = resp.add_attribute("memo", memo);
+        }
 
         Ok(resp)
     }
@@ -1179,12 +4535,191 @@ impl VaultApi for VaultContract<'_> {
         mut ctx: ExecCtx,
         tx_id: u64,
     ) -> Result<Response<Self::ExecC>, ContractError> {
+        let memo = self.tx_memos.may_load(ctx.deps.storage, tx_id)?;
         self.rollback_stake(&mut ctx, tx_id)?;
 
-        let resp = Response::new()
+        let mut resp = Response::new()
             .add_attribute("action", "rollback_tx")
             .add_attribute("sender", ctx.info.sender)
             .add_attribute("tx_id", tx_id.to_string());
+        if let Some(memo) = memo {
+            resp = resp.add_attribute("memo", memo);
+        }
         Ok(resp)
     }
+
+    fn auto_renew(
+        &self,
+        ctx: QueryCtx,
+        account: String,
+    ) -> Result<AutoRenewResponse, ContractError> {
+        let account = ctx.deps.api.addr_validate(&account)?;
+        let enabled = self.auto_renew_accounts.has(ctx.deps.storage, &account);
+        Ok(AutoRenewResponse { enabled })
+    }
+
+    fn lien(
+        &self,
+        ctx: QueryCtx,
+        account: String,
+        lienholder: String,
+    ) -> Result<LienAmountResponse, ContractError> {
+        let account = ctx.deps.api.addr_validate(&account)?;
+        let lienholder = ctx.deps.api.addr_validate(&lienholder)?;
+        let amount = self
+            .liens
+            .may_load(ctx.deps.storage, (&account, &lienholder))?
+            .map(|lien| lien.amount)
+            .unwrap_or_default();
+        Ok(LienAmountResponse {
+            low: amount.low(),
+            high: amount.high(),
+        })
+    }
+
+    fn lienholder_total(
+        &self,
+        ctx: QueryCtx,
+        lienholder: String,
+        denom: String,
+    ) -> Result<LienAmountResponse, ContractError> {
+        let lienholder = ctx.deps.api.addr_validate(&lienholder)?;
+        let total = self
+            .lienholder_totals
+            .may_load(ctx.deps.storage, (&lienholder, &denom))?
+            .unwrap_or_default();
+        Ok(LienAmountResponse {
+            low: total.low(),
+            high: total.high(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_schema::cw_serde;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env};
+
+    /// Lien as stored before it recorded its denom
+    #[cw_serde]
+    struct LegacyLien {
+        amount: ValueRange<Uint128>,
+        slashable: Decimal,
+    }
+
+    #[test]
+    fn migration_rekeys_single_denom_accounting() {
+        let mut deps = mock_dependencies();
+        let contract = VaultContract::new();
+        let user = Addr::unchecked("user");
+        let lienholder = Addr::unchecked("lienholder");
+
+        // Single-denom layout
+        contract
+            .config
+            .save(
+                &mut deps.storage,
+                &Config {
+                    denom: "uosmo".to_string(),
+                },
+            )
+            .unwrap();
+        let info = UserInfo {
+            collateral: Uint128::new(100),
+            max_lien: ValueRange::new_val(Uint128::new(50)),
+            total_slashable: ValueRange::new_val(Uint128::new(5)),
+        };
+        Map::<&Addr, UserInfo>::new("users")
+            .save(&mut deps.storage, &user, &info)
+            .unwrap();
+        Map::<(&Addr, &Addr), LegacyLien>::new("liens")
+            .save(
+                &mut deps.storage,
+                (&user, &lienholder),
+                &LegacyLien {
+                    amount: ValueRange::new_val(Uint128::new(50)),
+                    slashable: Decimal::percent(10),
+                },
+            )
+            .unwrap();
+        // Indexed under the same namespace as the legacy users
+        let tx = Tx::InFlightStaking {
+            id: 1,
+            amount: Uint128::new(10),
+            slashable: Decimal::percent(10),
+            user: user.clone(),
+            lienholder: lienholder.clone(),
+        };
+        contract
+            .pending
+            .txs
+            .save(&mut deps.storage, 1, &tx)
+            .unwrap();
+
+        // Stale totals are recomputed
+        contract.account_count.save(&mut deps.storage, &7).unwrap();
+
+        let ctx = MigrateCtx {
+            deps: deps.as_mut(),
+            env: mock_env(),
+        };
+        contract.migrate(ctx).unwrap();
+
+        assert_eq!(
+            contract
+                .users
+                .load(&deps.storage, (&user, "uosmo"))
+                .unwrap(),
+            info
+        );
+        assert!(Map::<&Addr, UserInfo>::new("users")
+            .may_load(&deps.storage, &user)
+            .unwrap()
+            .is_none());
+        assert_eq!(
+            contract
+                .collateral_totals
+                .load(&deps.storage, "uosmo")
+                .unwrap(),
+            Uint128::new(100)
+        );
+        assert_eq!(
+            contract
+                .slashable_totals
+                .load(&deps.storage, "uosmo")
+                .unwrap(),
+            ValueRange::new_val(Uint128::new(5))
+        );
+        assert_eq!(
+            contract
+                .lienholder_totals
+                .load(&deps.storage, (&lienholder, "uosmo"))
+                .unwrap(),
+            ValueRange::new_val(Uint128::new(50))
+        );
+        assert_eq!(contract.account_count.load(&deps.storage).unwrap(), 1);
+        assert!(contract.collateral_denoms.has(&deps.storage, "uosmo"));
+
+        let lien = contract
+            .liens
+            .load(&deps.storage, (&user, &lienholder))
+            .unwrap();
+        assert_eq!(lien.denom, "uosmo");
+        let indexed = contract
+            .liens
+            .idx
+            .lienholder
+            .prefix(lienholder.clone())
+            .keys(&deps.storage, None, None, Order::Ascending)
+            .count();
+        assert_eq!(indexed, 1);
+
+        assert_eq!(contract.pending.count.load(&deps.storage).unwrap(), 1);
+        // Pending txs are left alone
+        assert_eq!(
+            contract.pending.txs_by_user(&deps.storage, &user).unwrap(),
+            vec![tx]
+        );
+    }
 }
@@ -0,0 +1,35 @@
+//! Minimal, wire-compatible subset of the cw20 spec used by the vault to accept CW20 tokens
+//! as collateral.
+//!
+//! CW20 collateral is accounted as any other collateral denom, using the `cw20:<contract>`
+//! denom for the token contract. Tokens are bonded through the vault `receive` hook.
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{to_json_binary, Addr, StdResult, Uint128, WasmMsg};
+
+pub const CW20_DENOM_PREFIX: &str = "cw20:";
+
+/// Returns the collateral denom used for the given token contract
+pub fn cw20_denom(token: &Addr) -> String {
+    format!("{}{}", CW20_DENOM_PREFIX, token)
+}
+
+/// Returns the token contract if `denom` is a CW20 collateral denom
+pub fn cw20_token(denom: &str) -> Option<Addr> {
+    denom.strip_prefix(CW20_DENOM_PREFIX).map(Addr::unchecked)
+}
+
+#[cw_serde]
+pub enum Cw20ExecuteMsg {
+    Transfer { recipient: String, amount: Uint128 },
+    Burn { amount: Uint128 },
+}
+
+impl Cw20ExecuteMsg {
+    pub fn into_msg(self, token: &Addr) -> StdResult<WasmMsg> {
+        Ok(WasmMsg::Execute {
+            contract_addr: token.to_string(),
+            msg: to_json_binary(&self)?,
+            funds: vec![],
+        })
+    }
+}
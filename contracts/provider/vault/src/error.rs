@@ -1,8 +1,10 @@
-use cosmwasm_std::{Addr, StdError, Uint128};
+use cosmwasm_std::{Addr, Decimal, StdError, Timestamp, Uint128};
 use cw_utils::{ParseReplyError, PaymentError};
 use mesh_sync::{RangeError, Tx, ValueRange};
 use thiserror::Error;
 
+use crate::msg::PausableOperation;
+
 #[derive(Error, Debug, PartialEq)]
 pub enum ContractError {
     #[error("{0}")]
@@ -20,12 +22,78 @@ pub enum ContractError {
     #[error("Unauthorized")]
     Unauthorized {},
 
+    #[error("Contract {0} is not an allowed lienholder")]
+    LienholderNotAllowed(String),
+
+    #[error("Contract {0} is not marked as defunct")]
+    LienholderNotDefunct(String),
+
+    #[error("Liens of a defunct lienholder can only be released after {0}")]
+    DefunctGracePeriod(Timestamp),
+
+    #[error("Tx can only be expired after {0}")]
+    TxNotExpired(Timestamp),
+
+    #[error("Tx can only be cancelled after {0}")]
+    TxNotCancellable(Timestamp),
+
+    #[error("No pending ownership transfer")]
+    NoPendingOwnership {},
+
+    #[error("Ownership transfer expired")]
+    OwnershipTransferExpired {},
+
+    #[error("Tx timeout and cancel grace period must be positive")]
+    InvalidTxTimeout,
+
+    #[error("The local staking contract can't be marked as defunct")]
+    LocalStakingDefunct,
+
+    #[error("Operation {0} is paused")]
+    Paused(PausableOperation),
+
     #[error("All denoms are expected to be {0}")]
     UnexpectedDenom(String),
 
+    #[error("Denom {0} is not accepted as collateral")]
+    UnsupportedCollateralDenom(String),
+
+    #[error("Denom {0} is a CW20 token, which is not supported for this operation")]
+    Cw20Denom(String),
+
+    #[error("The primary collateral denom {0} cannot be changed")]
+    PrimaryDenom(String),
+
+    #[error("{0} is not a liquid staking token collateral")]
+    NotLstCollateral(String),
+
+    #[error("Exchange rate for {0} must be positive")]
+    ZeroExchangeRate(String),
+
     #[error("Claim is locked, only {0} can be unbonded")]
     ClaimsLocked(ValueRange<Uint128>),
 
+    #[error("Lienholder cap reached, only {0} can be staked")]
+    LienholderCapReached(Uint128),
+
+    #[error("Stake would exceed the max leverage of {0}")]
+    LeverageExceeded(Decimal),
+
+    #[error("Max leverage must be at least 1.0")]
+    InvalidMaxLeverage,
+
+    #[error("No swap adapter configured")]
+    NoSwapAdapter,
+
+    #[error("Denom {0} is the primary collateral denom, bond it directly")]
+    SwapPrimaryDenom(String),
+
+    #[error("Swap output {output} is below the minimum of {min_output}")]
+    SlippageExceeded {
+        min_output: Uint128,
+        output: Uint128,
+    },
+
     #[error("The address doesn't have sufficient balance for this operation")]
     InsufficentBalance,
 
@@ -47,6 +115,63 @@ pub enum ContractError {
     #[error("No local staking configured")]
     NoLocalStaking,
 
+    #[error("Unbond request amount must be positive")]
+    ZeroUnbondRequest,
+
     #[error("No claim found")]
     NoClaim,
+
+    #[error("No approval to stake on behalf of this user")]
+    NoApproval,
+
+    #[error("Approval expired")]
+    ApprovalExpired,
+
+    #[error("Approval exceeded, up to {0} can be staked")]
+    ApprovalExceeded(Uint128),
+
+    #[error("Cannot approve yourself as an operator")]
+    SelfApproval,
+
+    #[error("Dormancy flagging is not enabled")]
+    DormancyDisabled,
+
+    #[error("Dormancy period must be over zero")]
+    InvalidDormancyPeriod,
+
+    #[error("Account has no collateral")]
+    NoCollateral,
+
+    #[error("Account is active, it can only be flagged as dormant from {0}")]
+    AccountActive(Timestamp),
+
+    #[error("Account is already flagged as dormant")]
+    AlreadyDormant,
+
+    #[error("Account tx history has nothing to compact")]
+    NothingToCompact,
+
+    #[error("Fee collection is not enabled")]
+    FeesDisabled,
+
+    #[error("Fee recipients must be set, with shares over zero adding up to one")]
+    InvalidFeeRecipients,
+
+    #[error("Fee disbursement interval must be over zero")]
+    InvalidFeeInterval,
+
+    #[error("Fee source must not be empty")]
+    InvalidFeeSource,
+
+    #[error("No collected fees to be disbursed")]
+    NoFees,
+
+    #[error("Fees cannot be disbursed before {0}")]
+    FeeDisbursementNotDue(Timestamp),
+
+    #[error("Memo too long, up to {0} bytes allowed")]
+    MemoTooLong(usize),
+
+    #[error("Too many queries, up to {0} allowed")]
+    TooManyQueries(usize),
 }
@@ -1,8 +1,11 @@
 pub mod contract;
+pub mod cw20;
 pub mod error;
+pub mod lst;
 pub mod mock;
 pub mod msg;
 #[cfg(test)]
 pub mod multitest;
 mod state;
+pub mod swap;
 pub mod txs;
@@ -0,0 +1,38 @@
+//! Liquid staking token (LST) collateral support.
+//!
+//! LST collateral is accounted by value rather than by token amount. The value of `amount`
+//! tokens is `amount * exchange_rate`, where the exchange rate is fetched from a rate provider
+//! contract configured by governance. Liens and free collateral of LST denoms are expressed in
+//! value. Lienholders still stake, release and slash tokens, which are valued at the current rate
+//! every time, and value taken from their liens is burned in tokens at that rate as well.
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::{Addr, Decimal, QuerierWrapper, StdResult};
+
+/// Query interface rate provider contracts have to implement
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum RateProviderQueryMsg {
+    /// Value of a single `denom` token, in units of the underlying token
+    #[returns(ExchangeRateResponse)]
+    ExchangeRate { denom: String },
+}
+
+#[cw_serde]
+pub struct ExchangeRateResponse {
+    pub rate: Decimal,
+}
+
+/// Fetches the current exchange rate of `denom` from `provider`
+pub fn query_exchange_rate(
+    querier: &QuerierWrapper,
+    provider: &Addr,
+    denom: &str,
+) -> StdResult<Decimal> {
+    let ExchangeRateResponse { rate } = querier.query_wasm_smart(
+        provider,
+        &RateProviderQueryMsg::ExchangeRate {
+            denom: denom.to_owned(),
+        },
+    )?;
+    Ok(rate)
+}
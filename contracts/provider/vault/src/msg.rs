@@ -1,7 +1,10 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Binary, Uint128};
+use cosmwasm_std::{Binary, Coin, Decimal, Timestamp, Uint128};
+use cw_utils::Expiration;
 use mesh_sync::{Tx, ValueRange};
 
+use crate::state::{FeeDisbursement, SlashDustPolicy, TxHistorySummary, TxOutcome};
+
 /// This is the info used to construct the native staking contract
 #[cw_serde]
 pub struct StakingInitInfo {
@@ -13,6 +16,11 @@ pub struct StakingInitInfo {
     pub msg: Binary,
     /// A human-readable label for the local staking contract (will use a default if not provided)
     pub label: Option<String>,
+    /// Salt to instantiate the local staking contract with `instantiate2`, so that its address
+    /// can be derived from the vault address, the code checksum and the salt beforehand.
+    /// If empty, a regular instantiation is used
+    #[serde(default)]
+    pub salt: Option<Binary>,
 }
 
 /// Information about the local staking contract used during vault instantiation
@@ -32,6 +40,13 @@ pub struct ExistingContract {
     pub existing: String,
 }
 
+/// Message embedded in a CW20 `Send` to the vault
+#[cw_serde]
+pub enum Cw20HookMsg {
+    /// Bonds the sent tokens as collateral of the sender
+    Bond {},
+}
+
 #[cw_serde]
 pub struct AccountResponse {
     // Everything is denom, changing all Uint128 to coin with the same denom seems very inefficient
@@ -48,6 +63,19 @@ pub struct AccountDetailsResponse {
     pub free: ValueRange<Uint128>,
     pub max_lien: ValueRange<Uint128>,
     pub total_slashable: ValueRange<Uint128>,
+    /// Breakdown of `max_lien` and `total_slashable` per lienholder
+    pub liens: Vec<AccountLienDetails>,
+}
+
+#[cw_serde]
+pub struct AccountLienDetails {
+    pub lienholder: String,
+    /// Lien amount. Includes pending stakes at their highest value
+    pub amount: ValueRange<Uint128>,
+    /// Max slash ratio of the lienholder
+    pub slashable: Decimal,
+    /// Part of the lien that can be slashed
+    pub slashable_amount: ValueRange<Uint128>,
 }
 
 impl AccountResponse {
@@ -71,6 +99,13 @@ pub struct AllAccountsResponseItem {
     pub account: AccountResponse,
 }
 
+#[cw_serde]
+pub struct AccountAtHeightResponse {
+    pub height: u64,
+    /// Bonded collateral at the beginning of block `height`, per denom
+    pub collateral: Vec<Coin>,
+}
+
 #[cw_serde]
 pub struct AccountClaimsResponse {
     pub claims: Vec<LienResponse>,
@@ -79,6 +114,7 @@ pub struct AccountClaimsResponse {
 #[cw_serde]
 pub struct LienResponse {
     pub lienholder: String,
+    pub denom: String,
     pub amount: ValueRange<Uint128>,
 }
 
@@ -88,15 +124,299 @@ pub struct ConfigResponse {
     pub local_staking: Option<String>,
 }
 
+#[cw_serde]
+pub struct AccountCollateralResponse {
+    /// Collateral details for every denom the account ever bonded
+    pub collateral: Vec<AccountDetailsResponse>,
+}
+
+#[cw_serde]
+pub struct CollateralDenomsResponse {
+    pub denoms: Vec<String>,
+}
+
+#[cw_serde]
+pub struct MetricsResponse {
+    /// Number of accounts that ever bonded collateral
+    pub accounts: u64,
+    /// Total bonded collateral, per denom
+    pub total_bonded: Vec<Coin>,
+    /// Total liens, per denom. Includes the liens of pending txs
+    pub total_liens: Vec<Coin>,
+    /// Number of pending txs
+    pub pending_txs: u64,
+    /// Age of the oldest pending tx in seconds, if any
+    pub oldest_pending_tx_age: Option<u64>,
+}
+
+#[cw_serde]
+pub struct TotalStatsResponse {
+    /// Number of accounts that ever bonded collateral
+    pub accounts: u64,
+    /// Total bonded collateral, per denom
+    pub total_bonded: Vec<Coin>,
+    /// Total liens, per lienholder and denom. Includes the liens of pending txs
+    pub lienholder_totals: Vec<LienholderTotal>,
+    /// Total max-slashable exposure (in collateral value), per denom. Includes pending txs
+    pub total_slashable: Vec<Coin>,
+}
+
+#[cw_serde]
+pub struct LienholderTotal {
+    pub lienholder: String,
+    pub denom: String,
+    pub total: ValueRange<Uint128>,
+}
+
+#[cw_serde]
+pub struct LienholderCapResponse {
+    /// Maximum total lien of the lienholder in this denom, if any
+    pub cap: Option<Uint128>,
+    /// Total lien of the lienholder in this denom. Includes the liens of pending txs
+    pub total: ValueRange<Uint128>,
+    /// Amount that can still be staked with the lienholder, if capped
+    pub headroom: Option<Uint128>,
+}
+
+#[cw_serde]
+pub struct LeverageResponse {
+    pub denom: String,
+    /// Collateral value of the account in `denom`
+    pub collateral: Uint128,
+    /// Sum of the account liens in `denom`. Includes the liens of pending txs
+    pub total_liens: Uint128,
+    /// Ratio of `total_liens` to `collateral`. `None` if there is no collateral
+    pub leverage: Option<Decimal>,
+    /// Maximum leverage allowed, if capped
+    pub max_leverage: Option<Decimal>,
+}
+
+#[cw_serde]
+pub struct SwapAdapterResponse {
+    /// Swap adapter used by `swap_and_bond`, if configured
+    pub adapter: Option<String>,
+}
+
+/// Vault operations that can be paused independently
+#[cw_serde]
+#[derive(Copy)]
+pub enum PausableOperation {
+    Bond,
+    Unbond,
+    StakeRemote,
+    StakeLocal,
+}
+
+impl std::fmt::Display for PausableOperation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Bond => "bond",
+            Self::Unbond => "unbond",
+            Self::StakeRemote => "stake_remote",
+            Self::StakeLocal => "stake_local",
+        };
+        f.write_str(name)
+    }
+}
+
+#[cw_serde]
+pub struct PauseStatusResponse {
+    /// Address allowed to pause and unpause operations, besides governance
+    pub pause_authority: Option<String>,
+    /// Currently paused operations
+    pub paused: Vec<PausableOperation>,
+}
+
+#[cw_serde]
+pub struct UnbondRequestResponse {
+    pub id: u64,
+    pub denom: String,
+    /// Amount still waiting to be unbonded
+    pub amount: Uint128,
+}
+
+#[cw_serde]
+pub struct PendingUnbondsResponse {
+    /// Requests not (fully) fulfilled yet, oldest first
+    pub requests: Vec<UnbondRequestResponse>,
+    /// Unbonded collateral that can be claimed with `claim_unbonded`, per denom
+    pub claimable: Vec<Coin>,
+}
+
+#[cw_serde]
+pub struct AllowedLienholdersResponse {
+    pub lienholders: Vec<String>,
+}
+
+#[cw_serde]
+pub struct DefunctLienholderResponse {
+    pub lienholder: String,
+    pub marked_at: Timestamp,
+    /// Block time from which its liens can be force-released
+    pub release_after: Timestamp,
+}
+
+#[cw_serde]
+pub struct DefunctLienholdersResponse {
+    pub lienholders: Vec<DefunctLienholderResponse>,
+}
+
 #[cw_serde]
 pub struct AllActiveExternalStakingResponse {
     pub contracts: Vec<String>,
 }
 
 pub type TxResponse = Tx;
+
+#[cw_serde]
+pub struct ApprovalResponse {
+    /// Amount the operator can still stake
+    pub spend_limit: Coin,
+    pub expires: Expiration,
+    /// Whether the approval already expired
+    pub expired: bool,
+}
+
+#[cw_serde]
+pub struct TxMemoResponse {
+    pub memo: Option<String>,
+}
+
+#[cw_serde]
+pub struct OwnershipResponse {
+    /// Current vault owner. This is the contract admin until ownership is first transferred
+    pub owner: Option<String>,
+    /// Proposed owner, if an ownership transfer is pending
+    pub pending_owner: Option<String>,
+    /// Deadline to accept the pending ownership transfer, if any
+    pub pending_expiry: Option<Expiration>,
+}
+
+#[cw_serde]
+pub struct DenomSlashDust {
+    pub denom: String,
+    /// Dust slashed from users
+    pub burned: Uint128,
+    /// Dust taken from users and sent to the donation recipient
+    pub donated: Uint128,
+    /// Dust left to users
+    pub ignored: Uint128,
+}
+
+#[cw_serde]
+pub struct DormantAccount {
+    pub account: String,
+    /// Last activity of the account, if any since activity is tracked
+    pub last_activity: Option<Timestamp>,
+    /// Time the account was flagged as dormant at
+    pub flagged_at: Timestamp,
+}
+
+#[cw_serde]
+pub struct DormantAccountsResponse {
+    pub accounts: Vec<DormantAccount>,
+}
+
+#[cw_serde]
+pub struct FeeBucketInfo {
+    /// Source the fees were collected from
+    pub source: String,
+    /// Fees collected and not disbursed yet
+    pub pending: Uint128,
+    /// Fees disbursed so far
+    pub disbursed: Uint128,
+}
+
+#[cw_serde]
+pub struct FeeAccountingResponse {
+    /// Scheduled disbursement of the fees. Fees are not collected if not set
+    pub disbursement: Option<FeeDisbursement>,
+    /// Fees collected so far, by source
+    pub buckets: Vec<FeeBucketInfo>,
+    /// Time of the last disbursement, if any
+    pub last_disbursement: Option<Timestamp>,
+}
+
+#[cw_serde]
+pub struct SlashDustResponse {
+    pub policy: SlashDustPolicy,
+    /// Slash rounding dust of every collateral denom since it is tracked
+    pub dust: Vec<DenomSlashDust>,
+}
+
+#[cw_serde]
+pub struct TxTimeoutResponse {
+    /// Time (in seconds) after which a pending tx can be expired
+    pub timeout: u64,
+    /// Time (in seconds) after which a pending tx can be cancelled by its staker
+    pub cancel_grace_period: u64,
+}
 pub type AllTxsResponseItem = TxResponse;
 
 #[cw_serde]
 pub struct AllTxsResponse {
     pub txs: Vec<AllTxsResponseItem>,
 }
+
+/// A query that can be batched in `multi_query`
+#[cw_serde]
+pub enum SubQuery {
+    Account {
+        account: String,
+    },
+    AccountDetails {
+        account: String,
+    },
+    AccountClaims {
+        account: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    PendingTx {
+        tx_id: u64,
+    },
+    AllPendingTxsDesc {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+}
+
+/// Response to a `SubQuery`, of the matching variant
+#[cw_serde]
+pub enum SubQueryResponse {
+    Account(AccountResponse),
+    AccountDetails(AccountDetailsResponse),
+    AccountClaims(AccountClaimsResponse),
+    PendingTx(TxResponse),
+    AllPendingTxsDesc(AllTxsResponse),
+}
+
+#[cw_serde]
+pub struct MultiQueryResponse {
+    /// Responses, in the order of the queries
+    pub responses: Vec<SubQueryResponse>,
+}
+
+#[cw_serde]
+pub struct TxHistoryItem {
+    pub tx_id: u64,
+    pub lienholder: String,
+    pub denom: String,
+    pub amount: Uint128,
+    pub outcome: TxOutcome,
+    pub completed_height: u64,
+}
+
+#[cw_serde]
+pub struct DenomTxHistorySummary {
+    pub denom: String,
+    pub summary: TxHistorySummary,
+}
+
+#[cw_serde]
+pub struct TxHistoryResponse {
+    /// Retained completed txs, newest first
+    pub txs: Vec<TxHistoryItem>,
+    /// Aggregates of the compacted txs, per denom
+    pub summaries: Vec<DenomTxHistorySummary>,
+}
@@ -1,5 +1,6 @@
-use cosmwasm_std::{coin, coins, to_json_binary, Addr, Decimal, Uint128, Validator};
+use cosmwasm_std::{coin, coins, to_json_binary, Addr, Binary, Decimal, Event, Uint128, Validator};
 use cw_multi_test::{App as MtApp, StakingInfo};
+use cw_utils::Expiration;
 use mesh_apis::ibc::AddValidator;
 use mesh_external_staking::contract::sv::mt::ExternalStakingContractProxy;
 use mesh_external_staking::contract::ExternalStakingContract;
@@ -14,17 +15,29 @@ use mesh_sync::Tx::InFlightStaking;
 use mesh_sync::{Tx, ValueRange};
 use sylvia::multitest::{App, Proxy};
 
+use mesh_apis::cross_staking_api::sv::mt::CrossStakingApiProxy;
 use mesh_apis::vault_api::sv::mt::VaultApiProxy;
 use mesh_external_staking::test_methods::sv::mt::TestMethodsProxy;
 
+use crate::contract::{MAX_MEMO_LEN, MAX_MULTI_QUERIES, TX_HISTORY_RETENTION};
+use crate::cw20::{cw20_denom, Cw20ExecuteMsg};
 use crate::error::ContractError;
+use crate::lst::{ExchangeRateResponse, RateProviderQueryMsg};
 use crate::mock::sv::mt::CodeId as VaultCodeId;
 use crate::mock::sv::mt::VaultMockProxy;
 use crate::mock::VaultMock;
 use crate::msg::{
-    AccountResponse, AllAccountsResponseItem, AllActiveExternalStakingResponse, LienResponse,
-    LocalStakingInfo, StakingInitInfo,
+    AccountDetailsResponse, AccountLienDetails, AccountResponse, AllAccountsResponseItem,
+    AllActiveExternalStakingResponse, AllowedLienholdersResponse, Cw20HookMsg,
+    DefunctLienholderResponse, DenomSlashDust, DenomTxHistorySummary, DormantAccount,
+    DormantAccountsResponse, FeeAccountingResponse, FeeBucketInfo, LeverageResponse, LienResponse,
+    LienholderCapResponse, LienholderLienResponse, LienholderTotal, LocalStakingInfo,
+    OwnershipResponse, PausableOperation, PauseStatusResponse, PendingUnbondsResponse,
+    SlashDustResponse, StakingInitInfo, SubQuery, SubQueryResponse, TxHistoryItem,
+    UnbondRequestResponse,
 };
+use crate::state::{FeeDisbursement, FeeRecipient, SlashDustPolicy, TxHistorySummary, TxOutcome};
+use crate::swap::SwapAdapterExecuteMsg;
 
 const OSMO: &str = "OSMO";
 const STAR: &str = "star";
@@ -133,6 +146,7 @@ fn setup_inner<'app>(
             slash_ratio_dsign: Decimal::percent(10),
             slash_ratio_offline: Decimal::percent(10),
             proxy_code_id: native_staking_proxy_code.code_id(),
+            deterministic_proxies: None,
         };
 
         Some(LocalStakingInfo::New(StakingInitInfo {
@@ -140,6 +154,7 @@ fn setup_inner<'app>(
             code_id: native_staking_code.code_id(),
             msg: to_json_binary(&native_staking_inst_msg).unwrap(),
             label: None,
+            salt: None,
         }))
     } else {
         None
@@ -148,6 +163,7 @@ fn setup_inner<'app>(
     let vault = vault_code
         .instantiate(OSMO.to_owned(), staking_init_info)
         .with_label("Vault")
+        .with_admin(owner)
         .call(owner)
         .unwrap();
     let native_staking_addr = vault.config().unwrap().local_staking.map(Addr::unchecked);
@@ -169,7 +185,7 @@ fn setup_cross_stake<'app>(
     // FIXME: Connection endpoint should be unique
     let remote_contact = AuthorizedEndpoint::new("connection-2", "wasm-osmo1foobarbaz");
 
-    cross_staking_code
+    let cross_staking = cross_staking_code
         .instantiate(
             OSMO.to_owned(),
             STAR.to_owned(),
@@ -180,9 +196,17 @@ fn setup_cross_stake<'app>(
                 double_sign: Decimal::percent(slash_percent),
                 offline: Decimal::percent(slash_percent),
             },
+            None,
+            None,
+            None,
         )
         .call(owner)
-        .unwrap()
+        .unwrap();
+    vault
+        .add_allowed_lienholder(cross_staking.contract_addr.to_string())
+        .call(owner)
+        .unwrap();
+    cross_staking
 }
 
 /// Set some active validators
@@ -206,7 +230,7 @@ fn set_active_validators(
 /// Bond some tokens
 fn bond(vault: &Proxy<'_, MtApp, VaultMock<'_>>, user: &str, amount: u128) {
     vault
-        .bond()
+        .bond(None)
         .with_funds(&coins(amount, OSMO))
         .call(user)
         .unwrap();
@@ -223,7 +247,7 @@ fn stake_locally(
     };
 
     vault
-        .stake_local(coin(stake, OSMO), to_json_binary(&msg).unwrap())
+        .stake_local(coin(stake, OSMO), to_json_binary(&msg).unwrap(), None)
         .call(user)
 }
 
@@ -243,6 +267,7 @@ fn stake_remotely(
                     validator: validator.to_string(),
                 })
                 .unwrap(),
+                None,
             )
             .call(user)
             .unwrap();
@@ -316,7 +341,7 @@ fn instantiation() {
     let config = vault.config().unwrap();
     assert_eq!(config.denom, OSMO);
 
-    let users = vault.all_accounts(false, None, None).unwrap();
+    let users = vault.all_accounts(false, None, None, None).unwrap();
     assert_eq!(users.accounts, []);
 }
 
@@ -337,8 +362,6 @@ fn bonding() {
             free: ValueRange::new_val(Uint128::zero()),
         }
     );
-    let claims = vault.account_claims(user.to_owned(), None, None).unwrap();
-    assert_eq!(claims.claims, []);
 
     bond(&vault, user, 100);
 
@@ -350,8 +373,6 @@ fn bonding() {
             free: ValueRange::new_val(Uint128::new(100)),
         }
     );
-    let claims = vault.account_claims(user.to_owned(), None, None).unwrap();
-    assert_eq!(claims.claims, []);
     assert_eq!(
         app.app().wrap().query_balance(user, OSMO).unwrap(),
         coin(200, OSMO)
@@ -374,8 +395,6 @@ fn bonding() {
             free: ValueRange::new_val(Uint128::new(250)),
         }
     );
-    let claims = vault.account_claims(user.to_owned(), None, None).unwrap();
-    assert_eq!(claims.claims, []);
     assert_eq!(
         app.app().wrap().query_balance(user, OSMO).unwrap(),
         coin(50, OSMO)
@@ -399,8 +418,6 @@ fn bonding() {
             free: ValueRange::new_val(Uint128::new(50)),
         }
     );
-    let claims = vault.account_claims(user.to_owned(), None, None).unwrap();
-    assert_eq!(claims.claims, []);
     assert_eq!(
         app.app().wrap().query_balance(user, OSMO).unwrap(),
         coin(250, OSMO)
@@ -422,8 +439,6 @@ fn bonding() {
             free: ValueRange::new_val(Uint128::new(30)),
         }
     );
-    let claims = vault.account_claims(user.to_owned(), None, None).unwrap();
-    assert_eq!(claims.claims, []);
     assert_eq!(
         app.app().wrap().query_balance(user, OSMO).unwrap(),
         coin(270, OSMO)
@@ -476,6 +491,7 @@ fn local_staking_disabled() {
                 validator: remote_val.to_string(),
             })
             .unwrap(),
+            None,
         )
         .call(user)
         .unwrap();
@@ -512,8 +528,6 @@ fn stake_local() {
             free: ValueRange::new_val(Uint128::new(300)),
         }
     );
-    let claims = vault.account_claims(user.to_owned(), None, None).unwrap();
-    assert_eq!(claims.claims, []);
     assert_eq!(
         app.app()
             .wrap()
@@ -545,6 +559,7 @@ fn stake_local() {
         claims.claims,
         [LienResponse {
             lienholder: local_staking.contract_addr.to_string(),
+            denom: OSMO.to_owned(),
             amount: ValueRange::new_val(Uint128::new(100))
         }]
     );
@@ -571,6 +586,7 @@ fn stake_local() {
         claims.claims,
         [LienResponse {
             lienholder: local_staking.contract_addr.to_string(),
+            denom: OSMO.to_owned(),
             amount: ValueRange::new_val(Uint128::new(250))
         }]
     );
@@ -619,6 +635,7 @@ fn stake_local() {
         claims.claims,
         [LienResponse {
             lienholder: local_staking.contract_addr.to_string(),
+            denom: OSMO.to_owned(),
             amount: ValueRange::new_val(Uint128::new(200))
         }]
     );
@@ -650,6 +667,7 @@ fn stake_local() {
         claims.claims,
         [LienResponse {
             lienholder: local_staking.contract_addr.to_string(),
+            denom: OSMO.to_owned(),
             amount: ValueRange::new_val(Uint128::new(100))
         }]
     );
@@ -700,8 +718,6 @@ fn stake_cross() {
             free: ValueRange::new_val(Uint128::new(300)),
         }
     );
-    let claims = vault.account_claims(user.to_owned(), None, None).unwrap();
-    assert_eq!(claims.claims, []);
     assert_eq!(
         app.app()
             .wrap()
@@ -729,6 +745,7 @@ fn stake_cross() {
                 validator: validator.to_string(),
             })
             .unwrap(),
+            None,
         )
         .call(user)
         .unwrap();
@@ -774,6 +791,7 @@ fn stake_cross() {
         claims.claims,
         [LienResponse {
             lienholder: cross_staking.contract_addr.to_string(),
+            denom: OSMO.to_owned(),
             amount: ValueRange::new_val(Uint128::new(100))
         }]
     );
@@ -801,6 +819,7 @@ fn stake_cross() {
                 validator: validator.to_string(),
             })
             .unwrap(),
+            None,
         )
         .call(user)
         .unwrap();
@@ -838,6 +857,7 @@ fn stake_cross() {
         claims.claims,
         [LienResponse {
             lienholder: cross_staking.contract_addr.to_string(),
+            denom: OSMO.to_owned(),
             amount: ValueRange::new_val(Uint128::new(250))
         }]
     );
@@ -866,6 +886,7 @@ fn stake_cross() {
                 validator: validator.to_string(),
             })
             .unwrap(),
+            None,
         )
         .call(user)
         .unwrap_err();
@@ -910,6 +931,7 @@ fn stake_cross() {
         claims.claims,
         [LienResponse {
             lienholder: cross_staking.contract_addr.to_string(),
+            denom: OSMO.to_owned(),
             amount: ValueRange::new_val(Uint128::new(250))
         }]
     );
@@ -973,6 +995,7 @@ fn stake_cross() {
         claims.claims,
         [LienResponse {
             lienholder: cross_staking.contract_addr.to_string(),
+            denom: OSMO.to_owned(),
             amount: ValueRange::new_val(Uint128::new(200))
         }]
     );
@@ -1022,6 +1045,7 @@ fn stake_cross() {
         claims.claims,
         [LienResponse {
             lienholder: cross_staking.contract_addr.to_string(),
+            denom: OSMO.to_owned(),
             amount: ValueRange::new_val(Uint128::new(100))
         }]
     );
@@ -1077,8 +1101,6 @@ fn stake_cross_txs() {
             free: ValueRange::new_val(Uint128::new(300)),
         }
     );
-    let claims = vault.account_claims(user.to_owned(), None, None).unwrap();
-    assert_eq!(claims.claims, []);
 
     bond(&vault, user2, 500);
     assert_eq!(
@@ -1107,7 +1129,7 @@ fn stake_cross_txs() {
     // No pending txs
     assert_eq!(vault.all_pending_txs_desc(None, None).unwrap().txs, vec![]);
     // Can query all accounts
-    let accounts = vault.all_accounts(false, None, None).unwrap();
+    let accounts = vault.all_accounts(false, None, None, None).unwrap();
     assert_eq!(accounts.accounts.len(), 2);
 
     // Staking remotely
@@ -1120,6 +1142,7 @@ fn stake_cross_txs() {
                 validator: validator.to_string(),
             })
             .unwrap(),
+            None,
         )
         .call(user)
         .unwrap();
@@ -1138,6 +1161,7 @@ fn stake_cross_txs() {
                 validator: validator.to_string(),
             })
             .unwrap(),
+            None,
         )
         .call(user)
         .unwrap();
@@ -1153,6 +1177,7 @@ fn stake_cross_txs() {
                 validator: validator.to_string(),
             })
             .unwrap(),
+            None,
         )
         .call(user2)
         .unwrap();
@@ -1200,6 +1225,7 @@ fn stake_cross_txs() {
             .claims,
         [LienResponse {
             lienholder: "contract2".to_string(),
+            denom: OSMO.to_owned(),
             amount: ValueRange::new(Uint128::zero(), Uint128::new(150))
         }]
     );
@@ -1212,7 +1238,7 @@ fn stake_cross_txs() {
         coin(800, OSMO)
     );
     // Can query all accounts, and value ranges are reported
-    let accounts = vault.all_accounts(false, None, None).unwrap();
+    let accounts = vault.all_accounts(false, None, None, None).unwrap();
     assert_eq!(
         accounts.accounts,
         vec![
@@ -1251,6 +1277,7 @@ fn stake_cross_txs() {
         claims.claims,
         [LienResponse {
             lienholder: cross_staking.contract_addr.to_string(),
+            denom: OSMO.to_owned(),
             amount: ValueRange::new_val(Uint128::new(100))
         }]
     );
@@ -1278,6 +1305,7 @@ fn stake_cross_txs() {
         claims.claims,
         [LienResponse {
             lienholder: cross_staking.contract_addr.to_string(),
+            denom: OSMO.to_owned(),
             amount: ValueRange::new(Uint128::new(100), Uint128::new(150))
         }]
     );
@@ -1327,6 +1355,7 @@ fn stake_cross_rollback_tx() {
                 validator: validator.to_string(),
             })
             .unwrap(),
+            None,
         )
         .call(user)
         .unwrap();
@@ -1359,8 +1388,6 @@ fn stake_cross_rollback_tx() {
         }
     );
     // No non-empty claims
-    let claims = vault.account_claims(user.to_owned(), None, None).unwrap();
-    assert_eq!(claims.claims, []);
     // Vault has the funds
     assert_eq!(
         app.app()
@@ -1419,14 +1446,17 @@ fn multiple_stakes() {
         [
             LienResponse {
                 lienholder: local_staking.contract_addr.to_string(),
+                denom: OSMO.to_owned(),
                 amount: ValueRange::new_val(Uint128::new(300))
             },
             LienResponse {
                 lienholder: cross_staking1.contract_addr.to_string(),
+                denom: OSMO.to_owned(),
                 amount: ValueRange::new_val(Uint128::new(200))
             },
             LienResponse {
                 lienholder: cross_staking2.contract_addr.to_string(),
+                denom: OSMO.to_owned(),
                 amount: ValueRange::new_val(Uint128::new(100))
             },
         ]
@@ -1456,14 +1486,17 @@ fn multiple_stakes() {
         [
             LienResponse {
                 lienholder: local_staking.contract_addr.to_string(),
+                denom: OSMO.to_owned(),
                 amount: ValueRange::new_val(Uint128::new(300))
             },
             LienResponse {
                 lienholder: cross_staking1.contract_addr.to_string(),
+                denom: OSMO.to_owned(),
                 amount: ValueRange::new_val(Uint128::new(400))
             },
             LienResponse {
                 lienholder: cross_staking2.contract_addr.to_string(),
+                denom: OSMO.to_owned(),
                 amount: ValueRange::new_val(Uint128::new(500))
             },
         ]
@@ -1483,6 +1516,7 @@ fn multiple_stakes() {
                 validator: validator.to_string(),
             })
             .unwrap(),
+            None,
         )
         .call(user)
         .unwrap_err();
@@ -1502,16 +1536,16 @@ fn all_users_fetching() {
 
     // No users should show up no matter of collateral flag
 
-    let accounts = vault.all_accounts(false, None, None).unwrap();
+    let accounts = vault.all_accounts(false, None, None, None).unwrap();
     assert_eq!(accounts.accounts, []);
 
-    let accounts = vault.all_accounts(true, None, None).unwrap();
+    let accounts = vault.all_accounts(true, None, None, None).unwrap();
     assert_eq!(accounts.accounts, []);
 
     // When user bond some collateral, he should be visible
     bond(&vault, users[0], 100);
 
-    let accounts = vault.all_accounts(false, None, None).unwrap();
+    let accounts = vault.all_accounts(false, None, None, None).unwrap();
     assert_eq!(
         accounts.accounts,
         [AllAccountsResponseItem {
@@ -1524,7 +1558,7 @@ fn all_users_fetching() {
         }]
     );
 
-    let accounts = vault.all_accounts(true, None, None).unwrap();
+    let accounts = vault.all_accounts(true, None, None, None).unwrap();
     assert_eq!(
         accounts.accounts,
         [AllAccountsResponseItem {
@@ -1540,7 +1574,7 @@ fn all_users_fetching() {
     // Second user bonds - we want to see him
     bond(&vault, users[1], 200);
 
-    let accounts = vault.all_accounts(false, None, None).unwrap();
+    let accounts = vault.all_accounts(false, None, None, None).unwrap();
     assert_eq!(
         accounts.accounts,
         [
@@ -1563,7 +1597,7 @@ fn all_users_fetching() {
         ]
     );
 
-    let accounts = vault.all_accounts(true, None, None).unwrap();
+    let accounts = vault.all_accounts(true, None, None, None).unwrap();
     assert_eq!(
         accounts.accounts,
         [
@@ -1590,7 +1624,7 @@ fn all_users_fetching() {
 
     vault.unbond(coin(50, OSMO)).call(users[0]).unwrap();
 
-    let accounts = vault.all_accounts(false, None, None).unwrap();
+    let accounts = vault.all_accounts(false, None, None, None).unwrap();
     assert_eq!(
         accounts.accounts,
         [
@@ -1613,7 +1647,7 @@ fn all_users_fetching() {
         ]
     );
 
-    let accounts = vault.all_accounts(true, None, None).unwrap();
+    let accounts = vault.all_accounts(true, None, None, None).unwrap();
     assert_eq!(
         accounts.accounts,
         [
@@ -1639,7 +1673,7 @@ fn all_users_fetching() {
     // Unbonding all the collateral hides the user when the collateral flag is set
     vault.unbond(coin(200, OSMO)).call(users[1]).unwrap();
 
-    let accounts = vault.all_accounts(false, None, None).unwrap();
+    let accounts = vault.all_accounts(false, None, None, None).unwrap();
     assert_eq!(
         accounts.accounts,
         [
@@ -1662,7 +1696,7 @@ fn all_users_fetching() {
         ]
     );
 
-    let accounts = vault.all_accounts(true, None, None).unwrap();
+    let accounts = vault.all_accounts(true, None, None, None).unwrap();
     assert_eq!(
         accounts.accounts,
         [AllAccountsResponseItem {
@@ -1679,391 +1713,2783 @@ fn all_users_fetching() {
 /// Scenario 1:
 /// https://github.com/osmosis-labs/mesh-security/blob/main/docs/ibc/Slashing.md#scenario-1-slashed-delegator-has-free-collateral-on-the-vault
 #[test]
-fn cross_slash_scenario_1() {
+fn multiple_collateral_denoms() {
     let owner = "owner";
     let user = "user1";
-    // Remote slashing percentage
-    let slashing_percentage = 10;
-    let collateral = 200;
-    let local_validator = "local";
-    let validators = vec!["validator1", "validator2"];
-    let validator1 = validators[0];
-    let validator2 = validators[1];
+    const LST: &str = "ulst";
 
-    let mut app = init_app(&[user], &[1000]);
-    add_local_validator(&mut app, local_validator);
+    let app = App::custom(|router, _api, storage| {
+        router
+            .bank
+            .init_balance(
+                storage,
+                &Addr::unchecked(user),
+                vec![coin(300, OSMO), coin(200, LST)],
+            )
+            .unwrap();
+    });
+    set_chain_native_denom(&mut app.app_mut(), OSMO);
 
-    let (vault, local_staking, cross_staking) = setup(&app, owner, slashing_percentage, 100);
+    let (vault, _, _) = setup(&app, owner, 10, 100);
 
-    let (_update_valset_height, _update_valset_time) =
-        set_active_validators(&cross_staking, &validators);
+    // Only the primary denom is accepted initially
+    assert_eq!(vault.collateral_denoms().unwrap().denoms, [OSMO]);
+    let err = vault
+        .bond(None)
+        .with_funds(&coins(200, LST))
+        .call(user)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::UnsupportedCollateralDenom(LST.to_owned())
+    );
 
-    // Bond some collateral
-    bond(&vault, user, collateral);
+    // Governance approves the new denom
+    vault.add_collateral_denom(LST.to_owned()).unwrap();
+    assert_eq!(vault.collateral_denoms().unwrap().denoms, [OSMO, LST]);
 
-    // Stake some tokens locally
-    let local_stake = 190;
-    stake_locally(&vault, user, local_stake, local_validator).unwrap();
+    bond(&vault, user, 300);
+    vault
+        .bond(None)
+        .with_funds(&coins(200, LST))
+        .call(user)
+        .unwrap();
 
-    // Stake some tokens remotely
-    stake_remotely(&vault, &cross_staking, user, &validators, &[100, 50]);
+    // Cross staking contract accepting the new denom
+    let cross_staking_code = mesh_external_staking::contract::sv::mt::CodeId::store_code(&app);
+    let cross_staking = cross_staking_code
+        .instantiate(
+            LST.to_owned(),
+            STAR.to_owned(),
+            vault.contract_addr.to_string(),
+            100,
+            AuthorizedEndpoint::new("connection-2", "wasm-osmo1foobarbaz"),
+            SlashRatio {
+                double_sign: Decimal::percent(10),
+                offline: Decimal::percent(10),
+            },
+            None,
+            None,
+            None,
+        )
+        .call(owner)
+        .unwrap();
+    vault
+        .add_allowed_lienholder(cross_staking.contract_addr.to_string())
+        .call(owner)
+        .unwrap();
+    set_active_validators(&cross_staking, &["validator1"]);
 
-    let acc = vault.account(user.to_owned()).unwrap();
+    vault
+        .stake_remote(
+            cross_staking.contract_addr.to_string(),
+            coin(150, LST),
+            to_json_binary(&ReceiveVirtualStake {
+                validator: "validator1".to_string(),
+            })
+            .unwrap(),
+            None,
+        )
+        .call(user)
+        .unwrap();
+    let last_external_staking_tx = get_last_external_staking_pending_tx_id(&cross_staking).unwrap();
+    cross_staking
+        .test_commit_stake(last_external_staking_tx)
+        .call("test")
+        .unwrap();
+
+    // Liens on the new denom don't affect the primary denom collateral
     assert_eq!(
-        acc,
-        AccountResponse {
-            denom: OSMO.to_owned(),
-            bonded: Uint128::new(collateral),
-            free: ValueRange::new_val(Uint128::new(10)),
-        }
+        vault.account(user.to_owned()).unwrap(),
+        AccountResponse::new(
+            OSMO,
+            Uint128::new(300),
+            ValueRange::new_val(Uint128::new(300))
+        )
     );
-    let claims = vault.account_claims(user.to_owned(), None, None).unwrap();
     assert_eq!(
-        claims.claims,
+        vault
+            .account_collateral(user.to_owned())
+            .unwrap()
+            .collateral,
         [
-            LienResponse {
-                lienholder: local_staking.contract_addr.to_string(),
-                amount: ValueRange::new_val(Uint128::new(local_stake))
+            AccountDetailsResponse {
+                denom: OSMO.to_owned(),
+                bonded: Uint128::new(300),
+                free: ValueRange::new_val(Uint128::new(300)),
+                max_lien: ValueRange::new_val(Uint128::zero()),
+                total_slashable: ValueRange::new_val(Uint128::zero()),
+                liens: vec![],
             },
-            LienResponse {
-                lienholder: cross_staking.contract_addr.to_string(),
-                amount: ValueRange::new_val(Uint128::new(150))
+            AccountDetailsResponse {
+                denom: LST.to_owned(),
+                bonded: Uint128::new(200),
+                free: ValueRange::new_val(Uint128::new(50)),
+                max_lien: ValueRange::new_val(Uint128::new(150)),
+                total_slashable: ValueRange::new_val(Uint128::new(15)),
+                liens: vec![AccountLienDetails {
+                    lienholder: cross_staking.contract_addr.to_string(),
+                    amount: ValueRange::new_val(Uint128::new(150)),
+                    slashable: Decimal::percent(10),
+                    slashable_amount: ValueRange::new_val(Uint128::new(15)),
+                }],
             },
         ]
     );
-
-    let acc_details = vault.account_details(user.to_owned()).unwrap();
-    // Max lien
-    assert_eq!(acc_details.max_lien, ValueRange::new_val(Uint128::new(190)));
-    // Total slashable
     assert_eq!(
-        acc_details.total_slashable,
-        ValueRange::new_val(Uint128::new(34))
+        vault
+            .account_claims(user.to_owned(), None, None)
+            .unwrap()
+            .claims,
+        [LienResponse {
+            lienholder: cross_staking.contract_addr.to_string(),
+            denom: LST.to_owned(),
+            amount: ValueRange::new_val(Uint128::new(150)),
+        }]
     );
-    // Collateral
-    assert_eq!(acc_details.bonded, Uint128::new(collateral));
-    // Free collateral
-    assert_eq!(acc_details.free, ValueRange::new_val(Uint128::new(10)));
-
-    // Cross stakes
-    let cross_stake1 = cross_staking
-        .stake(user.to_string(), validator1.to_string())
-        .unwrap();
-    assert_eq!(cross_stake1.stake, ValueRange::new_val(Uint128::new(100)));
-    let cross_stake2 = cross_staking
-        .stake(user.to_string(), validator2.to_string())
-        .unwrap();
-    assert_eq!(cross_stake2.stake, ValueRange::new_val(Uint128::new(50)));
-
-    // Validator 1 is slashed
-    cross_staking
-        .test_handle_slashing(validator1.to_string(), Uint128::new(10))
-        .call("test")
-        .unwrap();
-
-    // Liens
-    let claims = vault.account_claims(user.to_owned(), None, None).unwrap();
     assert_eq!(
-        claims.claims,
+        vault
+            .all_accounts(false, None, None, None)
+            .unwrap()
+            .accounts,
         [
-            LienResponse {
-                lienholder: local_staking.contract_addr.to_string(),
-                amount: ValueRange::new_val(Uint128::new(local_stake))
+            AllAccountsResponseItem {
+                user: user.to_owned(),
+                account: AccountResponse::new(
+                    OSMO,
+                    Uint128::new(300),
+                    ValueRange::new_val(Uint128::new(300))
+                ),
             },
-            LienResponse {
-                lienholder: cross_staking.contract_addr.to_string(),
-                amount: ValueRange::new_val(Uint128::new(140))
+            AllAccountsResponseItem {
+                user: user.to_owned(),
+                account: AccountResponse::new(
+                    LST,
+                    Uint128::new(200),
+                    ValueRange::new_val(Uint128::new(50))
+                ),
             },
         ]
     );
 
-    let acc_details = vault.account_details(user.to_owned()).unwrap();
-    // Max lien
-    assert_eq!(acc_details.max_lien, ValueRange::new_val(Uint128::new(190)));
-    // Total slashable
+    // Collateral is not shared across denoms
+    let err = vault.unbond(coin(100, LST)).call(user).unwrap_err();
     assert_eq!(
-        acc_details.total_slashable,
-        ValueRange::new_val(Uint128::new(33))
+        err,
+        ContractError::ClaimsLocked(ValueRange::new_val(Uint128::new(50)))
     );
-    // Collateral
-    assert_eq!(acc_details.bonded, Uint128::new(190));
-    // Free collateral
-    assert_eq!(acc_details.free, ValueRange::new_val(Uint128::zero()));
 
-    // Cross stake
-    let cross_stake1 = cross_staking
-        .stake(user.to_string(), validator1.to_string())
-        .unwrap();
-    assert_eq!(cross_stake1.stake, ValueRange::new_val(Uint128::new(90))); // 10% slashed
-    let cross_stake2 = cross_staking
-        .stake(user.to_string(), validator2.to_string())
-        .unwrap();
-    assert_eq!(cross_stake2.stake, ValueRange::new_val(Uint128::new(50))); // no slashing
+    // The primary denom cannot be removed
+    let err = vault.remove_collateral_denom(OSMO.to_owned()).unwrap_err();
+    assert_eq!(err, ContractError::PrimaryDenom(OSMO.to_owned()));
+
+    // Removing a denom stops new bonds, but existing collateral can still be unbonded
+    vault.remove_collateral_denom(LST.to_owned()).unwrap();
+    assert_eq!(vault.collateral_denoms().unwrap().denoms, [OSMO]);
+    vault.unbond(coin(50, LST)).call(user).unwrap();
+    let err = vault
+        .bond(None)
+        .with_funds(&coins(50, LST))
+        .call(user)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::UnsupportedCollateralDenom(LST.to_owned())
+    );
+    assert_eq!(
+        app.app().wrap().query_balance(user, LST).unwrap(),
+        coin(50, LST)
+    );
 }
 
-/// Scenario 2:
-/// https://github.com/osmosis-labs/mesh-security/blob/main/docs/ibc/Slashing.md#scenario-2-slashed-delegator-has-no-free-collateral-on-the-vault
 #[test]
-fn cross_slash_scenario_2() {
+fn max_leverage() {
     let owner = "owner";
     let user = "user1";
-    // Remote slashing percentage
-    let slashing_percentage = 10;
-    let collateral = 200;
-    let local_validator = "local";
-    let validators = vec!["validator1", "validator2"];
-    let validator1 = validators[0];
-
-    let mut app = init_app(&[user], &[1000]);
-    add_local_validator(&mut app, local_validator);
-
-    let (vault, local_staking, cross_staking) = setup(&app, owner, slashing_percentage, 100);
 
-    let (_update_valset_height, _update_valset_time) =
-        set_active_validators(&cross_staking, &validators);
+    let mut app = init_app(&[user], &[300]);
+    add_local_validator(&mut app, "validator1");
 
-    // Bond some collateral
-    bond(&vault, user, collateral);
+    let (vault, _local_staking, cross_staking) = setup(&app, owner, 10, 100);
+    set_active_validators(&cross_staking, &["validator1"]);
+    bond(&vault, user, 100);
 
-    // Stake some tokens locally
-    let local_stake = 200;
-    stake_locally(&vault, user, local_stake, local_validator).unwrap();
+    let err = vault
+        .set_max_leverage(Some(Decimal::percent(50)))
+        .unwrap_err();
+    assert_eq!(err, ContractError::InvalidMaxLeverage);
+    vault.set_max_leverage(Some(Decimal::percent(150))).unwrap();
 
-    // Stake some tokens remotely
-    stake_remotely(&vault, &cross_staking, user, &[validator1], &[200]);
+    stake_remotely(&vault, &cross_staking, user, &["validator1"], &[100]);
+    let local_msg = to_json_binary(&mesh_native_staking::msg::StakeMsg {
+        validator: "validator1".to_string(),
+    })
+    .unwrap();
 
-    let claims = vault.account_claims(user.to_owned(), None, None).unwrap();
-    assert_eq!(
-        claims.claims,
-        [
-            LienResponse {
-                lienholder: local_staking.contract_addr.to_string(),
-                amount: ValueRange::new_val(Uint128::new(200))
-            },
-            LienResponse {
-                lienholder: cross_staking.contract_addr.to_string(),
-                amount: ValueRange::new_val(Uint128::new(200))
-            },
-        ]
-    );
+    // The sum of the liens is capped, even if each one is below the collateral
+    let err = vault
+        .stake_local(coin(60, OSMO), local_msg.clone(), None)
+        .call(user)
+        .unwrap_err();
+    assert_eq!(err, ContractError::LeverageExceeded(Decimal::percent(150)));
+    vault
+        .stake_local(coin(50, OSMO), local_msg.clone(), None)
+        .call(user)
+        .unwrap();
 
-    let acc_details = vault.account_details(user.to_owned()).unwrap();
-    // Max lien
-    assert_eq!(acc_details.max_lien, ValueRange::new_val(Uint128::new(200)));
-    // Total slashable
+    let resp = vault.leverage(user.to_owned(), None).unwrap();
     assert_eq!(
-        acc_details.total_slashable,
-        ValueRange::new_val(Uint128::new(40))
+        resp,
+        LeverageResponse {
+            denom: OSMO.to_owned(),
+            collateral: Uint128::new(100),
+            total_liens: Uint128::new(150),
+            leverage: Some(Decimal::percent(150)),
+            max_leverage: Some(Decimal::percent(150)),
+        }
     );
-    // Collateral
-    assert_eq!(acc_details.bonded, Uint128::new(collateral));
-    // Free collateral
-    assert_eq!(acc_details.free, ValueRange::new_val(Uint128::zero()));
 
-    // Cross stakes
-    let cross_stake1 = cross_staking
-        .stake(user.to_string(), validator1.to_string())
+    // Removing the cap lifts the limit
+    vault.set_max_leverage(None).unwrap();
+    vault
+        .stake_local(coin(10, OSMO), local_msg, None)
+        .call(user)
         .unwrap();
-    assert_eq!(cross_stake1.stake, ValueRange::new_val(Uint128::new(200)));
+    let resp = vault.leverage(user.to_owned(), None).unwrap();
+    assert_eq!(resp.leverage, Some(Decimal::percent(160)));
+    assert_eq!(resp.max_leverage, None);
+}
 
-    // Validator 1 is slashed
+#[test]
+fn operator_approvals() {
+    let owner = "owner";
+    let user = "user1";
+    let operator = "operator";
+
+    let mut app = init_app(&[user], &[300]);
+    add_local_validator(&mut app, "validator1");
+
+    let (vault, local_staking, cross_staking) = setup(&app, owner, 10, 100);
+    set_active_validators(&cross_staking, &["validator1"]);
+    bond(&vault, user, 300);
+
+    let remote_msg = to_json_binary(&ReceiveVirtualStake {
+        validator: "validator1".to_string(),
+    })
+    .unwrap();
+    let local_msg = to_json_binary(&mesh_native_staking::msg::StakeMsg {
+        validator: "validator1".to_string(),
+    })
+    .unwrap();
+
+    // No approval yet
+    let err = vault
+        .stake_remote_from(
+            user.to_owned(),
+            cross_staking.contract_addr.to_string(),
+            coin(100, OSMO),
+            remote_msg.clone(),
+            None,
+        )
+        .call(operator)
+        .unwrap_err();
+    assert_eq!(err, ContractError::NoApproval);
+
+    let expires_at = app.block_info().time.plus_seconds(100);
+    vault
+        .approve_operator(
+            operator.to_owned(),
+            coin(150, OSMO),
+            Some(Expiration::AtTime(expires_at)),
+        )
+        .call(user)
+        .unwrap();
+
+    // The operator stakes the user's collateral, spending the approval
+    vault
+        .stake_remote_from(
+            user.to_owned(),
+            cross_staking.contract_addr.to_string(),
+            coin(100, OSMO),
+            remote_msg.clone(),
+            None,
+        )
+        .call(operator)
+        .unwrap();
+    let last_external_staking_tx = get_last_external_staking_pending_tx_id(&cross_staking).unwrap();
     cross_staking
-        .test_handle_slashing(validator1.to_string(), Uint128::new(20))
+        .test_commit_stake(last_external_staking_tx)
         .call("test")
         .unwrap();
 
-    // Liens
     let claims = vault.account_claims(user.to_owned(), None, None).unwrap();
     assert_eq!(
         claims.claims,
-        [
-            LienResponse {
-                lienholder: local_staking.contract_addr.to_string(),
-                amount: ValueRange::new_val(Uint128::new(180))
-            },
-            LienResponse {
-                lienholder: cross_staking.contract_addr.to_string(),
-                amount: ValueRange::new_val(Uint128::new(180))
-            },
-        ]
+        [LienResponse {
+            lienholder: cross_staking.contract_addr.to_string(),
+            denom: OSMO.to_owned(),
+            amount: ValueRange::new_val(Uint128::new(100)),
+        }]
     );
+    let approval = vault
+        .approval(user.to_owned(), operator.to_owned(), OSMO.to_owned())
+        .unwrap();
+    assert_eq!(approval.spend_limit, coin(50, OSMO));
+    assert!(!approval.expired);
 
-    let acc_details = vault.account_details(user.to_owned()).unwrap();
-    // Max lien
-    assert_eq!(acc_details.max_lien, ValueRange::new_val(Uint128::new(180)));
-    // Total slashable
-    assert_eq!(
-        acc_details.total_slashable,
-        ValueRange::new_val(Uint128::new(36))
-    );
-    // Collateral
-    assert_eq!(acc_details.bonded, Uint128::new(180));
-    // Free collateral
-    assert_eq!(acc_details.free, ValueRange::new_val(Uint128::zero()));
+    // Staking over the approval fails
+    let err = vault
+        .stake_local_from(user.to_owned(), coin(60, OSMO), local_msg.clone(), None)
+        .call(operator)
+        .unwrap_err();
+    assert_eq!(err, ContractError::ApprovalExceeded(Uint128::new(50)));
+    vault
+        .stake_local_from(user.to_owned(), coin(50, OSMO), local_msg.clone(), None)
+        .call(operator)
+        .unwrap();
+    let claims = vault.account_claims(user.to_owned(), None, None).unwrap();
+    assert!(claims.claims.contains(&LienResponse {
+        lienholder: local_staking.contract_addr.to_string(),
+        denom: OSMO.to_owned(),
+        amount: ValueRange::new_val(Uint128::new(50)),
+    }));
 
-    // Cross stakes
-    let cross_stake1 = cross_staking
-        .stake(user.to_string(), validator1.to_string())
+    // Approvals expire
+    vault
+        .approve_operator(
+            operator.to_owned(),
+            coin(100, OSMO),
+            Some(Expiration::AtTime(expires_at)),
+        )
+        .call(user)
         .unwrap();
-    assert_eq!(cross_stake1.stake, ValueRange::new_val(Uint128::new(180))); // 10% slashed
+    skip_time(&app, 100);
+    let err = vault
+        .stake_local_from(user.to_owned(), coin(10, OSMO), local_msg.clone(), None)
+        .call(operator)
+        .unwrap_err();
+    assert_eq!(err, ContractError::ApprovalExpired);
+
+    // And can be revoked
+    vault
+        .approve_operator(operator.to_owned(), coin(100, OSMO), None)
+        .call(user)
+        .unwrap();
+    vault
+        .revoke_operator(operator.to_owned(), OSMO.to_owned())
+        .call(user)
+        .unwrap();
+    let err = vault
+        .stake_local_from(user.to_owned(), coin(10, OSMO), local_msg, None)
+        .call(operator)
+        .unwrap_err();
+    assert_eq!(err, ContractError::NoApproval);
 }
 
-/// Scenario 3:
-/// https://github.com/osmosis-labs/mesh-security/blob/main/docs/ibc/Slashing.md#scenario-3-slashed-delegator-has-some-free-collateral-on-the-vault
 #[test]
-fn cross_slash_scenario_3() {
+fn stake_memo() {
     let owner = "owner";
     let user = "user1";
-    // Remote slashing percentage
-    let slashing_percentage = 10;
-    let collateral = 200;
-    let local_validator = "local";
-    let validators = vec!["validator1", "validator2"];
-    let validator1 = validators[0];
 
-    let mut app = init_app(&[user], &[1000]);
-    add_local_validator(&mut app, local_validator);
-
-    let (vault, local_staking, cross_staking) = setup(&app, owner, slashing_percentage, 100);
+    let app = init_app(&[user], &[300]);
 
-    let (_update_valset_height, _update_valset_time) =
-        set_active_validators(&cross_staking, &validators);
+    let (vault, _local_staking, cross_staking) = setup(&app, owner, 10, 100);
+    set_active_validators(&cross_staking, &["validator1"]);
+    bond(&vault, user, 300);
 
-    // Bond some collateral
-    bond(&vault, user, collateral);
+    let stake_msg = to_json_binary(&ReceiveVirtualStake {
+        validator: "validator1".to_string(),
+    })
+    .unwrap();
 
-    // Stake some tokens locally
-    let local_stake = 190;
-    stake_locally(&vault, user, local_stake, local_validator).unwrap();
+    let err = vault
+        .stake_remote(
+            cross_staking.contract_addr.to_string(),
+            coin(100, OSMO),
+            stake_msg.clone(),
+            Some("x".repeat(MAX_MEMO_LEN + 1)),
+        )
+        .call(user)
+        .unwrap_err();
+    assert_eq!(err, ContractError::MemoTooLong(MAX_MEMO_LEN));
 
-    // Stake some tokens remotely
-    stake_remotely(&vault, &cross_staking, user, &[validator1], &[150]);
+    let resp = vault
+        .stake_remote(
+            cross_staking.contract_addr.to_string(),
+            coin(100, OSMO),
+            stake_msg,
+            Some("ledger-ref-42".to_owned()),
+        )
+        .call(user)
+        .unwrap();
+    assert!(resp.has_event(
+        &Event::new("wasm")
+            .add_attribute("action", "stake_remote")
+            .add_attribute("memo", "ledger-ref-42")
+    ));
 
-    let claims = vault.account_claims(user.to_owned(), None, None).unwrap();
+    // The memo is kept with the pending tx, until it is committed
+    let tx_id = get_last_vault_pending_tx_id(&vault).unwrap();
     assert_eq!(
-        claims.claims,
-        [
-            LienResponse {
-                lienholder: local_staking.contract_addr.to_string(),
-                amount: ValueRange::new_val(Uint128::new(190))
-            },
-            LienResponse {
-                lienholder: cross_staking.contract_addr.to_string(),
-                amount: ValueRange::new_val(Uint128::new(150))
-            },
-        ]
+        vault.tx_memo(tx_id).unwrap().memo,
+        Some("ledger-ref-42".to_owned())
     );
+    let last_external_staking_tx = get_last_external_staking_pending_tx_id(&cross_staking).unwrap();
+    cross_staking
+        .test_commit_stake(last_external_staking_tx)
+        .call("test")
+        .unwrap();
+    assert_eq!(vault.tx_memo(tx_id).unwrap().memo, None);
+}
 
-    let acc_details = vault.account_details(user.to_owned()).unwrap();
-    // Max lien
-    assert_eq!(acc_details.max_lien, ValueRange::new_val(Uint128::new(190)));
-    // Total slashable
-    assert_eq!(
-        acc_details.total_slashable,
-        ValueRange::new_val(Uint128::new(34))
-    );
-    // Collateral
-    assert_eq!(acc_details.bonded, Uint128::new(200));
-    // Free collateral
-    assert_eq!(acc_details.free, ValueRange::new_val(Uint128::new(10)));
+#[test]
+fn bond_on_behalf() {
+    let owner = "owner";
+    let custodian = "custodian";
+    let user = "user1";
 
-    // Cross stakes
-    let cross_stake1 = cross_staking
-        .stake(user.to_string(), validator1.to_string())
-        .unwrap();
-    assert_eq!(cross_stake1.stake, ValueRange::new_val(Uint128::new(150)));
+    let app = init_app(&[custodian], &[300]);
 
-    // Validator 1 is slashed
-    cross_staking
-        .test_handle_slashing(validator1.to_string(), Uint128::new(15))
-        .call("test")
+    let (vault, _local_staking, _cross_staking) = setup(&app, owner, 0, 100);
+
+    vault
+        .bond(Some(user.to_owned()))
+        .with_funds(&coins(200, OSMO))
+        .call(custodian)
         .unwrap();
 
-    // Liens
-    let claims = vault.account_claims(user.to_owned(), None, None).unwrap();
+    // Collateral is credited to the user, and only the user can unbond it
     assert_eq!(
-        claims.claims,
-        [
-            LienResponse {
-                lienholder: local_staking.contract_addr.to_string(),
-                amount: ValueRange::new_val(Uint128::new(185))
-            },
-            LienResponse {
-                lienholder: cross_staking.contract_addr.to_string(),
-                amount: ValueRange::new_val(Uint128::new(135))
-            },
-        ]
+        vault.account(user.to_owned()).unwrap().bonded,
+        Uint128::new(200)
     );
-
-    let acc_details = vault.account_details(user.to_owned()).unwrap();
-    // Max lien
-    assert_eq!(acc_details.max_lien, ValueRange::new_val(Uint128::new(185)));
-    // Total slashable
     assert_eq!(
-        acc_details.total_slashable,
-        ValueRange::new_val(Uint128::new(32 + 1)) // Because of rounding / truncation
+        vault.account(custodian.to_owned()).unwrap().bonded,
+        Uint128::zero()
+    );
+    vault.unbond(coin(100, OSMO)).call(custodian).unwrap_err();
+    vault.unbond(coin(100, OSMO)).call(user).unwrap();
+    assert_eq!(
+        app.app().wrap().query_balance(user, OSMO).unwrap(),
+        coin(100, OSMO)
     );
-    // Collateral
-    assert_eq!(acc_details.bonded, Uint128::new(185));
-    // Free collateral
-    assert_eq!(acc_details.free, ValueRange::new_val(Uint128::zero()));
 
-    // Cross stakes
-    let cross_stake1 = cross_staking
-        .stake(user.to_string(), validator1.to_string())
-        .unwrap();
-    assert_eq!(cross_stake1.stake, ValueRange::new_val(Uint128::new(135))); // 10% slashed
+    // Invalid addresses are rejected
+    vault
+        .bond(Some("".to_owned()))
+        .with_funds(&coins(100, OSMO))
+        .call(custodian)
+        .unwrap_err();
 }
 
-/// Scenario 4:
-/// https://github.com/osmosis-labs/mesh-security/blob/main/docs/ibc/Slashing.md#scenario-4-same-as-scenario-3-but-with-more-delegations
 #[test]
-fn cross_slash_scenario_4() {
+fn bond_and_stake() {
     let owner = "owner";
     let user = "user1";
-    // Remote slashing percentage
-    let slashing_percentage = 10;
-    let local_validator = "local";
-    let collateral = 200;
-    let validators_1 = vec!["validator1", "validator2"];
-    let validators_2 = vec!["validator3", "validator4"];
-    let validator1 = validators_1[0];
-
-    let mut app = init_app(&[user], &[1000]);
-    add_local_validator(&mut app, local_validator);
 
-    let (vault, local_staking, cross_staking_1) = setup(&app, owner, slashing_percentage, 100);
-    let cross_staking_2 = setup_cross_stake(&app, owner, &vault, slashing_percentage, 100);
+    let mut app = init_app(&[user], &[300]);
+    add_local_validator(&mut app, "validator1");
 
-    let (_, _) = set_active_validators(&cross_staking_1, &validators_1);
-    let (_update_valset_height, _update_valset_time) =
-        set_active_validators(&cross_staking_2, &validators_2);
+    let (vault, _local_staking, cross_staking) = setup(&app, owner, 10, 100);
+    set_active_validators(&cross_staking, &["validator1"]);
 
-    // Bond some collateral
-    bond(&vault, user, collateral);
+    // Remote stake goes through a pending tx
+    vault
+        .bond_and_stake(
+            Some(cross_staking.contract_addr.to_string()),
+            to_json_binary(&ReceiveVirtualStake {
+                validator: "validator1".to_string(),
+            })
+            .unwrap(),
+            None,
+        )
+        .with_funds(&coins(100, OSMO))
+        .call(user)
+        .unwrap();
 
-    // Stake some tokens locally
-    let local_stake = 190;
-    stake_locally(&vault, user, local_stake, local_validator).unwrap();
+    let acc = vault.account(user.to_owned()).unwrap();
+    assert_eq!(acc.bonded, Uint128::new(100));
+    assert_eq!(
+        acc.free,
+        ValueRange::new(Uint128::zero(), Uint128::new(100))
+    );
+    let claim = vault
+        .claim(user.to_owned(), cross_staking.contract_addr.to_string())
+        .unwrap();
+    assert_eq!(
+        claim.amount,
+        ValueRange::new(Uint128::zero(), Uint128::new(100))
+    );
 
-    // Stake some tokens remotely
-    stake_remotely(&vault, &cross_staking_1, user, &validators_1, &[140, 40]);
-    stake_remotely(&vault, &cross_staking_2, user, &validators_2, &[100, 88]);
+    let last_external_staking_tx = get_last_external_staking_pending_tx_id(&cross_staking).unwrap();
+    cross_staking
+        .test_commit_stake(last_external_staking_tx)
+        .call("test")
+        .unwrap();
+    let claim = vault
+        .claim(user.to_owned(), cross_staking.contract_addr.to_string())
+        .unwrap();
+    assert_eq!(claim.amount, ValueRange::new_val(Uint128::new(100)));
 
-    let claims = vault.account_claims(user.to_owned(), None, None).unwrap();
+    // Local stake is immediate
+    vault
+        .bond_and_stake(
+            None,
+            to_json_binary(&mesh_native_staking::msg::StakeMsg {
+                validator: "validator1".to_string(),
+            })
+            .unwrap(),
+            None,
+        )
+        .with_funds(&coins(200, OSMO))
+        .call(user)
+        .unwrap();
+
+    let acc = vault.account(user.to_owned()).unwrap();
+    assert_eq!(acc.bonded, Uint128::new(300));
+    // Max lien is the local stake, remote stake is only slashable
+    assert_eq!(acc.free, ValueRange::new_val(Uint128::new(100)));
+
+    // Nothing is bonded if the stake fails
+    let app = init_app(&[user], &[300]);
+    let (vault, _, cross_staking) = setup(&app, owner, 10, 100);
+    vault
+        .bond_and_stake(
+            Some(cross_staking.contract_addr.to_string()),
+            to_json_binary(&ReceiveVirtualStake {
+                validator: "validator1".to_string(),
+            })
+            .unwrap(),
+            None,
+        )
+        .with_funds(&coins(100, OSMO))
+        .call(user)
+        .unwrap_err();
+    assert_eq!(
+        vault.account(user.to_owned()).unwrap().bonded,
+        Uint128::zero()
+    );
+}
+
+#[test]
+fn metrics() {
+    let owner = "owner";
+    let users = ["user1", "user2"];
+
+    let app = init_app(&users, &[300, 300]);
+
+    let (vault, _, cross_staking) = setup(&app, owner, 10, 100);
+    set_active_validators(&cross_staking, &["validator1"]);
+
+    let metrics = vault.metrics().unwrap();
+    assert_eq!(metrics.accounts, 0);
+    assert_eq!(metrics.total_bonded, []);
+    assert_eq!(metrics.pending_txs, 0);
+    assert_eq!(metrics.oldest_pending_tx_age, None);
+
+    bond(&vault, users[0], 300);
+    bond(&vault, users[1], 200);
+
+    vault
+        .stake_remote(
+            cross_staking.contract_addr.to_string(),
+            coin(100, OSMO),
+            to_json_binary(&ReceiveVirtualStake {
+                validator: "validator1".to_string(),
+            })
+            .unwrap(),
+            None,
+        )
+        .call(users[0])
+        .unwrap();
+    skip_time(&app, 50);
+
+    let metrics = vault.metrics().unwrap();
+    assert_eq!(metrics.accounts, 2);
+    assert_eq!(metrics.total_bonded, [coin(500, OSMO)]);
+    assert_eq!(metrics.total_liens, [coin(100, OSMO)]);
+    assert_eq!(metrics.pending_txs, 1);
+    assert_eq!(metrics.oldest_pending_tx_age, Some(50));
+
+    let last_external_staking_tx = get_last_external_staking_pending_tx_id(&cross_staking).unwrap();
+    cross_staking
+        .test_commit_stake(last_external_staking_tx)
+        .call("test")
+        .unwrap();
+
+    let metrics = vault.metrics().unwrap();
+    assert_eq!(metrics.total_liens, [coin(100, OSMO)]);
+    assert_eq!(metrics.pending_txs, 0);
+    assert_eq!(metrics.oldest_pending_tx_age, None);
+}
+
+#[test]
+fn liens_by_lienholder() {
+    let owner = "owner";
+    let users = ["user1", "user2", "user3"];
+
+    let app = init_app(&users, &[300, 300, 300]);
+
+    let (vault, local_staking, cross_staking) = setup(&app, owner, 10, 100);
+    set_active_validators(&cross_staking, &["validator1"]);
+
+    for user in users {
+        bond(&vault, user, 300);
+    }
+    stake_remotely(&vault, &cross_staking, users[0], &["validator1"], &[100]);
+    stake_remotely(&vault, &cross_staking, users[2], &["validator1"], &[50]);
+    stake_locally(&vault, users[1], 200, "validator1").unwrap();
+
+    let liens = vault
+        .liens_by_lienholder(cross_staking.contract_addr.to_string(), None, None)
+        .unwrap()
+        .liens;
+    assert_eq!(
+        liens,
+        [
+            LienholderLienResponse {
+                user: users[0].to_owned(),
+                denom: OSMO.to_owned(),
+                amount: ValueRange::new_val(Uint128::new(100)),
+            },
+            LienholderLienResponse {
+                user: users[2].to_owned(),
+                denom: OSMO.to_owned(),
+                amount: ValueRange::new_val(Uint128::new(50)),
+            },
+        ]
+    );
+
+    // Paginated by user
+    let liens = vault
+        .liens_by_lienholder(
+            cross_staking.contract_addr.to_string(),
+            Some(users[0].to_owned()),
+            None,
+        )
+        .unwrap()
+        .liens;
+    assert_eq!(liens.len(), 1);
+    assert_eq!(liens[0].user, users[2]);
+
+    let liens = vault
+        .liens_by_lienholder(local_staking.contract_addr.to_string(), None, None)
+        .unwrap()
+        .liens;
+    assert_eq!(
+        liens,
+        [LienholderLienResponse {
+            user: users[1].to_owned(),
+            denom: OSMO.to_owned(),
+            amount: ValueRange::new_val(Uint128::new(200)),
+        }]
+    );
+
+    // Accounts can be filtered by lienholder
+    let accounts = vault
+        .all_accounts(
+            false,
+            None,
+            None,
+            Some(cross_staking.contract_addr.to_string()),
+        )
+        .unwrap()
+        .accounts;
+    let accounts: Vec<_> = accounts.iter().map(|item| item.user.as_str()).collect();
+    assert_eq!(accounts, [users[0], users[2]]);
+
+    let accounts = vault
+        .all_accounts(
+            false,
+            Some(users[0].to_owned()),
+            None,
+            Some(cross_staking.contract_addr.to_string()),
+        )
+        .unwrap()
+        .accounts;
+    let accounts: Vec<_> = accounts.iter().map(|item| item.user.as_str()).collect();
+    assert_eq!(accounts, [users[2]]);
+}
+
+#[test]
+fn account_at_height() {
+    let owner = "owner";
+    let users = ["user1", "user2"];
+
+    let app = init_app(&users, &[300, 300]);
+
+    let (vault, _, _) = setup(&app, owner, 10, 100);
+    let next_block = || app.app_mut().update_block(|block| block.height += 1);
+
+    let start = app.block_info().height;
+    bond(&vault, users[0], 200);
+    next_block();
+    bond(&vault, users[0], 100);
+    vault
+        .transfer_collateral(users[1].to_owned(), coin(50, OSMO))
+        .call(users[0])
+        .unwrap();
+    next_block();
+    vault.unbond(coin(150, OSMO)).call(users[0]).unwrap();
+    next_block();
+
+    let collateral_at = |user: &str, height: u64| {
+        vault
+            .account_at_height(user.to_owned(), height)
+            .unwrap()
+            .collateral
+    };
+    // Values are the ones at the beginning of the block
+    assert_eq!(collateral_at(users[0], start), []);
+    assert_eq!(collateral_at(users[0], start + 1), [coin(200, OSMO)]);
+    assert_eq!(collateral_at(users[0], start + 2), [coin(250, OSMO)]);
+    assert_eq!(collateral_at(users[0], start + 3), [coin(100, OSMO)]);
+    assert_eq!(collateral_at(users[1], start + 1), []);
+    assert_eq!(collateral_at(users[1], start + 2), [coin(50, OSMO)]);
+    assert_eq!(collateral_at(users[1], start + 3), [coin(50, OSMO)]);
+}
+
+#[test]
+fn total_stats() {
+    let owner = "owner";
+    let users = ["user1", "user2"];
+
+    let app = init_app(&users, &[300, 300]);
+
+    let (vault, _, cross_staking) = setup(&app, owner, 10, 100);
+    set_active_validators(&cross_staking, &["validator1"]);
+
+    let stats = vault.total_stats().unwrap();
+    assert_eq!(stats.accounts, 0);
+    assert_eq!(stats.total_bonded, []);
+    assert_eq!(stats.lienholder_totals, []);
+    assert_eq!(stats.total_slashable, []);
+
+    bond(&vault, users[0], 300);
+    bond(&vault, users[1], 200);
+    bond(&vault, users[1], 100);
+
+    vault
+        .stake_remote(
+            cross_staking.contract_addr.to_string(),
+            coin(100, OSMO),
+            to_json_binary(&ReceiveVirtualStake {
+                validator: "validator1".to_string(),
+            })
+            .unwrap(),
+            None,
+        )
+        .call(users[0])
+        .unwrap();
+
+    // Pending stakes are accounted in the upper bounds
+    let stats = vault.total_stats().unwrap();
+    assert_eq!(stats.accounts, 2);
+    assert_eq!(stats.total_bonded, [coin(600, OSMO)]);
+    assert_eq!(
+        stats.lienholder_totals,
+        [LienholderTotal {
+            lienholder: cross_staking.contract_addr.to_string(),
+            denom: OSMO.to_owned(),
+            total: ValueRange::new(Uint128::zero(), Uint128::new(100)),
+        }]
+    );
+    assert_eq!(stats.total_slashable, [coin(10, OSMO)]);
+
+    let last_external_staking_tx = get_last_external_staking_pending_tx_id(&cross_staking).unwrap();
+    cross_staking
+        .test_commit_stake(last_external_staking_tx)
+        .call("test")
+        .unwrap();
+    vault.unbond(coin(50, OSMO)).call(users[1]).unwrap();
+
+    let stats = vault.total_stats().unwrap();
+    assert_eq!(stats.accounts, 2);
+    assert_eq!(stats.total_bonded, [coin(550, OSMO)]);
+    assert_eq!(
+        stats.lienholder_totals[0].total,
+        ValueRange::new_val(Uint128::new(100))
+    );
+    assert_eq!(stats.total_slashable, [coin(10, OSMO)]);
+
+    // Totals match the ones computed by iterating over all accounts
+    let metrics = vault.metrics().unwrap();
+    assert_eq!(metrics.accounts, stats.accounts);
+    assert_eq!(metrics.total_bonded, stats.total_bonded);
+}
+
+/// Minimal CW20 token stub, reporting the transfers and burns it is asked to perform
+fn store_cw20_stub(app: &App<MtApp>) -> u64 {
+    use cosmwasm_std::{Binary, Deps, DepsMut, Empty, Env, MessageInfo, Response, StdResult};
+    use cw_multi_test::ContractWrapper;
+
+    fn execute(
+        _deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        msg: Cw20ExecuteMsg,
+    ) -> StdResult<Response> {
+        let resp = match msg {
+            Cw20ExecuteMsg::Transfer { recipient, amount } => Response::new()
+                .add_attribute("action", "transfer")
+                .add_attribute("recipient", recipient)
+                .add_attribute("amount", amount.to_string()),
+            Cw20ExecuteMsg::Burn { amount } => Response::new()
+                .add_attribute("action", "burn")
+                .add_attribute("amount", amount.to_string()),
+        };
+        Ok(resp)
+    }
+
+    fn instantiate(
+        _deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        _msg: Empty,
+    ) -> StdResult<Response> {
+        Ok(Response::new())
+    }
+
+    fn query(_deps: Deps, _env: Env, _msg: Empty) -> StdResult<Binary> {
+        Ok(Binary::default())
+    }
+
+    app.app_mut()
+        .store_code(Box::new(ContractWrapper::new(execute, instantiate, query)))
+}
+
+/// Returns the `action` and `amount` attributes of the token contract wasm events
+fn cw20_actions(resp: &cw_multi_test::AppResponse, token: &Addr) -> Vec<(String, String)> {
+    resp.events
+        .iter()
+        .filter(|event| {
+            event.ty == "wasm"
+                && event
+                    .attributes
+                    .iter()
+                    .any(|attr| attr.key == "_contract_address" && attr.value == token.as_str())
+        })
+        .map(|event| {
+            let attr = |key: &str| {
+                event
+                    .attributes
+                    .iter()
+                    .find(|attr| attr.key == key)
+                    .map(|attr| attr.value.clone())
+                    .unwrap_or_default()
+            };
+            (attr("action"), attr("amount"))
+        })
+        .collect()
+}
+
+#[test]
+fn cw20_collateral() {
+    let owner = "owner";
+    let user = "user1";
+    let validator = "validator1";
+
+    let app = init_app(&[user], &[300]);
+    let (vault, _, _) = setup(&app, owner, 10, 100);
+
+    let token_code = store_cw20_stub(&app);
+    let token = cw_multi_test::Executor::instantiate_contract(
+        &mut *app.app_mut(),
+        token_code,
+        Addr::unchecked(owner),
+        &cosmwasm_std::Empty {},
+        &[],
+        "Token",
+        None,
+    )
+    .unwrap();
+    let denom = cw20_denom(&token);
+    let bond_msg = to_json_binary(&Cw20HookMsg::Bond {}).unwrap();
+
+    // Token is not accepted as collateral yet
+    let err = vault
+        .receive(user.to_owned(), Uint128::new(200), bond_msg.clone())
+        .call(token.as_str())
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::UnsupportedCollateralDenom(denom.clone())
+    );
+
+    // CW20 denoms can only be accepted through their own sudo message
+    let err = vault.add_collateral_denom(denom.clone()).unwrap_err();
+    assert_eq!(err, ContractError::Cw20Denom(denom.clone()));
+    vault.add_token_collateral(token.to_string()).unwrap();
+    assert_eq!(
+        vault.collateral_denoms().unwrap().denoms,
+        [OSMO.to_owned(), denom.clone()]
+    );
+
+    // Bond through the token contract
+    vault
+        .receive(user.to_owned(), Uint128::new(200), bond_msg)
+        .call(token.as_str())
+        .unwrap();
+    let collateral = vault
+        .account_collateral(user.to_owned())
+        .unwrap()
+        .collateral;
+    assert_eq!(collateral.len(), 1);
+    assert_eq!(collateral[0].denom, denom);
+    assert_eq!(collateral[0].bonded, Uint128::new(200));
+
+    // CW20 collateral cannot be staked locally
+    let err = vault
+        .stake_local(coin(100, &denom), Binary::default(), None)
+        .call(user)
+        .unwrap_err();
+    assert_eq!(err, ContractError::Cw20Denom(denom.clone()));
+
+    // But it can be cross staked on a contract accepting the token
+    let cross_staking_code = mesh_external_staking::contract::sv::mt::CodeId::store_code(&app);
+    let cross_staking = cross_staking_code
+        .instantiate(
+            denom.clone(),
+            STAR.to_owned(),
+            vault.contract_addr.to_string(),
+            100,
+            AuthorizedEndpoint::new("connection-2", "wasm-osmo1foobarbaz"),
+            SlashRatio {
+                double_sign: Decimal::percent(10),
+                offline: Decimal::percent(10),
+            },
+            None,
+            None,
+            None,
+        )
+        .call(owner)
+        .unwrap();
+    vault
+        .add_allowed_lienholder(cross_staking.contract_addr.to_string())
+        .call(owner)
+        .unwrap();
+    set_active_validators(&cross_staking, &[validator]);
+    vault
+        .stake_remote(
+            cross_staking.contract_addr.to_string(),
+            coin(100, &denom),
+            to_json_binary(&ReceiveVirtualStake {
+                validator: validator.to_string(),
+            })
+            .unwrap(),
+            None,
+        )
+        .call(user)
+        .unwrap();
+    let tx_id = get_last_external_staking_pending_tx_id(&cross_staking).unwrap();
+    cross_staking.test_commit_stake(tx_id).call("test").unwrap();
+
+    // Slashed collateral is burned on the token contract
+    let resp = cross_staking
+        .test_handle_slashing(validator.to_string(), Uint128::new(10))
+        .call("test")
+        .unwrap();
+    assert_eq!(
+        cw20_actions(&resp, &token),
+        [("burn".to_owned(), "10".to_owned())]
+    );
+    let collateral = vault
+        .account_collateral(user.to_owned())
+        .unwrap()
+        .collateral;
+    assert_eq!(collateral[0].bonded, Uint128::new(190));
+
+    // Unbonding transfers the tokens back
+    let resp = vault.unbond(coin(50, &denom)).call(user).unwrap();
+    assert_eq!(
+        cw20_actions(&resp, &token),
+        [("transfer".to_owned(), "50".to_owned())]
+    );
+    let collateral = vault
+        .account_collateral(user.to_owned())
+        .unwrap()
+        .collateral;
+    assert_eq!(collateral[0].bonded, Uint128::new(140));
+}
+
+/// Rate provider stub. The rate is set at instantiation, and updated by any execute message
+fn store_rate_provider_stub(app: &App<MtApp>) -> u64 {
+    use cosmwasm_std::{Deps, DepsMut, Env, MessageInfo, Response, StdResult};
+    use cw_multi_test::ContractWrapper;
+    use cw_storage_plus::Item;
+
+    const RATE: Item<Decimal> = Item::new("rate");
+
+    fn execute(
+        deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        msg: ExchangeRateResponse,
+    ) -> StdResult<Response> {
+        RATE.save(deps.storage, &msg.rate)?;
+        Ok(Response::new())
+    }
+
+    fn query(deps: Deps, _env: Env, msg: RateProviderQueryMsg) -> StdResult<Binary> {
+        match msg {
+            RateProviderQueryMsg::ExchangeRate { .. } => to_json_binary(&ExchangeRateResponse {
+                rate: RATE.load(deps.storage)?,
+            }),
+        }
+    }
+
+    app.app_mut()
+        .store_code(Box::new(ContractWrapper::new(execute, execute, query)))
+}
+
+/// External staking of `lst` collateral, with `validator` active
+fn instantiate_lst_cross_staking<'app>(
+    app: &'app App<MtApp>,
+    owner: &str,
+    vault: &Proxy<'app, MtApp, VaultMock<'app>>,
+    lst: &str,
+    validator: &str,
+) -> Proxy<'app, MtApp, ExternalStakingContract<'app>> {
+    let cross_staking_code = mesh_external_staking::contract::sv::mt::CodeId::store_code(app);
+    let cross_staking = cross_staking_code
+        .instantiate(
+            lst.to_owned(),
+            STAR.to_owned(),
+            vault.contract_addr.to_string(),
+            100,
+            AuthorizedEndpoint::new("connection-2", "wasm-osmo1foobarbaz"),
+            SlashRatio {
+                double_sign: Decimal::percent(10),
+                offline: Decimal::percent(10),
+            },
+            None,
+            None,
+            None,
+        )
+        .call(owner)
+        .unwrap();
+    vault
+        .add_allowed_lienholder(cross_staking.contract_addr.to_string())
+        .call(owner)
+        .unwrap();
+    set_active_validators(&cross_staking, &[validator]);
+    cross_staking
+}
+
+#[test]
+fn lst_collateral() {
+    let owner = "owner";
+    let user = "user1";
+    let validator = "validator1";
+    let lst = "stOSMO";
+
+    let app = init_app(&[user], &[300]);
+    app.app_mut()
+        .init_modules(|router, _, storage| {
+            router.bank.init_balance(
+                storage,
+                &Addr::unchecked(user),
+                vec![coin(300, OSMO), coin(100, lst)],
+            )
+        })
+        .unwrap();
+    let (vault, _, _) = setup(&app, owner, 10, 100);
+
+    let provider_code = store_rate_provider_stub(&app);
+    let provider = cw_multi_test::Executor::instantiate_contract(
+        &mut *app.app_mut(),
+        provider_code,
+        Addr::unchecked(owner),
+        &ExchangeRateResponse {
+            rate: Decimal::percent(150),
+        },
+        &[],
+        "Rate provider",
+        None,
+    )
+    .unwrap();
+    let set_rate = |rate: Decimal| {
+        cw_multi_test::Executor::execute_contract(
+            &mut *app.app_mut(),
+            Addr::unchecked(owner),
+            provider.clone(),
+            &ExchangeRateResponse { rate },
+            &[],
+        )
+        .unwrap();
+    };
+
+    // The primary denom is never valued through a rate provider
+    let err = vault
+        .add_lst_collateral(OSMO.to_owned(), provider.to_string())
+        .unwrap_err();
+    assert_eq!(err, ContractError::PrimaryDenom(OSMO.to_owned()));
+    let err = vault
+        .refresh_exchange_rate(OSMO.to_owned())
+        .call(user)
+        .unwrap_err();
+    assert_eq!(err, ContractError::NotLstCollateral(OSMO.to_owned()));
+    assert_eq!(
+        vault.exchange_rate(OSMO.to_owned()).unwrap().rate,
+        Decimal::one()
+    );
+
+    vault
+        .add_lst_collateral(lst.to_owned(), provider.to_string())
+        .unwrap();
+    assert_eq!(
+        vault.exchange_rate(lst.to_owned()).unwrap().rate,
+        Decimal::percent(150)
+    );
+
+    // Free collateral is reported in value
+    vault
+        .bond(None)
+        .with_funds(&coins(100, lst))
+        .call(user)
+        .unwrap();
+    let collateral = vault
+        .account_collateral(user.to_owned())
+        .unwrap()
+        .collateral;
+    assert_eq!(collateral[0].denom, lst);
+    assert_eq!(collateral[0].bonded, Uint128::new(100));
+    assert_eq!(collateral[0].free, ValueRange::new_val(Uint128::new(150)));
+
+    // Liens are taken on the value of the staked tokens
+    let cross_staking = instantiate_lst_cross_staking(&app, owner, &vault, lst, validator);
+    let stake_msg = to_json_binary(&ReceiveVirtualStake {
+        validator: validator.to_string(),
+    })
+    .unwrap();
+    let err = vault
+        .stake_remote(
+            cross_staking.contract_addr.to_string(),
+            coin(101, lst),
+            stake_msg.clone(),
+            None,
+        )
+        .call(user)
+        .unwrap_err();
+    assert_eq!(err, ContractError::InsufficentBalance);
+    vault
+        .stake_remote(
+            cross_staking.contract_addr.to_string(),
+            coin(100, lst),
+            stake_msg,
+            None,
+        )
+        .call(user)
+        .unwrap();
+    let tx_id = get_last_external_staking_pending_tx_id(&cross_staking).unwrap();
+    cross_staking.test_commit_stake(tx_id).call("test").unwrap();
+
+    // Slashed tokens are taken from the lien at their value
+    cross_staking
+        .test_handle_slashing(validator.to_string(), Uint128::new(30))
+        .call("test")
+        .unwrap();
+    let collateral = vault
+        .account_collateral(user.to_owned())
+        .unwrap()
+        .collateral;
+    assert_eq!(collateral[0].bonded, Uint128::new(70));
+    assert_eq!(
+        collateral[0].max_lien,
+        ValueRange::new_val(Uint128::new(105))
+    );
+    assert_eq!(collateral[0].free, ValueRange::new_val(Uint128::zero()));
+
+    let err = vault.unbond(coin(1, lst)).call(user).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::ClaimsLocked(ValueRange::new_val(Uint128::zero()))
+    );
+
+    // A keeper refreshes the rate, freeing some collateral
+    set_rate(Decimal::percent(200));
+    vault
+        .refresh_exchange_rate(lst.to_owned())
+        .call("keeper")
+        .unwrap();
+    assert_eq!(
+        vault.exchange_rate(lst.to_owned()).unwrap().rate,
+        Decimal::percent(200)
+    );
+    let collateral = vault
+        .account_collateral(user.to_owned())
+        .unwrap()
+        .collateral;
+    assert_eq!(collateral[0].free, ValueRange::new_val(Uint128::new(35)));
+
+    vault.unbond(coin(17, lst)).call(user).unwrap();
+    let err = vault.unbond(coin(1, lst)).call(user).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::ClaimsLocked(ValueRange::new_val(Uint128::one()))
+    );
+
+    // Rate providers reporting a zero rate are rejected
+    set_rate(Decimal::zero());
+    let err = vault
+        .refresh_exchange_rate(lst.to_owned())
+        .call("keeper")
+        .unwrap_err();
+    assert_eq!(err, ContractError::ZeroExchangeRate(lst.to_owned()));
+}
+
+#[test]
+fn lst_liens_round_trip() {
+    let owner = "owner";
+    let user = "user1";
+    let validator = "validator1";
+    let lst = "stOSMO";
+
+    let app = init_app(&[user], &[300]);
+    app.app_mut()
+        .init_modules(|router, _, storage| {
+            router
+                .bank
+                .init_balance(storage, &Addr::unchecked(user), vec![coin(100, lst)])
+        })
+        .unwrap();
+    let (vault, _, _) = setup(&app, owner, 10, 100);
+
+    let provider = cw_multi_test::Executor::instantiate_contract(
+        &mut *app.app_mut(),
+        store_rate_provider_stub(&app),
+        Addr::unchecked(owner),
+        &ExchangeRateResponse {
+            rate: Decimal::percent(150),
+        },
+        &[],
+        "Rate provider",
+        None,
+    )
+    .unwrap();
+    vault
+        .add_lst_collateral(lst.to_owned(), provider.to_string())
+        .unwrap();
+    vault
+        .bond(None)
+        .with_funds(&coins(100, lst))
+        .call(user)
+        .unwrap();
+
+    let cross_staking = instantiate_lst_cross_staking(&app, owner, &vault, lst, validator);
+    vault
+        .stake_remote(
+            cross_staking.contract_addr.to_string(),
+            coin(60, lst),
+            to_json_binary(&ReceiveVirtualStake {
+                validator: validator.to_string(),
+            })
+            .unwrap(),
+            None,
+        )
+        .call(user)
+        .unwrap();
+    let tx_id = get_last_external_staking_pending_tx_id(&cross_staking).unwrap();
+    cross_staking.test_commit_stake(tx_id).call("test").unwrap();
+
+    // The rate changes while the tokens are staked
+    cw_multi_test::Executor::execute_contract(
+        &mut *app.app_mut(),
+        Addr::unchecked(owner),
+        provider,
+        &ExchangeRateResponse {
+            rate: Decimal::percent(175),
+        },
+        &[],
+    )
+    .unwrap();
+    vault
+        .refresh_exchange_rate(lst.to_owned())
+        .call("keeper")
+        .unwrap();
+
+    // The tokens are worth more than their lien now, so releasing them releases the whole lien
+    cross_staking
+        .unstake(validator.to_owned(), coin(60, lst))
+        .call(user)
+        .unwrap();
+    let tx_id = get_last_external_staking_pending_tx_id(&cross_staking).unwrap();
+    cross_staking
+        .test_commit_unstake(tx_id)
+        .call("test")
+        .unwrap();
+    skip_time(&app, 100);
+    cross_staking.withdraw_unbonded().call(user).unwrap();
+
+    let collateral = vault
+        .account_collateral(user.to_owned())
+        .unwrap()
+        .collateral;
+    assert_eq!(collateral[0].bonded, Uint128::new(100));
+    assert_eq!(collateral[0].max_lien, ValueRange::new_val(Uint128::zero()));
+    assert_eq!(collateral[0].free, ValueRange::new_val(Uint128::new(175)));
+
+    vault.unbond(coin(100, lst)).call(user).unwrap();
+    assert_eq!(
+        app.app().wrap().query_balance(user, lst).unwrap(),
+        coin(100, lst)
+    );
+}
+
+#[test]
+fn lst_slash_after_rate_change() {
+    let owner = "owner";
+    let user = "user1";
+    let validator = "validator1";
+    let lst = "stOSMO";
+
+    let app = init_app(&[user], &[300]);
+    app.app_mut()
+        .init_modules(|router, _, storage| {
+            router
+                .bank
+                .init_balance(storage, &Addr::unchecked(user), vec![coin(100, lst)])
+        })
+        .unwrap();
+    let (vault, _, _) = setup(&app, owner, 10, 100);
+
+    let provider = cw_multi_test::Executor::instantiate_contract(
+        &mut *app.app_mut(),
+        store_rate_provider_stub(&app),
+        Addr::unchecked(owner),
+        &ExchangeRateResponse {
+            rate: Decimal::percent(150),
+        },
+        &[],
+        "Rate provider",
+        None,
+    )
+    .unwrap();
+    vault
+        .add_lst_collateral(lst.to_owned(), provider.to_string())
+        .unwrap();
+    vault
+        .bond(None)
+        .with_funds(&coins(100, lst))
+        .call(user)
+        .unwrap();
+
+    // Staking 60 tokens at a 1.5 rate takes a lien of 90
+    let cross_staking = instantiate_lst_cross_staking(&app, owner, &vault, lst, validator);
+    vault
+        .stake_remote(
+            cross_staking.contract_addr.to_string(),
+            coin(60, lst),
+            to_json_binary(&ReceiveVirtualStake {
+                validator: validator.to_string(),
+            })
+            .unwrap(),
+            None,
+        )
+        .call(user)
+        .unwrap();
+    let tx_id = get_last_external_staking_pending_tx_id(&cross_staking).unwrap();
+    cross_staking.test_commit_stake(tx_id).call("test").unwrap();
+    let collateral = vault
+        .account_collateral(user.to_owned())
+        .unwrap()
+        .collateral;
+    assert_eq!(
+        collateral[0].max_lien,
+        ValueRange::new_val(Uint128::new(90))
+    );
+    assert_eq!(collateral[0].free, ValueRange::new_val(Uint128::new(60)));
+
+    // The rate goes up to 2 before the validator is slashed
+    cw_multi_test::Executor::execute_contract(
+        &mut *app.app_mut(),
+        Addr::unchecked(owner),
+        provider,
+        &ExchangeRateResponse {
+            rate: Decimal::percent(200),
+        },
+        &[],
+    )
+    .unwrap();
+    vault
+        .refresh_exchange_rate(lst.to_owned())
+        .call("keeper")
+        .unwrap();
+
+    // Slashing 20 tokens burns 20 tokens of collateral, and takes their value of 40 off the lien
+    cross_staking
+        .test_handle_slashing(validator.to_string(), Uint128::new(20))
+        .call("test")
+        .unwrap();
+    let collateral = vault
+        .account_collateral(user.to_owned())
+        .unwrap()
+        .collateral;
+    assert_eq!(collateral[0].bonded, Uint128::new(80));
+    assert_eq!(
+        collateral[0].max_lien,
+        ValueRange::new_val(Uint128::new(50))
+    );
+    assert_eq!(collateral[0].free, ValueRange::new_val(Uint128::new(110)));
+
+    // The 40 tokens left are worth 80 now, so releasing them releases the rest of the lien
+    cross_staking
+        .unstake(validator.to_owned(), coin(40, lst))
+        .call(user)
+        .unwrap();
+    let tx_id = get_last_external_staking_pending_tx_id(&cross_staking).unwrap();
+    cross_staking
+        .test_commit_unstake(tx_id)
+        .call("test")
+        .unwrap();
+    skip_time(&app, 100);
+    cross_staking.withdraw_unbonded().call(user).unwrap();
+
+    let collateral = vault
+        .account_collateral(user.to_owned())
+        .unwrap()
+        .collateral;
+    assert_eq!(collateral[0].max_lien, ValueRange::new_val(Uint128::zero()));
+    assert_eq!(collateral[0].free, ValueRange::new_val(Uint128::new(160)));
+
+    vault.unbond(coin(80, lst)).call(user).unwrap();
+    assert_eq!(
+        app.app().wrap().query_balance(user, lst).unwrap(),
+        coin(80, lst)
+    );
+}
+
+#[test]
+fn pause() {
+    let owner = "owner";
+    let user = "user1";
+    let guardian = "guardian";
+    let val = "validator";
+
+    let mut app = init_app(&[user], &[300]);
+    add_local_validator(&mut app, val);
+    let (vault, _, cross_staking) = setup(&app, owner, SLASHING_PERCENTAGE, 100);
+
+    bond(&vault, user, 200);
+    assert_eq!(
+        vault.pause_status().unwrap(),
+        PauseStatusResponse {
+            pause_authority: None,
+            paused: vec![],
+        }
+    );
+
+    // Without a pause authority, only governance and the owner can pause
+    let err = vault
+        .pause(vec![PausableOperation::Bond])
+        .call(guardian)
+        .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    vault
+        .set_pause_authority(Some(guardian.to_owned()))
+        .unwrap();
+    let err = vault
+        .pause(vec![PausableOperation::Bond])
+        .call(user)
+        .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    // Pausing bonding leaves the other operations untouched
+    vault
+        .pause(vec![PausableOperation::Bond])
+        .call(guardian)
+        .unwrap();
+    let err = vault
+        .bond(None)
+        .with_funds(&coins(100, OSMO))
+        .call(user)
+        .unwrap_err();
+    assert_eq!(err, ContractError::Paused(PausableOperation::Bond));
+    vault.unbond(coin(50, OSMO)).call(user).unwrap();
+    stake_locally(&vault, user, 50, val).unwrap();
+
+    // Governance can pause the rest. Operations listed twice are stored once
+    vault
+        .set_paused(
+            vec![
+                PausableOperation::Unbond,
+                PausableOperation::StakeRemote,
+                PausableOperation::StakeLocal,
+                PausableOperation::StakeRemote,
+            ],
+            true,
+        )
+        .unwrap();
+    assert_eq!(
+        vault.pause_status().unwrap(),
+        PauseStatusResponse {
+            pause_authority: Some(guardian.to_owned()),
+            paused: vec![
+                PausableOperation::Bond,
+                PausableOperation::Unbond,
+                PausableOperation::StakeRemote,
+                PausableOperation::StakeLocal,
+            ],
+        }
+    );
+    let err = vault.unbond(coin(50, OSMO)).call(user).unwrap_err();
+    assert_eq!(err, ContractError::Paused(PausableOperation::Unbond));
+    let err = stake_locally(&vault, user, 50, val).unwrap_err();
+    assert_eq!(err, ContractError::Paused(PausableOperation::StakeLocal));
+    let err = vault
+        .stake_remote(
+            cross_staking.contract_addr.to_string(),
+            coin(50, OSMO),
+            to_json_binary(&ReceiveVirtualStake {
+                validator: val.to_string(),
+            })
+            .unwrap(),
+            None,
+        )
+        .call(user)
+        .unwrap_err();
+    assert_eq!(err, ContractError::Paused(PausableOperation::StakeRemote));
+
+    // Unpausing is selective too
+    vault
+        .unpause(vec![PausableOperation::Bond, PausableOperation::Unbond])
+        .call(guardian)
+        .unwrap();
+    bond(&vault, user, 100);
+    vault.unbond(coin(50, OSMO)).call(user).unwrap();
+    let err = stake_locally(&vault, user, 50, val).unwrap_err();
+    assert_eq!(err, ContractError::Paused(PausableOperation::StakeLocal));
+
+    vault
+        .set_paused(vec![PausableOperation::StakeLocal], false)
+        .unwrap();
+    stake_locally(&vault, user, 50, val).unwrap();
+    assert_eq!(
+        vault.pause_status().unwrap().paused,
+        vec![PausableOperation::StakeRemote]
+    );
+}
+
+#[test]
+fn lienholder_cap() {
+    let owner = "owner";
+    let user1 = "user1";
+    let user2 = "user2";
+    let validator = "validator";
+
+    let app = init_app(&[user1, user2], &[300, 300]);
+    let (vault, _, cross_staking) = setup(&app, owner, SLASHING_PERCENTAGE, 100);
+    set_active_validators(&cross_staking, &[validator]);
+    bond(&vault, user1, 300);
+    bond(&vault, user2, 300);
+
+    let lienholder = cross_staking.contract_addr.to_string();
+    let cap_status = || {
+        vault
+            .lienholder_cap(lienholder.clone(), OSMO.to_owned())
+            .unwrap()
+    };
+    let stake_msg = to_json_binary(&ReceiveVirtualStake {
+        validator: validator.to_string(),
+    })
+    .unwrap();
+
+    // Uncapped by default
+    assert_eq!(
+        cap_status(),
+        LienholderCapResponse {
+            cap: None,
+            total: ValueRange::new_val(Uint128::zero()),
+            headroom: None,
+        }
+    );
+
+    vault
+        .set_lienholder_cap(lienholder.clone(), OSMO.to_owned(), Some(Uint128::new(250)))
+        .unwrap();
+
+    // Pending stakes count towards the cap, and rolled back ones are released
+    vault
+        .stake_remote(lienholder.clone(), coin(100, OSMO), stake_msg.clone(), None)
+        .call(user1)
+        .unwrap();
+    assert_eq!(
+        cap_status(),
+        LienholderCapResponse {
+            cap: Some(Uint128::new(250)),
+            total: ValueRange::new(Uint128::zero(), Uint128::new(100)),
+            headroom: Some(Uint128::new(150)),
+        }
+    );
+    let last_tx = get_last_vault_pending_tx_id(&vault).unwrap();
+    vault.rollback_tx(last_tx).call(&lienholder).unwrap();
+    assert_eq!(cap_status().headroom, Some(Uint128::new(250)));
+
+    // The cap is shared by all users
+    stake_remotely(&vault, &cross_staking, user1, &[validator], &[100]);
+    let err = vault
+        .stake_remote(lienholder.clone(), coin(200, OSMO), stake_msg.clone(), None)
+        .call(user2)
+        .unwrap_err();
+    assert_eq!(err, ContractError::LienholderCapReached(Uint128::new(150)));
+    stake_remotely(&vault, &cross_staking, user2, &[validator], &[150]);
+    assert_eq!(
+        cap_status(),
+        LienholderCapResponse {
+            cap: Some(Uint128::new(250)),
+            total: ValueRange::new_val(Uint128::new(250)),
+            headroom: Some(Uint128::zero()),
+        }
+    );
+    let err = vault
+        .stake_remote(lienholder.clone(), coin(1, OSMO), stake_msg.clone(), None)
+        .call(user1)
+        .unwrap_err();
+    assert_eq!(err, ContractError::LienholderCapReached(Uint128::zero()));
+
+    // Released liens free up headroom
+    vault
+        .release_cross_stake(user1.to_owned(), coin(50, OSMO))
+        .call(&lienholder)
+        .unwrap();
+    assert_eq!(cap_status().headroom, Some(Uint128::new(50)));
+
+    // Removing the cap lifts the limit
+    vault
+        .set_lienholder_cap(lienholder.clone(), OSMO.to_owned(), None)
+        .unwrap();
+    stake_remotely(&vault, &cross_staking, user1, &[validator], &[150]);
+    assert_eq!(
+        cap_status(),
+        LienholderCapResponse {
+            cap: None,
+            total: ValueRange::new_val(Uint128::new(350)),
+            headroom: None,
+        }
+    );
+}
+
+#[test]
+fn allowed_lienholders() {
+    let owner = "owner";
+    let user = "user1";
+    let validator = "validator";
+
+    let app = init_app(&[user], &[300]);
+    let (vault, _, cross_staking) = setup(&app, owner, SLASHING_PERCENTAGE, 100);
+    set_active_validators(&cross_staking, &[validator]);
+    bond(&vault, user, 300);
+
+    let lienholder = cross_staking.contract_addr.to_string();
+    let stake_msg = to_json_binary(&ReceiveVirtualStake {
+        validator: validator.to_string(),
+    })
+    .unwrap();
+    assert_eq!(
+        vault.list_allowed_lienholders(None, None).unwrap(),
+        AllowedLienholdersResponse {
+            lienholders: vec![lienholder.clone()],
+        }
+    );
+
+    // Only the admin manages the allowlist
+    let err = vault
+        .add_allowed_lienholder("malicious".to_owned())
+        .call(user)
+        .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    let err = vault
+        .remove_allowed_lienholder(lienholder.clone())
+        .call(user)
+        .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    // Unknown contracts are rejected
+    let err = vault
+        .stake_remote(
+            "malicious".to_owned(),
+            coin(100, OSMO),
+            stake_msg.clone(),
+            None,
+        )
+        .call(user)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::LienholderNotAllowed("malicious".to_owned())
+    );
+
+    stake_remotely(&vault, &cross_staking, user, &[validator], &[100]);
+
+    // Removed lienholders keep their liens, but cannot receive new stakes
+    vault
+        .remove_allowed_lienholder(lienholder.clone())
+        .call(owner)
+        .unwrap();
+    assert_eq!(
+        vault.list_allowed_lienholders(None, None).unwrap(),
+        AllowedLienholdersResponse {
+            lienholders: vec![],
+        }
+    );
+    let err = vault
+        .remove_allowed_lienholder(lienholder.clone())
+        .call(owner)
+        .unwrap_err();
+    assert_eq!(err, ContractError::LienholderNotAllowed(lienholder.clone()));
+    let err = vault
+        .stake_remote(lienholder.clone(), coin(100, OSMO), stake_msg.clone(), None)
+        .call(user)
+        .unwrap_err();
+    assert_eq!(err, ContractError::LienholderNotAllowed(lienholder.clone()));
+    assert_eq!(
+        vault
+            .claim(user.to_owned(), lienholder.clone())
+            .unwrap()
+            .amount,
+        ValueRange::new_val(Uint128::new(100))
+    );
+    vault
+        .release_cross_stake(user.to_owned(), coin(100, OSMO))
+        .call(&lienholder)
+        .unwrap();
+
+    // Paginated listing
+    vault
+        .add_allowed_lienholder(lienholder.clone())
+        .call(owner)
+        .unwrap();
+    vault
+        .add_allowed_lienholder("another".to_owned())
+        .call(owner)
+        .unwrap();
+    assert_eq!(
+        vault
+            .list_allowed_lienholders(Some("another".to_owned()), None)
+            .unwrap(),
+        AllowedLienholdersResponse {
+            lienholders: vec![lienholder],
+        }
+    );
+}
+
+#[test]
+fn compound_cross_stake() {
+    let owner = "owner";
+    let user = "user1";
+    let validator = "validator";
+
+    let app = init_app(&[user], &[300]);
+    let (vault, _, cross_staking) = setup(&app, owner, SLASHING_PERCENTAGE, 100);
+    set_active_validators(&cross_staking, &[validator]);
+    bond(&vault, user, 300);
+    stake_remotely(&vault, &cross_staking, user, &[validator], &[100]);
+    let lienholder = cross_staking.contract_addr.to_string();
+
+    // Only allowed lienholders compound
+    let err = vault
+        .compound_cross_stake(user.to_owned(), coin(50, OSMO))
+        .call(user)
+        .unwrap_err();
+    assert_eq!(err, ContractError::LienholderNotAllowed(user.to_owned()));
+
+    // The compounded stake is liened right away, out of the free collateral
+    vault
+        .compound_cross_stake(user.to_owned(), coin(150, OSMO))
+        .call(&lienholder)
+        .unwrap();
+    assert_eq!(
+        vault
+            .claim(user.to_owned(), lienholder.clone())
+            .unwrap()
+            .amount,
+        ValueRange::new_val(Uint128::new(250))
+    );
+    let err = vault
+        .compound_cross_stake(user.to_owned(), coin(100, OSMO))
+        .call(&lienholder)
+        .unwrap_err();
+    assert_eq!(err, ContractError::InsufficentBalance);
+}
+
+#[test]
+fn ownership_transfer() {
+    let owner = "owner";
+    let new_owner = "new_owner";
+    let typo = "new_ownr";
+
+    let app = init_app(&[], &[]);
+    let (vault, _, cross_staking) = setup(&app, owner, SLASHING_PERCENTAGE, 100);
+    let lienholder = cross_staking.contract_addr.to_string();
+
+    // The contract admin owns the vault until ownership is transferred
+    assert_eq!(
+        vault.ownership().unwrap(),
+        OwnershipResponse {
+            owner: Some(owner.to_owned()),
+            pending_owner: None,
+            pending_expiry: None,
+        }
+    );
+    let err = vault
+        .transfer_ownership(new_owner.to_owned(), None)
+        .call(new_owner)
+        .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    // A proposal to a wrong address can't be accepted by anyone else, and can be cancelled
+    vault
+        .transfer_ownership(typo.to_owned(), None)
+        .call(owner)
+        .unwrap();
+    let err = vault.accept_ownership().call(new_owner).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    vault.cancel_ownership_transfer().call(owner).unwrap();
+    let err = vault.accept_ownership().call(typo).unwrap_err();
+    assert_eq!(err, ContractError::NoPendingOwnership {});
+
+    // Expired proposals can't be accepted
+    let expiry = Expiration::AtHeight(app.block_info().height + 10);
+    vault
+        .transfer_ownership(new_owner.to_owned(), Some(expiry))
+        .call(owner)
+        .unwrap();
+    assert_eq!(
+        vault.ownership().unwrap(),
+        OwnershipResponse {
+            owner: Some(owner.to_owned()),
+            pending_owner: Some(new_owner.to_owned()),
+            pending_expiry: Some(expiry),
+        }
+    );
+    app.app_mut().update_block(|block| block.height += 10);
+    let err = vault.accept_ownership().call(new_owner).unwrap_err();
+    assert_eq!(err, ContractError::OwnershipTransferExpired {});
+
+    // The owner is only changed once the new owner accepts
+    vault
+        .transfer_ownership(new_owner.to_owned(), None)
+        .call(owner)
+        .unwrap();
+    vault.accept_ownership().call(new_owner).unwrap();
+    assert_eq!(
+        vault.ownership().unwrap(),
+        OwnershipResponse {
+            owner: Some(new_owner.to_owned()),
+            pending_owner: None,
+            pending_expiry: None,
+        }
+    );
+
+    // Privileged actions move to the new owner
+    let err = vault
+        .remove_allowed_lienholder(lienholder.clone())
+        .call(owner)
+        .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    let err = vault
+        .pause(vec![PausableOperation::Bond])
+        .call(owner)
+        .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    let err = vault
+        .update_lienholder_cap(lienholder.clone(), OSMO.to_owned(), Some(Uint128::new(100)))
+        .call(owner)
+        .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    vault
+        .update_lienholder_cap(lienholder.clone(), OSMO.to_owned(), Some(Uint128::new(100)))
+        .call(new_owner)
+        .unwrap();
+    assert_eq!(
+        vault
+            .lienholder_cap(lienholder.clone(), OSMO.to_owned())
+            .unwrap()
+            .cap,
+        Some(Uint128::new(100))
+    );
+    vault
+        .pause(vec![PausableOperation::Bond])
+        .call(new_owner)
+        .unwrap();
+    vault
+        .remove_allowed_lienholder(lienholder)
+        .call(new_owner)
+        .unwrap();
+}
+
+#[test]
+fn auto_renew() {
+    let owner = "owner";
+    let user = "user1";
+    let keeper = "keeper";
+    let val = "validator";
+
+    let mut app = init_app(&[user], &[300]);
+    add_local_validator(&mut app, val);
+    let (vault, local_staking, _) = setup(&app, owner, SLASHING_PERCENTAGE, 100);
+
+    bond(&vault, user, 300);
+    stake_locally(&vault, user, 200, val).unwrap();
+    let proxy = proxy_for_user(&local_staking, user, &app);
+    proxy
+        .unstake(val.to_string(), coin(100, OSMO))
+        .call(user)
+        .unwrap();
+    process_staking_unbondings(&app);
+
+    // Only the owner can release matured unbondings by default
+    assert!(!vault.auto_renew(user.to_owned()).unwrap().enabled);
+    let err = local_staking
+        .release_unbonded_for(user.to_owned())
+        .call(keeper)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        mesh_native_staking::error::ContractError::AutoRenewDisabled(user.to_owned())
+    );
+    proxy.release_unbonded().call(keeper).unwrap_err();
+
+    // With auto-renew, anyone can release them back into free collateral
+    vault.set_auto_renew(true).call(user).unwrap();
+    assert!(vault.auto_renew(user.to_owned()).unwrap().enabled);
+    local_staking
+        .release_unbonded_for(user.to_owned())
+        .call(keeper)
+        .unwrap();
+    assert_eq!(
+        vault.account(user.to_owned()).unwrap(),
+        AccountResponse {
+            denom: OSMO.to_owned(),
+            bonded: Uint128::new(300),
+            free: ValueRange::new_val(Uint128::new(200)),
+        }
+    );
+    assert_eq!(
+        app.app()
+            .wrap()
+            .query_balance(&vault.contract_addr, OSMO)
+            .unwrap(),
+        coin(200, OSMO)
+    );
+
+    // Staking locally again releases them automatically
+    proxy
+        .unstake(val.to_string(), coin(50, OSMO))
+        .call(user)
+        .unwrap();
+    process_staking_unbondings(&app);
+    stake_locally(&vault, user, 20, val).unwrap();
+    assert_eq!(
+        vault.account(user.to_owned()).unwrap(),
+        AccountResponse {
+            denom: OSMO.to_owned(),
+            bonded: Uint128::new(300),
+            free: ValueRange::new_val(Uint128::new(230)),
+        }
+    );
+    assert_eq!(
+        app.app()
+            .wrap()
+            .query_balance(&proxy.contract_addr, OSMO)
+            .unwrap(),
+        coin(0, OSMO)
+    );
+
+    // Opting out restores the default
+    vault.set_auto_renew(false).call(user).unwrap();
+    proxy
+        .unstake(val.to_string(), coin(50, OSMO))
+        .call(user)
+        .unwrap();
+    process_staking_unbondings(&app);
+    let err = local_staking
+        .release_unbonded_for(user.to_owned())
+        .call(keeper)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        mesh_native_staking::error::ContractError::AutoRenewDisabled(user.to_owned())
+    );
+}
+
+#[test]
+fn transfer_collateral() {
+    let owner = "owner";
+    let user1 = "user1";
+    let user2 = "user2";
+    let validator = "validator";
+
+    let app = init_app(&[user1], &[300]);
+    let (vault, _, cross_staking) = setup(&app, owner, SLASHING_PERCENTAGE, 100);
+    set_active_validators(&cross_staking, &[validator]);
+    bond(&vault, user1, 300);
+    stake_remotely(&vault, &cross_staking, user1, &[validator], &[200]);
+
+    // Only free collateral can be transferred
+    let err = vault
+        .transfer_collateral(user2.to_owned(), coin(101, OSMO))
+        .call(user1)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::ClaimsLocked(ValueRange::new_val(Uint128::new(100)))
+    );
+    let err = vault
+        .transfer_collateral(user2.to_owned(), coin(100, STAR))
+        .call(user1)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::UnsupportedCollateralDenom(STAR.to_owned())
+    );
+
+    vault
+        .transfer_collateral(user2.to_owned(), coin(80, OSMO))
+        .call(user1)
+        .unwrap();
+    assert_eq!(
+        vault.account(user1.to_owned()).unwrap(),
+        AccountResponse {
+            denom: OSMO.to_owned(),
+            bonded: Uint128::new(220),
+            free: ValueRange::new_val(Uint128::new(20)),
+        }
+    );
+    assert_eq!(
+        vault.account(user2.to_owned()).unwrap(),
+        AccountResponse {
+            denom: OSMO.to_owned(),
+            bonded: Uint128::new(80),
+            free: ValueRange::new_val(Uint128::new(80)),
+        }
+    );
+
+    // The recipient can use it as any bonded collateral, without the tokens moving
+    stake_remotely(&vault, &cross_staking, user2, &[validator], &[50]);
+    vault.unbond(coin(30, OSMO)).call(user2).unwrap();
+    assert_eq!(
+        app.app().wrap().query_balance(user2, OSMO).unwrap(),
+        coin(30, OSMO)
+    );
+    assert_eq!(
+        vault.account(user2.to_owned()).unwrap(),
+        AccountResponse {
+            denom: OSMO.to_owned(),
+            bonded: Uint128::new(50),
+            free: ValueRange::new_val(Uint128::zero()),
+        }
+    );
+}
+
+#[test]
+fn transfer_lien() {
+    let owner = "owner";
+    let user = "user1";
+    let validator = "validator";
+
+    let app = init_app(&[user], &[300]);
+    let (vault, local_staking, cross_staking) = setup(&app, owner, SLASHING_PERCENTAGE, 100);
+    set_active_validators(&cross_staking, &[validator]);
+    bond(&vault, user, 300);
+    stake_remotely(&vault, &cross_staking, user, &[validator], &[200]);
+
+    let from = cross_staking.contract_addr.to_string();
+    let to = local_staking.contract_addr.to_string();
+    let transfer = |amount: u128, sender: &str| {
+        vault
+            .transfer_lien(
+                user.to_owned(),
+                from.clone(),
+                to.clone(),
+                coin(amount, OSMO),
+            )
+            .call(sender)
+    };
+    let claims = || {
+        vault
+            .account_claims(user.to_owned(), None, None)
+            .unwrap()
+            .claims
+    };
+
+    // Only the lienholders involved can transfer the lien
+    let err = transfer(100, user).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    // A single lienholder can only propose it
+    transfer(100, &from).unwrap();
+    transfer(100, &from).unwrap();
+    assert_eq!(
+        claims(),
+        [LienResponse {
+            lienholder: from.clone(),
+            denom: OSMO.to_owned(),
+            amount: ValueRange::new_val(Uint128::new(200)),
+        }]
+    );
+
+    // A different amount is a new proposal
+    transfer(150, &to).unwrap();
+    assert_eq!(claims().len(), 1);
+
+    // Both lienholders agreeing moves the lien
+    transfer(150, &from).unwrap();
+    assert_eq!(
+        claims(),
+        [
+            LienResponse {
+                lienholder: from.clone(),
+                denom: OSMO.to_owned(),
+                amount: ValueRange::new_val(Uint128::new(50)),
+            },
+            LienResponse {
+                lienholder: to.clone(),
+                denom: OSMO.to_owned(),
+                amount: ValueRange::new_val(Uint128::new(150)),
+            },
+        ]
+    );
+    assert_eq!(
+        vault.account(user.to_owned()).unwrap(),
+        AccountResponse {
+            denom: OSMO.to_owned(),
+            bonded: Uint128::new(300),
+            free: ValueRange::new_val(Uint128::new(150)),
+        }
+    );
+
+    // The lien can't be moved to an arbitrary contract
+    let err = vault
+        .transfer_lien(
+            user.to_owned(),
+            from.clone(),
+            owner.to_owned(),
+            coin(50, OSMO),
+        )
+        .call(&from)
+        .unwrap_err();
+    assert_eq!(err, ContractError::LienholderNotAllowed(owner.to_owned()));
+}
+
+#[test]
+fn defunct_lienholder() {
+    let owner = "owner";
+    let user = "user1";
+    let keeper = "keeper";
+    let validator = "validator";
+
+    let app = init_app(&[user], &[300]);
+    let (vault, local_staking, cross_staking) = setup(&app, owner, SLASHING_PERCENTAGE, 100);
+    set_active_validators(&cross_staking, &[validator]);
+    bond(&vault, user, 300);
+    stake_remotely(&vault, &cross_staking, user, &[validator], &[100]);
+    // A stake the lienholder will never commit
+    vault
+        .stake_remote(
+            cross_staking.contract_addr.to_string(),
+            coin(50, OSMO),
+            to_json_binary(&ReceiveVirtualStake {
+                validator: validator.to_string(),
+            })
+            .unwrap(),
+            None,
+        )
+        .call(user)
+        .unwrap();
+    let lienholder = cross_staking.contract_addr.to_string();
+
+    // Liens can only be force-released for defunct lienholders
+    let err = vault
+        .release_defunct_lien(user.to_owned(), lienholder.clone())
+        .call(keeper)
+        .unwrap_err();
+    assert_eq!(err, ContractError::LienholderNotDefunct(lienholder.clone()));
+    let err = vault
+        .mark_lienholder_defunct(local_staking.contract_addr.to_string(), 0)
+        .unwrap_err();
+    assert_eq!(err, ContractError::LocalStakingDefunct);
+
+    vault
+        .mark_lienholder_defunct(lienholder.clone(), 3600)
+        .unwrap();
+    let marked_at = app.block_info().time;
+    assert_eq!(
+        vault.defunct_lienholders().unwrap().lienholders,
+        [DefunctLienholderResponse {
+            lienholder: lienholder.clone(),
+            marked_at,
+            release_after: marked_at.plus_seconds(3600),
+        }]
+    );
+    // No new stakes are accepted
+    let err = vault
+        .stake_remote(
+            lienholder.clone(),
+            coin(10, OSMO),
+            to_json_binary(&ReceiveVirtualStake {
+                validator: validator.to_string(),
+            })
+            .unwrap(),
+            None,
+        )
+        .call(user)
+        .unwrap_err();
+    assert_eq!(err, ContractError::LienholderNotAllowed(lienholder.clone()));
+
+    // Not before the grace period is over
+    let err = vault
+        .release_defunct_lien(user.to_owned(), lienholder.clone())
+        .call(keeper)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::DefunctGracePeriod(marked_at.plus_seconds(3600))
+    );
+
+    skip_time(&app, 3600);
+    vault
+        .release_defunct_lien(user.to_owned(), lienholder.clone())
+        .call(keeper)
+        .unwrap();
+    assert!(vault
+        .account_claims(user.to_owned(), None, None)
+        .unwrap()
+        .claims
+        .is_empty());
+    assert_eq!(get_last_vault_pending_tx_id(&vault), None);
+    assert_eq!(
+        vault.account(user.to_owned()).unwrap(),
+        AccountResponse {
+            denom: OSMO.to_owned(),
+            bonded: Uint128::new(300),
+            free: ValueRange::new_val(Uint128::new(300)),
+        }
+    );
+
+    // Nothing left to release
+    let err = vault
+        .release_defunct_lien(user.to_owned(), lienholder.clone())
+        .call(keeper)
+        .unwrap_err();
+    assert_eq!(err, ContractError::UnknownLienholder);
+
+    vault.unmark_lienholder_defunct(lienholder.clone()).unwrap();
+    assert!(vault.defunct_lienholders().unwrap().lienholders.is_empty());
+}
+
+#[test]
+fn unbond_requests() {
+    let owner = "owner";
+    let user = "user1";
+    let validator = "validator";
+
+    let app = init_app(&[user], &[300]);
+    let (vault, _, cross_staking) = setup(&app, owner, SLASHING_PERCENTAGE, 100);
+    set_active_validators(&cross_staking, &[validator]);
+    bond(&vault, user, 300);
+    stake_remotely(&vault, &cross_staking, user, &[validator], &[250]);
+    let lienholder = cross_staking.contract_addr.to_string();
+
+    // Locked collateral can't be unbonded right away
+    let err = vault.unbond(coin(100, OSMO)).call(user).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::ClaimsLocked(ValueRange::new_val(Uint128::new(50)))
+    );
+
+    // Requests are fulfilled with the free collateral right away
+    vault.request_unbond(coin(100, OSMO)).call(user).unwrap();
+    vault.request_unbond(coin(80, OSMO)).call(user).unwrap();
+    assert_eq!(
+        vault.pending_unbonds(user.to_owned()).unwrap(),
+        PendingUnbondsResponse {
+            requests: vec![
+                UnbondRequestResponse {
+                    id: 1,
+                    denom: OSMO.to_owned(),
+                    amount: Uint128::new(50),
+                },
+                UnbondRequestResponse {
+                    id: 2,
+                    denom: OSMO.to_owned(),
+                    amount: Uint128::new(80),
+                },
+            ],
+            claimable: coins(50, OSMO),
+        }
+    );
+    assert_eq!(
+        vault.account(user.to_owned()).unwrap(),
+        AccountResponse {
+            denom: OSMO.to_owned(),
+            bonded: Uint128::new(250),
+            free: ValueRange::new_val(Uint128::zero()),
+        }
+    );
+
+    // Collateral can't be requested twice
+    let err = vault
+        .request_unbond(coin(121, OSMO))
+        .call(user)
+        .unwrap_err();
+    assert_eq!(err, ContractError::InsufficentBalance);
+    let err = vault.request_unbond(coin(0, OSMO)).call(user).unwrap_err();
+    assert_eq!(err, ContractError::ZeroUnbondRequest);
+
+    // Released liens fulfil the requests in order, partially if needed
+    vault
+        .release_cross_stake(user.to_owned(), coin(70, OSMO))
+        .call(&lienholder)
+        .unwrap();
+    assert_eq!(
+        vault.pending_unbonds(user.to_owned()).unwrap(),
+        PendingUnbondsResponse {
+            requests: vec![UnbondRequestResponse {
+                id: 2,
+                denom: OSMO.to_owned(),
+                amount: Uint128::new(60),
+            }],
+            claimable: coins(120, OSMO),
+        }
+    );
+
+    // Claiming withdraws all the unbonded collateral
+    vault.claim_unbonded(OSMO.to_owned()).call(user).unwrap();
+    assert_eq!(
+        app.app().wrap().query_balance(user, OSMO).unwrap(),
+        coin(120, OSMO)
+    );
+    let err = vault
+        .claim_unbonded(OSMO.to_owned())
+        .call(user)
+        .unwrap_err();
+    assert_eq!(err, ContractError::NoClaim);
+
+    vault
+        .release_cross_stake(user.to_owned(), coin(100, OSMO))
+        .call(&lienholder)
+        .unwrap();
+    assert_eq!(
+        vault.pending_unbonds(user.to_owned()).unwrap(),
+        PendingUnbondsResponse {
+            requests: vec![],
+            claimable: coins(60, OSMO),
+        }
+    );
+    assert_eq!(
+        vault.account(user.to_owned()).unwrap(),
+        AccountResponse {
+            denom: OSMO.to_owned(),
+            bonded: Uint128::new(120),
+            free: ValueRange::new_val(Uint128::new(40)),
+        }
+    );
+}
+
+#[test]
+fn cross_slash_scenario_1() {
+    let owner = "owner";
+    let user = "user1";
+    // Remote slashing percentage
+    let slashing_percentage = 10;
+    let collateral = 200;
+    let local_validator = "local";
+    let validators = vec!["validator1", "validator2"];
+    let validator1 = validators[0];
+    let validator2 = validators[1];
+
+    let mut app = init_app(&[user], &[1000]);
+    add_local_validator(&mut app, local_validator);
+
+    let (vault, local_staking, cross_staking) = setup(&app, owner, slashing_percentage, 100);
+
+    let (_update_valset_height, _update_valset_time) =
+        set_active_validators(&cross_staking, &validators);
+
+    // Bond some collateral
+    bond(&vault, user, collateral);
+
+    // Stake some tokens locally
+    let local_stake = 190;
+    stake_locally(&vault, user, local_stake, local_validator).unwrap();
+
+    // Stake some tokens remotely
+    stake_remotely(&vault, &cross_staking, user, &validators, &[100, 50]);
+
+    let acc = vault.account(user.to_owned()).unwrap();
+    assert_eq!(
+        acc,
+        AccountResponse {
+            denom: OSMO.to_owned(),
+            bonded: Uint128::new(collateral),
+            free: ValueRange::new_val(Uint128::new(10)),
+        }
+    );
+    let claims = vault.account_claims(user.to_owned(), None, None).unwrap();
+    assert_eq!(
+        claims.claims,
+        [
+            LienResponse {
+                lienholder: local_staking.contract_addr.to_string(),
+                denom: OSMO.to_owned(),
+                amount: ValueRange::new_val(Uint128::new(local_stake))
+            },
+            LienResponse {
+                lienholder: cross_staking.contract_addr.to_string(),
+                denom: OSMO.to_owned(),
+                amount: ValueRange::new_val(Uint128::new(150))
+            },
+        ]
+    );
+
+    let acc_details = vault.account_details(user.to_owned()).unwrap();
+    // Max lien
+    assert_eq!(acc_details.max_lien, ValueRange::new_val(Uint128::new(190)));
+    // Total slashable
+    assert_eq!(
+        acc_details.total_slashable,
+        ValueRange::new_val(Uint128::new(34))
+    );
+    // Collateral
+    assert_eq!(acc_details.bonded, Uint128::new(collateral));
+    // Free collateral
+    assert_eq!(acc_details.free, ValueRange::new_val(Uint128::new(10)));
+
+    // Cross stakes
+    let cross_stake1 = cross_staking
+        .stake(user.to_string(), validator1.to_string())
+        .unwrap();
+    assert_eq!(cross_stake1.stake, ValueRange::new_val(Uint128::new(100)));
+    let cross_stake2 = cross_staking
+        .stake(user.to_string(), validator2.to_string())
+        .unwrap();
+    assert_eq!(cross_stake2.stake, ValueRange::new_val(Uint128::new(50)));
+
+    // Validator 1 is slashed
+    cross_staking
+        .test_handle_slashing(validator1.to_string(), Uint128::new(10))
+        .call("test")
+        .unwrap();
+
+    // Liens
+    let claims = vault.account_claims(user.to_owned(), None, None).unwrap();
+    assert_eq!(
+        claims.claims,
+        [
+            LienResponse {
+                lienholder: local_staking.contract_addr.to_string(),
+                denom: OSMO.to_owned(),
+                amount: ValueRange::new_val(Uint128::new(local_stake))
+            },
+            LienResponse {
+                lienholder: cross_staking.contract_addr.to_string(),
+                denom: OSMO.to_owned(),
+                amount: ValueRange::new_val(Uint128::new(140))
+            },
+        ]
+    );
+
+    let acc_details = vault.account_details(user.to_owned()).unwrap();
+    // Max lien
+    assert_eq!(acc_details.max_lien, ValueRange::new_val(Uint128::new(190)));
+    // Total slashable
+    assert_eq!(
+        acc_details.total_slashable,
+        ValueRange::new_val(Uint128::new(33))
+    );
+    // Collateral
+    assert_eq!(acc_details.bonded, Uint128::new(190));
+    // Free collateral
+    assert_eq!(acc_details.free, ValueRange::new_val(Uint128::zero()));
+
+    // Cross stake
+    let cross_stake1 = cross_staking
+        .stake(user.to_string(), validator1.to_string())
+        .unwrap();
+    assert_eq!(cross_stake1.stake, ValueRange::new_val(Uint128::new(90))); // 10% slashed
+    let cross_stake2 = cross_staking
+        .stake(user.to_string(), validator2.to_string())
+        .unwrap();
+    assert_eq!(cross_stake2.stake, ValueRange::new_val(Uint128::new(50))); // no slashing
+}
+
+/// Scenario 2:
+/// https://github.com/osmosis-labs/mesh-security/blob/main/docs/ibc/Slashing.md#scenario-2-slashed-delegator-has-no-free-collateral-on-the-vault
+#[test]
+fn cross_slash_scenario_2() {
+    let owner = "owner";
+    let user = "user1";
+    // Remote slashing percentage
+    let slashing_percentage = 10;
+    let collateral = 200;
+    let local_validator = "local";
+    let validators = vec!["validator1", "validator2"];
+    let validator1 = validators[0];
+
+    let mut app = init_app(&[user], &[1000]);
+    add_local_validator(&mut app, local_validator);
+
+    let (vault, local_staking, cross_staking) = setup(&app, owner, slashing_percentage, 100);
+
+    let (_update_valset_height, _update_valset_time) =
+        set_active_validators(&cross_staking, &validators);
+
+    // Bond some collateral
+    bond(&vault, user, collateral);
+
+    // Stake some tokens locally
+    let local_stake = 200;
+    stake_locally(&vault, user, local_stake, local_validator).unwrap();
+
+    // Stake some tokens remotely
+    stake_remotely(&vault, &cross_staking, user, &[validator1], &[200]);
+
+    let claims = vault.account_claims(user.to_owned(), None, None).unwrap();
+    assert_eq!(
+        claims.claims,
+        [
+            LienResponse {
+                lienholder: local_staking.contract_addr.to_string(),
+                denom: OSMO.to_owned(),
+                amount: ValueRange::new_val(Uint128::new(200))
+            },
+            LienResponse {
+                lienholder: cross_staking.contract_addr.to_string(),
+                denom: OSMO.to_owned(),
+                amount: ValueRange::new_val(Uint128::new(200))
+            },
+        ]
+    );
+
+    let acc_details = vault.account_details(user.to_owned()).unwrap();
+    // Max lien
+    assert_eq!(acc_details.max_lien, ValueRange::new_val(Uint128::new(200)));
+    // Total slashable
+    assert_eq!(
+        acc_details.total_slashable,
+        ValueRange::new_val(Uint128::new(40))
+    );
+    // Collateral
+    assert_eq!(acc_details.bonded, Uint128::new(collateral));
+    // Free collateral
+    assert_eq!(acc_details.free, ValueRange::new_val(Uint128::zero()));
+
+    // Cross stakes
+    let cross_stake1 = cross_staking
+        .stake(user.to_string(), validator1.to_string())
+        .unwrap();
+    assert_eq!(cross_stake1.stake, ValueRange::new_val(Uint128::new(200)));
+
+    // Validator 1 is slashed
+    cross_staking
+        .test_handle_slashing(validator1.to_string(), Uint128::new(20))
+        .call("test")
+        .unwrap();
+
+    // Liens
+    let claims = vault.account_claims(user.to_owned(), None, None).unwrap();
+    assert_eq!(
+        claims.claims,
+        [
+            LienResponse {
+                lienholder: local_staking.contract_addr.to_string(),
+                denom: OSMO.to_owned(),
+                amount: ValueRange::new_val(Uint128::new(180))
+            },
+            LienResponse {
+                lienholder: cross_staking.contract_addr.to_string(),
+                denom: OSMO.to_owned(),
+                amount: ValueRange::new_val(Uint128::new(180))
+            },
+        ]
+    );
+
+    let acc_details = vault.account_details(user.to_owned()).unwrap();
+    // Max lien
+    assert_eq!(acc_details.max_lien, ValueRange::new_val(Uint128::new(180)));
+    // Total slashable
+    assert_eq!(
+        acc_details.total_slashable,
+        ValueRange::new_val(Uint128::new(36))
+    );
+    // Collateral
+    assert_eq!(acc_details.bonded, Uint128::new(180));
+    // Free collateral
+    assert_eq!(acc_details.free, ValueRange::new_val(Uint128::zero()));
+
+    // Cross stakes
+    let cross_stake1 = cross_staking
+        .stake(user.to_string(), validator1.to_string())
+        .unwrap();
+    assert_eq!(cross_stake1.stake, ValueRange::new_val(Uint128::new(180))); // 10% slashed
+}
+
+/// Scenario 3:
+/// https://github.com/osmosis-labs/mesh-security/blob/main/docs/ibc/Slashing.md#scenario-3-slashed-delegator-has-some-free-collateral-on-the-vault
+#[test]
+fn cross_slash_scenario_3() {
+    let owner = "owner";
+    let user = "user1";
+    // Remote slashing percentage
+    let slashing_percentage = 10;
+    let collateral = 200;
+    let local_validator = "local";
+    let validators = vec!["validator1", "validator2"];
+    let validator1 = validators[0];
+
+    let mut app = init_app(&[user], &[1000]);
+    add_local_validator(&mut app, local_validator);
+
+    let (vault, local_staking, cross_staking) = setup(&app, owner, slashing_percentage, 100);
+
+    let (_update_valset_height, _update_valset_time) =
+        set_active_validators(&cross_staking, &validators);
+
+    // Bond some collateral
+    bond(&vault, user, collateral);
+
+    // Stake some tokens locally
+    let local_stake = 190;
+    stake_locally(&vault, user, local_stake, local_validator).unwrap();
+
+    // Stake some tokens remotely
+    stake_remotely(&vault, &cross_staking, user, &[validator1], &[150]);
+
+    let claims = vault.account_claims(user.to_owned(), None, None).unwrap();
+    assert_eq!(
+        claims.claims,
+        [
+            LienResponse {
+                lienholder: local_staking.contract_addr.to_string(),
+                denom: OSMO.to_owned(),
+                amount: ValueRange::new_val(Uint128::new(190))
+            },
+            LienResponse {
+                lienholder: cross_staking.contract_addr.to_string(),
+                denom: OSMO.to_owned(),
+                amount: ValueRange::new_val(Uint128::new(150))
+            },
+        ]
+    );
+
+    let acc_details = vault.account_details(user.to_owned()).unwrap();
+    // Max lien
+    assert_eq!(acc_details.max_lien, ValueRange::new_val(Uint128::new(190)));
+    // Total slashable
+    assert_eq!(
+        acc_details.total_slashable,
+        ValueRange::new_val(Uint128::new(34))
+    );
+    // Collateral
+    assert_eq!(acc_details.bonded, Uint128::new(200));
+    // Free collateral
+    assert_eq!(acc_details.free, ValueRange::new_val(Uint128::new(10)));
+
+    // Cross stakes
+    let cross_stake1 = cross_staking
+        .stake(user.to_string(), validator1.to_string())
+        .unwrap();
+    assert_eq!(cross_stake1.stake, ValueRange::new_val(Uint128::new(150)));
+
+    // Validator 1 is slashed
+    cross_staking
+        .test_handle_slashing(validator1.to_string(), Uint128::new(15))
+        .call("test")
+        .unwrap();
+
+    // Liens
+    let claims = vault.account_claims(user.to_owned(), None, None).unwrap();
+    assert_eq!(
+        claims.claims,
+        [
+            LienResponse {
+                lienholder: local_staking.contract_addr.to_string(),
+                denom: OSMO.to_owned(),
+                amount: ValueRange::new_val(Uint128::new(185))
+            },
+            LienResponse {
+                lienholder: cross_staking.contract_addr.to_string(),
+                denom: OSMO.to_owned(),
+                amount: ValueRange::new_val(Uint128::new(135))
+            },
+        ]
+    );
+
+    let acc_details = vault.account_details(user.to_owned()).unwrap();
+    // Max lien
+    assert_eq!(acc_details.max_lien, ValueRange::new_val(Uint128::new(185)));
+    // Total slashable
+    assert_eq!(
+        acc_details.total_slashable,
+        ValueRange::new_val(Uint128::new(32 + 1)) // Because of rounding / truncation
+    );
+    // Collateral
+    assert_eq!(acc_details.bonded, Uint128::new(185));
+    // Free collateral
+    assert_eq!(acc_details.free, ValueRange::new_val(Uint128::zero()));
+
+    // Cross stakes
+    let cross_stake1 = cross_staking
+        .stake(user.to_string(), validator1.to_string())
+        .unwrap();
+    assert_eq!(cross_stake1.stake, ValueRange::new_val(Uint128::new(135))); // 10% slashed
+}
+
+/// Scenario 4:
+/// https://github.com/osmosis-labs/mesh-security/blob/main/docs/ibc/Slashing.md#scenario-4-same-as-scenario-3-but-with-more-delegations
+#[test]
+fn cross_slash_scenario_4() {
+    let owner = "owner";
+    let user = "user1";
+    // Remote slashing percentage
+    let slashing_percentage = 10;
+    let local_validator = "local";
+    let collateral = 200;
+    let validators_1 = vec!["validator1", "validator2"];
+    let validators_2 = vec!["validator3", "validator4"];
+    let validator1 = validators_1[0];
+
+    let mut app = init_app(&[user], &[1000]);
+    add_local_validator(&mut app, local_validator);
+
+    let (vault, local_staking, cross_staking_1) = setup(&app, owner, slashing_percentage, 100);
+    let cross_staking_2 = setup_cross_stake(&app, owner, &vault, slashing_percentage, 100);
+
+    let (_, _) = set_active_validators(&cross_staking_1, &validators_1);
+    let (_update_valset_height, _update_valset_time) =
+        set_active_validators(&cross_staking_2, &validators_2);
+
+    // Bond some collateral
+    bond(&vault, user, collateral);
+
+    // Stake some tokens locally
+    let local_stake = 190;
+    stake_locally(&vault, user, local_stake, local_validator).unwrap();
+
+    // Stake some tokens remotely
+    stake_remotely(&vault, &cross_staking_1, user, &validators_1, &[140, 40]);
+    stake_remotely(&vault, &cross_staking_2, user, &validators_2, &[100, 88]);
+
+    let claims = vault.account_claims(user.to_owned(), None, None).unwrap();
     assert_eq!(
         claims.claims,
         [
             LienResponse {
                 lienholder: local_staking.contract_addr.to_string(),
+                denom: OSMO.to_owned(),
                 amount: ValueRange::new_val(Uint128::new(190))
             },
             LienResponse {
                 lienholder: cross_staking_1.contract_addr.to_string(),
+                denom: OSMO.to_owned(),
                 amount: ValueRange::new_val(Uint128::new(180))
             },
             LienResponse {
                 lienholder: cross_staking_2.contract_addr.to_string(),
+                denom: OSMO.to_owned(),
                 amount: ValueRange::new_val(Uint128::new(188))
             },
         ]
@@ -2118,14 +4544,17 @@ fn cross_slash_scenario_4() {
         [
             LienResponse {
                 lienholder: local_staking.contract_addr.to_string(),
+                denom: OSMO.to_owned(),
                 amount: ValueRange::new_val(Uint128::new(186))
             },
             LienResponse {
                 lienholder: cross_staking_1.contract_addr.to_string(),
+                denom: OSMO.to_owned(),
                 amount: ValueRange::new_val(Uint128::new(166))
             },
             LienResponse {
                 lienholder: cross_staking_2.contract_addr.to_string(),
+                denom: OSMO.to_owned(),
                 amount: ValueRange::new_val(Uint128::new(186))
             },
         ]
@@ -2214,18 +4643,22 @@ fn cross_slash_scenario_5() {
         [
             LienResponse {
                 lienholder: local_staking.contract_addr.to_string(),
+                denom: OSMO.to_owned(),
                 amount: ValueRange::new_val(Uint128::new(100))
             },
             LienResponse {
                 lienholder: cross_staking_1.contract_addr.to_string(),
+                denom: OSMO.to_owned(),
                 amount: ValueRange::new_val(Uint128::new(180))
             },
             LienResponse {
                 lienholder: cross_staking_2.contract_addr.to_string(),
+                denom: OSMO.to_owned(),
                 amount: ValueRange::new_val(Uint128::new(80))
             },
             LienResponse {
                 lienholder: cross_staking_3.contract_addr.to_string(),
+                denom: OSMO.to_owned(),
                 amount: ValueRange::new_val(Uint128::new(100))
             },
         ]
@@ -2297,18 +4730,22 @@ fn cross_slash_scenario_5() {
         [
             LienResponse {
                 lienholder: local_staking.contract_addr.to_string(),
+                denom: OSMO.to_owned(),
                 amount: ValueRange::new_val(Uint128::new(78)) // Rounded down
             },
             LienResponse {
                 lienholder: cross_staking_1.contract_addr.to_string(),
+                denom: OSMO.to_owned(),
                 amount: ValueRange::new_val(Uint128::new(68)) // Rounded down
             },
             LienResponse {
                 lienholder: cross_staking_2.contract_addr.to_string(),
+                denom: OSMO.to_owned(),
                 amount: ValueRange::new_val(Uint128::new(58)) // Rounded down
             },
             LienResponse {
                 lienholder: cross_staking_3.contract_addr.to_string(),
+                denom: OSMO.to_owned(),
                 amount: ValueRange::new_val(Uint128::new(78)) // Rounded down
             },
         ]
@@ -2366,6 +4803,94 @@ fn cross_slash_scenario_5() {
     );
 }
 
+/// Scenario 5 under every slash dust policy: propagating the slash rounds every lien up by 1,
+/// which is burned, donated or left to the user
+#[test]
+fn slash_dust_policy() {
+    let owner = "owner";
+    let user = "user1";
+    let treasury = "treasury";
+    let slashing_percentage = 50;
+    let local_validator = "local";
+    let validators = ["validator1", "validator2", "validator3"];
+
+    for (policy, liens, bonded) in [
+        (SlashDustPolicy::Burn, [78, 68, 58, 78], 110),
+        (
+            SlashDustPolicy::Donate {
+                recipient: Addr::unchecked(treasury),
+            },
+            [79, 69, 59, 79],
+            106,
+        ),
+        (SlashDustPolicy::Ignore, [79, 69, 59, 79], 110),
+    ] {
+        let mut app = init_app(&[user], &[1000]);
+        add_local_validator(&mut app, local_validator);
+
+        let (vault, _, cross_staking_1) = setup(&app, owner, slashing_percentage, 100);
+        let cross_staking_2 = setup_cross_stake(&app, owner, &vault, slashing_percentage, 100);
+        let cross_staking_3 = setup_cross_stake(&app, owner, &vault, slashing_percentage, 100);
+        set_active_validators(&cross_staking_1, &[validators[0]]);
+        set_active_validators(&cross_staking_2, &[validators[1]]);
+        set_active_validators(&cross_staking_3, &[validators[2]]);
+
+        vault.set_slash_dust_policy(policy.clone()).unwrap();
+
+        bond(&vault, user, 200);
+        stake_locally(&vault, user, 100, local_validator).unwrap();
+        stake_remotely(&vault, &cross_staking_1, user, &[validators[0]], &[180]);
+        stake_remotely(&vault, &cross_staking_2, user, &[validators[1]], &[80]);
+        stake_remotely(&vault, &cross_staking_3, user, &[validators[2]], &[100]);
+
+        cross_staking_1
+            .test_handle_slashing(
+                validators[0].to_string(),
+                Uint128::new(180) * Decimal::percent(slashing_percentage),
+            )
+            .call("test")
+            .unwrap();
+
+        let claims = vault.account_claims(user.to_owned(), None, None).unwrap();
+        let amounts: Vec<_> = claims
+            .claims
+            .iter()
+            .map(|claim| claim.amount.high().u128())
+            .collect();
+        assert_eq!(amounts, liens);
+        let acc_details = vault.account_details(user.to_owned()).unwrap();
+        assert_eq!(acc_details.bonded, Uint128::new(bonded));
+
+        // 4 liens, rounded up by 1 each
+        let dust = Uint128::new(4);
+        let (burned, donated, ignored) = match policy {
+            SlashDustPolicy::Burn => (dust, Uint128::zero(), Uint128::zero()),
+            SlashDustPolicy::Donate { .. } => (Uint128::zero(), dust, Uint128::zero()),
+            SlashDustPolicy::Ignore => (Uint128::zero(), Uint128::zero(), dust),
+        };
+        assert_eq!(
+            vault.slash_dust().unwrap(),
+            SlashDustResponse {
+                policy: policy.clone(),
+                dust: vec![DenomSlashDust {
+                    denom: OSMO.to_owned(),
+                    burned,
+                    donated,
+                    ignored,
+                }],
+            }
+        );
+        assert_eq!(
+            app.app()
+                .wrap()
+                .query_balance(treasury, OSMO)
+                .unwrap()
+                .amount,
+            donated
+        );
+    }
+}
+
 /// Scenario 6:
 /// Same as scenario 4 but with zero native staking
 #[test]
@@ -2401,10 +4926,12 @@ fn cross_slash_no_native_staking() {
         [
             LienResponse {
                 lienholder: cross_staking_1.contract_addr.to_string(),
+                denom: OSMO.to_owned(),
                 amount: ValueRange::new_val(Uint128::new(180))
             },
             LienResponse {
                 lienholder: cross_staking_2.contract_addr.to_string(),
+                denom: OSMO.to_owned(),
                 amount: ValueRange::new_val(Uint128::new(188))
             },
         ]
@@ -2436,10 +4963,12 @@ fn cross_slash_no_native_staking() {
         [
             LienResponse {
                 lienholder: cross_staking_1.contract_addr.to_string(),
+                denom: OSMO.to_owned(),
                 amount: ValueRange::new_val(Uint128::new(166))
             },
             LienResponse {
                 lienholder: cross_staking_2.contract_addr.to_string(),
+                denom: OSMO.to_owned(),
                 amount: ValueRange::new_val(Uint128::new(186))
             },
         ]
@@ -2459,20 +4988,169 @@ fn cross_slash_no_native_staking() {
     assert_eq!(acc_details.free, ValueRange::new_val(Uint128::zero()));
 }
 
-/// Checks that the slashing applies to unbonding amounts as well.
+/// Checks that the slashing applies to unbonding amounts as well.
+#[test]
+fn cross_slash_pending_unbonding() {
+    let owner = "owner";
+    let user = "user1";
+    // Remote slashing percentage
+    let slashing_percentage = 10;
+    let collateral = 200;
+    let validators = vec!["validator1", "validator2"];
+    let validator1 = validators[0];
+    let validator2 = validators[1];
+    let local_validator = "local";
+
+    let mut app = init_app(&[user], &[collateral]);
+    add_local_validator(&mut app, local_validator);
+
+    let (vault, local_staking, cross_staking) = setup(&app, owner, slashing_percentage, 100);
+
+    let (_update_valset_height, _update_valset_time) =
+        set_active_validators(&cross_staking, &validators);
+
+    // Bond some collateral
+    bond(&vault, user, collateral);
+
+    // Stake some tokens locally
+    let local_stake = 190;
+    stake_locally(&vault, user, local_stake, local_validator).unwrap();
+
+    // Stake some tokens remotely
+    stake_remotely(&vault, &cross_staking, user, &validators, &[100, 50]);
+
+    let acc = vault.account(user.to_owned()).unwrap();
+    assert_eq!(
+        acc,
+        AccountResponse {
+            denom: OSMO.to_owned(),
+            bonded: Uint128::new(collateral),
+            free: ValueRange::new_val(Uint128::new(10)),
+        }
+    );
+    let claims = vault.account_claims(user.to_owned(), None, None).unwrap();
+    assert_eq!(
+        claims.claims,
+        [
+            LienResponse {
+                lienholder: local_staking.contract_addr.to_string(),
+                denom: OSMO.to_owned(),
+                amount: ValueRange::new_val(Uint128::new(local_stake))
+            },
+            LienResponse {
+                lienholder: cross_staking.contract_addr.to_string(),
+                denom: OSMO.to_owned(),
+                amount: ValueRange::new_val(Uint128::new(150))
+            },
+        ]
+    );
+
+    let acc_details = vault.account_details(user.to_owned()).unwrap();
+    // Max lien
+    assert_eq!(acc_details.max_lien, ValueRange::new_val(Uint128::new(190)));
+    // Total slashable
+    assert_eq!(
+        acc_details.total_slashable,
+        ValueRange::new_val(Uint128::new(34))
+    );
+    // Collateral
+    assert_eq!(acc_details.bonded, Uint128::new(collateral));
+    // Free collateral
+    assert_eq!(acc_details.free, ValueRange::new_val(Uint128::new(10)));
+
+    // Cross stakes
+    let cross_stake1 = cross_staking
+        .stake(user.to_string(), validator1.to_string())
+        .unwrap();
+    assert_eq!(cross_stake1.stake, ValueRange::new_val(Uint128::new(100)));
+    let cross_stake2 = cross_staking
+        .stake(user.to_string(), validator2.to_string())
+        .unwrap();
+    assert_eq!(cross_stake2.stake, ValueRange::new_val(Uint128::new(50)));
+
+    // Unbond half the stake of validator1
+    cross_staking
+        .unstake(validator1.to_owned(), coin(50, OSMO))
+        .call(user)
+        .unwrap();
+    cross_staking
+        .test_commit_unstake(get_last_external_staking_pending_tx_id(&cross_staking).unwrap())
+        .call("test")
+        .unwrap();
+    // Cross stakes amount
+    let cross_stake1 = cross_staking
+        .stake(user.to_string(), validator1.to_string())
+        .unwrap();
+    assert_eq!(cross_stake1.stake, ValueRange::new_val(Uint128::new(50)));
+    assert_eq!(cross_stake1.pending_unbonds[0].amount, Uint128::new(50));
+
+    // Validator 1 is slashed, over the current bond
+    cross_staking
+        .test_handle_slashing(validator1.to_string(), Uint128::new(5))
+        .call("test")
+        .unwrap();
+
+    // Liens
+    let claims = vault.account_claims(user.to_owned(), None, None).unwrap();
+    assert_eq!(
+        claims.claims,
+        [
+            LienResponse {
+                lienholder: local_staking.contract_addr.to_string(),
+                denom: OSMO.to_owned(),
+                amount: ValueRange::new_val(Uint128::new(local_stake))
+            },
+            LienResponse {
+                lienholder: cross_staking.contract_addr.to_string(),
+                denom: OSMO.to_owned(),
+                amount: ValueRange::new_val(Uint128::new(140)) // 10% slashed
+            },
+        ]
+    );
+
+    let acc_details = vault.account_details(user.to_owned()).unwrap();
+    // Max lien
+    assert_eq!(acc_details.max_lien, ValueRange::new_val(Uint128::new(190)));
+    // Total slashable
+    assert_eq!(
+        acc_details.total_slashable,
+        ValueRange::new_val(Uint128::new(33)) // Due to rounding
+    );
+    // Collateral
+    assert_eq!(acc_details.bonded, Uint128::new(190));
+    // Free collateral
+    assert_eq!(acc_details.free, ValueRange::new_val(Uint128::new(0)));
+
+    // Cross stake
+    let cross_stake1 = cross_staking
+        .stake(user.to_string(), validator1.to_string())
+        .unwrap();
+    assert_eq!(cross_stake1.stake, ValueRange::new_val(Uint128::new(45))); // 10% slashed
+                                                                           // Pending unbondings have been slashed
+    assert_eq!(cross_stake1.pending_unbonds[0].amount, Uint128::new(45)); // 10% slashed
+    let cross_stake2 = cross_staking
+        .stake(user.to_string(), validator2.to_string())
+        .unwrap();
+    assert_eq!(cross_stake2.stake, ValueRange::new_val(Uint128::new(50))); // no slashing
+                                                                           // No pending unbondings
+    assert!(cross_stake2.pending_unbonds.is_empty());
+}
+
+/// Scenario 7:
+/// Same as scenario 1 but with native slashing, because of double-signing.
 #[test]
-fn cross_slash_pending_unbonding() {
+fn native_slashing_tombstoning() {
     let owner = "owner";
     let user = "user1";
-    // Remote slashing percentage
+    // Native slashing percentage
     let slashing_percentage = 10;
     let collateral = 200;
+    let local_validator = "local";
     let validators = vec!["validator1", "validator2"];
     let validator1 = validators[0];
     let validator2 = validators[1];
-    let local_validator = "local";
 
-    let mut app = init_app(&[user], &[collateral]);
+    let mut app = init_app(&[user], &[1000]);
     add_local_validator(&mut app, local_validator);
 
     let (vault, local_staking, cross_staking) = setup(&app, owner, slashing_percentage, 100);
@@ -2484,8 +5162,7 @@ fn cross_slash_pending_unbonding() {
     bond(&vault, user, collateral);
 
     // Stake some tokens locally
-    let local_stake = 190;
-    stake_locally(&vault, user, local_stake, local_validator).unwrap();
+    stake_locally(&vault, user, 190, local_validator).unwrap();
 
     // Stake some tokens remotely
     stake_remotely(&vault, &cross_staking, user, &validators, &[100, 50]);
@@ -2505,10 +5182,12 @@ fn cross_slash_pending_unbonding() {
         [
             LienResponse {
                 lienholder: local_staking.contract_addr.to_string(),
-                amount: ValueRange::new_val(Uint128::new(local_stake))
+                denom: OSMO.to_owned(),
+                amount: ValueRange::new_val(Uint128::new(190))
             },
             LienResponse {
                 lienholder: cross_staking.contract_addr.to_string(),
+                denom: OSMO.to_owned(),
                 amount: ValueRange::new_val(Uint128::new(150))
             },
         ]
@@ -2537,25 +5216,9 @@ fn cross_slash_pending_unbonding() {
         .unwrap();
     assert_eq!(cross_stake2.stake, ValueRange::new_val(Uint128::new(50)));
 
-    // Unbond half the stake of validator1
-    cross_staking
-        .unstake(validator1.to_owned(), coin(50, OSMO))
-        .call(user)
-        .unwrap();
-    cross_staking
-        .test_commit_unstake(get_last_external_staking_pending_tx_id(&cross_staking).unwrap())
-        .call("test")
-        .unwrap();
-    // Cross stakes amount
-    let cross_stake1 = cross_staking
-        .stake(user.to_string(), validator1.to_string())
-        .unwrap();
-    assert_eq!(cross_stake1.stake, ValueRange::new_val(Uint128::new(50)));
-    assert_eq!(cross_stake1.pending_unbonds[0].amount, Uint128::new(50));
-
-    // Validator 1 is slashed, over the current bond
-    cross_staking
-        .test_handle_slashing(validator1.to_string(), Uint128::new(5))
+    // Local validator is tombstoned (which implies slashing)
+    local_staking
+        .test_handle_jailing(vec![], vec![local_validator.to_string()])
         .call("test")
         .unwrap();
 
@@ -2566,47 +5229,45 @@ fn cross_slash_pending_unbonding() {
         [
             LienResponse {
                 lienholder: local_staking.contract_addr.to_string(),
-                amount: ValueRange::new_val(Uint128::new(local_stake))
+                denom: OSMO.to_owned(),
+                amount: ValueRange::new_val(Uint128::new(171)) // 10% slashing
             },
             LienResponse {
                 lienholder: cross_staking.contract_addr.to_string(),
-                amount: ValueRange::new_val(Uint128::new(140)) // 10% slashed
+                denom: OSMO.to_owned(),
+                amount: ValueRange::new_val(Uint128::new(150))
             },
         ]
     );
 
     let acc_details = vault.account_details(user.to_owned()).unwrap();
     // Max lien
-    assert_eq!(acc_details.max_lien, ValueRange::new_val(Uint128::new(190)));
-    // Total slashable
+    assert_eq!(acc_details.max_lien, ValueRange::new_val(Uint128::new(171))); // Adjusted
+                                                                              // Total slashable
     assert_eq!(
         acc_details.total_slashable,
-        ValueRange::new_val(Uint128::new(33)) // Due to rounding
+        ValueRange::new_val(Uint128::new(33))
     );
     // Collateral
-    assert_eq!(acc_details.bonded, Uint128::new(190));
+    assert_eq!(acc_details.bonded, Uint128::new(181));
     // Free collateral
-    assert_eq!(acc_details.free, ValueRange::new_val(Uint128::new(0)));
+    assert_eq!(acc_details.free, ValueRange::new_val(Uint128::new(10)));
 
     // Cross stake
     let cross_stake1 = cross_staking
         .stake(user.to_string(), validator1.to_string())
         .unwrap();
-    assert_eq!(cross_stake1.stake, ValueRange::new_val(Uint128::new(45))); // 10% slashed
-                                                                           // Pending unbondings have been slashed
-    assert_eq!(cross_stake1.pending_unbonds[0].amount, Uint128::new(45)); // 10% slashed
+    assert_eq!(cross_stake1.stake, ValueRange::new_val(Uint128::new(100))); // no slashing
     let cross_stake2 = cross_staking
         .stake(user.to_string(), validator2.to_string())
         .unwrap();
     assert_eq!(cross_stake2.stake, ValueRange::new_val(Uint128::new(50))); // no slashing
-                                                                           // No pending unbondings
-    assert!(cross_stake2.pending_unbonds.is_empty());
 }
 
-/// Scenario 7:
-/// Same as scenario 1 but with native slashing, because of double-signing.
+/// Scenario 8:
+/// Same as scenario 1 but with native slashing, because of offline.
 #[test]
-fn native_slashing_tombstoning() {
+fn native_slashing_jailing() {
     let owner = "owner";
     let user = "user1";
     // Native slashing percentage
@@ -2649,10 +5310,12 @@ fn native_slashing_tombstoning() {
         [
             LienResponse {
                 lienholder: local_staking.contract_addr.to_string(),
+                denom: OSMO.to_owned(),
                 amount: ValueRange::new_val(Uint128::new(190))
             },
             LienResponse {
                 lienholder: cross_staking.contract_addr.to_string(),
+                denom: OSMO.to_owned(),
                 amount: ValueRange::new_val(Uint128::new(150))
             },
         ]
@@ -2681,172 +5344,793 @@ fn native_slashing_tombstoning() {
         .unwrap();
     assert_eq!(cross_stake2.stake, ValueRange::new_val(Uint128::new(50)));
 
-    // Local validator is tombstoned (which implies slashing)
+    // Local validator is jailed (which implies slashing)
     local_staking
-        .test_handle_jailing(vec![], vec![local_validator.to_string()])
+        .test_handle_jailing(vec![local_validator.to_string()], vec![])
         .call("test")
         .unwrap();
 
-    // Liens
-    let claims = vault.account_claims(user.to_owned(), None, None).unwrap();
+    // Liens
+    let claims = vault.account_claims(user.to_owned(), None, None).unwrap();
+    assert_eq!(
+        claims.claims,
+        [
+            LienResponse {
+                lienholder: local_staking.contract_addr.to_string(),
+                denom: OSMO.to_owned(),
+                amount: ValueRange::new_val(Uint128::new(171)) // 10% slashing
+            },
+            LienResponse {
+                lienholder: cross_staking.contract_addr.to_string(),
+                denom: OSMO.to_owned(),
+                amount: ValueRange::new_val(Uint128::new(150))
+            },
+        ]
+    );
+
+    let acc_details = vault.account_details(user.to_owned()).unwrap();
+    // Max lien
+    assert_eq!(acc_details.max_lien, ValueRange::new_val(Uint128::new(171))); // Adjusted
+                                                                              // Total slashable
+    assert_eq!(
+        acc_details.total_slashable,
+        ValueRange::new_val(Uint128::new(33))
+    );
+    // Collateral
+    assert_eq!(acc_details.bonded, Uint128::new(181));
+    // Free collateral
+    assert_eq!(acc_details.free, ValueRange::new_val(Uint128::new(10)));
+
+    // Cross stake
+    let cross_stake1 = cross_staking
+        .stake(user.to_string(), validator1.to_string())
+        .unwrap();
+    assert_eq!(cross_stake1.stake, ValueRange::new_val(Uint128::new(100))); // no slashing
+    let cross_stake2 = cross_staking
+        .stake(user.to_string(), validator2.to_string())
+        .unwrap();
+    assert_eq!(cross_stake2.stake, ValueRange::new_val(Uint128::new(50))); // no slashing
+}
+
+/// Swap adapter stub, paying out half of the input amount from its own balance
+fn store_swap_adapter_stub(app: &App<MtApp>) -> u64 {
+    use cosmwasm_std::{BankMsg, Deps, DepsMut, Empty, Env, MessageInfo, Response, StdResult};
+    use cw_multi_test::ContractWrapper;
+
+    fn execute(
+        _deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        msg: SwapAdapterExecuteMsg,
+    ) -> StdResult<Response> {
+        let SwapAdapterExecuteMsg::Swap {
+            output_denom,
+            recipient,
+            ..
+        } = msg;
+        let output = info.funds[0].amount.u128() / 2;
+        Ok(Response::new().add_message(BankMsg::Send {
+            to_address: recipient,
+            amount: coins(output, output_denom),
+        }))
+    }
+
+    fn instantiate(
+        _deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        _msg: Empty,
+    ) -> StdResult<Response> {
+        Ok(Response::new())
+    }
+
+    fn query(_deps: Deps, _env: Env, _msg: Empty) -> StdResult<Binary> {
+        Ok(Binary::default())
+    }
+
+    app.app_mut()
+        .store_code(Box::new(ContractWrapper::new(execute, instantiate, query)))
+}
+
+#[test]
+fn swap_and_bond() {
+    let owner = "owner";
+    let user = "user1";
+    let ibc_denom = "ibc/ATOM";
+
+    let app = init_app(&[], &[]);
+    app.app_mut()
+        .init_modules(|router, _, storage| {
+            router.bank.init_balance(
+                storage,
+                &Addr::unchecked(user),
+                vec![coin(300, ibc_denom), coin(100, OSMO)],
+            )
+        })
+        .unwrap();
+    let (vault, _, _) = setup(&app, owner, 10, 100);
+
+    // Nothing to swap through yet
+    let err = vault
+        .swap_and_bond(Uint128::new(50))
+        .with_funds(&coins(100, ibc_denom))
+        .call(user)
+        .unwrap_err();
+    assert_eq!(err, ContractError::NoSwapAdapter);
+
+    let adapter_code = store_swap_adapter_stub(&app);
+    let adapter = cw_multi_test::Executor::instantiate_contract(
+        &mut *app.app_mut(),
+        adapter_code,
+        Addr::unchecked(owner),
+        &cosmwasm_std::Empty {},
+        &[],
+        "Swap adapter",
+        None,
+    )
+    .unwrap();
+    app.app_mut()
+        .init_modules(|router, _, storage| {
+            router
+                .bank
+                .init_balance(storage, &adapter, coins(1000, OSMO))
+        })
+        .unwrap();
+    vault.set_swap_adapter(Some(adapter.to_string())).unwrap();
+    assert_eq!(
+        vault.swap_adapter().unwrap().adapter,
+        Some(adapter.to_string())
+    );
+
+    // The primary denom is bonded directly
+    let err = vault
+        .swap_and_bond(Uint128::new(50))
+        .with_funds(&coins(100, OSMO))
+        .call(user)
+        .unwrap_err();
+    assert_eq!(err, ContractError::SwapPrimaryDenom(OSMO.to_owned()));
+
+    // Proceeds below the slippage limit revert the whole swap
+    let err = vault
+        .swap_and_bond(Uint128::new(60))
+        .with_funds(&coins(100, ibc_denom))
+        .call(user)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::SlippageExceeded {
+            min_output: Uint128::new(60),
+            output: Uint128::new(50),
+        }
+    );
+    assert_eq!(
+        app.app()
+            .wrap()
+            .query_balance(user, ibc_denom)
+            .unwrap()
+            .amount,
+        Uint128::new(300)
+    );
+
+    // Proceeds are bonded for the sender
+    vault
+        .swap_and_bond(Uint128::new(50))
+        .with_funds(&coins(100, ibc_denom))
+        .call(user)
+        .unwrap();
+    let collateral = vault
+        .account_collateral(user.to_owned())
+        .unwrap()
+        .collateral;
+    assert_eq!(collateral.len(), 1);
+    assert_eq!(collateral[0].denom, OSMO);
+    assert_eq!(collateral[0].bonded, Uint128::new(50));
+    assert_eq!(
+        app.app()
+            .wrap()
+            .query_balance(user, ibc_denom)
+            .unwrap()
+            .amount,
+        Uint128::new(200)
+    );
+    assert_eq!(
+        app.app()
+            .wrap()
+            .query_balance(vault.contract_addr.clone(), OSMO)
+            .unwrap()
+            .amount,
+        Uint128::new(50)
+    );
+
+    // Swaps are bonds, and can be paused as such
+    vault
+        .set_paused(vec![PausableOperation::Bond], true)
+        .unwrap();
+    let err = vault
+        .swap_and_bond(Uint128::new(50))
+        .with_funds(&coins(100, ibc_denom))
+        .call(user)
+        .unwrap_err();
+    assert_eq!(err, ContractError::Paused(PausableOperation::Bond));
+    vault
+        .set_paused(vec![PausableOperation::Bond], false)
+        .unwrap();
+
+    // Removing the adapter disables swap-and-bond
+    vault.set_swap_adapter(None).unwrap();
+    assert_eq!(vault.swap_adapter().unwrap().adapter, None);
+}
+
+#[test]
+fn expire_stale_tx() {
+    let owner = "owner";
+    let user = "user1";
+    let keeper = "keeper";
+    let validator = "validator";
+
+    let app = init_app(&[user], &[300]);
+    let (vault, _, cross_staking) = setup(&app, owner, SLASHING_PERCENTAGE, 100);
+    set_active_validators(&cross_staking, &[validator]);
+    bond(&vault, user, 300);
+    stake_remotely(&vault, &cross_staking, user, &[validator], &[100]);
+    // A stake the lienholder will never commit
+    vault
+        .stake_remote(
+            cross_staking.contract_addr.to_string(),
+            coin(50, OSMO),
+            to_json_binary(&ReceiveVirtualStake {
+                validator: validator.to_string(),
+            })
+            .unwrap(),
+            Some("stuck".to_owned()),
+        )
+        .call(user)
+        .unwrap();
+    let tx_id = get_last_vault_pending_tx_id(&vault).unwrap();
+    assert_eq!(
+        vault.account(user.to_owned()).unwrap().free,
+        ValueRange::new(Uint128::new(150), Uint128::new(200))
+    );
+
+    assert_eq!(
+        vault.tx_timeout().unwrap().timeout,
+        crate::contract::DEFAULT_TX_TIMEOUT
+    );
+    let err = vault.set_tx_timeout(0).unwrap_err();
+    assert_eq!(err, ContractError::InvalidTxTimeout);
+    vault.set_tx_timeout(3600).unwrap();
+    assert_eq!(vault.tx_timeout().unwrap().timeout, 3600);
+
+    // Not before the timeout
+    let started = app.block_info().time;
+    let err = vault.expire_tx(tx_id).call(keeper).unwrap_err();
+    assert_eq!(err, ContractError::TxNotExpired(started.plus_seconds(3600)));
+
+    // Anyone can expire it afterwards, releasing the tentative lien
+    skip_time(&app, 3600);
+    let resp = vault.expire_tx(tx_id).call(keeper).unwrap();
+    let event = resp
+        .events
+        .iter()
+        .find(|event| event.ty == "wasm-expire_tx")
+        .unwrap();
+    assert!(event
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "memo" && attr.value == "stuck"));
+    assert_eq!(get_last_vault_pending_tx_id(&vault), None);
+    assert_eq!(
+        vault.account(user.to_owned()).unwrap().free,
+        ValueRange::new_val(Uint128::new(200))
+    );
+    let claims = vault
+        .account_claims(user.to_owned(), None, None)
+        .unwrap()
+        .claims;
+    assert_eq!(claims[0].amount, ValueRange::new_val(Uint128::new(100)));
+    // The lienholder dropped it as well
+    let stake = cross_staking
+        .stake(user.to_owned(), validator.to_owned())
+        .unwrap();
+    assert_eq!(stake.stake, ValueRange::new_val(Uint128::new(100)));
+
+    // The tx is gone
+    vault.expire_tx(tx_id).call(keeper).unwrap_err();
+
+    // A lienholder failing to drop its side cannot keep the collateral locked
+    vault
+        .stake_remote(
+            cross_staking.contract_addr.to_string(),
+            coin(50, OSMO),
+            to_json_binary(&ReceiveVirtualStake {
+                validator: validator.to_string(),
+            })
+            .unwrap(),
+            None,
+        )
+        .call(user)
+        .unwrap();
+    let tx_id = get_last_vault_pending_tx_id(&vault).unwrap();
+    // The lienholder lost track of the tx, so aborting it there fails
+    cross_staking
+        .abort_stake(tx_id)
+        .call(vault.contract_addr.as_str())
+        .unwrap();
+    cross_staking
+        .abort_stake(tx_id)
+        .call(vault.contract_addr.as_str())
+        .unwrap_err();
+
+    skip_time(&app, 3600);
+    let resp = vault.expire_tx(tx_id).call(keeper).unwrap();
+    assert!(resp.events.iter().any(|event| event
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "action" && attr.value == "abort_stake_failed")));
+    assert_eq!(get_last_vault_pending_tx_id(&vault), None);
+    assert_eq!(
+        vault.account(user.to_owned()).unwrap().free,
+        ValueRange::new_val(Uint128::new(200))
+    );
+}
+
+#[test]
+fn cancel_own_stale_tx() {
+    let owner = "owner";
+    let user = "user1";
+    let other = "user2";
+    let validator = "validator";
+
+    let app = init_app(&[user], &[300]);
+    let (vault, _, cross_staking) = setup(&app, owner, SLASHING_PERCENTAGE, 100);
+    set_active_validators(&cross_staking, &[validator]);
+    bond(&vault, user, 300);
+    // A stake the lienholder will never commit
+    vault
+        .stake_remote(
+            cross_staking.contract_addr.to_string(),
+            coin(50, OSMO),
+            to_json_binary(&ReceiveVirtualStake {
+                validator: validator.to_string(),
+            })
+            .unwrap(),
+            None,
+        )
+        .call(user)
+        .unwrap();
+    let tx_id = get_last_vault_pending_tx_id(&vault).unwrap();
+
+    let err = vault.set_cancel_grace_period(0).unwrap_err();
+    assert_eq!(err, ContractError::InvalidTxTimeout);
+    vault.set_cancel_grace_period(600).unwrap();
+    assert_eq!(vault.tx_timeout().unwrap().cancel_grace_period, 600);
+
+    // Not before the grace period
+    let started = app.block_info().time;
+    let err = vault.cancel_my_tx(tx_id).call(user).unwrap_err();
     assert_eq!(
-        claims.claims,
-        [
-            LienResponse {
-                lienholder: local_staking.contract_addr.to_string(),
-                amount: ValueRange::new_val(Uint128::new(171)) // 10% slashing
-            },
-            LienResponse {
-                lienholder: cross_staking.contract_addr.to_string(),
-                amount: ValueRange::new_val(Uint128::new(150))
-            },
-        ]
+        err,
+        ContractError::TxNotCancellable(started.plus_seconds(600))
     );
 
-    let acc_details = vault.account_details(user.to_owned()).unwrap();
-    // Max lien
-    assert_eq!(acc_details.max_lien, ValueRange::new_val(Uint128::new(171))); // Adjusted
-                                                                              // Total slashable
+    // Only by the staker, before the tx timeout
+    skip_time(&app, 600);
+    let err = vault.cancel_my_tx(tx_id).call(other).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    vault.expire_tx(tx_id).call(other).unwrap_err();
+
+    let resp = vault.cancel_my_tx(tx_id).call(user).unwrap();
+    assert!(resp.events.iter().any(|event| event.ty == "wasm-cancel_tx"));
+    assert_eq!(get_last_vault_pending_tx_id(&vault), None);
     assert_eq!(
-        acc_details.total_slashable,
-        ValueRange::new_val(Uint128::new(33))
+        vault.account(user.to_owned()).unwrap().free,
+        ValueRange::new_val(Uint128::new(300))
     );
-    // Collateral
-    assert_eq!(acc_details.bonded, Uint128::new(181));
-    // Free collateral
-    assert_eq!(acc_details.free, ValueRange::new_val(Uint128::new(10)));
+    // The lienholder dropped it as well
+    let stake = cross_staking
+        .stake(user.to_owned(), validator.to_owned())
+        .unwrap();
+    assert_eq!(stake.stake, ValueRange::new_val(Uint128::zero()));
 
-    // Cross stake
-    let cross_stake1 = cross_staking
-        .stake(user.to_string(), validator1.to_string())
+    // The staker doesn't depend on the lienholder to get their collateral back
+    vault
+        .stake_remote(
+            cross_staking.contract_addr.to_string(),
+            coin(50, OSMO),
+            to_json_binary(&ReceiveVirtualStake {
+                validator: validator.to_string(),
+            })
+            .unwrap(),
+            None,
+        )
+        .call(user)
         .unwrap();
-    assert_eq!(cross_stake1.stake, ValueRange::new_val(Uint128::new(100))); // no slashing
-    let cross_stake2 = cross_staking
-        .stake(user.to_string(), validator2.to_string())
+    let tx_id = get_last_vault_pending_tx_id(&vault).unwrap();
+    cross_staking
+        .abort_stake(tx_id)
+        .call(vault.contract_addr.as_str())
         .unwrap();
-    assert_eq!(cross_stake2.stake, ValueRange::new_val(Uint128::new(50))); // no slashing
+
+    skip_time(&app, 600);
+    let resp = vault.cancel_my_tx(tx_id).call(user).unwrap();
+    assert!(resp.events.iter().any(|event| event
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "action" && attr.value == "abort_stake_failed")));
+    assert_eq!(get_last_vault_pending_tx_id(&vault), None);
+    assert_eq!(
+        vault.account(user.to_owned()).unwrap().free,
+        ValueRange::new_val(Uint128::new(300))
+    );
 }
 
-/// Scenario 8:
-/// Same as scenario 1 but with native slashing, because of offline.
 #[test]
-fn native_slashing_jailing() {
+fn dormant_accounts() {
     let owner = "owner";
-    let user = "user1";
-    // Native slashing percentage
-    let slashing_percentage = 10;
-    let collateral = 200;
-    let local_validator = "local";
-    let validators = vec!["validator1", "validator2"];
-    let validator1 = validators[0];
-    let validator2 = validators[1];
-
-    let mut app = init_app(&[user], &[1000]);
-    add_local_validator(&mut app, local_validator);
-
-    let (vault, local_staking, cross_staking) = setup(&app, owner, slashing_percentage, 100);
+    let users = ["user1", "user2"];
+    let keeper = "keeper";
 
-    let (_update_valset_height, _update_valset_time) =
-        set_active_validators(&cross_staking, &validators);
+    let app = init_app(&users, &[300, 300]);
+    let (vault, _, _) = setup(&app, owner, SLASHING_PERCENTAGE, 100);
+    for user in users {
+        bond(&vault, user, 100);
+    }
+    let start = app.block_info().time;
+    let advance = |seconds: u64| {
+        app.app_mut().update_block(|block| {
+            block.height += 1;
+            block.time = block.time.plus_seconds(seconds);
+        })
+    };
 
-    // Bond some collateral
-    bond(&vault, user, collateral);
+    let err = vault
+        .flag_dormant_account(users[0].to_owned())
+        .call(keeper)
+        .unwrap_err();
+    assert_eq!(err, ContractError::DormancyDisabled);
+    let err = vault.set_dormancy_period(Some(0)).unwrap_err();
+    assert_eq!(err, ContractError::InvalidDormancyPeriod);
+    vault.set_dormancy_period(Some(100)).unwrap();
 
-    // Stake some tokens locally
-    stake_locally(&vault, user, 190, local_validator).unwrap();
+    // Accounts can only be flagged once the period is over, and if they hold collateral
+    let err = vault
+        .flag_dormant_account(users[0].to_owned())
+        .call(keeper)
+        .unwrap_err();
+    assert_eq!(err, ContractError::AccountActive(start.plus_seconds(100)));
+    let err = vault
+        .flag_dormant_account(keeper.to_owned())
+        .call(keeper)
+        .unwrap_err();
+    assert_eq!(err, ContractError::NoCollateral);
 
-    // Stake some tokens remotely
-    stake_remotely(&vault, &cross_staking, user, &validators, &[100, 50]);
+    // Any activity postpones it
+    advance(100);
+    vault.unbond(coin(10, OSMO)).call(users[1]).unwrap();
+    vault
+        .flag_dormant_account(users[0].to_owned())
+        .call(keeper)
+        .unwrap();
+    let err = vault
+        .flag_dormant_account(users[0].to_owned())
+        .call(keeper)
+        .unwrap_err();
+    assert_eq!(err, ContractError::AlreadyDormant);
+    let err = vault
+        .flag_dormant_account(users[1].to_owned())
+        .call(keeper)
+        .unwrap_err();
+    assert_eq!(err, ContractError::AccountActive(start.plus_seconds(200)));
 
-    let acc = vault.account(user.to_owned()).unwrap();
+    advance(100);
+    vault
+        .flag_dormant_account(users[1].to_owned())
+        .call(keeper)
+        .unwrap();
     assert_eq!(
-        acc,
-        AccountResponse {
-            denom: OSMO.to_owned(),
-            bonded: Uint128::new(collateral),
-            free: ValueRange::new_val(Uint128::new(10)),
+        vault.dormant_accounts(None, None).unwrap(),
+        DormantAccountsResponse {
+            accounts: vec![
+                DormantAccount {
+                    account: users[0].to_owned(),
+                    last_activity: Some(start),
+                    flagged_at: start.plus_seconds(100),
+                },
+                DormantAccount {
+                    account: users[1].to_owned(),
+                    last_activity: Some(start.plus_seconds(100)),
+                    flagged_at: start.plus_seconds(200),
+                },
+            ],
         }
     );
-    let claims = vault.account_claims(user.to_owned(), None, None).unwrap();
-    assert_eq!(
-        claims.claims,
-        [
-            LienResponse {
-                lienholder: local_staking.contract_addr.to_string(),
-                amount: ValueRange::new_val(Uint128::new(190))
+    let accounts = vault
+        .dormant_accounts(Some(users[0].to_owned()), None)
+        .unwrap()
+        .accounts;
+    assert_eq!(accounts.len(), 1);
+    assert_eq!(accounts[0].account, users[1]);
+
+    // The flag is dropped on the next activity
+    bond(&vault, users[0], 10);
+    let accounts = vault.dormant_accounts(None, None).unwrap().accounts;
+    assert_eq!(accounts.len(), 1);
+    assert_eq!(accounts[0].account, users[1]);
+
+    // Disabling dormancy drops all the flags
+    vault.set_dormancy_period(None).unwrap();
+    assert_eq!(vault.dormant_accounts(None, None).unwrap().accounts, vec![]);
+}
+
+#[test]
+fn fee_disbursement() {
+    let owner = "owner";
+    let payer = "payer";
+    let community_pool = "community_pool";
+    let dev_fund = "dev_fund";
+
+    let app = init_app(&[payer], &[300]);
+    let (vault, _, _) = setup(&app, owner, SLASHING_PERCENTAGE, 100);
+    let start = app.block_info().time;
+    let advance = |seconds: u64| {
+        app.app_mut().update_block(|block| {
+            block.height += 1;
+            block.time = block.time.plus_seconds(seconds);
+        })
+    };
+    let disbursement = |shares: [u64; 2], interval: u64| FeeDisbursement {
+        recipients: vec![
+            FeeRecipient {
+                address: Addr::unchecked(community_pool),
+                share: Decimal::percent(shares[0]),
             },
-            LienResponse {
-                lienholder: cross_staking.contract_addr.to_string(),
-                amount: ValueRange::new_val(Uint128::new(150))
+            FeeRecipient {
+                address: Addr::unchecked(dev_fund),
+                share: Decimal::percent(shares[1]),
             },
-        ]
-    );
+        ],
+        interval,
+    };
 
-    let acc_details = vault.account_details(user.to_owned()).unwrap();
-    // Max lien
-    assert_eq!(acc_details.max_lien, ValueRange::new_val(Uint128::new(190)));
-    // Total slashable
-    assert_eq!(
-        acc_details.total_slashable,
-        ValueRange::new_val(Uint128::new(34))
-    );
-    // Collateral
-    assert_eq!(acc_details.bonded, Uint128::new(collateral));
-    // Free collateral
-    assert_eq!(acc_details.free, ValueRange::new_val(Uint128::new(10)));
+    // Fees are only collected once enabled
+    let err = vault
+        .deposit_fees("lien_fees".to_owned())
+        .with_funds(&coins(100, OSMO))
+        .call(payer)
+        .unwrap_err();
+    assert_eq!(err, ContractError::FeesDisabled);
+    let err = vault
+        .set_fee_disbursement(Some(disbursement([70, 20], 100)))
+        .unwrap_err();
+    assert_eq!(err, ContractError::InvalidFeeRecipients);
+    let err = vault
+        .set_fee_disbursement(Some(disbursement([70, 30], 0)))
+        .unwrap_err();
+    assert_eq!(err, ContractError::InvalidFeeInterval);
+    vault
+        .set_fee_disbursement(Some(disbursement([70, 30], 100)))
+        .unwrap();
 
-    // Cross stakes
-    let cross_stake1 = cross_staking
-        .stake(user.to_string(), validator1.to_string())
+    // Fees are accounted per source
+    vault
+        .deposit_fees("lien_fees".to_owned())
+        .with_funds(&coins(100, OSMO))
+        .call(payer)
         .unwrap();
-    assert_eq!(cross_stake1.stake, ValueRange::new_val(Uint128::new(100)));
-    let cross_stake2 = cross_staking
-        .stake(user.to_string(), validator2.to_string())
+    vault
+        .deposit_fees("slash_fees".to_owned())
+        .with_funds(&coins(1, OSMO))
+        .call(payer)
         .unwrap();
-    assert_eq!(cross_stake2.stake, ValueRange::new_val(Uint128::new(50)));
 
-    // Local validator is jailed (which implies slashing)
-    local_staking
-        .test_handle_jailing(vec![local_validator.to_string()], vec![])
-        .call("test")
-        .unwrap();
+    // Rounding dust goes to the last recipient
+    vault.disburse_fees().call(payer).unwrap();
+    assert_eq!(
+        app.app()
+            .wrap()
+            .query_balance(community_pool, OSMO)
+            .unwrap()
+            .amount
+            .u128(),
+        70
+    );
+    assert_eq!(
+        app.app()
+            .wrap()
+            .query_balance(dev_fund, OSMO)
+            .unwrap()
+            .amount
+            .u128(),
+        31
+    );
+    assert_eq!(
+        vault.fee_accounting().unwrap(),
+        FeeAccountingResponse {
+            disbursement: Some(disbursement([70, 30], 100)),
+            buckets: vec![
+                FeeBucketInfo {
+                    source: "lien_fees".to_owned(),
+                    pending: Uint128::zero(),
+                    disbursed: Uint128::new(100),
+                },
+                FeeBucketInfo {
+                    source: "slash_fees".to_owned(),
+                    pending: Uint128::zero(),
+                    disbursed: Uint128::new(1),
+                },
+            ],
+            last_disbursement: Some(start),
+        }
+    );
 
-    // Liens
-    let claims = vault.account_claims(user.to_owned(), None, None).unwrap();
+    // Disbursements are scheduled
+    vault
+        .deposit_fees("lien_fees".to_owned())
+        .with_funds(&coins(50, OSMO))
+        .call(payer)
+        .unwrap();
+    let err = vault.disburse_fees().call(payer).unwrap_err();
     assert_eq!(
-        claims.claims,
-        [
-            LienResponse {
-                lienholder: local_staking.contract_addr.to_string(),
-                amount: ValueRange::new_val(Uint128::new(171)) // 10% slashing
+        err,
+        ContractError::FeeDisbursementNotDue(start.plus_seconds(100))
+    );
+    advance(100);
+    vault.disburse_fees().call(payer).unwrap();
+    let buckets = vault.fee_accounting().unwrap().buckets;
+    assert_eq!(buckets[0].disbursed, Uint128::new(150));
+
+    advance(100);
+    let err = vault.disburse_fees().call(payer).unwrap_err();
+    assert_eq!(err, ContractError::NoFees);
+}
+
+#[test]
+fn multi_query() {
+    let owner = "owner";
+    let user = "user1";
+
+    let app = init_app(&[user], &[300]);
+
+    let (vault, _local_staking, _cross_staking) = setup(&app, owner, 0, 100);
+
+    bond(&vault, user, 100);
+
+    let responses = vault
+        .multi_query(vec![
+            SubQuery::Account {
+                account: user.to_owned(),
             },
-            LienResponse {
-                lienholder: cross_staking.contract_addr.to_string(),
-                amount: ValueRange::new_val(Uint128::new(150))
+            SubQuery::AccountDetails {
+                account: user.to_owned(),
+            },
+            SubQuery::AccountClaims {
+                account: user.to_owned(),
+                start_after: None,
+                limit: None,
             },
+            SubQuery::AllPendingTxsDesc {
+                start_after: None,
+                limit: None,
+            },
+        ])
+        .unwrap()
+        .responses;
+
+    // Responses match the ones of the individual queries, in order
+    assert_eq!(
+        responses,
+        vec![
+            SubQueryResponse::Account(vault.account(user.to_owned()).unwrap()),
+            SubQueryResponse::AccountDetails(vault.account_details(user.to_owned()).unwrap()),
+            SubQueryResponse::AccountClaims(
+                vault.account_claims(user.to_owned(), None, None).unwrap()
+            ),
+            SubQueryResponse::AllPendingTxsDesc(vault.all_pending_txs_desc(None, None).unwrap()),
         ]
     );
 
-    let acc_details = vault.account_details(user.to_owned()).unwrap();
-    // Max lien
-    assert_eq!(acc_details.max_lien, ValueRange::new_val(Uint128::new(171))); // Adjusted
-                                                                              // Total slashable
+    // A failing query fails the batch
+    vault
+        .multi_query(vec![
+            SubQuery::Account {
+                account: user.to_owned(),
+            },
+            SubQuery::PendingTx { tx_id: 1 },
+        ])
+        .unwrap_err();
+
+    // Batch size is limited
+    let queries = vec![
+        SubQuery::Account {
+            account: user.to_owned(),
+        };
+        MAX_MULTI_QUERIES + 1
+    ];
+    let err = vault.multi_query(queries).unwrap_err();
+    assert!(err
+        .to_string()
+        .contains(&ContractError::TooManyQueries(MAX_MULTI_QUERIES).to_string()));
+}
+
+#[test]
+fn tx_history_compaction() {
+    let owner = "owner";
+    let user = "user1";
+    let keeper = "keeper";
+    let validator = "validator";
+
+    let app = init_app(&[user], &[300]);
+    let (vault, _, cross_staking) = setup(&app, owner, SLASHING_PERCENTAGE, 100);
+    set_active_validators(&cross_staking, &[validator]);
+    bond(&vault, user, 300);
+    let lienholder = cross_staking.contract_addr.to_string();
+    let height = app.block_info().height;
+
+    // Committed and rolled back stakes are both kept
+    let committed = TX_HISTORY_RETENTION + 3;
+    for _ in 0..committed {
+        stake_remotely(&vault, &cross_staking, user, &[validator], &[1]);
+    }
+    vault
+        .stake_remote(
+            lienholder.clone(),
+            coin(10, OSMO),
+            to_json_binary(&ReceiveVirtualStake {
+                validator: validator.to_string(),
+            })
+            .unwrap(),
+            None,
+        )
+        .call(user)
+        .unwrap();
+    let last_tx = get_last_vault_pending_tx_id(&vault).unwrap();
+    vault.rollback_tx(last_tx).call(&lienholder).unwrap();
+
+    let history = vault.tx_history(user.to_owned(), None, None).unwrap();
+    assert_eq!(history.txs.len(), committed + 1);
     assert_eq!(
-        acc_details.total_slashable,
-        ValueRange::new_val(Uint128::new(33))
+        history.txs[0],
+        TxHistoryItem {
+            tx_id: last_tx,
+            lienholder: lienholder.clone(),
+            denom: OSMO.to_owned(),
+            amount: Uint128::new(10),
+            outcome: TxOutcome::RolledBack,
+            completed_height: height,
+        }
     );
-    // Collateral
-    assert_eq!(acc_details.bonded, Uint128::new(181));
-    // Free collateral
-    assert_eq!(acc_details.free, ValueRange::new_val(Uint128::new(10)));
-
-    // Cross stake
-    let cross_stake1 = cross_staking
-        .stake(user.to_string(), validator1.to_string())
+    assert_eq!(history.txs[1].outcome, TxOutcome::Committed);
+    assert_eq!(history.summaries, vec![]);
+    let next = vault
+        .tx_history(user.to_owned(), Some(last_tx), None)
         .unwrap();
-    assert_eq!(cross_stake1.stake, ValueRange::new_val(Uint128::new(100))); // no slashing
-    let cross_stake2 = cross_staking
-        .stake(user.to_string(), validator2.to_string())
+    assert_eq!(next.txs, history.txs[1..]);
+
+    // Anyone can compact the txs beyond the retained ones into summaries
+    vault
+        .compact_tx_history(user.to_owned())
+        .call(keeper)
         .unwrap();
-    assert_eq!(cross_stake2.stake, ValueRange::new_val(Uint128::new(50))); // no slashing
+    let err = vault
+        .compact_tx_history(user.to_owned())
+        .call(keeper)
+        .unwrap_err();
+    assert_eq!(err, ContractError::NothingToCompact);
+
+    let history = vault.tx_history(user.to_owned(), None, None).unwrap();
+    assert_eq!(history.txs.len(), TX_HISTORY_RETENTION);
+    assert_eq!(history.txs[0].tx_id, last_tx);
+    assert_eq!(
+        history.summaries,
+        vec![DenomTxHistorySummary {
+            denom: OSMO.to_owned(),
+            summary: TxHistorySummary {
+                committed_count: 4,
+                committed_amount: Uint128::new(4),
+                rolled_back_count: 0,
+                rolled_back_amount: Uint128::zero(),
+                last_height: height,
+            },
+        }]
+    );
 }
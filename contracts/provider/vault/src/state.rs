@@ -1,11 +1,15 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Decimal, Uint128};
+use cosmwasm_std::{Addr, Coin, Decimal, Timestamp, Uint128};
+use cw_storage_plus::{Index, IndexList, IndexedMap, KeyDeserialize, MultiIndex};
+use cw_utils::Expiration;
 use mesh_apis::local_staking_api::LocalStakingApiHelper;
 use mesh_sync::{max_range, ValueRange};
 
 #[cw_serde]
 pub struct Config {
-    /// The denom we accept for staking (only native tokens)
+    /// The primary collateral denom (only native tokens).
+    /// This is the denom used for local staking; further collateral denoms can be accepted
+    /// through governance
     pub denom: String,
 }
 
@@ -18,15 +22,148 @@ pub struct LocalStaking {
     pub max_slash: Decimal,
 }
 
+/// Exchange rate of a liquid staking token accepted as collateral
+#[cw_serde]
+pub struct LstRate {
+    /// Contract providing the exchange rate
+    pub provider: Addr,
+    /// Value of a single token, in units of the underlying token
+    pub rate: Decimal,
+    /// Block time of the last refresh
+    pub updated_at: Timestamp,
+}
+
 /// Single Lien description
 #[cw_serde]
 pub struct Lien {
-    /// Credit amount (denom is in `Config::denom`)
+    /// Collateral denom the lien is taken on. Empty for liens taken before it was recorded,
+    /// until the contract is migrated
+    #[serde(default)]
+    pub denom: String,
+    /// Credit amount (in `denom`). For LST collateral this is the value of the tokens
     pub amount: ValueRange<Uint128>,
     /// Slashable part - restricted to [0; 1] range
     pub slashable: Decimal,
 }
 
+pub struct LienIndexes<'a> {
+    // Last type param defines the pk deserialization type
+    pub lienholder: MultiIndex<'a, Addr, Lien, (Addr, Addr)>,
+}
+
+impl<'a> IndexList<Lien> for LienIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Lien>> + '_> {
+        let v: Vec<&dyn Index<Lien>> = vec![&self.lienholder];
+        Box::new(v.into_iter())
+    }
+}
+
+/// Liens indexed by `(user, lienholder)`, with a secondary index by lienholder
+pub type Liens<'a> = IndexedMap<'a, (&'a Addr, &'a Addr), Lien, LienIndexes<'a>>;
+
+/// Creates the liens map, indexed by lienholder under `lienholder_subkey`
+pub fn liens<'a>(storage_key: &'a str, lienholder_subkey: &'a str) -> Liens<'a> {
+    let indexes = LienIndexes {
+        lienholder: MultiIndex::new(
+            |pk, _| {
+                let (_, lienholder) = <(Addr, Addr)>::from_slice(pk).unwrap(); // mustn't fail
+                lienholder
+            },
+            storage_key,
+            lienholder_subkey,
+        ),
+    };
+    IndexedMap::new(storage_key, indexes)
+}
+
+/// Lienholder marked as permanently broken by governance
+#[cw_serde]
+pub struct DefunctLienholder {
+    /// Block time it was marked at
+    pub marked_at: Timestamp,
+    /// Block time from which its liens can be force-released
+    pub release_after: Timestamp,
+}
+
+/// Lien transfer proposed by one of the lienholders, waiting for the other one
+#[cw_serde]
+pub struct LienTransfer {
+    /// Amount of the lien to transfer
+    pub amount: Coin,
+    /// Lienholder that proposed the transfer
+    pub proposer: Addr,
+}
+
+/// Approval given by a user to an operator, to stake their collateral on their behalf
+#[cw_serde]
+pub struct Approval {
+    /// Amount (in the approval denom) the operator can still stake
+    pub spend_limit: Uint128,
+    /// When the approval stops being usable
+    pub expires: Expiration,
+}
+
+/// Vault ownership transfer, waiting to be accepted by the new owner
+#[cw_serde]
+pub struct PendingOwnership {
+    /// Proposed owner
+    pub new_owner: Addr,
+    /// Deadline to accept the transfer, if any
+    pub expiry: Option<Expiration>,
+}
+
+/// Treatment of slash rounding dust, i.e. the remainder every lien of a user is rounded up by
+/// when a slash is propagated across them
+#[cw_serde]
+#[derive(Default)]
+pub enum SlashDustPolicy {
+    /// Liens are rounded up, and the dust is burned along with the rest of the stake
+    #[default]
+    Burn,
+    /// Liens are not rounded up. The dust is taken from the user's collateral instead, and sent
+    /// to `recipient`
+    Donate { recipient: Addr },
+    /// Liens are not rounded up, so dust is left to the user
+    Ignore,
+}
+
+/// Slash rounding dust of a collateral denom, in value like liens
+#[cw_serde]
+#[derive(Default)]
+pub struct SlashDust {
+    /// Dust slashed from users
+    pub burned: Uint128,
+    /// Dust taken from users and sent to the donation recipient
+    #[serde(default)]
+    pub donated: Uint128,
+    /// Dust left to users
+    #[serde(default)]
+    pub ignored: Uint128,
+}
+
+/// Swap-and-bond waiting for the swap adapter reply
+#[cw_serde]
+pub struct PendingSwap {
+    /// Account the proceeds are bonded for
+    pub owner: Addr,
+    /// Swapped funds
+    pub input: Coin,
+    /// Balance of the swap recipient in the primary denom before the swap
+    pub balance_before: Uint128,
+    /// Minimum proceeds accepted
+    pub min_output: Uint128,
+}
+
+/// Request to unbond collateral, fulfilled as it is released from liens
+#[cw_serde]
+pub struct UnbondRequest {
+    /// Collateral denom to unbond
+    pub denom: String,
+    /// Amount (in `denom` tokens) still waiting to be unbonded
+    pub amount: Uint128,
+}
+
+/// Per-user, per-denom collateral accounting
 #[cw_serde]
 #[derive(Default)]
 pub struct UserInfo {
@@ -39,6 +176,15 @@ pub struct UserInfo {
 }
 
 impl UserInfo {
+    /// Returns the accounting with collateral converted to value at `rate`.
+    /// Liens are always expressed in value, so collateral checks operate on this view.
+    pub fn valued(&self, rate: Decimal) -> Self {
+        Self {
+            collateral: self.collateral * rate,
+            ..self.clone()
+        }
+    }
+
     // Return total used collateral
     pub fn used_collateral(&self) -> ValueRange<Uint128> {
         max_range(self.max_lien, self.total_slashable)
@@ -46,9 +192,11 @@ impl UserInfo {
 
     /// Returns free collateral
     pub fn free_collateral(&self) -> ValueRange<Uint128> {
+        // Saturating, as the value of LST collateral can drop below its liens
+        let used = self.used_collateral();
         ValueRange::new(
-            self.collateral - self.used_collateral().high(),
-            self.collateral - self.used_collateral().low(),
+            self.collateral.saturating_sub(used.high()),
+            self.collateral.saturating_sub(used.low()),
         )
     }
 
@@ -57,3 +205,78 @@ impl UserInfo {
         self.collateral >= self.used_collateral().high()
     }
 }
+
+/// Outcome of a completed stake tx
+#[cw_serde]
+#[derive(Copy)]
+pub enum TxOutcome {
+    Committed,
+    RolledBack,
+}
+
+/// A completed stake tx, kept in the user's tx history
+#[cw_serde]
+pub struct TxRecord {
+    pub lienholder: Addr,
+    pub denom: String,
+    pub amount: Uint128,
+    pub outcome: TxOutcome,
+    /// Height the tx was committed or rolled back at
+    pub completed_height: u64,
+}
+
+/// Aggregate of a user's compacted tx history, per denom
+#[cw_serde]
+#[derive(Default)]
+pub struct TxHistorySummary {
+    pub committed_count: u64,
+    pub committed_amount: Uint128,
+    pub rolled_back_count: u64,
+    pub rolled_back_amount: Uint128,
+    /// Height of the newest compacted tx
+    pub last_height: u64,
+}
+
+impl TxHistorySummary {
+    /// Folds `record` into the summary
+    pub fn add(&mut self, record: &TxRecord) {
+        match record.outcome {
+            TxOutcome::Committed => {
+                self.committed_count += 1;
+                self.committed_amount += record.amount;
+            }
+            TxOutcome::RolledBack => {
+                self.rolled_back_count += 1;
+                self.rolled_back_amount += record.amount;
+            }
+        }
+        self.last_height = self.last_height.max(record.completed_height);
+    }
+}
+
+/// Recipient of a share of the fees collected by the vault, e.g. the community pool or a dev fund
+#[cw_serde]
+pub struct FeeRecipient {
+    pub address: Addr,
+    /// Share of every disbursement sent to this recipient
+    pub share: Decimal,
+}
+
+/// Scheduled disbursement of the fees collected by the vault
+#[cw_serde]
+pub struct FeeDisbursement {
+    /// Recipients of the fees. Their shares must add up to one
+    pub recipients: Vec<FeeRecipient>,
+    /// Minimum time (in seconds) between two disbursements
+    pub interval: u64,
+}
+
+/// Fees collected from a single source
+#[cw_serde]
+#[derive(Default)]
+pub struct FeeBucket {
+    /// Fees collected and not disbursed yet
+    pub pending: Uint128,
+    /// Fees disbursed so far
+    pub disbursed: Uint128,
+}
@@ -0,0 +1,20 @@
+//! Swap adapter integration, to bond assets of other denoms (e.g. IBC-transferred ones).
+//!
+//! The vault doesn't swap by itself: it forwards the sent funds to a swap adapter contract
+//! configured by governance, and bonds whatever the swap yields in the primary collateral denom.
+//! The proceeds are measured on the recipient balance, so the adapter response format doesn't
+//! matter, and the swap is reverted if they are below the user-provided minimum.
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::Uint128;
+
+/// Execute interface swap adapter contracts have to implement
+#[cw_serde]
+pub enum SwapAdapterExecuteMsg {
+    /// Swaps the sent funds to `output_denom`, sending the output to `recipient`.
+    /// Fails if the output is below `min_output`
+    Swap {
+        output_denom: String,
+        min_output: Uint128,
+        recipient: String,
+    },
+}
@@ -1,5 +1,5 @@
 use cosmwasm_std::{Addr, Order, StdResult, Storage};
-use cw_storage_plus::{Index, IndexList, IndexedMap, MultiIndex};
+use cw_storage_plus::{Index, IndexList, IndexedMap, Item, MultiIndex};
 use mesh_sync::Tx;
 use mesh_sync::Tx::InFlightStaking;
 
@@ -17,10 +17,12 @@ impl<'a> IndexList<Tx> for TxIndexes<'a> {
 
 pub struct Txs<'a> {
     pub txs: IndexedMap<'a, u64, Tx, TxIndexes<'a>>,
+    /// Running count of the pending txs
+    pub count: Item<'a, u64>,
 }
 
 impl<'a> Txs<'a> {
-    pub fn new(storage_key: &'a str, user_subkey: &'a str) -> Self {
+    pub fn new(storage_key: &'a str, user_subkey: &'a str, count_key: &'a str) -> Self {
         let indexes = TxIndexes {
             users: MultiIndex::new(
                 |_, tx| {
@@ -36,7 +38,35 @@ impl<'a> Txs<'a> {
         };
         let txs = IndexedMap::new(storage_key, indexes);
 
-        Self { txs }
+        Self {
+            txs,
+            count: Item::new(count_key),
+        }
+    }
+
+    /// Saves a tx, keeping the count in sync
+    pub fn save(&self, storage: &mut dyn Storage, tx_id: u64, tx: &Tx) -> StdResult<()> {
+        if !self.txs.has(storage, tx_id) {
+            let count = self.count.may_load(storage)?.unwrap_or_default();
+            self.count.save(storage, &(count + 1))?;
+        }
+        self.txs.save(storage, tx_id, tx)
+    }
+
+    /// Removes a tx, keeping the count in sync
+    pub fn remove(&self, storage: &mut dyn Storage, tx_id: u64) -> StdResult<()> {
+        if self.txs.has(storage, tx_id) {
+            let count = self.count.may_load(storage)?.unwrap_or_default();
+            // Saturating, as txs pending before the count was tracked are not accounted
+            self.count.save(storage, &count.saturating_sub(1))?;
+        }
+        self.txs.remove(storage, tx_id)
+    }
+
+    /// Recomputes the count from the stored txs, for txs pending before it was tracked
+    pub fn seed_count(&self, storage: &mut dyn Storage) -> StdResult<()> {
+        let count = self.txs.keys(storage, None, None, Order::Ascending).count() as u64;
+        self.count.save(storage, &count)
     }
 
     pub fn txs_by_user(&self, storage: &dyn Storage, user: &Addr) -> StdResult<Vec<Tx>> {
@@ -2,7 +2,7 @@
 #![allow(clippy::too_many_arguments)]
 
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Coin, CustomMsg, CustomQuery, Response, StdError, Uint128, Validator};
+use cosmwasm_std::{Coin, CustomMsg, CustomQuery, Decimal, Response, StdError, Uint128, Validator};
 use sylvia::types::ExecCtx;
 use sylvia::{interface, schemars};
 
@@ -61,6 +61,16 @@ pub trait ConverterApi {
         validator: String,
         amount: Coin,
     ) -> Result<Response<Self::ExecC>, Self::Error>;
+
+    /// Sends the slashing params of this chain to the external staking contract on the Provider
+    /// via IBC.
+    #[sv::msg(exec)]
+    fn slashing_params_update(
+        &self,
+        ctx: ExecCtx<Self::QueryC>,
+        double_sign: Decimal,
+        offline: Decimal,
+    ) -> Result<Response<Self::ExecC>, Self::Error>;
 }
 
 #[cw_serde]
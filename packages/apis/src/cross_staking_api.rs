@@ -43,6 +43,12 @@ pub trait CrossStakingApi {
         validator: Option<String>,
     ) -> Result<Response, Self::Error>;
 
+    /// Gives up on the pending stake `tx_id`, e.g. once it expired. This is called by the vault,
+    /// after it rolled the tx back and released the lien on its own. The contract only drops its
+    /// side of the stake. The vault ignores any failure of this call.
+    #[sv::msg(exec)]
+    fn abort_stake(&self, ctx: ExecCtx, tx_id: u64) -> Result<Response, Self::Error>;
+
     /// Returns the maximum percentage that can be slashed
     #[sv::msg(query)]
     fn max_slash(&self, ctx: QueryCtx) -> Result<SlashRatioResponse, Self::Error>;
@@ -97,6 +103,16 @@ impl CrossStakingApiHelper {
         Ok(wasm)
     }
 
+    pub fn abort_stake(&self, tx_id: u64) -> Result<WasmMsg, StdError> {
+        let msg = sv::CrossStakingApiExecMsg::AbortStake { tx_id };
+        let wasm = WasmMsg::Execute {
+            contract_addr: self.0.to_string(),
+            msg: to_json_binary(&msg)?,
+            funds: vec![],
+        };
+        Ok(wasm)
+    }
+
     pub fn max_slash(&self, deps: Deps) -> Result<SlashRatioResponse, StdError> {
         let query = sv::CrossStakingApiQueryMsg::MaxSlash {};
         deps.querier.query_wasm_smart(&self.0, &query)
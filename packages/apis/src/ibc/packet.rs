@@ -1,7 +1,7 @@
 use std::error::Error;
 
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{to_json_binary, Binary, Coin, StdError, StdResult};
+use cosmwasm_std::{to_json_binary, Binary, Coin, Decimal, StdError, StdResult};
 use osmosis_std::shim::Timestamp as OsmosisTimestamp;
 use osmosis_std::types::tendermint::abci::RequestQuery;
 use prost::Message;
@@ -55,20 +55,66 @@ pub enum ProviderPacket {
         /// This is local to the sending side to track the transaction, should be passed through opaquely on the consumer
         tx_id: u64,
     },
+    /// This is part of the rewards protocol. It moves `rewards` from the distribution pool into
+    /// the delegation of `delegator`, as a stake of `stake` (already liened in the vault).
+    /// Acked as a Stake
+    CompoundRewards {
+        delegator: String,
+        validator: String,
+        /// This is the local (provider-side) denom that is held in the vault, as in `Stake`
+        stake: Coin,
+        /// Amount previously received by ConsumerPacket::Distribute, to be moved into the delegation
+        rewards: Coin,
+        /// This is local to the sending side to track the transaction, should be passed through opaquely on the consumer
+        tx_id: u64,
+    },
+    /// Periodic summary of the collateral liened to the external staking contract in the vault,
+    /// pending stakes included. The consumer uses it as a sanity bound on the stake it converts.
+    /// This is non-transactional, it is just sent again on the next period if it fails.
+    CollateralSummary {
+        /// This is the local (provider-side) denom that is held in the vault
+        total_collateral: Coin,
+    },
 }
 
 /// Ack sent for ProviderPacket::Stake
 #[cw_serde]
-pub struct StakeAck {}
+pub struct StakeAck {
+    /// Terms the stake was converted with. Not set by older consumers
+    pub conversion: Option<ConversionTerms>,
+}
 
 /// Ack sent for ProviderPacket::Unstake
 #[cw_serde]
-pub struct UnstakeAck {}
+pub struct UnstakeAck {
+    /// Terms the unstake was converted with. Not set by older consumers, nor for burns
+    pub conversion: Option<ConversionTerms>,
+}
+
+/// Terms a stake or unstake was converted to the consumer-side staking token with
+#[cw_serde]
+pub struct ConversionTerms {
+    /// Amount of the consumer-side staking token that was (un)bonded
+    pub converted: Coin,
+    /// Price from the price feed, in consumer-side tokens per provider-side token
+    pub price: Decimal,
+    /// Adjustment (discount) applied to the price
+    pub price_adjustment: Decimal,
+    /// Consumer block height the conversion happened at
+    pub height: u64,
+    /// Consumer block height of the last epoch processed by virtual staking, if any.
+    /// The (un)bond takes effect on the next epoch
+    pub epoch: Option<u64>,
+}
 
 /// Ack sent for ProviderPacket::TransferRewards
 #[cw_serde]
 pub struct TransferRewardsAck {}
 
+/// Ack sent for ProviderPacket::CollateralSummary
+#[cw_serde]
+pub struct CollateralSummaryAck {}
+
 /// These are messages sent from consumer -> provider
 /// ibc_packet_receive in external-staking must handle them all.
 #[cw_serde]
@@ -103,6 +149,12 @@ pub enum ConsumerPacket {
         /// unbonded instead.
         /// If the validator doesn't exist or is tombstoned, this is a no-op for that validator.
         unjailed: Vec<String>,
+        /// This is sent along with `unjailed`, for the unjailed validators that are back in the
+        /// active set. They will be set as active again, and accept new stake and rewards.
+        /// The other unjailed validators stay jailed until they are added or removed.
+        /// If the validator is not jailed, this is a no-op for that validator.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        reactivated: Vec<String>,
         /// This is sent when a validator is tombstoned. Not just leaving the active state,
         /// but when it is no longer a valid target to delegate to.
         /// The validator will be slashed for double signing as well.
@@ -115,6 +167,25 @@ pub enum ConsumerPacket {
         /// This has precedence over all other events in the same packet.
         slashed: Vec<ValidatorSlashInfo>,
     },
+    /// Authoritative snapshot of (a chunk of) the consumer's active validator set.
+    /// Sent on demand to recover from divergence accumulated during channel downtime.
+    ///
+    /// Every chunk covers the validator addresses in the `(start_after, end]` range, where `None`
+    /// means unbounded. Chunks can be applied independently and in any order.
+    ValsetResync {
+        /// Height of the snapshot on the consumer
+        height: u64,
+        /// Timestamp of the snapshot on the consumer, as unix seconds
+        time: u64,
+        /// Exclusive lower bound of the chunk
+        start_after: Option<String>,
+        /// Inclusive upper bound of the chunk
+        end: Option<String>,
+        /// All active validators in the chunk range.
+        /// The provider sets them as active, unless they are tombstoned, and removes any other
+        /// active validator in the range.
+        validators: Vec<AddValidator>,
+    },
     /// This is a part of zero max cap process
     /// The consumer chain will send this packet to provider, force user to unbond token
     InternalUnstake {
@@ -140,6 +211,28 @@ pub enum ConsumerPacket {
         /// Rewards denom
         denom: String,
     },
+    /// Delegation policy of the consumer. The provider enforces it on new stakes.
+    /// Replaces any previously sent policy
+    DelegationPolicy {
+        /// Max number of validators a single user can stake to. Unlimited if not set
+        max_validators: Option<u32>,
+        /// Max share of the total stake on the provider that can be staked to a single validator.
+        /// Unlimited if not set
+        max_validator_share: Option<Decimal>,
+    },
+    /// Slashing params of the consumer. Sent whenever they change (e.g. through governance), so
+    /// the provider can keep its slashing math aligned with the actual consumer ratios.
+    /// Replaces any previously sent params
+    SlashingParams {
+        /// Consumer height the params were read at. Used to discard outdated packets
+        height: u64,
+        /// Consumer time the params were read at, as unix seconds
+        time: u64,
+        /// Slash ratio for double signing
+        double_sign: Decimal,
+        /// Slash ratio for downtime
+        offline: Decimal,
+    },
 }
 
 #[cw_serde]
@@ -170,6 +263,14 @@ pub struct ValsetUpdateAck {}
 #[cw_serde]
 pub struct DistributeAck {}
 
+/// Ack sent for ConsumerPacket::DelegationPolicy
+#[cw_serde]
+pub struct DelegationPolicyAck {}
+
+/// Ack sent for ConsumerPacket::SlashingParams
+#[cw_serde]
+pub struct SlashingParamsAck {}
+
 #[cw_serde]
 pub struct PriceFeedAck {}
 
@@ -325,3 +426,225 @@ pub struct AcknowledgementResult {
 pub struct InterchainQueryPacketAck {
     pub data: Binary,
 }
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Debug;
+    use std::path::PathBuf;
+
+    use cosmwasm_std::{coin, from_json, to_json_string, Uint128};
+    use serde::de::DeserializeOwned;
+    use serde::Serialize;
+
+    use super::*;
+
+    /// Checks `value` serializes to the golden file `testdata/ibc/<name>.json`, and back.
+    /// Packets and acks are exchanged with deployed contracts on the other chain, so any change
+    /// to their wire format breaks live channels. Run with `UPDATE_GOLDEN=1` to regenerate the
+    /// files, for intended (versioned) changes only.
+    #[track_caller]
+    fn assert_wire_format<T>(name: &str, value: &T)
+    where
+        T: Serialize + DeserializeOwned + PartialEq + Debug,
+    {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("testdata/ibc")
+            .join(format!("{name}.json"));
+        let actual = to_json_string(value).unwrap();
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            std::fs::write(&path, format!("{actual}\n")).unwrap();
+        }
+
+        let golden = std::fs::read_to_string(&path)
+            .unwrap_or_else(|err| panic!("cannot read {}: {err}", path.display()));
+        let golden = golden.trim_end();
+        assert_eq!(
+            actual,
+            golden,
+            "wire format of {name} changed, compare with {}",
+            path.display()
+        );
+        assert_eq!(&from_json::<T>(golden).unwrap(), value);
+    }
+
+    #[test]
+    fn provider_packets_wire_format() {
+        assert_wire_format(
+            "provider_stake",
+            &ProviderPacket::Stake {
+                delegator: "delegator1".to_string(),
+                validator: "valoper1".to_string(),
+                stake: coin(1000, "uosmo"),
+                tx_id: 7,
+            },
+        );
+        assert_wire_format(
+            "provider_unstake",
+            &ProviderPacket::Unstake {
+                delegator: "delegator1".to_string(),
+                validator: "valoper1".to_string(),
+                unstake: coin(400, "uosmo"),
+                tx_id: 8,
+            },
+        );
+        assert_wire_format(
+            "provider_burn",
+            &ProviderPacket::Burn {
+                validators: vec!["valoper1".to_string(), "valoper2".to_string()],
+                burn: coin(50, "uosmo"),
+            },
+        );
+        assert_wire_format(
+            "provider_transfer_rewards",
+            &ProviderPacket::TransferRewards {
+                rewards: coin(25, "ustake"),
+                recipient: "recipient1".to_string(),
+                tx_id: 9,
+            },
+        );
+        assert_wire_format(
+            "provider_collateral_summary",
+            &ProviderPacket::CollateralSummary {
+                total_collateral: coin(5000, "uosmo"),
+            },
+        );
+    }
+
+    #[test]
+    fn consumer_packets_wire_format() {
+        assert_wire_format(
+            "consumer_valset_update",
+            &ConsumerPacket::ValsetUpdate {
+                height: 100,
+                time: 1700000000,
+                additions: vec![AddValidator {
+                    valoper: "valoper1".to_string(),
+                    pub_key: "pub_key1".to_string(),
+                }],
+                removals: vec!["valoper2".to_string()],
+                updated: vec![],
+                jailed: vec!["valoper3".to_string()],
+                unjailed: vec![],
+                reactivated: vec![],
+                tombstoned: vec!["valoper4".to_string()],
+                slashed: vec![ValidatorSlashInfo {
+                    address: "valoper4".to_string(),
+                    infraction_height: 90,
+                    infraction_time: 1699999000,
+                    power: 10,
+                    slash_amount: coin(5, "ustake"),
+                    slash_ratio: "0.05".to_string(),
+                }],
+            },
+        );
+        assert_wire_format(
+            "consumer_valset_update_unjail",
+            &ConsumerPacket::ValsetUpdate {
+                height: 110,
+                time: 1700000100,
+                additions: vec![],
+                removals: vec![],
+                updated: vec![],
+                jailed: vec![],
+                unjailed: vec!["valoper3".to_string(), "valoper5".to_string()],
+                reactivated: vec!["valoper3".to_string()],
+                tombstoned: vec![],
+                slashed: vec![],
+            },
+        );
+        assert_wire_format(
+            "consumer_valset_resync",
+            &ConsumerPacket::ValsetResync {
+                height: 200,
+                time: 1700001000,
+                start_after: None,
+                end: Some("valoper5".to_string()),
+                validators: vec![AddValidator {
+                    valoper: "valoper1".to_string(),
+                    pub_key: "pub_key1".to_string(),
+                }],
+            },
+        );
+        assert_wire_format(
+            "consumer_internal_unstake",
+            &ConsumerPacket::InternalUnstake {
+                delegator: "delegator1".to_string(),
+                validator: "valoper1".to_string(),
+                normalize_amount: coin(100, "uosmo"),
+                inverted_amount: coin(45, "ustake"),
+            },
+        );
+        assert_wire_format(
+            "consumer_distribute",
+            &ConsumerPacket::Distribute {
+                validator: "valoper1".to_string(),
+                rewards: coin(30, "ustake"),
+            },
+        );
+        assert_wire_format(
+            "consumer_distribute_batch",
+            &ConsumerPacket::DistributeBatch {
+                rewards: vec![
+                    RewardInfo {
+                        validator: "valoper1".to_string(),
+                        reward: Uint128::new(30),
+                    },
+                    RewardInfo {
+                        validator: "valoper2".to_string(),
+                        reward: Uint128::new(15),
+                    },
+                ],
+                denom: "ustake".to_string(),
+            },
+        );
+        assert_wire_format(
+            "consumer_delegation_policy",
+            &ConsumerPacket::DelegationPolicy {
+                max_validators: Some(5),
+                max_validator_share: None,
+            },
+        );
+        assert_wire_format(
+            "consumer_slashing_params",
+            &ConsumerPacket::SlashingParams {
+                height: 300,
+                time: 1700002000,
+                double_sign: Decimal::percent(5),
+                offline: Decimal::permille(1),
+            },
+        );
+    }
+
+    #[test]
+    fn acks_wire_format() {
+        assert_wire_format(
+            "ack_stake",
+            &StakeAck {
+                conversion: Some(ConversionTerms {
+                    converted: coin(450, "ustake"),
+                    price: Decimal::percent(50),
+                    price_adjustment: Decimal::percent(90),
+                    height: 4321,
+                    epoch: Some(4300),
+                }),
+            },
+        );
+        assert_wire_format("ack_unstake", &UnstakeAck { conversion: None });
+        assert_wire_format("ack_transfer_rewards", &TransferRewardsAck {});
+        assert_wire_format("ack_collateral_summary", &CollateralSummaryAck {});
+        assert_wire_format("ack_valset_update", &ValsetUpdateAck {});
+        assert_wire_format("ack_distribute", &DistributeAck {});
+        assert_wire_format("ack_delegation_policy", &DelegationPolicyAck {});
+        assert_wire_format("ack_slashing_params", &SlashingParamsAck {});
+        assert_wire_format("ack_price_feed", &PriceFeedAck {});
+
+        // The ICS wrapper, as built by `ack_success` / `ack_fail`
+        let result: AckWrapper =
+            from_json(ack_success(&UnstakeAck { conversion: None }).unwrap()).unwrap();
+        assert_wire_format("ack_wrapper_result", &result);
+        assert_wire_format(
+            "ack_wrapper_error",
+            &AckWrapper::Error("validator valoper1 is not active".to_string()),
+        );
+    }
+}
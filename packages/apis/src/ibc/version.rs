@@ -31,6 +31,10 @@ fn parse_version(version: &str) -> Result<Version, VersionError> {
 pub struct ProtocolVersion {
     pub protocol: String,
     pub version: String,
+    /// Chain id of the side sending this version. Announced by the provider in its handshake
+    /// response, so the consumer can verify it is talking to the expected chain.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chain_id: Option<String>,
 }
 
 impl ProtocolVersion {
@@ -38,9 +42,16 @@ impl ProtocolVersion {
         ProtocolVersion {
             protocol: protocol.to_string(),
             version: version.to_string(),
+            chain_id: None,
         }
     }
 
+    /// Announces `chain_id` as the chain sending this version
+    pub fn with_chain_id(mut self, chain_id: impl Into<String>) -> Self {
+        self.chain_id = Some(chain_id.into());
+        self
+    }
+
     pub fn validate(&self) -> Result<Version, VersionError> {
         if self.protocol != PROTOCOL_NAME {
             return Err(VersionError::InvalidProtocol(self.protocol.clone()));
@@ -75,6 +86,7 @@ impl ProtocolVersion {
             Ok(ProtocolVersion {
                 protocol: PROTOCOL_NAME.to_string(),
                 version: ver.to_string(),
+                chain_id: None,
             })
         }
     }
@@ -156,6 +168,15 @@ mod tests {
             supported.to_string().unwrap(),
             r#"{"protocol":"mesh-security","version":"1.2.3"}"#.to_string()
         );
+
+        let announced = supported.with_chain_id("osmosis-1");
+        assert_eq!(
+            announced.to_string().unwrap(),
+            r#"{"protocol":"mesh-security","version":"1.2.3","chain_id":"osmosis-1"}"#.to_string()
+        );
+        let parsed: ProtocolVersion =
+            cosmwasm_std::from_json(announced.to_string().unwrap()).unwrap();
+        assert_eq!(parsed.chain_id, Some("osmosis-1".to_string()));
     }
 
     #[test]
@@ -3,5 +3,6 @@ pub mod cross_staking_api;
 pub mod ibc;
 pub mod local_staking_api;
 pub mod price_feed_api;
+pub mod staker_callback_api;
 pub mod vault_api;
 pub mod virtual_staking_api;
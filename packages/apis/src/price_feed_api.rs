@@ -1,5 +1,5 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{CustomMsg, CustomQuery, Decimal, Response, StdError};
+use cosmwasm_std::{CustomMsg, CustomQuery, Decimal, Response, StdError, Timestamp};
 use sylvia::types::{QueryCtx, SudoCtx};
 use sylvia::{interface, schemars};
 
@@ -30,4 +30,7 @@ pub trait PriceFeedApi {
 #[cw_serde]
 pub struct PriceResponse {
     pub native_per_foreign: Decimal,
+    /// Time the price was observed, if the feed reports it
+    #[serde(default)]
+    pub time: Option<Timestamp>,
 }
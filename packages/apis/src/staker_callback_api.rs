@@ -0,0 +1,87 @@
+use cosmwasm_std::{to_json_binary, Addr, Coin, Response, StdError, StdResult, WasmMsg};
+use sylvia::types::ExecCtx;
+use sylvia::{interface, schemars};
+
+/// This is the interface a staker contract (e.g. a DAO, or a staking aggregator) implements to
+/// be told about the outcome of its cross stakes, once it opted into callbacks on the
+/// external-staking contract.
+///
+/// These outcomes depend on IBC acknowledgements, so they happen outside of any tx the staker
+/// contract takes part in. Failing callbacks are ignored by the caller, and don't affect the
+/// outcome itself.
+#[interface]
+pub trait StakerCallbackApi {
+    type Error: From<StdError>;
+
+    /// The stake of tx `tx_id` was accepted by the consumer, and is now bonded to `validator`
+    #[sv::msg(exec)]
+    fn stake_committed(
+        &self,
+        ctx: ExecCtx,
+        tx_id: u64,
+        validator: String,
+        amount: Coin,
+    ) -> Result<Response, Self::Error>;
+
+    /// The stake of tx `tx_id` was rejected by the consumer (or timed out).
+    /// The collateral is no longer locked for it
+    #[sv::msg(exec)]
+    fn stake_rolled_back(
+        &self,
+        ctx: ExecCtx,
+        tx_id: u64,
+        validator: String,
+        amount: Coin,
+    ) -> Result<Response, Self::Error>;
+
+    /// The unstake of tx `tx_id` was accepted by the consumer. `amount` is unbonding, and can be
+    /// withdrawn after `release_at` (in seconds)
+    #[sv::msg(exec)]
+    fn unstake_committed(
+        &self,
+        ctx: ExecCtx,
+        tx_id: u64,
+        validator: String,
+        amount: Coin,
+        release_at: u64,
+    ) -> Result<Response, Self::Error>;
+
+    /// The unstake of tx `tx_id` was rejected by the consumer (or timed out).
+    /// The stake is still bonded
+    #[sv::msg(exec)]
+    fn unstake_rolled_back(
+        &self,
+        ctx: ExecCtx,
+        tx_id: u64,
+        validator: String,
+        amount: Coin,
+    ) -> Result<Response, Self::Error>;
+
+    /// Matured unbondings were withdrawn. `amount` was released back to the vault, or relocked
+    /// if `relocked` is set
+    #[sv::msg(exec)]
+    fn unbonding_released(
+        &self,
+        ctx: ExecCtx,
+        amount: Coin,
+        relocked: bool,
+    ) -> Result<Response, Self::Error>;
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StakerCallbackApiHelper(pub Addr);
+
+impl StakerCallbackApiHelper {
+    pub fn addr(&self) -> &Addr {
+        &self.0
+    }
+
+    pub fn callback(&self, msg: &sv::StakerCallbackApiExecMsg) -> StdResult<WasmMsg> {
+        let wasm = WasmMsg::Execute {
+            contract_addr: self.0.to_string(),
+            msg: to_json_binary(msg)?,
+            funds: vec![],
+        };
+        Ok(wasm)
+    }
+}
@@ -1,6 +1,8 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{to_json_binary, Addr, Coin, CustomMsg, Response, StdError, Uint128, WasmMsg};
-use sylvia::types::ExecCtx;
+use cosmwasm_std::{
+    to_json_binary, Addr, Coin, CustomMsg, Deps, Response, StdError, Uint128, WasmMsg,
+};
+use sylvia::types::{ExecCtx, QueryCtx};
 use sylvia::{interface, schemars};
 
 /// This is the interface to the vault contract needed by staking contracts to release funds.
@@ -31,6 +33,46 @@ pub trait VaultApi {
         owner: String,
     ) -> Result<Response<Self::ExecC>, Self::Error>;
 
+    /// This must be called by the local staking contract after re-delegating staking rewards of
+    /// `owner`. The compounded `amount` is still delegated, so no tokens are sent along: it is
+    /// added to the collateral of `owner` and to their lien held by the local staking contract.
+    #[sv::msg(exec)]
+    fn compound_local_stake(
+        &self,
+        ctx: ExecCtx,
+        owner: String,
+        amount: Coin,
+    ) -> Result<Response<Self::ExecC>, Self::Error>;
+
+    /// This must be called by the remote staking contract before compounding staking rewards of
+    /// `owner` into more stake. The rewards stay on the consumer, so the compounded `amount` is
+    /// liened out of the free collateral of `owner`, right away.
+    /// It is released with `release_cross_stake` if the compounding fails.
+    #[sv::msg(exec)]
+    fn compound_cross_stake(
+        &self,
+        ctx: ExecCtx,
+        owner: String,
+        amount: Coin,
+    ) -> Result<Response<Self::ExecC>, Self::Error>;
+
+    /// Moves `amount` of the `owner` lien from `from_lienholder` to `to_lienholder`, e.g. when
+    /// migrating an external staking contract or replacing the local staking contract.
+    /// `to_lienholder` has to be the local staking contract or an allowed lienholder.
+    ///
+    /// Both lienholders have to call it with the same arguments: the first call proposes the
+    /// transfer, and the second one performs it. Lienholders are responsible for moving the
+    /// related stakes themselves.
+    #[sv::msg(exec)]
+    fn transfer_lien(
+        &self,
+        ctx: ExecCtx,
+        owner: String,
+        from_lienholder: String,
+        to_lienholder: String,
+        amount: Coin,
+    ) -> Result<Response<Self::ExecC>, Self::Error>;
+
     /// This must be called by the remote staking contract to commit the remote staking call on success.
     /// Transaction ID is used to identify the original (vault contract originated) transaction.
     #[sv::msg(exec)]
@@ -64,6 +106,42 @@ pub trait VaultApi {
         slashes: Vec<SlashInfo>,
         validator: String,
     ) -> Result<Response<Self::ExecC>, Self::Error>;
+
+    /// Returns whether `account` opted into auto-renew, i.e. having its matured local unbondings
+    /// released back into the vault as free collateral by anyone
+    #[sv::msg(query)]
+    fn auto_renew(&self, ctx: QueryCtx, account: String) -> Result<AutoRenewResponse, Self::Error>;
+
+    /// Returns the lien of `account` held by `lienholder`. Pending stakes are included in
+    /// `high` but not in `low`. Zero if there is no such lien
+    #[sv::msg(query)]
+    fn lien(
+        &self,
+        ctx: QueryCtx,
+        account: String,
+        lienholder: String,
+    ) -> Result<LienAmountResponse, Self::Error>;
+
+    /// Returns the total of the liens held by `lienholder` in `denom`, over all accounts.
+    /// Pending stakes are included in `high` but not in `low`
+    #[sv::msg(query)]
+    fn lienholder_total(
+        &self,
+        ctx: QueryCtx,
+        lienholder: String,
+        denom: String,
+    ) -> Result<LienAmountResponse, Self::Error>;
+}
+
+#[cw_serde]
+pub struct AutoRenewResponse {
+    pub enabled: bool,
+}
+
+#[cw_serde]
+pub struct LienAmountResponse {
+    pub low: Uint128,
+    pub high: Uint128,
 }
 
 #[cw_serde]
@@ -113,6 +191,47 @@ impl VaultApiHelper {
         Ok(wasm)
     }
 
+    pub fn compound_local_stake(&self, owner: String, amount: Coin) -> Result<WasmMsg, StdError> {
+        let msg = sv::VaultApiExecMsg::CompoundLocalStake { owner, amount };
+        let wasm = WasmMsg::Execute {
+            contract_addr: self.0.to_string(),
+            msg: to_json_binary(&msg)?,
+            funds: vec![],
+        };
+        Ok(wasm)
+    }
+
+    pub fn compound_cross_stake(&self, owner: String, amount: Coin) -> Result<WasmMsg, StdError> {
+        let msg = sv::VaultApiExecMsg::CompoundCrossStake { owner, amount };
+        let wasm = WasmMsg::Execute {
+            contract_addr: self.0.to_string(),
+            msg: to_json_binary(&msg)?,
+            funds: vec![],
+        };
+        Ok(wasm)
+    }
+
+    pub fn transfer_lien(
+        &self,
+        owner: String,
+        from_lienholder: String,
+        to_lienholder: String,
+        amount: Coin,
+    ) -> Result<WasmMsg, StdError> {
+        let msg = sv::VaultApiExecMsg::TransferLien {
+            owner,
+            from_lienholder,
+            to_lienholder,
+            amount,
+        };
+        let wasm = WasmMsg::Execute {
+            contract_addr: self.0.to_string(),
+            msg: to_json_binary(&msg)?,
+            funds: vec![],
+        };
+        Ok(wasm)
+    }
+
     pub fn process_local_slashing(
         &self,
         slashes: Vec<SlashInfo>,
@@ -166,4 +285,32 @@ impl VaultApiHelper {
         };
         Ok(wasm)
     }
+
+    pub fn auto_renew(&self, deps: Deps, account: String) -> Result<AutoRenewResponse, StdError> {
+        let query = sv::VaultApiQueryMsg::AutoRenew { account };
+        deps.querier.query_wasm_smart(&self.0, &query)
+    }
+
+    pub fn lien(
+        &self,
+        deps: Deps,
+        account: String,
+        lienholder: String,
+    ) -> Result<LienAmountResponse, StdError> {
+        let query = sv::VaultApiQueryMsg::Lien {
+            account,
+            lienholder,
+        };
+        deps.querier.query_wasm_smart(&self.0, &query)
+    }
+
+    pub fn lienholder_total(
+        &self,
+        deps: Deps,
+        lienholder: String,
+        denom: String,
+    ) -> Result<LienAmountResponse, StdError> {
+        let query = sv::VaultApiQueryMsg::LienholderTotal { lienholder, denom };
+        deps.querier.query_wasm_smart(&self.0, &query)
+    }
 }
@@ -73,11 +73,22 @@ pub trait VirtualStakingApi {
         ctx: ExecCtx<Self::QueryC>,
     ) -> Result<Response<Self::ExecC>, Self::Error>;
 
+    /// Sets the recipient of the rewards forwarded at every epoch (e.g. a rewards escrow contract),
+    /// instead of the converter. `None` removes the override, so rewards go to the converter again.
+    /// The recipient must accept the converter's `distribute_rewards` message.
+    #[sv::msg(exec)]
+    fn set_rewards_recipient(
+        &self,
+        ctx: ExecCtx<Self::QueryC>,
+        recipient: Option<String>,
+    ) -> Result<Response<Self::ExecC>, Self::Error>;
+
     /// SudoMsg::HandleEpoch{} should be called once per epoch by the sdk (in EndBlock).
     /// It allows the virtual staking contract to bond or unbond any pending requests, as well
     /// as to perform a rebalance if needed (over the max cap).
     ///
-    /// It should also withdraw all pending rewards here, and send them to the converter contract.
+    /// It should also withdraw all pending rewards here, and send them to the converter contract
+    /// (or to the rewards recipient, if set).
     #[sv::msg(sudo)]
     fn handle_epoch(
         &self,
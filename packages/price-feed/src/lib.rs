@@ -2,4 +2,4 @@ mod price_keeper;
 mod scheduler;
 
 pub use price_keeper::{PriceKeeper, PriceKeeperError};
-pub use scheduler::{Action, Scheduler};
+pub use scheduler::{Action, CatchUp, Scheduler};
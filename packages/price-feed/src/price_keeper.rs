@@ -14,6 +14,12 @@ pub struct PriceKeeper {
     pub price_info_ttl_in_secs: Item<'static, u64>,
 }
 
+impl Default for PriceKeeper {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl PriceKeeper {
     pub const fn new() -> Self {
         Self {
@@ -56,6 +62,11 @@ impl PriceKeeper {
     }
 
     pub fn price(&self, deps: Deps, env: &Env) -> Result<Decimal, PriceKeeperError> {
+        Ok(self.price_info(deps, env)?.native_per_foreign)
+    }
+
+    /// Returns the latest price, along with the time it was observed
+    pub fn price_info(&self, deps: Deps, env: &Env) -> Result<PriceInfo, PriceKeeperError> {
         let price_info_ttl = self.price_info_ttl_in_secs.load(deps.storage)?;
         let price_info = self
             .price_info
@@ -63,7 +74,7 @@ impl PriceKeeper {
             .ok_or(PriceKeeperError::NoPriceData)?;
 
         if env.block.time.minus_seconds(price_info_ttl) < price_info.time {
-            Ok(price_info.native_per_foreign)
+            Ok(price_info)
         } else {
             Err(PriceKeeperError::OutdatedPriceData)
         }
@@ -102,9 +113,14 @@ mod tests {
         let price = keeper.price(deps.as_ref(), &env).unwrap();
         assert_eq!(price, Decimal::one());
 
+        let observed_at = env.block.time;
         env.block.time = env.block.time.plus_seconds(559);
         let price = keeper.price(deps.as_ref(), &env).unwrap();
         assert_eq!(price, Decimal::one());
+
+        // The observation time is kept along with the price
+        let info = keeper.price_info(deps.as_ref(), &env).unwrap();
+        assert_eq!(info.time, observed_at);
     }
 
     #[test]
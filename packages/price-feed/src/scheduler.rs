@@ -1,16 +1,33 @@
 use std::marker::PhantomData;
 
-use cosmwasm_std::{DepsMut, Env, Response, StdError, Timestamp};
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{DepsMut, Env, Response, StdError, StdResult, Storage, Timestamp};
 use cw_storage_plus::Item;
 
 pub trait Action<Error>: Fn(DepsMut, &Env) -> Result<Response, Error> {}
 impl<F, Error> Action<Error> for F where F: Fn(DepsMut, &Env) -> Result<Response, Error> {}
 
+/// What the scheduler does when it is triggered after more than one epoch has passed
+#[cw_serde]
+#[derive(Copy, Default)]
+pub enum CatchUp {
+    /// Run the action once, the other missed executions are dropped
+    #[default]
+    Once,
+    /// Run the action once per missed epoch, up to `max` times
+    Repeat { max: u32 },
+    /// Don't run the action at all, wait for the next epoch
+    Skip,
+}
+
 /// A component that schedules a single action to be executed regularly,
 /// as in "every epoch". It relies on a trigger being called rather rapidly (every block?).
 pub struct Scheduler<A, Error> {
     last_epoch: Item<'static, Timestamp>,
     epoch_in_secs: Item<'static, u64>,
+    catch_up: Item<'static, CatchUp>,
+    /// Total number of executions dropped because of missed epochs
+    missed: Item<'static, u64>,
     action: A,
     _phantom_data: PhantomData<Error>, // Add a PhantomData to mark the error type
 }
@@ -24,28 +41,82 @@ where
         Self {
             last_epoch: Item::new("last_epoch"),
             epoch_in_secs: Item::new("epoch"),
+            catch_up: Item::new("catch_up"),
+            missed: Item::new("missed_epochs"),
             action,
             _phantom_data: PhantomData, // initialize PhantomData
         }
     }
 
     pub fn init(&self, deps: &mut DepsMut, epoch_in_secs: u64) -> Result<(), E> {
+        self.init_with_catch_up(deps, epoch_in_secs, CatchUp::default())
+    }
+
+    pub fn init_with_catch_up(
+        &self,
+        deps: &mut DepsMut,
+        epoch_in_secs: u64,
+        catch_up: CatchUp,
+    ) -> Result<(), E> {
         self.last_epoch
             .save(deps.storage, &Timestamp::from_seconds(0))?;
         self.epoch_in_secs.save(deps.storage, &epoch_in_secs)?;
+        self.set_catch_up(deps.storage, catch_up)?;
         Ok(())
     }
 
-    pub fn trigger(&self, deps: DepsMut, env: &Env) -> Result<Response, E> {
+    pub fn set_catch_up(&self, storage: &mut dyn Storage, catch_up: CatchUp) -> StdResult<()> {
+        self.catch_up.save(storage, &catch_up)
+    }
+
+    pub fn catch_up(&self, storage: &dyn Storage) -> StdResult<CatchUp> {
+        Ok(self.catch_up.may_load(storage)?.unwrap_or_default())
+    }
+
+    /// Total number of executions that were dropped after missed epochs
+    pub fn missed_executions(&self, storage: &dyn Storage) -> StdResult<u64> {
+        Ok(self.missed.may_load(storage)?.unwrap_or_default())
+    }
+
+    pub fn trigger(&self, mut deps: DepsMut, env: &Env) -> Result<Response, E> {
         let last_epoch = self.last_epoch.load(deps.storage)?;
         let epoch_in_secs = self.epoch_in_secs.load(deps.storage)?;
         let secs_since_last_epoch = env.block.time.seconds() - last_epoch.seconds();
-        if secs_since_last_epoch >= epoch_in_secs {
-            self.last_epoch.save(deps.storage, &env.block.time)?;
-            (self.action)(deps, env)
-        } else {
-            Ok(Response::new())
+        if secs_since_last_epoch < epoch_in_secs {
+            return Ok(Response::new());
         }
+
+        // Number of epochs that ended since the last execution. The first epoch always
+        // fires exactly once.
+        let due = match secs_since_last_epoch.checked_div(epoch_in_secs) {
+            Some(due) if last_epoch.seconds() != 0 => due,
+            _ => 1,
+        };
+        let runs = match self.catch_up(deps.storage)? {
+            CatchUp::Once => 1,
+            CatchUp::Repeat { max } => due.min(max.max(1).into()),
+            CatchUp::Skip if due > 1 => 0,
+            CatchUp::Skip => 1,
+        };
+        self.last_epoch.save(deps.storage, &env.block.time)?;
+
+        let mut resp = Response::new();
+        let missed = due - runs;
+        if missed > 0 {
+            let total = self.missed_executions(deps.storage)? + missed;
+            self.missed.save(deps.storage, &total)?;
+            resp = resp.add_attribute("missed_epochs", missed.to_string());
+        }
+        for _ in 0..runs {
+            let run = (self.action)(deps.branch(), env)?;
+            resp.messages.extend(run.messages);
+            resp.attributes.extend(run.attributes);
+            resp.events.extend(run.events);
+            if run.data.is_some() {
+                resp.data = run.data;
+            }
+        }
+        Ok(resp)
     }
 }
 
@@ -117,4 +188,60 @@ mod tests {
         env.block.time = env.block.time.plus_seconds(5);
         assert_fired(&scheduler, deps.as_mut(), &env);
     }
+
+    fn counting_scheduler() -> TestScheduler {
+        TestScheduler::new(Box::new(|deps, _| {
+            let runs = Item::<u64>::new("runs");
+            let count = runs.may_load(deps.storage)?.unwrap_or_default() + 1;
+            runs.save(deps.storage, &count)?;
+            Ok(Response::new().add_attribute("run", count.to_string()))
+        }))
+    }
+
+    fn runs(resp: &Response) -> usize {
+        resp.attributes.iter().filter(|a| a.key == "run").count()
+    }
+
+    #[test]
+    fn scheduler_catch_up() {
+        let mut env = mock_env();
+
+        // Once by default
+        let scheduler = counting_scheduler();
+        let mut deps = mock_dependencies();
+        scheduler.init(&mut deps.as_mut(), 10).unwrap();
+        assert_eq!(runs(&scheduler.trigger(deps.as_mut(), &env).unwrap()), 1);
+        env.block.time = env.block.time.plus_seconds(35);
+        assert_eq!(runs(&scheduler.trigger(deps.as_mut(), &env).unwrap()), 1);
+        assert_eq!(scheduler.missed_executions(&deps.storage).unwrap(), 2);
+
+        // Repeated up to the limit
+        let scheduler = counting_scheduler();
+        let mut deps = mock_dependencies();
+        scheduler
+            .init_with_catch_up(&mut deps.as_mut(), 10, CatchUp::Repeat { max: 3 })
+            .unwrap();
+        assert_eq!(runs(&scheduler.trigger(deps.as_mut(), &env).unwrap()), 1);
+        env.block.time = env.block.time.plus_seconds(25);
+        assert_eq!(runs(&scheduler.trigger(deps.as_mut(), &env).unwrap()), 2);
+        assert_eq!(scheduler.missed_executions(&deps.storage).unwrap(), 0);
+        env.block.time = env.block.time.plus_seconds(50);
+        let resp = scheduler.trigger(deps.as_mut(), &env).unwrap();
+        assert_eq!(runs(&resp), 3);
+        assert_eq!(scheduler.missed_executions(&deps.storage).unwrap(), 2);
+
+        // Skipped, then back to normal
+        let scheduler = counting_scheduler();
+        let mut deps = mock_dependencies();
+        scheduler
+            .init_with_catch_up(&mut deps.as_mut(), 10, CatchUp::Skip)
+            .unwrap();
+        assert_eq!(runs(&scheduler.trigger(deps.as_mut(), &env).unwrap()), 1);
+        env.block.time = env.block.time.plus_seconds(30);
+        assert_eq!(runs(&scheduler.trigger(deps.as_mut(), &env).unwrap()), 0);
+        assert_eq!(scheduler.missed_executions(&deps.storage).unwrap(), 3);
+        env.block.time = env.block.time.plus_seconds(10);
+        assert_eq!(runs(&scheduler.trigger(deps.as_mut(), &env).unwrap()), 1);
+        assert_eq!(scheduler.missed_executions(&deps.storage).unwrap(), 3);
+    }
 }
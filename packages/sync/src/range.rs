@@ -5,18 +5,78 @@ use std::{
 };
 use thiserror::Error;
 
-use cosmwasm_schema::cw_serde;
+use schemars::gen::SchemaGenerator;
+use schemars::schema::Schema;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 
 /// This is designed to work with two numeric primitives that can be added, subtracted, and compared.
-#[cw_serde]
-#[derive(Default, Copy)]
+///
+/// A collapsed range (`low == high`) is serialized as the single value, otherwise as
+/// `{"l": low, "h": high}`. Both forms are accepted on deserialization.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default, Copy)]
+#[serde(
+    from = "ValueRangeRepr<T>",
+    into = "ValueRangeRepr<T>",
+    bound(
+        serialize = "T: Serialize + Clone + PartialEq",
+        deserialize = "T: Deserialize<'de> + Clone"
+    )
+)]
 pub struct ValueRange<T> {
-    #[serde(rename = "l")]
     low: T,
-    #[serde(rename = "h")]
     high: T,
 }
 
+/// Serialized form of `ValueRange`
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(untagged, deny_unknown_fields)]
+enum ValueRangeRepr<T> {
+    Value(T),
+    Range { l: T, h: T },
+}
+
+impl<T> From<ValueRangeRepr<T>> for ValueRange<T>
+where
+    T: Clone,
+{
+    fn from(repr: ValueRangeRepr<T>) -> Self {
+        match repr {
+            ValueRangeRepr::Value(value) => Self {
+                low: value.clone(),
+                high: value,
+            },
+            ValueRangeRepr::Range { l, h } => Self { low: l, high: h },
+        }
+    }
+}
+
+impl<T> From<ValueRange<T>> for ValueRangeRepr<T>
+where
+    T: PartialEq,
+{
+    fn from(range: ValueRange<T>) -> Self {
+        if range.low == range.high {
+            Self::Value(range.low)
+        } else {
+            Self::Range {
+                l: range.low,
+                h: range.high,
+            }
+        }
+    }
+}
+
+impl<T: JsonSchema> JsonSchema for ValueRange<T> {
+    fn schema_name() -> String {
+        format!("ValueRange_for_{}", T::schema_name())
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        ValueRangeRepr::<T>::json_schema(gen)
+    }
+}
+
 #[derive(Debug, Error, PartialEq, Eq)]
 pub enum RangeError {
     #[error("Underflow minimum value")]
@@ -436,6 +496,32 @@ mod tests {
         assert_eq!(all, ValueRange::new(40, 380));
     }
 
+    #[test]
+    fn serialization() {
+        use cosmwasm_std::{from_json, to_json_vec};
+
+        // Collapsed ranges are serialized as a single value
+        let range = ValueRange::new_val(Uint128::new(80));
+        assert_eq!(to_json_vec(&range).unwrap(), br#""80""#);
+        assert_eq!(from_json::<ValueRange<Uint128>>(br#""80""#).unwrap(), range);
+
+        let range = ValueRange::new(Uint128::new(80), Uint128::new(120));
+        assert_eq!(to_json_vec(&range).unwrap(), br#"{"l":"80","h":"120"}"#);
+        assert_eq!(
+            from_json::<ValueRange<Uint128>>(br#"{"l":"80","h":"120"}"#).unwrap(),
+            range
+        );
+
+        // The full form of collapsed ranges is still accepted
+        assert_eq!(
+            from_json::<ValueRange<Uint128>>(br#"{"l":"80","h":"80"}"#).unwrap(),
+            ValueRange::new_val(Uint128::new(80))
+        );
+
+        // Unknown fields are rejected
+        from_json::<ValueRange<Uint128>>(br#"{"l":"80","h":"120","x":"100"}"#).unwrap_err();
+    }
+
     // most tests will use i32 for simplicity - just ensure APIs work properly with Uint128
     #[test]
     fn works_with_uint128() {
@@ -544,6 +630,7 @@ mod tests {
 
 #[cfg(test)]
 mod examples {
+    use cosmwasm_schema::cw_serde;
     use cosmwasm_std::{testing::MockStorage, Decimal, Order, StdError, Storage, Uint128};
     use cw_storage_plus::Map;
 
@@ -1,5 +1,5 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Decimal, Uint128};
+use cosmwasm_std::{Addr, Decimal, Timestamp, Uint128};
 use std::fmt::Formatter;
 
 #[cw_serde]
@@ -36,6 +36,19 @@ pub enum Tx {
         /// Remote validator
         validator: String,
     },
+    /// This is stored on the provider side when cancelling a pending unbond
+    InFlightRemoteRestaking {
+        /// Transaction id
+        id: u64,
+        /// Associated amount
+        amount: Uint128,
+        /// Associated owner
+        user: Addr,
+        /// Remote validator
+        validator: String,
+        /// Amounts taken from each cancelled unbond, with their release times (to revert)
+        unbonds: Vec<(Uint128, Timestamp)>,
+    },
     /// This is stored on the provider side when releasing funds
     InFlightTransferFunds {
         id: u64,
@@ -46,6 +59,14 @@ pub enum Tx {
         /// The validator whose rewards they come from (to revert)
         validator: String,
     },
+    /// This is stored on the provider side when releasing funds from all the staker's validators
+    InFlightTransferAllFunds {
+        id: u64,
+        /// Amount of rewards being withdrawn, per validator (to revert)
+        amounts: Vec<(String, Uint128)>,
+        /// The staker sending the funds
+        staker: Addr,
+    },
 }
 
 impl Tx {
@@ -54,7 +75,21 @@ impl Tx {
             Tx::InFlightStaking { id, .. } => *id,
             Tx::InFlightRemoteStaking { id, .. } => *id,
             Tx::InFlightRemoteUnstaking { id, .. } => *id,
+            Tx::InFlightRemoteRestaking { id, .. } => *id,
             Tx::InFlightTransferFunds { id, .. } => *id,
+            Tx::InFlightTransferAllFunds { id, .. } => *id,
+        }
+    }
+
+    /// The user the tx was made for (the staker, for funds transfers)
+    pub fn user(&self) -> &Addr {
+        match self {
+            Tx::InFlightStaking { user, .. } => user,
+            Tx::InFlightRemoteStaking { user, .. } => user,
+            Tx::InFlightRemoteUnstaking { user, .. } => user,
+            Tx::InFlightRemoteRestaking { user, .. } => user,
+            Tx::InFlightTransferFunds { staker, .. } => staker,
+            Tx::InFlightTransferAllFunds { staker, .. } => staker,
         }
     }
 }